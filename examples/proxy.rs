@@ -1,8 +1,7 @@
 #[macro_use]
 extern crate log;
 
-use futures::executor::block_on;
-use rsocket_rust::prelude::*;
+use rsocket_rust::proxy::Proxy;
 use rsocket_rust_transport_tcp::*;
 use std::error::Error;
 
@@ -10,19 +9,12 @@ use std::error::Error;
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     env_logger::builder().format_timestamp_millis().init();
 
-    RSocketFactory::receive()
+    Proxy::builder()
         .transport(TcpServerTransport::from("127.0.0.1:7979"))
-        .acceptor(|setup, _sending_socket| {
-            info!("incoming socket: setup={:?}", setup);
-            Ok(Box::new(block_on(async move {
-                RSocketFactory::connect()
-                    .transport(TcpClientTransport::from("127.0.0.1:7878"))
-                    .acceptor(|| Box::new(EchoRSocket))
-                    .setup(Payload::from("I'm Rust!"))
-                    .start()
-                    .await
-                    .unwrap()
-            })))
+        .upstream(|| TcpClientTransport::from("127.0.0.1:7878"))
+        .on_frame(|direction, frame| {
+            info!("{:?}: {:?}", direction, frame);
+            frame
         })
         .serve()
         .await
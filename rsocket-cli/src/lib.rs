@@ -0,0 +1,290 @@
+//! Command line client and echo server for poking at RSocket services.
+#[macro_use]
+extern crate log;
+
+use futures::StreamExt;
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use rsocket_rust_transport_websocket::{WebsocketClientTransport, WebsocketServerTransport};
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, ToSocketAddrs};
+
+pub const USAGE: &str = "\
+usage: rsocket-cli [options] <uri>
+
+Connect to <uri> and send one request, or serve an echo server on it with --server.
+Supported URIs are tcp://host:port and ws://host:port.
+
+options:
+  --request             send a REQUEST_RESPONSE, the default
+  --stream              send a REQUEST_STREAM and print every response
+  --fnf                 send a REQUEST_FNF
+  --metadata-push       send a METADATA_PUSH
+  --server              serve an echo server
+  -i, --input <data>    data of the request, - reads stdin
+  -m, --metadata <data> metadata of the request
+  -r, --route <route>   route the request with composite metadata, repeat for more tags
+  --setup <data>        data of the SETUP frame
+  --data-format <mime>  data MIME type
+  --metadata-format <mime>
+                        metadata MIME type
+  -n, --limit <n>       stop a stream after n responses
+  --debug               print metadata of responses too
+  -h, --help            print this help";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Interaction {
+    RequestResponse,
+    RequestStream,
+    FireAndForget,
+    MetadataPush,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Mode {
+    Client(Interaction),
+    Server,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Endpoint {
+    Tcp(String),
+    Websocket(String),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Args {
+    pub endpoint: Endpoint,
+    pub mode: Mode,
+    pub input: Option<String>,
+    pub metadata: Option<String>,
+    pub routes: Vec<String>,
+    pub setup: Option<String>,
+    pub data_format: Option<String>,
+    pub metadata_format: Option<String>,
+    pub limit: Option<usize>,
+    pub debug: bool,
+}
+
+impl Endpoint {
+    pub fn parse(uri: &str) -> Result<Endpoint, String> {
+        if let Some(addr) = uri.strip_prefix("tcp://") {
+            Ok(Endpoint::Tcp(addr.to_string()))
+        } else if let Some(addr) = uri.strip_prefix("ws://") {
+            Ok(Endpoint::Websocket(addr.to_string()))
+        } else {
+            Err(format!("unsupported URI: {}", uri))
+        }
+    }
+
+    fn resolve(&self) -> Result<SocketAddr, String> {
+        let addr = match self {
+            Endpoint::Tcp(it) | Endpoint::Websocket(it) => it,
+        };
+        addr.to_socket_addrs()
+            .map_err(|e| format!("resolve {} failed: {}", addr, e))?
+            .next()
+            .ok_or_else(|| format!("resolve {} failed", addr))
+    }
+}
+
+impl Args {
+    /// Parse the arguments following the program name. Err carries the message to print,
+    /// which is the usage for `--help`.
+    pub fn parse<I>(args: I) -> Result<Args, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let mut endpoint = None;
+        let mut mode = Mode::Client(Interaction::RequestResponse);
+        let mut parsed = Args {
+            endpoint: Endpoint::Tcp(String::new()),
+            mode,
+            input: None,
+            metadata: None,
+            routes: vec![],
+            setup: None,
+            data_format: None,
+            metadata_format: None,
+            limit: None,
+            debug: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("missing value of {}", name))
+            };
+            match arg.as_str() {
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                "--request" => mode = Mode::Client(Interaction::RequestResponse),
+                "--stream" => mode = Mode::Client(Interaction::RequestStream),
+                "--fnf" => mode = Mode::Client(Interaction::FireAndForget),
+                "--metadata-push" => mode = Mode::Client(Interaction::MetadataPush),
+                "--server" => mode = Mode::Server,
+                "--debug" => parsed.debug = true,
+                "-i" | "--input" => parsed.input = Some(value(&arg)?),
+                "-m" | "--metadata" => parsed.metadata = Some(value(&arg)?),
+                "-r" | "--route" => parsed.routes.push(value(&arg)?),
+                "--setup" => parsed.setup = Some(value(&arg)?),
+                "--data-format" => parsed.data_format = Some(value(&arg)?),
+                "--metadata-format" => parsed.metadata_format = Some(value(&arg)?),
+                "-n" | "--limit" => {
+                    let n = value(&arg)?;
+                    parsed.limit = Some(n.parse().map_err(|_| format!("invalid limit: {}", n))?);
+                }
+                it if it.starts_with('-') => return Err(format!("unknown option: {}", it)),
+                it => {
+                    if endpoint.is_some() {
+                        return Err(format!("unexpected argument: {}", it));
+                    }
+                    endpoint = Some(Endpoint::parse(it)?);
+                }
+            }
+        }
+        if parsed.metadata.is_some() && !parsed.routes.is_empty() {
+            return Err(String::from("--metadata and --route are exclusive"));
+        }
+        parsed.endpoint = endpoint.ok_or_else(|| String::from("missing URI"))?;
+        parsed.mode = mode;
+        Ok(parsed)
+    }
+
+    fn payload(&self) -> Result<Payload, Box<dyn Error + Send + Sync>> {
+        let mut bu = Payload::builder();
+        match self.input.as_deref() {
+            Some("-") => {
+                let mut data = String::new();
+                io::stdin().read_to_string(&mut data)?;
+                bu = bu.set_data_utf8(&data);
+            }
+            Some(data) => bu = bu.set_data_utf8(data),
+            None => (),
+        }
+        if let Some(metadata) = &self.metadata {
+            bu = bu.set_metadata_utf8(metadata);
+        }
+        if self.routes.is_empty() {
+            return Ok(bu.build());
+        }
+        let mut bu = bu.metadata();
+        for route in &self.routes {
+            bu = bu.route(route);
+        }
+        Ok(bu.build())
+    }
+}
+
+/// Serve an echo server, or send the request and write its responses to `out`.
+pub async fn run<W>(args: &Args, out: &mut W) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    W: Write,
+{
+    if args.mode == Mode::Server {
+        return serve(args).await;
+    }
+    let cli = connect(args).await?;
+    let req = args.payload()?;
+    match args.mode {
+        Mode::Client(Interaction::RequestResponse) => {
+            let res = cli.request_response(req).await?;
+            print(args, &res, out)?;
+        }
+        Mode::Client(Interaction::RequestStream) => {
+            let mut results = cli.request_stream(req);
+            let mut n = 0;
+            while args.limit.is_none_or(|limit| n < limit) {
+                match results.next().await {
+                    Some(it) => print(args, &it?, out)?,
+                    None => break,
+                }
+                n += 1;
+            }
+        }
+        Mode::Client(Interaction::FireAndForget) => cli.fire_and_forget(req).await,
+        Mode::Client(Interaction::MetadataPush) => cli.metadata_push(req).await,
+        Mode::Server => unreachable!(),
+    }
+    cli.close();
+    Ok(())
+}
+
+async fn connect(args: &Args) -> Result<Client<DefaultSpawner>, Box<dyn Error + Send + Sync>> {
+    let mut setup = Payload::builder();
+    if let Some(data) = &args.setup {
+        setup = setup.set_data_utf8(data);
+    }
+    let metadata_format = match &args.metadata_format {
+        Some(it) => Some(it.as_str()),
+        // routes are sent as composite metadata.
+        None if !args.routes.is_empty() => Some(mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0),
+        None => None,
+    };
+    macro_rules! start {
+        ($transport:expr) => {{
+            let mut bu = RSocketFactory::connect()
+                .transport($transport)
+                .setup(setup.build());
+            if let Some(it) = &args.data_format {
+                bu = bu.data_mime_type(it);
+            }
+            if let Some(it) = metadata_format {
+                bu = bu.metadata_mime_type(it);
+            }
+            bu.start().await
+        }};
+    }
+    match &args.endpoint {
+        Endpoint::Tcp(_) => start!(TcpClientTransport::from(args.endpoint.resolve()?)),
+        Endpoint::Websocket(addr) => {
+            start!(WebsocketClientTransport::from(
+                format!("ws://{}", addr).as_str()
+            ))
+        }
+    }
+}
+
+async fn serve(args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let addr = args.endpoint.resolve()?;
+    let on_start = || info!("echo server started");
+    let acceptor = |setup: SetupPayload, _socket| {
+        info!("accept setup: {:?}", setup);
+        Ok(Box::new(EchoRSocket) as Box<dyn RSocket>)
+    };
+    match &args.endpoint {
+        Endpoint::Tcp(_) => {
+            RSocketFactory::receive()
+                .transport(TcpServerTransport::from(addr))
+                .acceptor(acceptor)
+                .on_start(on_start)
+                .serve()
+                .await
+        }
+        Endpoint::Websocket(_) => {
+            RSocketFactory::receive()
+                .transport(WebsocketServerTransport::from(addr))
+                .acceptor(acceptor)
+                .on_start(on_start)
+                .serve()
+                .await
+        }
+    }
+}
+
+fn print<W>(args: &Args, res: &Payload, out: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    if args.debug {
+        if let Some(m) = res.metadata() {
+            writeln!(out, "metadata: {}", String::from_utf8_lossy(m))?;
+        }
+    }
+    if let Some(d) = res.data() {
+        writeln!(out, "{}", String::from_utf8_lossy(d))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use rsocket_rust_cli::{run, Args};
+use std::env;
+use std::io;
+use std::process;
+
+fn main() {
+    env_logger::builder().format_timestamp_millis().init();
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(it) => it,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            process::exit(2);
+        }
+    };
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    if let Err(e) = rt.block_on(run(&args, &mut io::stdout())) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,70 @@
+#ifndef RSOCKET_H
+#define RSOCKET_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RSOCKET_OK 0
+#define RSOCKET_ERROR -1
+#define RSOCKET_NEXT 1
+#define RSOCKET_COMPLETE 0
+#define RSOCKET_TIMEOUT -2
+
+typedef struct RSocketClient rsocket_client;
+typedef struct RSocketStream rsocket_stream;
+typedef struct RSocketPayload rsocket_payload;
+
+/* Called with either a response or an error, both are only valid during the call.
+ * Streams call it once more with both NULL when they complete. */
+typedef void (*rsocket_response_cb)(void *user_data, const rsocket_payload *response,
+                                    const char *error);
+
+/* The message of the last failure on the calling thread, or NULL. */
+const char *rsocket_last_error(void);
+
+/* Connect to a tcp://host:port or ws://host:port URI, returns NULL on failure. */
+rsocket_client *rsocket_client_connect(const char *uri);
+
+/* Close the connection and free the client. Must not be called from a callback. */
+void rsocket_client_close(rsocket_client *client);
+
+/* Data and metadata may be NULL, callbacks run on threads of the client. */
+int rsocket_request_response(const rsocket_client *client, const uint8_t *data,
+                             size_t data_len, const uint8_t *metadata, size_t metadata_len,
+                             rsocket_response_cb callback, void *user_data);
+
+int rsocket_fire_and_forget(const rsocket_client *client, const uint8_t *data,
+                            size_t data_len, const uint8_t *metadata, size_t metadata_len);
+
+int rsocket_request_stream_callback(const rsocket_client *client, const uint8_t *data,
+                                    size_t data_len, const uint8_t *metadata,
+                                    size_t metadata_len, rsocket_response_cb callback,
+                                    void *user_data);
+
+rsocket_stream *rsocket_request_stream(const rsocket_client *client, const uint8_t *data,
+                                       size_t data_len, const uint8_t *metadata,
+                                       size_t metadata_len);
+
+/* Wait up to timeout_ms for the next response, or forever if it is negative.
+ * Returns RSOCKET_NEXT and sets response, which must be freed with rsocket_payload_free,
+ * RSOCKET_COMPLETE, RSOCKET_TIMEOUT or RSOCKET_ERROR. */
+int rsocket_stream_poll(rsocket_stream *stream, int64_t timeout_ms,
+                        rsocket_payload **response);
+
+/* Free the stream, which cancels it if it did not terminate yet. */
+void rsocket_stream_free(rsocket_stream *stream);
+
+/* Returns NULL if there is no data or metadata. */
+const uint8_t *rsocket_payload_data(const rsocket_payload *payload, size_t *len);
+const uint8_t *rsocket_payload_metadata(const rsocket_payload *payload, size_t *len);
+void rsocket_payload_free(rsocket_payload *payload);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,390 @@
+//! C bindings of the RSocket client, see `include/rsocket.h`.
+//!
+//! Every pointer passed in must be either NULL where allowed or valid for the documented
+//! length, and every handle must come from this library and be freed exactly once.
+#![allow(clippy::missing_safety_doc)]
+
+#[macro_use]
+extern crate log;
+
+use bytes::Bytes;
+use futures::StreamExt;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust_transport_tcp::TcpClientTransport;
+use rsocket_rust_transport_websocket::WebsocketClientTransport;
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::slice;
+use std::time::Duration;
+use tokio::runtime::{Handle, Runtime};
+use tokio::time;
+
+pub const RSOCKET_OK: c_int = 0;
+pub const RSOCKET_ERROR: c_int = -1;
+pub const RSOCKET_NEXT: c_int = 1;
+pub const RSOCKET_COMPLETE: c_int = 0;
+pub const RSOCKET_TIMEOUT: c_int = -2;
+
+/// Called with either a response or an error, both are only valid during the call.
+/// Streams call it once more with both NULL when they complete.
+pub type ResponseCallback =
+    extern "C" fn(user_data: *mut c_void, response: *const RSocketPayload, error: *const c_char);
+
+/// A connected client, which owns the runtime its requests and callbacks run on.
+pub struct RSocketClient {
+    rt: Runtime,
+    client: Client<DefaultSpawner>,
+}
+
+pub struct RSocketStream {
+    handle: Handle,
+    responses: Flux<Result<Payload, RSocketError>>,
+}
+
+#[repr(transparent)]
+pub struct RSocketPayload(Payload);
+
+struct Callback {
+    f: ResponseCallback,
+    user_data: *mut c_void,
+}
+
+// user_data is owned by the caller, who promised it may be used from the runtime threads.
+unsafe impl Send for Callback {}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+impl Callback {
+    fn call(&self, response: Option<&Payload>, error: Option<&str>) {
+        let response = response.map(|it| it as *const Payload as *const RSocketPayload);
+        let error = error.map(c_string);
+        (self.f)(
+            self.user_data,
+            response.unwrap_or(ptr::null()),
+            error.as_ref().map(|it| it.as_ptr()).unwrap_or(ptr::null()),
+        );
+    }
+}
+
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', " ")).unwrap()
+}
+
+fn set_last_error<E>(e: E)
+where
+    E: ToString,
+{
+    let e = e.to_string();
+    debug!("rsocket ffi error: {}", e);
+    LAST_ERROR.with(|it| *it.borrow_mut() = Some(c_string(&e)));
+}
+
+unsafe fn bytes(b: *const u8, len: usize) -> Option<Bytes> {
+    if b.is_null() {
+        None
+    } else {
+        Some(Bytes::copy_from_slice(slice::from_raw_parts(b, len)))
+    }
+}
+
+unsafe fn payload(
+    data: *const u8,
+    data_len: usize,
+    metadata: *const u8,
+    metadata_len: usize,
+) -> Payload {
+    Payload::new(bytes(data, data_len), bytes(metadata, metadata_len))
+}
+
+fn resolve(addr: &str) -> Result<SocketAddr, Box<dyn Error + Send + Sync>> {
+    addr.to_socket_addrs()?
+        .next()
+        .ok_or_else(|| From::from(format!("resolve {} failed", addr)))
+}
+
+async fn connect(uri: &str) -> Result<Client<DefaultSpawner>, Box<dyn Error + Send + Sync>> {
+    if let Some(addr) = uri.strip_prefix("tcp://") {
+        RSocketFactory::connect()
+            .transport(TcpClientTransport::from(resolve(addr)?))
+            .start()
+            .await
+    } else if uri.starts_with("ws://") {
+        RSocketFactory::connect()
+            .transport(WebsocketClientTransport::from(uri))
+            .start()
+            .await
+    } else {
+        Err(From::from(format!("unsupported URI: {}", uri)))
+    }
+}
+
+/// Returns the message of the last failure on the calling thread, or NULL.
+#[no_mangle]
+pub extern "C" fn rsocket_last_error() -> *const c_char {
+    LAST_ERROR.with(|it| {
+        it.borrow()
+            .as_ref()
+            .map(|it| it.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
+
+/// Connect to a `tcp://host:port` or `ws://host:port` URI, returns NULL on failure.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_client_connect(uri: *const c_char) -> *mut RSocketClient {
+    if uri.is_null() {
+        set_last_error("uri is NULL");
+        return ptr::null_mut();
+    }
+    let uri = match CStr::from_ptr(uri).to_str() {
+        Ok(it) => it,
+        Err(e) => {
+            set_last_error(e);
+            return ptr::null_mut();
+        }
+    };
+    let mut rt = match Runtime::new() {
+        Ok(it) => it,
+        Err(e) => {
+            set_last_error(e);
+            return ptr::null_mut();
+        }
+    };
+    match rt.block_on(connect(uri)) {
+        Ok(client) => Box::into_raw(Box::new(RSocketClient { rt, client })),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Close the connection and free the client. Must not be called from a callback.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_client_close(client: *mut RSocketClient) {
+    if client.is_null() {
+        return;
+    }
+    let client = Box::from_raw(client);
+    client.client.close();
+    client.rt.shutdown_timeout(Duration::from_secs(1));
+}
+
+/// Send a REQUEST_RESPONSE, `callback` is called once on a runtime thread.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_request_response(
+    client: *const RSocketClient,
+    data: *const u8,
+    data_len: usize,
+    metadata: *const u8,
+    metadata_len: usize,
+    callback: ResponseCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let client = match client.as_ref() {
+        Some(it) => it,
+        None => {
+            set_last_error("client is NULL");
+            return RSOCKET_ERROR;
+        }
+    };
+    let req = payload(data, data_len, metadata, metadata_len);
+    let callback = Callback {
+        f: callback,
+        user_data,
+    };
+    let res = client.rt.enter(|| client.client.request_response(req));
+    client.rt.spawn(async move {
+        match res.await {
+            Ok(it) => callback.call(Some(&it), None),
+            Err(e) => callback.call(None, Some(&e.to_string())),
+        }
+    });
+    RSOCKET_OK
+}
+
+/// Send a REQUEST_FNF, blocks until the frame is queued.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_fire_and_forget(
+    client: *const RSocketClient,
+    data: *const u8,
+    data_len: usize,
+    metadata: *const u8,
+    metadata_len: usize,
+) -> c_int {
+    match client.as_ref() {
+        Some(client) => {
+            let req = payload(data, data_len, metadata, metadata_len);
+            let handle = client.rt.handle();
+            handle.block_on(async { client.client.fire_and_forget(req).await });
+            RSOCKET_OK
+        }
+        None => {
+            set_last_error("client is NULL");
+            RSOCKET_ERROR
+        }
+    }
+}
+
+/// Send a REQUEST_STREAM whose responses are polled with `rsocket_stream_poll`.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_request_stream(
+    client: *const RSocketClient,
+    data: *const u8,
+    data_len: usize,
+    metadata: *const u8,
+    metadata_len: usize,
+) -> *mut RSocketStream {
+    let client = match client.as_ref() {
+        Some(it) => it,
+        None => {
+            set_last_error("client is NULL");
+            return ptr::null_mut();
+        }
+    };
+    let req = payload(data, data_len, metadata, metadata_len);
+    let handle = client.rt.handle().clone();
+    let responses = handle.enter(|| client.client.request_stream(req));
+    Box::into_raw(Box::new(RSocketStream { handle, responses }))
+}
+
+/// Send a REQUEST_STREAM, `callback` is called on a runtime thread for every response and
+/// once more when the stream terminates.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_request_stream_callback(
+    client: *const RSocketClient,
+    data: *const u8,
+    data_len: usize,
+    metadata: *const u8,
+    metadata_len: usize,
+    callback: ResponseCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    let client = match client.as_ref() {
+        Some(it) => it,
+        None => {
+            set_last_error("client is NULL");
+            return RSOCKET_ERROR;
+        }
+    };
+    let req = payload(data, data_len, metadata, metadata_len);
+    let callback = Callback {
+        f: callback,
+        user_data,
+    };
+    let mut responses = client.rt.enter(|| client.client.request_stream(req));
+    client.rt.spawn(async move {
+        while let Some(next) = responses.next().await {
+            match next {
+                Ok(it) => callback.call(Some(&it), None),
+                Err(e) => return callback.call(None, Some(&e.to_string())),
+            }
+        }
+        callback.call(None, None);
+    });
+    RSOCKET_OK
+}
+
+/// Wait up to `timeout_ms` for the next response, or forever if it is negative.
+/// Returns RSOCKET_NEXT and sets `response`, RSOCKET_COMPLETE, RSOCKET_TIMEOUT or RSOCKET_ERROR.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_stream_poll(
+    stream: *mut RSocketStream,
+    timeout_ms: i64,
+    response: *mut *mut RSocketPayload,
+) -> c_int {
+    let stream = match stream.as_mut() {
+        Some(it) => it,
+        None => {
+            set_last_error("stream is NULL");
+            return RSOCKET_ERROR;
+        }
+    };
+    let next = if timeout_ms < 0 {
+        stream.handle.block_on(stream.responses.next())
+    } else {
+        let timeout = Duration::from_millis(timeout_ms as u64);
+        let responses = &mut stream.responses;
+        match stream
+            .handle
+            .block_on(async { time::timeout(timeout, responses.next()).await })
+        {
+            Ok(it) => it,
+            Err(_) => return RSOCKET_TIMEOUT,
+        }
+    };
+    match next {
+        Some(Ok(it)) => {
+            if !response.is_null() {
+                *response = Box::into_raw(Box::new(RSocketPayload(it)));
+            }
+            RSOCKET_NEXT
+        }
+        Some(Err(e)) => {
+            set_last_error(e);
+            RSOCKET_ERROR
+        }
+        None => RSOCKET_COMPLETE,
+    }
+}
+
+/// Free the stream, which cancels it if it did not terminate yet.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_stream_free(stream: *mut RSocketStream) {
+    if !stream.is_null() {
+        let stream = Box::from_raw(stream);
+        let handle = stream.handle.clone();
+        handle.enter(|| drop(stream));
+    }
+}
+
+/// Returns the data of the payload and sets `len`, or NULL if there is no data.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_payload_data(
+    payload: *const RSocketPayload,
+    len: *mut usize,
+) -> *const u8 {
+    match payload.as_ref() {
+        Some(RSocketPayload(it)) => slice_parts(it.data(), len),
+        None => ptr::null(),
+    }
+}
+
+/// Returns the metadata of the payload and sets `len`, or NULL if there is no metadata.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_payload_metadata(
+    payload: *const RSocketPayload,
+    len: *mut usize,
+) -> *const u8 {
+    match payload.as_ref() {
+        Some(RSocketPayload(it)) => slice_parts(it.metadata(), len),
+        None => ptr::null(),
+    }
+}
+
+/// Free a payload returned by `rsocket_stream_poll`.
+#[no_mangle]
+pub unsafe extern "C" fn rsocket_payload_free(payload: *mut RSocketPayload) {
+    if !payload.is_null() {
+        drop(Box::from_raw(payload));
+    }
+}
+
+unsafe fn slice_parts(b: &Option<Bytes>, len: *mut usize) -> *const u8 {
+    let (p, n) = match b {
+        Some(it) => (it.as_ptr(), it.len()),
+        None => (ptr::null(), 0),
+    };
+    if !len.is_null() {
+        *len = n;
+    }
+    p
+}
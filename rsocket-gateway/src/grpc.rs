@@ -0,0 +1,559 @@
+//! A bridge between gRPC and RSocket, to front RSocket services with gRPC or the other way
+//! around during migrations.
+//!
+//! Unary calls map to REQUEST_RESPONSE, server streaming calls to REQUEST_STREAM, client
+//! streaming and bidi calls to REQUEST_CHANNEL. The method `/pkg.Service/Method` is the route
+//! `pkg.Service.Method` and messages are the data of payloads.
+//!
+//! `GrpcBridge` serves gRPC calls with a backend RSocket, `GrpcResponder` is an RSocket which
+//! calls gRPC methods through a `GrpcChannel`, e.g. a wrapped tonic client.
+use super::{forward_headers, value_of};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::{future, stream, FutureExt, StreamExt};
+use http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use http::{Request, Response, StatusCode};
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::extension::{AuthMetadata, CompositeMetadata};
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::route_of;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+pub const APPLICATION_GRPC: &str = "application/grpc";
+
+const GRPC_STATUS: &str = "grpc-status";
+const GRPC_MESSAGE: &str = "grpc-message";
+const PREFIX_LEN: usize = 5;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CallKind {
+    Unary,
+    ServerStreaming,
+    ClientStreaming,
+    Bidi,
+}
+
+/// Status codes of gRPC.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Code {
+    Ok = 0,
+    Cancelled = 1,
+    Unknown = 2,
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    AlreadyExists = 6,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    FailedPrecondition = 9,
+    Aborted = 10,
+    OutOfRange = 11,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    DataLoss = 15,
+    Unauthenticated = 16,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Status {
+    code: Code,
+    message: String,
+}
+
+/// A frame of a gRPC response body, the trailers are the last one.
+#[derive(Debug)]
+pub enum GrpcFrame {
+    Data(Bytes),
+    Trailers(HeaderMap),
+}
+
+/// Calls gRPC methods for a `GrpcResponder`.
+pub trait GrpcChannel: Send + Sync {
+    /// Call a method with request messages, returns the response messages or the status the
+    /// call failed with. Messages have no length prefix.
+    fn call(
+        &self,
+        path: &str,
+        kind: CallKind,
+        metadata: HeaderMap,
+        messages: Flux<Bytes>,
+    ) -> Flux<Result<Bytes, Status>>;
+}
+
+/// Serves gRPC calls with a backend RSocket.
+#[derive(Clone)]
+pub struct GrpcBridge {
+    backend: Arc<dyn RSocket>,
+    kinds: HashMap<String, CallKind>,
+    headers: Vec<(HeaderName, String)>,
+}
+
+/// An RSocket which serves requests by calling gRPC methods.
+pub struct GrpcResponder<C> {
+    channel: Arc<C>,
+    headers: Vec<(HeaderName, String)>,
+}
+
+/// Splits the chunks of a body into length prefixed messages.
+#[derive(Debug, Default)]
+pub struct MessageDecoder {
+    buf: BytesMut,
+}
+
+impl Code {
+    pub fn from_i32(code: i32) -> Code {
+        match code {
+            0 => Code::Ok,
+            1 => Code::Cancelled,
+            3 => Code::InvalidArgument,
+            4 => Code::DeadlineExceeded,
+            5 => Code::NotFound,
+            6 => Code::AlreadyExists,
+            7 => Code::PermissionDenied,
+            8 => Code::ResourceExhausted,
+            9 => Code::FailedPrecondition,
+            10 => Code::Aborted,
+            11 => Code::OutOfRange,
+            12 => Code::Unimplemented,
+            13 => Code::Internal,
+            14 => Code::Unavailable,
+            15 => Code::DataLoss,
+            16 => Code::Unauthenticated,
+            _ => Code::Unknown,
+        }
+    }
+}
+
+impl Status {
+    pub fn new<S>(code: Code, message: S) -> Status
+    where
+        S: Into<String>,
+    {
+        Status {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn ok() -> Status {
+        Status::new(Code::Ok, "")
+    }
+
+    pub fn get_code(&self) -> Code {
+        self.code
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the `grpc-status` and `grpc-message` trailers of the status.
+    pub fn to_trailers(&self) -> HeaderMap {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(GRPC_STATUS, HeaderValue::from(self.code as i32));
+        if !self.message.is_empty() {
+            let message = HeaderValue::from_str(&percent_encode(&self.message)).unwrap();
+            trailers.insert(GRPC_MESSAGE, message);
+        }
+        trailers
+    }
+
+    /// Read the status of trailers, None if there is no `grpc-status`.
+    pub fn from_trailers(trailers: &HeaderMap) -> Option<Status> {
+        let code = value_of(trailers, &HeaderName::from_static(GRPC_STATUS))?;
+        let code = Code::from_i32(code.parse().unwrap_or(Code::Unknown as i32));
+        let message = value_of(trailers, &HeaderName::from_static(GRPC_MESSAGE))
+            .map(percent_decode)
+            .unwrap_or_default();
+        Some(Status::new(code, message))
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl From<&RSocketError> for Status {
+    fn from(e: &RSocketError) -> Status {
+        match e.kind() {
+            ErrorKind::Internal(code, msg) => {
+                let code = match *code {
+                    error::ERR_INVALID => Code::InvalidArgument,
+                    error::ERR_REJECTED => Code::Unavailable,
+                    error::ERR_CANCELED => Code::Cancelled,
+                    error::ERR_APPLICATION => Code::Unknown,
+                    error::ERR_CONN_FAILED | error::ERR_CONN_CLOSED => Code::Unavailable,
+                    _ => Code::Internal,
+                };
+                Status::new(code, msg.as_str())
+            }
+            ErrorKind::Cancelled() => Status::new(Code::Cancelled, e.to_string()),
+            _ => Status::new(Code::Unavailable, e.to_string()),
+        }
+    }
+}
+
+impl From<Status> for RSocketError {
+    fn from(status: Status) -> RSocketError {
+        let code = match status.code {
+            Code::InvalidArgument => error::ERR_INVALID,
+            Code::Unavailable | Code::ResourceExhausted => error::ERR_REJECTED,
+            Code::Cancelled => error::ERR_CANCELED,
+            _ => error::ERR_APPLICATION,
+        };
+        let msg = if status.message.is_empty() {
+            format!("{:?}", status.code)
+        } else {
+            status.message
+        };
+        RSocketError::from(ErrorKind::Internal(code, msg))
+    }
+}
+
+/// Encode a message with the gRPC length prefix, uncompressed.
+pub fn encode_message(msg: &[u8]) -> Bytes {
+    let mut b = BytesMut::with_capacity(PREFIX_LEN + msg.len());
+    b.put_u8(0);
+    b.put_u32(msg.len() as u32);
+    b.put_slice(msg);
+    b.freeze()
+}
+
+impl MessageDecoder {
+    pub fn new() -> MessageDecoder {
+        MessageDecoder::default()
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Returns the next complete message, compressed messages are not supported.
+    pub fn next_message(&mut self) -> Result<Option<Bytes>, Status> {
+        if self.buf.len() < PREFIX_LEN {
+            return Ok(None);
+        }
+        if self.buf[0] != 0 {
+            return Err(Status::new(
+                Code::Unimplemented,
+                "compressed messages are not supported",
+            ));
+        }
+        let len = (&self.buf[1..PREFIX_LEN]).get_u32() as usize;
+        if self.buf.len() < PREFIX_LEN + len {
+            return Ok(None);
+        }
+        self.buf.advance(PREFIX_LEN);
+        Ok(Some(self.buf.split_to(len).freeze()))
+    }
+
+    /// Returns true if no bytes of an incomplete message are left.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+impl GrpcBridge {
+    pub fn new<R>(backend: R) -> GrpcBridge
+    where
+        R: RSocket + 'static,
+    {
+        GrpcBridge {
+            backend: Arc::new(backend),
+            kinds: HashMap::new(),
+            headers: vec![],
+        }
+    }
+
+    /// Set the kind of a method like `/pkg.Service/Method`, methods are unary by default.
+    pub fn method(mut self, path: &str, kind: CallKind) -> Self {
+        self.kinds
+            .insert(String::from(path.trim_start_matches('/')), kind);
+        self
+    }
+
+    /// Forward the values of a header as metadata entries of the given MIME type, a bearer
+    /// `Authorization` is always forwarded.
+    pub fn header(mut self, name: &str, mime_type: &str) -> Self {
+        let name = HeaderName::from_bytes(name.as_bytes()).expect("invalid header name");
+        self.headers.push((name, String::from(mime_type)));
+        self
+    }
+
+    pub async fn handle(&self, req: Request<Flux<Bytes>>) -> Response<Flux<GrpcFrame>> {
+        let (parts, body) = req.into_parts();
+        let content_type = value_of(&parts.headers, &header::CONTENT_TYPE).unwrap_or_default();
+        if !content_type.starts_with(APPLICATION_GRPC) {
+            return Response::builder()
+                .status(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                .body(Box::pin(stream::empty()) as Flux<GrpcFrame>)
+                .unwrap();
+        }
+        let path = parts.uri.path().trim_start_matches('/');
+        let kind = self.kinds.get(path).copied().unwrap_or(CallKind::Unary);
+        let route = path.replace('/', ".");
+        debug!("gRPC {} -> route {}", parts.uri, route);
+        let mut messages = decode_messages(body);
+        // every interaction of RSocket starts with a payload.
+        let first = match messages.next().await {
+            Some(Ok(it)) => it,
+            Some(Err(status)) => return respond(Box::pin(stream::iter(Some(Err(status))))),
+            None => {
+                let status = Status::new(Code::InvalidArgument, "missing request message");
+                return respond(Box::pin(stream::iter(Some(Err(status)))));
+            }
+        };
+        let req = forward_headers(
+            Payload::builder().set_data(first).metadata().route(&route),
+            &parts.headers,
+            &self.headers,
+        )
+        .build();
+        let results = match kind {
+            CallKind::Unary => Box::pin(stream::once(self.backend.request_response(req))),
+            CallKind::ServerStreaming => self.backend.request_stream(req),
+            CallKind::ClientStreaming | CallKind::Bidi => {
+                let rest = messages.map(|it| match it {
+                    Ok(msg) => Ok(Payload::builder().set_data(msg).build()),
+                    Err(status) => Err(RSocketError::from(status)),
+                });
+                let reqs = stream::iter(Some(Ok(req))).chain(rest);
+                let outputs = self.backend.request_channel(Box::pin(reqs));
+                if kind == CallKind::ClientStreaming {
+                    Box::pin(outputs.take(1))
+                } else {
+                    outputs
+                }
+            }
+        };
+        respond(Box::pin(results.map(|it| match it {
+            Ok(res) => Ok(res.split().0.unwrap_or_default()),
+            Err(e) => Err(Status::from(&e)),
+        })))
+    }
+}
+
+impl<C> GrpcResponder<C>
+where
+    C: GrpcChannel,
+{
+    pub fn new(channel: C) -> GrpcResponder<C> {
+        GrpcResponder {
+            channel: Arc::new(channel),
+            headers: vec![],
+        }
+    }
+
+    /// Forward metadata entries of the given MIME type as values of a header, bearer
+    /// authentication metadata is always forwarded as `Authorization`.
+    pub fn header(mut self, name: &str, mime_type: &str) -> Self {
+        let name = HeaderName::from_bytes(name.as_bytes()).expect("invalid header name");
+        self.headers.push((name, String::from(mime_type)));
+        self
+    }
+
+    fn call(
+        &self,
+        req: &Payload,
+        kind: CallKind,
+        messages: Flux<Bytes>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let path = match route_of(req).as_deref().and_then(path_of) {
+            Some(it) => it,
+            None => {
+                let e =
+                    ErrorKind::Internal(error::ERR_INVALID, String::from("missing gRPC method"));
+                return Box::pin(stream::iter(Some(Err(RSocketError::from(e)))));
+            }
+        };
+        let metadata = self.headers_of(req);
+        let results = self.channel.call(&path, kind, metadata, messages);
+        Box::pin(results.map(|it| match it {
+            Ok(msg) => Ok(Payload::builder().set_data(msg).build()),
+            Err(status) => Err(RSocketError::from(status)),
+        }))
+    }
+
+    fn headers_of(&self, req: &Payload) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let composite = match req.metadata() {
+            Some(it) => CompositeMetadata::decode(&mut BytesMut::from(it.as_ref())).ok(),
+            None => None,
+        };
+        let composite = match composite {
+            Some(it) => it,
+            None => return headers,
+        };
+        for it in composite.iter() {
+            let mime = it.get_mime().as_str();
+            if mime == rsocket_rust::mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0 {
+                let mut b = BytesMut::from(it.get_payload().as_ref());
+                if let Ok(AuthMetadata::Bearer(token)) = AuthMetadata::decode(&mut b) {
+                    if let Ok(value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+                        headers.insert(header::AUTHORIZATION, value);
+                    }
+                }
+                continue;
+            }
+            for (name, forwarded) in &self.headers {
+                if forwarded != mime {
+                    continue;
+                }
+                match HeaderValue::from_bytes(it.get_payload()) {
+                    Ok(value) => {
+                        headers.append(name, value);
+                    }
+                    Err(_) => warn!("drop invalid value of header {}", name),
+                }
+            }
+        }
+        headers
+    }
+}
+
+impl<C> RSocket for GrpcResponder<C>
+where
+    C: GrpcChannel + 'static,
+{
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(future::ready(()))
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        let mut results = self.call(&req, CallKind::Unary, data_of(req.clone()));
+        Box::pin(async move {
+            if let Some(Err(e)) = results.next().await {
+                warn!("fire-and-forget gRPC call failed: {}", e);
+            }
+        })
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let mut results = self.call(&req, CallKind::Unary, data_of(req.clone()));
+        Box::pin(async move {
+            match results.next().await {
+                Some(it) => it,
+                None => Err(RSocketError::from(Status::new(
+                    Code::Internal,
+                    "missing response message",
+                ))),
+            }
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.call(&req, CallKind::ServerStreaming, data_of(req.clone()))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        // the route is carried by the first payload of a channel.
+        let responder = GrpcResponder {
+            channel: self.channel.clone(),
+            headers: self.headers.clone(),
+        };
+        let results = reqs.into_future().map(move |(first, rest)| match first {
+            Some(Ok(first)) => {
+                // the input ends with its first error.
+                let rest = rest
+                    .take_while(|it| future::ready(it.is_ok()))
+                    .filter_map(|it| future::ready(it.ok().and_then(|it| it.split().0)));
+                let messages = data_of(first.clone()).chain(rest);
+                responder.call(&first, CallKind::Bidi, Box::pin(messages))
+            }
+            Some(Err(e)) => Box::pin(stream::iter(Some(Err(e)))) as Flux<_>,
+            None => Box::pin(stream::empty()) as Flux<_>,
+        });
+        Box::pin(stream::once(results).flatten())
+    }
+}
+
+/// Respond with the messages of a call and trailers with the status it ended with.
+fn respond(results: Flux<Result<Bytes, Status>>) -> Response<Flux<GrpcFrame>> {
+    let frames = stream::unfold(Some(results), |state| async move {
+        let mut results = state?;
+        match results.next().await {
+            Some(Ok(msg)) => Some((GrpcFrame::Data(encode_message(&msg)), Some(results))),
+            Some(Err(status)) => Some((GrpcFrame::Trailers(status.to_trailers()), None)),
+            None => Some((GrpcFrame::Trailers(Status::ok().to_trailers()), None)),
+        }
+    });
+    Response::builder()
+        .header(header::CONTENT_TYPE, APPLICATION_GRPC)
+        .body(Box::pin(frames) as Flux<GrpcFrame>)
+        .unwrap()
+}
+
+fn decode_messages(body: Flux<Bytes>) -> Flux<Result<Bytes, Status>> {
+    let messages = stream::unfold(Some((body, MessageDecoder::new())), |state| async move {
+        let (mut body, mut decoder) = state?;
+        loop {
+            match decoder.next_message() {
+                Ok(Some(msg)) => return Some((Ok(msg), Some((body, decoder)))),
+                Ok(None) => (),
+                Err(status) => return Some((Err(status), None)),
+            }
+            match body.next().await {
+                Some(chunk) => decoder.push(&chunk),
+                None if decoder.is_empty() => return None,
+                None => {
+                    let status = Status::new(Code::Internal, "truncated request message");
+                    return Some((Err(status), None));
+                }
+            }
+        }
+    });
+    Box::pin(messages)
+}
+
+#[inline]
+fn data_of(req: Payload) -> Flux<Bytes> {
+    Box::pin(stream::iter(Some(req.split().0.unwrap_or_default())))
+}
+
+/// Returns the method path of a route, `pkg.Service.Method` is `/pkg.Service/Method`.
+fn path_of(route: &str) -> Option<String> {
+    let (service, method) = route.rsplit_once('.')?;
+    Some(format!("/{}/{}", service, method))
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if (0x20..=0x7e).contains(&b) && b != b'%' {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str) -> String {
+    let b = s.as_bytes();
+    let mut decoded = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        let escaped = b
+            .get(i + 1..i + 3)
+            .filter(|_| b[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(it) => {
+                decoded.push(it);
+                i += 3;
+            }
+            None => {
+                decoded.push(b[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
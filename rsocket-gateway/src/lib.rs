@@ -0,0 +1,207 @@
+//! A gateway which serves HTTP requests with a backend RSocket, so HTTP consumers can reach
+//! RSocket services.
+//!
+//! The path of a request is its route, the body its data and headers are mapped to composite
+//! metadata entries. Requests accepting `text/event-stream` are sent as REQUEST_STREAM and
+//! answered with server-sent events, any other request as REQUEST_RESPONSE.
+//!
+//! `Gateway::handle` works with the types of the `http` crate, so it can be mounted in any HTTP
+//! server built on them, e.g. in a hyper service:
+//!
+//! ```ignore
+//! let gateway = Gateway::new(client).header("x-request-id", "text/plain");
+//! let make_svc = make_service_fn(move |_| {
+//!     let gateway = gateway.clone();
+//!     async move {
+//!         Ok::<_, Infallible>(service_fn(move |req: Request<hyper::Body>| {
+//!             let gateway = gateway.clone();
+//!             async move {
+//!                 let (parts, body) = req.into_parts();
+//!                 let body = hyper::body::to_bytes(body).await?;
+//!                 let res = gateway.handle(Request::from_parts(parts, body)).await;
+//!                 Ok::<_, hyper::Error>(res.map(|body| match body {
+//!                     Body::Full(b) => hyper::Body::from(b),
+//!                     Body::Events(events) => hyper::Body::wrap_stream(events.map(Ok::<_, Infallible>)),
+//!                 }))
+//!             }
+//!         }))
+//!     }
+//! });
+//! ```
+#[macro_use]
+extern crate log;
+
+pub mod grpc;
+
+use bytes::Bytes;
+use futures::{future, StreamExt};
+use http::header::{self, HeaderMap, HeaderName};
+use http::{Request, Response, StatusCode};
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::prelude::*;
+use std::sync::Arc;
+
+pub const TEXT_EVENT_STREAM: &str = "text/event-stream";
+
+/// Body of a response.
+pub enum Body {
+    Full(Bytes),
+    /// Server-sent events, each item is an encoded event.
+    Events(Flux<Bytes>),
+}
+
+#[derive(Clone)]
+pub struct Gateway {
+    backend: Arc<dyn RSocket>,
+    headers: Vec<(HeaderName, String)>,
+}
+
+impl Gateway {
+    pub fn new<R>(backend: R) -> Gateway
+    where
+        R: RSocket + 'static,
+    {
+        Gateway {
+            backend: Arc::new(backend),
+            headers: vec![],
+        }
+    }
+
+    /// Forward the values of a header as metadata entries of the given MIME type.
+    ///
+    /// `Content-Type` is always forwarded as the MIME type of the request data and a bearer
+    /// `Authorization` as authentication metadata.
+    pub fn header(mut self, name: &str, mime_type: &str) -> Self {
+        let name = HeaderName::from_bytes(name.as_bytes()).expect("invalid header name");
+        self.headers.push((name, String::from(mime_type)));
+        self
+    }
+
+    pub async fn handle(&self, req: Request<Bytes>) -> Response<Body> {
+        let (parts, body) = req.into_parts();
+        let route = parts.uri.path().trim_start_matches('/');
+        if route.is_empty() {
+            return text(StatusCode::NOT_FOUND, "missing route");
+        }
+        debug!("{} {} -> route {}", parts.method, parts.uri, route);
+        let payload = match self.payload_of(route, &parts.headers, body) {
+            Ok(it) => it,
+            Err(e) => return text(StatusCode::BAD_REQUEST, &e.to_string()),
+        };
+        if !accepts_events(&parts.headers) {
+            return match self.backend.request_response(payload).await {
+                Ok(res) => {
+                    let (data, _) = res.split();
+                    Response::new(Body::Full(data.unwrap_or_default()))
+                }
+                Err(e) => text(status_of(&e), &e.to_string()),
+            };
+        }
+        // the stream ends with the first error.
+        let events = self
+            .backend
+            .request_stream(payload)
+            .scan(false, |failed, it| {
+                if *failed {
+                    return future::ready(None);
+                }
+                *failed = it.is_err();
+                future::ready(Some(event_of(it)))
+            });
+        Response::builder()
+            .header(header::CONTENT_TYPE, TEXT_EVENT_STREAM)
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(Body::Events(Box::pin(events)))
+            .unwrap()
+    }
+
+    fn payload_of(
+        &self,
+        route: &str,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> Result<Payload, RSocketError> {
+        let mut bu = Payload::builder();
+        if !body.is_empty() {
+            bu = bu.set_data(body);
+        }
+        let mut metadata = bu.metadata().route(route);
+        if let Some(mime_type) = value_of(headers, &header::CONTENT_TYPE) {
+            metadata = metadata.mime_type(mime_type)?;
+        }
+        Ok(forward_headers(metadata, headers, &self.headers).build())
+    }
+}
+
+/// Add a bearer `Authorization` and the forwarded headers to metadata.
+fn forward_headers(
+    mut metadata: MetadataBuilder,
+    headers: &HeaderMap,
+    forwarded: &[(HeaderName, String)],
+) -> MetadataBuilder {
+    if let Some(token) =
+        value_of(headers, &header::AUTHORIZATION).and_then(|it| it.strip_prefix("Bearer "))
+    {
+        metadata = metadata.bearer(token);
+    }
+    for (name, mime_type) in forwarded {
+        for value in headers.get_all(name) {
+            metadata = metadata.custom(mime_type, value.as_bytes());
+        }
+    }
+    metadata
+}
+
+#[inline]
+fn value_of<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|it| it.to_str().ok())
+}
+
+#[inline]
+fn accepts_events(headers: &HeaderMap) -> bool {
+    value_of(headers, &header::ACCEPT).is_some_and(|it| it.contains(TEXT_EVENT_STREAM))
+}
+
+/// Encode a response as a server-sent event, errors are sent as `error` events.
+fn event_of(res: Result<Payload, RSocketError>) -> Bytes {
+    let (kind, data) = match &res {
+        Ok(it) => (
+            "",
+            String::from_utf8_lossy(it.data().as_deref().unwrap_or_default()),
+        ),
+        Err(e) => ("error", e.to_string().into()),
+    };
+    let mut event = String::new();
+    if !kind.is_empty() {
+        event.push_str("event: ");
+        event.push_str(kind);
+        event.push('\n');
+    }
+    for line in data.split('\n') {
+        event.push_str("data: ");
+        event.push_str(line);
+        event.push('\n');
+    }
+    event.push('\n');
+    Bytes::from(event)
+}
+
+fn status_of(e: &RSocketError) -> StatusCode {
+    match e.kind() {
+        ErrorKind::Internal(code, _) => match *code {
+            error::ERR_INVALID => StatusCode::BAD_REQUEST,
+            error::ERR_REJECTED => StatusCode::SERVICE_UNAVAILABLE,
+            error::ERR_APPLICATION => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_GATEWAY,
+        },
+        _ => StatusCode::BAD_GATEWAY,
+    }
+}
+
+fn text(status: StatusCode, msg: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Body::Full(Bytes::from(msg.to_string())))
+        .unwrap()
+}
@@ -0,0 +1,68 @@
+extern crate proc_macro;
+
+mod route;
+mod service;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ItemFn, ItemTrait, LitStr};
+
+/// Generate a typed client stub and a routing responder for a service trait.
+///
+/// Every method takes `&self` and one request argument, the interaction model is
+/// inferred from the return type:
+///
+/// - `Mono<()>`: fire-and-forget
+/// - `Mono<Result<T, RSocketError>>`: request-response
+/// - `Flux<Result<T, RSocketError>>`: request-stream
+///
+/// Methods are routed by `{Trait}.{method}` unless a `#[route("...")]` is given.
+/// For a trait `Greeter`, a `GreeterClient<R: RSocket>` implementing `Greeter` and
+/// a `GreeterServer<T: Greeter>` implementing `RSocket` are generated.
+/// Requires the `serde` feature of `rsocket_rust`.
+///
+/// ```ignore
+/// #[rsocket_rust_macros::service]
+/// pub trait Greeter: Send + Sync {
+///     #[route("greetings.hello")]
+///     fn hello(&self, req: HelloRequest) -> Mono<Result<HelloReply, RSocketError>>;
+///     fn subscribe(&self, topic: String) -> Flux<Result<Event, RSocketError>>;
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn service(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemTrait);
+    match service::expand(args.into(), item) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Turn an async function into a `Router` handler of the given route.
+///
+/// The unmarked argument is deserialized from the payload data with its per-stream
+/// MIME type (JSON by default), arguments marked `#[metadata]` are extracted from the
+/// composite metadata with `rsocket_rust::rpc::FromMetadata`. Functions returning
+/// nothing handle fire-and-forget, others return `Result<T, RSocketError>` and handle
+/// request-response. The function is replaced by a unit struct of the same name, which
+/// is registered with `Router::handler`. A route ending with `*` matches by prefix.
+///
+/// ```ignore
+/// #[route("orders.create")]
+/// async fn create_order(
+///     order: Order,
+///     #[metadata] auth: AuthMetadata,
+/// ) -> Result<Receipt, RSocketError> {
+///     ...
+/// }
+///
+/// let router = Router::new().handler(create_order);
+/// ```
+#[proc_macro_attribute]
+pub fn route(args: TokenStream, input: TokenStream) -> TokenStream {
+    let route = parse_macro_input!(args as LitStr);
+    let item = parse_macro_input!(input as ItemFn);
+    match route::expand(route, item) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
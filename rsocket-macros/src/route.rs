@@ -0,0 +1,156 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Error, FnArg, ItemFn, LitStr, Result, ReturnType, Type};
+
+pub(crate) fn expand(route: LitStr, mut item: ItemFn) -> Result<TokenStream> {
+    if item.sig.asyncness.is_none() {
+        return Err(Error::new(item.sig.span(), "route handlers must be async"));
+    }
+    if !item.sig.generics.params.is_empty() {
+        return Err(Error::new(
+            item.sig.generics.span(),
+            "route handlers must not be generic",
+        ));
+    }
+
+    let mut data: Option<Type> = None;
+    let mut types = vec![];
+    let mut extracts = vec![];
+    for arg in item.sig.inputs.iter_mut() {
+        let arg = match arg {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(r) => {
+                return Err(Error::new(
+                    r.span(),
+                    "route handlers must be free functions",
+                ))
+            }
+        };
+        let before = arg.attrs.len();
+        arg.attrs.retain(|it| !it.path.is_ident("metadata"));
+        let ty = (*arg.ty).clone();
+        if arg.attrs.len() != before {
+            extracts.push(quote! { ::rsocket_rust::rpc::extract::<#ty>(&composite)? });
+        } else if data.is_some() {
+            return Err(Error::new(
+                arg.span(),
+                "only one data argument is allowed, mark metadata arguments with #[metadata]",
+            ));
+        } else {
+            data = Some(ty.clone());
+            extracts
+                .push(quote! { ::rsocket_rust::codec::decode_payload::<#ty>(req, &mime_type)? });
+        }
+        types.push(ty);
+    }
+    let args: Vec<_> = (0..types.len())
+        .map(|i| format_ident!("arg{}", i))
+        .collect();
+    let fire_and_forget = matches!(item.sig.output, ReturnType::Default);
+
+    let vis = item.vis.clone();
+    let name = item.sig.ident.clone();
+    item.sig.ident = format_ident!("handle");
+    item.vis = syn::Visibility::Inherited;
+
+    let (fnf, rr) = if fire_and_forget {
+        (
+            quote! {
+                match #name::extract(&req) {
+                    Ok((#(#args,)* _)) => ::std::boxed::Box::pin(async move {
+                        #name::handle(#(#args),*).await;
+                    }),
+                    Err(e) => ::rsocket_rust::rpc::error_fnf(e),
+                }
+            },
+            quote! {
+                ::rsocket_rust::rpc::error_mono(::rsocket_rust::rpc::unsupported_interaction(#route))
+            },
+        )
+    } else {
+        (
+            quote! {
+                ::rsocket_rust::rpc::error_fnf(::rsocket_rust::rpc::unsupported_interaction(#route))
+            },
+            quote! {
+                match #name::extract(&req) {
+                    Ok((#(#args,)* mime_type)) => ::rsocket_rust::rpc::encode_mono(
+                        ::std::boxed::Box::pin(#name::handle(#(#args),*)),
+                        mime_type,
+                    ),
+                    Err(e) => ::rsocket_rust::rpc::error_mono(e),
+                }
+            },
+        )
+    };
+
+    Ok(quote! {
+        #[allow(non_camel_case_types)]
+        #vis struct #name;
+
+        impl #name {
+            #item
+
+            fn extract(
+                req: &::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::RSocketResult<(#(#types,)* String)> {
+                let composite = ::rsocket_rust::rpc::composite_of(req);
+                let mime_type = ::rsocket_rust::codec::data_mime_type_of(req)
+                    .unwrap_or_else(|| String::from(::rsocket_rust::mime::APPLICATION_JSON));
+                Ok((#(#extracts,)* mime_type))
+            }
+        }
+
+        impl ::rsocket_rust::router::Routed for #name {
+            fn route(&self) -> &str {
+                #route
+            }
+        }
+
+        impl ::rsocket_rust::prelude::RSocket for #name {
+            fn metadata_push(
+                &self,
+                _req: ::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::Mono<()> {
+                ::std::boxed::Box::pin(async {})
+            }
+
+            fn fire_and_forget(
+                &self,
+                req: ::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::Mono<()> {
+                #fnf
+            }
+
+            fn request_response(
+                &self,
+                req: ::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::Mono<
+                Result<::rsocket_rust::prelude::Payload, ::rsocket_rust::error::RSocketError>,
+            > {
+                #rr
+            }
+
+            fn request_stream(
+                &self,
+                _req: ::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::Flux<
+                Result<::rsocket_rust::prelude::Payload, ::rsocket_rust::error::RSocketError>,
+            > {
+                ::rsocket_rust::rpc::error_flux(::rsocket_rust::rpc::unsupported_interaction(#route))
+            }
+
+            fn request_channel(
+                &self,
+                _reqs: ::rsocket_rust::prelude::Flux<
+                    Result<::rsocket_rust::prelude::Payload, ::rsocket_rust::error::RSocketError>,
+                >,
+            ) -> ::rsocket_rust::prelude::Flux<
+                Result<::rsocket_rust::prelude::Payload, ::rsocket_rust::error::RSocketError>,
+            > {
+                ::rsocket_rust::rpc::error_flux(::rsocket_rust::rpc::unsupported_interaction(#route))
+            }
+        }
+    })
+}
@@ -0,0 +1,336 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::spanned::Spanned;
+use syn::{
+    Error, FnArg, GenericArgument, Ident, ItemTrait, LitStr, PathArguments, Result, ReturnType,
+    TraitItem, TraitItemMethod, Type,
+};
+
+enum Model {
+    FireAndForget,
+    RequestResponse,
+    RequestStream,
+}
+
+struct Method {
+    ident: Ident,
+    route: LitStr,
+    req: Type,
+    ret: Type,
+    model: Model,
+}
+
+pub(crate) fn expand(args: TokenStream, mut item: ItemTrait) -> Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(Error::new(args.span(), "service takes no arguments"));
+    }
+    let mut methods = vec![];
+    let mut routes = HashSet::new();
+    for it in item.items.iter_mut() {
+        if let TraitItem::Method(m) = it {
+            let method = parse_method(&item.ident, m)?;
+            if !routes.insert(method.route.value()) {
+                return Err(Error::new(method.route.span(), "duplicated route"));
+            }
+            methods.push(method);
+        }
+    }
+
+    let vis = &item.vis;
+    let service = &item.ident;
+    let client = format_ident!("{}Client", service);
+    let server = format_ident!("{}Server", service);
+
+    let stubs = methods.iter().map(|m| {
+        let Method {
+            ident,
+            route,
+            req,
+            ret,
+            model,
+        } = m;
+        let body = match model {
+            Model::FireAndForget => quote! {
+                Ok(req) => self.inner.fire_and_forget(req),
+                Err(e) => ::rsocket_rust::rpc::error_fnf(e),
+            },
+            Model::RequestResponse => quote! {
+                Ok(req) => ::rsocket_rust::rpc::decode_mono(
+                    self.inner.request_response(req),
+                    self.mime_type.clone(),
+                ),
+                Err(e) => ::rsocket_rust::rpc::error_mono(e),
+            },
+            Model::RequestStream => quote! {
+                Ok(req) => ::rsocket_rust::rpc::decode_flux(
+                    self.inner.request_stream(req),
+                    self.mime_type.clone(),
+                ),
+                Err(e) => ::rsocket_rust::rpc::error_flux(e),
+            },
+        };
+        quote! {
+            fn #ident(&self, req: #req) -> #ret {
+                match ::rsocket_rust::rpc::encode_request(#route, &self.mime_type, &req) {
+                    #body
+                }
+            }
+        }
+    });
+
+    let arms = |model: fn(&Model) -> bool, call: &dyn Fn(&Ident) -> TokenStream| {
+        methods
+            .iter()
+            .filter(|m| model(&m.model))
+            .map(|m| {
+                let route = &m.route;
+                let call = call(&m.ident);
+                quote! { Some(#route) => #call, }
+            })
+            .collect::<Vec<_>>()
+    };
+    let fnf_arms = arms(|m| matches!(m, Model::FireAndForget), &|ident| {
+        quote! {
+            match ::rsocket_rust::rpc::decode_request(&req, &self.mime_type) {
+                Ok((v, _)) => self.inner.#ident(v),
+                Err(e) => ::rsocket_rust::rpc::error_fnf(e),
+            }
+        }
+    });
+    let rr_arms = arms(|m| matches!(m, Model::RequestResponse), &|ident| {
+        quote! {
+            match ::rsocket_rust::rpc::decode_request(&req, &self.mime_type) {
+                Ok((v, mime_type)) => ::rsocket_rust::rpc::encode_mono(self.inner.#ident(v), mime_type),
+                Err(e) => ::rsocket_rust::rpc::error_mono(e),
+            }
+        }
+    });
+    let stream_arms = arms(|m| matches!(m, Model::RequestStream), &|ident| {
+        quote! {
+            match ::rsocket_rust::rpc::decode_request(&req, &self.mime_type) {
+                Ok((v, mime_type)) => ::rsocket_rust::rpc::encode_flux(self.inner.#ident(v), mime_type),
+                Err(e) => ::rsocket_rust::rpc::error_flux(e),
+            }
+        }
+    });
+
+    Ok(quote! {
+        #item
+
+        #vis struct #client<R> {
+            inner: R,
+            mime_type: String,
+        }
+
+        impl<R> #client<R>
+        where
+            R: ::rsocket_rust::prelude::RSocket,
+        {
+            #vis fn new(inner: R) -> #client<R> {
+                #client {
+                    inner,
+                    mime_type: String::from(::rsocket_rust::mime::APPLICATION_JSON),
+                }
+            }
+
+            #vis fn mime_type(mut self, mime_type: &str) -> Self {
+                self.mime_type = String::from(mime_type);
+                self
+            }
+        }
+
+        impl<R> #service for #client<R>
+        where
+            R: ::rsocket_rust::prelude::RSocket,
+        {
+            #(#stubs)*
+        }
+
+        #vis struct #server<T> {
+            inner: ::std::sync::Arc<T>,
+            mime_type: String,
+        }
+
+        impl<T> #server<T>
+        where
+            T: #service + Send + Sync + 'static,
+        {
+            #vis fn new(inner: T) -> #server<T> {
+                #server {
+                    inner: ::std::sync::Arc::new(inner),
+                    mime_type: String::from(::rsocket_rust::mime::APPLICATION_JSON),
+                }
+            }
+
+            /// Set the data MIME type used when a request carries no per-stream MIME type.
+            #vis fn mime_type(mut self, mime_type: &str) -> Self {
+                self.mime_type = String::from(mime_type);
+                self
+            }
+        }
+
+        impl<T> ::rsocket_rust::prelude::RSocket for #server<T>
+        where
+            T: #service + Send + Sync + 'static,
+        {
+            fn metadata_push(
+                &self,
+                _req: ::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::Mono<()> {
+                ::std::boxed::Box::pin(async {})
+            }
+
+            fn fire_and_forget(
+                &self,
+                req: ::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::Mono<()> {
+                let route = ::rsocket_rust::rpc::route_of(&req);
+                match route.as_deref() {
+                    #(#fnf_arms)*
+                    other => ::rsocket_rust::rpc::error_fnf(::rsocket_rust::rpc::unknown_route(other)),
+                }
+            }
+
+            fn request_response(
+                &self,
+                req: ::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::Mono<
+                Result<::rsocket_rust::prelude::Payload, ::rsocket_rust::error::RSocketError>,
+            > {
+                let route = ::rsocket_rust::rpc::route_of(&req);
+                match route.as_deref() {
+                    #(#rr_arms)*
+                    other => ::rsocket_rust::rpc::error_mono(::rsocket_rust::rpc::unknown_route(other)),
+                }
+            }
+
+            fn request_stream(
+                &self,
+                req: ::rsocket_rust::prelude::Payload,
+            ) -> ::rsocket_rust::prelude::Flux<
+                Result<::rsocket_rust::prelude::Payload, ::rsocket_rust::error::RSocketError>,
+            > {
+                let route = ::rsocket_rust::rpc::route_of(&req);
+                match route.as_deref() {
+                    #(#stream_arms)*
+                    other => ::rsocket_rust::rpc::error_flux(::rsocket_rust::rpc::unknown_route(other)),
+                }
+            }
+
+            fn request_channel(
+                &self,
+                _reqs: ::rsocket_rust::prelude::Flux<
+                    Result<::rsocket_rust::prelude::Payload, ::rsocket_rust::error::RSocketError>,
+                >,
+            ) -> ::rsocket_rust::prelude::Flux<
+                Result<::rsocket_rust::prelude::Payload, ::rsocket_rust::error::RSocketError>,
+            > {
+                ::rsocket_rust::rpc::error_flux(::rsocket_rust::error::RSocketError::from(
+                    "request_channel is not supported by services",
+                ))
+            }
+        }
+    })
+}
+
+fn parse_method(service: &Ident, m: &mut TraitItemMethod) -> Result<Method> {
+    let sig = &m.sig;
+    if sig.asyncness.is_some() || !sig.generics.params.is_empty() {
+        return Err(Error::new(
+            sig.span(),
+            "service methods must be neither async nor generic",
+        ));
+    }
+    if m.default.is_some() {
+        return Err(Error::new(m.span(), "service methods must not have a body"));
+    }
+    let mut inputs = sig.inputs.iter();
+    match inputs.next() {
+        Some(FnArg::Receiver(r)) if r.reference.is_some() && r.mutability.is_none() => (),
+        _ => return Err(Error::new(sig.span(), "service methods must take `&self`")),
+    }
+    let req = match (inputs.next(), inputs.next()) {
+        (Some(FnArg::Typed(arg)), None) => (*arg.ty).clone(),
+        _ => {
+            return Err(Error::new(
+                sig.span(),
+                "service methods must take exactly one request argument",
+            ))
+        }
+    };
+    let ret = match &sig.output {
+        ReturnType::Type(_, ty) => (**ty).clone(),
+        ReturnType::Default => {
+            return Err(Error::new(
+                sig.span(),
+                "service methods must return Mono or Flux",
+            ))
+        }
+    };
+    let model = model_of(&ret)?;
+
+    let mut route = None;
+    let mut err = None;
+    m.attrs.retain(|attr| {
+        if !attr.path.is_ident("route") {
+            return true;
+        }
+        match attr.parse_args::<LitStr>() {
+            Ok(lit) => route = Some(lit),
+            Err(e) => err = Some(e),
+        }
+        false
+    });
+    if let Some(e) = err {
+        return Err(e);
+    }
+    let route = route
+        .unwrap_or_else(|| LitStr::new(&format!("{}.{}", service, m.sig.ident), Span::call_site()));
+    Ok(Method {
+        ident: m.sig.ident.clone(),
+        route,
+        req,
+        ret,
+        model,
+    })
+}
+
+fn model_of(ret: &Type) -> Result<Model> {
+    let invalid = || {
+        Error::new(
+            ret.span(),
+            "expect Mono<()>, Mono<Result<T, RSocketError>> or Flux<Result<T, RSocketError>>",
+        )
+    };
+    let (name, inner) = match first_generic(ret) {
+        Some(it) => it,
+        None => return Err(invalid()),
+    };
+    match name.as_str() {
+        "Mono" => match inner {
+            Type::Tuple(t) if t.elems.is_empty() => Ok(Model::FireAndForget),
+            _ if first_generic(inner).is_some() => Ok(Model::RequestResponse),
+            _ => Err(invalid()),
+        },
+        "Flux" if first_generic(inner).is_some() => Ok(Model::RequestStream),
+        _ => Err(invalid()),
+    }
+}
+
+/// Returns the last path segment name and its first generic type argument.
+fn first_generic(ty: &Type) -> Option<(String, &Type)> {
+    let path = match ty {
+        Type::Path(p) => &p.path,
+        _ => return None,
+    };
+    let seg = path.segments.last()?;
+    let args = match &seg.arguments {
+        PathArguments::AngleBracketed(args) => args,
+        _ => return None,
+    };
+    args.args.iter().find_map(|it| match it {
+        GenericArgument::Type(t) => Some((seg.ident.to_string(), t)),
+        _ => None,
+    })
+}
@@ -0,0 +1,168 @@
+use bytes::{Bytes, BytesMut};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rsocket_rust::error::ERR_APPLICATION;
+use rsocket_rust::extension::{CompositeMetadata, RoutingMetadata};
+use rsocket_rust::frame::*;
+use rsocket_rust::mime;
+use rsocket_rust::utils::Writeable;
+use std::time::Duration;
+
+fn encoded<W: Writeable>(w: &W) -> Bytes {
+    let mut bf = BytesMut::with_capacity(w.len());
+    w.write_to(&mut bf);
+    bf.freeze()
+}
+
+fn frames() -> Vec<(&'static str, Frame)> {
+    let data = Bytes::from("Hello World!");
+    let metadata = Bytes::from("foobar");
+    vec![
+        (
+            "setup",
+            Setup::builder(0, 0)
+                .set_keepalive(Duration::from_secs(20))
+                .set_lifetime(Duration::from_secs(90))
+                .set_mime_metadata(mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0)
+                .set_mime_data(mime::APPLICATION_JSON)
+                .set_metadata(metadata.clone())
+                .set_data(data.clone())
+                .build(),
+        ),
+        (
+            "lease",
+            Lease::builder(0, 0)
+                .set_ttl(1000)
+                .set_number_of_requests(100)
+                .set_metadata(metadata.clone())
+                .build(),
+        ),
+        (
+            "keepalive",
+            Keepalive::builder(0, FLAG_RESPOND)
+                .set_last_received_position(123)
+                .set_data(data.clone())
+                .build(),
+        ),
+        (
+            "request_response",
+            RequestResponse::builder(1, 0)
+                .set_metadata(metadata.clone())
+                .set_data(data.clone())
+                .build(),
+        ),
+        (
+            "request_fnf",
+            RequestFNF::builder(1, 0)
+                .set_metadata(metadata.clone())
+                .set_data(data.clone())
+                .build(),
+        ),
+        (
+            "request_stream",
+            RequestStream::builder(1, 0)
+                .set_initial_request_n(128)
+                .set_metadata(metadata.clone())
+                .set_data(data.clone())
+                .build(),
+        ),
+        (
+            "request_channel",
+            RequestChannel::builder(1, 0)
+                .set_initial_request_n(128)
+                .set_metadata(metadata.clone())
+                .set_data(data.clone())
+                .build(),
+        ),
+        ("request_n", RequestN::builder(1, 0).set_n(128).build()),
+        ("cancel", Cancel::builder(1, 0).build()),
+        (
+            "payload",
+            Payload::builder(1, FLAG_NEXT | FLAG_COMPLETE)
+                .set_metadata(metadata.clone())
+                .set_data(data.clone())
+                .build(),
+        ),
+        (
+            "error",
+            Error::builder(1, 0)
+                .set_code(ERR_APPLICATION)
+                .set_data(data)
+                .build(),
+        ),
+        (
+            "metadata_push",
+            MetadataPush::builder(0, 0).set_metadata(metadata).build(),
+        ),
+        (
+            "resume",
+            Resume::builder(0, 0)
+                .set_token(Bytes::from("token"))
+                .set_last_received_server_position(123)
+                .set_first_available_client_position(456)
+                .build(),
+        ),
+        (
+            "resume_ok",
+            ResumeOK::builder(0, 0).set_position(123).build(),
+        ),
+    ]
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame_encode");
+    for (name, f) in frames() {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &f, |b, f| {
+            b.iter(|| {
+                let mut bf = BytesMut::with_capacity(f.len());
+                f.write_to(&mut bf);
+                bf
+            })
+        });
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame_decode");
+    for (name, f) in frames() {
+        let raw = encoded(&f);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &raw, |b, raw| {
+            b.iter(|| Frame::decode(&mut BytesMut::from(&raw[..])).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn composite_metadata(c: &mut Criterion) {
+    let routing = RoutingMetadata::builder()
+        .push_str("/orders")
+        .push_str("/orders/77778888")
+        .build();
+    let composite = CompositeMetadata::builder()
+        .push(mime::MESSAGE_X_RSOCKET_ROUTING_V0, encoded(&routing))
+        .push(mime::MESSAGE_X_RSOCKET_MIME_TYPE_V0, b"\x85")
+        .push("application/x.custom", b"Hello World!")
+        .build();
+    let raw = encoded(&composite);
+    let mut group = c.benchmark_group("composite_metadata");
+    group.bench_function("encode", |b| {
+        b.iter(|| {
+            let mut bf = BytesMut::with_capacity(composite.len());
+            composite.write_to(&mut bf);
+            bf
+        })
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| CompositeMetadata::decode(&mut BytesMut::from(&raw[..])).unwrap())
+    });
+    group.bench_function("decode_route", |b| {
+        b.iter(|| {
+            let composite = CompositeMetadata::decode(&mut BytesMut::from(&raw[..])).unwrap();
+            RoutingMetadata::from_composite(&composite).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode, composite_metadata);
+criterion_main!(benches);
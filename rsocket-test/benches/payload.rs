@@ -0,0 +1,60 @@
+use bytes::{Bytes, BytesMut};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rsocket_rust::frame::{Frame, Payload, FLAG_NEXT};
+use rsocket_rust::utils::Writeable;
+
+const SIZES: &[usize] = &[64 * 1024, 1024 * 1024];
+
+fn frame_of(size: usize) -> Frame {
+    Payload::builder(1, FLAG_NEXT)
+        .set_data(Bytes::from(vec![0x42u8; size]))
+        .set_metadata(Bytes::from("foobar"))
+        .build()
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for size in SIZES {
+        let f = frame_of(*size);
+        group.throughput(Throughput::Bytes(*size as u64));
+        // copies the payload behind the header.
+        group.bench_with_input(BenchmarkId::new("copy", size), &f, |b, f| {
+            b.iter(|| {
+                let mut bf = BytesMut::with_capacity(f.len());
+                f.write_to(&mut bf);
+                bf
+            })
+        });
+        // what the TCP transport hands to vectored writes.
+        group.bench_with_input(BenchmarkId::new("vectored", size), &f, |b, f| {
+            b.iter(|| {
+                let mut bf = BytesMut::with_capacity(16);
+                let chunks = f.write_head_to(&mut bf);
+                (bf, chunks)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for size in SIZES {
+        let f = frame_of(*size);
+        let mut encoded = BytesMut::with_capacity(f.len());
+        f.write_to(&mut encoded);
+        let encoded = encoded.freeze();
+        group.throughput(Throughput::Bytes(*size as u64));
+        // payloads alias the receive buffer, only the buffer is copied here.
+        group.bench_with_input(BenchmarkId::new("frame", size), &encoded, |b, raw| {
+            b.iter(|| Frame::decode(&mut BytesMut::from(raw.as_ref())).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("buffer_only", size), &encoded, |b, raw| {
+            b.iter(|| BytesMut::from(raw.as_ref()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
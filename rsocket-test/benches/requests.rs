@@ -0,0 +1,95 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use futures::future::join_all;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const ADDR: &str = "127.0.0.1:7815";
+const BATCH: usize = 100;
+
+/// Echoes request-response and answers every stream with `BATCH` payloads.
+struct BenchRSocket;
+
+impl RSocket for BenchRSocket {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(async move { Ok(req) })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(futures::stream::iter(
+            std::iter::repeat_n(req, BATCH).map(Ok),
+        ))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+fn loopback(c: &mut Criterion) {
+    let mut rt = Runtime::new().unwrap();
+    rt.spawn(async {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(ADDR))
+            .acceptor(|_setup, _socket| Ok(Box::new(BenchRSocket)))
+            .serve()
+            .await
+    });
+    let cli = rt.block_on(async {
+        tokio::time::delay_for(Duration::from_millis(300)).await;
+        RSocketFactory::connect()
+            .transport(TcpClientTransport::from(ADDR))
+            .start()
+            .await
+            .unwrap()
+    });
+
+    let mut group = c.benchmark_group("loopback");
+    group.throughput(Throughput::Elements(1));
+    // requests spawn onto the runtime, so they are created inside block_on.
+    group.bench_function("request_response", |b| {
+        b.iter(|| {
+            rt.block_on(async { cli.request_response(Payload::from("Hello World!")).await })
+                .unwrap()
+        })
+    });
+    group.throughput(Throughput::Elements(BATCH as u64));
+    group.bench_function("request_response_concurrent", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                join_all((0..BATCH).map(|_| cli.request_response(Payload::from("Hello World!"))))
+                    .await
+            })
+        })
+    });
+    group.bench_function("request_stream", |b| {
+        b.iter(|| {
+            rt.block_on(async {
+                let mut results = cli.request_stream(Payload::from("Hello World!"));
+                let mut n = 0;
+                while let Some(it) = results.next().await {
+                    it.unwrap();
+                    n += 1;
+                }
+                assert_eq!(BATCH, n);
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, loopback);
+criterion_main!(benches);
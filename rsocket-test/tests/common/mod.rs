@@ -0,0 +1,59 @@
+//! Fixtures shared by the tests which talk to a client or a server frame by frame.
+// every test uses some of the fixtures only.
+#![allow(dead_code)]
+
+use futures::channel::mpsc;
+use rsocket_rust::frame::{Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::{loopback, LoopbackServerTransport, LoopbackTransport};
+use std::error::Error;
+use std::future::Future;
+
+/// Connect the client `connect` starts over a loopback transport to a raw peer, which already
+/// received its SETUP. Returns the client, the frames the peer receives and the sender of the
+/// frames it sends.
+pub async fn connect_raw<F, Fut>(
+    connect: F,
+) -> (
+    Client<DefaultSpawner>,
+    mpsc::Receiver<Frame>,
+    tokio::sync::mpsc::Sender<Frame>,
+)
+where
+    F: FnOnce(LoopbackTransport) -> Fut,
+    Fut: Future<Output = Result<Client<DefaultSpawner>, Box<dyn Error + Send + Sync>>>,
+{
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let cli = connect(client).await.unwrap();
+    assert!(matches!(
+        incoming.next().await.unwrap().get_body(),
+        Body::Setup(_)
+    ));
+    (cli, incoming, sending)
+}
+
+/// Connect a raw client to the server `serve` runs over a loopback transport, `setup` is sent.
+/// Returns the frames the client receives and the sender of the frames it sends.
+pub async fn serve_raw<F, Fut>(
+    serve: F,
+    setup: Frame,
+) -> (mpsc::Receiver<Frame>, tokio::sync::mpsc::Sender<Frame>)
+where
+    F: FnOnce(LoopbackServerTransport) -> Fut,
+    Fut: Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send + 'static,
+{
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(serve(server));
+    let (incoming_tx, incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    sending.send(setup).await.unwrap();
+    (incoming, sending)
+}
@@ -0,0 +1,173 @@
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::{future, stream};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::frame::{self, Body};
+use rsocket_rust::interceptor::{AccessLog, AccessRecord, Outcome};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{pair, LoopbackServerTransport};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+
+/// Streams three copies of the request, or fails if asked to.
+struct Tripler;
+
+impl RSocket for Tripler {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        match req.data_utf8() {
+            Some("fail") => Box::pin(future::err(RSocketError::from("failed"))),
+            _ => Box::pin(future::ok(req)),
+        }
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let data = req.data_utf8().unwrap_or_default().to_string();
+        if data == "forever" {
+            return Box::pin(
+                stream::iter(vec![Ok(Payload::from("tick"))]).chain(stream::pending()),
+            );
+        }
+        let items = (0..3).map(move |_| Ok(Payload::builder().set_data_utf8(&data).build()));
+        Box::pin(stream::iter(items))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+type Records = Arc<Mutex<Vec<AccessRecord>>>;
+
+async fn start() -> (Client<rsocket_rust::runtime::DefaultSpawner>, Records) {
+    let records: Records = Default::default();
+    let recorded = records.clone();
+    let logged = AccessLog::new(Tripler).sink(move |it: &AccessRecord| {
+        recorded.lock().unwrap().push(it.clone());
+    });
+    (pair(logged).await, records)
+}
+
+async fn last_record(records: &Records, n: usize) -> AccessRecord {
+    for _ in 0..100 {
+        if let Some(it) = records.lock().unwrap().get(n) {
+            return it.clone();
+        }
+        time::delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("no record {}", n);
+}
+
+fn routed(route: &str, data: &str) -> Payload {
+    Payload::builder()
+        .set_data_utf8(data)
+        .metadata()
+        .route(route)
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn record_every_request() {
+    let (cli, records) = start().await;
+    let req = routed("greet", "hello");
+    let size = req.len();
+    cli.request_response(req).await.unwrap();
+    let record = last_record(&records, 0).await;
+    assert_eq!("request_response", record.get_interaction());
+    assert_eq!(Some("greet"), record.get_route());
+    assert_eq!(size, record.get_request_size());
+    assert_eq!(1, record.get_responses());
+    assert_eq!(&Outcome::Completed, record.get_outcome());
+    assert!(record.to_string().contains("route=greet"), "{}", record);
+
+    cli.request_response(routed("greet", "fail"))
+        .await
+        .unwrap_err();
+    let record = last_record(&records, 1).await;
+    assert!(matches!(record.get_outcome(), Outcome::Failed(_)));
+
+    let results: Vec<_> = cli.request_stream(Payload::from("abc")).collect().await;
+    assert_eq!(3, results.len());
+    let record = last_record(&records, 2).await;
+    assert_eq!("request_stream", record.get_interaction());
+    assert_eq!(None, record.get_route());
+    assert_eq!(3, record.get_request_size());
+    assert_eq!(3, record.get_responses());
+    assert_eq!(9, record.get_response_size());
+    assert_eq!(&Outcome::Completed, record.get_outcome());
+
+    let reqs = stream::iter(vec![Ok(routed("chat", "a")), Ok(Payload::from("bc"))]);
+    let results: Vec<_> = cli.request_channel(Box::pin(reqs)).collect().await;
+    assert_eq!(2, results.len());
+    let record = last_record(&records, 3).await;
+    assert_eq!("request_channel", record.get_interaction());
+    assert_eq!(Some("chat"), record.get_route());
+    assert_eq!(2, record.get_responses());
+}
+
+/// Records of the server of `record_cancelled_streams`, its acceptor captures nothing.
+static CANCELLED: Mutex<Vec<AccessRecord>> = Mutex::new(Vec::new());
+
+#[tokio::main]
+#[test]
+async fn record_cancelled_streams() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| {
+                let logged = AccessLog::new(Tripler).sink(|it: &AccessRecord| {
+                    CANCELLED.lock().unwrap().push(it.clone());
+                });
+                Ok(Box::new(logged))
+            })
+            .serve(),
+    );
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    let frames = vec![
+        frame::Setup::builder(0, 0).build(),
+        frame::RequestStream::builder(1, 0)
+            .set_initial_request_n(8)
+            .set_data(Bytes::from("forever"))
+            .build(),
+    ];
+    for it in frames {
+        sending.send(it).await.unwrap();
+    }
+    let first = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(first.get_body_ref(), Body::Payload(_)));
+    sending
+        .send(frame::Cancel::builder(1, 0).build())
+        .await
+        .unwrap();
+    for _ in 0..100 {
+        if let Some(record) = CANCELLED.lock().unwrap().first() {
+            assert_eq!("request_stream", record.get_interaction());
+            assert_eq!(1, record.get_responses());
+            assert_eq!(&Outcome::Cancelled, record.get_outcome());
+            return;
+        }
+        time::delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("cancelled stream is not recorded");
+}
@@ -0,0 +1,84 @@
+extern crate rsocket_rust;
+
+use bytes::BytesMut;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::extension::{AuthMetadata, CompositeMetadata};
+use rsocket_rust::interceptor::{AuthToken, BearerAuthInjector};
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn bearer_of(res: &Payload) -> AuthMetadata {
+    let mut bf = BytesMut::from(res.metadata().as_ref().unwrap().as_ref());
+    let composite = CompositeMetadata::decode(&mut bf).unwrap();
+    let auth = composite
+        .find(mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0)
+        .unwrap();
+    AuthMetadata::decode(&mut BytesMut::from(auth.get_payload().as_ref())).unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn bearer_auth_refresh() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls2 = calls.clone();
+    let requester = BearerAuthInjector::new(EchoRSocket, move || {
+        let n = calls2.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            Ok(AuthToken::new(format!("token_{}", n)).expires_in(Duration::from_secs(60)))
+        })
+    })
+    .refresh_before(Duration::from_secs(10));
+
+    for _ in 0..3 {
+        let res = requester
+            .request_response(Payload::from("Hello World!"))
+            .await
+            .unwrap();
+        assert_eq!(AuthMetadata::bearer("token_0"), bearer_of(&res));
+    }
+    assert_eq!(1, calls.load(Ordering::SeqCst));
+
+    let mut results = requester.request_stream(Payload::from("Hello World!"));
+    while let Some(res) = results.next().await {
+        assert_eq!(AuthMetadata::bearer("token_0"), bearer_of(&res.unwrap()));
+    }
+
+    requester.invalidate();
+    let res = requester
+        .request_response(Payload::from("Hello World!"))
+        .await
+        .unwrap();
+    assert_eq!(AuthMetadata::bearer("token_1"), bearer_of(&res));
+
+    // the token is refreshed when it is about to expire.
+    let calls2 = calls.clone();
+    let requester = BearerAuthInjector::new(EchoRSocket, move || {
+        calls2.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async { Ok(AuthToken::new("short").expires_in(Duration::from_secs(5))) })
+    });
+    for _ in 0..2 {
+        requester
+            .request_response(Payload::from("Hello World!"))
+            .await
+            .unwrap();
+    }
+    assert_eq!(4, calls.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn bearer_auth_provider_error() {
+    let requester = BearerAuthInjector::new(EchoRSocket, || {
+        Box::pin(async { Err(RSocketError::from("token endpoint unavailable")) })
+    });
+    let res = requester
+        .request_response(Payload::from("Hello World!"))
+        .await;
+    assert!(res.is_err());
+    let mut results = requester.request_stream(Payload::from("Hello World!"));
+    assert!(results.next().await.unwrap().is_err());
+    assert!(results.next().await.is_none());
+}
@@ -0,0 +1,140 @@
+use bytes::Bytes;
+use futures::channel::mpsc;
+use rsocket_rust::error::{self, RSocketError};
+use rsocket_rust::extension::AuthMetadata;
+use rsocket_rust::frame::{self, Body};
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{LoopbackConnector, LoopbackServerTransport};
+use rsocket_rust::transport::Principal;
+use std::time::Duration;
+use tokio::time;
+
+/// Responds with the name and roles of the principal of the connection.
+struct WhoAmI;
+
+impl RSocket for WhoAmI {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        EchoRSocket.request_response(req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        _req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        let who = match ctx.get_principal() {
+            Some(it) => format!("{}:{}", it.get_name(), it.get_roles().join(",")),
+            None => "anonymous".to_string(),
+        };
+        Box::pin(async move { Ok(Payload::from(who)) })
+    }
+}
+
+fn start_server() -> LoopbackConnector {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .authenticator(|auth: Option<AuthMetadata>| async move {
+                match auth {
+                    Some(AuthMetadata::Simple { username, password })
+                        if password.as_ref() == b"secret" =>
+                    {
+                        Ok(Principal::new(username).role("admin"))
+                    }
+                    Some(AuthMetadata::Bearer(token)) if token == "token" => {
+                        Ok(Principal::new("service"))
+                    }
+                    _ => Err(RSocketError::from("bad credentials")),
+                }
+            })
+            .acceptor(|_setup, _socket| Ok(Box::new(WhoAmI)))
+            .serve(),
+    );
+    connector
+}
+
+#[tokio::main]
+#[test]
+async fn attach_principal_of_setup() {
+    let connector = start_server();
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .metadata_mime_type(mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0)
+        .setup(
+            Payload::builder()
+                .metadata()
+                .simple_auth("alice", "secret")
+                .build(),
+        )
+        .start()
+        .await
+        .unwrap();
+    let res = cli.request_response(Payload::from("ping")).await.unwrap();
+    assert_eq!(Some("alice:admin"), res.data_utf8());
+
+    // the whole metadata may be the authentication.
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .metadata_mime_type(mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0)
+        .setup(Payload::from((
+            None,
+            Some(Bytes::from(AuthMetadata::bearer("token"))),
+        )))
+        .start()
+        .await
+        .unwrap();
+    let res = cli.request_response(Payload::from("ping")).await.unwrap();
+    assert_eq!(Some("service:"), res.data_utf8());
+}
+
+#[tokio::main]
+#[test]
+async fn reject_setup_failing_authentication() {
+    let connector = start_server();
+    for metadata in [None, Some(AuthMetadata::bearer("guess"))].iter() {
+        let (incoming_tx, mut incoming) = mpsc::channel(1024);
+        let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+        connector
+            .connect()
+            .unwrap()
+            .attach(incoming_tx, sending_rx, None);
+        let mut setup = frame::Setup::builder(0, 0)
+            .set_mime_metadata(mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0);
+        if let Some(auth) = metadata {
+            setup = setup.set_metadata(Bytes::from(auth.clone()));
+        }
+        sending.send(setup.build()).await.unwrap();
+        let frame = time::timeout(Duration::from_secs(1), incoming.next())
+            .await
+            .unwrap()
+            .expect("should be rejected");
+        match frame.get_body() {
+            Body::Error(e) => {
+                assert_eq!(error::ERR_REJECT_SETUP, e.get_code());
+                assert_eq!("bad credentials", e.get_data_utf8());
+            }
+            other => panic!("expect ERROR frame, got: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::frame::Frame;
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use rsocket_rust::transport::{RxBounded, TxOnce};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// A transport whose writer only runs after being released.
+struct StalledTransport {
+    writer: Arc<Mutex<Option<RxBounded<Frame>>>>,
+    reader: Arc<Mutex<Option<Tx<Frame>>>>,
+}
+
+impl ClientTransport for StalledTransport {
+    fn attach(
+        self,
+        incoming: Tx<Frame>,
+        sending: RxBounded<Frame>,
+        connected: Option<TxOnce<Result<(), RSocketError>>>,
+    ) {
+        // the peer stays connected, it only reads nothing.
+        self.reader.lock().unwrap().replace(incoming);
+        self.writer.lock().unwrap().replace(sending);
+        if let Some(sender) = connected {
+            sender.send(Ok(())).unwrap();
+        }
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn slow_writer_slows_producers() {
+    let writer = Arc::new(Mutex::new(None));
+    let reader = Arc::new(Mutex::new(None));
+    let cli = RSocketFactory::connect()
+        .transport(StalledTransport {
+            writer: writer.clone(),
+            reader: reader.clone(),
+        })
+        .outbound_capacity(4)
+        .start()
+        .await
+        .unwrap();
+
+    let sent = Arc::new(AtomicUsize::new(0));
+    let counter = sent.clone();
+    let (progress_tx, mut progress) = watch::channel(0);
+    let producer = tokio::spawn(async move {
+        for _ in 0..100 {
+            cli.fire_and_forget(Payload::from("Hello World!")).await;
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            let _ = progress_tx.broadcast(n);
+        }
+    });
+    // wait for the producer to fill the socket queue.
+    while let Some(n) = progress.recv().await {
+        if n >= 4 {
+            break;
+        }
+    }
+    // the producer stays within the transport queue, the pump and the socket queue of
+    // the frames written so far.
+    let mut sending = writer.lock().unwrap().take().unwrap();
+    let mut written = 0;
+    while written < 101 {
+        let ahead = sent.load(Ordering::SeqCst);
+        assert!(
+            ahead < written + 16,
+            "sent {} frames to a writer which wrote {}",
+            ahead,
+            written
+        );
+        sending.next().await.unwrap();
+        written += 1;
+    }
+    producer.await.unwrap();
+    assert_eq!(100, sent.load(Ordering::SeqCst));
+}
+
+/// Responds streams with 50 items, then fails them.
+struct FailingRSocket;
+
+impl RSocket for FailingRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        EchoRSocket.request_response(req)
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let items = (0..50).map(|n| Ok(Payload::from(format!("{}", n))));
+        Box::pin(stream::iter(
+            items.chain(Some(Err(RSocketError::from("boom")))),
+        ))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn full_stream_keeps_payloads_before_error() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(FailingRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .stream_capacity(4)
+        .start()
+        .await
+        .unwrap();
+    let results = cli
+        .request_stream(Payload::from("ping"))
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(51, results.len());
+    for (n, it) in results.iter().take(50).enumerate() {
+        let it = it.as_ref().unwrap();
+        assert_eq!(Some(format!("{}", n).as_str()), it.data_utf8());
+    }
+    assert!(results[50].is_err());
+}
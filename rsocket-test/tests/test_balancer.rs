@@ -0,0 +1,523 @@
+use futures::channel::mpsc;
+use futures::future::{self, BoxFuture};
+use futures::stream;
+use rsocket_rust::balancer::{LoadBalancer, Resolver};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::frame::Frame;
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::{loopback, pair};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+
+/// Responds with the address it serves.
+struct Named(SocketAddr);
+
+impl RSocket for Named {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let res = Payload::builder()
+            .set_data_utf8(&self.0.to_string())
+            .build();
+        Box::pin(future::ok(res))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let res = Payload::builder()
+            .set_data_utf8(&self.0.to_string())
+            .build();
+        Box::pin(stream::iter(vec![Ok(res)]).chain(stream::pending()))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+/// Streams two numbers from the cursor of the request, together with the address it serves.
+struct Counting(SocketAddr);
+
+impl RSocket for Counting {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(future::err(RSocketError::from("unsupported")))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let cursor: u32 = req.data_utf8().unwrap().parse().unwrap();
+        let at = self.0;
+        let items = (cursor..cursor + 2).map(move |n| {
+            Ok(Payload::builder()
+                .set_data_utf8(&format!("{} {}", n, at))
+                .build())
+        });
+        Box::pin(stream::iter(items).chain(stream::pending()))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+/// Fails every request, as a server which is not ready yet.
+struct Cold;
+
+impl RSocket for Cold {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(future::err(RSocketError::from("warming up")))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+/// DNS records which the tests change.
+#[derive(Clone, Default)]
+struct Records {
+    hosts: Arc<Mutex<HashMap<String, Vec<SocketAddr>>>>,
+}
+
+impl Records {
+    fn set(&self, host: &str, addrs: &[SocketAddr]) {
+        self.hosts
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), addrs.to_vec());
+    }
+}
+
+impl Resolver for Records {
+    fn resolve(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> BoxFuture<'static, Result<Vec<SocketAddr>, RSocketError>> {
+        let found = match self.hosts.lock().unwrap().get(host) {
+            Some(addrs) => Ok(addrs
+                .iter()
+                .map(|it| SocketAddr::new(it.ip(), port))
+                .collect()),
+            None => Err(RSocketError::from(format!("unknown host: {}", host))),
+        };
+        Box::pin(future::ready(found))
+    }
+}
+
+type Connections = Arc<Mutex<HashMap<SocketAddr, Client<DefaultSpawner>>>>;
+
+fn addr(ip: &str) -> SocketAddr {
+    format!("{}:7878", ip).parse().unwrap()
+}
+
+async fn start(records: &Records, connections: &Connections, interval: Duration) -> LoadBalancer {
+    let connections = connections.clone();
+    LoadBalancer::builder()
+        .endpoint("tcp://svc.local:7878")
+        .resolver(records.clone())
+        .refresh_interval(interval)
+        .connect(move |addr| {
+            let connections = connections.clone();
+            async move {
+                let client = pair(Named(addr)).await;
+                connections.lock().unwrap().insert(addr, client.clone());
+                Ok(client)
+            }
+        })
+        .start()
+        .await
+        .unwrap()
+}
+
+async fn served_by(balancer: &LoadBalancer) -> Result<String, RSocketError> {
+    let res = balancer.request_response(Payload::from("who")).await?;
+    Ok(res.data_utf8().unwrap().to_string())
+}
+
+async fn next_data(results: &mut Flux<Result<Payload, RSocketError>>) -> String {
+    let res = time::timeout(Duration::from_secs(3), results.next()).await;
+    res.unwrap()
+        .unwrap()
+        .unwrap()
+        .data_utf8()
+        .unwrap()
+        .to_string()
+}
+
+fn is_closed(connections: &Connections, at: SocketAddr) -> bool {
+    connections.lock().unwrap().get(&at).unwrap().is_closed()
+}
+
+#[tokio::main]
+#[test]
+async fn follow_dns_records() {
+    let records = Records::default();
+    records.set("svc.local", &[addr("10.0.0.1"), addr("10.0.0.2")]);
+    let connections: Connections = Default::default();
+    let balancer = start(&records, &connections, Duration::from_millis(100)).await;
+    assert_eq!(
+        vec![addr("10.0.0.1"), addr("10.0.0.2")],
+        balancer.get_members()
+    );
+    let mut served = vec![];
+    for _ in 0..4 {
+        served.push(served_by(&balancer).await.unwrap());
+    }
+    assert_eq!(
+        vec![
+            "10.0.0.1:7878",
+            "10.0.0.2:7878",
+            "10.0.0.1:7878",
+            "10.0.0.2:7878"
+        ],
+        served
+    );
+
+    records.set("svc.local", &[addr("10.0.0.2"), addr("10.0.0.3")]);
+    time::delay_for(Duration::from_millis(300)).await;
+    assert_eq!(
+        vec![addr("10.0.0.2"), addr("10.0.0.3")],
+        balancer.get_members()
+    );
+    assert!(is_closed(&connections, addr("10.0.0.1")));
+    assert!(!is_closed(&connections, addr("10.0.0.2")));
+}
+
+#[tokio::main]
+#[test]
+async fn drain_removed_connections() {
+    let records = Records::default();
+    records.set("svc.local", &[addr("10.0.0.1")]);
+    let connections: Connections = Default::default();
+    let balancer = start(&records, &connections, Duration::from_secs(0)).await;
+    let mut results = balancer.request_stream(Payload::from("watch"));
+    assert_eq!(
+        Some("10.0.0.1:7878"),
+        results.next().await.unwrap().unwrap().data_utf8()
+    );
+
+    records.set("svc.local", &[addr("10.0.0.2")]);
+    balancer.refresh().await;
+    assert_eq!(vec![addr("10.0.0.2")], balancer.get_members());
+    assert_eq!("10.0.0.2:7878", served_by(&balancer).await.unwrap());
+    // the stream still runs on the removed connection.
+    time::delay_for(Duration::from_millis(50)).await;
+    assert!(!is_closed(&connections, addr("10.0.0.1")));
+
+    drop(results);
+    time::delay_for(Duration::from_millis(50)).await;
+    assert!(is_closed(&connections, addr("10.0.0.1")));
+}
+
+#[tokio::main]
+#[test]
+async fn resolve_again_when_connection_fails() {
+    let records = Records::default();
+    records.set("svc.local", &[addr("10.0.0.1")]);
+    let connections: Connections = Default::default();
+    let balancer = start(&records, &connections, Duration::from_secs(3600)).await;
+    assert_eq!("10.0.0.1:7878", served_by(&balancer).await.unwrap());
+
+    records.set("svc.local", &[addr("10.0.0.2")]);
+    let failed = connections
+        .lock()
+        .unwrap()
+        .remove(&addr("10.0.0.1"))
+        .unwrap();
+    failed.close();
+    let e = served_by(&balancer).await.unwrap_err();
+    assert!(e.to_string().contains("no available connection"), "{}", e);
+    time::delay_for(Duration::from_millis(50)).await;
+    assert_eq!(vec![addr("10.0.0.2")], balancer.get_members());
+    assert_eq!("10.0.0.2:7878", served_by(&balancer).await.unwrap());
+}
+
+#[tokio::main]
+#[test]
+async fn reject_invalid_endpoints() {
+    let built = LoadBalancer::builder()
+        .endpoint("svc.local")
+        .resolver(Records::default())
+        .connect(|addr| async move { Ok(pair(Named(addr)).await) })
+        .start()
+        .await;
+    assert!(built.is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn change_endpoints_while_running() {
+    let records = Records::default();
+    records.set("a.local", &[addr("10.0.0.1")]);
+    records.set("b.local", &[addr("10.0.0.2")]);
+    let connections: Connections = Default::default();
+    let connecting = connections.clone();
+    let balancer = LoadBalancer::builder()
+        .resolver(records.clone())
+        .refresh_interval(Duration::from_secs(0))
+        .connect(move |addr| {
+            let connections = connecting.clone();
+            async move {
+                let client = pair(Named(addr)).await;
+                connections.lock().unwrap().insert(addr, client.clone());
+                Ok(client)
+            }
+        })
+        .start()
+        .await
+        .unwrap();
+    assert!(balancer.get_members().is_empty());
+
+    balancer.add_endpoint("a.local:7878").await.unwrap();
+    let mut results = balancer.request_stream(Payload::from("watch"));
+    assert_eq!(
+        Some("10.0.0.1:7878"),
+        results.next().await.unwrap().unwrap().data_utf8()
+    );
+    balancer.add_endpoint("tcp://b.local:7878").await.unwrap();
+    assert_eq!(
+        vec![addr("10.0.0.1"), addr("10.0.0.2")],
+        balancer.get_members()
+    );
+
+    assert!(balancer.remove_endpoint("a.local:7878").await.unwrap());
+    assert!(!balancer.remove_endpoint("a.local:7878").await.unwrap());
+    assert!(balancer.add_endpoint("b.local").await.is_err());
+    assert_eq!(vec!["b.local:7878"], balancer.get_endpoints());
+    assert_eq!(vec![addr("10.0.0.2")], balancer.get_members());
+    for _ in 0..2 {
+        assert_eq!("10.0.0.2:7878", served_by(&balancer).await.unwrap());
+    }
+    // the removed endpoint is drained.
+    assert!(!is_closed(&connections, addr("10.0.0.1")));
+    drop(results);
+    time::delay_for(Duration::from_millis(50)).await;
+    assert!(is_closed(&connections, addr("10.0.0.1")));
+}
+
+#[tokio::main]
+#[test]
+async fn warm_up_new_connections() {
+    let records = Records::default();
+    records.set(
+        "svc.local",
+        &[addr("10.0.0.1"), addr("10.0.0.2"), addr("10.0.0.3")],
+    );
+    // ends of the connections to a server which never answers.
+    let silent = Arc::new(Mutex::new(vec![]));
+    let balancer = LoadBalancer::builder()
+        .endpoint("svc.local:7878")
+        .resolver(records.clone())
+        .refresh_interval(Duration::from_secs(0))
+        .warm_up_keepalive()
+        .warm_up_probe(Payload::from("ready?"))
+        .warm_up_timeout(Duration::from_millis(200))
+        .connect(move |at| {
+            let silent = silent.clone();
+            async move {
+                if at == addr("10.0.0.2") {
+                    return Ok(pair(Cold).await);
+                }
+                if at == addr("10.0.0.3") {
+                    let (client, server) = loopback();
+                    let (incoming_tx, incoming_rx) = mpsc::channel::<Frame>(1024);
+                    let (sending_tx, sending_rx) = tokio::sync::mpsc::channel(16);
+                    server.attach(incoming_tx, sending_rx, None);
+                    silent.lock().unwrap().push((incoming_rx, sending_tx));
+                    return RSocketFactory::connect().transport(client).start().await;
+                }
+                Ok(pair(Named(at)).await)
+            }
+        })
+        .start()
+        .await
+        .unwrap();
+    assert_eq!(vec![addr("10.0.0.1")], balancer.get_members());
+    assert_eq!("10.0.0.1:7878", served_by(&balancer).await.unwrap());
+}
+
+#[tokio::main]
+#[test]
+async fn deprioritize_unhealthy_connections() {
+    let records = Records::default();
+    records.set("svc.local", &[addr("10.0.0.1"), addr("10.0.0.2")]);
+    let balancer = LoadBalancer::builder()
+        .endpoint("svc.local:7878")
+        .resolver(records.clone())
+        .refresh_interval(Duration::from_secs(0))
+        .connect(|at| async move {
+            if at == addr("10.0.0.2") {
+                return Ok(pair(Cold).await);
+            }
+            Ok(pair(Named(at)).await)
+        })
+        .start()
+        .await
+        .unwrap();
+    assert_eq!("10.0.0.1:7878", served_by(&balancer).await.unwrap());
+    assert!(served_by(&balancer).await.is_err());
+    // the failing connection stays a member, without receiving requests.
+    for _ in 0..4 {
+        assert_eq!("10.0.0.1:7878", served_by(&balancer).await.unwrap());
+    }
+    let health = balancer.get_health();
+    assert_eq!(
+        vec![addr("10.0.0.1"), addr("10.0.0.2")],
+        balancer.get_members()
+    );
+    assert_eq!(1.0, health[0].1.get_score());
+    assert_eq!(1.0, health[1].1.get_error_rate());
+}
+
+#[tokio::main]
+#[test]
+async fn ping_server() {
+    let client = pair(Named(addr("10.0.0.1"))).await;
+    let rtt = client.ping().await.unwrap();
+    assert!(rtt < Duration::from_secs(3));
+    client.clone().close();
+    assert!(client.ping().await.is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn route_by_affinity_key() {
+    const SESSION: &str = "application/x.session";
+    let records = Records::default();
+    records.set(
+        "svc.local",
+        &[addr("10.0.0.1"), addr("10.0.0.2"), addr("10.0.0.3")],
+    );
+    let balancer = LoadBalancer::builder()
+        .endpoint("svc.local:7878")
+        .resolver(records.clone())
+        .refresh_interval(Duration::from_secs(0))
+        .affinity(SESSION)
+        .connect(|at| async move { Ok(pair(Named(at)).await) })
+        .start()
+        .await
+        .unwrap();
+    let request = |session: String| {
+        let balancer = balancer.clone();
+        async move {
+            let req = Payload::builder()
+                .set_data_utf8("who")
+                .metadata()
+                .custom(SESSION, &session)
+                .build();
+            let res = balancer.request_response(req).await.unwrap();
+            res.data_utf8().unwrap().to_string()
+        }
+    };
+
+    let mut owners = HashMap::new();
+    for i in 0..20 {
+        let session = format!("session_{}", i);
+        let owner = request(session.clone()).await;
+        for _ in 0..3 {
+            assert_eq!(owner, request(session.clone()).await);
+        }
+        owners.insert(session, owner);
+    }
+    let used: std::collections::HashSet<&String> = owners.values().collect();
+    assert!(used.len() > 1);
+
+    // only the keys of a removed member move.
+    records.set("svc.local", &[addr("10.0.0.1"), addr("10.0.0.2")]);
+    balancer.refresh().await;
+    for (session, owner) in owners.iter() {
+        let now = request(session.clone()).await;
+        if owner != "10.0.0.3:7878" {
+            assert_eq!(owner, &now);
+        } else {
+            assert_ne!(owner, &now);
+        }
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn resubscribe_lost_streams() {
+    let records = Records::default();
+    records.set("svc.local", &[addr("10.0.0.1")]);
+    let connections: Connections = Default::default();
+    let registry = connections.clone();
+    let balancer = LoadBalancer::builder()
+        .endpoint("svc.local:7878")
+        .resolver(records.clone())
+        .refresh_interval(Duration::from_secs(0))
+        .resubscribe(|first, last| match last {
+            Some(it) => {
+                let seen: u32 = it
+                    .data_utf8()
+                    .unwrap()
+                    .split(' ')
+                    .next()
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                Payload::builder()
+                    .set_data_utf8(&(seen + 1).to_string())
+                    .build()
+            }
+            None => first.clone(),
+        })
+        .connect(move |at| {
+            let registry = registry.clone();
+            async move {
+                let client = pair(Counting(at)).await;
+                registry.lock().unwrap().insert(at, client.clone());
+                Ok(client)
+            }
+        })
+        .start()
+        .await
+        .unwrap();
+    let mut results = balancer.request_stream(Payload::from("0"));
+    assert_eq!("0 10.0.0.1:7878", next_data(&mut results).await);
+    assert_eq!("1 10.0.0.1:7878", next_data(&mut results).await);
+
+    records.set("svc.local", &[addr("10.0.0.2")]);
+    let lost = connections.lock().unwrap()[&addr("10.0.0.1")].clone();
+    lost.close();
+    assert_eq!("2 10.0.0.2:7878", next_data(&mut results).await);
+    assert_eq!("3 10.0.0.2:7878", next_data(&mut results).await);
+}
@@ -0,0 +1,185 @@
+use futures::channel::mpsc;
+use futures::stream;
+use rsocket_rust::broker::Broker;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::frame;
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::{LoopbackConnector, LoopbackServerTransport};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::time;
+
+static FIRED: AtomicUsize = AtomicUsize::new(0);
+
+struct Named(&'static str);
+
+impl RSocket for Named {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        FIRED.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let msg = format!("{}: {}", self.0, req.data_utf8().unwrap_or_default());
+        Box::pin(async move { Ok(Payload::from(msg)) })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(vec![
+            Ok(Payload::from(self.0)),
+            Ok(Payload::from(self.0)),
+        ]))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+fn routed(route: &str, data: &str) -> Payload {
+    Payload::builder()
+        .set_data_utf8(data)
+        .metadata()
+        .route(route)
+        .build()
+}
+
+fn start_broker() -> (Broker, LoopbackConnector) {
+    let (server, connector) = LoopbackServerTransport::new();
+    let broker = Broker::new();
+    tokio::spawn({
+        let broker = broker.clone();
+        async move { broker.serve(server).await }
+    });
+    (broker, connector)
+}
+
+async fn connect(
+    connector: &LoopbackConnector,
+    setup: Payload,
+    acceptor: fn() -> Box<dyn RSocket>,
+) -> Client<DefaultSpawner> {
+    RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .setup(setup)
+        .acceptor(acceptor)
+        .start()
+        .await
+        .unwrap()
+}
+
+async fn wait_until<F: Fn() -> bool>(cond: F) {
+    for _ in 0..100 {
+        if cond() {
+            return;
+        }
+        time::delay_for(Duration::from_millis(20)).await;
+    }
+    panic!("condition not reached");
+}
+
+#[tokio::main]
+#[test]
+async fn forward_requests_by_route() {
+    let (broker, connector) = start_broker();
+    let setup = Payload::builder()
+        .metadata()
+        .route("greetings")
+        .route("numbers")
+        .build();
+    let _service = connect(&connector, setup, || Box::new(Named("svc"))).await;
+    wait_until(|| broker.get_routes().len() == 2).await;
+    assert_eq!(vec!["greetings", "numbers"], broker.get_routes());
+
+    let cli = connect(&connector, Payload::from("hi"), || Box::new(EchoRSocket)).await;
+    let res = cli
+        .request_response(routed("greetings", "Jeffsky"))
+        .await
+        .unwrap();
+    assert_eq!(Some("svc: Jeffsky"), res.data_utf8());
+
+    let results: Vec<_> = cli.request_stream(routed("numbers", "")).collect().await;
+    assert_eq!(2, results.len());
+    assert!(results
+        .iter()
+        .all(|it| it.as_ref().unwrap().data_utf8() == Some("svc")));
+
+    let reqs = stream::iter(vec![
+        Ok(routed("greetings", "a")),
+        Ok(Payload::from("b")),
+        Ok(Payload::from("c")),
+    ]);
+    let results: Vec<_> = cli.request_channel(Box::pin(reqs)).collect().await;
+    let results: Vec<_> = results
+        .iter()
+        .map(|it| it.as_ref().unwrap().data_utf8().unwrap().to_string())
+        .collect();
+    assert_eq!(vec!["a", "b", "c"], results);
+
+    let fired = FIRED.load(Ordering::SeqCst);
+    cli.fire_and_forget(routed("greetings", "x")).await;
+    wait_until(|| FIRED.load(Ordering::SeqCst) > fired).await;
+}
+
+#[tokio::main]
+#[test]
+async fn reject_unknown_routes() {
+    let (_broker, connector) = start_broker();
+    let cli = connect(&connector, Payload::from("hi"), || Box::new(EchoRSocket)).await;
+    let res = cli.request_response(routed("nowhere", "x")).await;
+    assert!(res.is_err());
+    let results: Vec<_> = cli.request_stream(Payload::from("x")).collect().await;
+    assert_eq!(1, results.len());
+    assert!(results[0].is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn balance_between_connections() {
+    let (_broker, connector) = start_broker();
+    let setup = || Payload::builder().metadata().route("greetings").build();
+    let _a = connect(&connector, setup(), || Box::new(Named("a"))).await;
+    let _b = connect(&connector, setup(), || Box::new(Named("b"))).await;
+    time::delay_for(Duration::from_millis(100)).await;
+
+    let cli = connect(&connector, Payload::from("hi"), || Box::new(EchoRSocket)).await;
+    let mut seen = vec![];
+    for _ in 0..4 {
+        let res = cli
+            .request_response(routed("greetings", "x"))
+            .await
+            .unwrap();
+        seen.push(res.data_utf8().unwrap().to_string());
+    }
+    assert!(seen.contains(&"a: x".to_string()));
+    assert!(seen.contains(&"b: x".to_string()));
+}
+
+#[tokio::main]
+#[test]
+async fn unregister_closed_connections() {
+    let (broker, connector) = start_broker();
+    let (incoming_tx, _incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    let metadata = Payload::builder().metadata().route("greetings").build();
+    let setup = frame::Setup::builder(0, 0)
+        .set_metadata(metadata.metadata().clone().unwrap())
+        .build();
+    sending.send(setup).await.unwrap();
+    wait_until(|| broker.get_routes() == vec!["greetings"]).await;
+
+    drop(sending);
+    wait_until(|| broker.get_routes().is_empty()).await;
+}
@@ -0,0 +1,97 @@
+use bytes::Bytes;
+use futures::channel::mpsc;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::loopback;
+use rsocket_rust::transport::ByteBudget;
+use std::time::Duration;
+use tokio::time;
+
+async fn next_frame(incoming: &mut mpsc::Receiver<Frame>) -> Frame {
+    time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Returns the n of the next REQUEST_N frame, None if none arrives for a while.
+async fn request_n(incoming: &mut mpsc::Receiver<Frame>) -> Option<u32> {
+    let frame = time::timeout(Duration::from_millis(200), incoming.next())
+        .await
+        .ok()??;
+    match frame.get_body() {
+        Body::RequestN(v) => Some(v.get_n()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
+
+fn payload_of(sid: u32, size: usize) -> Frame {
+    frame::Payload::builder(sid, frame::FLAG_NEXT)
+        .set_data(Bytes::from(vec![b'x'; size]))
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn request_payloads_within_byte_budget() {
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let cli = RSocketFactory::connect()
+        .transport(client)
+        .byte_budget(ByteBudget::new().max_stream_bytes(4096))
+        .start()
+        .await
+        .unwrap();
+    assert!(matches!(
+        next_frame(&mut incoming).await.get_body(),
+        Body::Setup(_)
+    ));
+    let mut results = cli.request_stream(Payload::from("ping"));
+    let sid = match next_frame(&mut incoming).await.get_body() {
+        Body::RequestStream(v) => {
+            // nothing is known of the sizes of the payloads yet.
+            assert_eq!(1, v.get_initial_request_n());
+            1
+        }
+        other => panic!("unexpected frame: {:?}", other),
+    };
+
+    sending.send(payload_of(sid, 1000)).await.unwrap();
+    results.next().await.unwrap().unwrap();
+    // as many payloads of the same size as fit in the budget.
+    assert_eq!(Some(4), request_n(&mut incoming).await);
+
+    for _ in 0..4 {
+        sending.send(payload_of(sid, 1000)).await.unwrap();
+    }
+    assert_eq!(None, request_n(&mut incoming).await);
+    results.next().await.unwrap().unwrap();
+    assert_eq!(Some(1), request_n(&mut incoming).await);
+}
+
+#[tokio::main]
+#[test]
+async fn request_huge_payloads_one_by_one() {
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let cli = RSocketFactory::connect()
+        .transport(client)
+        .byte_budget(ByteBudget::new().max_connection_bytes(1024))
+        .start()
+        .await
+        .unwrap();
+    next_frame(&mut incoming).await;
+    let mut results = cli.request_stream(Payload::from("ping"));
+    next_frame(&mut incoming).await;
+
+    for _ in 0..3 {
+        sending.send(payload_of(1, 64 * 1024)).await.unwrap();
+        results.next().await.unwrap().unwrap();
+        // payloads larger than the budget are requested once nothing is outstanding.
+        assert_eq!(Some(1), request_n(&mut incoming).await);
+    }
+}
@@ -0,0 +1,108 @@
+extern crate rsocket_rust;
+
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::interceptor::ResponseCache;
+use rsocket_rust::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Answers with the number of requests it received and the request data.
+#[derive(Clone, Default)]
+struct Counter(Arc<AtomicUsize>);
+
+impl RSocket for Counter {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let n = self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            if req.data_utf8() == Some("fail") {
+                return Err(RSocketError::from("failed"));
+            }
+            let data = req.data_utf8().unwrap_or_default();
+            Ok(Payload::from(format!("{} {}", n, data)))
+        })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+fn routed(route: &str, data: &str) -> Payload {
+    Payload::builder()
+        .set_data_utf8(data)
+        .metadata()
+        .route(route)
+        .build()
+}
+
+async fn call<R: RSocket>(requester: &R, req: Payload) -> String {
+    let res = requester.request_response(req).await.unwrap();
+    let data = res.data_utf8().unwrap();
+    data.split(' ').next().unwrap().to_string()
+}
+
+#[tokio::main]
+#[test]
+async fn cache_responses_by_route_and_data() {
+    let counter = Counter::default();
+    let cache = ResponseCache::new(counter.clone());
+    assert_eq!("0", call(&cache, routed("users.get", "1")).await);
+    assert_eq!("0", call(&cache, routed("users.get", "1")).await);
+    assert_eq!("1", call(&cache, routed("users.get", "2")).await);
+    assert_eq!("2", call(&cache, routed("orders.get", "1")).await);
+    assert_eq!("3", call(&cache, Payload::from("1")).await);
+    assert_eq!("3", call(&cache, Payload::from("1")).await);
+    assert_eq!(4, counter.0.load(Ordering::SeqCst));
+    assert_eq!((2, 4), cache.stats());
+
+    // errors are not cached.
+    assert!(cache.request_response(Payload::from("fail")).await.is_err());
+    assert!(cache.request_response(Payload::from("fail")).await.is_err());
+    assert_eq!(6, counter.0.load(Ordering::SeqCst));
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!("6", call(&cache, routed("users.get", "1")).await);
+}
+
+#[tokio::main]
+#[test]
+async fn expire_and_evict_responses() {
+    let cache = ResponseCache::new(Counter::default())
+        .ttl(Duration::from_millis(50))
+        .capacity(2)
+        .max_entry_size(16);
+    assert_eq!("0", call(&cache, Payload::from("a")).await);
+    assert_eq!("1", call(&cache, Payload::from("b")).await);
+    // "a" is used more recently, so "b" is evicted for "c".
+    assert_eq!("0", call(&cache, Payload::from("a")).await);
+    assert_eq!("2", call(&cache, Payload::from("c")).await);
+    assert_eq!(2, cache.len());
+    assert_eq!("0", call(&cache, Payload::from("a")).await);
+    assert_eq!("3", call(&cache, Payload::from("b")).await);
+
+    tokio::time::delay_for(Duration::from_millis(100)).await;
+    assert_eq!("4", call(&cache, Payload::from("a")).await);
+
+    // responses larger than max_entry_size are not cached.
+    let large = "x".repeat(32);
+    assert_eq!("5", call(&cache, Payload::from(large.clone())).await);
+    assert_eq!("6", call(&cache, Payload::from(large.clone())).await);
+}
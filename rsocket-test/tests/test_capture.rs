@@ -0,0 +1,65 @@
+use rsocket_rust::frame::Body;
+use rsocket_rust::interceptor::{CaptureReader, CaptureRecorder};
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::time::Duration;
+
+#[tokio::main]
+#[test]
+async fn capture_frames() {
+    let addr = "127.0.0.1:7812";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let path = std::env::temp_dir().join("rsocket_rust_test_capture.bin");
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .capture(CaptureRecorder::create(&path).unwrap())
+        .start()
+        .await
+        .unwrap();
+    let res = cli
+        .request_response(Payload::from("Hello World!"))
+        .await
+        .unwrap();
+    assert_eq!(Some("Hello World!"), res.data_utf8());
+    tokio::time::delay_for(Duration::from_millis(100)).await;
+
+    let captured = CaptureReader::open(&path)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let frames = captured
+        .iter()
+        .map(|it| (it.is_outbound(), it.to_frame().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(3, frames.len());
+    assert!(frames[0].0);
+    assert!(matches!(frames[0].1.get_body_ref(), Body::Setup(_)));
+    assert!(frames[1].0);
+    assert!(matches!(
+        frames[1].1.get_body_ref(),
+        Body::RequestResponse(_)
+    ));
+    assert!(!frames[2].0);
+    assert!(matches!(frames[2].1.get_body_ref(), Body::Payload(_)));
+    assert_eq!(frames[1].1.get_stream_id(), frames[2].1.get_stream_id());
+    assert!(captured[0].get_timestamp() <= captured[2].get_timestamp());
+}
+
+#[test]
+fn reject_invalid_capture() {
+    assert!(CaptureReader::new(&b"not a capture"[..]).is_err());
+    let truncated = b"RSCAP\x01\x01\x00\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x10abc";
+    let mut reader = CaptureReader::new(&truncated[..]).unwrap();
+    assert!(reader.next().unwrap().is_err());
+    let mut empty = CaptureReader::new(&b"RSCAP\x01"[..]).unwrap();
+    assert!(empty.next().is_none());
+}
@@ -0,0 +1,380 @@
+mod common;
+
+use futures::channel::mpsc;
+use futures::{future, stream};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::pair;
+use rsocket_rust::transport::ChannelSink;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time;
+
+/// Counts how many values are dropped.
+struct Guard(&'static AtomicUsize);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Emits ticks forever, until the stream is dropped.
+fn ticks(dropped: &'static AtomicUsize) -> Flux<Result<Payload, RSocketError>> {
+    Box::pin(stream::unfold(Guard(dropped), |guard| async move {
+        time::delay_for(Duration::from_millis(5)).await;
+        Some((Ok(Payload::from("tick")), guard))
+    }))
+}
+
+async fn wait_until<F: Fn() -> bool>(cond: F) {
+    for _ in 0..300 {
+        if cond() {
+            return;
+        }
+        time::delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("condition not reached");
+}
+
+static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+static OUTPUTS_DROPPED: AtomicUsize = AtomicUsize::new(0);
+static INPUTS_DROPPED: AtomicUsize = AtomicUsize::new(0);
+static INSPECTED_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// Errors which ended the inbound payloads of the responder, by the first payload.
+static INBOUND_ERRORS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+fn inbound_error(first: &str) -> Option<String> {
+    let errors = INBOUND_ERRORS.lock().unwrap();
+    errors
+        .iter()
+        .find(|it| it.0 == first)
+        .map(|it| it.1.clone())
+}
+
+/// Record the error which ends `reqs`.
+fn inspect(first: String, mut reqs: Flux<Result<Payload, RSocketError>>) {
+    tokio::spawn(async move {
+        while let Some(next) = reqs.next().await {
+            if let Err(e) = next {
+                INBOUND_ERRORS.lock().unwrap().push((first, e.to_string()));
+                return;
+            }
+        }
+    });
+}
+
+/// Behaves by the data of the first payload of a channel.
+struct Responder;
+
+impl RSocket for Responder {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(async move { Ok(req) })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        mut reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(async_stream(move |tx| async move {
+            let first = reqs.next().await.unwrap().unwrap();
+            let first = first.data_utf8().unwrap().to_string();
+            if first.starts_with("inspect") {
+                inspect(first, reqs);
+                let mut outputs = ticks(&INSPECTED_DROPPED);
+                while let Some(next) = outputs.next().await {
+                    if tx.unbounded_send(next).is_err() {
+                        break;
+                    }
+                }
+                return;
+            }
+            match first.as_str() {
+                // respond after the inbound payloads are completed.
+                "after-complete" => {
+                    let n = reqs.count().await + 1;
+                    for _ in 0..3 {
+                        let _ = tx.unbounded_send(Ok(Payload::from(format!("{}", n))));
+                    }
+                }
+                // complete at once, keep consuming the inbound payloads.
+                "complete-early" => {
+                    tokio::spawn(async move {
+                        while let Some(Ok(_)) = reqs.next().await {
+                            RECEIVED.fetch_add(1, Ordering::SeqCst);
+                        }
+                    });
+                    let _ = tx.unbounded_send(Ok(Payload::from("done")));
+                }
+                // fail while the inbound payloads are flowing.
+                "fail-mid-stream" => {
+                    inspect(first, reqs);
+                    let _ = tx.unbounded_send(Ok(Payload::from("one")));
+                    let _ = tx.unbounded_send(Err(RSocketError::from("boom")));
+                }
+                "fail-after-complete" => {
+                    while reqs.next().await.is_some() {}
+                    let _ = tx.unbounded_send(Ok(Payload::from("one")));
+                    let _ = tx.unbounded_send(Err(RSocketError::from("boom")));
+                }
+                "ticks" => {
+                    let mut outputs = ticks(&OUTPUTS_DROPPED);
+                    while let Some(next) = outputs.next().await {
+                        if tx.unbounded_send(next).is_err() {
+                            break;
+                        }
+                    }
+                }
+                // stop consuming the inbound payloads, keep sending.
+                "stop-consuming" => {
+                    drop(reqs);
+                    for _ in 0..20 {
+                        time::delay_for(Duration::from_millis(5)).await;
+                        let _ = tx.unbounded_send(Ok(Payload::from("tick")));
+                    }
+                }
+                _ => (),
+            }
+        }))
+    }
+}
+
+/// Turn a producer task into a stream of its items.
+fn async_stream<F, Fut>(f: F) -> impl Stream<Item = Result<Payload, RSocketError>>
+where
+    F: FnOnce(mpsc::UnboundedSender<Result<Payload, RSocketError>>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (tx, rx) = mpsc::unbounded();
+    tokio::spawn(f(tx));
+    rx
+}
+
+fn payloads(items: &[&'static str]) -> Flux<Result<Payload, RSocketError>> {
+    let items: Vec<_> = items.iter().map(|it| Ok(Payload::from(*it))).collect();
+    Box::pin(stream::iter(items))
+}
+
+async fn collect(flux: Flux<Result<Payload, RSocketError>>) -> Vec<Result<String, String>> {
+    flux.map(|it| {
+        it.map(|it| it.data_utf8().unwrap().to_string())
+            .map_err(|e| e.to_string())
+    })
+    .collect()
+    .await
+}
+
+#[tokio::main]
+#[test]
+async fn respond_after_requester_completes() {
+    let cli = pair(Responder).await;
+    let res = collect(cli.request_channel(payloads(&["after-complete", "a", "b"]))).await;
+    assert_eq!(vec![Ok("3".to_string()); 3], res);
+    // a channel of only one payload completes the inbound side of the responder at once.
+    let res = collect(cli.request_channel(payloads(&["after-complete"]))).await;
+    assert_eq!(vec![Ok("1".to_string()); 3], res);
+}
+
+#[tokio::main]
+#[test]
+async fn keep_sending_after_responder_completes() {
+    let cli = pair(Responder).await;
+    let reqs = stream::iter(vec!["complete-early", "a", "b", "c"]).then(|it| async move {
+        time::delay_for(Duration::from_millis(20)).await;
+        Ok(Payload::from(it))
+    });
+    let res = collect(cli.request_channel(Box::pin(reqs))).await;
+    assert_eq!(vec![Ok("done".to_string())], res);
+    wait_until(|| RECEIVED.load(Ordering::SeqCst) == 3).await;
+}
+
+#[tokio::main]
+#[test]
+async fn error_after_requester_completes() {
+    let cli = pair(Responder).await;
+    let res = collect(cli.request_channel(payloads(&["fail-after-complete", "a"]))).await;
+    assert_eq!(2, res.len());
+    assert_eq!(Ok("one".to_string()), res[0]);
+    assert!(res[1].as_ref().unwrap_err().ends_with("boom"));
+}
+
+#[tokio::main]
+#[test]
+async fn cancel_after_requester_completes() {
+    let cli = pair(Responder).await;
+    let mut res = cli.request_channel(payloads(&["ticks"]));
+    for _ in 0..2 {
+        assert_eq!(Some("tick"), res.next().await.unwrap().unwrap().data_utf8());
+    }
+    drop(res);
+    wait_until(|| OUTPUTS_DROPPED.load(Ordering::SeqCst) == 1).await;
+}
+
+#[tokio::main]
+#[test]
+async fn stop_sending_when_responder_cancels() {
+    let cli = pair(Responder).await;
+    let reqs =
+        stream::iter(Some(Ok(Payload::from("stop-consuming")))).chain(ticks(&INPUTS_DROPPED));
+    let res = collect(cli.request_channel(Box::pin(reqs))).await;
+    // the responder keeps sending after it cancelled the inbound payloads.
+    assert_eq!(20, res.len());
+    wait_until(|| INPUTS_DROPPED.load(Ordering::SeqCst) == 1).await;
+}
+
+async fn next_data(incoming: &mut mpsc::Receiver<Frame>) -> String {
+    let frame = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    let data = match frame.get_body() {
+        Body::RequestChannel(v) => v.get_data().clone(),
+        Body::Payload(v) => v.get_data().clone(),
+        other => panic!("unexpected frame: {:?}", other),
+    };
+    String::from_utf8(data.unwrap().to_vec()).unwrap()
+}
+
+async fn is_ready(sink: &mut ChannelSink) -> bool {
+    let ready = future::poll_fn(|cx| sink.poll_ready_unpin(cx));
+    time::timeout(Duration::from_millis(50), ready)
+        .await
+        .is_ok()
+}
+
+#[tokio::main]
+#[test]
+async fn send_through_sink() {
+    let cli = pair(Responder).await;
+    let (mut sink, res) = cli.request_channel_sink();
+    for it in &["after-complete", "a", "b", "c"] {
+        sink.send(Payload::from(*it)).await.unwrap();
+    }
+    sink.close().await.unwrap();
+    assert_eq!(vec![Ok("4".to_string()); 3], collect(res).await);
+}
+
+#[tokio::main]
+#[test]
+async fn sink_waits_for_request_n() {
+    let (cli, mut incoming, mut sending) =
+        common::connect_raw(|tp| RSocketFactory::connect().transport(tp).start()).await;
+    let (mut sink, _res) = cli.request_channel_sink();
+    // the first payload is sent with the request.
+    assert!(is_ready(&mut sink).await);
+    sink.send(Payload::from("first")).await.unwrap();
+    assert_eq!("first", next_data(&mut incoming).await);
+    assert!(!is_ready(&mut sink).await);
+
+    let request_n = frame::RequestN::builder(1, 0).set_n(2).build();
+    sending.send(request_n).await.unwrap();
+    sink.send(Payload::from("a")).await.unwrap();
+    sink.send(Payload::from("b")).await.unwrap();
+    assert!(!is_ready(&mut sink).await);
+    assert_eq!("a", next_data(&mut incoming).await);
+    assert_eq!("b", next_data(&mut incoming).await);
+
+    // a cancelled channel does not accept payloads anymore.
+    sending
+        .send(frame::Cancel::builder(1, 0).build())
+        .await
+        .unwrap();
+    let closed = future::poll_fn(|cx| sink.poll_ready_unpin(cx));
+    assert!(time::timeout(Duration::from_secs(3), closed)
+        .await
+        .unwrap()
+        .is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn channel_stream_waits_for_request_n() {
+    let (cli, mut incoming, mut sending) =
+        common::connect_raw(|tp| RSocketFactory::connect().transport(tp).start()).await;
+    let reqs = payloads(&["first", "a", "b", "c"]);
+    let _res = cli.request_channel(reqs);
+    assert_eq!("first", next_data(&mut incoming).await);
+    let nothing = time::timeout(Duration::from_millis(50), incoming.next()).await;
+    assert!(nothing.is_err());
+
+    let request_n = frame::RequestN::builder(1, 0).set_n(2).build();
+    sending.send(request_n).await.unwrap();
+    assert_eq!("a", next_data(&mut incoming).await);
+    assert_eq!("b", next_data(&mut incoming).await);
+    let nothing = time::timeout(Duration::from_millis(50), incoming.next()).await;
+    assert!(nothing.is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn requester_error_terminates_both_sides() {
+    let cli = pair(Responder).await;
+    let reqs = stream::iter(vec![Ok("inspect-stream"), Err("boom")]).then(|it| async move {
+        time::delay_for(Duration::from_millis(30)).await;
+        it.map(Payload::from).map_err(RSocketError::from)
+    });
+    let res = collect(cli.request_channel(Box::pin(reqs))).await;
+    assert!(res.len() > 1);
+    assert!(res[..res.len() - 1].iter().all(|it| it.is_ok()));
+    assert_eq!(Err("boom".to_string()), res[res.len() - 1]);
+
+    // the responder sees the translated error and drops its outputs.
+    wait_until(|| inbound_error("inspect-stream").is_some()).await;
+    assert_eq!(
+        "ERROR(APPLICATION): boom",
+        inbound_error("inspect-stream").unwrap()
+    );
+    wait_until(|| INSPECTED_DROPPED.load(Ordering::SeqCst) >= 1).await;
+}
+
+#[tokio::main]
+#[test]
+async fn requester_sink_error_terminates_both_sides() {
+    let cli = pair(Responder).await;
+    let (mut sink, mut res) = cli.request_channel_sink();
+    sink.send(Payload::from("inspect-sink")).await.unwrap();
+    assert!(res.next().await.unwrap().is_ok());
+    sink.error(RSocketError::from("boom")).await;
+    let res = collect(res).await;
+    assert_eq!(Some(&Err("boom".to_string())), res.last());
+    wait_until(|| inbound_error("inspect-sink").is_some()).await;
+    assert_eq!(
+        "ERROR(APPLICATION): boom",
+        inbound_error("inspect-sink").unwrap()
+    );
+}
+
+#[tokio::main]
+#[test]
+async fn responder_error_terminates_both_sides() {
+    let cli = pair(Responder).await;
+    let (mut sink, res) = cli.request_channel_sink();
+    sink.send(Payload::from("fail-mid-stream")).await.unwrap();
+    let res = collect(res).await;
+    assert_eq!(Ok("one".to_string()), res[0]);
+    assert_eq!(Err("ERROR(APPLICATION): boom".to_string()), res[1]);
+    assert_eq!(2, res.len());
+
+    // the sink of the requester fails with the same error.
+    let closed = future::poll_fn(|cx| sink.poll_ready_unpin(cx)).await;
+    assert_eq!("ERROR(APPLICATION): boom", closed.unwrap_err().to_string());
+    // the inbound payloads of the responder end with its own error.
+    wait_until(|| inbound_error("fail-mid-stream").is_some()).await;
+    assert_eq!("boom", inbound_error("fail-mid-stream").unwrap());
+}
@@ -0,0 +1,78 @@
+use rsocket_rust_cli::{run, Args, Endpoint, Interaction, Mode};
+use std::time::Duration;
+use tokio::time::delay_for;
+
+fn parse(args: &[&str]) -> Result<Args, String> {
+    Args::parse(args.iter().map(|it| it.to_string()))
+}
+
+#[test]
+fn parse_args() {
+    let args = parse(&["-i", "hello", "-m", "world", "tcp://127.0.0.1:7878"]).unwrap();
+    assert_eq!(Endpoint::Tcp("127.0.0.1:7878".to_string()), args.endpoint);
+    assert_eq!(Mode::Client(Interaction::RequestResponse), args.mode);
+    assert_eq!(Some("hello".to_string()), args.input);
+    assert_eq!(Some("world".to_string()), args.metadata);
+
+    let args = parse(&[
+        "--stream",
+        "-r",
+        "a",
+        "-r",
+        "b",
+        "-n",
+        "3",
+        "ws://localhost:80",
+    ])
+    .unwrap();
+    assert_eq!(
+        Endpoint::Websocket("localhost:80".to_string()),
+        args.endpoint
+    );
+    assert_eq!(Mode::Client(Interaction::RequestStream), args.mode);
+    assert_eq!(vec!["a".to_string(), "b".to_string()], args.routes);
+    assert_eq!(Some(3), args.limit);
+
+    assert_eq!(
+        Mode::Server,
+        parse(&["--server", "tcp://0.0.0.0:1"]).unwrap().mode
+    );
+
+    assert!(parse(&[]).is_err());
+    assert!(parse(&["http://127.0.0.1:80"]).is_err());
+    assert!(parse(&["-i"]).is_err());
+    assert!(parse(&["-n", "many", "tcp://127.0.0.1:1"]).is_err());
+    assert!(parse(&["--foo", "tcp://127.0.0.1:1"]).is_err());
+    assert!(parse(&["-m", "x", "-r", "y", "tcp://127.0.0.1:1"]).is_err());
+    assert!(parse(&["tcp://127.0.0.1:1", "tcp://127.0.0.1:2"]).is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn request_echo_server() {
+    for uri in &["tcp://127.0.0.1:7821", "ws://127.0.0.1:7822"] {
+        let server = parse(&["--server", uri]).unwrap();
+        tokio::spawn(async move { run(&server, &mut vec![]).await.unwrap() });
+        delay_for(Duration::from_millis(500)).await;
+
+        let mut out = vec![];
+        let args = parse(&["--debug", "-i", "hello", "-r", "echo", uri]).unwrap();
+        run(&args, &mut out).await.unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("metadata: "), "{}", out);
+        assert!(out.ends_with("\nhello\n"), "{}", out);
+
+        let mut out = vec![];
+        let args = parse(&["--stream", "-i", "hi", uri]).unwrap();
+        run(&args, &mut out).await.unwrap();
+        assert_eq!("hi\nhi\nhi\n", String::from_utf8(out).unwrap());
+
+        let mut out = vec![];
+        let args = parse(&["--stream", "-n", "2", "-i", "hi", uri]).unwrap();
+        run(&args, &mut out).await.unwrap();
+        assert_eq!("hi\nhi\n", String::from_utf8(out).unwrap());
+
+        let args = parse(&["--fnf", "-i", "hi", uri]).unwrap();
+        run(&args, &mut vec![]).await.unwrap();
+    }
+}
@@ -1,9 +1,10 @@
 #[macro_use]
 extern crate log;
 
+use bytes::Bytes;
 use futures::stream;
 use rsocket_rust::prelude::*;
-use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use rsocket_rust_transport_tcp::{FlushStrategy, TcpClientTransport, TcpServerTransport};
 use rsocket_rust_transport_websocket::{WebsocketClientTransport, WebsocketServerTransport};
 use std::thread::sleep;
 use std::time::Duration;
@@ -116,6 +117,141 @@ fn test_tcp() {
     });
 }
 
+#[tokio::main]
+#[test]
+async fn test_tcp_large_payload() {
+    let addr = "127.0.0.1:7813";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap();
+    let data = (0..1024 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+    let metadata = vec![0x5Au8; 64 * 1024];
+    let sending = Payload::builder()
+        .set_data(Bytes::from(data.clone()))
+        .set_metadata(Bytes::from(metadata.clone()))
+        .build();
+    let res = cli.request_response(sending).await.unwrap();
+    assert_eq!(&Some(Bytes::from(data)), res.data());
+    assert_eq!(&Some(Bytes::from(metadata)), res.metadata());
+}
+
+#[tokio::main]
+#[test]
+async fn test_tcp_flush_strategy() {
+    let addr = "127.0.0.1:7814";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(
+                TcpServerTransport::from(addr)
+                    .flush_strategy(FlushStrategy::OnIdle { max_bytes: 16384 }),
+            )
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(
+            TcpClientTransport::from(addr).flush_strategy(FlushStrategy::Delayed {
+                max_bytes: 16384,
+                max_delay: Duration::from_millis(5),
+            }),
+        )
+        .start()
+        .await
+        .unwrap();
+    // mix small frames with frames larger than the batch.
+    let requests = (0..200).map(|i| {
+        let data = if i % 50 == 0 {
+            vec![b'x'; 32768]
+        } else {
+            format!("Hello {}", i).into_bytes()
+        };
+        let cli = &cli;
+        async move {
+            let res = cli
+                .request_response(
+                    Payload::builder()
+                        .set_data(Bytes::from(data.clone()))
+                        .build(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(&Some(Bytes::from(data)), res.data());
+        }
+    });
+    futures::future::join_all(requests).await;
+    for _ in 0..100 {
+        cli.fire_and_forget(Payload::from("Hello World!")).await;
+    }
+    let mut results = cli.request_stream(Payload::from("Hello World!"));
+    let mut n = 0;
+    while let Some(res) = results.next().await {
+        assert_eq!(Some("Hello World!"), res.unwrap().data_utf8());
+        n += 1;
+    }
+    assert_eq!(3, n);
+}
+
+#[tokio::main]
+#[test]
+async fn test_tcp_batch_small_frames() {
+    let addr = "127.0.0.1:7819";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(
+            TcpClientTransport::from(addr).flush_strategy(FlushStrategy::Batched {
+                max_frame_size: 64,
+                max_bytes: 16384,
+                max_delay: Duration::from_millis(300),
+            }),
+        )
+        .start()
+        .await
+        .unwrap();
+    // a frame above the limit is written at once.
+    let started = std::time::Instant::now();
+    let data = vec![b'x'; 1024];
+    let res = cli
+        .request_response(
+            Payload::builder()
+                .set_data(Bytes::from(data.clone()))
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(&Some(Bytes::from(data)), res.data());
+    assert!(started.elapsed() < Duration::from_millis(250));
+    // a tiny frame is held until the delay expires.
+    let started = std::time::Instant::now();
+    let res = cli
+        .request_response(Payload::from("Hello World!"))
+        .await
+        .unwrap();
+    assert_eq!(Some("Hello World!"), res.data_utf8());
+    assert!(started.elapsed() >= Duration::from_millis(250));
+}
+
 #[tokio::main]
 #[test]
 #[ignore]
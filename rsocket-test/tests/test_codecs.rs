@@ -0,0 +1,133 @@
+#[macro_use]
+extern crate log;
+
+use rsocket_rust::codec;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+struct Student {
+    id: u32,
+    name: String,
+    tags: Vec<String>,
+}
+
+fn student() -> Student {
+    Student {
+        id: 1,
+        name: String::from("Jeffsky"),
+        tags: vec![String::from("foo"), String::from("bar")],
+    }
+}
+
+#[test]
+fn codecs() {
+    let mimes = vec![
+        mime::APPLICATION_JSON,
+        mime::APPLICATION_CBOR,
+        mime::APPLICATION_MSGPACK,
+    ];
+    for mime_type in mimes {
+        assert!(codec::is_supported(mime_type));
+        let b = codec::encode(mime_type, &student()).unwrap();
+        let decoded: Student = codec::decode(mime_type, &b).unwrap();
+        assert_eq!(student(), decoded);
+        assert!(codec::decode::<Vec<u32>>(mime_type, &b).is_err());
+    }
+    assert!(!codec::is_supported(mime::TEXT_PLAIN));
+    assert!(codec::encode(mime::TEXT_PLAIN, &student()).is_err());
+}
+
+#[test]
+fn per_stream_mime_type() {
+    let req = Payload::builder()
+        .set_data(codec::encode(mime::APPLICATION_CBOR, &student()).unwrap())
+        .metadata()
+        .mime_type(mime::APPLICATION_CBOR)
+        .unwrap()
+        .build();
+    assert_eq!(
+        Some(mime::APPLICATION_CBOR.to_string()),
+        codec::data_mime_type_of(&req)
+    );
+    let decoded: Student = codec::decode_payload(&req, mime::APPLICATION_JSON).unwrap();
+    assert_eq!(student(), decoded);
+
+    let req = Payload::from(codec::encode(mime::APPLICATION_MSGPACK, &student()).unwrap());
+    assert_eq!(None, codec::data_mime_type_of(&req));
+    let decoded: Student = codec::decode_payload(&req, mime::APPLICATION_MSGPACK).unwrap();
+    assert_eq!(student(), decoded);
+}
+
+#[tokio::main]
+#[test]
+async fn typed_request_api() {
+    let addr = "127.0.0.1:7803";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|setup, _socket| {
+                info!("accept setup: {:?}", setup);
+                Ok(Box::new(EchoRSocket))
+            })
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .mime_type(
+            mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0,
+            mime::APPLICATION_CBOR,
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let res: Student = cli.request_response_typed(&student()).await.unwrap();
+    assert_eq!(student(), res);
+
+    // echoed with the per-stream MIME type attached to the request.
+    let mut results =
+        cli.request_stream_as::<Student, Student>(mime::APPLICATION_MSGPACK, &student());
+    let mut n = 0;
+    while let Some(res) = results.next().await {
+        assert_eq!(student(), res.unwrap());
+        n += 1;
+    }
+    assert_eq!(3, n);
+
+    let reqs = futures::stream::iter(vec![student(), student()]);
+    let results: Vec<Student> = cli
+        .request_channel_typed(reqs)
+        .map(|it| it.unwrap())
+        .collect()
+        .await;
+    assert_eq!(vec![student(), student()], results);
+    cli.close();
+}
+
+#[tokio::main]
+#[test]
+async fn typed_streams() {
+    let values = futures::stream::iter(vec![student(), student(), student()]);
+    let payloads = codec::encode_stream(values, mime::APPLICATION_MSGPACK);
+    let results: Vec<Student> = codec::decode_stream(payloads, mime::APPLICATION_MSGPACK)
+        .map(|it| it.unwrap())
+        .collect()
+        .await;
+    assert_eq!(3, results.len());
+
+    let values = futures::stream::iter(vec![Ok(1u32), Err(RSocketError::from("broken"))]);
+    let payloads = codec::try_encode_stream(values, mime::APPLICATION_JSON);
+    let results: Vec<_> = codec::decode_stream::<_, u32>(payloads, mime::APPLICATION_JSON)
+        .collect()
+        .await;
+    assert_eq!(1, *results[0].as_ref().unwrap());
+    assert!(results[1].is_err());
+}
@@ -23,3 +23,33 @@ fn encode_and_decode_composite_metadata() {
     let cm2 = CompositeMetadata::decode(&mut bf).unwrap();
     bingo(cm2.iter().collect());
 }
+
+#[test]
+fn keep_order_beyond_inline_entries() {
+    let mut bu = CompositeMetadata::builder();
+    for i in 0..5 {
+        bu = bu.push(format!("application/x.entry{}", i), format!("entry {}", i));
+    }
+    let mut bf = BytesMut::new();
+    bu.build().write_to(&mut bf);
+    let cm = CompositeMetadata::decode(&mut bf).unwrap();
+    let mimes = cm
+        .iter()
+        .map(|it| it.get_mime().as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            "application/x.entry0",
+            "application/x.entry1",
+            "application/x.entry2",
+            "application/x.entry3",
+            "application/x.entry4",
+        ],
+        mimes
+    );
+    assert_eq!(
+        Some(&b"entry 3"[..]),
+        cm.find("application/x.entry3")
+            .map(|it| it.get_payload().as_ref())
+    );
+}
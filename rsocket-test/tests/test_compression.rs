@@ -0,0 +1,137 @@
+use rsocket_rust::extension::AuthMetadata;
+use rsocket_rust::interceptor::{CompressingRequester, CompressingResponder, Compression};
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use rsocket_rust::transport::Principal;
+
+#[test]
+fn compression_of_mime_type() {
+    assert_eq!(
+        Some(Compression::Gzip),
+        Compression::from_mime("application/json+gzip")
+    );
+    assert_eq!(
+        Some(Compression::Zstd),
+        Compression::from_mime("application/cbor+zstd")
+    );
+    assert_eq!(None, Compression::from_mime("application/json"));
+    assert_eq!(None, Compression::from_mime("application/cloudevents+json"));
+
+    let data = "rsocket ".repeat(512);
+    for compression in [Compression::Gzip, Compression::Zstd].iter() {
+        let compressed = compression.compress(data.as_bytes()).unwrap();
+        assert!(compressed.len() < data.len());
+        let decompressed = compression.decompress(&compressed).unwrap();
+        assert_eq!(data.as_bytes(), decompressed.as_ref());
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn compress_payloads_both_ways() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|setup, _socket| {
+                let compression =
+                    Compression::from_mime(setup.data_mime_type().as_ref().unwrap()).unwrap();
+                Ok(Box::new(
+                    CompressingResponder::new(EchoRSocket, compression).threshold(64),
+                ))
+            })
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .data_mime_type("text/plain+zstd")
+        .start()
+        .await
+        .unwrap();
+    let compression = Compression::from_mime(cli.get_data_mime_type()).unwrap();
+    let requester = CompressingRequester::new(cli, compression).threshold(64);
+
+    let large = "rsocket ".repeat(512);
+    let req = Payload::builder()
+        .set_data_utf8(&large)
+        .metadata()
+        .route("echo")
+        .build();
+    let res = requester.request_response(req).await.unwrap();
+    assert_eq!(Some(large.as_str()), res.data_utf8());
+
+    // small payloads are sent as they are.
+    let res = requester
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+
+    let mut results = requester.request_stream(Payload::builder().set_data_utf8(&large).build());
+    let mut n = 0;
+    while let Some(it) = results.next().await {
+        assert_eq!(Some(large.as_str()), it.unwrap().data_utf8());
+        n += 1;
+    }
+    assert_eq!(3, n);
+}
+
+#[tokio::main]
+#[test]
+async fn keep_request_context_for_guarded_routes() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .authenticator(|auth: Option<AuthMetadata>| async move {
+                match auth {
+                    Some(AuthMetadata::Bearer(token)) if token == "root" => {
+                        Ok(Principal::new("root").role("admin"))
+                    }
+                    _ => Ok(Principal::new("guest")),
+                }
+            })
+            .acceptor(|_setup, _socket| {
+                let router = Router::new().route("admin.*", EchoRSocket).authorize(
+                    "admin.*",
+                    |principal, _metadata| {
+                        principal.map(|it| it.has_role("admin")).unwrap_or(false)
+                    },
+                );
+                Ok(Box::new(
+                    CompressingResponder::new(router, Compression::Gzip).threshold(64),
+                ))
+            })
+            .serve(),
+    );
+    let connect = |token: &str| {
+        RSocketFactory::connect()
+            .transport(connector.connect().unwrap())
+            .metadata_mime_type(mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0)
+            .setup(Payload::builder().metadata().bearer(token).build())
+            .start()
+    };
+    let large = "rsocket ".repeat(512);
+    let routed = || {
+        Payload::builder()
+            .set_data_utf8(&large)
+            .metadata()
+            .route("admin.users")
+            .build()
+    };
+
+    // the principal of the connection reaches the guard through the wrapper.
+    let root = connect("root").await.unwrap();
+    let root = CompressingRequester::new(root, Compression::Gzip).threshold(64);
+    let res = root.request_response(routed()).await.unwrap();
+    assert_eq!(Some(large.as_str()), res.data_utf8());
+
+    let guest = connect("guest").await.unwrap();
+    let guest = CompressingRequester::new(guest, Compression::Gzip).threshold(64);
+    let e = guest.request_response(routed()).await.unwrap_err();
+    assert!(e
+        .to_string()
+        .contains("access denied to route: admin.users"));
+}
@@ -0,0 +1,53 @@
+use futures::future::join_all;
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::time::Duration;
+
+#[tokio::main(core_threads = 4)]
+#[test]
+async fn many_concurrent_streams() {
+    let addr = "127.0.0.1:7816";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap();
+    let responses = join_all((0..2000).map(|i| {
+        let cli = cli.clone();
+        tokio::spawn(async move {
+            let req = Payload::builder()
+                .set_data_utf8(&format!("Hello {}", i))
+                .build();
+            (i, cli.request_response(req).await.unwrap())
+        })
+    }))
+    .await;
+    assert_eq!(2000, responses.len());
+    for it in responses {
+        let (i, res) = it.unwrap();
+        assert_eq!(Some(format!("Hello {}", i).as_str()), res.data_utf8());
+    }
+
+    let streams = join_all((0..200).map(|_| {
+        let mut results = cli.request_stream(Payload::from("Hello World!"));
+        async move {
+            let mut n = 0;
+            while let Some(it) = results.next().await {
+                it.unwrap();
+                n += 1;
+            }
+            n
+        }
+    }))
+    .await;
+    assert!(streams.iter().all(|n| *n == 3));
+}
@@ -0,0 +1,175 @@
+use bytes::{Bytes, BytesMut};
+use rsocket_rust::frame::*;
+use rsocket_rust::utils::Writeable;
+use std::collections::HashSet;
+use std::time::Duration;
+
+const FIXTURES: &str = include_str!("fixtures/frames.txt");
+
+fn fixtures() -> Vec<(&'static str, Vec<u8>)> {
+    FIXTURES
+        .lines()
+        .filter(|it| !it.is_empty() && !it.starts_with('#'))
+        .map(|line| {
+            let mut kv = line.splitn(2, ' ');
+            let name = kv.next().unwrap();
+            let raw = hex::decode(kv.next().unwrap().trim()).unwrap();
+            (name, raw)
+        })
+        .collect()
+}
+
+fn expected(name: &str) -> Frame {
+    match name {
+        "setup" => Setup::builder(0, 0)
+            .set_mime_metadata("text/plain")
+            .set_mime_data("application/json")
+            .set_metadata(Bytes::from("md"))
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "setup_resume_lease" => Setup::builder(0, FLAG_LEASE)
+            .set_keepalive(Duration::from_secs(1))
+            .set_lifetime(Duration::from_secs(5))
+            .set_token(Bytes::from("tok"))
+            .set_mime_metadata("a")
+            .set_mime_data("b")
+            .build(),
+        "setup_empty" => Setup::builder(0, 0)
+            .set_mime_metadata("")
+            .set_mime_data("")
+            .build(),
+        "lease" => Lease::builder(0, 0)
+            .set_ttl(1000)
+            .set_number_of_requests(10)
+            .build(),
+        "lease_metadata" => Lease::builder(0, 0)
+            .set_ttl(1000)
+            .set_number_of_requests(10)
+            .set_metadata(Bytes::from("md"))
+            .build(),
+        "keepalive_respond" => Keepalive::builder(0, FLAG_RESPOND)
+            .set_last_received_position(123)
+            .set_data(Bytes::from("ping"))
+            .build(),
+        "keepalive" => Keepalive::builder(0, 0).build(),
+        "request_response" => RequestResponse::builder(1, 0)
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "request_response_metadata" => RequestResponse::builder(1, 0)
+            .set_metadata(Bytes::from("md"))
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "request_response_follows" => RequestResponse::builder(1, FLAG_FOLLOW)
+            .set_metadata(Bytes::from("md"))
+            .set_data(Bytes::from("hel"))
+            .build(),
+        "request_response_empty_metadata" => RequestResponse::builder(1, 0)
+            .set_metadata(Bytes::new())
+            .build(),
+        "request_fnf" => RequestFNF::builder(3, 0)
+            .set_metadata(Bytes::from("md"))
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "request_stream" => RequestStream::builder(5, 0)
+            .set_initial_request_n(5)
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "request_stream_metadata" => RequestStream::builder(5, 0)
+            .set_initial_request_n(REQUEST_MAX)
+            .set_metadata(Bytes::from("md"))
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "request_channel_complete" => RequestChannel::builder(7, FLAG_COMPLETE)
+            .set_initial_request_n(1)
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "request_channel_metadata" => RequestChannel::builder(7, 0)
+            .set_initial_request_n(8)
+            .set_metadata(Bytes::from("md"))
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "request_n" => RequestN::builder(5, 0).set_n(100).build(),
+        "cancel" => Cancel::builder(5, 0).build(),
+        "payload_next" => Payload::builder(1, FLAG_NEXT)
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "payload_next_complete_metadata" => Payload::builder(1, FLAG_NEXT | FLAG_COMPLETE)
+            .set_metadata(Bytes::from("md"))
+            .set_data(Bytes::from("hello"))
+            .build(),
+        "payload_complete" => Payload::builder(1, FLAG_COMPLETE).build(),
+        "payload_follows" => Payload::builder(1, FLAG_FOLLOW | FLAG_NEXT)
+            .set_data(Bytes::from("hel"))
+            .build(),
+        "error_application" => Error::builder(1, 0)
+            .set_code(0x0201)
+            .set_data(Bytes::from("boom"))
+            .build(),
+        "error_invalid_setup" => Error::builder(0, 0)
+            .set_code(0x0001)
+            .set_data(Bytes::from("bad setup"))
+            .build(),
+        "error_rejected" => Error::builder(2, 0).set_code(0x0202).build(),
+        "metadata_push" => MetadataPush::builder(0, 0)
+            .set_metadata(Bytes::from("md"))
+            .build(),
+        "resume" => Resume::builder(0, 0)
+            .set_token(Bytes::from("tok"))
+            .set_last_received_server_position(10)
+            .set_first_available_client_position(20)
+            .build(),
+        "resume_ok" => ResumeOK::builder(0, 0).set_position(42).build(),
+        _ => panic!("no expected frame for fixture {}", name),
+    }
+}
+
+#[test]
+fn decode_fixtures() {
+    for (name, raw) in fixtures() {
+        let decoded = Frame::decode(&mut BytesMut::from(&raw[..]))
+            .unwrap_or_else(|e| panic!("decode {} failed: {}", name, e));
+        assert_eq!(expected(name), decoded, "decoded {} doesn't match", name);
+    }
+}
+
+#[test]
+fn encode_fixtures() {
+    for (name, raw) in fixtures() {
+        let frame = expected(name);
+        let mut bf = BytesMut::new();
+        frame.write_to(&mut bf);
+        assert_eq!(
+            hex::encode(&raw),
+            hex::encode(&bf),
+            "encoded {} doesn't match",
+            name
+        );
+        assert_eq!(raw.len(), frame.len(), "length of {} doesn't match", name);
+    }
+}
+
+#[test]
+fn fixtures_cover_every_frame_type() {
+    let types: HashSet<u16> = fixtures()
+        .iter()
+        .map(|(name, _)| expected(name).get_frame_type())
+        .collect();
+    for kind in &[
+        TYPE_SETUP,
+        TYPE_LEASE,
+        TYPE_KEEPALIVE,
+        TYPE_REQUEST_RESPONSE,
+        TYPE_REQUEST_FNF,
+        TYPE_REQUEST_STREAM,
+        TYPE_REQUEST_CHANNEL,
+        TYPE_REQUEST_N,
+        TYPE_CANCEL,
+        TYPE_PAYLOAD,
+        TYPE_ERROR,
+        TYPE_METADATA_PUSH,
+        TYPE_RESUME,
+        TYPE_RESUME_OK,
+    ] {
+        assert!(types.contains(kind), "no fixture of frame type {}", kind);
+    }
+}
@@ -0,0 +1,107 @@
+use bytes::Bytes;
+use futures::channel::mpsc;
+use rsocket_rust::error;
+use rsocket_rust::frame::{self, Body};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{loopback, LoopbackServerTransport};
+use std::time::Duration;
+use tokio::time;
+
+#[tokio::main]
+#[test]
+async fn drive_connection_by_caller() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let (requester, connection) = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .connect()
+        .await
+        .unwrap();
+    let connection = tokio::spawn(connection);
+    let res = requester
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+
+    // the requester has the API of a client.
+    assert_eq!(
+        "application/binary",
+        requester.params().get_data_mime_type()
+    );
+    requester.ping().await.unwrap();
+    requester
+        .health_check(Duration::from_secs(3))
+        .await
+        .unwrap();
+    let (_sink, _responses) = requester.request_channel_sink();
+
+    requester.close();
+    let outcome = time::timeout(Duration::from_secs(3), connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(outcome.is_ok());
+}
+
+#[tokio::main]
+#[test]
+async fn resolve_connection_by_its_error() {
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let (_requester, connection) = RSocketFactory::connect()
+        .transport(client)
+        .connect()
+        .await
+        .unwrap();
+    assert!(matches!(
+        incoming.next().await.unwrap().get_body(),
+        Body::Setup(_)
+    ));
+    let connection = tokio::spawn(connection);
+
+    let bye = frame::Error::builder(0, 0)
+        .set_code(error::ERR_CONN_CLOSED)
+        .set_data(Bytes::from("bye"))
+        .build();
+    sending.send(bye).await.unwrap();
+    let e = time::timeout(Duration::from_secs(3), connection)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!("ERROR(CONN_CLOSED): bye", e.to_string());
+}
+
+#[tokio::main]
+#[test]
+async fn drop_connection_fails_requests() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let (requester, connection) = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .connect()
+        .await
+        .unwrap();
+    drop(connection);
+    assert!(requester.is_closed());
+    let res = time::timeout(
+        Duration::from_secs(3),
+        requester.request_response(Payload::from("ping")),
+    )
+    .await
+    .unwrap();
+    assert!(res.is_err());
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::stream;
+use rsocket_rust::error::{self, RSocketError};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::time;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Guard(&'static AtomicUsize);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+struct Responder;
+
+impl RSocket for Responder {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(async move {
+            if req.data_utf8() == Some("slow") {
+                time::delay_for(Duration::from_millis(200)).await;
+            }
+            Ok(req)
+        })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::unfold(Guard(&DROPPED), |guard| async move {
+            time::delay_for(Duration::from_millis(5)).await;
+            Some((Ok(Payload::from("tick")), guard))
+        }))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+async fn next_frame(incoming: &mut mpsc::Receiver<Frame>) -> Frame {
+    time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+fn connection_closed() -> Frame {
+    frame::Error::builder(0, 0)
+        .set_code(error::ERR_CONN_CLOSED)
+        .set_data(Bytes::from("bye"))
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn connection_error_terminates_requesters() {
+    let (cli, mut incoming, mut sending) = common::connect_raw(|tp| {
+        RSocketFactory::connect()
+            .transport(tp)
+            .acceptor(|| Box::new(Responder))
+            .start()
+    })
+    .await;
+    let rr = {
+        let cli = cli.clone();
+        tokio::spawn(async move { cli.request_response(Payload::from("ping")).await })
+    };
+    let mut responses = cli.request_stream(Payload::from("ping"));
+    let (_sink, mut inbound) = cli.request_channel_sink();
+    // the request frames reached the peer.
+    next_frame(&mut incoming).await;
+    next_frame(&mut incoming).await;
+
+    sending.send(connection_closed()).await.unwrap();
+    let expected = "ERROR(CONN_CLOSED): bye";
+    let e = time::timeout(Duration::from_secs(3), rr)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(expected, e.to_string());
+    let e = time::timeout(Duration::from_secs(3), responses.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(expected, e.to_string());
+    let e = time::timeout(Duration::from_secs(3), inbound.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(expected, e.to_string());
+}
+
+#[tokio::main]
+#[test]
+async fn connection_error_cancels_responders() {
+    let (_cli, mut incoming, mut sending) = common::connect_raw(|tp| {
+        RSocketFactory::connect()
+            .transport(tp)
+            .acceptor(|| Box::new(Responder))
+            .start()
+    })
+    .await;
+    let req = frame::RequestStream::builder(2, 0)
+        .set_initial_request_n(u32::MAX >> 1)
+        .set_data(Bytes::from("ticks"))
+        .build();
+    sending.send(req).await.unwrap();
+    assert_eq!(2, next_frame(&mut incoming).await.get_stream_id());
+
+    sending.send(connection_closed()).await.unwrap();
+    for _ in 0..300 {
+        if DROPPED.load(Ordering::SeqCst) == 1 {
+            return;
+        }
+        time::delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("responder stream is not cancelled");
+}
+
+#[tokio::main]
+#[test]
+async fn stream_error_cancels_responder() {
+    let (_cli, mut incoming, mut sending) = common::connect_raw(|tp| {
+        RSocketFactory::connect()
+            .transport(tp)
+            .acceptor(|| Box::new(Responder))
+            .start()
+    })
+    .await;
+    let req = frame::RequestResponse::builder(2, 0)
+        .set_data(Bytes::from("slow"))
+        .build();
+    sending.send(req).await.unwrap();
+    let err = frame::Error::builder(2, 0)
+        .set_code(error::ERR_APPLICATION)
+        .set_data(Bytes::from("gave up"))
+        .build();
+    sending.send(err).await.unwrap();
+
+    // the connection goes on with the next request.
+    let req = frame::RequestResponse::builder(4, 0)
+        .set_data(Bytes::from("ping"))
+        .build();
+    sending.send(req).await.unwrap();
+    let res = next_frame(&mut incoming).await;
+    assert_eq!(4, res.get_stream_id());
+    match res.get_body() {
+        Body::Payload(v) => assert_eq!(&Some(Bytes::from("ping")), v.get_data()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
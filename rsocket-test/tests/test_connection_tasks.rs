@@ -0,0 +1,90 @@
+use futures::{future, stream};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::time;
+
+static DROPPED: AtomicBool = AtomicBool::new(false);
+
+/// Sets `DROPPED` once the handler holding it is dropped.
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Never responds.
+struct PendingRSocket;
+
+impl RSocket for PendingRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(async move {
+            let _guard = Guard;
+            future::pending().await
+        })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::pending())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn close_ends_connection_tasks() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(PendingRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+    let requester = cli.clone();
+    let pending = tokio::spawn(async move {
+        requester
+            .request_response(Payload::from("ping"))
+            .await
+            .unwrap_err()
+    });
+    let _stream = cli.request_stream(Payload::from("ping"));
+    time::delay_for(Duration::from_millis(100)).await;
+    assert!(!DROPPED.load(Ordering::SeqCst));
+
+    time::timeout(Duration::from_secs(3), cli.shutdown())
+        .await
+        .unwrap();
+    let e = pending.await.unwrap();
+    assert_eq!("ERROR(CONN_CLOSED): connection closed", e.to_string());
+    // the handler of the server is dropped with the connection.
+    for _ in 0..300 {
+        if DROPPED.load(Ordering::SeqCst) {
+            return;
+        }
+        time::delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("handler outlives the connection");
+}
@@ -0,0 +1,170 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rsocket_rust::extension::{
+    AuthMetadata, CompositeMetadata, MimeTypeMetadata, RoutingMetadata, TracingMetadata,
+};
+use rsocket_rust::frame::*;
+use rsocket_rust::utils::Writeable;
+use rsocket_rust_transport_tcp::LengthBasedFrameCodec;
+use tokio_util::codec::Decoder;
+
+fn samples() -> Vec<Frame> {
+    vec![
+        Setup::builder(0, FLAG_METADATA | FLAG_RESUME)
+            .set_mime_data("application/binary")
+            .set_mime_metadata("text/plain")
+            .set_token(Bytes::from("token"))
+            .set_data(Bytes::from("Hello World!"))
+            .set_metadata(Bytes::from("foobar"))
+            .build(),
+        Keepalive::builder(0, FLAG_RESPOND)
+            .set_last_received_position(123)
+            .set_data(Bytes::from("foobar"))
+            .build(),
+        RequestResponse::builder(1, 0)
+            .set_data(Bytes::from("Hello World"))
+            .set_metadata(Bytes::from("Foobar"))
+            .build(),
+        RequestStream::builder(1, 0)
+            .set_initial_request_n(8)
+            .set_metadata(Bytes::from("Foobar"))
+            .build(),
+        RequestChannel::builder(1, 0)
+            .set_initial_request_n(1)
+            .set_data(Bytes::from("Hello World!"))
+            .set_metadata(Bytes::from("foobar"))
+            .build(),
+        RequestFNF::builder(1, 0)
+            .set_data(Bytes::from("Hello"))
+            .set_metadata(Bytes::from("World"))
+            .build(),
+        Payload::builder(1, FLAG_NEXT | FLAG_COMPLETE)
+            .set_data(Bytes::from("Hello World!"))
+            .set_metadata(Bytes::from("foobar"))
+            .build(),
+        MetadataPush::builder(0, 0)
+            .set_metadata(Bytes::from("Hello"))
+            .build(),
+        RequestN::builder(1, 0).set_n(77).build(),
+        Lease::builder(0, FLAG_METADATA)
+            .set_ttl(1000)
+            .set_number_of_requests(10)
+            .set_metadata(Bytes::from("lease"))
+            .build(),
+        Cancel::builder(1, 0).build(),
+        Error::builder(1, 0)
+            .set_code(0x0201)
+            .set_data(Bytes::from("boom"))
+            .build(),
+        ResumeOK::builder(0, 0).set_position(42).build(),
+        Resume::builder(0, 0)
+            .set_token(Bytes::from("token"))
+            .set_last_received_server_position(1)
+            .set_first_available_client_position(2)
+            .build(),
+    ]
+}
+
+fn encode(frame: &Frame) -> BytesMut {
+    let mut bf = BytesMut::new();
+    frame.write_to(&mut bf);
+    bf
+}
+
+/// Feed `b` into every decoder, which may fail but must not panic.
+fn decode_all(b: &[u8]) {
+    if let Ok(frame) = Frame::decode(&mut BytesMut::from(b)) {
+        let mut bf = BytesMut::new();
+        frame.write_to(&mut bf);
+        assert_eq!(frame.len(), bf.len(), "{:?}", frame);
+    }
+    let _ = dump::annotate(b);
+    let _ = CompositeMetadata::decode(&mut BytesMut::from(b));
+    let _ = RoutingMetadata::decode(&mut BytesMut::from(b));
+    let _ = MimeTypeMetadata::decode(&mut BytesMut::from(b));
+    let _ = AuthMetadata::decode(&mut BytesMut::from(b));
+    let _ = TracingMetadata::decode(&mut BytesMut::from(b));
+    let mut codec = LengthBasedFrameCodec;
+    let mut bf = BytesMut::new();
+    for chunk in b.chunks(5) {
+        bf.extend_from_slice(chunk);
+        match codec.decode(&mut bf) {
+            Ok(_) => (),
+            Err(_) => break,
+        }
+    }
+}
+
+#[test]
+fn truncated_frames_fail_without_panic() {
+    for frame in samples() {
+        let bf = encode(&frame);
+        for cut in 0..bf.len() {
+            decode_all(&bf[..cut]);
+        }
+        assert_eq!(frame, Frame::decode(&mut bf.clone()).unwrap());
+    }
+    // a header only.
+    assert!(Frame::decode(&mut BytesMut::from(&[0u8, 0, 0, 1, 0x10][..])).is_err());
+    assert!(Frame::decode(&mut BytesMut::from(&[0u8, 0, 0, 1, 0x20, 0][..])).is_err());
+}
+
+#[test]
+fn corrupted_frames_fail_without_panic() {
+    let mut rng = StdRng::seed_from_u64(0x5eed);
+    let frames: Vec<BytesMut> = samples().iter().map(encode).collect();
+    for _ in 0..20_000 {
+        let mut b = frames[rng.gen_range(0, frames.len())].to_vec();
+        for _ in 0..rng.gen_range(1, 4) {
+            let i = rng.gen_range(0, b.len());
+            b[i] = rng.gen();
+        }
+        decode_all(&b);
+        let cut = rng.gen_range(0, b.len() + 1);
+        decode_all(&b[..cut]);
+    }
+    for _ in 0..20_000 {
+        let n = rng.gen_range(0, 48);
+        let b: Vec<u8> = (0..n).map(|_| rng.gen()).collect();
+        decode_all(&b);
+    }
+}
+
+#[test]
+fn metadata_length_beyond_frame() {
+    let mut bf = BytesMut::new();
+    bf.put_u32(1);
+    bf.put_u16((0x04 << 10) | FLAG_METADATA);
+    // 0xFFFFFF bytes of metadata are announced but only 2 follow.
+    bf.put_slice(&[0xFF, 0xFF, 0xFF, 1, 2]);
+    assert!(Frame::decode(&mut bf).is_err());
+}
+
+#[test]
+fn setup_mime_type_beyond_frame() {
+    let mut bf = encode(&samples()[0]);
+    // the metadata MIME type length follows the header, version, keepalive, lifetime and the
+    // 2 bytes token length plus 5 bytes of token.
+    bf[6 + 12 + 2 + 5] = 0xFF;
+    assert!(Frame::decode(&mut bf).is_err());
+
+    // not UTF-8.
+    let mut bf = encode(&samples()[0]);
+    bf[6 + 12 + 2 + 5 + 1] = 0xFF;
+    assert!(Frame::decode(&mut bf).is_err());
+}
+
+#[test]
+fn composite_metadata_with_unknown_well_known_mime() {
+    let mut bf = BytesMut::from(&[0xD0u8, 0, 0, 1, 0][..]);
+    assert!(CompositeMetadata::decode(&mut bf).is_err());
+}
+
+#[test]
+fn invalid_utf8_fails_to_decode() {
+    let mut bf = BytesMut::from(&[0x02u8, 0xC3, 0x28][..]);
+    assert!(RoutingMetadata::decode(&mut bf).is_err());
+    let mut bf = BytesMut::from(&[0x02u8, 0xC3, 0x28, 0, 0, 0][..]);
+    assert!(CompositeMetadata::decode(&mut bf).is_err());
+}
@@ -0,0 +1,163 @@
+use bytes::Bytes;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::extension::AuthMetadata;
+use rsocket_rust::interceptor::{EncryptingRequester, EncryptingResponder, PayloadCipher};
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use rsocket_rust::transport::Principal;
+
+/// Xors data with a key of one byte, keys are named after the byte.
+struct XorCipher(u8);
+
+impl PayloadCipher for XorCipher {
+    fn key_id(&self) -> &str {
+        "xor"
+    }
+
+    fn encrypt(&self, key_id: &str, data: &[u8]) -> Result<Bytes, RSocketError> {
+        self.decrypt(key_id, data)
+    }
+
+    fn decrypt(&self, key_id: &str, data: &[u8]) -> Result<Bytes, RSocketError> {
+        if key_id != "xor" {
+            return Err(RSocketError::from(format!("unknown key: {}", key_id)));
+        }
+        Ok(data.iter().map(|b| b ^ self.0).collect::<Vec<u8>>().into())
+    }
+}
+
+/// Echoes requests, checking they arrive decrypted and without the key id.
+struct PlainCheck;
+
+impl RSocket for PlainCheck {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        assert_eq!(Some("ping"), req.data_utf8());
+        assert!(req.metadata().is_none());
+        EchoRSocket.request_response(req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn encrypt_payloads_end_to_end() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| {
+                Ok(Box::new(EncryptingResponder::new(
+                    PlainCheck,
+                    XorCipher(42),
+                )))
+            })
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+
+    let requester = EncryptingRequester::new(cli.clone(), XorCipher(42));
+    let res = requester
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+    assert!(res.metadata().is_none());
+
+    // plaintext is rejected unless it is allowed.
+    let e = cli
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("payload data is not encrypted"));
+}
+
+#[tokio::main]
+#[test]
+async fn encrypt_streams() {
+    // the echoed data comes back encrypted under the key id it was sent with.
+    let requester = EncryptingRequester::new(EchoRSocket, XorCipher(7));
+    let mut results = requester.request_stream(Payload::from("ping"));
+    let mut n = 0;
+    while let Some(it) = results.next().await {
+        assert_eq!(Some("ping"), it.unwrap().data_utf8());
+        n += 1;
+    }
+    assert_eq!(3, n);
+}
+
+#[tokio::main]
+#[test]
+async fn keep_request_context_for_guarded_routes() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .authenticator(|auth: Option<AuthMetadata>| async move {
+                match auth {
+                    Some(AuthMetadata::Bearer(token)) if token == "root" => {
+                        Ok(Principal::new("root").role("admin"))
+                    }
+                    _ => Ok(Principal::new("guest")),
+                }
+            })
+            .acceptor(|_setup, _socket| {
+                let router = Router::new().route("admin.*", EchoRSocket).authorize(
+                    "admin.*",
+                    |principal, _metadata| {
+                        principal.map(|it| it.has_role("admin")).unwrap_or(false)
+                    },
+                );
+                Ok(Box::new(EncryptingResponder::new(router, XorCipher(42))))
+            })
+            .serve(),
+    );
+    let connect = |token: &str| {
+        RSocketFactory::connect()
+            .transport(connector.connect().unwrap())
+            .metadata_mime_type(mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0)
+            .setup(Payload::builder().metadata().bearer(token).build())
+            .start()
+    };
+    let routed = || {
+        Payload::builder()
+            .set_data_utf8("ping")
+            .metadata()
+            .route("admin.users")
+            .build()
+    };
+
+    // the principal of the connection reaches the guard through the wrapper.
+    let root = EncryptingRequester::new(connect("root").await.unwrap(), XorCipher(42));
+    let res = root.request_response(routed()).await.unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+
+    let guest = EncryptingRequester::new(connect("guest").await.unwrap(), XorCipher(42));
+    let e = guest.request_response(routed()).await.unwrap_err();
+    assert!(e
+        .to_string()
+        .contains("access denied to route: admin.users"));
+}
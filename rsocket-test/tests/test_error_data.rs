@@ -0,0 +1,96 @@
+use bytes::Bytes;
+use futures::stream;
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::pair;
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Detail {
+    reason: String,
+    retry_after: u64,
+}
+
+fn detailed() -> RSocketError {
+    RSocketError::from(ErrorKind::Internal(
+        error::ERR_REJECTED,
+        "overloaded".into(),
+    ))
+    .with_data(Bytes::from(r#"{"reason":"overloaded","retry_after":5}"#))
+}
+
+struct Failing;
+
+impl RSocket for Failing {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(async move {
+            match req.data_utf8() {
+                Some("detailed") => Err(detailed()),
+                _ => Err(RSocketError::from("plain failure")),
+            }
+        })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(vec![
+            Ok(Payload::from("first")),
+            Err(detailed()),
+        ]))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn decode_error_data_of_request_response() {
+    let cli = pair(Failing).await;
+    let err = cli
+        .request_response(Payload::from("detailed"))
+        .await
+        .unwrap_err();
+    match err.kind() {
+        ErrorKind::Internal(code, _) => assert_eq!(error::ERR_REJECTED, *code),
+        other => panic!("unexpected error: {:?}", other),
+    }
+    assert_eq!(
+        Detail {
+            reason: "overloaded".into(),
+            retry_after: 5,
+        },
+        err.data_json::<Detail>().unwrap()
+    );
+
+    let err = cli
+        .request_response(Payload::from("plain"))
+        .await
+        .unwrap_err();
+    assert_eq!(Some(&Bytes::from("plain failure")), err.get_data());
+    assert!(err.data_json::<Detail>().is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn keep_error_data_of_stream() {
+    let cli = pair(Failing).await;
+    let mut results = cli.request_stream(Payload::from("any"));
+    assert_eq!(
+        Some("first"),
+        results.next().await.unwrap().unwrap().data_utf8()
+    );
+    let err = results.next().await.unwrap().unwrap_err();
+    assert_eq!("overloaded", err.data_json::<Detail>().unwrap().reason);
+}
@@ -0,0 +1,112 @@
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use rsocket_rust::transport::{Execution, WorkerPool};
+use std::thread;
+use std::time::Duration;
+use tokio::time;
+
+/// Responds with the name of the thread which ran the handler, after blocking it a while.
+struct BusyRSocket;
+
+impl RSocket for BusyRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(async move {
+            thread::sleep(Duration::from_millis(300));
+            let name = thread::current().name().unwrap_or_default().to_string();
+            Ok(Payload::from(name))
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn run_handlers_on_worker_pool() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .execution(Execution::Pool(WorkerPool::new(1, 1)))
+            .acceptor(|_setup, _socket| Ok(Box::new(BusyRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+
+    // one request runs, one waits in the queue and the last one finds it full.
+    let mut pending = vec![];
+    for _ in 0..3 {
+        let cli = cli.clone();
+        pending.push(tokio::spawn(async move {
+            cli.request_response(Payload::from("ping")).await
+        }));
+        time::delay_for(Duration::from_millis(50)).await;
+    }
+    let mut responded = vec![];
+    let mut rejected = vec![];
+    for it in pending {
+        match it.await.unwrap() {
+            Ok(res) => responded.push(res.data_utf8().unwrap().to_string()),
+            Err(e) => rejected.push(e),
+        }
+    }
+    assert_eq!(vec!["rsocket-worker-0", "rsocket-worker-0"], responded);
+    assert_eq!(1, rejected.len());
+    match rejected[0].kind() {
+        ErrorKind::Internal(code, msg) => {
+            assert_eq!(error::ERR_REJECTED, *code);
+            assert_eq!("worker pool is full", msg);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn run_handlers_inline() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .execution(Execution::Inline)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+    let res = cli.request_response(Payload::from("ping")).await.unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+    // streams are spawned even so.
+    let mut results = cli.request_stream(Payload::from("ping"));
+    let mut n = 0;
+    while let Some(it) = results.next().await {
+        assert_eq!(Some("ping"), it.unwrap().data_utf8());
+        n += 1;
+    }
+    assert_eq!(3, n);
+}
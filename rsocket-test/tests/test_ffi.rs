@@ -0,0 +1,167 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust_ffi::*;
+use rsocket_rust_transport_tcp::TcpServerTransport;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::slice;
+use std::sync::mpsc::{self, Sender};
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
+
+const URI: &str = "tcp://127.0.0.1:7823";
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Next(String),
+    Complete,
+    Error(String),
+}
+
+fn start_echo_server() {
+    static START: Once = Once::new();
+    START.call_once(|| {
+        thread::spawn(|| {
+            let mut rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                RSocketFactory::receive()
+                    .transport(TcpServerTransport::from("127.0.0.1:7823"))
+                    .acceptor(|_, _| Ok(Box::new(EchoRSocket)))
+                    .serve()
+                    .await
+            })
+            .unwrap();
+        });
+        thread::sleep(Duration::from_millis(500));
+    });
+}
+
+fn connect() -> *mut RSocketClient {
+    start_echo_server();
+    let uri = CString::new(URI).unwrap();
+    let client = unsafe { rsocket_client_connect(uri.as_ptr()) };
+    assert!(!client.is_null());
+    client
+}
+
+unsafe fn data(payload: *const RSocketPayload) -> String {
+    let mut len = 0;
+    let p = rsocket_payload_data(payload, &mut len);
+    String::from_utf8(slice::from_raw_parts(p, len).to_vec()).unwrap()
+}
+
+extern "C" fn on_response(
+    user_data: *mut c_void,
+    response: *const RSocketPayload,
+    error: *const c_char,
+) {
+    let tx = unsafe { &*(user_data as *const Sender<Event>) };
+    let event = if !error.is_null() {
+        Event::Error(
+            unsafe { CStr::from_ptr(error) }
+                .to_string_lossy()
+                .to_string(),
+        )
+    } else if !response.is_null() {
+        Event::Next(unsafe { data(response) })
+    } else {
+        Event::Complete
+    };
+    tx.send(event).unwrap();
+}
+
+#[test]
+fn connect_failures() {
+    let uri = CString::new("http://127.0.0.1:7823").unwrap();
+    let client = unsafe { rsocket_client_connect(uri.as_ptr()) };
+    assert!(client.is_null());
+    let e = unsafe { CStr::from_ptr(rsocket_last_error()) };
+    assert!(e.to_str().unwrap().contains("unsupported URI"));
+    assert!(unsafe { rsocket_client_connect(ptr::null()) }.is_null());
+}
+
+#[test]
+fn request_response_callback() {
+    let client = connect();
+    let (tx, rx) = mpsc::channel();
+    let tx = Box::into_raw(Box::new(tx));
+    let rc = unsafe {
+        rsocket_request_response(
+            client,
+            b"hello".as_ptr(),
+            5,
+            ptr::null(),
+            0,
+            on_response,
+            tx as *mut c_void,
+        )
+    };
+    assert_eq!(RSOCKET_OK, rc);
+    let event = rx.recv_timeout(Duration::from_secs(3)).unwrap();
+    assert_eq!(Event::Next("hello".to_string()), event);
+    unsafe {
+        assert_eq!(
+            RSOCKET_OK,
+            rsocket_fire_and_forget(client, b"ping".as_ptr(), 4, ptr::null(), 0)
+        );
+        rsocket_client_close(client);
+        drop(Box::from_raw(tx));
+    }
+}
+
+#[test]
+fn request_stream_poll() {
+    let client = connect();
+    unsafe {
+        let stream = rsocket_request_stream(client, b"hi".as_ptr(), 2, b"md".as_ptr(), 2);
+        assert!(!stream.is_null());
+        let mut res = ptr::null_mut();
+        for _ in 0..3 {
+            assert_eq!(RSOCKET_NEXT, rsocket_stream_poll(stream, 3000, &mut res));
+            assert_eq!("hi", data(res));
+            let mut len = 0;
+            assert!(!rsocket_payload_metadata(res, &mut len).is_null());
+            assert_eq!(2, len);
+            rsocket_payload_free(res);
+        }
+        assert_eq!(RSOCKET_COMPLETE, rsocket_stream_poll(stream, -1, &mut res));
+        rsocket_stream_free(stream);
+        rsocket_client_close(client);
+    }
+}
+
+#[test]
+fn request_stream_callback() {
+    let client = connect();
+    let (tx, rx) = mpsc::channel();
+    let tx = Box::into_raw(Box::new(tx));
+    unsafe {
+        let rc = rsocket_request_stream_callback(
+            client,
+            b"hi".as_ptr(),
+            2,
+            ptr::null(),
+            0,
+            on_response,
+            tx as *mut c_void,
+        );
+        assert_eq!(RSOCKET_OK, rc);
+    }
+    let events: Vec<Event> = (0..4)
+        .map(|_| rx.recv_timeout(Duration::from_secs(3)).unwrap())
+        .collect();
+    assert_eq!(
+        vec![
+            Event::Next("hi".to_string()),
+            Event::Next("hi".to_string()),
+            Event::Next("hi".to_string()),
+            Event::Complete,
+        ],
+        events
+    );
+    unsafe {
+        rsocket_client_close(client);
+        drop(Box::from_raw(tx));
+    }
+}
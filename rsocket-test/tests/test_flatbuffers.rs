@@ -0,0 +1,34 @@
+extern crate rsocket_rust;
+
+use rsocket_rust::codec::flatbuffers::{self, FlatBufferBuilder};
+use rsocket_rust::prelude::*;
+
+#[test]
+fn flatbuffers_root() {
+    let mut builder = FlatBufferBuilder::new();
+    let s = builder.create_string("Hello World!");
+    builder.finish_minimal(s);
+    let payload = Payload::from(flatbuffers::encode(builder));
+
+    let root = flatbuffers::root::<&str>(&payload).unwrap();
+    assert_eq!("Hello World!", root);
+    // the root points into the payload data.
+    let data = payload.data().as_ref().unwrap();
+    let range = data.as_ptr() as usize..data.as_ptr() as usize + data.len();
+    assert!(range.contains(&(root.as_ptr() as usize)));
+
+    let root = unsafe { flatbuffers::root_unchecked::<&str>(&payload) }.unwrap();
+    assert_eq!("Hello World!", root);
+}
+
+#[test]
+fn flatbuffers_verify() {
+    let mut builder = FlatBufferBuilder::new();
+    let s = builder.create_string("Hello World!");
+    builder.finish_minimal(s);
+    let mut raw = flatbuffers::encode(builder).to_vec();
+    // offset of the string points out of the buffer.
+    raw[0] = 0xFF;
+    assert!(flatbuffers::root::<&str>(&Payload::from(raw)).is_err());
+    assert!(flatbuffers::root::<&str>(&Payload::new(None, None)).is_err());
+}
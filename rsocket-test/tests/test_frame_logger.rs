@@ -0,0 +1,71 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use rsocket_rust::interceptor::{FrameLogger, Redaction};
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::sync::Mutex;
+use std::time::Duration;
+
+static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CaptureLogger;
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug && metadata.target() == "rsocket_rust::frames"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            RECORDS.lock().unwrap().push(format!("{}", record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[tokio::main]
+#[test]
+async fn log_frames_with_redaction() {
+    log::set_logger(&CaptureLogger).unwrap();
+    log::set_max_level(LevelFilter::Debug);
+
+    let addr = "127.0.0.1:7806";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .frame_logger(FrameLogger::new())
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .frame_logger(
+            FrameLogger::new()
+                .data(Redaction::Show)
+                .metadata(Redaction::Truncate(4)),
+        )
+        .start()
+        .await
+        .unwrap();
+    let req = Payload::from_utf8_with_metadata("Hello World!", "secret token");
+    cli.request_response(req).await.unwrap();
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let records = RECORDS.lock().unwrap();
+    let sent = records
+        .iter()
+        .filter(|it| it.starts_with("===> SND: REQUEST_RESPONSE"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            "===> SND: REQUEST_RESPONSE sid=1 flags=0x100 metadata=\"secr\"...<12 bytes> data=\"Hello World!\""
+        ],
+        sent
+    );
+    assert!(records.iter().any(|it| it
+        == "<=== RCV: REQUEST_RESPONSE sid=1 flags=0x100 metadata=<12 bytes> data=<12 bytes>"));
+    assert!(records.iter().all(|it| !it.contains("secret")));
+}
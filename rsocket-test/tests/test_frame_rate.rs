@@ -0,0 +1,115 @@
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use rsocket_rust::transport::FrameRate;
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// Responds streams with as many items as the request asks for, endless ones by default.
+struct EndlessRSocket;
+
+impl RSocket for EndlessRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        EchoRSocket.request_response(req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let count = req
+            .data_utf8()
+            .and_then(|it| it.parse().ok())
+            .unwrap_or(u64::MAX);
+        Box::pin(stream::iter(
+            (0..count).map(|n| Ok(Payload::from(format!("{}", n)))),
+        ))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+async fn connect(rate: FrameRate) -> Client<DefaultSpawner> {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .frame_rate(rate)
+            .fair_scheduling()
+            .acceptor(|_setup, _socket| Ok(Box::new(EndlessRSocket)))
+            .serve(),
+    );
+    RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap()
+}
+
+/// Returns the number of items received within `period`.
+async fn count_items(results: &mut Flux<Result<Payload, RSocketError>>, period: Duration) -> usize {
+    let deadline = Instant::now() + period;
+    let mut n = 0;
+    while let Ok(Some(it)) = time::timeout_at(deadline, results.next()).await {
+        it.unwrap();
+        n += 1;
+    }
+    n
+}
+
+#[tokio::main]
+#[test]
+async fn throttle_frames_per_connection() {
+    let cli = connect(FrameRate::new().max_frames(20).burst(1)).await;
+    // the throttled payloads of both streams fit in the outbound queue.
+    let mut first = cli.request_stream(Payload::from("100"));
+    let mut second = cli.request_stream(Payload::from("100"));
+    let period = Duration::from_millis(500);
+    let (a, b) = futures::join!(
+        count_items(&mut first, period),
+        count_items(&mut second, period)
+    );
+    // about ten frames are written within the period, shared by both streams.
+    assert!(a + b >= 5 && a + b <= 15, "{} frames written", a + b);
+    assert!(a > 0 && b > 0);
+}
+
+#[tokio::main]
+#[test]
+async fn throttle_frames_per_stream() {
+    let cli = connect(FrameRate::new().max_stream_frames(20).burst(1)).await;
+    // the throttled payloads of both streams fit in the outbound queue.
+    let mut first = cli.request_stream(Payload::from("100"));
+    let mut second = cli.request_stream(Payload::from("100"));
+    let period = Duration::from_millis(500);
+    let (a, b) = futures::join!(
+        count_items(&mut first, period),
+        count_items(&mut second, period)
+    );
+    for n in [a, b].iter() {
+        assert!(*n >= 5 && *n <= 15, "{} frames written", n);
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn keepalive_passes_throttled_frames() {
+    let cli = connect(FrameRate::new().max_frames(10).burst(1)).await;
+    // five seconds worth of payloads wait in the outbound queue of the server.
+    let _results = cli.request_stream(Payload::from("50"));
+    time::delay_for(Duration::from_millis(200)).await;
+    let rtt = cli.ping().await.unwrap();
+    assert!(rtt < Duration::from_millis(100), "round trip of {:?}", rtt);
+}
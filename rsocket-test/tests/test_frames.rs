@@ -4,7 +4,7 @@ extern crate rsocket_rust;
 
 use bytes::{Bytes, BytesMut};
 use rsocket_rust::frame::*;
-use rsocket_rust::utils::Writeable;
+use rsocket_rust::utils::{Writeable, U24};
 
 #[test]
 fn test_setup() {
@@ -119,11 +119,101 @@ fn test_resume() {
     try_codec(f);
 }
 
+#[test]
+fn test_resume_without_token() {
+    let f = Resume::builder(0, 0)
+        .set_last_received_server_position(123)
+        .set_first_available_client_position(22)
+        .build();
+    try_codec(f);
+}
+
+#[test]
+fn test_decode_without_copy() {
+    let f = Payload::builder(1234, FLAG_NEXT)
+        .set_data(Bytes::from("Hello World!"))
+        .set_metadata(Bytes::from("foobar"))
+        .build();
+    let mut bf = BytesMut::with_capacity(f.len());
+    f.write_to(&mut bf);
+    let start = bf.as_ptr() as usize;
+    let range = start..start + bf.len();
+    let (d, m) = match Frame::decode(&mut bf).unwrap().get_body() {
+        Body::Payload(p) => p.split(),
+        _ => panic!("expect PAYLOAD"),
+    };
+    // payload data and metadata alias the receive buffer.
+    assert!(range.contains(&(d.unwrap().as_ptr() as usize)));
+    assert!(range.contains(&(m.unwrap().as_ptr() as usize)));
+}
+
+#[test]
+fn test_annotate() {
+    let f = RequestResponse::builder(1, 0)
+        .set_data(Bytes::from("Hello World!"))
+        .set_metadata(Bytes::from("secr"))
+        .build();
+    let mut bf = BytesMut::with_capacity(f.len());
+    f.write_to(&mut bf);
+    let s = dump::annotate(&bf);
+    println!("{}", s);
+    assert!(s.starts_with("Frame (25 bytes)\n  Stream ID: 1\n"));
+    assert!(s.contains("  Frame Type: REQUEST_RESPONSE (0x04)\n"));
+    assert!(s.contains("  Flags: 0x100 (METADATA)\n"));
+    assert!(s.contains("  Metadata Length: 4\n  Metadata (4 bytes):\n    0000  73 65 63 72 "));
+    assert!(s.contains("  Data (12 bytes):\n"));
+    assert!(s.ends_with("Hello World!\n"));
+
+    // truncated input is rendered as far as possible.
+    let s = dump::annotate(&bf[..8]);
+    println!("{}", s);
+    assert!(s.contains("<truncated Metadata Length: need 3 bytes at offset 6, 2 remaining>"));
+    assert!(dump::annotate(&[]).contains("<truncated Stream ID"));
+    assert!(dump::annotate(&[0, 0, 0, 0, 0xFC, 0]).contains("illegal frame type: 63"));
+}
+
+#[test]
+fn test_hexdump() {
+    assert_eq!(
+        "0000  48 65 6c 6c 6f 0a                                ello.\n".replace("ello", "Hello"),
+        dump::hexdump(b"Hello\n")
+    );
+    assert_eq!(2, dump::hexdump(&[0u8; 17]).lines().count());
+}
+
+#[test]
+fn test_encode_without_copy() {
+    let data = Bytes::from(vec![0x42u8; 65536]);
+    let metadata = Bytes::from("foobar");
+    let f = Payload::builder(1234, FLAG_NEXT)
+        .set_data(data.clone())
+        .set_metadata(metadata.clone())
+        .build();
+    let mut head = BytesMut::new();
+    let (m, d) = f.write_head_to(&mut head);
+    // the head only carries the header and the metadata length.
+    assert_eq!(9, head.len());
+    assert_eq!(data.as_ptr(), d.unwrap().as_ptr());
+    assert_eq!(metadata.as_ptr(), m.unwrap().as_ptr());
+}
+
 fn try_codec(f: Frame) {
     println!("******* codec: {:?}", f);
     let mut bf = BytesMut::with_capacity(f.len() as usize);
     f.write_to(&mut bf);
     println!("####### encode: {}", hex::encode(bf.to_vec()));
+    let mut vectored = BytesMut::new();
+    let (m, d) = f.write_head_to(&mut vectored);
+    for b in m.iter().chain(d.iter()) {
+        vectored.extend_from_slice(b);
+    }
+    assert_eq!(bf, vectored, "vectored encoding doesn't match");
+    let mut prefixed = BytesMut::from(&b"left"[..]);
+    f.write_length_prefixed_to(&mut prefixed);
+    let mut encoded = prefixed.split_off(4);
+    assert_eq!(b"left", &prefixed[..]);
+    assert_eq!(f.len() as u32, U24::read_advance(&mut encoded));
+    assert_eq!(bf, encoded, "length-prefixed encoding doesn't match");
     let f2 = Frame::decode(&mut bf).unwrap();
     println!("####### decode: {:?}", f2);
     assert_eq!(
@@ -0,0 +1,153 @@
+use bytes::Bytes;
+use futures::stream;
+use http::{Request, StatusCode};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::extension::{AuthMetadata, CompositeMetadata, MimeTypeMetadata};
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use rsocket_rust_gateway::{Body, Gateway};
+
+/// Answers with the metadata entries of requests, one per line.
+struct Inspect;
+
+impl RSocket for Inspect {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let mut metadata = bytes::BytesMut::from(req.metadata().as_deref().unwrap());
+        let composite = CompositeMetadata::decode(&mut metadata).unwrap();
+        let mut lines = vec![format!("data={}", req.data_utf8().unwrap_or_default())];
+        for it in composite.iter() {
+            let value = match it.get_mime().as_str() {
+                "message/x.rsocket.routing.v0" => String::from("-"),
+                "message/x.rsocket.mime-type.v0" => {
+                    let mut b = bytes::BytesMut::from(it.get_payload().as_ref());
+                    MimeTypeMetadata::decode(&mut b)
+                        .unwrap()
+                        .get_mime()
+                        .to_string()
+                }
+                "message/x.rsocket.authentication.v0" => {
+                    let mut b = bytes::BytesMut::from(it.get_payload().as_ref());
+                    match AuthMetadata::decode(&mut b).unwrap() {
+                        AuthMetadata::Bearer(token) => token,
+                        other => format!("{:?}", other),
+                    }
+                }
+                _ => String::from_utf8_lossy(it.get_payload()).to_string(),
+            };
+            lines.push(format!("{}={}", it.get_mime(), value));
+        }
+        Box::pin(async move { Ok(Payload::from(lines.join("\n"))) })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(vec![
+            Ok(Payload::from("first")),
+            Ok(Payload::from("two\nlines")),
+        ]))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+async fn start_gateway() -> Gateway {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_, _| Ok(Box::new(Router::new().route("inspect", Inspect))))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+    Gateway::new(cli).header("x-request-id", "text/x.request-id")
+}
+
+async fn body_of(body: Body) -> String {
+    let b = match body {
+        Body::Full(b) => b,
+        Body::Events(events) => {
+            let all: Vec<Bytes> = events.collect().await;
+            Bytes::from(all.concat())
+        }
+    };
+    String::from_utf8(b.to_vec()).unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn map_requests_to_request_response() {
+    let gateway = start_gateway().await;
+    let req = Request::post("/inspect?ignored=1")
+        .header("content-type", "application/json")
+        .header("authorization", "Bearer t0ken")
+        .header("x-request-id", "42")
+        .header("x-other", "dropped")
+        .body(Bytes::from("{}"))
+        .unwrap();
+    let res = gateway.handle(req).await;
+    assert_eq!(StatusCode::OK, res.status());
+    let lines = body_of(res.into_body()).await;
+    assert_eq!(
+        vec![
+            "data={}",
+            "message/x.rsocket.routing.v0=-",
+            "message/x.rsocket.mime-type.v0=application/json",
+            "message/x.rsocket.authentication.v0=t0ken",
+            "text/x.request-id=42",
+        ],
+        lines.split('\n').collect::<Vec<_>>()
+    );
+}
+
+#[tokio::main]
+#[test]
+async fn map_event_streams_to_request_stream() {
+    let gateway = start_gateway().await;
+    let req = Request::get("/inspect")
+        .header("accept", "text/event-stream")
+        .body(Bytes::new())
+        .unwrap();
+    let res = gateway.handle(req).await;
+    assert_eq!(StatusCode::OK, res.status());
+    assert_eq!("text/event-stream", res.headers()["content-type"]);
+    assert_eq!(
+        "data: first\n\ndata: two\ndata: lines\n\n",
+        body_of(res.into_body()).await
+    );
+
+    let req = Request::get("/nowhere")
+        .header("accept", "text/event-stream")
+        .body(Bytes::new())
+        .unwrap();
+    let events = body_of(gateway.handle(req).await.into_body()).await;
+    assert!(events.starts_with("event: error\ndata: "));
+}
+
+#[tokio::main]
+#[test]
+async fn map_errors_to_status_codes() {
+    let gateway = start_gateway().await;
+    let req = Request::get("/nowhere").body(Bytes::new()).unwrap();
+    let res = gateway.handle(req).await;
+    assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, res.status());
+
+    let req = Request::get("/").body(Bytes::new()).unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, gateway.handle(req).await.status());
+}
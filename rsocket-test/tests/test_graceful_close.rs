@@ -0,0 +1,103 @@
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::time::{Duration, Instant};
+use tokio::time;
+
+/// Responds after a while.
+struct SlowRSocket;
+
+impl RSocket for SlowRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(async move {
+            time::delay_for(Duration::from_millis(300)).await;
+            Ok(req)
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+async fn start() -> Client<DefaultSpawner> {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(SlowRSocket)))
+            .serve(),
+    );
+    RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn close_after_requests_in_flight() {
+    let cli = start().await;
+    let requester = cli.clone();
+    let pending =
+        tokio::spawn(async move { requester.request_response(Payload::from("ping")).await });
+    time::delay_for(Duration::from_millis(50)).await;
+
+    let closing = tokio::spawn(cli.clone().close_gracefully(Duration::from_secs(3)));
+    time::delay_for(Duration::from_millis(50)).await;
+    // no request is admitted once closing.
+    let e = cli
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap_err();
+    match e.kind() {
+        ErrorKind::Internal(code, msg) => {
+            assert_eq!(error::ERR_REJECTED, *code);
+            assert_eq!("connection is closing", msg);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    let res = pending.await.unwrap().unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+    time::timeout(Duration::from_secs(1), closing)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(cli.is_closed());
+}
+
+#[tokio::main]
+#[test]
+async fn terminate_requests_beyond_grace() {
+    let cli = start().await;
+    let requester = cli.clone();
+    let pending =
+        tokio::spawn(async move { requester.request_response(Payload::from("ping")).await });
+    time::delay_for(Duration::from_millis(50)).await;
+
+    let started = Instant::now();
+    cli.clone()
+        .close_gracefully(Duration::from_millis(100))
+        .await;
+    assert!(started.elapsed() < Duration::from_millis(250));
+    let e = pending.await.unwrap().unwrap_err();
+    assert_eq!("ERROR(CONN_CLOSED): connection closed", e.to_string());
+}
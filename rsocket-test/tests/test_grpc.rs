@@ -0,0 +1,278 @@
+use bytes::Bytes;
+use futures::stream;
+use http::header::HeaderMap;
+use http::Request;
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust_gateway::grpc::*;
+
+struct Greeter;
+
+impl RSocket for Greeter {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let name = req.data_utf8().unwrap_or_default().to_string();
+        Box::pin(async move {
+            if name.is_empty() {
+                let e = ErrorKind::Internal(error::ERR_INVALID, String::from("empty name é"));
+                return Err(RSocketError::from(e));
+            }
+            Ok(Payload::from(format!("Hello {}!", name)))
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let name = req.data_utf8().unwrap_or_default().to_string();
+        Box::pin(stream::iter(vec![
+            Ok(Payload::from(format!("1 {}", name))),
+            Ok(Payload::from(format!("2 {}", name))),
+        ]))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+/// Answers calls with the path, the authorization header and the request messages.
+struct Echo;
+
+impl GrpcChannel for Echo {
+    fn call(
+        &self,
+        path: &str,
+        kind: CallKind,
+        metadata: HeaderMap,
+        messages: Flux<Bytes>,
+    ) -> Flux<Result<Bytes, Status>> {
+        if path.ends_with("/Fail") {
+            let status = Status::new(Code::NotFound, "no such thing");
+            return Box::pin(stream::iter(Some(Err(status))));
+        }
+        let auth = metadata
+            .get("authorization")
+            .map(|it| it.to_str().unwrap().to_string())
+            .unwrap_or_default();
+        let head = format!("{} {:?} {}", path, kind, auth);
+        let head = stream::iter(Some(Ok(Bytes::from(head))));
+        Box::pin(head.chain(messages.map(Ok)))
+    }
+}
+
+fn bridge() -> GrpcBridge {
+    let router = Router::new()
+        .route("helloworld.Greeter.SayHello", Greeter)
+        .route("helloworld.Greeter.Lots", Greeter)
+        .route("helloworld.Greeter.Chat", Greeter);
+    GrpcBridge::new(router)
+        .method("/helloworld.Greeter/Lots", CallKind::ServerStreaming)
+        .method("/helloworld.Greeter/Chat", CallKind::Bidi)
+}
+
+fn call(path: &str, chunks: Vec<Bytes>) -> Request<Flux<Bytes>> {
+    Request::post(path)
+        .header("content-type", "application/grpc+proto")
+        .body(Box::pin(stream::iter(chunks)) as Flux<Bytes>)
+        .unwrap()
+}
+
+/// Returns the decoded messages and the status of a response body.
+async fn read(frames: Flux<GrpcFrame>) -> (Vec<String>, Status) {
+    let frames: Vec<GrpcFrame> = frames.collect().await;
+    let mut messages = vec![];
+    let mut status = None;
+    for it in frames {
+        match it {
+            GrpcFrame::Data(b) => {
+                assert!(status.is_none(), "data after trailers");
+                let mut decoder = MessageDecoder::new();
+                decoder.push(&b);
+                let msg = decoder.next_message().unwrap().unwrap();
+                messages.push(String::from_utf8(msg.to_vec()).unwrap());
+            }
+            GrpcFrame::Trailers(trailers) => status = Status::from_trailers(&trailers),
+        }
+    }
+    (messages, status.expect("missing trailers"))
+}
+
+#[test]
+fn decode_length_prefixed_messages() {
+    let mut decoder = MessageDecoder::new();
+    let msg = encode_message(b"hello");
+    assert_eq!(&[0, 0, 0, 0, 5], &msg[..5]);
+    decoder.push(&msg[..3]);
+    assert_eq!(None, decoder.next_message().unwrap());
+    decoder.push(&msg[3..]);
+    decoder.push(&encode_message(b""));
+    assert_eq!(Some(Bytes::from("hello")), decoder.next_message().unwrap());
+    assert_eq!(Some(Bytes::new()), decoder.next_message().unwrap());
+    assert!(decoder.is_empty());
+
+    decoder.push(&[1, 0, 0, 0, 0]);
+    let status = decoder.next_message().unwrap_err();
+    assert_eq!(Code::Unimplemented, status.get_code());
+}
+
+#[test]
+fn status_trailers() {
+    let status = Status::new(Code::InvalidArgument, "50% off\nnow");
+    let trailers = status.to_trailers();
+    assert_eq!("3", trailers["grpc-status"]);
+    assert_eq!("50%25 off%0Anow", trailers["grpc-message"]);
+    assert_eq!(Some(status), Status::from_trailers(&trailers));
+    assert_eq!(None, Status::from_trailers(&HeaderMap::new()));
+
+    let e = RSocketError::from(Status::new(Code::Unavailable, "busy"));
+    match e.kind() {
+        ErrorKind::Internal(code, msg) => {
+            assert_eq!(error::ERR_REJECTED, *code);
+            assert_eq!("busy", msg);
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn serve_grpc_calls_with_rsocket() {
+    let bridge = bridge();
+    let res = bridge
+        .handle(call(
+            "/helloworld.Greeter/SayHello",
+            vec![encode_message(b"Jeffsky")],
+        ))
+        .await;
+    assert_eq!("application/grpc", res.headers()["content-type"]);
+    let (messages, status) = read(res.into_body()).await;
+    assert_eq!(vec!["Hello Jeffsky!"], messages);
+    assert_eq!(Code::Ok, status.get_code());
+
+    let res = bridge
+        .handle(call("/helloworld.Greeter/Lots", vec![encode_message(b"x")]))
+        .await;
+    let (messages, status) = read(res.into_body()).await;
+    assert_eq!(vec!["1 x", "2 x"], messages);
+    assert_eq!(Code::Ok, status.get_code());
+
+    // messages may be split across chunks of the body.
+    let mut body = encode_message(b"a").to_vec();
+    body.extend_from_slice(&encode_message(b"b"));
+    let chunks = vec![
+        Bytes::from(body[..7].to_vec()),
+        Bytes::from(body[7..].to_vec()),
+    ];
+    let res = bridge
+        .handle(call("/helloworld.Greeter/Chat", chunks))
+        .await;
+    let (messages, status) = read(res.into_body()).await;
+    assert_eq!(vec!["a", "b"], messages);
+    assert_eq!(Code::Ok, status.get_code());
+}
+
+#[tokio::main]
+#[test]
+async fn map_rsocket_errors_to_status() {
+    let bridge = bridge();
+    let res = bridge
+        .handle(call(
+            "/helloworld.Greeter/SayHello",
+            vec![encode_message(b"")],
+        ))
+        .await;
+    let (messages, status) = read(res.into_body()).await;
+    assert!(messages.is_empty());
+    assert_eq!(Status::new(Code::InvalidArgument, "empty name é"), status);
+
+    let res = bridge
+        .handle(call("/helloworld.Greeter/Nope", vec![encode_message(b"x")]))
+        .await;
+    let (_, status) = read(res.into_body()).await;
+    assert_eq!(Code::Unknown, status.get_code());
+
+    let res = bridge
+        .handle(call("/helloworld.Greeter/SayHello", vec![]))
+        .await;
+    let (_, status) = read(res.into_body()).await;
+    assert_eq!(Code::InvalidArgument, status.get_code());
+
+    let req = Request::post("/helloworld.Greeter/SayHello")
+        .body(Box::pin(stream::empty()) as Flux<Bytes>)
+        .unwrap();
+    assert_eq!(415, bridge.handle(req).await.status().as_u16());
+}
+
+fn routed(route: &str, data: &str) -> Payload {
+    Payload::builder()
+        .set_data_utf8(data)
+        .metadata()
+        .route(route)
+        .bearer("t0ken")
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn serve_rsocket_requests_with_grpc() {
+    let responder = GrpcResponder::new(Echo);
+    let res = responder
+        .request_response(routed("helloworld.Greeter.SayHello", "x"))
+        .await
+        .unwrap();
+    assert_eq!(
+        Some("/helloworld.Greeter/SayHello Unary Bearer t0ken"),
+        res.data_utf8()
+    );
+
+    let results: Vec<_> = responder
+        .request_stream(routed("helloworld.Greeter.Lots", "x"))
+        .collect()
+        .await;
+    let results: Vec<_> = results
+        .iter()
+        .map(|it| it.as_ref().unwrap().data_utf8().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        vec!["/helloworld.Greeter/Lots ServerStreaming Bearer t0ken", "x"],
+        results
+    );
+
+    let reqs = stream::iter(vec![
+        Ok(routed("helloworld.Greeter.Chat", "a")),
+        Ok(Payload::from("b")),
+    ]);
+    let results: Vec<_> = responder.request_channel(Box::pin(reqs)).collect().await;
+    let results: Vec<_> = results
+        .iter()
+        .map(|it| it.as_ref().unwrap().data_utf8().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        vec!["/helloworld.Greeter/Chat Bidi Bearer t0ken", "a", "b"],
+        results
+    );
+
+    let e = responder
+        .request_response(routed("helloworld.Greeter.Fail", "x"))
+        .await
+        .unwrap_err();
+    assert!(e.to_string().contains("no such thing"));
+    let e = responder
+        .request_response(Payload::from("no route"))
+        .await
+        .unwrap_err();
+    match e.kind() {
+        ErrorKind::Internal(code, _) => assert_eq!(error::ERR_INVALID, *code),
+        other => panic!("unexpected error {:?}", other),
+    }
+}
@@ -0,0 +1,72 @@
+use rsocket_rust::error::{self, ErrorKind};
+use rsocket_rust::health::HealthCheck;
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::{LoopbackConnector, LoopbackServerTransport};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static SERVING: AtomicBool = AtomicBool::new(true);
+
+async fn connect(
+    connector: &LoopbackConnector,
+    metadata_mime_type: &str,
+) -> Client<DefaultSpawner> {
+    RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .metadata_mime_type(metadata_mime_type)
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn check_health_by_route() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| {
+                let health = HealthCheck::new().check(|| SERVING.load(Ordering::SeqCst));
+                Ok(Box::new(Router::new().handler(health)))
+            })
+            .serve(),
+    );
+    let cli = connect(&connector, mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0).await;
+    cli.health_check(Duration::from_secs(1)).await.unwrap();
+
+    SERVING.store(false, Ordering::SeqCst);
+    let e = cli.health_check(Duration::from_secs(1)).await.unwrap_err();
+    match e.kind() {
+        ErrorKind::Internal(code, msg) => {
+            assert_eq!(error::ERR_REJECTED, *code);
+            assert_eq!("NOT_SERVING", msg);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn check_health_by_keepalive() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(Router::new())))
+            .serve(),
+    );
+    // the server has no health route.
+    let cli = connect(&connector, mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0).await;
+    cli.health_check(Duration::from_secs(1)).await.unwrap();
+    // routes can not be sent without composite metadata.
+    let cli = connect(&connector, mime::APPLICATION_BINARY).await;
+    cli.health_check(Duration::from_secs(1)).await.unwrap();
+
+    let cli2 = cli.clone();
+    cli2.close();
+    assert!(cli.health_check(Duration::from_secs(1)).await.is_err());
+}
@@ -0,0 +1,114 @@
+use futures::channel::mpsc;
+use futures::{future, stream};
+use rsocket_rust::error::{self, RSocketError};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::time::Duration;
+use tokio::time;
+
+/// Never responds requests, responds streams with an item every 50ms.
+struct SlowRSocket;
+
+impl RSocket for SlowRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(future::pending())
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::unfold(0, |n| async move {
+            time::delay_for(Duration::from_millis(50)).await;
+            Some((Ok(Payload::from(format!("{}", n))), n + 1))
+        }))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+async fn next_frame(incoming: &mut mpsc::Receiver<Frame>) -> Frame {
+    time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn cancel_idle_requests() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(SlowRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .idle_timeout(Duration::from_millis(200))
+        .start()
+        .await
+        .unwrap();
+    let e = time::timeout(
+        Duration::from_secs(3),
+        cli.request_response(Payload::from("ping")),
+    )
+    .await
+    .unwrap()
+    .unwrap_err();
+    assert_eq!("ERROR(CANCELED): stream is idle", e.to_string());
+
+    // streams with frames flowing are kept.
+    let results: Vec<_> = cli
+        .request_stream(Payload::from("ping"))
+        .take(12)
+        .collect()
+        .await;
+    assert!(results.iter().all(|it| it.is_ok()));
+    assert_eq!(12, results.len());
+}
+
+#[tokio::main]
+#[test]
+async fn reject_idle_requests_of_peer() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .idle_timeout(Duration::from_millis(200))
+            .acceptor(|_setup, _socket| Ok(Box::new(SlowRSocket)))
+            .serve(),
+    );
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    sending
+        .send(frame::Setup::builder(0, 0).build())
+        .await
+        .unwrap();
+    sending
+        .send(frame::RequestResponse::builder(1, 0).build())
+        .await
+        .unwrap();
+    let frame = next_frame(&mut incoming).await;
+    assert_eq!(1, frame.get_stream_id());
+    match frame.get_body() {
+        Body::Error(v) => assert_eq!(error::ERR_CANCELED, v.get_code()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
@@ -0,0 +1,62 @@
+#[macro_use]
+extern crate log;
+
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+struct Student {
+    id: u32,
+    name: String,
+}
+
+#[tokio::main]
+#[test]
+async fn json_request_api() {
+    let addr = "127.0.0.1:7802";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|setup, _socket| {
+                info!("accept setup: {:?}", setup);
+                Ok(Box::new(EchoRSocket))
+            })
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .mime_type(
+            mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0,
+            mime::APPLICATION_JSON,
+        )
+        .start()
+        .await
+        .unwrap();
+    assert_eq!(mime::APPLICATION_JSON, cli.get_data_mime_type());
+
+    let student = Student {
+        id: 1,
+        name: String::from("Jeffsky"),
+    };
+    let res: Student = cli.request_response_json(&student).await.unwrap();
+    assert_eq!(student, res);
+
+    let mut results = cli.request_stream_json::<Student, Student>(&student);
+    let mut n = 0;
+    while let Some(res) = results.next().await {
+        assert_eq!(student, res.unwrap());
+        n += 1;
+    }
+    assert_eq!(3, n);
+
+    let res: Result<Vec<u32>, _> = cli.request_response_json(&student).await;
+    assert!(res.is_err());
+    cli.fire_and_forget_json(&student).await.unwrap();
+    cli.close();
+}
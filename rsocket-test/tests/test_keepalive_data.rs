@@ -0,0 +1,70 @@
+use bytes::Bytes;
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+
+type Received = Arc<Mutex<Vec<Bytes>>>;
+
+fn recorder(received: &Received) -> impl Fn(Bytes) + Send + Sync + 'static {
+    let received = received.clone();
+    move |data| received.lock().unwrap().push(data)
+}
+
+#[tokio::main]
+#[test]
+async fn exchange_custom_keepalive_data() {
+    let on_server: Received = Default::default();
+    let on_client: Received = Default::default();
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .keepalive_data(|| Bytes::from("clock=42"))
+            .on_keepalive(recorder(&on_server))
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .keepalive(Duration::from_millis(50), Duration::from_secs(30), 3)
+        .keepalive_data(|| Bytes::from("load=0.5"))
+        .on_keepalive(recorder(&on_client))
+        .start()
+        .await
+        .unwrap();
+    time::delay_for(Duration::from_millis(200)).await;
+
+    let rtt = cli.ping().await.unwrap();
+    assert!(rtt < Duration::from_secs(3));
+    assert!(cli.stats().get_keepalive_rtt().is_some());
+    let on_server = on_server.lock().unwrap();
+    assert!(on_server.len() >= 2);
+    assert!(on_server.iter().all(|it| it == &Bytes::from("load=0.5")));
+    let on_client = on_client.lock().unwrap();
+    assert!(on_client.len() >= 2);
+    assert!(on_client.iter().all(|it| it == &Bytes::from("clock=42")));
+}
+
+#[tokio::main]
+#[test]
+async fn echo_keepalive_data_by_default() {
+    let on_client: Received = Default::default();
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .keepalive_data(|| Bytes::from("load=0.5"))
+        .on_keepalive(recorder(&on_client))
+        .start()
+        .await
+        .unwrap();
+    cli.ping().await.unwrap();
+    assert_eq!(vec![Bytes::from("load=0.5")], *on_client.lock().unwrap());
+}
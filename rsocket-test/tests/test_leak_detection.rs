@@ -0,0 +1,105 @@
+use futures::future;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::sync::Mutex;
+use std::time::Duration;
+
+static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CaptureLogger;
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn && metadata.target() == "rsocket_rust::leaks"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            RECORDS.lock().unwrap().push(format!("{}", record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Never responds to requests with data "pending".
+struct PendingRSocket;
+
+impl RSocket for PendingRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        if req.data_utf8() == Some("pending") {
+            Box::pin(future::pending())
+        } else {
+            EchoRSocket.request_response(req)
+        }
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn detect_leaked_streams() {
+    log::set_logger(&CaptureLogger).unwrap();
+    log::set_max_level(LevelFilter::Warn);
+
+    let addr = "127.0.0.1:7809";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .leak_detection(Duration::from_millis(100))
+            .acceptor(|_setup, _socket| Ok(Box::new(PendingRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .leak_detection(Duration::from_millis(100))
+        .start()
+        .await
+        .unwrap();
+    cli.request_response(Payload::from("Hello World!"))
+        .await
+        .unwrap();
+    let mut results = cli.request_stream(Payload::from("Hello World!"));
+    while let Some(res) = results.next().await {
+        res.unwrap();
+    }
+    // the request is abandoned by the requester without CANCEL.
+    let res = tokio::time::timeout(
+        Duration::from_millis(50),
+        cli.request_response(Payload::from("pending")),
+    )
+    .await;
+    assert!(res.is_err());
+    tokio::time::delay_for(Duration::from_millis(500)).await;
+
+    let records = RECORDS.lock().unwrap();
+    // reported once by both requester and responder.
+    assert_eq!(2, records.len());
+    for it in records.iter() {
+        assert!(it.starts_with("possible leaked stream: sid=5, interaction=request_response"));
+        assert!(it.contains("created at:"));
+    }
+}
@@ -0,0 +1,270 @@
+mod common;
+
+use bytes::Bytes;
+use futures::channel::mpsc;
+use rsocket_rust::error;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{loopback, LoopbackServerTransport};
+use rsocket_rust::transport::LeasePolicy;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time;
+
+static REQUESTER: Mutex<Option<Box<dyn RSocket>>> = Mutex::new(None);
+static OVERLOADED: AtomicBool = AtomicBool::new(false);
+
+/// Connect a raw peer which negotiates leasing, returns the requester of the server once its
+/// first LEASE arrived.
+async fn connect_leasing() -> (
+    Box<dyn RSocket>,
+    mpsc::Receiver<Frame>,
+    tokio::sync::mpsc::Sender<Frame>,
+) {
+    let (mut incoming, sending) = common::serve_raw(
+        |tp| {
+            RSocketFactory::receive()
+                .transport(tp)
+                .lease(LeasePolicy::new(Duration::from_secs(60), 16))
+                .acceptor(|_setup, socket| {
+                    *REQUESTER.lock().unwrap() = Some(socket);
+                    Ok(Box::new(EchoRSocket))
+                })
+                .serve()
+        },
+        frame::Setup::builder(0, frame::FLAG_LEASE).build(),
+    )
+    .await;
+    let granted = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(granted.get_body_ref(), Body::Lease(_)));
+    let requester = REQUESTER.lock().unwrap().take();
+    (requester.unwrap(), incoming, sending)
+}
+
+fn lease(ttl: u32, number_of_requests: u32) -> Frame {
+    frame::Lease::builder(0, 0)
+        .set_ttl(ttl)
+        .set_number_of_requests(number_of_requests)
+        .build()
+}
+
+async fn request(requester: &dyn RSocket) -> String {
+    match requester.request_response(Payload::from("ping")).await {
+        Ok(_) => String::from("ok"),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn requests_under_lease() {
+    let (requester, mut incoming, mut sending) = connect_leasing().await;
+    assert_eq!(
+        "ERROR(REJECTED): no lease granted",
+        request(&*requester).await
+    );
+
+    sending.send(lease(10_000, 1)).await.unwrap();
+    time::delay_for(Duration::from_millis(50)).await;
+    let allowed = tokio::spawn(async move {
+        let res = request(&*requester).await;
+        (requester, res)
+    });
+    let req = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(req.get_body_ref(), Body::RequestResponse(_)));
+    let res = frame::Payload::builder(req.get_stream_id(), frame::FLAG_NEXT | frame::FLAG_COMPLETE)
+        .set_data(Bytes::from("pong"))
+        .build();
+    sending.send(res).await.unwrap();
+    let (requester, res) = allowed.await.unwrap();
+    assert_eq!("ok", res);
+    assert_eq!(
+        "ERROR(REJECTED): lease exhausted",
+        request(&*requester).await
+    );
+
+    sending.send(lease(20, 5)).await.unwrap();
+    time::delay_for(Duration::from_millis(100)).await;
+    assert_eq!("ERROR(REJECTED): lease expired", request(&*requester).await);
+}
+
+#[tokio::main]
+#[test]
+async fn reject_leasing_without_policy() {
+    let (mut incoming, _sending) = common::serve_raw(
+        |tp| {
+            RSocketFactory::receive()
+                .transport(tp)
+                .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+                .serve()
+        },
+        frame::Setup::builder(0, frame::FLAG_LEASE).build(),
+    )
+    .await;
+    let rejected = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    match rejected.get_body() {
+        Body::Error(e) => assert_eq!(error::ERR_UNSUPPORTED_SETUP, e.get_code()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
+
+#[test]
+#[should_panic(expected = "lease ttl must be positive")]
+fn reject_lease_of_zero_ttl() {
+    LeasePolicy::new(Duration::from_secs(0), 8);
+}
+
+#[tokio::main]
+#[test]
+async fn no_lease_stats_without_leasing() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+    cli.request_response(Payload::from("ping")).await.unwrap();
+    assert!(cli.stats().get_lease().is_none());
+}
+
+#[tokio::main]
+#[test]
+async fn client_requests_leasing() {
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let cli = RSocketFactory::connect()
+        .transport(client)
+        .lease()
+        .start()
+        .await
+        .unwrap();
+    let setup = incoming.next().await.unwrap();
+    assert!(matches!(setup.get_body_ref(), Body::Setup(_)));
+    assert_ne!(0, setup.get_flag() & frame::FLAG_LEASE);
+    assert!(cli.params().is_lease_enabled());
+
+    // requests fail fast until the server grants a lease.
+    assert_eq!("ERROR(REJECTED): no lease granted", request(&cli).await);
+    sending.send(lease(10_000, 1)).await.unwrap();
+    time::delay_for(Duration::from_millis(50)).await;
+    let requester = cli.clone();
+    let allowed = tokio::spawn(async move { request(&requester).await });
+    let req = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(req.get_body_ref(), Body::RequestResponse(_)));
+    let res = frame::Payload::builder(req.get_stream_id(), frame::FLAG_NEXT | frame::FLAG_COMPLETE)
+        .set_data(Bytes::from("pong"))
+        .build();
+    sending.send(res).await.unwrap();
+    assert_eq!("ok", allowed.await.unwrap());
+    let stats = cli.stats();
+    let leases = stats.get_lease().unwrap();
+    assert_eq!(1, leases.get_leases_granted());
+    assert_eq!(1, leases.get_requests_allowed());
+    assert_eq!(1, leases.get_requests_rejected());
+
+    // the client grants no leases to the server.
+    let req = frame::RequestResponse::builder(2, 0)
+        .set_data(Bytes::from("ping"))
+        .build();
+    sending.send(req).await.unwrap();
+    let rejected = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(2, rejected.get_stream_id());
+    match rejected.get_body() {
+        Body::Error(e) => assert_eq!(error::ERR_REJECTED, e.get_code()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn server_sheds_load_by_leases() {
+    let (server, connector) = LoopbackServerTransport::new();
+    let policy = LeasePolicy::new(Duration::from_millis(200), 8).load(|| {
+        if OVERLOADED.load(Ordering::SeqCst) {
+            1.0
+        } else {
+            0.5
+        }
+    });
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .lease(policy)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    let setup = frame::Setup::builder(0, frame::FLAG_LEASE).build();
+    sending.send(setup).await.unwrap();
+
+    // half loaded, the lease allows half of the requests.
+    let granted = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    match granted.get_body() {
+        Body::Lease(v) => {
+            assert_eq!(200, v.get_ttl());
+            assert_eq!(4, v.get_number_of_requests());
+        }
+        other => panic!("unexpected frame: {:?}", other),
+    }
+    let req = frame::RequestResponse::builder(1, 0)
+        .set_data(Bytes::from("ping"))
+        .build();
+    sending.send(req).await.unwrap();
+    let res = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(res.get_body_ref(), Body::Payload(_)));
+
+    // overloaded, the lease is withheld and the current one lapses.
+    OVERLOADED.store(true, Ordering::SeqCst);
+    time::delay_for(Duration::from_millis(400)).await;
+    while let Ok(Some(frame)) = time::timeout(Duration::from_millis(10), incoming.next()).await {
+        assert!(matches!(frame.get_body_ref(), Body::Lease(_)));
+    }
+    let req = frame::RequestResponse::builder(3, 0)
+        .set_data(Bytes::from("ping"))
+        .build();
+    sending.send(req).await.unwrap();
+    let rejected = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(3, rejected.get_stream_id());
+    match rejected.get_body() {
+        Body::Error(e) => assert_eq!(error::ERR_REJECTED, e.get_code()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
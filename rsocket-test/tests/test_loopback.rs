@@ -0,0 +1,70 @@
+use futures::stream;
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{pair, Expectation, LoopbackServerTransport, MockRSocket};
+use std::time::Duration;
+
+#[tokio::main]
+#[test]
+async fn echo_over_pair() {
+    let cli = pair(EchoRSocket).await;
+    let res = cli
+        .request_response(Payload::from("Hello World!"))
+        .await
+        .unwrap();
+    assert_eq!(Some("Hello World!"), res.data_utf8());
+
+    let n = cli
+        .request_stream(Payload::from("Hello World!"))
+        .map(|it| it.unwrap())
+        .collect::<Vec<_>>()
+        .await
+        .len();
+    assert_eq!(3, n);
+
+    let reqs = stream::iter((0..5).map(|i| Ok(Payload::from(if i % 2 == 0 { "a" } else { "b" }))));
+    let echoed = cli
+        .request_channel(Box::pin(reqs))
+        .map(|it| it.unwrap().data_utf8().unwrap().to_string())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(vec!["a", "b", "a", "b", "a"], echoed);
+}
+
+#[tokio::main]
+#[test]
+async fn errors_over_pair() {
+    let mock = MockRSocket::new();
+    mock.expect(Expectation::request_response().respond_error("boom"));
+    let cli = pair(mock.clone()).await;
+    let res = cli.request_response(Payload::from("Hello World!")).await;
+    assert!(res.is_err());
+    cli.fire_and_forget(Payload::from("unexpected")).await;
+    tokio::time::delay_for(Duration::from_millis(50)).await;
+    assert_eq!(1, mock.count("request_response"));
+    assert_eq!(1, mock.count("fire_and_forget"));
+}
+
+#[tokio::main]
+#[test]
+async fn serve_loopback_transport() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    for _ in 0..3 {
+        let cli = RSocketFactory::connect()
+            .transport(connector.connect().unwrap())
+            .start()
+            .await
+            .unwrap();
+        let res = cli
+            .request_response(Payload::from("Hello World!"))
+            .await
+            .unwrap();
+        assert_eq!(Some("Hello World!"), res.data_utf8());
+    }
+}
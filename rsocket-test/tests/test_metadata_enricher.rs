@@ -0,0 +1,103 @@
+use bytes::{Bytes, BytesMut};
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::extension::{CompositeMetadata, Metadata};
+use rsocket_rust::interceptor::MetadataEnricher;
+use rsocket_rust::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const TENANT: &str = "application/x.tenant";
+const REQUEST_ID: &str = "application/x.request-id";
+
+fn entries_of(res: &Payload) -> Vec<(String, String)> {
+    let mut bf = BytesMut::from(res.metadata().as_ref().unwrap().as_ref());
+    CompositeMetadata::decode(&mut bf)
+        .unwrap()
+        .iter()
+        .map(|it| {
+            let value = String::from_utf8(it.get_payload().to_vec()).unwrap();
+            (it.get_mime().clone(), value)
+        })
+        .collect()
+}
+
+fn entry(mime: &str, value: &str) -> (String, String) {
+    (String::from(mime), String::from(value))
+}
+
+fn enricher() -> MetadataEnricher<EchoRSocket> {
+    let ids = Arc::new(AtomicUsize::new(0));
+    MetadataEnricher::new(EchoRSocket, move |composite| {
+        let id = ids.fetch_add(1, Ordering::SeqCst);
+        composite.set(Metadata::new(String::from(TENANT), Bytes::from("tenant_1")));
+        composite.push(Metadata::new(
+            String::from(REQUEST_ID),
+            Bytes::from(format!("req_{}", id)),
+        ));
+    })
+}
+
+#[tokio::main]
+#[test]
+async fn enrich_requests() {
+    let requester = enricher();
+    let res = requester
+        .request_response(Payload::from("Hello World!"))
+        .await
+        .unwrap();
+    assert_eq!(
+        vec![entry(TENANT, "tenant_1"), entry(REQUEST_ID, "req_0")],
+        entries_of(&res)
+    );
+
+    // entries set by the user are kept or overridden.
+    let req = Payload::builder()
+        .set_data_utf8("Hello World!")
+        .metadata()
+        .custom(TENANT, "tenant_2")
+        .route("orders")
+        .build();
+    let mut results = requester.request_stream(req);
+    let res = results.next().await.unwrap().unwrap();
+    let entries = entries_of(&res);
+    assert_eq!(3, entries.len());
+    assert!(entries.contains(&entry(TENANT, "tenant_1")));
+    assert!(entries.contains(&entry(REQUEST_ID, "req_1")));
+
+    // only the first payload of a channel opens the request.
+    let reqs: Vec<Result<Payload, RSocketError>> =
+        vec![Ok(Payload::from("first")), Ok(Payload::from("second"))];
+    let mut results = requester.request_channel(Box::pin(stream::iter(reqs)));
+    let first = results.next().await.unwrap().unwrap();
+    assert_eq!(
+        vec![entry(TENANT, "tenant_1"), entry(REQUEST_ID, "req_2")],
+        entries_of(&first)
+    );
+    let second = results.next().await.unwrap().unwrap();
+    assert!(second.metadata().is_none());
+}
+
+#[tokio::main]
+#[test]
+async fn keep_non_composite_metadata() {
+    let requester = enricher();
+    let res = requester
+        .request_response(Payload::from_utf8_with_metadata("data", "plain"))
+        .await
+        .unwrap();
+    assert_eq!(Some("plain"), res.metadata_utf8());
+}
+
+#[test]
+fn remove_composite_entries() {
+    let mut composite = CompositeMetadata::builder()
+        .push(TENANT, "a")
+        .push(REQUEST_ID, "b")
+        .push(TENANT, "c")
+        .build();
+    let removed = composite.remove(TENANT).unwrap();
+    assert_eq!(&Bytes::from("a"), removed.get_payload());
+    assert_eq!(1, composite.iter().count());
+    assert!(composite.remove(TENANT).is_none());
+}
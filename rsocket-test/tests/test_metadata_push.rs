@@ -0,0 +1,148 @@
+mod common;
+
+use bytes::Bytes;
+use futures::channel::mpsc;
+use rsocket_rust::error;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+
+static RESPONDER_PUSHED: AtomicUsize = AtomicUsize::new(0);
+
+struct Responder;
+
+impl RSocket for Responder {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        RESPONDER_PUSHED.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, error::RSocketError>> {
+        Box::pin(async move { Ok(req) })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, error::RSocketError>> {
+        Box::pin(futures::stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, error::RSocketError>>,
+    ) -> Flux<Result<Payload, error::RSocketError>> {
+        reqs
+    }
+}
+
+async fn wait_until<F: Fn() -> bool>(cond: F) {
+    for _ in 0..300 {
+        if cond() {
+            return;
+        }
+        time::delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("condition not reached");
+}
+
+type Pushed = Arc<Mutex<Vec<String>>>;
+
+/// Connect a client recording METADATA_PUSH to a raw peer.
+async fn connect_recording(
+    pushed: Pushed,
+) -> (mpsc::Receiver<Frame>, tokio::sync::mpsc::Sender<Frame>) {
+    let (cli, incoming, sending) = common::connect_raw(|tp| {
+        RSocketFactory::connect()
+            .transport(tp)
+            .on_metadata_push(move |req: Payload| {
+                let metadata = req.metadata_utf8().unwrap_or_default().to_string();
+                pushed.lock().unwrap().push(metadata);
+                async {}
+            })
+            .max_metadata_push_size(8)
+            .start()
+    })
+    .await;
+    // the client is kept by its connection.
+    drop(cli);
+    (incoming, sending)
+}
+
+fn metadata_push(sid: u32, metadata: &'static str) -> Frame {
+    frame::MetadataPush::builder(sid, 0)
+        .set_metadata(Bytes::from(metadata))
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn handle_metadata_push_with_hook() {
+    let (server, connector) = LoopbackServerTransport::new();
+    let pushed: Pushed = Arc::new(Mutex::new(vec![]));
+    let recorder = pushed.clone();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(Responder)))
+            .on_metadata_push(move |req: Payload| {
+                let recorder = recorder.clone();
+                async move {
+                    let metadata = req.metadata_utf8().unwrap_or_default().to_string();
+                    recorder.lock().unwrap().push(metadata);
+                }
+            })
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+    cli.metadata_push(Payload::builder().set_metadata_utf8("config").build())
+        .await;
+    wait_until(|| pushed.lock().unwrap().len() == 1).await;
+    assert_eq!(vec!["config"], *pushed.lock().unwrap());
+    // other requests still reach the responder.
+    let res = cli.request_response(Payload::from("ping")).await.unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+    assert_eq!(0, RESPONDER_PUSHED.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn drop_large_metadata_push() {
+    let pushed: Pushed = Arc::new(Mutex::new(vec![]));
+    let (_incoming, mut sending) = connect_recording(pushed.clone()).await;
+    sending
+        .send(metadata_push(0, "too large metadata"))
+        .await
+        .unwrap();
+    sending.send(metadata_push(0, "small")).await.unwrap();
+    wait_until(|| !pushed.lock().unwrap().is_empty()).await;
+    time::delay_for(Duration::from_millis(20)).await;
+    assert_eq!(vec!["small"], *pushed.lock().unwrap());
+}
+
+#[tokio::main]
+#[test]
+async fn reject_metadata_push_on_stream() {
+    let pushed: Pushed = Arc::new(Mutex::new(vec![]));
+    let (mut incoming, mut sending) = connect_recording(pushed.clone()).await;
+    sending.send(metadata_push(3, "small")).await.unwrap();
+    let rejected = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(0, rejected.get_stream_id());
+    match rejected.get_body() {
+        Body::Error(e) => assert_eq!(error::ERR_CONN_FAILED, e.get_code()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+    assert!(pushed.lock().unwrap().is_empty());
+}
@@ -0,0 +1,60 @@
+extern crate rsocket_rust;
+
+use bytes::Bytes;
+use rsocket_rust::extension::{CompositeMetadata, MetadataRegistry, RoutingMetadata};
+use rsocket_rust::mime;
+
+#[derive(Debug, PartialEq)]
+struct TenantId(u32);
+
+const TENANT_MIME: &str = "application/x.tenant-id";
+
+#[test]
+fn metadata_registry_custom() {
+    let mut registry = MetadataRegistry::default();
+    registry.register(
+        TENANT_MIME,
+        |v: &TenantId| Bytes::from(v.0.to_be_bytes().to_vec()),
+        |b| {
+            if b.len() != 4 {
+                return Err("invalid tenant id".into());
+            }
+            let mut raw = [0u8; 4];
+            raw.copy_from_slice(&b[..]);
+            Ok(TenantId(u32::from_be_bytes(raw)))
+        },
+    );
+    assert!(registry.encode(TENANT_MIME, &"not a tenant").is_err());
+    assert!(registry
+        .encode("application/x.unknown", &TenantId(1))
+        .is_err());
+
+    let routing = RoutingMetadata::builder().push_str("orders.create").build();
+    let cm = CompositeMetadata::builder()
+        .push_metadata(registry.encode(TENANT_MIME, &TenantId(42)).unwrap())
+        .push_metadata(
+            registry
+                .encode(mime::MESSAGE_X_RSOCKET_ROUTING_V0, &routing)
+                .unwrap(),
+        )
+        .push("application/x.opaque", b"raw")
+        .build();
+
+    let decoded = registry.decode_all(&cm).unwrap();
+    assert_eq!(3, decoded.len());
+    assert_eq!(Some(&TenantId(42)), decoded[0].downcast_ref::<TenantId>());
+    let tags = decoded[1]
+        .downcast_ref::<RoutingMetadata>()
+        .unwrap()
+        .get_tags();
+    assert_eq!(&vec!["orders.create".to_string()], tags);
+    assert!(!decoded[2].is_typed());
+    assert_eq!(b"raw", decoded[2].get_raw().as_ref());
+
+    let broken = CompositeMetadata::builder()
+        .push(TENANT_MIME, b"xx")
+        .build();
+    assert!(registry.decode_all(&broken).is_err());
+    assert!(registry.unregister(TENANT_MIME));
+    assert!(!registry.decode_all(&broken).unwrap()[0].is_typed());
+}
@@ -0,0 +1,177 @@
+#[macro_use]
+extern crate log;
+
+use metrics::{
+    Counter, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+};
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Default)]
+struct TestRecorder {
+    counters: Arc<Mutex<HashMap<String, Arc<AtomicU64>>>>,
+    gauges: Arc<Mutex<HashMap<String, Arc<AtomicU64>>>>,
+    histograms: Arc<Mutex<HashMap<String, Arc<Samples>>>>,
+}
+
+#[derive(Default)]
+struct Samples(AtomicU64);
+
+impl HistogramFn for Samples {
+    fn record(&self, _: f64) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl TestRecorder {
+    fn counter(&self, key: &str) -> u64 {
+        match self.counters.lock().unwrap().get(key) {
+            Some(v) => v.load(Ordering::SeqCst),
+            None => 0,
+        }
+    }
+
+    fn gauge(&self, key: &str) -> f64 {
+        match self.gauges.lock().unwrap().get(key) {
+            Some(v) => f64::from_bits(v.load(Ordering::SeqCst)),
+            None => 0.0,
+        }
+    }
+
+    fn samples(&self, key: &str) -> u64 {
+        match self.histograms.lock().unwrap().get(key) {
+            Some(v) => v.0.load(Ordering::SeqCst),
+            None => 0,
+        }
+    }
+}
+
+fn key_of(key: &Key) -> String {
+    let mut labels = key
+        .labels()
+        .map(|it| format!("{}={}", it.key(), it.value()))
+        .collect::<Vec<_>>();
+    labels.sort();
+    format!("{}{{{}}}", key.name(), labels.join(","))
+}
+
+impl Recorder for TestRecorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        let mut counters = self.counters.lock().unwrap();
+        Counter::from_arc(counters.entry(key_of(key)).or_default().clone())
+    }
+
+    fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+        let mut gauges = self.gauges.lock().unwrap();
+        Gauge::from_arc(gauges.entry(key_of(key)).or_default().clone())
+    }
+
+    fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+        let mut histograms = self.histograms.lock().unwrap();
+        Histogram::from_arc(histograms.entry(key_of(key)).or_default().clone())
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn connection_metrics() {
+    let recorder = TestRecorder::default();
+    metrics::set_global_recorder(recorder.clone()).unwrap();
+
+    let addr = "127.0.0.1:7804";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|setup, _socket| {
+                info!("accept setup: {:?}", setup);
+                Ok(Box::new(EchoRSocket))
+            })
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap();
+    let req = Payload::builder()
+        .set_data_utf8("Hello World!")
+        .metadata()
+        .route("echo")
+        .end()
+        .build();
+    cli.request_response(req).await.unwrap();
+    let mut results = cli.request_stream(Payload::from("Hello World!"));
+    while let Some(res) = results.next().await {
+        res.unwrap();
+    }
+    cli.fire_and_forget(Payload::from("Hello World!")).await;
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    assert_eq!(1.0, recorder.gauge("rsocket_connections{side=client}"));
+    assert_eq!(1.0, recorder.gauge("rsocket_connections{side=server}"));
+    for side in &["client", "server"] {
+        assert_eq!(
+            0.0,
+            recorder.gauge(&format!("rsocket_active_streams{{side={}}}", side))
+        );
+    }
+    for interaction in &["request_response", "request_stream", "fire_and_forget"] {
+        assert_eq!(
+            1,
+            recorder.counter(&format!(
+                "rsocket_requests_total{{interaction={},role=requester,side=client}}",
+                interaction
+            ))
+        );
+        assert_eq!(
+            1,
+            recorder.counter(&format!(
+                "rsocket_requests_total{{interaction={},role=responder,side=server}}",
+                interaction
+            ))
+        );
+    }
+    assert_eq!(
+        1,
+        recorder.counter("rsocket_frames_total{direction=outbound,side=client,type=SETUP}")
+    );
+    assert_eq!(
+        1,
+        recorder.counter("rsocket_frames_total{direction=inbound,side=server,type=SETUP}")
+    );
+    let sent = recorder.counter("rsocket_bytes_total{direction=outbound,side=client}");
+    assert!(sent > 0);
+    assert_eq!(
+        sent,
+        recorder.counter("rsocket_bytes_total{direction=inbound,side=server}")
+    );
+    for side in &["requester", "responder"] {
+        assert_eq!(
+            1,
+            recorder.samples(&format!(
+                "rsocket_request_duration_seconds{{interaction=request_response,outcome=ok,route=echo,side={}}}",
+                side
+            ))
+        );
+        assert_eq!(
+            1,
+            recorder.samples(&format!(
+                "rsocket_request_duration_seconds{{interaction=request_stream,outcome=ok,route=,side={}}}",
+                side
+            ))
+        );
+    }
+}
@@ -0,0 +1,57 @@
+extern crate rsocket_rust;
+
+use bytes::{Bytes, BytesMut};
+use rsocket_rust::extension::{CompositeMetadata, MimeTypeMetadata};
+use rsocket_rust::mime;
+use rsocket_rust::utils::Writeable;
+
+#[test]
+fn mime_type_metadata_codec() {
+    for it in &["application/json", "application/x.custom+json"] {
+        let m = MimeTypeMetadata::new(*it).unwrap();
+        let mut bf = BytesMut::new();
+        m.write_to(&mut bf);
+        assert_eq!(m.len(), bf.len());
+        let m2 = MimeTypeMetadata::decode(&mut bf).unwrap();
+        assert_eq!(*it, m2.get_mime());
+    }
+    // well-known MIME is encoded in a single byte.
+    let mut bf = BytesMut::new();
+    MimeTypeMetadata::new(mime::APPLICATION_JSON)
+        .unwrap()
+        .write_to(&mut bf);
+    assert_eq!(&[0x85], bf.as_ref());
+}
+
+#[test]
+fn mime_type_metadata_broken() {
+    let mut bf = BytesMut::from(&[0x10, b'a'][..]);
+    assert!(MimeTypeMetadata::decode(&mut bf).is_err());
+    let mut bf = BytesMut::new();
+    assert!(MimeTypeMetadata::decode(&mut bf).is_err());
+}
+
+#[test]
+fn mime_type_metadata_invalid_length() {
+    assert!(MimeTypeMetadata::new("").is_err());
+    assert!(MimeTypeMetadata::new("a".repeat(129)).is_err());
+    assert!(MimeTypeMetadata::new("a".repeat(128)).is_ok());
+}
+
+#[test]
+fn mime_type_metadata_from_composite() {
+    let cm = CompositeMetadata::builder()
+        .push(mime::TEXT_PLAIN, b"Hello World!")
+        .push(
+            mime::MESSAGE_X_RSOCKET_MIME_TYPE_V0,
+            Bytes::from(MimeTypeMetadata::new(mime::APPLICATION_CBOR).unwrap()),
+        )
+        .build();
+    let found = MimeTypeMetadata::from_composite(&cm).unwrap().unwrap();
+    assert_eq!(mime::APPLICATION_CBOR, found.get_mime());
+
+    let cm = CompositeMetadata::builder()
+        .push(mime::TEXT_PLAIN, b"Hello World!")
+        .build();
+    assert!(MimeTypeMetadata::from_composite(&cm).unwrap().is_none());
+}
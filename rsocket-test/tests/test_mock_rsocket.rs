@@ -0,0 +1,88 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{Expectation, MockRSocket};
+
+fn routed(route: &str, data: &str) -> Payload {
+    Payload::builder()
+        .set_data_utf8(data)
+        .metadata()
+        .route(route)
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn scripted_responses() {
+    let mock = MockRSocket::new();
+    mock.expect(
+        Expectation::request_response()
+            .route("orders.get")
+            .data("42")
+            .respond(Payload::from("order 42"))
+            .times(1),
+    )
+    .expect(
+        Expectation::request_response()
+            .route("orders.get")
+            .respond_error("no such order"),
+    )
+    .expect(
+        Expectation::request_stream()
+            .route("orders.list")
+            .respond_stream(vec![Payload::from("order 1"), Payload::from("order 2")]),
+    )
+    .expect(
+        Expectation::request_channel()
+            .matching(|req| req.data_utf8() == Some("first"))
+            .respond_stream_with(|req| vec![Ok(req)]),
+    )
+    .expect(Expectation::fire_and_forget().times(2));
+
+    let res = mock
+        .request_response(routed("orders.get", "42"))
+        .await
+        .unwrap();
+    assert_eq!(Some("order 42"), res.data_utf8());
+    // the first expectation is used up.
+    assert!(mock
+        .request_response(routed("orders.get", "42"))
+        .await
+        .is_err());
+
+    let items = mock
+        .request_stream(routed("orders.list", ""))
+        .map(|it| it.unwrap().data_utf8().unwrap().to_string())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(vec!["order 1", "order 2"], items);
+
+    let reqs = futures::stream::iter(vec![Ok(Payload::from("first")), Ok(Payload::from("x"))]);
+    let mut results = mock.request_channel(Box::pin(reqs));
+    let echoed = results.next().await.unwrap().unwrap();
+    assert_eq!(Some("first"), echoed.data_utf8());
+    assert!(results.next().await.is_none());
+
+    mock.fire_and_forget(Payload::from("a")).await;
+    mock.fire_and_forget(Payload::from("b")).await;
+
+    assert_eq!(2, mock.count("request_response"));
+    assert_eq!(6, mock.calls().len());
+    assert!(mock.calls().iter().all(|it| it.is_matched()));
+    mock.verify();
+}
+
+#[tokio::main]
+#[test]
+async fn reject_unexpected_requests() {
+    let mock = MockRSocket::new();
+    mock.expect(Expectation::request_response().route("known"));
+    let res = mock.request_response(routed("unknown", "")).await;
+    assert!(res.is_err());
+    let mut results = mock.request_stream(Payload::from("Hello World!"));
+    assert!(results.next().await.unwrap().is_err());
+
+    let calls = mock.calls();
+    assert_eq!("request_response", calls[0].get_interaction());
+    assert!(!calls[0].is_matched());
+    let verified = std::panic::catch_unwind(|| mock.verify());
+    assert!(verified.is_err());
+}
@@ -0,0 +1,117 @@
+use opentelemetry::trace::{SpanKind, Status};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+use opentelemetry_sdk::trace::TracerProvider;
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::interceptor::{OtelExtractor, OtelInjector};
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::time::Duration;
+use tokio::time;
+
+/// Fails every request.
+struct Failing;
+
+impl RSocket for Failing {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let e = RSocketError::from(ErrorKind::Internal(error::ERR_INVALID, "boom".into()));
+        Box::pin(async move { Err(e) })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+fn routed(route: &str) -> Payload {
+    Payload::builder()
+        .set_data_utf8("ping")
+        .metadata()
+        .route(route)
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn trace_requests_across_connection() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = TracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    global::set_tracer_provider(provider);
+
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| {
+                let router = Router::new()
+                    .route("echo", EchoRSocket)
+                    .route("fail", Failing);
+                Ok(Box::new(OtelExtractor::new(router)))
+            })
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .metadata_mime_type(rsocket_rust::mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0)
+        .start()
+        .await
+        .unwrap();
+    let requester = OtelInjector::new(cli);
+    let res = requester.request_response(routed("echo")).await.unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+    assert!(requester.request_response(routed("fail")).await.is_err());
+    time::delay_for(Duration::from_millis(100)).await;
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let find = |route: &str, kind: SpanKind| {
+        spans
+            .iter()
+            .find(|it| it.name == route && it.span_kind == kind)
+            .unwrap_or_else(|| panic!("missing span {} {:?}", route, kind))
+    };
+    // the server span is a child of the client span.
+    let client = find("echo", SpanKind::Client);
+    let server = find("echo", SpanKind::Server);
+    assert_eq!(
+        client.span_context.trace_id(),
+        server.span_context.trace_id()
+    );
+    assert_eq!(client.span_context.span_id(), server.parent_span_id);
+    for it in [client, server].iter() {
+        assert!(it
+            .attributes
+            .contains(&KeyValue::new("rpc.system", "rsocket")));
+        assert!(it.attributes.contains(&KeyValue::new("rpc.method", "echo")));
+        assert!(it
+            .attributes
+            .contains(&KeyValue::new("rsocket.interaction", "request_response")));
+        assert!(it
+            .attributes
+            .contains(&KeyValue::new("rsocket.outcome", "ok")));
+    }
+
+    let failed = find("fail", SpanKind::Client);
+    assert!(matches!(failed.status, Status::Error { .. }));
+    assert!(failed.attributes.contains(&KeyValue::new(
+        "rsocket.error_code",
+        i64::from(error::ERR_INVALID)
+    )));
+}
@@ -0,0 +1,38 @@
+use rsocket_rust::frame::Version;
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::time::Duration;
+
+#[tokio::main]
+#[test]
+async fn read_negotiated_params() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .mime_type(
+            mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0,
+            mime::APPLICATION_JSON,
+        )
+        .keepalive(Duration::from_secs(5), Duration::from_secs(30), 3)
+        .start()
+        .await
+        .unwrap();
+    let params = cli.params();
+    assert_eq!(Version::default(), params.get_version());
+    assert_eq!(mime::APPLICATION_JSON, params.get_data_mime_type());
+    assert_eq!(
+        mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0,
+        params.get_metadata_mime_type()
+    );
+    assert_eq!(Duration::from_secs(5), params.get_keepalive_interval());
+    assert_eq!(Duration::from_secs(90), params.get_max_lifetime());
+    assert!(!params.is_lease_enabled());
+    assert!(!params.is_resume_enabled());
+}
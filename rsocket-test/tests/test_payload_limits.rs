@@ -0,0 +1,96 @@
+use futures::stream;
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::{LoopbackConnector, LoopbackServerTransport};
+use rsocket_rust::transport::{PayloadLimits, SizeLimit};
+use std::time::Duration;
+use tokio::time;
+
+fn serve() -> LoopbackConnector {
+    let (server, connector) = LoopbackServerTransport::new();
+    let limits = PayloadLimits::new()
+        .setup(SizeLimit::new().max_data(8))
+        .request_response(SizeLimit::new().max_data(4))
+        .stream(SizeLimit::new().max_data(64).max_metadata(4));
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .payload_limits(limits)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    connector
+}
+
+async fn connect(connector: &LoopbackConnector, setup: &'static str) -> Client<DefaultSpawner> {
+    RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .setup(Payload::from(setup))
+        .start()
+        .await
+        .unwrap()
+}
+
+fn assert_rejected(e: RSocketError, expected: &str) {
+    match e.kind() {
+        ErrorKind::Internal(code, msg) => {
+            assert_eq!(error::ERR_REJECTED, *code);
+            assert!(msg.contains(expected), "{}", msg);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn reject_oversized_payloads_per_interaction() {
+    let connector = serve();
+    let cli = connect(&connector, "hello").await;
+
+    let e = cli
+        .request_response(Payload::from("hello"))
+        .await
+        .unwrap_err();
+    assert_rejected(e, "data of 5 bytes exceeds 4");
+    let res = cli.request_response(Payload::from("hi")).await.unwrap();
+    assert_eq!(Some("hi"), res.data_utf8());
+
+    // streams have their own limits.
+    let mut results = cli.request_stream(Payload::from("hello"));
+    assert_eq!(
+        Some("hello"),
+        results.next().await.unwrap().unwrap().data_utf8()
+    );
+    let mut results = cli.request_stream(Payload::from(("hello", "route")));
+    assert_rejected(
+        results.next().await.unwrap().unwrap_err(),
+        "metadata of 5 bytes exceeds 4",
+    );
+
+    let reqs = stream::iter(vec![
+        Ok(Payload::from("first")),
+        Ok(Payload::builder().set_data_utf8(&"x".repeat(65)).build()),
+    ]);
+    // the echo of the first payload may come before or after the rejection.
+    let mut results = cli.request_channel(Box::pin(reqs));
+    let e = loop {
+        match results.next().await.unwrap() {
+            Ok(res) => assert_eq!(Some("first"), res.data_utf8()),
+            Err(e) => break e,
+        }
+    };
+    assert_rejected(e, "data of 65 bytes exceeds 64");
+}
+
+#[tokio::main]
+#[test]
+async fn reject_oversized_setup() {
+    let connector = serve();
+    let rejected = connect(&connector, "0123456789").await;
+    time::delay_for(Duration::from_millis(50)).await;
+    assert!(rejected.is_closed());
+    let accepted = connect(&connector, "01234567").await;
+    let res = accepted.request_response(Payload::from("hi")).await;
+    assert_eq!(Some("hi"), res.unwrap().data_utf8());
+}
@@ -0,0 +1,56 @@
+extern crate rsocket_rust;
+
+use bytes::{Bytes, BytesMut};
+use rsocket_rust::extension::{AuthMetadata, CompositeMetadata, MetadataRegistry, RoutingMetadata};
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::Writeable;
+
+#[test]
+fn auth_metadata_codec() {
+    let auths = vec![
+        AuthMetadata::simple("user", "secret"),
+        AuthMetadata::bearer("my_token"),
+        AuthMetadata::custom("x.custom", Bytes::from("payload")),
+    ];
+    for auth in auths {
+        let mut bf = BytesMut::new();
+        auth.write_to(&mut bf);
+        assert_eq!(auth.len(), bf.len());
+        assert_eq!(auth, AuthMetadata::decode(&mut bf).unwrap());
+    }
+    let mut bf = BytesMut::from(&[0x80, 0x00, 0x10, b'a'][..]);
+    assert!(AuthMetadata::decode(&mut bf).is_err());
+}
+
+#[test]
+fn payload_metadata_builder() {
+    let req = Payload::builder()
+        .set_data_utf8("Hello World!")
+        .metadata()
+        .route("orders.create")
+        .route("orders.v2")
+        .bearer("my_token")
+        .mime_type(mime::APPLICATION_JSON)
+        .unwrap()
+        .custom("application/x.tenant", "tenant_1")
+        .build();
+    assert_eq!("Hello World!", req.data().as_ref().unwrap());
+
+    let mut bf = BytesMut::from(req.metadata().as_ref().unwrap().as_ref());
+    let composite = CompositeMetadata::decode(&mut bf).unwrap();
+    let decoded = MetadataRegistry::default().decode_all(&composite).unwrap();
+    assert_eq!(4, decoded.len());
+    let routing = decoded[0].downcast_ref::<RoutingMetadata>().unwrap();
+    assert_eq!(
+        &vec!["orders.create".to_string(), "orders.v2".to_string()],
+        routing.get_tags()
+    );
+    assert_eq!(
+        Some(&AuthMetadata::bearer("my_token")),
+        decoded[1].downcast_ref::<AuthMetadata>()
+    );
+    assert_eq!(mime::MESSAGE_X_RSOCKET_MIME_TYPE_V0, decoded[2].get_mime());
+    assert_eq!("application/x.tenant", decoded[3].get_mime());
+    assert_eq!(b"tenant_1", decoded[3].get_raw().as_ref());
+}
@@ -0,0 +1,37 @@
+extern crate rsocket_rust;
+
+use bytes::Bytes;
+use rsocket_rust::prelude::*;
+
+#[test]
+fn payload_constructors() {
+    let p = Payload::from_utf8("Hello World!");
+    assert_eq!(Some("Hello World!"), p.data_utf8());
+    assert!(p.metadata().is_none());
+
+    let p = Payload::from_utf8_with_metadata("Hello", "World");
+    assert_eq!(Some("Hello"), p.data_utf8());
+    assert_eq!(Some("World"), p.metadata_utf8());
+    assert_eq!(10, p.len());
+
+    let p = Payload::builder()
+        .set_data(Bytes::from("data"))
+        .set_metadata_utf8("metadata")
+        .build();
+    let (d, m) = p.into();
+    assert_eq!(Some(Bytes::from("data")), d);
+    assert_eq!(Some(Bytes::from("metadata")), m);
+
+    let p = Payload::from((Bytes::from("data"), Bytes::from("metadata")));
+    assert_eq!(Some("metadata"), p.metadata_utf8());
+
+    let p = Payload::from(vec![0xFF, 0xFE]);
+    assert_eq!(2, p.len());
+    assert!(p.data_utf8().is_none());
+
+    assert!(Payload::new(None, None).is_empty());
+    assert_eq!(
+        Some("Hello World!"),
+        Payload::from(String::from("Hello World!")).data_utf8()
+    );
+}
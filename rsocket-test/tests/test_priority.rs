@@ -0,0 +1,173 @@
+use bytes::{Bytes, BytesMut};
+use futures::SinkExt;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::extension::{CompositeMetadata, PriorityMetadata};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::transport::{RxBounded, TxOnce};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+
+type Writer = Arc<Mutex<Option<RxBounded<Frame>>>>;
+type Reader = Arc<Mutex<Option<Tx<Frame>>>>;
+
+/// A transport whose writer only runs when the test reads the sent frames.
+struct Stalled {
+    writer: Writer,
+    reader: Reader,
+}
+
+impl ClientTransport for Stalled {
+    fn attach(
+        self,
+        incoming: Tx<Frame>,
+        sending: RxBounded<Frame>,
+        connected: Option<TxOnce<Result<(), RSocketError>>>,
+    ) {
+        *self.writer.lock().unwrap() = Some(sending);
+        *self.reader.lock().unwrap() = Some(incoming);
+        if let Some(connected) = connected {
+            connected.send(Ok(())).unwrap();
+        }
+    }
+}
+
+fn data_of(frame: &Frame) -> String {
+    match frame.get_body_ref() {
+        Body::RequestFNF(v) => String::from_utf8(v.get_data().clone().unwrap().to_vec()).unwrap(),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
+
+#[test]
+fn priority_metadata_codec() {
+    let req = Payload::builder()
+        .set_data_utf8("bulk")
+        .metadata()
+        .route("upload")
+        .priority(7)
+        .build();
+    let mut bf = BytesMut::from(req.metadata().as_ref().unwrap().as_ref());
+    let composite = CompositeMetadata::decode(&mut bf).unwrap();
+    let priority = PriorityMetadata::from_composite(&composite)
+        .unwrap()
+        .unwrap();
+    assert_eq!(7, priority.get_priority());
+    assert!(PriorityMetadata::decode(&mut BytesMut::new()).is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn write_higher_priority_first() {
+    let writer: Writer = Arc::new(Mutex::new(None));
+    // the peer stays connected, it only reads nothing.
+    let reader: Reader = Arc::new(Mutex::new(None));
+    let cli = RSocketFactory::connect()
+        .transport(Stalled {
+            writer: writer.clone(),
+            reader: reader.clone(),
+        })
+        .outbound_capacity(4)
+        .priority_scheduling()
+        .start()
+        .await
+        .unwrap();
+    // fill the transport queue after SETUP.
+    for i in 0..3 {
+        let req = Payload::builder()
+            .set_data_utf8(&format!("filler{}", i))
+            .build();
+        cli.fire_and_forget(req).await;
+    }
+    time::delay_for(Duration::from_millis(50)).await;
+    for i in 0..3 {
+        let req = Payload::builder()
+            .set_data_utf8(&format!("bulk{}", i))
+            .metadata()
+            .priority(1)
+            .build();
+        cli.fire_and_forget(req).await;
+    }
+    let req = Payload::builder()
+        .set_data_utf8("interactive")
+        .metadata()
+        .priority(9)
+        .build();
+    cli.fire_and_forget(req).await;
+    time::delay_for(Duration::from_millis(50)).await;
+
+    let mut sending = writer.lock().unwrap().take().unwrap();
+    assert!(matches!(
+        sending.recv().await.unwrap().get_body(),
+        Body::Setup(_)
+    ));
+    let mut written = vec![];
+    for _ in 0..7 {
+        let frame = time::timeout(Duration::from_secs(3), sending.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        written.push(data_of(&frame));
+    }
+    assert_eq!(
+        vec![
+            "filler0",
+            "filler1",
+            "filler2",
+            "interactive",
+            "bulk0",
+            "bulk1",
+            "bulk2"
+        ],
+        written
+    );
+}
+
+#[tokio::main]
+#[test]
+async fn write_streams_by_turns() {
+    let writer: Writer = Arc::new(Mutex::new(None));
+    let reader: Reader = Arc::new(Mutex::new(None));
+    let cli = RSocketFactory::connect()
+        .transport(Stalled {
+            writer: writer.clone(),
+            reader: reader.clone(),
+        })
+        .acceptor(|| Box::new(EchoRSocket))
+        .outbound_capacity(8)
+        .fair_scheduling()
+        .start()
+        .await
+        .unwrap();
+    // fill the transport queue after SETUP.
+    for i in 0..7 {
+        let req = Payload::builder()
+            .set_data_utf8(&format!("filler{}", i))
+            .build();
+        cli.fire_and_forget(req).await;
+    }
+    time::delay_for(Duration::from_millis(50)).await;
+    let mut incoming = reader.lock().unwrap().take().unwrap();
+    for sid in [2, 4].iter() {
+        let req = frame::RequestStream::builder(*sid, 0)
+            .set_initial_request_n(16)
+            .set_data(Bytes::from("echo"))
+            .build();
+        incoming.send(req).await.unwrap();
+        time::delay_for(Duration::from_millis(50)).await;
+    }
+
+    let mut sending = writer.lock().unwrap().take().unwrap();
+    for _ in 0..8 {
+        sending.recv().await.unwrap();
+    }
+    let mut written = vec![];
+    while let Ok(Some(frame)) = time::timeout(Duration::from_millis(200), sending.recv()).await {
+        written.push(frame.get_stream_id());
+    }
+    assert!(written.len() >= 6);
+    for (i, sid) in written.iter().enumerate() {
+        assert_eq!(if i % 2 == 0 { 2 } else { 4 }, *sid);
+    }
+}
@@ -0,0 +1,202 @@
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::StreamExt;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::proxy::{Direction, Proxy};
+use rsocket_rust::test_helpers::{LoopbackConnector, LoopbackServerTransport, LoopbackTransport};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+/// Serve a proxy in front of `upstream` and return a connector to it.
+fn start_proxy<F>(upstream: LoopbackConnector, on_frame: F) -> LoopbackConnector
+where
+    F: Fn(Direction, Frame) -> Frame + Send + Sync + 'static,
+{
+    let (downstream, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        Proxy::builder()
+            .transport(downstream)
+            .upstream(move || upstream.connect().unwrap())
+            .on_frame(on_frame)
+            .serve(),
+    );
+    connector
+}
+
+/// Attach to a transport and return the frames it receives and a sender of frames.
+fn raw(tp: LoopbackTransport) -> (mpsc::Receiver<Frame>, tokio::sync::mpsc::Sender<Frame>) {
+    let (incoming_tx, incoming) = mpsc::channel(1024);
+    let (sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    tp.attach(incoming_tx, sending_rx, None);
+    (incoming, sending)
+}
+
+async fn next(frames: &mut mpsc::Receiver<Frame>) -> Frame {
+    time::timeout(Duration::from_secs(3), frames.next())
+        .await
+        .expect("no frame received")
+        .unwrap()
+}
+
+fn request_response(sid: u32) -> Frame {
+    frame::RequestResponse::builder(sid, 0)
+        .set_data(Bytes::from("ping"))
+        .build()
+}
+
+fn response(sid: u32) -> Frame {
+    frame::Payload::builder(sid, frame::FLAG_NEXT | frame::FLAG_COMPLETE)
+        .set_data(Bytes::from("pong"))
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn relay_requests_through_proxy() {
+    let (server, upstream) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let relayed = Arc::new(AtomicUsize::new(0));
+    let connector = start_proxy(upstream, {
+        let relayed = relayed.clone();
+        move |_direction, frame| {
+            relayed.fetch_add(1, Ordering::SeqCst);
+            frame
+        }
+    });
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .setup(Payload::from("hello"))
+        .start()
+        .await
+        .unwrap();
+
+    let res = cli
+        .request_response(Payload::from_utf8_with_metadata("ping", "md"))
+        .await
+        .unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+    assert_eq!(Some("md"), res.metadata_utf8());
+
+    let results: Vec<_> = cli.request_stream(Payload::from("s")).collect().await;
+    assert_eq!(3, results.len());
+    assert!(results
+        .iter()
+        .all(|it| it.as_ref().unwrap().data_utf8() == Some("s")));
+
+    let reqs = futures::stream::iter(vec![Ok(Payload::from("a")), Ok(Payload::from("b"))]);
+    let results: Vec<_> = cli.request_channel(Box::pin(reqs)).collect().await;
+    let results: Vec<_> = results
+        .iter()
+        .map(|it| it.as_ref().unwrap().data_utf8().unwrap().to_string())
+        .collect();
+    assert_eq!(vec!["a", "b"], results);
+    assert!(relayed.load(Ordering::SeqCst) > 0);
+}
+
+#[tokio::main]
+#[test]
+async fn rewrite_stream_ids() {
+    let (server, upstream) = LoopbackServerTransport::new();
+    let (accepted_tx, mut accepted) = mpsc::unbounded();
+    tokio::spawn(server.start(None, move |tp| {
+        accepted_tx.unbounded_send(tp).unwrap();
+    }));
+    let seen = Arc::new(std::sync::Mutex::new(vec![]));
+    let connector = start_proxy(upstream, {
+        let seen = seen.clone();
+        move |direction, frame| {
+            seen.lock()
+                .unwrap()
+                .push((direction, frame.get_stream_id()));
+            frame
+        }
+    });
+    let (mut down_in, mut down_out) = raw(connector.connect().unwrap());
+    let setup = frame::Setup::builder(0, 0).build();
+    down_out.send(setup).await.unwrap();
+    let (mut up_in, mut up_out) = raw(accepted.next().await.unwrap());
+    assert_eq!(frame::TYPE_SETUP, next(&mut up_in).await.get_frame_type());
+
+    // streams of the client get upstream IDs allocated by the proxy.
+    down_out.send(request_response(7)).await.unwrap();
+    assert_eq!(1, next(&mut up_in).await.get_stream_id());
+    let stream = frame::RequestStream::builder(21, 0)
+        .set_initial_request_n(2)
+        .build();
+    down_out.send(stream).await.unwrap();
+    assert_eq!(3, next(&mut up_in).await.get_stream_id());
+
+    up_out.send(response(1)).await.unwrap();
+    let res = next(&mut down_in).await;
+    assert_eq!(7, res.get_stream_id());
+    assert_eq!(Some(&Bytes::from("pong")), res.get_data());
+
+    // the completed stream is forgotten, frames of it are dropped.
+    up_out.send(response(1)).await.unwrap();
+    up_out
+        .send(frame::Payload::builder(3, frame::FLAG_NEXT).build())
+        .await
+        .unwrap();
+    assert_eq!(21, next(&mut down_in).await.get_stream_id());
+    down_out
+        .send(frame::RequestN::builder(21, 0).set_n(1).build())
+        .await
+        .unwrap();
+    match next(&mut up_in).await {
+        it if it.get_stream_id() == 3 => match it.get_body() {
+            Body::RequestN(n) => assert_eq!(1, n.get_n()),
+            other => panic!("unexpected frame {:?}", other),
+        },
+        other => panic!("unexpected frame {:?}", other),
+    }
+    down_out
+        .send(frame::Cancel::builder(21, 0).build())
+        .await
+        .unwrap();
+    assert_eq!(frame::TYPE_CANCEL, next(&mut up_in).await.get_frame_type());
+
+    // the ID of a finished stream can be requested again.
+    down_out.send(request_response(7)).await.unwrap();
+    assert_eq!(5, next(&mut up_in).await.get_stream_id());
+
+    // streams of the server keep even IDs.
+    up_out.send(request_response(10)).await.unwrap();
+    assert_eq!(2, next(&mut down_in).await.get_stream_id());
+    down_out.send(response(2)).await.unwrap();
+    assert_eq!(10, next(&mut up_in).await.get_stream_id());
+
+    // connection frames pass through.
+    let keepalive = frame::Keepalive::builder(0, frame::FLAG_RESPOND).build();
+    down_out.send(keepalive).await.unwrap();
+    assert_eq!(
+        frame::TYPE_KEEPALIVE,
+        next(&mut up_in).await.get_frame_type()
+    );
+
+    let seen = seen.lock().unwrap();
+    assert!(seen.contains(&(Direction::Upstream, 1)));
+    assert!(seen.contains(&(Direction::Downstream, 7)));
+}
+
+#[tokio::main]
+#[test]
+async fn upstream_connect_failure_closes_downstream() {
+    let (server, upstream) = LoopbackServerTransport::new();
+    // connections are accepted by the transport but dropped right away.
+    tokio::spawn(server.start(None, drop));
+    let connector = start_proxy(upstream, |_, frame| frame);
+    let (mut down_in, _down_out) = raw(connector.connect().unwrap());
+    let err = next(&mut down_in).await;
+    assert_eq!(frame::TYPE_ERROR, err.get_frame_type());
+    assert_eq!(0, err.get_stream_id());
+    let closed = time::timeout(Duration::from_secs(3), down_in.next()).await;
+    assert!(closed.unwrap().is_none());
+}
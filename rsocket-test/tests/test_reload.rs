@@ -0,0 +1,110 @@
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust::reload::Reloadable;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::{LoopbackConnector, LoopbackServerTransport};
+use std::time::Duration;
+use tokio::time;
+
+/// Responds with the version of its handler.
+struct Versioned(&'static str);
+
+impl RSocket for Versioned {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let version = self.0;
+        Box::pin(async move { Ok(Payload::from(version)) })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+fn serve(reloadable: &Reloadable) -> LoopbackConnector {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .reloadable(reloadable.clone())
+            .serve(),
+    );
+    connector
+}
+
+async fn connect(connector: &LoopbackConnector) -> Client<DefaultSpawner> {
+    RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap()
+}
+
+async fn version(cli: &Client<DefaultSpawner>) -> String {
+    let res = cli
+        .request_response(Payload::from("version"))
+        .await
+        .unwrap();
+    res.data_utf8().unwrap().to_string()
+}
+
+#[tokio::main]
+#[test]
+async fn swap_acceptor_of_new_connections() {
+    let reloadable = Reloadable::new(|_setup, _socket| Ok(Box::new(Versioned("v1"))));
+    let connector = serve(&reloadable);
+    let old = connect(&connector).await;
+    assert_eq!("v1", version(&old).await);
+
+    reloadable.set(|_setup, _socket| Ok(Box::new(Versioned("v2"))));
+    let new = connect(&connector).await;
+    assert_eq!("v2", version(&new).await);
+    assert_eq!("v1", version(&old).await);
+    assert_eq!(2, reloadable.get_connections());
+}
+
+#[tokio::main]
+#[test]
+async fn swap_responders_of_existing_connections() {
+    let reloadable = Reloadable::new(|_setup, _socket| Ok(Box::new(Versioned("v1"))));
+    let connector = serve(&reloadable);
+    let first = connect(&connector).await;
+    let second = connect(&connector).await;
+    assert_eq!("v1", version(&first).await);
+
+    assert_eq!(
+        2,
+        reloadable.set_all(|_setup, _socket| Ok(Box::new(Versioned("v2"))))
+    );
+    assert_eq!("v2", version(&first).await);
+    assert_eq!("v2", version(&second).await);
+
+    // a rejected connection keeps its responder, new connections are rejected.
+    assert_eq!(
+        0,
+        reloadable.set_all(|_setup, _socket| Err("closed".into()))
+    );
+    assert_eq!("v2", version(&first).await);
+    let rejected = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+    time::delay_for(Duration::from_millis(50)).await;
+    assert!(rejected.is_closed());
+}
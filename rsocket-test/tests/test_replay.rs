@@ -0,0 +1,119 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust::replay::{ReplayPolicy, ReplayTopics};
+use rsocket_rust::router::Router;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time;
+
+static ROUTER: Mutex<Option<Router>> = Mutex::new(None);
+
+fn item(data: &str) -> Payload {
+    Payload::builder().set_data_utf8(data).build()
+}
+
+fn data_of(items: Vec<Payload>) -> Vec<String> {
+    items
+        .iter()
+        .map(|it| it.data_utf8().unwrap().to_string())
+        .collect()
+}
+
+fn subscription(route: &str, topic: &str) -> Payload {
+    Payload::builder()
+        .set_data_utf8(topic)
+        .metadata()
+        .route(route)
+        .build()
+}
+
+async fn take(
+    results: &mut Flux<Result<Payload, rsocket_rust::error::RSocketError>>,
+    n: usize,
+) -> Vec<String> {
+    let mut received = vec![];
+    for _ in 0..n {
+        let it = time::timeout(Duration::from_secs(3), results.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        received.push(it.data_utf8().unwrap().to_string());
+    }
+    received
+}
+
+#[test]
+fn retain_by_policy() {
+    let topics = ReplayTopics::new(ReplayPolicy::last(2));
+    for data in &["a", "b", "c"] {
+        topics.publish("letters", item(data));
+    }
+    topics.publish("digits", item("1"));
+    assert_eq!(vec!["b", "c"], data_of(topics.retained("letters")));
+    assert_eq!(vec!["1"], data_of(topics.retained("digits")));
+    assert!(topics.retained("unknown").is_empty());
+    topics.close("letters");
+    assert!(topics.retained("letters").is_empty());
+}
+
+#[tokio::main]
+#[test]
+async fn forget_expired_items() {
+    let topics = ReplayTopics::new(ReplayPolicy::within(Duration::from_millis(50)));
+    topics.publish("prices", item("old"));
+    time::delay_for(Duration::from_millis(100)).await;
+    topics.publish("prices", item("new"));
+    assert_eq!(vec!["new"], data_of(topics.retained("prices")));
+}
+
+#[tokio::main]
+#[test]
+async fn replay_to_resubscribing_client() {
+    let prices = ReplayTopics::new(ReplayPolicy::last(2));
+    let news = ReplayTopics::new(ReplayPolicy::last(1));
+    let (server, connector) = LoopbackServerTransport::new();
+    let router = Router::new()
+        .route("prices", prices.clone())
+        .route("news", news.clone());
+    *ROUTER.lock().unwrap() = Some(router);
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(ROUTER.lock().unwrap().clone().unwrap())))
+            .serve(),
+    );
+    let connect = || async {
+        RSocketFactory::connect()
+            .transport(connector.connect().unwrap())
+            .start()
+            .await
+            .unwrap()
+    };
+
+    let cli: Client<DefaultSpawner> = connect().await;
+    let mut results = cli.request_stream(subscription("prices", "ACME"));
+    time::delay_for(Duration::from_millis(50)).await;
+    prices.publish("ACME", item("10"));
+    assert_eq!(vec!["10"], take(&mut results, 1).await);
+    drop(results);
+    cli.close();
+
+    // updates published while the client is away.
+    for data in &["11", "12", "13"] {
+        prices.publish("ACME", item(data));
+    }
+    prices.publish("OTHER", item("99"));
+    news.publish("ACME", item("merger"));
+    news.publish("ACME", item("results"));
+
+    let cli: Client<DefaultSpawner> = connect().await;
+    let mut results = cli.request_stream(subscription("prices", "ACME"));
+    assert_eq!(vec!["12", "13"], take(&mut results, 2).await);
+    prices.publish("ACME", item("14"));
+    assert_eq!(vec!["14"], take(&mut results, 1).await);
+
+    let mut results = cli.request_stream(subscription("news", "ACME"));
+    assert_eq!(vec!["results"], take(&mut results, 1).await);
+}
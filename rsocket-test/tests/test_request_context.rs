@@ -0,0 +1,127 @@
+use futures::future;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use rsocket_rust::transport::PeerInfo;
+
+const TENANT: &str = "application/x.tenant";
+
+/// Describes the context of every request-response.
+struct Responder;
+
+impl RSocket for Responder {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(future::err(RSocketError::from("no context")))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(futures::stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        _req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        let tenant = ctx
+            .get_metadata()
+            .and_then(|it| it.find(TENANT))
+            .map(|it| String::from_utf8(it.get_payload().to_vec()).unwrap());
+        let previous = ctx.get_attributes().get("previous");
+        ctx.get_attributes()
+            .set("previous", &ctx.get_stream_id().to_string());
+        let described = format!(
+            "{}|{}|{}|{}|{}",
+            ctx.get_peer().get_identity().unwrap_or("-"),
+            tenant.as_deref().unwrap_or("-"),
+            ctx.get_params().unwrap().get_data_mime_type(),
+            ctx.get_stream_id(),
+            previous.as_deref().unwrap_or("-"),
+        );
+        let res = Payload::builder().set_data_utf8(&described).build();
+        Box::pin(future::ok(res))
+    }
+}
+
+async fn request(cli: &Client<rsocket_rust::runtime::DefaultSpawner>, tenant: &str) -> String {
+    let req = Payload::builder()
+        .set_data_utf8("hello")
+        .metadata()
+        .route("describe")
+        .custom(TENANT, tenant)
+        .build();
+    let res = cli.request_response(req).await.unwrap();
+    res.data_utf8().unwrap().to_string()
+}
+
+#[tokio::main]
+#[test]
+async fn pass_context_to_responder() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(Router::new().route("describe", Responder))))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(
+            connector
+                .connect_as(PeerInfo::new(None).identity("alice"))
+                .unwrap(),
+        )
+        .mime_type(
+            mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0,
+            mime::APPLICATION_JSON,
+        )
+        .start()
+        .await
+        .unwrap();
+    assert_eq!(
+        "alice|tenant_1|application/json|1|-",
+        request(&cli, "tenant_1").await
+    );
+    // attributes are shared by the requests of a connection.
+    assert_eq!(
+        "alice|tenant_2|application/json|3|1",
+        request(&cli, "tenant_2").await
+    );
+}
+
+#[tokio::main]
+#[test]
+async fn skip_metadata_of_other_mime_types() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(Responder)))
+            .serve(),
+    );
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap();
+    assert_eq!(
+        "-|-|application/binary|1|-",
+        request(&cli, "tenant_1").await
+    );
+}
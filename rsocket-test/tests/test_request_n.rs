@@ -0,0 +1,84 @@
+use futures::channel::mpsc;
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use std::time::Duration;
+use tokio::time;
+
+/// Responds streams with endless items.
+struct EndlessRSocket;
+
+impl RSocket for EndlessRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        EchoRSocket.request_response(req)
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(
+            (0u64..).map(|n| Ok(Payload::from(format!("{}", n)))),
+        ))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+/// Returns the number of PAYLOAD frames received until none arrives for a while.
+async fn count_payloads(incoming: &mut mpsc::Receiver<Frame>) -> usize {
+    let mut n = 0;
+    while let Ok(Some(frame)) = time::timeout(Duration::from_millis(200), incoming.next()).await {
+        match frame.get_body_ref() {
+            Body::Payload(_) => n += 1,
+            other => panic!("unexpected frame: {:?}", other),
+        }
+    }
+    n
+}
+
+#[tokio::main]
+#[test]
+async fn accumulate_request_n() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EndlessRSocket)))
+            .serve(),
+    );
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    sending
+        .send(frame::Setup::builder(0, 0).build())
+        .await
+        .unwrap();
+    let req = frame::RequestStream::builder(1, 0)
+        .set_initial_request_n(2)
+        .build();
+    sending.send(req).await.unwrap();
+    assert_eq!(2, count_payloads(&mut incoming).await);
+
+    // demand adds up across REQUEST_N frames, one item is sent per unit.
+    for n in [1, 3].iter() {
+        let request_n = frame::RequestN::builder(1, 0).set_n(*n).build();
+        sending.send(request_n).await.unwrap();
+    }
+    assert_eq!(4, count_payloads(&mut incoming).await);
+}
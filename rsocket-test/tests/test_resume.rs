@@ -0,0 +1,179 @@
+use bytes::Bytes;
+use futures::channel::mpsc;
+use rsocket_rust::error;
+use rsocket_rust::frame::{self, Body};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{loopback, LoopbackServerTransport};
+use rsocket_rust::transport::{Reconnected, ResumeSession};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time;
+
+#[tokio::main]
+#[test]
+async fn reject_resume() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    let resume = frame::Resume::builder(0, 0)
+        .set_token(Bytes::from("session"))
+        .set_last_received_server_position(42)
+        .build();
+    sending.send(resume).await.unwrap();
+
+    let rejected = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(0, rejected.get_stream_id());
+    match rejected.get_body() {
+        Body::Error(e) => assert_eq!(error::ERR_REJECT_RESUME, e.get_code()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn resume_session() {
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let peer = tokio::spawn(async move {
+        let resume = incoming.next().await.unwrap();
+        let resume_ok = frame::ResumeOK::builder(0, 0).set_position(7).build();
+        sending.send(resume_ok).await.unwrap();
+        (resume, incoming, sending)
+    });
+
+    let reconnected = Arc::new(Mutex::new(Vec::new()));
+    let seen = reconnected.clone();
+    let (requester, connection) = RSocketFactory::connect()
+        .transport(client)
+        .resume(ResumeSession::new(Bytes::from("session")).last_received_server_position(42))
+        .on_reconnect(move |it| seen.lock().unwrap().push(format!("{:?}", it)))
+        .connect()
+        .await
+        .unwrap();
+    let (resume, mut incoming, _sending) = peer.await.unwrap();
+    match resume.get_body() {
+        Body::Resume(v) => {
+            assert_eq!(&Some(Bytes::from("session")), v.get_token());
+            assert_eq!(42, v.get_last_received_server_position());
+        }
+        other => panic!("unexpected frame: {:?}", other),
+    }
+    assert_eq!(
+        vec![String::from("Resumed { position: 7 }")],
+        *reconnected.lock().unwrap()
+    );
+
+    // the session goes on without SETUP.
+    let _connection = tokio::spawn(connection);
+    requester.fire_and_forget(Payload::from("hello")).await;
+    let next = time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(next.get_body(), Body::RequestFNF(_)));
+}
+
+#[tokio::main]
+#[test]
+async fn fall_back_to_setup_on_rejected_resume() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+    let reconnected = Arc::new(Mutex::new(Vec::new()));
+    let seen = reconnected.clone();
+    let fallback = connector.connect().unwrap();
+    let client = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .resume(ResumeSession::new(Bytes::from("session")))
+        .resume_fallback(move || fallback)
+        .on_reconnect(move |it| {
+            if let Reconnected::Setup { reason } = it {
+                seen.lock().unwrap().push(reason.to_string());
+            }
+        })
+        .start()
+        .await
+        .unwrap();
+    assert_eq!(
+        vec![String::from(
+            "ERROR(REJECT_RESUME): resumption is not supported"
+        )],
+        *reconnected.lock().unwrap()
+    );
+    let res = client
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+}
+
+#[tokio::main]
+#[test]
+async fn fail_resume_before_first_available_position() {
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let peer = tokio::spawn(async move {
+        let _resume = incoming.next().await.unwrap();
+        let resume_ok = frame::ResumeOK::builder(0, 0).set_position(3).build();
+        sending.send(resume_ok).await.unwrap();
+        let answer = incoming.next().await.unwrap();
+        (answer, sending)
+    });
+    let result = RSocketFactory::connect()
+        .transport(client)
+        .resume(ResumeSession::new(Bytes::from("session")).first_available_client_position(10))
+        .start()
+        .await;
+    match result {
+        Ok(_) => panic!("RESUME_OK of an unavailable position was accepted"),
+        Err(e) => assert_eq!(
+            "ERROR(CONN_FAILED): RESUME_OK position 3 is before the first available position 10",
+            e.to_string()
+        ),
+    }
+    let (answer, _sending) = peer.await.unwrap();
+    assert_eq!(0, answer.get_stream_id());
+    match answer.get_body() {
+        Body::Error(e) => assert_eq!(error::ERR_CONN_FAILED, e.get_code()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn fail_unanswered_resume() {
+    let (client, server) = loopback();
+    let (incoming_tx, _incoming) = mpsc::channel(1024);
+    let (_sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let result = RSocketFactory::connect()
+        .transport(client)
+        .resume(ResumeSession::new(Bytes::from("session")).timeout(Duration::from_millis(100)))
+        .start()
+        .await;
+    match result {
+        Ok(_) => panic!("RESUME was not answered"),
+        Err(e) => assert_eq!("ERROR(CONN_FAILED): RESUME timed out", e.to_string()),
+    }
+}
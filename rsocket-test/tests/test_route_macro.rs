@@ -0,0 +1,117 @@
+extern crate rsocket_rust;
+
+use rsocket_rust::codec;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::extension::{AuthMetadata, RoutingMetadata};
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust_macros::route;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CANCELLED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Order {
+    item: String,
+    amount: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+struct Receipt {
+    user: String,
+    total: u32,
+}
+
+#[route("orders.create")]
+async fn create_order(
+    order: Order,
+    #[metadata] auth: AuthMetadata,
+) -> Result<Receipt, RSocketError> {
+    match auth {
+        AuthMetadata::Bearer(user) => Ok(Receipt {
+            user,
+            total: order.amount * 2,
+        }),
+        _ => Err(RSocketError::from("bearer token required")),
+    }
+}
+
+#[route("orders.*")]
+async fn other_orders(#[metadata] routing: RoutingMetadata) -> Result<String, RSocketError> {
+    Ok(routing.get_tags()[0].clone())
+}
+
+#[route("orders.cancel")]
+async fn cancel_order(_order: Order, #[metadata] auth: Option<AuthMetadata>) {
+    if auth.is_none() {
+        CANCELLED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn order_request(route: &str, bearer: Option<&str>) -> Payload {
+    let order = Order {
+        item: String::from("book"),
+        amount: 21,
+    };
+    let mut bu = Payload::builder()
+        .set_data(codec::encode(mime::APPLICATION_CBOR, &order).unwrap())
+        .metadata()
+        .route(route)
+        .mime_type(mime::APPLICATION_CBOR)
+        .unwrap();
+    if let Some(token) = bearer {
+        bu = bu.bearer(token);
+    }
+    bu.build()
+}
+
+#[tokio::main]
+#[test]
+async fn route_handlers() {
+    let router = Router::new()
+        .handler(create_order)
+        .handler(other_orders)
+        .handler(cancel_order);
+
+    let res = router
+        .request_response(order_request("orders.create", Some("jeffsky")))
+        .await
+        .unwrap();
+    let receipt: Receipt = codec::decode_payload(&res, mime::APPLICATION_JSON).unwrap();
+    assert_eq!(
+        Receipt {
+            user: String::from("jeffsky"),
+            total: 42,
+        },
+        receipt
+    );
+
+    // missing required metadata.
+    let res = router
+        .request_response(order_request("orders.create", None))
+        .await;
+    assert!(res.unwrap_err().to_string().contains("missing metadata"));
+
+    // matched by prefix.
+    let res = router
+        .request_response(order_request("orders.list", None))
+        .await
+        .unwrap();
+    let route: String = codec::decode_payload(&res, mime::APPLICATION_JSON).unwrap();
+    assert_eq!("orders.list", route);
+
+    router
+        .fire_and_forget(order_request("orders.cancel", None))
+        .await;
+    router
+        .fire_and_forget(order_request("orders.cancel", Some("jeffsky")))
+        .await;
+    assert_eq!(1, CANCELLED.load(Ordering::SeqCst));
+
+    let res = router
+        .request_response(order_request("orders.cancel", None))
+        .await;
+    assert!(res.is_err());
+}
@@ -0,0 +1,209 @@
+extern crate rsocket_rust;
+
+use futures::stream;
+use rsocket_rust::error::{self, ErrorKind, RSocketError};
+use rsocket_rust::extension::AuthMetadata;
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust::test_helpers::{pair, LoopbackServerTransport};
+use rsocket_rust::transport::Principal;
+
+struct Greeting(&'static str);
+
+impl RSocket for Greeting {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let msg = format!("{} {}!", self.0, req.data_utf8().unwrap_or_default());
+        Box::pin(async move { Ok(Payload::from(msg)) })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(vec![Ok(Payload::from(self.0))]))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+fn routed(route: &str, data: &str) -> Payload {
+    Payload::builder()
+        .set_data_utf8(data)
+        .metadata()
+        .route(route)
+        .build()
+}
+
+#[tokio::main]
+#[test]
+async fn router_dispatch() {
+    let router = Router::new()
+        .route("greetings.hello", Greeting("Hello"))
+        .route("greetings.bye", Greeting("Goodbye"));
+    assert_eq!(2, router.get_routes().len());
+
+    let res = router
+        .request_response(routed("greetings.hello", "Jeffsky"))
+        .await
+        .unwrap();
+    assert_eq!(Some("Hello Jeffsky!"), res.data_utf8());
+    let res = router
+        .request_response(routed("greetings.bye", "Jeffsky"))
+        .await
+        .unwrap();
+    assert_eq!(Some("Goodbye Jeffsky!"), res.data_utf8());
+
+    let mut results = router.request_stream(routed("greetings.bye", ""));
+    assert_eq!(
+        Some("Goodbye"),
+        results.next().await.unwrap().unwrap().data_utf8()
+    );
+    assert!(results.next().await.is_none());
+
+    let reqs = vec![Ok(routed("greetings.hello", "a")), Ok(Payload::from("b"))];
+    let results: Vec<_> = router
+        .request_channel(Box::pin(stream::iter(reqs)))
+        .map(|it| it.unwrap().data_utf8().unwrap().to_string())
+        .collect()
+        .await;
+    assert_eq!(vec!["a", "b"], results);
+
+    let res = router
+        .request_response(routed("greetings.unknown", "Jeffsky"))
+        .await;
+    assert!(res.unwrap_err().to_string().contains("greetings.unknown"));
+    let mut results = router.request_channel(Box::pin(stream::iter(vec![Ok(Payload::from("a"))])));
+    assert!(results.next().await.unwrap().is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn router_fallback() {
+    let router = Router::new()
+        .route("greetings.hello", Greeting("Hello"))
+        .fallback(EchoRSocket);
+    let res = router
+        .request_response(routed("greetings.unknown", "Jeffsky"))
+        .await
+        .unwrap();
+    assert_eq!(Some("Jeffsky"), res.data_utf8());
+    let res = router
+        .request_response(Payload::from("no route"))
+        .await
+        .unwrap();
+    assert_eq!(Some("no route"), res.data_utf8());
+}
+
+#[tokio::main]
+#[test]
+async fn change_routes_while_serving() {
+    let router = Router::new().route("greetings.hello", Greeting("Hello"));
+    let cli = pair(router.clone()).await;
+    let greet = |route: &'static str| {
+        let cli = cli.clone();
+        async move {
+            cli.request_response(routed(route, "Jeffsky"))
+                .await
+                .map(|it| it.data_utf8().unwrap().to_string())
+        }
+    };
+    assert_eq!("Hello Jeffsky!", greet("greetings.hello").await.unwrap());
+    assert!(greet("greetings.bye").await.is_err());
+
+    assert!(!router.add_route("greetings.bye", Greeting("Goodbye")));
+    assert!(router.add_route("greetings.hello", Greeting("Hi")));
+    assert!(!router.add_route("farewells.*", Greeting("Farewell")));
+    assert_eq!("Goodbye Jeffsky!", greet("greetings.bye").await.unwrap());
+    assert_eq!("Hi Jeffsky!", greet("greetings.hello").await.unwrap());
+    assert_eq!("Farewell Jeffsky!", greet("farewells.all").await.unwrap());
+
+    assert!(router.remove_route("greetings.bye"));
+    assert!(router.remove_route("farewells.*"));
+    assert!(!router.remove_route("farewells.*"));
+    assert!(greet("greetings.bye").await.is_err());
+    assert!(greet("farewells.all").await.is_err());
+    assert_eq!(vec!["greetings.hello"], router.get_routes());
+}
+
+fn guarded_router() -> Router {
+    Router::new()
+        .route("greetings.hello", Greeting("Hello"))
+        .route("admin.*", Greeting("Welcome"))
+        .authorize("admin.*", |principal, _metadata| {
+            principal.map(|it| it.has_role("admin")).unwrap_or(false)
+        })
+        .authorize("greetings.hello", |_principal, metadata| {
+            metadata.find("application/x.tenant").is_some()
+        })
+}
+
+#[tokio::main]
+#[test]
+async fn authorize_routes() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .authenticator(|auth: Option<AuthMetadata>| async move {
+                match auth {
+                    Some(AuthMetadata::Bearer(token)) if token == "root" => {
+                        Ok(Principal::new("root").role("admin"))
+                    }
+                    _ => Ok(Principal::new("guest")),
+                }
+            })
+            .acceptor(|_setup, _socket| Ok(Box::new(guarded_router())))
+            .serve(),
+    );
+    let connect = |token: &str| {
+        RSocketFactory::connect()
+            .transport(connector.connect().unwrap())
+            .metadata_mime_type(mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0)
+            .setup(Payload::builder().metadata().bearer(token).build())
+            .start()
+    };
+    let root = connect("root").await.unwrap();
+    let guest = connect("guest").await.unwrap();
+
+    let res = root
+        .request_response(routed("admin.users", "root"))
+        .await
+        .unwrap();
+    assert_eq!(Some("Welcome root!"), res.data_utf8());
+    let e = guest
+        .request_response(routed("admin.users", "guest"))
+        .await
+        .unwrap_err();
+    match e.kind() {
+        ErrorKind::Internal(code, msg) => {
+            assert_eq!(error::ERR_REJECTED, *code);
+            assert_eq!("access denied to route: admin.users", msg);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    // guards may decide by the metadata of the request.
+    assert!(guest
+        .request_response(routed("greetings.hello", "guest"))
+        .await
+        .is_err());
+    let req = Payload::builder()
+        .set_data_utf8("guest")
+        .metadata()
+        .route("greetings.hello")
+        .custom("application/x.tenant", "tenant_1")
+        .build();
+    let res = guest.request_response(req).await.unwrap();
+    assert_eq!(Some("Hello guest!"), res.data_utf8());
+}
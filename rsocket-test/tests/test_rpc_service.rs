@@ -0,0 +1,98 @@
+extern crate rsocket_rust;
+
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust_macros::service;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct HelloRequest {
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct HelloReply {
+    message: String,
+}
+
+#[service]
+pub trait Greeter: Send + Sync {
+    #[route("greetings.hello")]
+    fn hello(&self, req: HelloRequest) -> Mono<Result<HelloReply, RSocketError>>;
+
+    fn count_down(&self, from: u32) -> Flux<Result<u32, RSocketError>>;
+
+    fn notify(&self, name: String) -> Mono<()>;
+}
+
+struct GreeterImpl {
+    notified: Arc<AtomicUsize>,
+}
+
+impl Greeter for GreeterImpl {
+    fn hello(&self, req: HelloRequest) -> Mono<Result<HelloReply, RSocketError>> {
+        Box::pin(async move {
+            Ok(HelloReply {
+                message: format!("Hello {}!", req.name),
+            })
+        })
+    }
+
+    fn count_down(&self, from: u32) -> Flux<Result<u32, RSocketError>> {
+        Box::pin(stream::iter((0..from).rev().map(Ok)))
+    }
+
+    fn notify(&self, _name: String) -> Mono<()> {
+        self.notified.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async {})
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn rpc_service() {
+    let notified = Arc::new(AtomicUsize::new(0));
+    let server = GreeterServer::new(GreeterImpl {
+        notified: notified.clone(),
+    });
+    let cli = GreeterClient::new(server).mime_type(mime::APPLICATION_CBOR);
+
+    let reply = cli
+        .hello(HelloRequest {
+            name: String::from("Jeffsky"),
+        })
+        .await
+        .unwrap();
+    assert_eq!("Hello Jeffsky!", reply.message);
+
+    let results: Vec<u32> = cli
+        .count_down(3)
+        .map(|it| it.unwrap())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(vec![2, 1, 0], results);
+
+    cli.notify(String::from("Jeffsky")).await;
+    assert_eq!(1, notified.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn rpc_service_unknown_route() {
+    let server = GreeterServer::new(GreeterImpl {
+        notified: Arc::new(AtomicUsize::new(0)),
+    });
+    let req = Payload::builder()
+        .set_data_utf8("{}")
+        .metadata()
+        .route("greetings.missing")
+        .build();
+    let res = server.request_response(req).await;
+    assert!(res.unwrap_err().to_string().contains("greetings.missing"));
+    let res = server.request_response(Payload::from("{}")).await;
+    assert!(res.is_err());
+}
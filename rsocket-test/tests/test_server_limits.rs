@@ -0,0 +1,84 @@
+use futures::channel::mpsc;
+use rsocket_rust::frame;
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{LoopbackConnector, LoopbackServerTransport, LoopbackTransport};
+use rsocket_rust::transport::{ClientTransport, PeerInfo};
+use std::time::Duration;
+use tokio::time;
+
+async fn connect(tp: LoopbackTransport) -> bool {
+    match RSocketFactory::connect().transport(tp).start().await {
+        Ok(cli) => {
+            let res = cli.request_response(Payload::from("ping")).await.unwrap();
+            assert_eq!(Some("ping"), res.data_utf8());
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+async fn reconnect(connector: &LoopbackConnector) -> bool {
+    for _ in 0..100 {
+        if connect(connector.connect().unwrap()).await {
+            return true;
+        }
+        time::delay_for(Duration::from_millis(10)).await;
+    }
+    false
+}
+
+#[tokio::main]
+#[test]
+async fn limit_connections() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .max_connections(1)
+            .serve(),
+    );
+
+    // hold the only connection with a raw transport, which can be closed.
+    let (incoming_tx, _incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    sending
+        .send(frame::Setup::builder(0, 0).build())
+        .await
+        .unwrap();
+    assert!(!connect(connector.connect().unwrap()).await);
+
+    drop(sending);
+    assert!(reconnect(&connector).await);
+}
+
+#[tokio::main]
+#[test]
+async fn filter_peers() {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .accept_filter(|peer: PeerInfo| {
+                let trusted = peer.get_identity() == Some("trusted");
+                async move {
+                    time::delay_for(Duration::from_millis(10)).await;
+                    trusted
+                }
+            })
+            .serve(),
+    );
+
+    let addr = "127.0.0.1:7878".parse().ok();
+    let trusted = PeerInfo::new(addr).identity("trusted");
+    assert_eq!(addr, trusted.get_addr());
+    assert!(connect(connector.connect_as(trusted).unwrap()).await);
+    let stranger = PeerInfo::new(addr).identity("stranger");
+    assert!(!connect(connector.connect_as(stranger).unwrap()).await);
+    assert!(!connect(connector.connect().unwrap()).await);
+}
@@ -0,0 +1,91 @@
+#[macro_use]
+extern crate log;
+
+use futures::channel::{mpsc, oneshot};
+use futures::StreamExt;
+use rsocket_rust::error;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::mime;
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::time::Duration;
+
+async fn start_server(addr: &'static str) {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .data_mime_types(&[mime::APPLICATION_JSON, mime::APPLICATION_CBOR])
+            .metadata_mime_types(&[mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0])
+            .acceptor(|setup, _socket| {
+                info!("accept setup: {:?}", setup);
+                Ok(Box::new(EchoRSocket))
+            })
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+}
+
+async fn send_setup(addr: &str, data_mime: &str, metadata_mime: &str) -> Option<Frame> {
+    let (incoming_tx, mut incoming_rx) = mpsc::channel::<Frame>(1024);
+    let (mut sending_tx, sending_rx) = tokio::sync::mpsc::channel::<Frame>(16);
+    let (connected_tx, connected_rx) = oneshot::channel();
+    TcpClientTransport::from(addr).attach(incoming_tx, sending_rx, Some(connected_tx));
+    connected_rx.await.unwrap().unwrap();
+    let setup = frame::Setup::builder(0, 0)
+        .set_mime_data(data_mime)
+        .set_mime_metadata(metadata_mime)
+        .build();
+    sending_tx.send(setup).await.unwrap();
+    tokio::time::timeout(Duration::from_millis(500), incoming_rx.next())
+        .await
+        .ok()
+        .flatten()
+}
+
+fn assert_invalid_setup(frame: Option<Frame>, expect_msg: &str) {
+    let frame = frame.expect("should be rejected");
+    assert_eq!(0, frame.get_stream_id());
+    match frame.get_body() {
+        Body::Error(e) => {
+            assert_eq!(error::ERR_INVALID_SETUP, e.get_code());
+            assert!(
+                e.get_data_utf8().contains(expect_msg),
+                "{}",
+                e.get_data_utf8()
+            );
+        }
+        other => panic!("expect ERROR frame, got: {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn test_setup_mime_rejected() {
+    let addr = "127.0.0.1:7801";
+    start_server(addr).await;
+
+    let rejected = send_setup(
+        addr,
+        mime::TEXT_PLAIN,
+        mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0,
+    )
+    .await;
+    assert_invalid_setup(rejected, "unsupported data MIME type: text/plain");
+
+    let rejected = send_setup(addr, mime::APPLICATION_JSON, mime::TEXT_PLAIN).await;
+    assert_invalid_setup(rejected, "unsupported metadata MIME type: text/plain");
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .mime_type(
+            mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0,
+            mime::APPLICATION_CBOR,
+        )
+        .start()
+        .await
+        .unwrap();
+    let res = cli.request_response(Payload::from("Hello")).await.unwrap();
+    assert_eq!("Hello", res.data().as_ref().unwrap());
+    cli.close();
+}
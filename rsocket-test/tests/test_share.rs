@@ -0,0 +1,161 @@
+use futures::channel::mpsc;
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust::share::{BufferPolicy, ShareExt, SharedStreams};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::time;
+
+static REQUESTED: AtomicUsize = AtomicUsize::new(0);
+
+/// Streams its request forever, counting the requests.
+struct Ticker;
+
+impl RSocket for Ticker {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(async move { Ok(req) })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        REQUESTED.fetch_add(1, Ordering::SeqCst);
+        Box::pin(stream::iter(Some(Ok(req))).chain(stream::pending()))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+fn upstream() -> (
+    mpsc::UnboundedSender<Result<u32, RSocketError>>,
+    rsocket_rust::share::Shared<u32>,
+) {
+    let (tx, rx) = mpsc::unbounded();
+    (tx, rx.share())
+}
+
+async fn next<T>(flux: &mut Flux<Result<T, RSocketError>>) -> Option<Result<T, RSocketError>> {
+    time::timeout(Duration::from_secs(3), flux.next())
+        .await
+        .expect("no item received")
+}
+
+async fn wait_until<F: Fn() -> bool>(cond: F) {
+    for _ in 0..100 {
+        if cond() {
+            return;
+        }
+        time::delay_for(Duration::from_millis(10)).await;
+    }
+    panic!("condition not reached");
+}
+
+#[tokio::main]
+#[test]
+async fn multicast_to_subscribers() {
+    let (tx, shared) = upstream();
+    let mut a = shared.subscribe(BufferPolicy::Unbounded);
+    let mut b = shared.subscribe(BufferPolicy::Unbounded);
+    assert_eq!(2, shared.subscribers());
+    tx.unbounded_send(Ok(1)).unwrap();
+    assert_eq!(1, next(&mut a).await.unwrap().unwrap());
+    assert_eq!(1, next(&mut b).await.unwrap().unwrap());
+
+    // late subscribers see the items produced after they subscribed.
+    let mut c = shared.subscribe(BufferPolicy::Unbounded);
+    tx.unbounded_send(Ok(2)).unwrap();
+    for it in [&mut a, &mut b, &mut c] {
+        assert_eq!(2, next(it).await.unwrap().unwrap());
+    }
+
+    tx.unbounded_send(Err(RSocketError::from("boom"))).unwrap();
+    for it in [&mut a, &mut b, &mut c] {
+        assert_eq!("boom", next(it).await.unwrap().unwrap_err().to_string());
+        assert!(next(it).await.is_none());
+    }
+    assert!(shared.is_terminated());
+    let mut late = shared.subscribe(BufferPolicy::Unbounded);
+    assert!(next(&mut late).await.unwrap().is_err());
+    assert!(next(&mut late).await.is_none());
+}
+
+#[tokio::main]
+#[test]
+async fn cancel_upstream_without_subscribers() {
+    let (tx, shared) = upstream();
+    let a = shared.subscribe(BufferPolicy::Unbounded);
+    let b = shared.subscribe(BufferPolicy::Unbounded);
+    drop(a);
+    tx.unbounded_send(Ok(1)).unwrap();
+    assert!(!tx.is_closed());
+    drop(b);
+    wait_until(|| tx.is_closed()).await;
+    assert!(shared.is_terminated());
+}
+
+#[tokio::main]
+#[test]
+async fn buffer_slow_subscribers() {
+    let (tx, shared) = upstream();
+    let mut fast = shared.subscribe(BufferPolicy::Unbounded);
+    let oldest = shared.subscribe(BufferPolicy::DropOldest(2));
+    let newest = shared.subscribe(BufferPolicy::DropNewest(2));
+    let error = shared.subscribe(BufferPolicy::Error(2));
+    for i in 0..5 {
+        tx.unbounded_send(Ok(i)).unwrap();
+    }
+    // the fast subscriber receives everything, the others did not consume yet.
+    for i in 0..5 {
+        assert_eq!(i, next(&mut fast).await.unwrap().unwrap());
+    }
+    drop(tx);
+    assert!(next(&mut fast).await.is_none());
+
+    let collect = |flux: Flux<Result<u32, RSocketError>>| async move {
+        let all: Vec<_> = flux.collect().await;
+        all.into_iter()
+            .map(|it| it.map_err(|e| e.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(vec![Ok(3), Ok(4)], collect(oldest).await);
+    assert_eq!(vec![Ok(0), Ok(1)], collect(newest).await);
+    let results = collect(error).await;
+    assert_eq!(3, results.len());
+    assert_eq!(vec![Ok(0), Ok(1)], results[..2].to_vec());
+    assert!(results[2].is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn collapse_identical_requests() {
+    let streams = SharedStreams::new(Ticker);
+    let before = REQUESTED.load(Ordering::SeqCst);
+    let mut a = streams.request_stream(Payload::from("AAPL"), BufferPolicy::Unbounded);
+    assert_eq!(
+        Some("AAPL"),
+        next(&mut a).await.unwrap().unwrap().data_utf8()
+    );
+    let b = streams.request_stream(Payload::from("AAPL"), BufferPolicy::DropOldest(16));
+    let c = streams.request_stream(Payload::from("MSFT"), BufferPolicy::Unbounded);
+    assert_eq!(2, REQUESTED.load(Ordering::SeqCst) - before);
+    assert_eq!(2, streams.active());
+
+    drop(a);
+    drop(b);
+    wait_until(|| streams.active() == 1).await;
+    let _a = streams.request_stream(Payload::from("AAPL"), BufferPolicy::Unbounded);
+    assert_eq!(3, REQUESTED.load(Ordering::SeqCst) - before);
+    drop(c);
+}
@@ -0,0 +1,110 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::{Expectation, MockRSocket, SimNetwork};
+use std::time::Duration;
+use tokio::time;
+
+#[tokio::test]
+async fn keepalive_rtt_follows_simulated_delay() {
+    time::pause();
+    let net = SimNetwork::new(7);
+    net.set_delay(Duration::from_millis(40));
+    let (server, connector) = net.server();
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    let cli = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .keepalive(Duration::from_secs(1), Duration::from_secs(10), 3)
+        .start()
+        .await
+        .unwrap();
+    time::delay_for(Duration::from_millis(1100)).await;
+    // timers fire on millisecond boundaries.
+    let rtt = cli.stats().get_keepalive_rtt().unwrap();
+    assert!(rtt >= Duration::from_millis(80) && rtt < Duration::from_millis(82));
+}
+
+#[tokio::test]
+async fn partition_loses_requests_until_healed() {
+    time::pause();
+    let net = SimNetwork::new(7);
+    net.set_delay(Duration::from_millis(5));
+    let mock = MockRSocket::new();
+    mock.expect(Expectation::request_response().respond(Payload::from("pong")));
+    let cli = net.pair(mock.clone()).await;
+    // the SETUP frame must arrive before the partition.
+    let res = cli.request_response(Payload::from("ping")).await.unwrap();
+    assert_eq!(Some("pong"), res.data_utf8());
+
+    net.partition();
+    let res = time::timeout(
+        Duration::from_secs(30),
+        cli.request_response(Payload::from("ping")),
+    )
+    .await;
+    assert!(res.is_err());
+    assert_eq!(1, mock.count("request_response"));
+
+    net.heal();
+    let res = cli.request_response(Payload::from("ping")).await.unwrap();
+    assert_eq!(Some("pong"), res.data_utf8());
+    assert!(net.dropped() > 0);
+}
+
+async fn arrival_order(seed: u64) -> Vec<String> {
+    let net = SimNetwork::new(seed);
+    net.set_delay(Duration::from_millis(10))
+        .set_jitter(Duration::from_millis(50));
+    let mock = MockRSocket::new();
+    let cli = net.pair(mock.clone()).await;
+    for i in 0..10 {
+        cli.fire_and_forget(Payload::from(i.to_string())).await;
+    }
+    time::delay_for(Duration::from_millis(100)).await;
+    mock.calls()
+        .iter()
+        .map(|it| it.get_payload().data_utf8().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn seeded_jitter_reorders_reproducibly() {
+    time::pause();
+    let first = arrival_order(42).await;
+    let second = arrival_order(42).await;
+    assert_eq!(10, first.len());
+    assert_eq!(first, second);
+    let sent = (0..10).map(|it| it.to_string()).collect::<Vec<_>>();
+    assert_ne!(sent, first);
+}
+
+#[tokio::test]
+async fn disconnect_closes_existing_connections() {
+    time::pause();
+    let net = SimNetwork::new(7);
+    let mock = MockRSocket::new();
+    mock.expect(Expectation::request_response().respond(Payload::from("pong")));
+    let cli = net.pair(mock.clone()).await;
+    cli.request_response(Payload::from("ping")).await.unwrap();
+
+    net.disconnect();
+    let res = time::timeout(
+        Duration::from_secs(30),
+        cli.request_response(Payload::from("ping")),
+    )
+    .await
+    .unwrap();
+    assert!(res.is_err());
+    assert_eq!(1, mock.count("request_response"));
+
+    let reconnected = net.pair(mock.clone()).await;
+    let res = reconnected
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap();
+    assert_eq!(Some("pong"), res.data_utf8());
+}
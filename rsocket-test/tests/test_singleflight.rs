@@ -0,0 +1,91 @@
+extern crate rsocket_rust;
+
+use futures::{future, stream};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::interceptor::Singleflight;
+use rsocket_rust::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Answers slowly with the number of requests it received.
+#[derive(Clone, Default)]
+struct Slow(Arc<AtomicUsize>);
+
+impl RSocket for Slow {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let n = self.0.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            tokio::time::delay_for(Duration::from_millis(50)).await;
+            if req.data_utf8() == Some("fail") {
+                return Err(RSocketError::from("failed"));
+            }
+            Ok(Payload::from(format!("{}", n)))
+        })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn coalesce_identical_requests() {
+    let slow = Slow::default();
+    let requester = Singleflight::new(slow.clone());
+    let mut calls: Vec<_> = (0..5)
+        .map(|_| requester.request_response(Payload::from_utf8_with_metadata("a", "m")))
+        .collect();
+    calls.push(requester.request_response(Payload::from("a")));
+    assert_eq!(2, requester.in_flight());
+    let results: Vec<_> = future::join_all(calls)
+        .await
+        .into_iter()
+        .map(|it| it.unwrap().data_utf8().unwrap().to_string())
+        .collect();
+    assert_eq!(vec!["0", "0", "0", "0", "0", "1"], results);
+    assert_eq!(2, slow.0.load(Ordering::SeqCst));
+    assert_eq!(0, requester.in_flight());
+
+    // only concurrent requests are coalesced.
+    let res = requester
+        .request_response(Payload::from("a"))
+        .await
+        .unwrap();
+    assert_eq!(Some("2"), res.data_utf8());
+
+    let calls = (0..3).map(|_| requester.request_response(Payload::from("fail")));
+    for it in future::join_all(calls).await {
+        assert_eq!("failed", it.unwrap_err().to_string());
+    }
+    assert_eq!(4, slow.0.load(Ordering::SeqCst));
+}
+
+#[tokio::main]
+#[test]
+async fn survive_cancelled_leader() {
+    let slow = Slow::default();
+    let requester = Singleflight::new(slow.clone());
+    let leader = requester.request_response(Payload::from("a"));
+    let follower = requester.request_response(Payload::from("a"));
+    drop(leader);
+    let res = follower.await.unwrap();
+    assert_eq!(Some("0"), res.data_utf8());
+    assert_eq!(1, slow.0.load(Ordering::SeqCst));
+}
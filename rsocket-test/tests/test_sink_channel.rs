@@ -0,0 +1,107 @@
+use futures::{future, stream};
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+
+/// Answers every payload twice, "fail" terminates the responses with an error.
+struct Responder;
+
+impl RSocket for Responder {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(future::ok(req))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        sink_channel(reqs, |mut reqs, mut responses| async move {
+            let mut n = 0;
+            while let Some(Ok(req)) = reqs.next().await {
+                let data = req.data_utf8().unwrap_or_default().to_string();
+                if data == "fail" {
+                    responses.error(RSocketError::from("boom")).await;
+                    return;
+                }
+                n += 1;
+                for suffix in &["a", "b"] {
+                    let res = Payload::builder()
+                        .set_data_utf8(&format!("{}.{}", data, suffix))
+                        .build();
+                    if responses.send(res).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            let res = Payload::builder()
+                .set_data_utf8(&format!("done:{}", n))
+                .build();
+            let _ = responses.send(res).await;
+        })
+    }
+}
+
+async fn connect() -> Client<DefaultSpawner> {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .acceptor(|_setup, _socket| Ok(Box::new(Responder)))
+            .serve(),
+    );
+    RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .start()
+        .await
+        .unwrap()
+}
+
+fn payloads(items: &[&str]) -> Flux<Result<Payload, RSocketError>> {
+    let items: Vec<Result<Payload, RSocketError>> = items
+        .iter()
+        .map(|it| Ok(Payload::builder().set_data_utf8(it).build()))
+        .collect();
+    Box::pin(stream::iter(items))
+}
+
+#[tokio::main]
+#[test]
+async fn respond_channel_through_sink() {
+    let cli = connect().await;
+    let mut results = cli.request_channel(payloads(&["x", "y"]));
+    let mut received = vec![];
+    while let Some(it) = results.next().await {
+        received.push(it.unwrap().data_utf8().unwrap().to_string());
+    }
+    assert_eq!(vec!["x.a", "x.b", "y.a", "y.b", "done:2"], received);
+}
+
+#[tokio::main]
+#[test]
+async fn fail_channel_through_sink() {
+    let cli = connect().await;
+    let mut results = cli.request_channel(payloads(&["x", "fail"]));
+    assert_eq!(
+        Some("x.a"),
+        results.next().await.unwrap().unwrap().data_utf8()
+    );
+    assert_eq!(
+        Some("x.b"),
+        results.next().await.unwrap().unwrap().data_utf8()
+    );
+    let e = results.next().await.unwrap().unwrap_err();
+    assert!(e.to_string().contains("boom"), "{}", e);
+}
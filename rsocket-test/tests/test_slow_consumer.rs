@@ -0,0 +1,137 @@
+use bytes::{BufMut, BytesMut};
+use futures::stream;
+use rsocket_rust::error::{self, RSocketError};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::transport::SlowConsumerPolicy;
+use rsocket_rust::utils::{Writeable, U24};
+use rsocket_rust_transport_tcp::TcpServerTransport;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::prelude::*;
+
+/// Responds streams with five items.
+struct FiveRSocket;
+
+impl RSocket for FiveRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        EchoRSocket.request_response(req)
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(
+            (0..5).map(|n| Ok(Payload::from(format!("{}", n)))),
+        ))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+async fn serve(addr: &'static str, policy: SlowConsumerPolicy) -> TcpStream {
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .slow_consumer(Duration::from_millis(100), policy)
+            .acceptor(|_setup, _socket| Ok(Box::new(FiveRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+    let mut conn = TcpStream::connect(addr).await.unwrap();
+    let setup = frame::Setup::builder(0, 0)
+        .set_mime_data("text/plain")
+        .set_mime_metadata("text/plain")
+        .build();
+    write_frame(&mut conn, setup).await;
+    conn
+}
+
+async fn write_frame(conn: &mut TcpStream, frame: Frame) {
+    let mut bf = BytesMut::new();
+    U24::write(frame.len() as u32, &mut bf);
+    frame.write_to(&mut bf);
+    conn.write_all(&bf).await.unwrap();
+}
+
+async fn read_frame(conn: &mut TcpStream) -> Frame {
+    let mut len = [0u8; 3];
+    conn.read_exact(&mut len).await.unwrap();
+    let mut bf = BytesMut::new();
+    bf.put_slice(&len);
+    let n = U24::read(&mut bf) as usize;
+    let mut b = vec![0u8; n];
+    conn.read_exact(&mut b).await.unwrap();
+    Frame::decode(&mut BytesMut::from(&b[..])).unwrap()
+}
+
+/// Returns the data of received PAYLOAD frames until COMPLETE or ERROR.
+async fn read_stream(conn: &mut TcpStream) -> (Vec<String>, Option<u32>) {
+    let mut items = vec![];
+    loop {
+        let frame = read_frame(conn).await;
+        let complete = frame.has_complete();
+        match frame.get_body() {
+            Body::Payload(v) => {
+                if let Some(b) = v.get_data() {
+                    items.push(String::from_utf8(b.to_vec()).unwrap());
+                }
+                if complete {
+                    return (items, None);
+                }
+            }
+            Body::Error(e) => return (items, Some(e.get_code())),
+            _ => (),
+        }
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn slow_consumer_error() {
+    let mut conn = serve("127.0.0.1:7810", SlowConsumerPolicy::Error).await;
+    let req = frame::RequestStream::builder(1, 0)
+        .set_initial_request_n(2)
+        .build();
+    write_frame(&mut conn, req).await;
+    let (items, err) = read_stream(&mut conn).await;
+    assert_eq!(vec!["0", "1"], items);
+    assert_eq!(Some(error::ERR_APPLICATION), err);
+
+    // the requester demands more in time.
+    let req = frame::RequestStream::builder(3, 0)
+        .set_initial_request_n(2)
+        .build();
+    write_frame(&mut conn, req).await;
+    write_frame(&mut conn, frame::RequestN::builder(3, 0).set_n(10).build()).await;
+    let (items, err) = read_stream(&mut conn).await;
+    assert_eq!(vec!["0", "1", "2", "3", "4"], items);
+    assert!(err.is_none());
+}
+
+#[tokio::main]
+#[test]
+async fn slow_consumer_drop_oldest() {
+    let mut conn = serve("127.0.0.1:7811", SlowConsumerPolicy::DropOldest(2)).await;
+    let req = frame::RequestStream::builder(1, 0)
+        .set_initial_request_n(1)
+        .build();
+    write_frame(&mut conn, req).await;
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+    write_frame(&mut conn, frame::RequestN::builder(1, 0).build()).await;
+    let (items, err) = read_stream(&mut conn).await;
+    assert_eq!(vec!["0", "3", "4"], items);
+    assert!(err.is_none());
+}
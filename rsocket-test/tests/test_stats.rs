@@ -0,0 +1,205 @@
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::{future, stream};
+use rsocket_rust::error::{self, RSocketError};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::router::Router;
+use rsocket_rust::test_helpers::loopback;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::time::Duration;
+
+#[tokio::main]
+#[test]
+async fn connection_stats() {
+    let addr = "127.0.0.1:7807";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|_setup, _socket| Ok(Box::new(Router::new().route("echo", EchoRSocket))))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap();
+    let req = || {
+        Payload::builder()
+            .set_data_utf8("Hello World!")
+            .metadata()
+            .route("echo")
+            .end()
+            .build()
+    };
+    cli.request_response(req()).await.unwrap();
+    let mut results = cli.request_stream(req());
+    while let Some(res) = results.next().await {
+        res.unwrap();
+    }
+    let stats = cli.stats();
+    assert_eq!(0, stats.get_active_streams());
+    assert!(stats.get_last_error().is_none());
+
+    let res = cli.request_response(Payload::from("Hello World!")).await;
+    assert!(res.is_err());
+
+    let stats = cli.stats();
+    assert_eq!(0, stats.get_active_streams());
+    assert_eq!(
+        Some(error::ERR_APPLICATION),
+        stats.get_last_error().map(|(code, _)| code)
+    );
+    // SETUP, 2 REQUEST_RESPONSE and REQUEST_STREAM.
+    assert_eq!(4, stats.get_frames_sent());
+    // 2 responses, 3 items and COMPLETE.
+    assert_eq!(6, stats.get_frames_received());
+    assert!(stats.get_bytes_sent() > 0);
+    assert!(stats.get_bytes_received() > 0);
+    assert!(stats.get_uptime() > Duration::from_millis(0));
+    assert!(stats.get_keepalive_rtt().is_none());
+}
+
+#[tokio::main]
+#[test]
+async fn keepalive_rtt() {
+    let addr = "127.0.0.1:7808";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .keepalive(Duration::from_millis(50), Duration::from_secs(5), 3)
+        .start()
+        .await
+        .unwrap();
+    assert!(cli.stats().get_keepalive_rtt().is_none());
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let stats = cli.stats();
+    let rtt = stats.get_keepalive_rtt().unwrap();
+    let ewma = stats.get_keepalive_rtt_ewma().unwrap();
+    assert!(rtt < Duration::from_millis(50));
+    assert!(ewma < Duration::from_millis(50));
+    assert!(stats.get_frames_received() >= 3);
+}
+
+/// Streams the same payload forever.
+struct Endless;
+
+impl RSocket for Endless {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(future::ok(req))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::repeat(Ok(req)))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+async fn next(incoming: &mut mpsc::Receiver<Frame>) -> Frame {
+    tokio::time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::main]
+#[test]
+async fn health_score() {
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let cli = RSocketFactory::connect()
+        .transport(client)
+        .acceptor(|| Box::new(Endless))
+        .start()
+        .await
+        .unwrap();
+    assert!(matches!(
+        next(&mut incoming).await.get_body_ref(),
+        Body::Setup(_)
+    ));
+    let health = cli.stats().get_health();
+    assert_eq!(1.0, health.get_score());
+
+    // the server demands one item of a stream of the client.
+    sending
+        .send(
+            frame::RequestStream::builder(2, 0)
+                .set_initial_request_n(1)
+                .set_data(Bytes::from("tick"))
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert!(matches!(
+        next(&mut incoming).await.get_body_ref(),
+        Body::Payload(_)
+    ));
+    tokio::time::delay_for(Duration::from_millis(50)).await;
+
+    let requester = cli.clone();
+    let failing =
+        tokio::spawn(async move { requester.request_response(Payload::from("ping")).await });
+    let sid = next(&mut incoming).await.get_stream_id();
+    sending
+        .send(
+            frame::Error::builder(sid, 0)
+                .set_code(error::ERR_APPLICATION)
+                .set_data(Bytes::from("failed"))
+                .build(),
+        )
+        .await
+        .unwrap();
+    assert!(failing.await.unwrap().is_err());
+
+    let stats = cli.stats();
+    assert_eq!(1, stats.get_stalled_streams());
+    let health = stats.get_health();
+    assert_eq!(1.0, health.get_error_rate());
+    assert_eq!(1.0, health.get_saturation());
+    assert_eq!(0.0, health.get_score());
+
+    let requester = cli.clone();
+    let succeeding =
+        tokio::spawn(async move { requester.request_response(Payload::from("ping")).await });
+    let sid = next(&mut incoming).await.get_stream_id();
+    sending
+        .send(
+            frame::Payload::builder(sid, frame::FLAG_NEXT | frame::FLAG_COMPLETE)
+                .set_data(Bytes::from("pong"))
+                .build(),
+        )
+        .await
+        .unwrap();
+    succeeding.await.unwrap().unwrap();
+    let health = cli.stats().get_health();
+    assert_eq!(0.5, health.get_error_rate());
+    assert_eq!(0.25, health.get_score());
+}
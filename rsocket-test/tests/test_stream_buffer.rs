@@ -0,0 +1,124 @@
+use futures::channel::mpsc;
+use futures::stream;
+use rsocket_rust::error::{self, RSocketError};
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::LoopbackServerTransport;
+use rsocket_rust::transport::OverflowPolicy;
+use std::time::Duration;
+use tokio::time;
+
+/// Responds streams with five items.
+struct FiveRSocket;
+
+impl RSocket for FiveRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        EchoRSocket.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        EchoRSocket.request_response(req)
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(
+            (0..5).map(|n| Ok(Payload::from(format!("{}", n)))),
+        ))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        EchoRSocket.request_channel(reqs)
+    }
+}
+
+/// Request a stream of one item from a server buffering 2 items by `policy`, then request
+/// the rest once the producer has finished.
+async fn request_slowly(policy: OverflowPolicy) -> (Vec<String>, Option<u32>) {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .stream_buffer(2, policy)
+            .acceptor(|_setup, _socket| Ok(Box::new(FiveRSocket)))
+            .serve(),
+    );
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    connector
+        .connect()
+        .unwrap()
+        .attach(incoming_tx, sending_rx, None);
+    sending
+        .send(frame::Setup::builder(0, 0).build())
+        .await
+        .unwrap();
+    let req = frame::RequestStream::builder(1, 0)
+        .set_initial_request_n(1)
+        .build();
+    sending.send(req).await.unwrap();
+    time::delay_for(Duration::from_millis(200)).await;
+    sending
+        .send(frame::RequestN::builder(1, 0).build())
+        .await
+        .unwrap();
+    read_stream(&mut incoming).await
+}
+
+/// Returns the data of received PAYLOAD frames until COMPLETE or ERROR.
+async fn read_stream(incoming: &mut mpsc::Receiver<Frame>) -> (Vec<String>, Option<u32>) {
+    let mut items = vec![];
+    loop {
+        let frame = time::timeout(Duration::from_secs(3), incoming.next())
+            .await
+            .unwrap()
+            .unwrap();
+        let complete = frame.has_complete();
+        match frame.get_body() {
+            Body::Payload(v) => {
+                if let Some(b) = v.get_data() {
+                    items.push(String::from_utf8(b.to_vec()).unwrap());
+                }
+                if complete {
+                    return (items, None);
+                }
+            }
+            Body::Error(e) => return (items, Some(e.get_code())),
+            _ => (),
+        }
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn suspend_producer_when_buffer_is_full() {
+    let (items, err) = request_slowly(OverflowPolicy::Suspend).await;
+    assert_eq!(vec!["0", "1", "2", "3", "4"], items);
+    assert!(err.is_none());
+}
+
+#[tokio::main]
+#[test]
+async fn drop_items_when_buffer_is_full() {
+    let (items, err) = request_slowly(OverflowPolicy::DropOldest).await;
+    assert_eq!(vec!["0", "3", "4"], items);
+    assert!(err.is_none());
+
+    let (items, err) = request_slowly(OverflowPolicy::DropNewest).await;
+    assert_eq!(vec!["0", "1", "2"], items);
+    assert!(err.is_none());
+}
+
+#[tokio::main]
+#[test]
+async fn error_stream_when_buffer_is_full() {
+    let (items, err) = request_slowly(OverflowPolicy::Error).await;
+    assert_eq!(vec!["0"], items);
+    assert_eq!(Some(error::ERR_APPLICATION), err);
+}
@@ -0,0 +1,67 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::time::Duration;
+
+async fn serve(addr: &'static str) {
+    // the server requests the client, whose responder side allocates per-stream states.
+    RSocketFactory::receive()
+        .transport(TcpServerTransport::from(addr))
+        .acceptor(|_setup, socket| {
+            tokio::spawn(async move {
+                tokio::time::delay_for(Duration::from_millis(100)).await;
+                for _ in 0..20 {
+                    socket
+                        .request_response(Payload::from("Hello World!"))
+                        .await
+                        .unwrap();
+                    tokio::time::delay_for(Duration::from_millis(10)).await;
+                }
+            });
+            Ok(Box::new(EchoRSocket))
+        })
+        .serve()
+        .await
+        .unwrap();
+}
+
+#[tokio::main]
+#[test]
+async fn reuse_stream_states() {
+    let addr = "127.0.0.1:7817";
+    tokio::spawn(serve(addr));
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .acceptor(|| Box::new(EchoRSocket))
+        .start()
+        .await
+        .unwrap();
+    tokio::time::delay_for(Duration::from_millis(800)).await;
+    let stats = cli.stats();
+    assert_eq!(
+        20,
+        stats.get_stream_pool_hits() + stats.get_stream_pool_misses()
+    );
+    assert!(stats.get_stream_pool_hits() >= 10);
+}
+
+#[tokio::main]
+#[test]
+async fn disable_stream_pool() {
+    let addr = "127.0.0.1:7818";
+    tokio::spawn(serve(addr));
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .acceptor(|| Box::new(EchoRSocket))
+        .stream_pool_size(0)
+        .start()
+        .await
+        .unwrap();
+    tokio::time::delay_for(Duration::from_millis(800)).await;
+    let stats = cli.stats();
+    assert_eq!(0, stats.get_stream_pool_hits());
+    assert_eq!(20, stats.get_stream_pool_misses());
+}
@@ -0,0 +1,139 @@
+use rsocket_rust::tck::{
+    parse_client_script, Assertion, ClientDriver, Interaction, Marble, Signal, Step, TckResponder,
+};
+use rsocket_rust::test_helpers::pair;
+use std::time::Duration;
+
+const SERVER: &str = r#"
+rr%%hello%%world%%a|&&{"a":{"hi":"there"}}
+rr%%boom%%%%#
+rs%%count%%%%-a-b-c-|
+rs%%endless%%%%ab
+fnf%%ping%%
+EOF
+"#;
+
+const CLIENT: &str = r#"
+!
+name%%requestResponse
+pass
+subscribe%%rr%%r1%%hello%%world
+request%%1%%r1
+await%%terminal%%r1
+assert%%no_error%%r1
+assert%%completed%%r1
+assert%%received%%r1%%hi,there
+!
+name%%requestResponseError
+subscribe%%rr%%r2%%boom%%
+request%%1%%r2
+await%%terminal%%r2
+assert%%error%%r2
+!
+name%%streamDemand
+subscribe%%rs%%s1%%count%%
+request%%2%%s1
+await%%atLeast%%s1%%2%%1000
+await%%no_events%%s1%%50
+assert%%received_n%%s1%%2
+assert%%no_completed%%s1
+request%%5%%s1
+await%%terminal%%s1
+assert%%received%%s1%%a,&&b,&&c,
+assert%%completed%%s1
+!
+name%%streamCancel
+subscribe%%rs%%s2%%endless%%
+request%%10%%s2
+await%%atLeast%%s2%%2%%1000
+cancel%%s2
+assert%%canceled%%s2
+assert%%no_completed%%s2
+!
+name%%fireAndForget
+subscribe%%fnf%%f1%%ping%%
+await%%terminal%%f1
+assert%%completed%%f1
+!
+name%%expectedFailure
+fail
+subscribe%%rr%%r3%%hello%%world
+request%%1%%r3
+await%%terminal%%r3
+assert%%received_n%%r3%%2
+EOF
+"#;
+
+#[test]
+fn parse_client_tests() {
+    let tests = parse_client_script(CLIENT).unwrap();
+    assert_eq!(6, tests.len());
+    assert_eq!("requestResponse", tests[0].get_name());
+    assert!(tests[0].is_expected_to_pass());
+    assert!(!tests[5].is_expected_to_pass());
+    assert_eq!(
+        Step::Subscribe {
+            interaction: Interaction::RequestResponse,
+            id: "r1".to_string(),
+            data: "hello".to_string(),
+            metadata: "world".to_string(),
+        },
+        tests[0].get_steps()[0]
+    );
+    assert_eq!(
+        Step::AwaitAtLeast {
+            id: "s1".to_string(),
+            n: 2,
+            timeout: Duration::from_millis(1000),
+        },
+        tests[2].get_steps()[2]
+    );
+    assert_eq!(
+        Step::Assert {
+            id: "r1".to_string(),
+            assertion: Assertion::Received(vec![("hi".to_string(), "there".to_string())]),
+        },
+        tests[0].get_steps()[5]
+    );
+
+    assert!(parse_client_script("subscribe%%rr%%a%%b%%c").is_err());
+    assert!(parse_client_script("!\nsubscribe%%channel%%a%%b%%c").is_err());
+    assert!(parse_client_script("!\nrequest%%many%%a").is_err());
+}
+
+#[test]
+fn parse_marbles() {
+    let marble = Marble::parse(r#"-a-b-|&&{"a":{"x":"y"}}"#).unwrap();
+    assert_eq!(
+        &[
+            Signal::Next("x".to_string(), "y".to_string()),
+            Signal::Next("b".to_string(), String::new()),
+            Signal::Complete,
+        ],
+        marble.get_signals()
+    );
+    assert_eq!(&[Signal::Error], Marble::parse("#a").unwrap().get_signals());
+    assert!(Marble::parse("a^").is_err());
+    assert!(Marble::parse("a|&&{").is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn run_client_against_server_script() {
+    let responder = TckResponder::parse(SERVER).unwrap();
+    let cli = pair(responder).await;
+    let tests = parse_client_script(CLIENT).unwrap();
+    let results = ClientDriver::new()
+        .timeout(Duration::from_secs(5))
+        .run_all(&cli, &tests)
+        .await;
+    for result in &results {
+        assert!(
+            result.is_ok(),
+            "{}: {:?}",
+            result.get_name(),
+            result.get_failure()
+        );
+    }
+    assert!(results[5].get_failure().is_some());
+}
@@ -0,0 +1,111 @@
+use futures::stream;
+use rsocket_rust::error::RSocketError;
+use rsocket_rust::prelude::*;
+use rsocket_rust::runtime::DefaultSpawner;
+use rsocket_rust::tenant::Tenants;
+use rsocket_rust::test_helpers::{LoopbackConnector, LoopbackServerTransport};
+use std::time::Duration;
+use tokio::time;
+
+const TENANT: &str = "application/x.tenant";
+
+/// Responds with the name of its tenant.
+#[derive(Clone)]
+struct Named(&'static str);
+
+impl RSocket for Named {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let msg = format!("{}: {}", self.0, req.data_utf8().unwrap_or_default());
+        Box::pin(async move { Ok(Payload::from(msg)) })
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::empty())
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        reqs
+    }
+}
+
+fn serve(tenants: Tenants) -> LoopbackConnector {
+    let (server, connector) = LoopbackServerTransport::new();
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(server)
+            .tenants(tenants)
+            .serve(),
+    );
+    connector
+}
+
+async fn connect(connector: &LoopbackConnector, tenant: Option<&str>) -> Client<DefaultSpawner> {
+    let setup = match tenant {
+        Some(tenant) => Payload::builder().metadata().custom(TENANT, tenant).build(),
+        None => Payload::from("anyone"),
+    };
+    RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .setup(setup)
+        .start()
+        .await
+        .unwrap()
+}
+
+async fn ask(cli: &Client<DefaultSpawner>) -> String {
+    let res = cli.request_response(Payload::from("hi")).await.unwrap();
+    res.data_utf8().unwrap().to_string()
+}
+
+#[tokio::main]
+#[test]
+async fn dispatch_connections_by_tenant() {
+    let connector = serve(
+        Tenants::by_metadata(TENANT)
+            .tenant("acme", |_setup, _socket| Ok(Box::new(Named("acme"))))
+            .tenant("globex", |_setup, _socket| Ok(Box::new(Named("globex")))),
+    );
+    let acme = connect(&connector, Some("acme")).await;
+    let globex = connect(&connector, Some("globex")).await;
+    assert_eq!("acme: hi", ask(&acme).await);
+    assert_eq!("globex: hi", ask(&globex).await);
+    assert_eq!("acme: hi", ask(&acme).await);
+
+    for tenant in &[Some("initech"), None] {
+        let rejected = connect(&connector, *tenant).await;
+        time::delay_for(Duration::from_millis(50)).await;
+        assert!(rejected.is_closed());
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn serve_unknown_tenants_by_fallback() {
+    let connector = serve(
+        Tenants::by(|setup| setup.data_mime_type().clone())
+            .tenant("application/json", |_setup, _socket| {
+                Ok(Box::new(Named("v2")))
+            })
+            .fallback(|_setup, _socket| Ok(Box::new(Named("v1")))),
+    );
+    let v2 = RSocketFactory::connect()
+        .transport(connector.connect().unwrap())
+        .data_mime_type("application/json")
+        .start()
+        .await
+        .unwrap();
+    assert_eq!("v2: hi", ask(&v2).await);
+    let v1 = connect(&connector, None).await;
+    assert_eq!("v1: hi", ask(&v1).await);
+}
@@ -0,0 +1,164 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{
+    Handshake, TcpClientTransport, TcpServerTransport, TlsAcceptor, TlsClientConfig, TlsConnector,
+    TlsServerConfig, TlsStream,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time;
+
+/// A handshake in the clear, which negotiates ALPN like TLS: the client sends the server
+/// name and its protocols, the server answers with the protocol it selected.
+struct PlainConnector {
+    server_names: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+struct PlainAcceptor;
+
+impl TlsConnector for PlainConnector {
+    fn connect(
+        &self,
+        config: &TlsClientConfig,
+        server_name: Option<&str>,
+        stream: TcpStream,
+    ) -> Handshake {
+        self.server_names
+            .lock()
+            .unwrap()
+            .push(server_name.map(String::from));
+        let offered = config
+            .get_alpn_protocols()
+            .iter()
+            .map(|it| String::from_utf8(it.clone()).unwrap())
+            .collect::<Vec<_>>()
+            .join(",");
+        Box::pin(async move {
+            let mut stream = BufReader::new(stream);
+            stream
+                .get_mut()
+                .write_all(format!("{}\n", offered).as_bytes())
+                .await?;
+            let mut selected = String::new();
+            stream.read_line(&mut selected).await?;
+            let selected = selected.trim_end();
+            let alpn = if selected.is_empty() {
+                None
+            } else {
+                Some(selected.as_bytes().to_vec())
+            };
+            Ok(TlsStream::new(stream.into_inner(), alpn))
+        })
+    }
+}
+
+impl TlsAcceptor for PlainAcceptor {
+    fn accept(&self, config: &TlsServerConfig, stream: TcpStream) -> Handshake {
+        let config = config.clone();
+        Box::pin(async move {
+            let mut stream = BufReader::new(stream);
+            let mut offered = String::new();
+            stream.read_line(&mut offered).await?;
+            let offered = offered
+                .trim_end()
+                .split(',')
+                .map(|it| it.as_bytes())
+                .collect::<Vec<_>>();
+            let selected = config.select_alpn(&offered).map(|it| it.to_vec());
+            let answer = selected.clone().unwrap_or_default();
+            stream.get_mut().write_all(&answer).await?;
+            stream.get_mut().write_all(b"\n").await?;
+            Ok(TlsStream::new(stream.into_inner(), selected))
+        })
+    }
+}
+
+fn serve(addr: &'static str) {
+    tokio::spawn(
+        RSocketFactory::receive()
+            .transport(
+                TcpServerTransport::from(addr).tls(
+                    PlainAcceptor,
+                    TlsServerConfig::new()
+                        .alpn_protocol(b"rsocket")
+                        .alpn_protocol(b"rsocket-legacy"),
+                ),
+            )
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve(),
+    );
+}
+
+#[test]
+fn select_alpn_by_preference_of_server() {
+    let config = TlsServerConfig::new()
+        .alpn_protocol(b"rsocket")
+        .alpn_protocol(b"h2");
+    assert_eq!(
+        Some(&b"rsocket"[..]),
+        config.select_alpn(&[b"h2", b"rsocket"])
+    );
+    assert_eq!(None, config.select_alpn(&[b"http/1.1"]));
+    assert_eq!(None, TlsServerConfig::new().select_alpn(&[b"h2"]));
+}
+
+#[tokio::main]
+#[test]
+async fn connect_with_alpn_and_sni() {
+    serve("127.0.0.1:7824");
+    time::delay_for(Duration::from_millis(200)).await;
+
+    let server_names = Arc::new(Mutex::new(Vec::new()));
+    let tp = TcpClientTransport::from("127.0.0.1:7824").tls(
+        PlainConnector {
+            server_names: server_names.clone(),
+        },
+        TlsClientConfig::new()
+            .alpn_protocol(b"h2")
+            .alpn_protocol(b"rsocket")
+            .server_name("rsocket.example.com"),
+    );
+    let client = RSocketFactory::connect()
+        .transport(tp)
+        .start()
+        .await
+        .unwrap();
+    let res = client
+        .request_response(Payload::from("ping"))
+        .await
+        .unwrap();
+    assert_eq!(Some("ping"), res.data_utf8());
+    assert_eq!(
+        vec![Some(String::from("rsocket.example.com"))],
+        *server_names.lock().unwrap()
+    );
+}
+
+#[tokio::main]
+#[test]
+async fn close_connection_without_alpn_of_server() {
+    serve("127.0.0.1:7825");
+    time::delay_for(Duration::from_millis(200)).await;
+
+    let server_names = Arc::new(Mutex::new(Vec::new()));
+    let tp = TcpClientTransport::from("127.0.0.1:7825").tls(
+        PlainConnector {
+            server_names: server_names.clone(),
+        },
+        TlsClientConfig::new().alpn_protocol(b"h2"),
+    );
+    let client = RSocketFactory::connect()
+        .transport(tp)
+        .start()
+        .await
+        .unwrap();
+    let res = time::timeout(
+        Duration::from_secs(3),
+        client.request_response(Payload::from("ping")),
+    )
+    .await;
+    assert!(!matches!(res, Ok(Ok(_))));
+    // no SNI is sent without a server name.
+    assert_eq!(vec![None], *server_names.lock().unwrap());
+}
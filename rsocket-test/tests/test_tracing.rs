@@ -0,0 +1,136 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+#[derive(Clone, Default)]
+struct Fields(HashMap<String, String>);
+
+impl Fields {
+    fn get(&self, name: &str) -> Option<&String> {
+        self.0.get(name)
+    }
+}
+
+#[derive(Clone, Default)]
+struct SpanCollector {
+    seq: Arc<AtomicU64>,
+    spans: Arc<Mutex<HashMap<u64, Fields>>>,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl SpanCollector {
+    fn find(&self, side: &str, interaction: &str) -> Vec<Fields> {
+        self.spans
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|it| {
+                it.get("side").map(|s| s.as_str()) == Some(side)
+                    && it.get("interaction").map(|s| s.as_str()) == Some(interaction)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl Subscriber for SpanCollector {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.name() == "rsocket"
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut fields = Fields::default();
+        span.record(&mut fields);
+        self.spans.lock().unwrap().insert(id, fields);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(fields) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            values.record(fields);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[tokio::main]
+#[test]
+async fn request_spans() {
+    let collector = SpanCollector::default();
+    tracing::subscriber::set_global_default(collector.clone()).unwrap();
+
+    let addr = "127.0.0.1:7805";
+    tokio::spawn(async move {
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(addr))
+            .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+            .serve()
+            .await
+    });
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let cli = RSocketFactory::connect()
+        .transport(TcpClientTransport::from(addr))
+        .start()
+        .await
+        .unwrap();
+
+    let req = Payload::builder()
+        .set_data_utf8("Hello World!")
+        .metadata()
+        .route("echo")
+        .end()
+        .build();
+    cli.request_response(req).await.unwrap();
+    let mut results = cli.request_stream(Payload::from("Hello World!"));
+    while let Some(res) = results.next().await {
+        res.unwrap();
+    }
+    let mut results = cli.request_stream(Payload::from("Hello World!"));
+    results.next().await;
+    drop(results);
+    tokio::time::delay_for(Duration::from_millis(300)).await;
+
+    let spans = collector.find("requester", "request_response");
+    assert_eq!(1, spans.len());
+    assert_eq!(Some(&String::from("ok")), spans[0].get("outcome"));
+    assert_eq!(Some(&String::from("echo")), spans[0].get("route"));
+    assert_eq!(Some(&String::from("1")), spans[0].get("stream_id"));
+
+    let spans = collector.find("responder", "request_response");
+    assert_eq!(1, spans.len());
+    assert_eq!(Some(&String::from("echo")), spans[0].get("route"));
+
+    let mut outcomes = collector
+        .find("requester", "request_stream")
+        .into_iter()
+        .map(|it| it.get("outcome").cloned().unwrap())
+        .collect::<Vec<_>>();
+    outcomes.sort();
+    assert_eq!(vec!["cancelled", "ok"], outcomes);
+}
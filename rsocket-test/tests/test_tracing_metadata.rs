@@ -0,0 +1,81 @@
+extern crate rsocket_rust;
+
+use bytes::BytesMut;
+use rsocket_rust::extension::{Sampling, TracingMetadata};
+use rsocket_rust::interceptor::{ZipkinExtractor, ZipkinInjector};
+use rsocket_rust::prelude::*;
+use rsocket_rust::utils::Writeable;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn tracing_metadata_codec() {
+    let spans = vec![
+        TracingMetadata::builder()
+            .set_sampling(Sampling::NotSampled)
+            .build(),
+        TracingMetadata::builder()
+            .set_sampling(Sampling::Sampled)
+            .set_trace_id(1234)
+            .set_span_id(5678)
+            .build(),
+        TracingMetadata::builder()
+            .set_sampling(Sampling::Debug)
+            .set_trace_id_high(1)
+            .set_trace_id(2)
+            .set_span_id(3)
+            .set_parent_id(4)
+            .build(),
+    ];
+    for span in spans {
+        let mut bf = BytesMut::new();
+        span.write_to(&mut bf);
+        assert_eq!(span.len(), bf.len());
+        let decoded = TracingMetadata::decode(&mut bf).unwrap();
+        assert_eq!(span, decoded);
+    }
+    let mut bf = BytesMut::from(&[0xA0, 0x00, 0x01][..]);
+    assert!(TracingMetadata::decode(&mut bf).is_err());
+}
+
+#[test]
+fn tracing_metadata_b3() {
+    let b3 = "80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1-1-05e3ac9a4f6e3b90";
+    let span = TracingMetadata::from_b3(b3).unwrap();
+    assert_eq!(Sampling::Sampled, span.get_sampling());
+    assert_eq!(0x80f1_98ee_5634_3ba8, span.get_trace_id_high());
+    assert_eq!(0x64fe_8b2a_57d3_eff7, span.get_trace_id());
+    assert_eq!(0xe457_b5a2_e4d8_6bd1, span.get_span_id());
+    assert_eq!(Some(0x05e3_ac9a_4f6e_3b90), span.get_parent_id());
+    assert_eq!(b3, span.to_b3());
+    assert!(TracingMetadata::from_b3("0").unwrap().is_empty());
+    assert!(TracingMetadata::from_b3("xyz-abc").is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn tracing_interceptors() {
+    let parent = TracingMetadata::new_root(Sampling::Sampled);
+    let injected = Arc::new(Mutex::new(vec![]));
+    let extracted = Arc::new(Mutex::new(vec![]));
+
+    let injected2 = injected.clone();
+    let extracted2 = extracted.clone();
+    let parent2 = parent.clone();
+    let requester = ZipkinInjector::new(ZipkinExtractor::new(EchoRSocket, move |span| {
+        extracted2.lock().unwrap().push(span.clone())
+    }))
+    .current_span(move || Some(parent2.clone()))
+    .on_inject(move |span| injected2.lock().unwrap().push(span.clone()));
+
+    requester
+        .request_response(Payload::from("Hello World!"))
+        .await
+        .unwrap();
+
+    let injected = injected.lock().unwrap();
+    let extracted = extracted.lock().unwrap();
+    assert_eq!(1, extracted.len());
+    assert_eq!(*injected, *extracted);
+    assert_eq!(parent.get_trace_id(), extracted[0].get_trace_id());
+    assert_eq!(Some(parent.get_span_id()), extracted[0].get_parent_id());
+}
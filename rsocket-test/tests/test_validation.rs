@@ -0,0 +1,137 @@
+mod common;
+
+use bytes::Bytes;
+use futures::channel::mpsc;
+use rsocket_rust::error;
+use rsocket_rust::frame::{self, Body, Frame};
+use rsocket_rust::prelude::*;
+use rsocket_rust::test_helpers::loopback;
+use rsocket_rust::transport::ValidationMode;
+use std::time::Duration;
+use tokio::time;
+
+/// Connect a raw client to a server of `mode`, SETUP is sent.
+async fn connect_validating(
+    mode: ValidationMode,
+) -> (mpsc::Receiver<Frame>, tokio::sync::mpsc::Sender<Frame>) {
+    common::serve_raw(
+        move |tp| {
+            RSocketFactory::receive()
+                .transport(tp)
+                .validation(mode)
+                .acceptor(|_setup, _socket| Ok(Box::new(EchoRSocket)))
+                .serve()
+        },
+        frame::Setup::builder(0, 0).build(),
+    )
+    .await
+}
+
+fn request_response(sid: u32, data: &'static str) -> Frame {
+    frame::RequestResponse::builder(sid, 0)
+        .set_data(Bytes::from(data))
+        .build()
+}
+
+async fn next(incoming: &mut mpsc::Receiver<Frame>) -> Frame {
+    time::timeout(Duration::from_secs(3), incoming.next())
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+fn connection_error_of(frame: Frame) -> String {
+    assert_eq!(0, frame.get_stream_id());
+    match frame.get_body() {
+        Body::Error(e) => {
+            assert_eq!(error::ERR_CONN_FAILED, e.get_code());
+            e.get_data_utf8()
+        }
+        other => panic!("unexpected frame: {:?}", other),
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn close_connection_in_strict_mode() {
+    let violations = vec![
+        (frame::RequestN::builder(1, 0).set_n(0).build(), "REQUEST_N"),
+        (request_response(2, "ping"), "stream ID"),
+        (
+            frame::Payload::builder(1, 0).build(),
+            "neither NEXT nor COMPLETE",
+        ),
+        (frame::Keepalive::builder(1, 0).build(), "stream 0"),
+        (frame::Setup::builder(0, 0).build(), "unexpected SETUP"),
+    ];
+    for (violation, expected) in violations {
+        let (mut incoming, mut sending) = connect_validating(ValidationMode::Strict).await;
+        sending.send(violation).await.unwrap();
+        let reason = connection_error_of(next(&mut incoming).await);
+        assert!(reason.contains(expected), "{}", reason);
+    }
+}
+
+#[tokio::main]
+#[test]
+async fn ignore_violations_in_lenient_mode() {
+    let (mut incoming, mut sending) = connect_validating(ValidationMode::Lenient).await;
+    sending.send(request_response(2, "even")).await.unwrap();
+    sending.send(request_response(0, "zero")).await.unwrap();
+    sending
+        .send(frame::RequestN::builder(1, 0).set_n(0).build())
+        .await
+        .unwrap();
+    sending.send(request_response(1, "ping")).await.unwrap();
+    let res = next(&mut incoming).await;
+    assert_eq!(1, res.get_stream_id());
+    match res.get_body() {
+        Body::Payload(v) => assert_eq!(&Some(Bytes::from("ping")), v.get_data()),
+        other => panic!("unexpected frame: {:?}", other),
+    }
+    assert!(time::timeout(Duration::from_millis(100), incoming.next())
+        .await
+        .is_err());
+}
+
+#[tokio::main]
+#[test]
+async fn terminate_streams_of_strict_client() {
+    let (client, server) = loopback();
+    let (incoming_tx, mut incoming) = mpsc::channel(1024);
+    let (mut sending, sending_rx) = tokio::sync::mpsc::channel(16);
+    server.attach(incoming_tx, sending_rx, None);
+    let cli = RSocketFactory::connect()
+        .transport(client)
+        .validation(ValidationMode::Strict)
+        .start()
+        .await
+        .unwrap();
+    assert!(matches!(
+        next(&mut incoming).await.get_body_ref(),
+        Body::Setup(_)
+    ));
+    let requester = cli.clone();
+    let pending = tokio::spawn(async move {
+        requester
+            .request_response(Payload::from("ping"))
+            .await
+            .unwrap_err()
+            .to_string()
+    });
+    assert!(matches!(
+        next(&mut incoming).await.get_body_ref(),
+        Body::RequestResponse(_)
+    ));
+    // a server never sends SETUP.
+    sending
+        .send(frame::Setup::builder(0, 0).build())
+        .await
+        .unwrap();
+    let reason = connection_error_of(next(&mut incoming).await);
+    assert_eq!("unexpected SETUP", reason);
+    assert_eq!(
+        "ERROR(CONN_FAILED): unexpected SETUP",
+        pending.await.unwrap()
+    );
+}
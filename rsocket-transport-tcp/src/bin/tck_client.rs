@@ -0,0 +1,58 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust::tck::{parse_client_script, ClientDriver};
+use rsocket_rust_transport_tcp::TcpClientTransport;
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 {
+        eprintln!("usage: rsocket-tck-client <addr> <script>...");
+        process::exit(2);
+    }
+    let mut tests = vec![];
+    for path in &args[1..] {
+        let src = fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("read {} failed: {}", path, e);
+            process::exit(2)
+        });
+        match parse_client_script(&src) {
+            Ok(it) => tests.extend(it),
+            Err(e) => {
+                eprintln!("parse {} failed: {}", path, e);
+                process::exit(2)
+            }
+        }
+    }
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let failed = rt.block_on(async {
+        let cli = RSocketFactory::connect()
+            .transport(TcpClientTransport::from(args[0].as_str()))
+            .start()
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("connect {} failed: {}", args[0], e);
+                process::exit(2)
+            });
+        let results = ClientDriver::new().run_all(&cli, &tests).await;
+        let mut failed = 0;
+        for result in &results {
+            if result.is_ok() {
+                println!("test {} ... ok", result.get_name());
+            } else {
+                failed += 1;
+                println!(
+                    "test {} ... FAILED: {}",
+                    result.get_name(),
+                    result.get_failure().unwrap_or("expected to fail")
+                );
+            }
+        }
+        println!("{} passed, {} failed", results.len() - failed, failed);
+        failed
+    });
+    if failed > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,38 @@
+use rsocket_rust::prelude::*;
+use rsocket_rust::tck::TckResponder;
+use rsocket_rust_transport_tcp::TcpServerTransport;
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::OnceLock;
+
+// acceptors are plain functions.
+static RESPONDER: OnceLock<TckResponder> = OnceLock::new();
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() != 2 {
+        eprintln!("usage: rsocket-tck-server <addr> <script>");
+        process::exit(2);
+    }
+    let responder = fs::read_to_string(&args[1])
+        .map_err(|e| e.to_string())
+        .and_then(|src| TckResponder::parse(&src).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            eprintln!("load {} failed: {}", args[1], e);
+            process::exit(2)
+        });
+    RESPONDER.set(responder).unwrap();
+    let mut rt = tokio::runtime::Runtime::new().unwrap();
+    let served = rt.block_on(
+        RSocketFactory::receive()
+            .transport(TcpServerTransport::from(args[0].as_str()))
+            .acceptor(|_setup, _socket| Ok(Box::new(RESPONDER.get().unwrap().clone())))
+            .on_start(|| println!("tck server started"))
+            .serve(),
+    );
+    if let Err(e) = served {
+        eprintln!("serve failed: {}", e);
+        process::exit(1);
+    }
+}
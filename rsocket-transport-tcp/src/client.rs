@@ -1,36 +1,82 @@
-use super::codec::LengthBasedFrameCodec;
+use super::codec::{FrameWriter, LengthBasedFrameCodec};
+use super::flush::{self, FlushStrategy};
+use super::tls::{Io, Tls, TlsClientConfig, TlsConnector};
 use futures::{SinkExt, StreamExt};
 use rsocket_rust::error::RSocketError;
 use rsocket_rust::frame::Frame;
 use rsocket_rust::runtime::{DefaultSpawner, Spawner};
-use rsocket_rust::transport::{ClientTransport, Rx, Tx, TxOnce};
-use std::future::Future;
+use rsocket_rust::transport::{ClientTransport, PeerInfo, RxBounded, Tx, TxOnce};
 use std::net::SocketAddr;
 use std::net::TcpStream as StdTcpStream;
-use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
-use tokio_util::codec::Framed;
+use tokio_util::codec::FramedRead;
 
 enum Connector {
     Direct(TcpStream),
     Lazy(SocketAddr),
 }
 
+enum Connected {
+    Plain(TcpStream),
+    Tls(Box<dyn Io>),
+}
+
 pub struct TcpClientTransport {
     connector: Connector,
+    flush: FlushStrategy,
+    tls: Option<Tls>,
 }
 
 impl TcpClientTransport {
     #[inline]
     fn new(connector: Connector) -> TcpClientTransport {
-        TcpClientTransport { connector }
+        TcpClientTransport {
+            connector,
+            flush: FlushStrategy::default(),
+            tls: None,
+        }
+    }
+
+    /// Set how outbound frames are written, see `FlushStrategy`.
+    pub fn flush_strategy(mut self, strategy: FlushStrategy) -> TcpClientTransport {
+        self.flush = strategy;
+        self
+    }
+
+    /// Connect over TLS by `connector`, see `TlsClientConfig` for ALPN and SNI.
+    pub fn tls<C>(mut self, connector: C, config: TlsClientConfig) -> TcpClientTransport
+    where
+        C: TlsConnector + 'static,
+    {
+        self.tls = Some(Tls::Connect(Arc::new(connector), config));
+        self
+    }
+
+    pub(crate) fn accept_tls(mut self, tls: Option<Tls>) -> TcpClientTransport {
+        self.tls = tls;
+        self
     }
 
     #[inline]
-    async fn connect(self) -> Result<TcpStream, RSocketError> {
-        match self.connector {
+    async fn connect(self) -> Result<Connected, RSocketError> {
+        let tls = self.tls;
+        let stream = Self::dial(self.connector).await?;
+        match tls {
+            Some(tls) => match tls.handshake(stream).await {
+                Ok(io) => Ok(Connected::Tls(io)),
+                Err(e) => Err(RSocketError::from(e)),
+            },
+            None => Ok(Connected::Plain(stream)),
+        }
+    }
+
+    #[inline]
+    async fn dial(connector: Connector) -> Result<TcpStream, RSocketError> {
+        match connector {
             Connector::Direct(stream) => Ok(stream),
-            Connector::Lazy(addr) => match StdTcpStream::connect(&addr) {
+            Connector::Lazy(addr) => match StdTcpStream::connect(addr) {
                 Ok(raw) => match TcpStream::from_std(raw) {
                     Ok(stream) => Ok(stream),
                     Err(e) => Err(RSocketError::from(e)),
@@ -42,29 +88,35 @@ impl TcpClientTransport {
 }
 
 impl ClientTransport for TcpClientTransport {
+    fn peer(&self) -> PeerInfo {
+        match &self.connector {
+            Connector::Direct(stream) => PeerInfo::new(stream.peer_addr().ok()),
+            Connector::Lazy(_) => PeerInfo::default(),
+        }
+    }
+
     fn attach(
         self,
         incoming: Tx<Frame>,
-        mut sending: Rx<Frame>,
+        sending: RxBounded<Frame>,
         connected: Option<TxOnce<Result<(), RSocketError>>>,
     ) {
         DefaultSpawner.spawn(async move {
+            let strategy = self.flush;
             match self.connect().await {
                 Ok(socket) => {
                     if let Some(sender) = connected {
                         sender.send(Ok(())).unwrap();
                     }
-                    let (mut writer, mut reader) =
-                        Framed::new(socket, LengthBasedFrameCodec).split();
-                    DefaultSpawner.spawn(async move {
-                        while let Some(it) = reader.next().await {
-                            incoming.unbounded_send(it.unwrap()).unwrap();
+                    match socket {
+                        Connected::Plain(socket) => {
+                            let (reader, writer) = socket.into_split();
+                            serve(reader, writer, incoming, sending, strategy).await;
+                        }
+                        Connected::Tls(socket) => {
+                            let (reader, writer) = tokio::io::split(socket);
+                            serve(reader, writer, incoming, sending, strategy).await;
                         }
-                    });
-                    // loop write
-                    while let Some(it) = sending.next().await {
-                        debug!("===> SND: {:?}", &it);
-                        writer.send(it).await.unwrap()
                     }
                 }
                 Err(e) => {
@@ -77,6 +129,30 @@ impl ClientTransport for TcpClientTransport {
     }
 }
 
+/// Read the frames of `reader` into `incoming` and write those of `sending` to `writer`.
+async fn serve<Rd, Wr>(
+    reader: Rd,
+    writer: Wr,
+    mut incoming: Tx<Frame>,
+    mut sending: RxBounded<Frame>,
+    strategy: FlushStrategy,
+) where
+    Rd: AsyncRead + Send + Unpin + 'static,
+    Wr: AsyncWrite + Unpin,
+{
+    let mut reader = FramedRead::new(reader, LengthBasedFrameCodec);
+    DefaultSpawner.spawn(async move {
+        while let Some(it) = reader.next().await {
+            incoming.send(it.unwrap()).await.unwrap();
+        }
+    });
+    // loop write
+    let mut writer = FrameWriter::new(writer);
+    if let Err(e) = flush::write_loop(&mut writer, &mut sending, strategy).await {
+        error!("write frame failed: {}", e);
+    }
+}
+
 impl From<SocketAddr> for TcpClientTransport {
     fn from(addr: SocketAddr) -> TcpClientTransport {
         TcpClientTransport::new(Connector::Lazy(addr))
@@ -1,9 +1,15 @@
-use bytes::{Buf, BytesMut};
-use rsocket_rust::frame::Frame;
+use bytes::{Buf, Bytes, BytesMut};
+use rsocket_rust::frame::{dump, Frame};
 use rsocket_rust::utils::{Writeable, U24};
-use std::io::{Error, ErrorKind};
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, IoSlice};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio_util::codec::{Decoder, Encoder};
 
+/// Frames smaller than this are encoded in place into the writer buffer, larger ones keep
+/// their payload apart.
+pub(crate) const VECTORED_MIN: usize = 16 * 1024;
+
 pub struct LengthBasedFrameCodec;
 
 impl Decoder for LengthBasedFrameCodec {
@@ -21,9 +27,19 @@ impl Decoder for LengthBasedFrameCodec {
         }
         buf.advance(3);
         let mut bb = buf.split_to(l);
+        let raw = if log_enabled!(log::Level::Debug) {
+            Some(bb.clone())
+        } else {
+            None
+        };
         match Frame::decode(&mut bb) {
             Ok(v) => Ok(Some(v)),
-            Err(_e) => Err(Error::from(ErrorKind::InvalidInput)),
+            Err(e) => {
+                if let Some(raw) = raw {
+                    debug!("decode frame failed: {}\n{}", e, dump::annotate(&raw));
+                }
+                Err(Error::from(ErrorKind::InvalidInput))
+            }
         }
     }
 }
@@ -32,10 +48,101 @@ impl Encoder for LengthBasedFrameCodec {
     type Item = Frame;
     type Error = Error;
     fn encode(&mut self, item: Frame, buf: &mut BytesMut) -> Result<(), Self::Error> {
-        let l = item.len();
-        buf.reserve(3 + l);
-        U24::write(l as u32, buf);
-        item.write_to(buf);
+        item.write_length_prefixed_to(buf);
         Ok(())
     }
 }
+
+/// Writes length-prefixed frames to a socket, safe to cancel: a frame is committed whole to
+/// the outbound buffer before any of it is written, and the bytes an interrupted `flush` left
+/// are written first by the next one. Metadata and data of large frames are buffered as they
+/// are instead of being copied behind the header, and handed to the socket with vectored I/O.
+pub(crate) struct FrameWriter<W> {
+    w: W,
+    bf: BytesMut,
+    // committed bytes which are not written yet, in order, after those of `bf`.
+    chunks: VecDeque<Bytes>,
+}
+
+impl<W> FrameWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub(crate) fn new(w: W) -> FrameWriter<W> {
+        FrameWriter {
+            w,
+            bf: BytesMut::with_capacity(VECTORED_MIN),
+            chunks: VecDeque::new(),
+        }
+    }
+
+    /// Commit a frame to the outbound buffer, nothing is written until `flush`.
+    pub(crate) fn push(&mut self, frame: &Frame) {
+        if frame.len() < VECTORED_MIN {
+            frame.write_length_prefixed_to(&mut self.bf);
+            return;
+        }
+        U24::write(frame.len() as u32, &mut self.bf);
+        let (m, d) = frame.write_head_to(&mut self.bf);
+        self.chunks.push_back(self.bf.split().freeze());
+        for b in m.into_iter().chain(d) {
+            if !b.is_empty() {
+                self.chunks.push_back(b);
+            }
+        }
+    }
+
+    /// Returns the number of committed bytes which are not written yet.
+    pub(crate) fn buffered(&self) -> usize {
+        self.bf.len() + self.chunks.iter().map(|it| it.len()).sum::<usize>()
+    }
+
+    /// Write every committed byte.
+    pub(crate) async fn flush(&mut self) -> Result<(), Error> {
+        if !self.bf.is_empty() {
+            self.chunks.push_back(self.bf.split().freeze());
+        }
+        let mut buf = Chunks(&mut self.chunks);
+        while buf.has_remaining() {
+            // a write advances the buffer by what it took, so progress is never lost.
+            if self.w.write_buf(&mut buf).await? == 0 {
+                return Err(Error::from(ErrorKind::WriteZero));
+            }
+        }
+        self.w.flush().await
+    }
+}
+
+/// The committed bytes of a `FrameWriter` chained as one buffer.
+struct Chunks<'a>(&'a mut VecDeque<Bytes>);
+
+impl Buf for Chunks<'_> {
+    fn remaining(&self) -> usize {
+        self.0.iter().map(|it| it.len()).sum()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.0.front().map(|it| &it[..]).unwrap_or_default()
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        while cnt > 0 {
+            let front = self.0.front_mut().expect("advance past the end of chunks");
+            if cnt < front.len() {
+                front.advance(cnt);
+                return;
+            }
+            cnt -= front.len();
+            self.0.pop_front();
+        }
+    }
+
+    fn bytes_vectored<'b>(&'b self, dst: &mut [IoSlice<'b>]) -> usize {
+        let mut n = 0;
+        for (slot, chunk) in dst.iter_mut().zip(self.0.iter()) {
+            *slot = IoSlice::new(chunk);
+            n += 1;
+        }
+        n
+    }
+}
@@ -0,0 +1,98 @@
+use super::codec::FrameWriter;
+use futures::StreamExt;
+use rsocket_rust::frame::Frame;
+use rsocket_rust::transport::RxBounded;
+use rsocket_rust::utils::Writeable;
+use std::io::Error;
+use std::time::Duration;
+use tokio::io::AsyncWrite;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::time::{self, Instant};
+
+/// How outbound frames are written to the socket.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum FlushStrategy {
+    /// Write every frame as soon as it is sent, this is the default.
+    #[default]
+    Immediate,
+    /// Coalesce frames which are already queued into one write, until the queue is empty
+    /// or `max_bytes` are buffered.
+    OnIdle { max_bytes: usize },
+    /// Like `OnIdle`, but also wait for more frames for up to `max_delay` after the first
+    /// buffered one. Trades latency for fewer writes.
+    Delayed {
+        max_bytes: usize,
+        max_delay: Duration,
+    },
+    /// Nagle-like batching of tiny frames such as REQUEST_N, KEEPALIVE and short payloads:
+    /// frames up to `max_frame_size` are held for up to `max_delay` or until `max_bytes` are
+    /// buffered, a larger frame is written at once together with the held ones.
+    Batched {
+        max_frame_size: usize,
+        max_bytes: usize,
+        max_delay: Duration,
+    },
+}
+
+/// Write the frames of `sending` until it is closed. `w` keeps the frames which are not
+/// fully written when the returned future is dropped.
+pub(crate) async fn write_loop<W>(
+    w: &mut FrameWriter<W>,
+    sending: &mut RxBounded<Frame>,
+    strategy: FlushStrategy,
+) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+{
+    let (max_bytes, max_delay, max_frame_size) = match strategy {
+        FlushStrategy::Immediate => {
+            while let Some(it) = sending.next().await {
+                debug!("===> SND: {:?}", &it);
+                w.push(&it);
+                w.flush().await?;
+            }
+            return Ok(());
+        }
+        FlushStrategy::OnIdle { max_bytes } => (max_bytes, None, None),
+        FlushStrategy::Delayed {
+            max_bytes,
+            max_delay,
+        } => (max_bytes, Some(max_delay), None),
+        FlushStrategy::Batched {
+            max_frame_size,
+            max_bytes,
+            max_delay,
+        } => (max_bytes, Some(max_delay), Some(max_frame_size)),
+    };
+    while let Some(first) = sending.next().await {
+        let deadline = max_delay.map(|it| Instant::now() + it);
+        let mut next = Some(first);
+        while let Some(it) = next.take() {
+            debug!("===> SND: {:?}", &it);
+            let urgent = max_frame_size.is_some_and(|limit| it.len() > limit);
+            w.push(&it);
+            if w.buffered() >= max_bytes {
+                w.flush().await?;
+            }
+            if urgent {
+                break;
+            }
+            next = match sending.try_recv() {
+                Ok(it) => Some(it),
+                Err(TryRecvError::Closed) => break,
+                // the queue is idle.
+                Err(TryRecvError::Empty) => match deadline {
+                    Some(deadline) if w.buffered() > 0 => {
+                        // a timeout leaves None, which ends the batch.
+                        time::timeout_at(deadline, sending.next())
+                            .await
+                            .unwrap_or_default()
+                    }
+                    _ => None,
+                },
+            };
+        }
+        w.flush().await?;
+    }
+    Ok(())
+}
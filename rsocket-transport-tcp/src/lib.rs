@@ -3,7 +3,14 @@ extern crate log;
 
 mod client;
 mod codec;
+mod flush;
 mod server;
+mod tls;
 
 pub use client::TcpClientTransport;
+pub use codec::LengthBasedFrameCodec;
+pub use flush::FlushStrategy;
 pub use server::TcpServerTransport;
+pub use tls::{
+    Handshake, Io, TlsAcceptor, TlsClientConfig, TlsConnector, TlsServerConfig, TlsStream,
+};
@@ -1,18 +1,43 @@
 use super::client::TcpClientTransport;
+use super::flush::FlushStrategy;
+use super::tls::{Tls, TlsAcceptor, TlsServerConfig};
 use rsocket_rust::transport::{ClientTransport, ServerTransport};
 use std::error::Error;
 use std::future::Future;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 
 pub struct TcpServerTransport {
     addr: SocketAddr,
+    flush: FlushStrategy,
+    tls: Option<Tls>,
 }
 
 impl TcpServerTransport {
     fn new(addr: SocketAddr) -> TcpServerTransport {
-        TcpServerTransport { addr }
+        TcpServerTransport {
+            addr,
+            flush: FlushStrategy::default(),
+            tls: None,
+        }
+    }
+
+    /// Set how outbound frames of accepted connections are written, see `FlushStrategy`.
+    pub fn flush_strategy(mut self, strategy: FlushStrategy) -> TcpServerTransport {
+        self.flush = strategy;
+        self
+    }
+
+    /// Serve over TLS by `acceptor`, see `TlsServerConfig` for ALPN. The handshake of each
+    /// connection runs once it is accepted, off the accept loop.
+    pub fn tls<A>(mut self, acceptor: A, config: TlsServerConfig) -> TcpServerTransport
+    where
+        A: TlsAcceptor + 'static,
+    {
+        self.tls = Some(Tls::Accept(Arc::new(acceptor), config));
+        self
     }
 }
 
@@ -35,7 +60,9 @@ impl ServerTransport for TcpServerTransport {
                         bingo();
                     }
                     while let Ok((socket, _)) = listener.accept().await {
-                        let tp = TcpClientTransport::from(socket);
+                        let tp = TcpClientTransport::from(socket)
+                            .flush_strategy(self.flush)
+                            .accept_tls(self.tls.clone());
                         acceptor(tp);
                     }
                     Ok(())
@@ -0,0 +1,165 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+
+/// A bidirectional stream which carries frames, such as a TLS session over TCP.
+pub trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T> Io for T where T: AsyncRead + AsyncWrite + Send + Unpin {}
+
+pub type Handshake = Pin<Box<dyn Send + Future<Output = io::Result<TlsStream>>>>;
+
+/// Makes TLS client sessions over TCP, by the TLS library of the application.
+///
+/// Implementations are to offer the ALPN protocols of the config in the ClientHello, send
+/// `server_name` as SNI and verify the certificate of the server against it. Without a
+/// `server_name` no SNI is sent, since SNI carries no IP addresses (RFC 6066 §3).
+pub trait TlsConnector: Send + Sync {
+    fn connect(
+        &self,
+        config: &TlsClientConfig,
+        server_name: Option<&str>,
+        stream: TcpStream,
+    ) -> Handshake;
+}
+
+/// Makes TLS server sessions over accepted TCP connections, by the TLS library of the
+/// application.
+///
+/// Implementations are to select the ALPN protocol by `TlsServerConfig::select_alpn`.
+pub trait TlsAcceptor: Send + Sync {
+    fn accept(&self, config: &TlsServerConfig, stream: TcpStream) -> Handshake;
+}
+
+/// An established TLS session and the ALPN protocol it negotiated, if any.
+pub struct TlsStream {
+    io: Box<dyn Io>,
+    alpn_protocol: Option<Vec<u8>>,
+}
+
+/// Options of the TLS client sessions of a transport.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TlsClientConfig {
+    alpn_protocols: Vec<Vec<u8>>,
+    server_name: Option<String>,
+}
+
+/// Options of the TLS server sessions of a transport.
+///
+/// Connections which negotiate none of its ALPN protocols are closed, so the port may be
+/// shared with other services behind the same TLS termination. Any connection is served if
+/// it has no ALPN protocols, which is the default.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TlsServerConfig {
+    alpn_protocols: Vec<Vec<u8>>,
+}
+
+#[derive(Clone)]
+pub(crate) enum Tls {
+    Connect(Arc<dyn TlsConnector>, TlsClientConfig),
+    Accept(Arc<dyn TlsAcceptor>, TlsServerConfig),
+}
+
+impl TlsStream {
+    pub fn new<S>(io: S, alpn_protocol: Option<Vec<u8>>) -> TlsStream
+    where
+        S: Io + 'static,
+    {
+        TlsStream {
+            io: Box::new(io),
+            alpn_protocol,
+        }
+    }
+
+    pub fn get_alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn_protocol.as_deref()
+    }
+}
+
+impl TlsClientConfig {
+    pub fn new() -> TlsClientConfig {
+        TlsClientConfig::default()
+    }
+
+    /// Offer `protocol` by ALPN, in the order they are added.
+    pub fn alpn_protocol(mut self, protocol: &[u8]) -> Self {
+        self.alpn_protocols.push(protocol.to_vec());
+        self
+    }
+
+    /// Send `name` as SNI and verify the server against it. Without it no SNI is sent, so
+    /// servers with certificates for host names need it.
+    pub fn server_name(mut self, name: &str) -> Self {
+        self.server_name = Some(name.to_string());
+        self
+    }
+
+    pub fn get_alpn_protocols(&self) -> &[Vec<u8>] {
+        &self.alpn_protocols
+    }
+
+    pub fn get_server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+}
+
+impl TlsServerConfig {
+    pub fn new() -> TlsServerConfig {
+        TlsServerConfig::default()
+    }
+
+    /// Accept `protocol` by ALPN, the protocols added first are preferred.
+    pub fn alpn_protocol(mut self, protocol: &[u8]) -> Self {
+        self.alpn_protocols.push(protocol.to_vec());
+        self
+    }
+
+    pub fn get_alpn_protocols(&self) -> &[Vec<u8>] {
+        &self.alpn_protocols
+    }
+
+    /// Returns the most preferred of the protocols offered by a client, if it offered any
+    /// of the protocols of the server.
+    pub fn select_alpn<'a>(&self, offered: &[&'a [u8]]) -> Option<&'a [u8]> {
+        self.alpn_protocols
+            .iter()
+            .find_map(|it| offered.iter().find(|offer| **offer == &it[..]).copied())
+    }
+
+    fn admits(&self, negotiated: Option<&[u8]>) -> bool {
+        if self.alpn_protocols.is_empty() {
+            return true;
+        }
+        match negotiated {
+            Some(protocol) => self.alpn_protocols.iter().any(|it| &it[..] == protocol),
+            None => false,
+        }
+    }
+}
+
+impl Tls {
+    /// Run the handshake over `stream`.
+    pub(crate) async fn handshake(&self, stream: TcpStream) -> io::Result<Box<dyn Io>> {
+        match self {
+            Tls::Connect(connector, config) => {
+                let session = connector
+                    .connect(config, config.get_server_name(), stream)
+                    .await?;
+                Ok(session.io)
+            }
+            Tls::Accept(acceptor, config) => {
+                let session = acceptor.accept(config, stream).await?;
+                if !config.admits(session.get_alpn_protocol()) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "no ALPN protocol of the server was negotiated",
+                    ));
+                }
+                Ok(session.io)
+            }
+        }
+    }
+}
@@ -1,14 +1,13 @@
 use bytes::BytesMut;
-use futures_channel::{mpsc, oneshot};
-use futures_util::{SinkExt, StreamExt, TryStreamExt};
+use futures_channel::oneshot;
+use futures_util::{SinkExt, StreamExt};
 use js_sys::{ArrayBuffer, Uint8Array};
 use rsocket_rust::error::RSocketError;
 use rsocket_rust::frame::Frame;
-use rsocket_rust::transport::{ClientTransport, Rx, Tx, TxOnce};
+use rsocket_rust::transport::{ClientTransport, RxBounded, Tx, TxOnce};
 use rsocket_rust::utils::Writeable;
 use std::cell::RefCell;
 use std::future::Future;
-use std::pin::Pin;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
@@ -52,7 +51,7 @@ impl ClientTransport for WebsocketClientTransport {
     fn attach(
         self,
         incoming: Tx<Frame>,
-        mut sending: Rx<Frame>,
+        mut sending: RxBounded<Frame>,
         connected: Option<TxOnce<Result<(), RSocketError>>>,
     ) {
         spawn_local(async move {
@@ -88,11 +87,12 @@ impl ClientTransport for WebsocketClientTransport {
                         sender.send(Ok(())).unwrap();
                     }
 
+                    let mut bf = BytesMut::new();
                     while let Some(v) = sending.next().await {
-                        let mut bf = BytesMut::new();
+                        bf.clear();
+                        bf.reserve(v.len());
                         v.write_to(&mut bf);
-                        let mut raw = bf.to_vec();
-                        ws.send_with_u8_array(&mut raw[..])
+                        ws.send_with_u8_array(&bf[..])
                             .expect("write data into websocket failed.");
                     }
                     console_log!("***** attch end *****");
@@ -135,7 +135,11 @@ fn read_binary(value: JsValue, incoming: Tx<Frame>) {
             // Use data...
             let mut bf = BytesMut::from(&raw[..]);
             let msg = Frame::decode(&mut bf).unwrap();
-            incoming.unbounded_send(msg).unwrap();
+            // wait for room in the inbound frames outside of the event handler.
+            let mut incoming = incoming;
+            spawn_local(async move {
+                incoming.send(msg).await.unwrap();
+            });
         })
     };
 
@@ -17,21 +17,22 @@ pub struct JsClient {
     inner: Client<WASMSpawner>,
 }
 
-impl Into<JsValue> for &JsPayload {
-    fn into(self) -> JsValue {
-        JsValue::from_serde(self).unwrap()
+impl From<&JsPayload> for JsValue {
+    #[allow(deprecated)]
+    fn from(input: &JsPayload) -> JsValue {
+        JsValue::from_serde(input).unwrap()
     }
 }
 
-impl Into<Payload> for JsPayload {
-    fn into(self) -> Payload {
+impl From<JsPayload> for Payload {
+    fn from(input: JsPayload) -> Payload {
         let mut bu = Payload::builder();
-        if let Some(v) = self.data {
+        if let Some(v) = input.data {
             let mut bf = BytesMut::new();
             bf.put_slice(&v[..]);
             bu = bu.set_data(bf.freeze());
         }
-        if let Some(v) = self.metadata {
+        if let Some(v) = input.metadata {
             let mut bf = BytesMut::new();
             bf.put_slice(&v[..]);
             bu = bu.set_metadata(bf.freeze());
@@ -73,6 +74,7 @@ pub async fn connect(url: String) -> Result<JsClient, JsValue> {
 
 #[wasm_bindgen]
 impl JsClient {
+    #[allow(deprecated)]
     pub fn request_response(&self, request: &JsValue) -> Promise {
         let inner = self.inner.clone();
         let request: JsPayload = request.into_serde().unwrap();
@@ -93,10 +95,7 @@ fn to_vec(input: JsValue) -> Option<Vec<u8>> {
     if input.is_null() || input.is_undefined() {
         None
     } else if input.is_string() {
-        match input.as_string() {
-            Some(s) => Some(s.into_bytes()),
-            None => None,
-        }
+        input.as_string().map(String::into_bytes)
     } else {
         Some(Uint8Array::from(input).to_vec())
     }
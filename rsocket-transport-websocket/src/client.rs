@@ -1,13 +1,11 @@
-use bytes::{BufMut, BytesMut};
+use bytes::BytesMut;
 use futures::{SinkExt, StreamExt};
 use rsocket_rust::error::RSocketError;
 use rsocket_rust::frame::Frame;
 use rsocket_rust::runtime::{DefaultSpawner, Spawner};
-use rsocket_rust::transport::{ClientTransport, Rx, Tx, TxOnce};
+use rsocket_rust::transport::{ClientTransport, PeerInfo, RxBounded, Tx, TxOnce};
 use rsocket_rust::utils::Writeable;
-use std::future::Future;
 use std::net::SocketAddr;
-use std::pin::Pin;
 use tokio::net::TcpStream;
 use tokio_tungstenite::{accept_async, connect_async, tungstenite::Message, WebSocketStream};
 use url::Url;
@@ -41,10 +39,17 @@ impl WebsocketClientTransport {
 }
 
 impl ClientTransport for WebsocketClientTransport {
+    fn peer(&self) -> PeerInfo {
+        match &self.connector {
+            Connector::Direct(stream) => PeerInfo::new(stream.peer_addr().ok()),
+            Connector::Lazy(_) => PeerInfo::default(),
+        }
+    }
+
     fn attach(
         self,
-        incoming: Tx<Frame>,
-        mut sending: Rx<Frame>,
+        mut incoming: Tx<Frame>,
+        mut sending: RxBounded<Frame>,
         connected: Option<TxOnce<Result<(), RSocketError>>>,
     ) {
         DefaultSpawner.spawn(async move {
@@ -59,20 +64,22 @@ impl ClientTransport for WebsocketClientTransport {
                             match next {
                                 Ok(msg) => {
                                     let raw = msg.into_data();
-                                    let mut bf = BytesMut::new();
-                                    bf.put_slice(&raw[..]);
+                                    let mut bf = BytesMut::from(&raw[..]);
                                     let f = Frame::decode(&mut bf).unwrap();
-                                    incoming.unbounded_send(f).unwrap();
+                                    incoming.send(f).await.unwrap();
                                 }
                                 Err(e) => error!("got error: {}", e),
                             }
                         }
                     });
+                    // frames are encoded into one buffer of the connection.
+                    let mut bf = BytesMut::new();
                     while let Some(it) = sending.next().await {
                         debug!("===> SND: {:?}", &it);
-                        let mut bf = BytesMut::new();
+                        bf.clear();
+                        bf.reserve(it.len());
                         it.write_to(&mut bf);
-                        let msg = Message::binary(bf.to_vec());
+                        let msg = Message::binary(&bf[..]);
                         write.send(msg).await.unwrap();
                     }
                 }
@@ -0,0 +1,17 @@
+#![no_main]
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use rsocket_rust::extension::{
+    AuthMetadata, CompositeMetadata, MimeTypeMetadata, RoutingMetadata, TracingMetadata,
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(composite) = CompositeMetadata::decode(&mut BytesMut::from(data)) {
+        let _ = RoutingMetadata::from_composite(&composite);
+        let _ = MimeTypeMetadata::from_composite(&composite);
+    }
+    let _ = RoutingMetadata::decode(&mut BytesMut::from(data));
+    let _ = MimeTypeMetadata::decode(&mut BytesMut::from(data));
+    let _ = AuthMetadata::decode(&mut BytesMut::from(data));
+    let _ = TracingMetadata::decode(&mut BytesMut::from(data));
+});
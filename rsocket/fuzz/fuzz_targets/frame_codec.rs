@@ -0,0 +1,25 @@
+#![no_main]
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use rsocket_rust_transport_tcp::LengthBasedFrameCodec;
+use tokio_util::codec::Decoder;
+
+// the first byte picks the size of the chunks the stream arrives in.
+fuzz_target!(|data: &[u8]| {
+    if data.is_empty() {
+        return;
+    }
+    let chunk = data[0] as usize % 16 + 1;
+    let mut codec = LengthBasedFrameCodec;
+    let mut bf = BytesMut::new();
+    for it in data[1..].chunks(chunk) {
+        bf.extend_from_slice(it);
+        loop {
+            match codec.decode(&mut bf) {
+                Ok(Some(_)) => continue,
+                Ok(None) => break,
+                Err(_) => return,
+            }
+        }
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use rsocket_rust::frame::{dump, Frame};
+use rsocket_rust::utils::Writeable;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = dump::annotate(data);
+    if let Ok(frame) = Frame::decode(&mut BytesMut::from(data)) {
+        // whatever decodes must encode again.
+        let mut bf = BytesMut::new();
+        frame.write_to(&mut bf);
+        assert_eq!(frame.len(), bf.len());
+    }
+});
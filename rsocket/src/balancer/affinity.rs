@@ -0,0 +1,37 @@
+#[cfg(feature = "extension")]
+use crate::interceptor::composite_of;
+use crate::payload::Payload;
+use bytes::Bytes;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
+
+/// Returns the value of the metadata entry of `mime` in the composite metadata of a request.
+#[cfg(feature = "extension")]
+pub(crate) fn key_of(mime: &str, req: &Payload) -> Option<Bytes> {
+    composite_of(req)?
+        .find(mime)
+        .map(|it| it.get_payload().clone())
+}
+
+#[cfg(not(feature = "extension"))]
+pub(crate) fn key_of(_mime: &str, _req: &Payload) -> Option<Bytes> {
+    None
+}
+
+/// Choose the address of a key by rendezvous hashing, so a key only moves to another address
+/// when its own one goes away.
+pub(crate) fn choose<'a, I>(key: &[u8], addrs: I) -> Option<usize>
+where
+    I: Iterator<Item = &'a SocketAddr>,
+{
+    addrs
+        .enumerate()
+        .max_by_key(|(_, addr)| {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            addr.hash(&mut hasher);
+            hasher.finish()
+        })
+        .map(|(i, _)| i)
+}
@@ -0,0 +1,569 @@
+//! Client side load balancing over the connections to a set of endpoints.
+//!
+//! Endpoints are given as `host:port`, optionally prefixed by a scheme such as `tcp://`. Their
+//! hostnames are resolved again on every refresh interval and whenever a connection is found
+//! closed: a connection is opened to every new address, and the connections to addresses
+//! which are gone are drained, closed once their in-flight requests finished. Endpoints may
+//! also be added and removed while the balancer runs. A new connection may have to warm up,
+//! by a KEEPALIVE round trip and a probe request, before it receives requests.
+//!
+//! Requests without an affinity key skip the connections whose health score is below half
+//! the best one, until they recover.
+//!
+//! Streams and channels stay on the connection they started on, streams may be re-issued on
+//! another connection when theirs is lost. Requests may also be routed by the value of a
+//! metadata entry, all the requests of a value go to the same connection.
+mod affinity;
+mod resolver;
+mod resubscribe;
+mod warmup;
+
+pub use resolver::{DnsResolver, Resolver};
+
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::runtime::{DefaultSpawner, Spawner};
+use crate::spi::{Flux, Mono, RSocket};
+use crate::transport::Health;
+use crate::x::Client;
+use futures::future::{self, BoxFuture};
+use futures::{Future, Stream};
+use resubscribe::Resubscriber;
+use std::error::Error;
+use std::fmt;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::Notify;
+use warmup::WarmUp;
+
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Share of the best health score a connection needs to receive requests without affinity.
+const HEALTHY_SHARE: f64 = 0.5;
+
+type Connector = Arc<
+    dyn Fn(SocketAddr) -> BoxFuture<'static, Result<Client<DefaultSpawner>, RSocketError>>
+        + Send
+        + Sync,
+>;
+
+/// A requester which spreads requests over the connections to its endpoints, round-robin.
+#[derive(Clone)]
+pub struct LoadBalancer {
+    inner: Arc<Inner>,
+}
+
+pub struct LoadBalancerBuilder {
+    endpoints: Vec<String>,
+    resolver: Arc<dyn Resolver>,
+    refresh_interval: Duration,
+    connector: Option<Connector>,
+    warm_up: WarmUp,
+    affinity: Option<String>,
+    resubscriber: Option<Resubscriber>,
+}
+
+struct Inner {
+    endpoints: Mutex<Vec<Endpoint>>,
+    resolver: Arc<dyn Resolver>,
+    connector: Connector,
+    warm_up: WarmUp,
+    affinity: Option<String>,
+    resubscriber: Option<Resubscriber>,
+    members: Mutex<Vec<Member>>,
+    next: AtomicUsize,
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+}
+
+#[derive(Clone)]
+struct Member {
+    endpoint: Endpoint,
+    addr: SocketAddr,
+    client: Client<DefaultSpawner>,
+    inflight: Arc<InFlight>,
+}
+
+/// Number of the unfinished requests of a member.
+#[derive(Default)]
+struct InFlight {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+struct InFlightGuard(Arc<InFlight>);
+
+struct Tracked {
+    inner: Flux<Result<Payload, RSocketError>>,
+    _guard: InFlightGuard,
+}
+
+impl LoadBalancer {
+    pub fn builder() -> LoadBalancerBuilder {
+        LoadBalancerBuilder {
+            endpoints: vec![],
+            resolver: Arc::new(DnsResolver),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            connector: None,
+            warm_up: WarmUp::default(),
+            affinity: None,
+            resubscriber: None,
+        }
+    }
+
+    /// Returns the addresses of the connections which receive requests.
+    pub fn get_members(&self) -> Vec<SocketAddr> {
+        self.inner
+            .members
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|it| it.addr)
+            .collect()
+    }
+
+    /// Returns the health of the connections which receive requests.
+    pub fn get_health(&self) -> Vec<(SocketAddr, Health)> {
+        self.inner
+            .members
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|it| (it.addr, it.client.stats().get_health()))
+            .collect()
+    }
+
+    /// Returns the endpoints, as `host:port`.
+    pub fn get_endpoints(&self) -> Vec<String> {
+        self.inner
+            .endpoints
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|it| it.to_string())
+            .collect()
+    }
+
+    /// Add an endpoint and connect to its addresses.
+    pub async fn add_endpoint(&self, uri: &str) -> Result<(), RSocketError> {
+        let endpoint = Endpoint::parse(uri)?;
+        {
+            let mut endpoints = self.inner.endpoints.lock().unwrap();
+            if endpoints.contains(&endpoint) {
+                return Ok(());
+            }
+            endpoints.push(endpoint);
+        }
+        self.inner.refresh().await;
+        Ok(())
+    }
+
+    /// Remove an endpoint, its connections stop receiving requests right away and are closed
+    /// once their in-flight requests finished. Returns false if there was no such endpoint.
+    pub async fn remove_endpoint(&self, uri: &str) -> Result<bool, RSocketError> {
+        let endpoint = Endpoint::parse(uri)?;
+        {
+            let mut endpoints = self.inner.endpoints.lock().unwrap();
+            let before = endpoints.len();
+            endpoints.retain(|it| *it != endpoint);
+            if endpoints.len() == before {
+                return Ok(false);
+            }
+        }
+        self.inner.refresh().await;
+        Ok(true)
+    }
+
+    /// Resolve the endpoints again and update the connections to follow their addresses.
+    pub async fn refresh(&self) {
+        self.inner.refresh().await;
+    }
+
+    /// Choose the member of a request, by its affinity key if it has one.
+    fn pick(&self, req: Option<&Payload>) -> Option<(Client<DefaultSpawner>, InFlightGuard)> {
+        let key = match (&self.inner.affinity, req) {
+            (Some(mime), Some(req)) => affinity::key_of(mime, req),
+            _ => None,
+        };
+        let members = self.inner.members.lock().unwrap();
+        let alive: Vec<&Member> = members.iter().filter(|it| !it.client.is_closed()).collect();
+        if alive.len() < members.len() || alive.is_empty() {
+            self.refresh_later();
+        }
+        if alive.is_empty() {
+            return None;
+        }
+        let member = match key {
+            Some(key) => alive[affinity::choose(&key, alive.iter().map(|it| &it.addr))?],
+            None => {
+                let healthy = healthiest(&alive);
+                let n = self.inner.next.fetch_add(1, Ordering::Relaxed);
+                healthy[n % healthy.len()]
+            }
+        };
+        Some((member.client.clone(), InFlightGuard::new(&member.inflight)))
+    }
+
+    fn refresh_later(&self) {
+        let inner = self.inner.clone();
+        DefaultSpawner.spawn(async move {
+            inner.refresh().await;
+        });
+    }
+}
+
+impl LoadBalancerBuilder {
+    /// Add an endpoint, as `host:port`.
+    pub fn endpoint(mut self, uri: &str) -> Self {
+        self.endpoints.push(uri.to_string());
+        self
+    }
+
+    pub fn resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: Resolver + 'static,
+    {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Set how often the endpoints are resolved again, 30 seconds by default.
+    pub fn refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Set how a connection to an address is opened.
+    pub fn connect<F, Fut>(mut self, connector: F) -> Self
+    where
+        F: Fn(SocketAddr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Client<DefaultSpawner>, Box<dyn Error + Send + Sync>>>
+            + Send
+            + 'static,
+    {
+        self.connector = Some(Arc::new(move |addr| {
+            let connecting = connector(addr);
+            Box::pin(async move {
+                connecting
+                    .await
+                    .map_err(|e| RSocketError::from(format!("connect {} failed: {}", addr, e)))
+            })
+        }));
+        self
+    }
+
+    /// Wait for a KEEPALIVE round trip before a new connection receives requests.
+    pub fn warm_up_keepalive(mut self) -> Self {
+        self.warm_up.keepalive = true;
+        self
+    }
+
+    /// Send a request-response which must succeed before a new connection receives requests.
+    pub fn warm_up_probe(mut self, probe: Payload) -> Self {
+        self.warm_up.probe = Some(probe);
+        self
+    }
+
+    /// Set how long the warm-up of a new connection may take, 5 seconds by default.
+    pub fn warm_up_timeout(mut self, timeout: Duration) -> Self {
+        self.warm_up.timeout = timeout;
+        self
+    }
+
+    /// Send the requests which carry the same value of the metadata entry of `mime` to the
+    /// same connection while it is a member.
+    #[cfg(feature = "extension")]
+    pub fn affinity(mut self, mime: &str) -> Self {
+        self.affinity = Some(mime.to_string());
+        self
+    }
+
+    /// Re-issue a stream on another connection when its connection is lost, so its consumer
+    /// sees one stream. `hook` returns the request to re-issue from the first request and the
+    /// last item received, if any, such as a request from the cursor of that item.
+    pub fn resubscribe<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Payload, Option<&Payload>) -> Payload + Send + Sync + 'static,
+    {
+        self.resubscriber = Some(Arc::new(hook));
+        self
+    }
+
+    /// Connect to the endpoints, a balancer without any connection yet keeps resolving them.
+    /// Endpoints may also be added once started.
+    pub async fn start(self) -> Result<LoadBalancer, RSocketError> {
+        let connector = self
+            .connector
+            .ok_or_else(|| RSocketError::from("missing connector"))?;
+        let endpoints = self
+            .endpoints
+            .iter()
+            .map(|it| Endpoint::parse(it))
+            .collect::<Result<Vec<_>, _>>()?;
+        let inner = Arc::new(Inner {
+            endpoints: Mutex::new(endpoints),
+            resolver: self.resolver,
+            connector,
+            warm_up: self.warm_up,
+            affinity: self.affinity,
+            resubscriber: self.resubscriber,
+            members: Mutex::new(vec![]),
+            next: AtomicUsize::new(0),
+            refreshing: tokio::sync::Mutex::new(()),
+        });
+        inner.refresh().await;
+        if self.refresh_interval > Duration::from_secs(0) {
+            DefaultSpawner.spawn(refresh_periodically(
+                Arc::downgrade(&inner),
+                self.refresh_interval,
+            ));
+        }
+        Ok(LoadBalancer { inner })
+    }
+}
+
+async fn refresh_periodically(inner: Weak<Inner>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    // the first tick completes immediately.
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        match inner.upgrade() {
+            Some(inner) => inner.refresh().await,
+            None => return,
+        }
+    }
+}
+
+impl Inner {
+    async fn refresh(&self) {
+        let _refreshing = self.refreshing.lock().await;
+        let endpoints = self.endpoints.lock().unwrap().clone();
+        let mut wanted: Vec<(Endpoint, SocketAddr)> = vec![];
+        for endpoint in endpoints {
+            match self.resolver.resolve(&endpoint.host, endpoint.port).await {
+                Ok(addrs) => {
+                    for addr in addrs {
+                        wanted.push((endpoint.clone(), addr));
+                    }
+                }
+                Err(e) => {
+                    // keep the current connections of the endpoint.
+                    warn!("{}", e);
+                    let members = self.members.lock().unwrap();
+                    for it in members.iter().filter(|it| it.endpoint == endpoint) {
+                        wanted.push((endpoint.clone(), it.addr));
+                    }
+                }
+            }
+        }
+
+        let removed: Vec<Member> = {
+            let mut members = self.members.lock().unwrap();
+            let (kept, removed) = members.drain(..).partition(|it: &Member| {
+                !it.client.is_closed() && wanted.iter().any(|(_, addr)| *addr == it.addr)
+            });
+            *members = kept;
+            removed
+        };
+        for member in removed {
+            debug!("drain connection to {}", member.addr);
+            DefaultSpawner.spawn(member.drain());
+        }
+
+        for (endpoint, addr) in wanted {
+            let connected = self
+                .members
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|it| it.addr == addr);
+            if connected {
+                continue;
+            }
+            let client = match (self.connector)(addr).await {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("{}", e);
+                    continue;
+                }
+            };
+            // a connection which is not ready is retried by the next refresh.
+            match self.warm_up.run(&client).await {
+                Ok(()) => {
+                    debug!("connected to {}", addr);
+                    self.members.lock().unwrap().push(Member {
+                        endpoint,
+                        addr,
+                        client,
+                        inflight: Arc::new(InFlight::default()),
+                    });
+                }
+                Err(e) => {
+                    warn!("warm up connection to {} failed: {}", addr, e);
+                    client.close();
+                }
+            }
+        }
+    }
+}
+
+impl Endpoint {
+    fn parse(uri: &str) -> Result<Endpoint, RSocketError> {
+        let authority = match uri.find("://") {
+            Some(n) => &uri[n + 3..],
+            None => uri,
+        };
+        let invalid = || RSocketError::from(format!("invalid endpoint: {}", uri));
+        let (host, port) = authority.rsplit_once(':').ok_or_else(invalid)?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Endpoint {
+            host: host.to_string(),
+            port: port.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl Member {
+    /// Close the connection once its in-flight requests finished.
+    async fn drain(self) {
+        while self.inflight.count.load(Ordering::SeqCst) > 0 && !self.client.is_closed() {
+            self.inflight.idle.notified().await;
+        }
+        self.client.close();
+    }
+}
+
+impl InFlightGuard {
+    fn new(inflight: &Arc<InFlight>) -> InFlightGuard {
+        inflight.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(inflight.clone())
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify();
+        }
+    }
+}
+
+impl Stream for Tracked {
+    type Item = Result<Payload, RSocketError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+/// Returns the members whose health score is at least `HEALTHY_SHARE` of the best one.
+fn healthiest<'a>(members: &[&'a Member]) -> Vec<&'a Member> {
+    let scores: Vec<f64> = members
+        .iter()
+        .map(|it| it.client.stats().get_health().get_score())
+        .collect();
+    let best = scores.iter().cloned().fold(0.0, f64::max);
+    members
+        .iter()
+        .zip(scores)
+        .filter(|(_, score)| *score >= best * HEALTHY_SHARE)
+        .map(|(it, _)| *it)
+        .collect()
+}
+
+fn no_connection() -> RSocketError {
+    RSocketError::from("no available connection")
+}
+
+impl RSocket for LoadBalancer {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        // metadata push is about the connection, every one receives it.
+        let clients: Vec<Client<DefaultSpawner>> = self
+            .inner
+            .members
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|it| it.client.clone())
+            .collect();
+        Box::pin(async move {
+            for client in clients {
+                client.metadata_push(req.clone()).await;
+            }
+        })
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        match self.pick(Some(&req)) {
+            Some((client, guard)) => Box::pin(async move {
+                client.fire_and_forget(req).await;
+                drop(guard);
+            }),
+            None => {
+                warn!("drop fire_and_forget: {}", no_connection());
+                Box::pin(async {})
+            }
+        }
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        match self.pick(Some(&req)) {
+            Some((client, guard)) => Box::pin(async move {
+                let res = client.request_response(req).await;
+                drop(guard);
+                res
+            }),
+            None => Box::pin(future::err(no_connection())),
+        }
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        if let Some(resubscriber) = &self.inner.resubscriber {
+            return resubscribe::request_stream(self.clone(), req, resubscriber.clone());
+        }
+        match self.pick(Some(&req)) {
+            Some((client, guard)) => Box::pin(Tracked {
+                inner: client.request_stream(req),
+                _guard: guard,
+            }),
+            None => Box::pin(futures::stream::iter(vec![Err(no_connection())])),
+        }
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        // a channel stays on the connection it started on, as every stream.
+        match self.pick(None) {
+            Some((client, guard)) => Box::pin(Tracked {
+                inner: client.request_channel(reqs),
+                _guard: guard,
+            }),
+            None => Box::pin(futures::stream::iter(vec![Err(no_connection())])),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::error::RSocketError;
+use futures::future::BoxFuture;
+use std::net::SocketAddr;
+
+/// Resolves the hostname of an endpoint to the addresses to connect to.
+pub trait Resolver: Send + Sync {
+    fn resolve(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> BoxFuture<'static, Result<Vec<SocketAddr>, RSocketError>>;
+}
+
+/// Resolves hostnames with the resolver of the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DnsResolver;
+
+impl Resolver for DnsResolver {
+    fn resolve(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> BoxFuture<'static, Result<Vec<SocketAddr>, RSocketError>> {
+        let target = format!("{}:{}", host, port);
+        Box::pin(async move {
+            match tokio::net::lookup_host(target.as_str()).await {
+                Ok(addrs) => Ok(addrs.collect()),
+                Err(e) => Err(RSocketError::from(format!(
+                    "resolve {} failed: {}",
+                    target, e
+                ))),
+            }
+        })
+    }
+}
@@ -0,0 +1,101 @@
+use super::{no_connection, LoadBalancer, Tracked};
+use crate::error::{self, ErrorKind, RSocketError};
+use crate::payload::Payload;
+use crate::runtime::{DefaultSpawner, Spawner};
+use crate::spi::{Flux, RSocket};
+use futures::channel::{mpsc, oneshot};
+use futures::future::{self, Either};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+const MAX_RESUBSCRIBE_ATTEMPTS: usize = 3;
+const RESUBSCRIBE_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Returns the request re-issued for a stream, from its first request and its last item.
+pub(crate) type Resubscriber = Arc<dyn Fn(&Payload, Option<&Payload>) -> Payload + Send + Sync>;
+
+/// The items of a stream over all its subscriptions, dropping it cancels the current one.
+struct Resubscribed {
+    items: mpsc::UnboundedReceiver<Result<Payload, RSocketError>>,
+    _cancel: oneshot::Sender<()>,
+}
+
+impl Stream for Resubscribed {
+    type Item = Result<Payload, RSocketError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.items).poll_next(cx)
+    }
+}
+
+pub(crate) fn request_stream(
+    balancer: LoadBalancer,
+    req: Payload,
+    resubscriber: Resubscriber,
+) -> Flux<Result<Payload, RSocketError>> {
+    let (tx, items) = mpsc::unbounded();
+    let (cancel, cancelled) = oneshot::channel();
+    DefaultSpawner.spawn(async move {
+        let mut cancelled = cancelled;
+        let mut last: Option<Payload> = None;
+        let mut attempts = 0;
+        let mut request = req.clone();
+        loop {
+            let lost = match balancer.pick(Some(&request)) {
+                Some((client, guard)) => {
+                    let mut results = Tracked {
+                        inner: client.request_stream(request),
+                        _guard: guard,
+                    };
+                    loop {
+                        let next = match future::select(results.next(), &mut cancelled).await {
+                            Either::Left((next, _)) => next,
+                            Either::Right(_) => return,
+                        };
+                        match next {
+                            Some(Err(e)) if is_connection_lost(&e) => break e,
+                            Some(Ok(it)) => {
+                                attempts = 0;
+                                last = Some(it.clone());
+                                if tx.unbounded_send(Ok(it)).is_err() {
+                                    return;
+                                }
+                            }
+                            Some(Err(e)) => {
+                                let _ = tx.unbounded_send(Err(e));
+                                return;
+                            }
+                            None => return,
+                        }
+                    }
+                }
+                None => no_connection(),
+            };
+            attempts += 1;
+            if attempts > MAX_RESUBSCRIBE_ATTEMPTS {
+                let _ = tx.unbounded_send(Err(lost));
+                return;
+            }
+            debug!("resubscribe stream, attempt {}: {}", attempts, lost);
+            tokio::time::delay_for(RESUBSCRIBE_BACKOFF * attempts as u32).await;
+            balancer.refresh().await;
+            request = resubscriber(&req, last.as_ref());
+        }
+    });
+    Box::pin(Resubscribed {
+        items,
+        _cancel: cancel,
+    })
+}
+
+/// Returns true if a stream failed because its connection went away.
+fn is_connection_lost(e: &RSocketError) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::Internal(error::ERR_CONN_CLOSED, _)
+            | ErrorKind::Internal(error::ERR_CONN_FAILED, _)
+    )
+}
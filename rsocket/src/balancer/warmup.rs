@@ -0,0 +1,44 @@
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::runtime::DefaultSpawner;
+use crate::spi::RSocket;
+use crate::x::Client;
+use std::time::Duration;
+
+const DEFAULT_WARM_UP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Checks a new connection must pass, after SETUP, before it receives requests.
+#[derive(Clone)]
+pub(crate) struct WarmUp {
+    pub(crate) keepalive: bool,
+    pub(crate) probe: Option<Payload>,
+    pub(crate) timeout: Duration,
+}
+
+impl Default for WarmUp {
+    fn default() -> Self {
+        WarmUp {
+            keepalive: false,
+            probe: None,
+            timeout: DEFAULT_WARM_UP_TIMEOUT,
+        }
+    }
+}
+
+impl WarmUp {
+    pub(crate) async fn run(&self, client: &Client<DefaultSpawner>) -> Result<(), RSocketError> {
+        let checks = async {
+            if self.keepalive {
+                client.ping().await?;
+            }
+            if let Some(probe) = &self.probe {
+                client.request_response(probe.clone()).await?;
+            }
+            Ok(())
+        };
+        match tokio::time::timeout(self.timeout, checks).await {
+            Ok(checked) => checked,
+            Err(_) => Err(RSocketError::from("warm up timed out")),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! A broker which routes requests between the connections it accepted.
+//!
+//! A connection registers routes with the routing tags in the composite metadata of its SETUP
+//! frame. Requests of every connection are forwarded by their first routing tag to one of the
+//! connections which registered it, round robin, and their responses are streamed back. A
+//! connection's routes are unregistered when it closes.
+use crate::error::RSocketError;
+use crate::extension::{CompositeMetadata, RoutingMetadata};
+use crate::frame::Frame;
+use crate::payload::{Payload, SetupPayload};
+use crate::router::{route_of, unknown_route};
+use crate::runtime::{DefaultSpawner, Spawner};
+use crate::spi::{Flux, Mono, RSocket};
+use crate::transport::{
+    self, Acceptor, BoxedAcceptor, ClientTransport, DuplexSocket, ServerTransport, SocketOptions,
+};
+use bytes::BytesMut;
+use futures::{future, stream, FutureExt, StreamExt};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+pub struct Broker {
+    registry: Arc<Registry>,
+}
+
+/// A registered connection, by the ID the broker assigned to it.
+type Registered = (u64, Arc<dyn RSocket>);
+
+#[derive(Default)]
+struct Registry {
+    next_connection: AtomicU64,
+    next_pick: AtomicUsize,
+    routes: Mutex<HashMap<String, Vec<Registered>>>,
+}
+
+/// Responder of a connection, which forwards its requests to other connections.
+#[derive(Clone)]
+struct Forwarder {
+    registry: Arc<Registry>,
+}
+
+impl Broker {
+    pub fn new() -> Broker {
+        Broker::default()
+    }
+
+    /// Returns the registered routes.
+    pub fn get_routes(&self) -> Vec<String> {
+        let mut routes: Vec<String> = self
+            .registry
+            .routes
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        routes.sort();
+        routes
+    }
+
+    /// Accept connections of `transport` until it stops, a broker may serve several transports.
+    pub async fn serve<T, C>(&self, transport: T) -> Result<(), Box<dyn Error + Send + Sync>>
+    where
+        T: Send + Sync + ServerTransport<Item = C> + 'static,
+        C: Send + Sync + ClientTransport + 'static,
+    {
+        let registry = self.registry.clone();
+        transport
+            .start(None, move |tp| accept(registry.clone(), tp))
+            .await
+    }
+}
+
+fn accept<C>(registry: Arc<Registry>, tp: C)
+where
+    C: ClientTransport,
+{
+    let opts = SocketOptions::default();
+    let (rcv_tx, rcv_rx) = transport::new_tx_rx::<Frame>(opts.inbound_capacity());
+    let (snd_tx, snd_rx) = transport::new_tx_rx_bounded::<Frame>(opts.outbound_capacity());
+    tp.attach(rcv_tx, snd_rx, None);
+    let id = registry.next_connection.fetch_add(1, Ordering::Relaxed);
+    let setuper: Arc<BoxedAcceptor> = Arc::new({
+        let registry = registry.clone();
+        move |setup: SetupPayload, socket: Box<dyn RSocket>| {
+            let routes = routes_of(&setup)?;
+            debug!("connection {} registers routes {:?}", id, routes);
+            registry.register(id, &routes, Arc::from(socket));
+            Ok(Box::new(Forwarder {
+                registry: registry.clone(),
+            }))
+        }
+    });
+    DefaultSpawner.spawn(async move {
+        let ds = DuplexSocket::new(DefaultSpawner, 2, snd_tx, opts).await;
+        if let Err(e) = ds.event_loop(Acceptor::Generate(setuper), rcv_rx).await {
+            debug!("connection {} failed: {}", id, e);
+        }
+        debug!("connection {} closed", id);
+        registry.unregister(id);
+    });
+}
+
+/// Returns the routing tags of the SETUP metadata, which need not be composite metadata
+/// if there are none.
+fn routes_of(setup: &SetupPayload) -> Result<Vec<String>, Box<dyn Error>> {
+    let metadata = match setup.metadata() {
+        Some(it) if !it.is_empty() => it,
+        _ => return Ok(vec![]),
+    };
+    let composite = match CompositeMetadata::decode(&mut BytesMut::from(metadata.as_ref())) {
+        Ok(it) => it,
+        Err(_) => return Ok(vec![]),
+    };
+    match RoutingMetadata::from_composite(&composite)? {
+        Some(routing) => Ok(routing.get_tags().to_vec()),
+        None => Ok(vec![]),
+    }
+}
+
+impl Registry {
+    fn register(&self, id: u64, routes: &[String], socket: Arc<dyn RSocket>) {
+        let mut all = self.routes.lock().unwrap();
+        for route in routes {
+            all.entry(route.clone())
+                .or_default()
+                .push((id, socket.clone()));
+        }
+    }
+
+    fn unregister(&self, id: u64) {
+        let mut all = self.routes.lock().unwrap();
+        all.retain(|_, sockets| {
+            sockets.retain(|(it, _)| *it != id);
+            !sockets.is_empty()
+        });
+    }
+
+    fn find(&self, route: Option<&str>) -> Result<Arc<dyn RSocket>, RSocketError> {
+        let all = self.routes.lock().unwrap();
+        let sockets = route.and_then(|it| all.get(it));
+        match sockets {
+            Some(sockets) => {
+                let n = self.next_pick.fetch_add(1, Ordering::Relaxed);
+                Ok(sockets[n % sockets.len()].1.clone())
+            }
+            None => Err(unknown_route(route)),
+        }
+    }
+}
+
+impl RSocket for Forwarder {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        match self.registry.find(route_of(&req).as_deref()) {
+            Ok(target) => target.metadata_push(req),
+            Err(e) => {
+                debug!("drop metadata push: {}", e);
+                Box::pin(future::ready(()))
+            }
+        }
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        match self.registry.find(route_of(&req).as_deref()) {
+            Ok(target) => target.fire_and_forget(req),
+            Err(e) => {
+                warn!("drop fire-and-forget request: {}", e);
+                Box::pin(future::ready(()))
+            }
+        }
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        match self.registry.find(route_of(&req).as_deref()) {
+            Ok(target) => target.request_response(req),
+            Err(e) => Box::pin(future::err(e)),
+        }
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        match self.registry.find(route_of(&req).as_deref()) {
+            Ok(target) => target.request_stream(req),
+            Err(e) => Box::pin(stream::iter(Some(Err(e)))),
+        }
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        // the route is carried by the first payload of a channel.
+        let registry = self.registry.clone();
+        let results = reqs.into_future().map(move |(first, rest)| {
+            let route = match &first {
+                Some(Ok(req)) => route_of(req),
+                _ => None,
+            };
+            match registry.find(route.as_deref()) {
+                Ok(target) => {
+                    let reqs = stream::iter(first).chain(rest);
+                    target.request_channel(Box::pin(reqs))
+                }
+                Err(e) => Box::pin(stream::iter(Some(Err(e)))) as Flux<_>,
+            }
+        });
+        Box::pin(stream::once(results).flatten())
+    }
+}
@@ -0,0 +1,59 @@
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::utils::RSocketResult;
+use ::flatbuffers::{Follow, Verifiable, VerifierOptions};
+use bytes::Bytes;
+
+pub use ::flatbuffers::{FlatBufferBuilder, InvalidFlatbuffer};
+
+/// Verify the data of a payload as a flatbuffer and return its root.
+///
+/// The root borrows the payload data, fields are read in place without copying or parsing.
+pub fn root<'a, T>(payload: &'a Payload) -> RSocketResult<T::Inner>
+where
+    T: 'a + Follow<'a> + Verifiable,
+{
+    root_with_opts::<T>(&VerifierOptions::default(), payload)
+}
+
+pub fn root_with_opts<'a, T>(
+    opts: &VerifierOptions,
+    payload: &'a Payload,
+) -> RSocketResult<T::Inner>
+where
+    T: 'a + Follow<'a> + Verifiable,
+{
+    ::flatbuffers::root_with_opts::<T>(opts, data_of(payload)?).map_err(invalid)
+}
+
+/// Return the root of a payload without verification.
+///
+/// # Safety
+///
+/// The payload data must be a valid flatbuffer of `T`, e.g. from a trusted peer or
+/// verified before.
+pub unsafe fn root_unchecked<'a, T>(payload: &'a Payload) -> RSocketResult<T::Inner>
+where
+    T: 'a + Follow<'a>,
+{
+    Ok(::flatbuffers::root_unchecked::<T>(data_of(payload)?))
+}
+
+/// Take the finished data of a builder as payload data, the buffer is moved without copying.
+pub fn encode(builder: FlatBufferBuilder) -> Bytes {
+    let (buf, head) = builder.collapse();
+    Bytes::from(buf).slice(head..)
+}
+
+#[inline]
+fn data_of(payload: &Payload) -> RSocketResult<&[u8]> {
+    match payload.data() {
+        Some(b) => Ok(b.as_ref()),
+        None => Err(RSocketError::from("empty payload data")),
+    }
+}
+
+#[inline]
+fn invalid(e: InvalidFlatbuffer) -> RSocketError {
+    RSocketError::from(format!("invalid flatbuffer: {}", e))
+}
@@ -0,0 +1,22 @@
+use crate::error::RSocketError;
+use crate::utils::RSocketResult;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub(crate) fn encode<T>(value: &T) -> RSocketResult<Bytes>
+where
+    T: Serialize,
+{
+    serde_json::to_vec(value)
+        .map(Bytes::from)
+        .map_err(|e| RSocketError::from(format!("encode json failed: {}", e)))
+}
+
+pub(crate) fn decode<T>(raw: &[u8]) -> RSocketResult<T>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_slice(raw)
+        .map_err(|e| RSocketError::from(format!("decode json failed: {}", e)))
+}
@@ -0,0 +1,29 @@
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "flatbuffers")]
+pub mod flatbuffers;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+#[cfg(feature = "serde")]
+mod typed;
+
+#[cfg(feature = "serde")]
+pub use typed::*;
+
+use crate::extension::MimeTypeMetadata;
+use crate::interceptor::composite_of;
+use crate::payload::Payload;
+
+/// Returns the per-stream data MIME type carried in the composite metadata of a payload.
+pub fn data_mime_type_of(payload: &Payload) -> Option<String> {
+    let composite = composite_of(payload)?;
+    match MimeTypeMetadata::from_composite(&composite) {
+        Ok(it) => it.map(|it| it.get_mime().clone()),
+        Err(e) => {
+            warn!("decode per-stream MIME type failed: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::error::RSocketError;
+use crate::utils::RSocketResult;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub(crate) fn encode<T>(value: &T) -> RSocketResult<Bytes>
+where
+    T: Serialize,
+{
+    rmp_serde::to_vec_named(value)
+        .map(Bytes::from)
+        .map_err(|e| RSocketError::from(format!("encode msgpack failed: {}", e)))
+}
+
+pub(crate) fn decode<T>(raw: &[u8]) -> RSocketResult<T>
+where
+    T: DeserializeOwned,
+{
+    rmp_serde::from_slice(raw)
+        .map_err(|e| RSocketError::from(format!("decode msgpack failed: {}", e)))
+}
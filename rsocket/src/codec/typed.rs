@@ -0,0 +1,110 @@
+#[cfg(feature = "cbor")]
+use super::cbor;
+use super::data_mime_type_of;
+use super::json;
+#[cfg(feature = "msgpack")]
+use super::msgpack;
+use crate::error::RSocketError;
+use crate::mime;
+use crate::payload::Payload;
+use crate::spi::Flux;
+use crate::utils::RSocketResult;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Returns true if values of this data MIME type can be (de)serialized.
+pub fn is_supported(mime_type: &str) -> bool {
+    match mime_type {
+        mime::APPLICATION_JSON => true,
+        #[cfg(feature = "cbor")]
+        mime::APPLICATION_CBOR => true,
+        #[cfg(feature = "msgpack")]
+        mime::APPLICATION_MSGPACK => true,
+        _ => false,
+    }
+}
+
+/// Serialize a value into payload data of the given MIME type.
+pub fn encode<T>(mime_type: &str, value: &T) -> RSocketResult<Bytes>
+where
+    T: Serialize,
+{
+    match mime_type {
+        mime::APPLICATION_JSON => json::encode(value),
+        #[cfg(feature = "cbor")]
+        mime::APPLICATION_CBOR => cbor::encode(value),
+        #[cfg(feature = "msgpack")]
+        mime::APPLICATION_MSGPACK => msgpack::encode(value),
+        other => Err(unsupported(other)),
+    }
+}
+
+/// Deserialize payload data of the given MIME type.
+pub fn decode<T>(mime_type: &str, raw: &[u8]) -> RSocketResult<T>
+where
+    T: DeserializeOwned,
+{
+    match mime_type {
+        mime::APPLICATION_JSON => json::decode(raw),
+        #[cfg(feature = "cbor")]
+        mime::APPLICATION_CBOR => cbor::decode(raw),
+        #[cfg(feature = "msgpack")]
+        mime::APPLICATION_MSGPACK => msgpack::decode(raw),
+        other => Err(unsupported(other)),
+    }
+}
+
+/// Deserialize the data of a payload, the per-stream MIME type takes precedence
+/// over the connection MIME type.
+pub fn decode_payload<T>(payload: &Payload, connection_mime_type: &str) -> RSocketResult<T>
+where
+    T: DeserializeOwned,
+{
+    let mime_type = data_mime_type_of(payload);
+    let mime_type = mime_type.as_deref().unwrap_or(connection_mime_type);
+    match payload.data() {
+        Some(b) => decode(mime_type, b),
+        None => Err(RSocketError::from("empty payload data")),
+    }
+}
+
+/// Deserialize every payload of a stream, see `decode_payload`.
+pub fn decode_stream<S, T>(
+    results: S,
+    connection_mime_type: &str,
+) -> impl Stream<Item = RSocketResult<T>>
+where
+    S: Stream<Item = RSocketResult<Payload>>,
+    T: DeserializeOwned,
+{
+    let mime_type = connection_mime_type.to_string();
+    results.map(move |it| it.and_then(|res| decode_payload(&res, &mime_type)))
+}
+
+/// Serialize a stream of values into payloads, for responders of request-stream
+/// and request-channel. No per-stream MIME type is attached.
+pub fn encode_stream<S, T>(values: S, mime_type: &str) -> Flux<RSocketResult<Payload>>
+where
+    S: Stream<Item = T> + Send + Sync + 'static,
+    T: Serialize,
+{
+    let mime_type = mime_type.to_string();
+    Box::pin(values.map(move |it| encode(&mime_type, &it).map(Payload::from)))
+}
+
+/// Like `encode_stream`, errors of the stream are passed through.
+pub fn try_encode_stream<S, T>(results: S, mime_type: &str) -> Flux<RSocketResult<Payload>>
+where
+    S: Stream<Item = RSocketResult<T>> + Send + Sync + 'static,
+    T: Serialize,
+{
+    let mime_type = mime_type.to_string();
+    Box::pin(results.map(move |it| it.and_then(|v| encode(&mime_type, &v).map(Payload::from))))
+}
+
+#[inline]
+fn unsupported(mime_type: &str) -> RSocketError {
+    RSocketError::from(format!("no codec for data MIME type: {}", mime_type))
+}
@@ -1,5 +1,9 @@
+use alloc::string::String;
+use bytes::Bytes;
+use core::fmt;
+#[cfg(feature = "std")]
 use std::error::Error as StdError;
-use std::fmt;
+#[cfg(feature = "std")]
 use std::io;
 
 pub const ERR_INVALID_SETUP: u32 = 0x0000_0001;
@@ -17,15 +21,61 @@ pub const ERR_INVALID: u32 = 0x0000_0204;
 pub enum ErrorKind {
     Internal(u32, String),
     WithDescription(String),
+    #[cfg(feature = "std")]
     IO(io::Error),
     Cancelled(),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RSocketError {
     kind: ErrorKind,
+    data: Option<Bytes>,
+}
+
+/// IO errors are cloned with their kind and message only.
+impl Clone for ErrorKind {
+    fn clone(&self) -> ErrorKind {
+        match self {
+            ErrorKind::Internal(c, s) => ErrorKind::Internal(*c, s.clone()),
+            ErrorKind::WithDescription(s) => ErrorKind::WithDescription(s.clone()),
+            #[cfg(feature = "std")]
+            ErrorKind::IO(e) => ErrorKind::IO(io::Error::new(e.kind(), e.to_string())),
+            ErrorKind::Cancelled() => ErrorKind::Cancelled(),
+        }
+    }
+}
+
+impl RSocketError {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Returns the raw data of the ERROR frame, which is sent in place of the message.
+    pub fn get_data(&self) -> Option<&Bytes> {
+        self.data.as_ref()
+    }
+
+    /// Set the data sent by the ERROR frame of this error, such as JSON describing it.
+    pub fn with_data(mut self, data: Bytes) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Deserialize the data of the ERROR frame as JSON.
+    #[cfg(feature = "serde")]
+    pub fn data_json<T>(&self) -> Result<T, RSocketError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match &self.data {
+            Some(data) => serde_json::from_slice(data)
+                .map_err(|e| RSocketError::from(format!("decode error data failed: {}", e))),
+            None => Err(RSocketError::from("error has no data")),
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 impl StdError for RSocketError {}
 
 impl fmt::Display for RSocketError {
@@ -33,38 +83,34 @@ impl fmt::Display for RSocketError {
         match &self.kind {
             ErrorKind::Internal(c, s) => write!(f, "ERROR({}): {}", translate(c), s),
             ErrorKind::WithDescription(s) => write!(f, "{}", s),
+            #[cfg(feature = "std")]
             ErrorKind::IO(e) => write!(f, "{}", e),
             ErrorKind::Cancelled() => write!(f, "ERROR(CANCELLED)"),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl From<io::Error> for RSocketError {
     fn from(e: io::Error) -> RSocketError {
-        RSocketError {
-            kind: ErrorKind::IO(e),
-        }
+        RSocketError::from(ErrorKind::IO(e))
     }
 }
 
 impl From<ErrorKind> for RSocketError {
     fn from(kind: ErrorKind) -> RSocketError {
-        RSocketError { kind }
+        RSocketError { kind, data: None }
     }
 }
 impl From<String> for RSocketError {
     fn from(e: String) -> RSocketError {
-        RSocketError {
-            kind: ErrorKind::WithDescription(e),
-        }
+        RSocketError::from(ErrorKind::WithDescription(e))
     }
 }
 
 impl From<&'static str> for RSocketError {
     fn from(e: &'static str) -> RSocketError {
-        RSocketError {
-            kind: ErrorKind::WithDescription(String::from(e)),
-        }
+        RSocketError::from(ErrorKind::WithDescription(String::from(e)))
     }
 }
 
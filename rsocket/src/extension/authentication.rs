@@ -0,0 +1,130 @@
+use crate::error::RSocketError;
+use crate::utils::{RSocketResult, Writeable};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+const AUTH_TYPE_SIMPLE: u8 = 0x00;
+const AUTH_TYPE_BEARER: u8 = 0x01;
+const MAX_AUTH_TYPE_LEN: usize = 0x80;
+
+/// Authentication metadata (`message/x.rsocket.authentication.v0`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AuthMetadata {
+    Simple { username: String, password: Bytes },
+    Bearer(String),
+    Custom(String, Bytes),
+}
+
+impl AuthMetadata {
+    pub fn simple<U, P>(username: U, password: P) -> AuthMetadata
+    where
+        U: Into<String>,
+        P: AsRef<[u8]>,
+    {
+        let username = username.into();
+        if username.len() > u16::MAX as usize {
+            panic!("too large username!");
+        }
+        AuthMetadata::Simple {
+            username,
+            password: Bytes::from(password.as_ref().to_vec()),
+        }
+    }
+
+    pub fn bearer<T>(token: T) -> AuthMetadata
+    where
+        T: Into<String>,
+    {
+        AuthMetadata::Bearer(token.into())
+    }
+
+    pub fn custom<A>(auth_type: A, payload: Bytes) -> AuthMetadata
+    where
+        A: Into<String>,
+    {
+        let auth_type = auth_type.into();
+        if auth_type.is_empty() || auth_type.len() > MAX_AUTH_TYPE_LEN {
+            panic!("invalid auth type length!");
+        }
+        AuthMetadata::Custom(auth_type, payload)
+    }
+
+    pub fn decode(bf: &mut BytesMut) -> RSocketResult<AuthMetadata> {
+        if bf.is_empty() {
+            return Err(RSocketError::from("broken AUTHENTICATION metadata bytes!"));
+        }
+        let first = bf.get_u8();
+        if first & 0x80 == 0 {
+            // encoded length is one less than the actual length.
+            let n = first as usize + 1;
+            if bf.len() < n {
+                return Err(RSocketError::from("broken AUTHENTICATION metadata bytes!"));
+            }
+            let auth_type = utf8(bf.split_to(n))?;
+            return Ok(AuthMetadata::Custom(auth_type, bf.split().freeze()));
+        }
+        match first & 0x7F {
+            AUTH_TYPE_SIMPLE => {
+                if bf.len() < 2 {
+                    return Err(RSocketError::from("broken AUTHENTICATION metadata bytes!"));
+                }
+                let n = bf.get_u16() as usize;
+                if bf.len() < n {
+                    return Err(RSocketError::from("broken AUTHENTICATION metadata bytes!"));
+                }
+                let username = utf8(bf.split_to(n))?;
+                Ok(AuthMetadata::Simple {
+                    username,
+                    password: bf.split().freeze(),
+                })
+            }
+            AUTH_TYPE_BEARER => Ok(AuthMetadata::Bearer(utf8(bf.split())?)),
+            n => Err(RSocketError::from(format!(
+                "unknown well-known auth type: {}",
+                n
+            ))),
+        }
+    }
+}
+
+impl Writeable for AuthMetadata {
+    fn write_to(&self, bf: &mut BytesMut) {
+        match self {
+            AuthMetadata::Simple { username, password } => {
+                bf.put_u8(0x80 | AUTH_TYPE_SIMPLE);
+                bf.put_u16(username.len() as u16);
+                bf.put_slice(username.as_bytes());
+                bf.put_slice(password.as_ref());
+            }
+            AuthMetadata::Bearer(token) => {
+                bf.put_u8(0x80 | AUTH_TYPE_BEARER);
+                bf.put_slice(token.as_bytes());
+            }
+            AuthMetadata::Custom(auth_type, payload) => {
+                bf.put_u8((auth_type.len() - 1) as u8);
+                bf.put_slice(auth_type.as_bytes());
+                bf.put_slice(payload.as_ref());
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            AuthMetadata::Simple { username, password } => 3 + username.len() + password.len(),
+            AuthMetadata::Bearer(token) => 1 + token.len(),
+            AuthMetadata::Custom(auth_type, payload) => 1 + auth_type.len() + payload.len(),
+        }
+    }
+}
+
+impl From<AuthMetadata> for Bytes {
+    fn from(input: AuthMetadata) -> Bytes {
+        let mut bf = BytesMut::new();
+        input.write_to(&mut bf);
+        bf.freeze()
+    }
+}
+
+#[inline]
+fn utf8(bf: BytesMut) -> RSocketResult<String> {
+    String::from_utf8(bf.to_vec()).map_err(|e| RSocketError::from(format!("{}", e)))
+}
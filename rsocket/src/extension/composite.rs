@@ -2,14 +2,16 @@ use crate::error::{ErrorKind, RSocketError};
 use crate::mime::WellKnownMIME;
 use crate::utils::{RSocketResult, Writeable, U24};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use std::collections::LinkedList;
+use smallvec::SmallVec;
 use std::result::Result;
 
 const MAX_MIME_LEN: usize = 0x7F;
+// most composite metadata carry a route and maybe one more entry.
+const INLINE_ENTRIES: usize = 2;
 
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
 pub struct CompositeMetadata {
-    metadatas: LinkedList<Metadata>,
+    metadatas: SmallVec<[Metadata; INLINE_ENTRIES]>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -45,26 +47,26 @@ impl CompositeMetadataBuilder {
     }
 }
 
-impl Into<Vec<u8>> for CompositeMetadata {
-    fn into(self) -> Vec<u8> {
+impl From<CompositeMetadata> for Vec<u8> {
+    fn from(input: CompositeMetadata) -> Vec<u8> {
         let mut bf = BytesMut::new();
-        self.write_to(&mut bf);
+        input.write_to(&mut bf);
         bf.to_vec()
     }
 }
 
-impl Into<Bytes> for CompositeMetadata {
-    fn into(self) -> Bytes {
+impl From<CompositeMetadata> for Bytes {
+    fn from(input: CompositeMetadata) -> Bytes {
         let mut bf = BytesMut::new();
-        self.write_to(&mut bf);
+        input.write_to(&mut bf);
         bf.freeze()
     }
 }
 
-impl Into<BytesMut> for CompositeMetadata {
-    fn into(self) -> BytesMut {
+impl From<CompositeMetadata> for BytesMut {
+    fn from(input: CompositeMetadata) -> BytesMut {
         let mut bf = BytesMut::new();
-        self.write_to(&mut bf);
+        input.write_to(&mut bf);
         bf
     }
 }
@@ -93,11 +95,11 @@ impl CompositeMetadata {
     }
 
     pub fn decode(b: &mut BytesMut) -> RSocketResult<CompositeMetadata> {
-        let mut metadatas = LinkedList::new();
+        let mut metadatas = SmallVec::new();
         loop {
             match Self::decode_once(b) {
                 Ok(op) => match op {
-                    Some(v) => metadatas.push_back(v),
+                    Some(v) => metadatas.push(v),
                     None => break,
                 },
                 Err(e) => return Err(e),
@@ -110,6 +112,10 @@ impl CompositeMetadata {
         self.metadatas.iter()
     }
 
+    pub fn find(&self, mime: &str) -> Option<&Metadata> {
+        self.metadatas.iter().find(|it| it.mime == mime)
+    }
+
     #[inline]
     fn decode_once(bs: &mut BytesMut) -> RSocketResult<Option<Metadata>> {
         if bs.is_empty() {
@@ -119,6 +125,12 @@ impl CompositeMetadata {
         let m = if 0x80 & first != 0 {
             // Well
             let well = WellKnownMIME::from(first & 0x7F);
+            if well == WellKnownMIME::Unknown {
+                return Err(RSocketError::from(format!(
+                    "unknown well-known MIME id: {}",
+                    first & 0x7F
+                )));
+            }
             well.str().to_string()
         } else {
             // Bad
@@ -127,7 +139,8 @@ impl CompositeMetadata {
                 return Err(RSocketError::from("broken COMPOSITE_METADATA bytes!"));
             }
             let front = bs.split_to(mime_len);
-            String::from_utf8(front.to_vec()).unwrap()
+            String::from_utf8(front.to_vec())
+                .map_err(|e| RSocketError::from(format!("invalid MIME type: {}", e)))?
         };
 
         if bs.len() < 3 {
@@ -142,7 +155,27 @@ impl CompositeMetadata {
     }
 
     pub fn push(&mut self, metadata: Metadata) {
-        self.metadatas.push_back(metadata)
+        self.metadatas.push(metadata)
+    }
+
+    /// Replace the first entry with the same MIME type, or push it if there is none.
+    pub fn set(&mut self, metadata: Metadata) {
+        match self
+            .metadatas
+            .iter_mut()
+            .find(|it| it.mime == metadata.mime)
+        {
+            Some(it) => *it = metadata,
+            None => self.metadatas.push(metadata),
+        }
+    }
+
+    /// Remove every entry of `mime`, returns the first one.
+    pub fn remove(&mut self, mime: &str) -> Option<Metadata> {
+        let first = self.metadatas.iter().position(|it| it.mime == mime)?;
+        let removed = self.metadatas.remove(first);
+        self.metadatas.retain(|it| it.mime != mime);
+        Some(removed)
     }
 }
 
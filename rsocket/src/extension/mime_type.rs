@@ -0,0 +1,105 @@
+use super::CompositeMetadata;
+use crate::error::{ErrorKind, RSocketError};
+use crate::mime::{self, WellKnownMIME};
+use crate::utils::{RSocketResult, Writeable};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+const MAX_MIME_LEN: usize = 0x80;
+
+/// Per-stream data MIME type (`message/x.rsocket.mime-type.v0`).
+///
+/// It overrides the data MIME type negotiated in SETUP for the stream which carries it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MimeTypeMetadata {
+    mime: String,
+}
+
+impl MimeTypeMetadata {
+    /// Returns an error if the MIME type is empty or longer than 128 bytes.
+    pub fn new<I>(mime: I) -> RSocketResult<MimeTypeMetadata>
+    where
+        I: Into<String>,
+    {
+        let mime = mime.into();
+        if mime.is_empty() || mime.len() > MAX_MIME_LEN {
+            return Err(RSocketError::from(format!(
+                "invalid MIME type length: {}",
+                mime.len()
+            )));
+        }
+        Ok(MimeTypeMetadata { mime })
+    }
+
+    pub fn decode(bf: &mut BytesMut) -> RSocketResult<MimeTypeMetadata> {
+        if bf.is_empty() {
+            return Err(RSocketError::from("broken MIME_TYPE metadata bytes!"));
+        }
+        let first = bf.get_u8();
+        if first & 0x80 != 0 {
+            let well = WellKnownMIME::from(first & 0x7F);
+            if well == WellKnownMIME::Unknown {
+                return Err(RSocketError::from(format!(
+                    "unknown well-known MIME id: {}",
+                    first & 0x7F
+                )));
+            }
+            return Ok(MimeTypeMetadata {
+                mime: well.str().to_string(),
+            });
+        }
+        // encoded length is one less than the actual length.
+        let mime_len = first as usize + 1;
+        if bf.len() < mime_len {
+            return Err(RSocketError::from("broken MIME_TYPE metadata bytes!"));
+        }
+        match String::from_utf8(bf.split_to(mime_len).to_vec()) {
+            Ok(mime) => Ok(MimeTypeMetadata { mime }),
+            Err(e) => Err(RSocketError::from(format!("invalid MIME type: {}", e))),
+        }
+    }
+
+    /// Find and decode the per-stream MIME type entry of a composite metadata.
+    pub fn from_composite(
+        composite: &CompositeMetadata,
+    ) -> RSocketResult<Option<MimeTypeMetadata>> {
+        match composite.find(mime::MESSAGE_X_RSOCKET_MIME_TYPE_V0) {
+            Some(it) => {
+                let mut bf = BytesMut::from(it.get_payload().as_ref());
+                Self::decode(&mut bf).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_mime(&self) -> &String {
+        &self.mime
+    }
+}
+
+impl Writeable for MimeTypeMetadata {
+    fn write_to(&self, bf: &mut BytesMut) {
+        let well = WellKnownMIME::from(self.mime.as_str());
+        if well == WellKnownMIME::Unknown {
+            bf.put_u8((self.mime.len() - 1) as u8);
+            bf.put_slice(self.mime.as_bytes());
+        } else {
+            bf.put_u8(0x80 | well.raw());
+        }
+    }
+
+    fn len(&self) -> usize {
+        if WellKnownMIME::from(self.mime.as_str()) == WellKnownMIME::Unknown {
+            1 + self.mime.len()
+        } else {
+            1
+        }
+    }
+}
+
+impl From<MimeTypeMetadata> for Bytes {
+    fn from(input: MimeTypeMetadata) -> Bytes {
+        let mut bf = BytesMut::new();
+        input.write_to(&mut bf);
+        bf.freeze()
+    }
+}
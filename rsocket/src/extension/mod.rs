@@ -1,5 +1,15 @@
+mod authentication;
 mod composite;
+mod mime_type;
+mod priority;
+mod registry;
 mod routing;
+mod tracing;
 
+pub use authentication::AuthMetadata;
 pub use composite::{CompositeMetadata, Metadata};
+pub use mime_type::MimeTypeMetadata;
+pub use priority::PriorityMetadata;
+pub use registry::{DecodedMetadata, MetadataRegistry};
 pub use routing::{RoutingMetadata, RoutingMetadataBuilder};
+pub use tracing::{Sampling, TracingMetadata, TracingMetadataBuilder};
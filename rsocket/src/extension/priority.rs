@@ -0,0 +1,64 @@
+use super::CompositeMetadata;
+use crate::error::RSocketError;
+use crate::mime;
+use crate::utils::{RSocketResult, Writeable};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// Priority of a stream (`message/x.rsocket.priority.v0`), carried by its request.
+///
+/// Streams without it have priority 0. When the connection schedules by priority, frames of
+/// higher priority streams are written first while the transport is busy.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PriorityMetadata {
+    priority: u8,
+}
+
+impl PriorityMetadata {
+    pub fn new(priority: u8) -> PriorityMetadata {
+        PriorityMetadata { priority }
+    }
+
+    pub fn decode(bf: &mut BytesMut) -> RSocketResult<PriorityMetadata> {
+        if bf.len() != 1 {
+            return Err(RSocketError::from("broken PRIORITY metadata bytes!"));
+        }
+        Ok(PriorityMetadata {
+            priority: bf.get_u8(),
+        })
+    }
+
+    /// Find and decode the priority entry of a composite metadata.
+    pub fn from_composite(
+        composite: &CompositeMetadata,
+    ) -> RSocketResult<Option<PriorityMetadata>> {
+        match composite.find(mime::MESSAGE_X_RSOCKET_PRIORITY_V0) {
+            Some(it) => {
+                let mut bf = BytesMut::from(it.get_payload().as_ref());
+                Self::decode(&mut bf).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_priority(&self) -> u8 {
+        self.priority
+    }
+}
+
+impl Writeable for PriorityMetadata {
+    fn write_to(&self, bf: &mut BytesMut) {
+        bf.put_u8(self.priority);
+    }
+
+    fn len(&self) -> usize {
+        1
+    }
+}
+
+impl From<PriorityMetadata> for Bytes {
+    fn from(input: PriorityMetadata) -> Bytes {
+        let mut bf = BytesMut::new();
+        input.write_to(&mut bf);
+        bf.freeze()
+    }
+}
@@ -0,0 +1,172 @@
+use super::{
+    AuthMetadata, CompositeMetadata, Metadata, MimeTypeMetadata, RoutingMetadata, TracingMetadata,
+};
+use crate::error::RSocketError;
+use crate::mime;
+use crate::utils::{RSocketResult, Writeable};
+use bytes::{Bytes, BytesMut};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+type AnyValue = Box<dyn Any + Send + Sync>;
+type FnEncode = Arc<dyn Fn(&dyn Any) -> Option<Bytes> + Send + Sync>;
+type FnDecode = Arc<dyn Fn(&Bytes) -> RSocketResult<AnyValue> + Send + Sync>;
+
+/// Registry of metadata MIME types and their codecs.
+///
+/// Routing, per-stream MIME type, authentication and zipkin tracing metadata are registered
+/// by default, applications can register their own MIME types with `register`.
+#[derive(Clone)]
+pub struct MetadataRegistry {
+    codecs: HashMap<String, (FnEncode, FnDecode)>,
+}
+
+/// A composite metadata entry decoded by a `MetadataRegistry`.
+pub struct DecodedMetadata {
+    mime: String,
+    raw: Bytes,
+    value: Option<AnyValue>,
+}
+
+impl MetadataRegistry {
+    /// Create a registry without any codec.
+    pub fn empty() -> MetadataRegistry {
+        MetadataRegistry {
+            codecs: HashMap::new(),
+        }
+    }
+
+    pub fn register<T, E, D>(&mut self, mime: &str, encode: E, decode: D) -> &mut Self
+    where
+        T: Send + Sync + 'static,
+        E: Fn(&T) -> Bytes + Send + Sync + 'static,
+        D: Fn(&Bytes) -> RSocketResult<T> + Send + Sync + 'static,
+    {
+        let encode: FnEncode = Arc::new(move |v: &dyn Any| v.downcast_ref::<T>().map(&encode));
+        let decode: FnDecode = Arc::new(move |b: &Bytes| match decode(b) {
+            Ok(v) => Ok(Box::new(v) as AnyValue),
+            Err(e) => Err(e),
+        });
+        self.codecs.insert(mime.to_string(), (encode, decode));
+        self
+    }
+
+    pub fn unregister(&mut self, mime: &str) -> bool {
+        self.codecs.remove(mime).is_some()
+    }
+
+    pub fn contains(&self, mime: &str) -> bool {
+        self.codecs.contains_key(mime)
+    }
+
+    /// Encode a typed value into a composite metadata entry.
+    pub fn encode<T>(&self, mime: &str, value: &T) -> RSocketResult<Metadata>
+    where
+        T: 'static,
+    {
+        let (encode, _) = self
+            .codecs
+            .get(mime)
+            .ok_or_else(|| RSocketError::from(format!("unregistered metadata MIME: {}", mime)))?;
+        match encode(value) {
+            Some(b) => Ok(Metadata::new(mime.to_string(), b)),
+            None => Err(RSocketError::from(format!(
+                "mismatched value type for metadata MIME: {}",
+                mime
+            ))),
+        }
+    }
+
+    /// Decode a composite metadata entry, entries of unregistered MIME types are kept raw.
+    pub fn decode(&self, metadata: &Metadata) -> RSocketResult<DecodedMetadata> {
+        let raw = metadata.get_payload().clone();
+        let value = match self.codecs.get(metadata.get_mime()) {
+            Some((_, decode)) => Some(decode(&raw)?),
+            None => None,
+        };
+        Ok(DecodedMetadata {
+            mime: metadata.get_mime().clone(),
+            raw,
+            value,
+        })
+    }
+
+    pub fn decode_all(&self, composite: &CompositeMetadata) -> RSocketResult<Vec<DecodedMetadata>> {
+        composite.iter().map(|it| self.decode(it)).collect()
+    }
+}
+
+impl Default for MetadataRegistry {
+    fn default() -> MetadataRegistry {
+        let mut registry = MetadataRegistry::empty();
+        registry
+            .register(
+                mime::MESSAGE_X_RSOCKET_ROUTING_V0,
+                |v: &RoutingMetadata| to_bytes(v),
+                |b| RoutingMetadata::decode(&mut BytesMut::from(b.as_ref())),
+            )
+            .register(
+                mime::MESSAGE_X_RSOCKET_MIME_TYPE_V0,
+                |v: &MimeTypeMetadata| to_bytes(v),
+                |b| MimeTypeMetadata::decode(&mut BytesMut::from(b.as_ref())),
+            )
+            .register(
+                mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0,
+                |v: &AuthMetadata| to_bytes(v),
+                |b| AuthMetadata::decode(&mut BytesMut::from(b.as_ref())),
+            )
+            .register(
+                mime::MESSAGE_X_RSOCKET_TRACING_ZIPKIN_V0,
+                |v: &TracingMetadata| to_bytes(v),
+                |b| TracingMetadata::decode(&mut BytesMut::from(b.as_ref())),
+            );
+        registry
+    }
+}
+
+impl fmt::Debug for MetadataRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.codecs.keys()).finish()
+    }
+}
+
+impl DecodedMetadata {
+    pub fn get_mime(&self) -> &String {
+        &self.mime
+    }
+
+    pub fn get_raw(&self) -> &Bytes {
+        &self.raw
+    }
+
+    /// Returns true if the entry was decoded by a registered codec.
+    pub fn is_typed(&self) -> bool {
+        self.value.is_some()
+    }
+
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: 'static,
+    {
+        self.value.as_ref().and_then(|v| v.downcast_ref::<T>())
+    }
+}
+
+impl fmt::Debug for DecodedMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DecodedMetadata")
+            .field("mime", &self.mime)
+            .field("raw", &self.raw)
+            .field("typed", &self.is_typed())
+            .finish()
+    }
+}
+
+#[inline]
+fn to_bytes(value: &impl Writeable) -> Bytes {
+    let mut bf = BytesMut::with_capacity(value.len());
+    value.write_to(&mut bf);
+    bf.freeze()
+}
@@ -1,13 +1,16 @@
+use super::CompositeMetadata;
 use crate::error::{ErrorKind, RSocketError};
-use crate::mime::WellKnownMIME;
+use crate::mime::{self, WellKnownMIME};
 use crate::utils::{RSocketResult, Writeable, U24};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+use smallvec::SmallVec;
 
 const MAX_ROUTING_TAG_LEN: usize = 0xFF;
+const INLINE_TAGS: usize = 2;
 
 #[derive(Debug, Clone)]
 pub struct RoutingMetadata {
-    tags: Vec<String>,
+    tags: SmallVec<[String; INLINE_TAGS]>,
 }
 
 pub struct RoutingMetadataBuilder {
@@ -33,7 +36,9 @@ impl RoutingMetadataBuilder {
 impl RoutingMetadata {
     pub fn builder() -> RoutingMetadataBuilder {
         RoutingMetadataBuilder {
-            inner: RoutingMetadata { tags: vec![] },
+            inner: RoutingMetadata {
+                tags: SmallVec::new(),
+            },
         }
     }
 
@@ -51,7 +56,17 @@ impl RoutingMetadata {
         Ok(bu.build())
     }
 
-    pub fn get_tags(&self) -> &Vec<String> {
+    pub fn from_composite(composite: &CompositeMetadata) -> RSocketResult<Option<RoutingMetadata>> {
+        match composite.find(mime::MESSAGE_X_RSOCKET_ROUTING_V0) {
+            Some(it) => {
+                let mut bf = BytesMut::from(it.get_payload().as_ref());
+                Self::decode(&mut bf).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_tags(&self) -> &[String] {
         &self.tags
     }
 
@@ -63,8 +78,10 @@ impl RoutingMetadata {
         if bf.len() < size {
             return Err(RSocketError::from("require more bytes!"));
         }
-        let tag = String::from_utf8(bf.split_to(size).to_vec()).unwrap();
-        Ok(Some(tag))
+        match String::from_utf8(bf.split_to(size).to_vec()) {
+            Ok(tag) => Ok(Some(tag)),
+            Err(e) => Err(RSocketError::from(format!("invalid routing tag: {}", e))),
+        }
     }
 }
 
@@ -80,8 +97,16 @@ impl Writeable for RoutingMetadata {
     fn len(&self) -> usize {
         let mut n = 0;
         for tag in &self.tags {
-            n += 1 + tag.as_bytes().len();
+            n += 1 + tag.len();
         }
         n
     }
 }
+
+impl From<RoutingMetadata> for Bytes {
+    fn from(input: RoutingMetadata) -> Bytes {
+        let mut bf = BytesMut::new();
+        input.write_to(&mut bf);
+        bf.freeze()
+    }
+}
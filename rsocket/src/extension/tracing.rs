@@ -0,0 +1,354 @@
+use crate::error::RSocketError;
+use crate::utils::{RSocketResult, Writeable};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+
+const FLAG_IDS_SET: u8 = 0x80;
+const FLAG_DEBUG: u8 = 0x40;
+const FLAG_SAMPLED: u8 = 0x20;
+const FLAG_NOT_SAMPLED: u8 = 0x10;
+const FLAG_EXTENDED_TRACE_ID: u8 = 0x08;
+const FLAG_PARENT_ID: u8 = 0x04;
+
+/// Sampling decision carried by tracing metadata.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Sampling {
+    Unspecified,
+    Debug,
+    Sampled,
+    NotSampled,
+}
+
+/// Zipkin tracing metadata (`message/x.rsocket.tracing-zipkin.v0`).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TracingMetadata {
+    sampling: Sampling,
+    ids: Option<TraceIds>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct TraceIds {
+    trace_id_high: u64,
+    trace_id: u64,
+    span_id: u64,
+    parent_id: Option<u64>,
+}
+
+pub struct TracingMetadataBuilder {
+    sampling: Sampling,
+    trace_id_high: u64,
+    trace_id: u64,
+    span_id: u64,
+    parent_id: Option<u64>,
+}
+
+impl TracingMetadataBuilder {
+    pub fn set_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn set_trace_id(mut self, trace_id: u64) -> Self {
+        self.trace_id = trace_id;
+        self
+    }
+
+    pub fn set_trace_id_high(mut self, trace_id_high: u64) -> Self {
+        self.trace_id_high = trace_id_high;
+        self
+    }
+
+    pub fn set_span_id(mut self, span_id: u64) -> Self {
+        self.span_id = span_id;
+        self
+    }
+
+    pub fn set_parent_id(mut self, parent_id: u64) -> Self {
+        self.parent_id = Some(parent_id);
+        self
+    }
+
+    pub fn build(self) -> TracingMetadata {
+        let ids = if self.trace_id == 0 && self.trace_id_high == 0 {
+            None
+        } else {
+            Some(TraceIds {
+                trace_id_high: self.trace_id_high,
+                trace_id: self.trace_id,
+                span_id: self.span_id,
+                parent_id: self.parent_id,
+            })
+        };
+        TracingMetadata {
+            sampling: self.sampling,
+            ids,
+        }
+    }
+}
+
+impl TracingMetadata {
+    pub fn builder() -> TracingMetadataBuilder {
+        TracingMetadataBuilder {
+            sampling: Sampling::Unspecified,
+            trace_id_high: 0,
+            trace_id: 0,
+            span_id: 0,
+            parent_id: None,
+        }
+    }
+
+    /// Start a new trace with random 64-bit trace id and span id.
+    pub fn new_root(sampling: Sampling) -> TracingMetadata {
+        let trace_id = random_id();
+        TracingMetadata::builder()
+            .set_sampling(sampling)
+            .set_trace_id(trace_id)
+            .set_span_id(trace_id)
+            .build()
+    }
+
+    /// Create a child span which shares the trace id and sampling decision.
+    pub fn new_child(&self) -> TracingMetadata {
+        match &self.ids {
+            Some(ids) => TracingMetadata {
+                sampling: self.sampling,
+                ids: Some(TraceIds {
+                    trace_id_high: ids.trace_id_high,
+                    trace_id: ids.trace_id,
+                    span_id: random_id(),
+                    parent_id: Some(ids.span_id),
+                }),
+            },
+            None => TracingMetadata::new_root(self.sampling),
+        }
+    }
+
+    pub fn decode(bf: &mut BytesMut) -> RSocketResult<TracingMetadata> {
+        if bf.is_empty() {
+            return Err(RSocketError::from("broken TRACING metadata bytes!"));
+        }
+        let flags = bf.get_u8();
+        let sampling = if flags & FLAG_DEBUG != 0 {
+            Sampling::Debug
+        } else if flags & FLAG_SAMPLED != 0 {
+            Sampling::Sampled
+        } else if flags & FLAG_NOT_SAMPLED != 0 {
+            Sampling::NotSampled
+        } else {
+            Sampling::Unspecified
+        };
+        if flags & FLAG_IDS_SET == 0 {
+            return Ok(TracingMetadata {
+                sampling,
+                ids: None,
+            });
+        }
+        let mut need = 16;
+        if flags & FLAG_EXTENDED_TRACE_ID != 0 {
+            need += 8;
+        }
+        if flags & FLAG_PARENT_ID != 0 {
+            need += 8;
+        }
+        if bf.len() < need {
+            return Err(RSocketError::from("broken TRACING metadata bytes!"));
+        }
+        let trace_id_high = if flags & FLAG_EXTENDED_TRACE_ID != 0 {
+            bf.get_u64()
+        } else {
+            0
+        };
+        let trace_id = bf.get_u64();
+        let span_id = bf.get_u64();
+        let parent_id = if flags & FLAG_PARENT_ID != 0 {
+            Some(bf.get_u64())
+        } else {
+            None
+        };
+        Ok(TracingMetadata {
+            sampling,
+            ids: Some(TraceIds {
+                trace_id_high,
+                trace_id,
+                span_id,
+                parent_id,
+            }),
+        })
+    }
+
+    /// Parse a B3 single header value (`{trace_id}-{span_id}-{sampling}-{parent_id}`).
+    pub fn from_b3(value: &str) -> RSocketResult<TracingMetadata> {
+        let sampling_of = |s: &str| match s {
+            "1" => Ok(Sampling::Sampled),
+            "0" => Ok(Sampling::NotSampled),
+            "d" => Ok(Sampling::Debug),
+            _ => Err(RSocketError::from(format!("invalid b3 sampling: {}", s))),
+        };
+        let parts: Vec<&str> = value.split('-').collect();
+        if parts.len() == 1 {
+            return Ok(TracingMetadata {
+                sampling: sampling_of(parts[0])?,
+                ids: None,
+            });
+        }
+        if parts.len() > 4 {
+            return Err(RSocketError::from(format!("invalid b3 header: {}", value)));
+        }
+        let hex = |s: &str| {
+            u64::from_str_radix(s, 16)
+                .map_err(|_| RSocketError::from(format!("invalid b3 id: {}", s)))
+        };
+        let trace = parts[0];
+        let (trace_id_high, trace_id) = match trace.len() {
+            16 => (0, hex(trace)?),
+            32 => (hex(&trace[..16])?, hex(&trace[16..])?),
+            _ => {
+                return Err(RSocketError::from(format!(
+                    "invalid b3 trace id: {}",
+                    trace
+                )))
+            }
+        };
+        let mut bu = TracingMetadata::builder()
+            .set_trace_id_high(trace_id_high)
+            .set_trace_id(trace_id)
+            .set_span_id(hex(parts[1])?);
+        if parts.len() > 2 {
+            bu = bu.set_sampling(sampling_of(parts[2])?);
+        }
+        if parts.len() > 3 {
+            bu = bu.set_parent_id(hex(parts[3])?);
+        }
+        Ok(bu.build())
+    }
+
+    /// Format as a B3 single header value, for propagation over HTTP hops.
+    pub fn to_b3(&self) -> String {
+        let sampling = match self.sampling {
+            Sampling::Sampled => Some("1"),
+            Sampling::NotSampled => Some("0"),
+            Sampling::Debug => Some("d"),
+            Sampling::Unspecified => None,
+        };
+        match &self.ids {
+            None => sampling.unwrap_or("").to_string(),
+            Some(ids) => {
+                let mut s = if ids.trace_id_high != 0 {
+                    format!("{:016x}{:016x}", ids.trace_id_high, ids.trace_id)
+                } else {
+                    format!("{:016x}", ids.trace_id)
+                };
+                s.push_str(&format!("-{:016x}", ids.span_id));
+                if let Some(v) = sampling {
+                    s.push('-');
+                    s.push_str(v);
+                    if let Some(p) = ids.parent_id {
+                        s.push_str(&format!("-{:016x}", p));
+                    }
+                }
+                s
+            }
+        }
+    }
+
+    pub fn get_sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_none()
+    }
+
+    pub fn get_trace_id_high(&self) -> u64 {
+        self.ids.map(|it| it.trace_id_high).unwrap_or(0)
+    }
+
+    pub fn get_trace_id(&self) -> u64 {
+        self.ids.map(|it| it.trace_id).unwrap_or(0)
+    }
+
+    pub fn get_span_id(&self) -> u64 {
+        self.ids.map(|it| it.span_id).unwrap_or(0)
+    }
+
+    pub fn get_parent_id(&self) -> Option<u64> {
+        self.ids.and_then(|it| it.parent_id)
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = match self.sampling {
+            Sampling::Debug => FLAG_DEBUG,
+            Sampling::Sampled => FLAG_SAMPLED,
+            Sampling::NotSampled => FLAG_NOT_SAMPLED,
+            Sampling::Unspecified => 0,
+        };
+        if let Some(ids) = &self.ids {
+            flags |= FLAG_IDS_SET;
+            if ids.trace_id_high != 0 {
+                flags |= FLAG_EXTENDED_TRACE_ID;
+            }
+            if ids.parent_id.is_some() {
+                flags |= FLAG_PARENT_ID;
+            }
+        }
+        flags
+    }
+}
+
+impl Writeable for TracingMetadata {
+    fn write_to(&self, bf: &mut BytesMut) {
+        bf.put_u8(self.flags());
+        if let Some(ids) = &self.ids {
+            if ids.trace_id_high != 0 {
+                bf.put_u64(ids.trace_id_high);
+            }
+            bf.put_u64(ids.trace_id);
+            bf.put_u64(ids.span_id);
+            if let Some(p) = ids.parent_id {
+                bf.put_u64(p);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match &self.ids {
+            None => 1,
+            Some(ids) => {
+                let mut n = 17;
+                if ids.trace_id_high != 0 {
+                    n += 8;
+                }
+                if ids.parent_id.is_some() {
+                    n += 8;
+                }
+                n
+            }
+        }
+    }
+}
+
+impl From<TracingMetadata> for Bytes {
+    fn from(input: TracingMetadata) -> Bytes {
+        let mut bf = BytesMut::new();
+        input.write_to(&mut bf);
+        bf.freeze()
+    }
+}
+
+impl fmt::Display for TracingMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_b3())
+    }
+}
+
+#[inline]
+fn random_id() -> u64 {
+    loop {
+        let n = RandomState::new().build_hasher().finish();
+        if n != 0 {
+            return n;
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Human-readable renderings of raw frame bytes, useful in tests and when decoding fails.
+//!
+//! The input of `annotate` is an encoded frame without any transport length prefix,
+//! malformed or truncated input is rendered as far as possible and never panics.
+use super::*;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+const HEXDUMP_WIDTH: usize = 16;
+
+/// Render bytes as lines of offset, hex and ASCII columns.
+pub fn hexdump(b: &[u8]) -> String {
+    let mut s = String::new();
+    hexdump_to(&mut s, b, "");
+    s
+}
+
+/// Render the header fields, flags and payload boundaries of an encoded frame.
+///
+/// ```text
+/// Frame (25 bytes)
+///   Stream ID: 1
+///   Frame Type: REQUEST_RESPONSE (0x04)
+///   Flags: 0x100 (METADATA)
+///   Metadata Length: 4
+///   Metadata (4 bytes):
+///     0000  73 65 63 72                                      secr
+///   Data (12 bytes):
+///     0000  48 65 6c 6c 6f 20 57 6f 72 6c 64 21              Hello World!
+/// ```
+pub fn annotate(b: &[u8]) -> String {
+    let mut s = String::new();
+    writeln!(s, "Frame ({} bytes)", b.len()).unwrap();
+    let mut c = Cursor { b, pos: 0, out: s };
+    if let Err(e) = c.frame() {
+        writeln!(c.out, "  <{}>", e).unwrap();
+    }
+    if c.pos < b.len() {
+        writeln!(c.out, "  Unparsed ({} bytes):", b.len() - c.pos).unwrap();
+        hexdump_to(&mut c.out, &b[c.pos..], "    ");
+    }
+    c.out
+}
+
+fn hexdump_to(s: &mut String, b: &[u8], indent: &str) {
+    for (i, line) in b.chunks(HEXDUMP_WIDTH).enumerate() {
+        write!(s, "{}{:04x} ", indent, i * HEXDUMP_WIDTH).unwrap();
+        for x in line {
+            write!(s, " {:02x}", x).unwrap();
+        }
+        for _ in line.len()..HEXDUMP_WIDTH {
+            s.push_str("   ");
+        }
+        s.push_str("  ");
+        for x in line {
+            s.push(if x.is_ascii_graphic() || *x == b' ' {
+                *x as char
+            } else {
+                '.'
+            });
+        }
+        s.push('\n');
+    }
+}
+
+fn type_name(kind: u16) -> &'static str {
+    match kind {
+        TYPE_SETUP => "SETUP",
+        TYPE_LEASE => "LEASE",
+        TYPE_KEEPALIVE => "KEEPALIVE",
+        TYPE_REQUEST_RESPONSE => "REQUEST_RESPONSE",
+        TYPE_REQUEST_FNF => "REQUEST_FNF",
+        TYPE_REQUEST_STREAM => "REQUEST_STREAM",
+        TYPE_REQUEST_CHANNEL => "REQUEST_CHANNEL",
+        TYPE_REQUEST_N => "REQUEST_N",
+        TYPE_CANCEL => "CANCEL",
+        TYPE_PAYLOAD => "PAYLOAD",
+        TYPE_ERROR => "ERROR",
+        TYPE_METADATA_PUSH => "METADATA_PUSH",
+        TYPE_RESUME => "RESUME",
+        TYPE_RESUME_OK => "RESUME_OK",
+        _ => "UNKNOWN",
+    }
+}
+
+fn flag_names(kind: u16, flag: u16) -> Vec<&'static str> {
+    let mut names = vec![];
+    if flag & FLAG_IGNORE != 0 {
+        names.push("IGNORE");
+    }
+    if flag & FLAG_METADATA != 0 {
+        names.push("METADATA");
+    }
+    if flag & FLAG_FOLLOW != 0 {
+        names.push(match kind {
+            TYPE_SETUP => "RESUME",
+            TYPE_KEEPALIVE => "RESPOND",
+            _ => "FOLLOW",
+        });
+    }
+    if flag & FLAG_COMPLETE != 0 {
+        names.push(if kind == TYPE_SETUP {
+            "LEASE"
+        } else {
+            "COMPLETE"
+        });
+    }
+    if flag & FLAG_NEXT != 0 {
+        names.push("NEXT");
+    }
+    names
+}
+
+struct Cursor<'a> {
+    b: &'a [u8],
+    pos: usize,
+    out: String,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize, what: &str) -> Result<&'a [u8], String> {
+        let remaining = self.b.len() - self.pos;
+        if remaining < n {
+            return Err(format!(
+                "truncated {}: need {} bytes at offset {}, {} remaining",
+                what, n, self.pos, remaining
+            ));
+        }
+        let b = self.b;
+        let v = &b[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(v)
+    }
+
+    fn uint(&mut self, n: usize, what: &str) -> Result<u64, String> {
+        Ok(self
+            .take(n, what)?
+            .iter()
+            .fold(0u64, |acc, x| (acc << 8) | *x as u64))
+    }
+
+    fn field(&mut self, n: usize, what: &str) -> Result<u64, String> {
+        let v = self.uint(n, what)?;
+        writeln!(self.out, "  {}: {}", what, v).unwrap();
+        Ok(v)
+    }
+
+    fn bytes(&mut self, n: usize, what: &str) -> Result<(), String> {
+        let v = self.take(n, what)?;
+        writeln!(self.out, "  {} ({} bytes):", what, n).unwrap();
+        hexdump_to(&mut self.out, v, "    ");
+        Ok(())
+    }
+
+    fn string(&mut self, n: usize, what: &str) -> Result<(), String> {
+        let v = self.take(n, what)?;
+        writeln!(self.out, "  {}: {:?}", what, String::from_utf8_lossy(v)).unwrap();
+        Ok(())
+    }
+
+    fn rest(&mut self, what: &str) -> Result<(), String> {
+        let n = self.b.len() - self.pos;
+        if n > 0 {
+            self.bytes(n, what)?;
+        }
+        Ok(())
+    }
+
+    fn payload(&mut self, flag: u16) -> Result<(), String> {
+        if flag & FLAG_METADATA != 0 {
+            let n = self.field(3, "Metadata Length")?;
+            self.bytes(n as usize, "Metadata")?;
+        }
+        self.rest("Data")
+    }
+
+    fn frame(&mut self) -> Result<(), String> {
+        let sid = self.uint(4, "Stream ID")?;
+        let stream_id = sid & 0x7FFF_FFFF;
+        if sid == stream_id {
+            writeln!(self.out, "  Stream ID: {}", stream_id).unwrap();
+        } else {
+            writeln!(self.out, "  Stream ID: {} (reserved bit set)", stream_id).unwrap();
+        }
+        let n = self.uint(2, "Frame Type and Flags")? as u16;
+        let (flag, kind) = (n & 0x03FF, (n & 0xFC00) >> 10);
+        writeln!(
+            self.out,
+            "  Frame Type: {} (0x{:02X})",
+            type_name(kind),
+            kind
+        )
+        .unwrap();
+        let names = flag_names(kind, flag);
+        if names.is_empty() {
+            writeln!(self.out, "  Flags: 0x{:03X}", flag).unwrap();
+        } else {
+            writeln!(self.out, "  Flags: 0x{:03X} ({})", flag, names.join(" | ")).unwrap();
+        }
+        match kind {
+            TYPE_SETUP => {
+                self.field(2, "Major Version")?;
+                self.field(2, "Minor Version")?;
+                self.field(4, "Keepalive")?;
+                self.field(4, "Lifetime")?;
+                if flag & FLAG_RESUME != 0 {
+                    let n = self.field(2, "Resume Token Length")?;
+                    self.bytes(n as usize, "Resume Token")?;
+                }
+                let n = self.uint(1, "Metadata MIME Length")?;
+                self.string(n as usize, "Metadata MIME")?;
+                let n = self.uint(1, "Data MIME Length")?;
+                self.string(n as usize, "Data MIME")?;
+                self.payload(flag)
+            }
+            TYPE_LEASE => {
+                self.field(4, "Time-To-Live")?;
+                self.field(4, "Number of Requests")?;
+                self.rest("Metadata")
+            }
+            TYPE_KEEPALIVE => {
+                self.field(8, "Last Received Position")?;
+                self.rest("Data")
+            }
+            TYPE_REQUEST_RESPONSE | TYPE_REQUEST_FNF | TYPE_PAYLOAD => self.payload(flag),
+            TYPE_REQUEST_STREAM | TYPE_REQUEST_CHANNEL => {
+                self.field(4, "Initial Request N")?;
+                self.payload(flag)
+            }
+            TYPE_REQUEST_N => self.field(4, "Request N").map(|_| ()),
+            TYPE_CANCEL => Ok(()),
+            TYPE_ERROR => {
+                let code = self.uint(4, "Error Code")?;
+                writeln!(self.out, "  Error Code: 0x{:08X}", code).unwrap();
+                self.rest("Error Data")
+            }
+            TYPE_METADATA_PUSH => self.rest("Metadata"),
+            TYPE_RESUME => {
+                self.field(2, "Major Version")?;
+                self.field(2, "Minor Version")?;
+                let n = self.field(2, "Resume Token Length")?;
+                self.bytes(n as usize, "Resume Token")?;
+                self.field(8, "Last Received Server Position")?;
+                self.field(8, "First Available Client Position")?;
+                Ok(())
+            }
+            TYPE_RESUME_OK => self.field(8, "Last Received Client Position").map(|_| ()),
+            _ => Err(format!("illegal frame type: {}", kind)),
+        }
+    }
+}
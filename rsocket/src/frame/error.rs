@@ -1,7 +1,8 @@
-use super::{Body, Frame};
+use super::{utils, Body, Frame};
 use crate::utils::{RSocketResult, Writeable};
+use alloc::string::String;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use std::fmt;
+use core::fmt;
 
 #[derive(Debug, PartialEq)]
 pub struct Error {
@@ -44,6 +45,7 @@ impl ErrorBuilder {
 
 impl Error {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<Error> {
+        utils::require(bf, 4)?;
         let code = bf.get_u32();
         let d: Option<Bytes> = if !bf.is_empty() {
             Some(bf.to_bytes())
@@ -76,9 +78,8 @@ impl Error {
 impl Writeable for Error {
     fn write_to(&self, bf: &mut BytesMut) {
         bf.put_u32(self.code);
-        match &self.data {
-            Some(v) => bf.put(v.bytes()),
-            None => (),
+        if let Some(v) = &self.data {
+            bf.put(v.bytes());
         }
     }
 
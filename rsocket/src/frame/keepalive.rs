@@ -1,4 +1,4 @@
-use super::{Body, Frame};
+use super::{utils, Body, Frame};
 use crate::utils::{RSocketResult, Writeable};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -43,6 +43,7 @@ impl KeepaliveBuilder {
 
 impl Keepalive {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<Keepalive> {
+        utils::require(bf, 8)?;
         let position = bf.get_u64();
         let mut d: Option<Bytes> = None;
         if !bf.is_empty() {
@@ -74,9 +75,8 @@ impl Keepalive {
 impl Writeable for Keepalive {
     fn write_to(&self, bf: &mut BytesMut) {
         bf.put_u64(self.last_received_position);
-        match &self.data {
-            Some(v) => bf.put(v.bytes()),
-            None => (),
+        if let Some(v) = &self.data {
+            bf.put(v.bytes());
         }
     }
 
@@ -1,4 +1,4 @@
-use super::{Body, Frame, FLAG_METADATA};
+use super::{utils, Body, Frame, FLAG_METADATA};
 use crate::utils::{RSocketResult, Writeable};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -51,6 +51,7 @@ impl LeaseBuilder {
 
 impl Lease {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<Lease> {
+        utils::require(bf, 8)?;
         let ttl = bf.get_u32();
         let n = bf.get_u32();
         let m = if flag & FLAG_METADATA != 0 {
@@ -86,9 +87,8 @@ impl Writeable for Lease {
     fn write_to(&self, bf: &mut BytesMut) {
         bf.put_u32(self.ttl);
         bf.put_u32(self.number_of_requests);
-        match &self.metadata {
-            Some(v) => bf.put(v.bytes()),
-            None => (),
+        if let Some(v) = &self.metadata {
+            bf.put(v.bytes());
         }
     }
 
@@ -1,4 +1,4 @@
-use super::{Body, Frame};
+use super::{Body, Frame, FLAG_METADATA};
 use crate::utils::{RSocketResult, Writeable};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -24,6 +24,7 @@ impl MetadataPushBuiler {
 
     pub fn set_metadata(mut self, metadata: Bytes) -> Self {
         self.value.metadata = Some(metadata);
+        self.flag |= FLAG_METADATA;
         self
     }
 
@@ -34,7 +35,7 @@ impl MetadataPushBuiler {
 
 impl MetadataPush {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<MetadataPush> {
-        let m = Bytes::from(bf.to_vec());
+        let m = bf.split().freeze();
         Ok(MetadataPush { metadata: Some(m) })
     }
 
@@ -53,9 +54,8 @@ impl MetadataPush {
 
 impl Writeable for MetadataPush {
     fn write_to(&self, bf: &mut BytesMut) {
-        match &self.metadata {
-            Some(v) => bf.put(v.bytes()),
-            None => (),
+        if let Some(v) = &self.metadata {
+            bf.put(v.bytes());
         }
     }
 
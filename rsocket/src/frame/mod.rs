@@ -3,6 +3,7 @@ use crate::utils::{RSocketResult, Writeable};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 mod cancel;
+pub mod dump;
 mod error;
 mod keepalive;
 mod lease;
@@ -143,7 +144,7 @@ impl Frame {
     }
 
     pub fn decode(b: &mut BytesMut) -> RSocketResult<Frame> {
-        // TODO: check size
+        utils::require(b, 6)?;
         let sid = b.get_u32();
         let n = b.get_u16();
         let (flag, kind) = (n & 0x03FF, (n & 0xFC00) >> 10);
@@ -167,10 +168,98 @@ impl Frame {
         body.map(|it| Frame::new(sid, it, flag))
     }
 
+    /// Write the frame header and all fields preceding the payload into `bf`, then return
+    /// the metadata and data which follow it without copying them. Writing them in order
+    /// gives the same bytes as `write_to`, so they can be sent with vectored I/O.
+    pub fn write_head_to(&self, bf: &mut BytesMut) -> (Option<Bytes>, Option<Bytes>) {
+        bf.put_u32(self.stream_id);
+        bf.put_u16((to_frame_type(&self.body) << 10) | self.flag);
+        match &self.body {
+            Body::Setup(v) => {
+                v.write_head(bf);
+                PayloadSupport::write_len(bf, v.get_metadata());
+            }
+            Body::RequestStream(v) => {
+                bf.put_u32(v.get_initial_request_n());
+                PayloadSupport::write_len(bf, v.get_metadata());
+            }
+            Body::RequestChannel(v) => {
+                bf.put_u32(v.get_initial_request_n());
+                PayloadSupport::write_len(bf, v.get_metadata());
+            }
+            Body::RequestResponse(v) => PayloadSupport::write_len(bf, v.get_metadata()),
+            Body::RequestFNF(v) => PayloadSupport::write_len(bf, v.get_metadata()),
+            Body::Payload(v) => PayloadSupport::write_len(bf, v.get_metadata()),
+            Body::Keepalive(v) => bf.put_u64(v.get_last_received_position()),
+            Body::Error(v) => bf.put_u32(v.get_code()),
+            Body::Lease(v) => {
+                bf.put_u32(v.get_ttl());
+                bf.put_u32(v.get_number_of_requests());
+            }
+            Body::MetadataPush(_) => (),
+            Body::RequestN(v) => v.write_to(bf),
+            Body::Cancel() => (),
+            Body::ResumeOK(v) => v.write_to(bf),
+            Body::Resume(v) => v.write_to(bf),
+        }
+        (self.get_metadata().cloned(), self.get_data().cloned())
+    }
+
     pub fn get_body(self) -> Body {
         self.body
     }
 
+    pub fn get_body_ref(&self) -> &Body {
+        &self.body
+    }
+
+    pub fn get_data(&self) -> Option<&Bytes> {
+        match &self.body {
+            Body::Setup(v) => v.get_data().as_ref(),
+            Body::RequestResponse(v) => v.get_data().as_ref(),
+            Body::RequestStream(v) => v.get_data().as_ref(),
+            Body::RequestChannel(v) => v.get_data().as_ref(),
+            Body::RequestFNF(v) => v.get_data().as_ref(),
+            Body::Keepalive(v) => v.get_data().as_ref(),
+            Body::Payload(v) => v.get_data().as_ref(),
+            Body::Error(v) => v.get_data().as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_metadata(&self) -> Option<&Bytes> {
+        match &self.body {
+            Body::Setup(v) => v.get_metadata().as_ref(),
+            Body::RequestResponse(v) => v.get_metadata().as_ref(),
+            Body::RequestStream(v) => v.get_metadata().as_ref(),
+            Body::RequestChannel(v) => v.get_metadata().as_ref(),
+            Body::RequestFNF(v) => v.get_metadata().as_ref(),
+            Body::MetadataPush(v) => v.get_metadata().as_ref(),
+            Body::Payload(v) => v.get_metadata().as_ref(),
+            Body::Lease(v) => v.get_metadata().as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_frame_type_name(&self) -> &'static str {
+        match &self.body {
+            Body::Setup(_) => "SETUP",
+            Body::Lease(_) => "LEASE",
+            Body::Keepalive(_) => "KEEPALIVE",
+            Body::RequestResponse(_) => "REQUEST_RESPONSE",
+            Body::RequestFNF(_) => "REQUEST_FNF",
+            Body::RequestStream(_) => "REQUEST_STREAM",
+            Body::RequestChannel(_) => "REQUEST_CHANNEL",
+            Body::RequestN(_) => "REQUEST_N",
+            Body::Cancel() => "CANCEL",
+            Body::Payload(_) => "PAYLOAD",
+            Body::Error(_) => "ERROR",
+            Body::MetadataPush(_) => "METADATA_PUSH",
+            Body::Resume(_) => "RESUME",
+            Body::ResumeOK(_) => "RESUME_OK",
+        }
+    }
+
     pub fn get_frame_type(&self) -> u16 {
         to_frame_type(&self.body)
     }
@@ -183,6 +272,10 @@ impl Frame {
         self.stream_id
     }
 
+    pub(crate) fn set_stream_id(&mut self, stream_id: u32) {
+        self.stream_id = stream_id;
+    }
+
     pub fn has_next(&self) -> bool {
         self.flag & FLAG_NEXT != 0
     }
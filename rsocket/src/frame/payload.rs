@@ -44,7 +44,7 @@ impl PayloadBuilder {
 
 impl Payload {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<Payload> {
-        let (m, d) = PayloadSupport::read(flag, bf);
+        let (m, d) = PayloadSupport::read(flag, bf)?;
         Ok(Payload {
             metadata: m,
             data: d,
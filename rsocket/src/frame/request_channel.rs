@@ -1,4 +1,4 @@
-use super::{Body, Frame, PayloadSupport, FLAG_METADATA, REQUEST_MAX};
+use super::{utils, Body, Frame, PayloadSupport, FLAG_METADATA, REQUEST_MAX};
 use crate::utils::{RSocketResult, Writeable};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -51,8 +51,9 @@ impl RequestChannelBuilder {
 
 impl RequestChannel {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<RequestChannel> {
+        utils::require(bf, 4)?;
         let n = bf.get_u32();
-        let (m, d) = PayloadSupport::read(flag, bf);
+        let (m, d) = PayloadSupport::read(flag, bf)?;
         Ok(RequestChannel {
             initial_request_n: n,
             metadata: m,
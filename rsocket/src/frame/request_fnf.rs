@@ -44,7 +44,7 @@ impl RequestFNFBuilder {
 
 impl RequestFNF {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<RequestFNF> {
-        let (m, d) = PayloadSupport::read(flag, bf);
+        let (m, d) = PayloadSupport::read(flag, bf)?;
         Ok(RequestFNF {
             metadata: m,
             data: d,
@@ -1,4 +1,4 @@
-use super::{Body, Frame, REQUEST_MAX};
+use super::{utils, Body, Frame, REQUEST_MAX};
 use crate::utils::{RSocketResult, Writeable};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -34,6 +34,7 @@ impl RequestNBuilder {
 
 impl RequestN {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<RequestN> {
+        utils::require(bf, 4)?;
         let n = bf.get_u32();
         Ok(RequestN { n })
     }
@@ -44,7 +44,7 @@ impl RequestResponseBuilder {
 
 impl RequestResponse {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<RequestResponse> {
-        let (m, d) = PayloadSupport::read(flag, bf);
+        let (m, d) = PayloadSupport::read(flag, bf)?;
         Ok(RequestResponse {
             metadata: m,
             data: d,
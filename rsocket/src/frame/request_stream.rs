@@ -1,4 +1,4 @@
-use super::{Body, Frame, PayloadSupport, FLAG_METADATA, REQUEST_MAX};
+use super::{utils, Body, Frame, PayloadSupport, FLAG_METADATA, REQUEST_MAX};
 use crate::utils::{RSocketResult, Writeable, U24};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -39,8 +39,9 @@ impl RequestStreamBuilder {
 
 impl RequestStream {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<RequestStream> {
+        utils::require(bf, 4)?;
         let n = bf.get_u32();
-        let (m, d) = PayloadSupport::read(flag, bf);
+        let (m, d) = PayloadSupport::read(flag, bf)?;
         Ok(RequestStream {
             initial_request_n: n,
             metadata: m,
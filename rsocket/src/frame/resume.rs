@@ -1,4 +1,4 @@
-use super::{Body, Frame, Version};
+use super::{utils, Body, Frame, Version};
 use crate::utils::{RSocketResult, Writeable};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -27,11 +27,13 @@ impl Resume {
     }
 
     pub fn decode(flag: u16, b: &mut BytesMut) -> RSocketResult<Resume> {
+        utils::require(b, 6)?;
         let major = b.get_u16();
         let minor = b.get_u16();
-        let token_size = b.get_u16();
+        let token_size = b.get_u16() as usize;
+        utils::require(b, token_size + 16)?;
         let token = if token_size > 0 {
-            Some(b.split_to(token_size as usize).to_bytes())
+            Some(b.split_to(token_size).to_bytes())
         } else {
             None
         };
@@ -102,9 +104,13 @@ impl ResumeBuilder {
 impl Writeable for Resume {
     fn write_to(&self, bf: &mut BytesMut) {
         self.version.write_to(bf);
-        if let Some(b) = self.get_token() {
-            bf.put_u16(b.len() as u16);
-            bf.put(b.bytes());
+        // the token length is written even without a token.
+        match self.get_token() {
+            Some(b) => {
+                bf.put_u16(b.len() as u16);
+                bf.put(b.bytes());
+            }
+            None => bf.put_u16(0),
         }
         bf.put_u64(self.get_last_received_server_position());
         bf.put_u64(self.get_first_available_client_position());
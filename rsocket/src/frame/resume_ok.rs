@@ -1,4 +1,4 @@
-use super::{Body, Frame};
+use super::{utils, Body, Frame};
 use crate::utils::{RSocketResult, Writeable};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
@@ -33,6 +33,7 @@ impl ResumeOKBuilder {
 
 impl ResumeOK {
     pub fn decode(flag: u16, bf: &mut BytesMut) -> RSocketResult<ResumeOK> {
+        utils::require(bf, 8)?;
         let position = bf.get_u64();
         Ok(ResumeOK { position })
     }
@@ -1,7 +1,9 @@
-use super::{Body, Frame, PayloadSupport, Version, FLAG_METADATA, FLAG_RESUME};
+use super::{utils, Body, Frame, PayloadSupport, Version, FLAG_METADATA, FLAG_RESUME};
+use crate::error::RSocketError;
 use crate::utils::{RSocketResult, Writeable, DEFAULT_MIME_TYPE};
+use alloc::string::String;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use std::time::Duration;
+use core::time::Duration;
 
 #[derive(Debug, PartialEq)]
 pub struct Setup {
@@ -28,6 +30,14 @@ impl Writeable for Setup {
     }
 
     fn write_to(&self, bf: &mut BytesMut) {
+        self.write_head(bf);
+        PayloadSupport::write(bf, self.get_metadata(), self.get_data());
+    }
+}
+
+impl Setup {
+    /// Write all fields preceding metadata and data.
+    pub(crate) fn write_head(&self, bf: &mut BytesMut) {
         self.version.write_to(bf);
         bf.put_u32(self.keepalive);
         bf.put_u32(self.lifetime);
@@ -35,46 +45,49 @@ impl Writeable for Setup {
             bf.put_u16(b.len() as u16);
             bf.put(b.bytes());
         }
-        // TODO: remove string clone
         bf.put_u8(self.mime_metadata.len() as u8);
-        bf.put(Bytes::from(self.mime_metadata.clone()));
+        bf.put_slice(self.mime_metadata.as_bytes());
         bf.put_u8(self.mime_data.len() as u8);
-        bf.put(Bytes::from(self.mime_data.clone()));
-        PayloadSupport::write(bf, self.get_metadata(), self.get_data());
+        bf.put_slice(self.mime_data.as_bytes());
     }
-}
 
-impl Setup {
     pub fn decode(flag: u16, b: &mut BytesMut) -> RSocketResult<Setup> {
+        utils::require(b, 12)?;
         let major = b.get_u16();
         let minor = b.get_u16();
         let keepalive = b.get_u32();
         let lifetime = b.get_u32();
         let token: Option<Bytes> = if flag & FLAG_RESUME != 0 {
-            let l = b.get_u16();
-            Some(b.split_to(l as usize).to_bytes())
+            utils::require(b, 2)?;
+            let l = b.get_u16() as usize;
+            utils::require(b, l)?;
+            Some(b.split_to(l).to_bytes())
         } else {
             None
         };
-        let mut len_mime: usize = b[0] as usize;
-        b.advance(1);
-        let mime_metadata = b.split_to(len_mime);
-        len_mime = b[0] as usize;
-        b.advance(1);
-        let mime_data = b.split_to(len_mime);
-        let (metadata, data) = PayloadSupport::read(flag, b);
+        let mime_metadata = Self::decode_mime(b)?;
+        let mime_data = Self::decode_mime(b)?;
+        let (metadata, data) = PayloadSupport::read(flag, b)?;
         Ok(Setup {
             version: Version::new(major, minor),
             keepalive,
             lifetime,
             token,
-            mime_metadata: String::from_utf8(mime_metadata.to_vec()).unwrap(),
-            mime_data: String::from_utf8(mime_data.to_vec()).unwrap(),
+            mime_metadata,
+            mime_data,
             metadata,
             data,
         })
     }
 
+    fn decode_mime(b: &mut BytesMut) -> RSocketResult<String> {
+        utils::require(b, 1)?;
+        let n = b.get_u8() as usize;
+        utils::require(b, n)?;
+        String::from_utf8(b.split_to(n).to_vec())
+            .map_err(|e| RSocketError::from(format!("invalid MIME type: {}", e)))
+    }
+
     pub fn builder(stream_id: u32, flag: u16) -> SetupBuilder {
         SetupBuilder::new(stream_id, flag)
     }
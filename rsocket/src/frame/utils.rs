@@ -1,5 +1,6 @@
 use super::FLAG_METADATA;
-use crate::utils::U24;
+use crate::error::RSocketError;
+use crate::utils::{RSocketResult, U24};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 pub(crate) struct PayloadSupport {}
@@ -17,26 +18,33 @@ impl PayloadSupport {
         a + b
     }
 
-    // TODO: change to Result
-    pub fn read(flag: u16, bf: &mut BytesMut) -> (Option<Bytes>, Option<Bytes>) {
+    pub fn read(flag: u16, bf: &mut BytesMut) -> RSocketResult<(Option<Bytes>, Option<Bytes>)> {
         let m: Option<Bytes> = if flag & FLAG_METADATA != 0 {
-            let n = U24::read_advance(bf);
-            Some(bf.split_to(n as usize).to_bytes())
+            require(bf, 3)?;
+            let n = U24::read_advance(bf) as usize;
+            require(bf, n)?;
+            Some(bf.split_to(n).freeze())
         } else {
             None
         };
         let d: Option<Bytes> = if bf.is_empty() {
             None
         } else {
-            Some(Bytes::from(bf.to_vec()))
+            // keep aliasing the receive buffer.
+            Some(bf.split().freeze())
         };
-        (m, d)
+        Ok((m, d))
+    }
+
+    pub fn write_len(bf: &mut BytesMut, metadata: &Option<Bytes>) {
+        if let Some(v) = metadata {
+            U24::write(v.len() as u32, bf);
+        }
     }
 
     pub fn write(bf: &mut BytesMut, metadata: &Option<Bytes>, data: &Option<Bytes>) {
+        Self::write_len(bf, metadata);
         if let Some(v) = metadata {
-            let n = v.len() as u32;
-            U24::write(n, bf);
             bf.put(v.bytes());
         }
         if let Some(v) = data {
@@ -44,3 +52,17 @@ impl PayloadSupport {
         }
     }
 }
+
+/// Fails unless at least `n` bytes are left, decoding must never index past the end of a frame.
+#[inline]
+pub(crate) fn require(bf: &BytesMut, n: usize) -> RSocketResult<()> {
+    if bf.len() < n {
+        Err(RSocketError::from(format!(
+            "incomplete frame: require {} more bytes, got {}",
+            n,
+            bf.len()
+        )))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+//! A standard health check, served by `HealthCheck` at `HEALTH_ROUTE` and issued by
+//! `Client::health_check`.
+use crate::error::{self, ErrorKind, RSocketError};
+use crate::payload::Payload;
+use crate::router::{unsupported_interaction, Routed};
+use crate::spi::{Flux, Mono, RSocket};
+use futures::{future, stream};
+use std::sync::Arc;
+
+/// The route of health checks.
+pub const HEALTH_ROUTE: &str = "rsocket.health";
+
+const SERVING: &str = "SERVING";
+const NOT_SERVING: &str = "NOT_SERVING";
+
+type FnCheck = Arc<dyn Fn() -> bool + Send + Sync>;
+
+/// Responder of health checks, which answers `SERVING` or rejects them with `NOT_SERVING`.
+///
+/// It knows its route, so it is added to a router by `Router::handler`. It is always serving
+/// unless a check is set.
+#[derive(Clone, Default)]
+pub struct HealthCheck {
+    check: Option<FnCheck>,
+}
+
+impl HealthCheck {
+    pub fn new() -> HealthCheck {
+        HealthCheck::default()
+    }
+
+    /// Decide whether the server is serving by `check`, called on every health check.
+    pub fn check<F>(mut self, check: F) -> Self
+    where
+        F: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.check = Some(Arc::new(check));
+        self
+    }
+
+    fn status(&self) -> Result<Payload, RSocketError> {
+        if self.check.as_ref().map(|check| check()).unwrap_or(true) {
+            Ok(Payload::from(SERVING))
+        } else {
+            Err(RSocketError::from(ErrorKind::Internal(
+                error::ERR_REJECTED,
+                NOT_SERVING.to_string(),
+            )))
+        }
+    }
+}
+
+impl Routed for HealthCheck {
+    fn route(&self) -> &str {
+        HEALTH_ROUTE
+    }
+}
+
+impl RSocket for HealthCheck {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(future::ready(()))
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(future::ready(()))
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(future::ready(self.status()))
+    }
+
+    fn request_stream(&self, _req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(Some(self.status())))
+    }
+
+    fn request_channel(
+        &self,
+        _reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(stream::iter(Some(Err(unsupported_interaction(
+            HEALTH_ROUTE,
+        )))))
+    }
+}
+
+/// Returns true if a health check failed because the server does not serve them, servers
+/// without the route answer APPLICATION_ERROR.
+pub(crate) fn is_unsupported(e: &RSocketError) -> bool {
+    matches!(e.kind(), ErrorKind::Internal(code, _) if *code == error::ERR_APPLICATION)
+}
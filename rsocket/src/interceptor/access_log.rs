@@ -0,0 +1,369 @@
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::router::route_of;
+use crate::spi::{Flux, Mono, RSocket, RequestContext};
+use futures::{Stream, StreamExt};
+use std::fmt;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+const LOG_TARGET: &str = "rsocket_rust::access";
+
+/// How a logged request ended.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Outcome {
+    Completed,
+    Failed(String),
+    Cancelled,
+}
+
+/// One line of the access log, recorded once a request ended.
+#[derive(Debug, Clone)]
+pub struct AccessRecord {
+    interaction: &'static str,
+    route: Option<String>,
+    peer: Option<SocketAddr>,
+    identity: Option<String>,
+    request_size: usize,
+    response_size: usize,
+    responses: usize,
+    duration: Duration,
+    outcome: Outcome,
+}
+
+/// Where access records go, any `Fn(&AccessRecord)` is a sink.
+pub trait AccessSink: Send + Sync {
+    fn record(&self, record: &AccessRecord);
+}
+
+impl<F> AccessSink for F
+where
+    F: Fn(&AccessRecord) + Send + Sync,
+{
+    fn record(&self, record: &AccessRecord) {
+        self(record)
+    }
+}
+
+/// Logs the records at info level with target `rsocket_rust::access`.
+struct LogSink;
+
+impl AccessSink for LogSink {
+    fn record(&self, record: &AccessRecord) {
+        info!(target: LOG_TARGET, "{}", record);
+    }
+}
+
+/// Responder side interceptor which records one line per request, comparable to the access
+/// log of an HTTP server: interaction model, route, peer, sizes, duration and outcome.
+///
+/// Sizes count the data and metadata of payloads, a stream or channel is recorded when it
+/// completes, fails or is cancelled. Records are logged by default, see `sink`.
+pub struct AccessLog<T> {
+    inner: T,
+    sink: Arc<dyn AccessSink>,
+}
+
+/// A request being served, recorded as cancelled if it is dropped before it ended.
+struct Pending {
+    record: Option<AccessRecord>,
+    started: Instant,
+    sink: Arc<dyn AccessSink>,
+}
+
+/// The responses of a stream or channel being served.
+struct Logged {
+    inner: Flux<Result<Payload, RSocketError>>,
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl<T> AccessLog<T>
+where
+    T: RSocket,
+{
+    pub fn new(inner: T) -> AccessLog<T> {
+        AccessLog {
+            inner,
+            sink: Arc::new(LogSink),
+        }
+    }
+
+    /// Send the records to `sink` instead of the log.
+    pub fn sink<S>(mut self, sink: S) -> Self
+    where
+        S: AccessSink + 'static,
+    {
+        self.sink = Arc::new(sink);
+        self
+    }
+
+    fn start(
+        &self,
+        interaction: &'static str,
+        ctx: Option<&RequestContext>,
+        req: Option<&Payload>,
+    ) -> Pending {
+        let peer = ctx.map(|it| it.get_peer());
+        let record = AccessRecord {
+            interaction,
+            route: req.and_then(route_of),
+            peer: peer.and_then(|it| it.get_addr()),
+            identity: peer.and_then(|it| it.get_identity()).map(String::from),
+            request_size: req.map(|it| it.len()).unwrap_or(0),
+            response_size: 0,
+            responses: 0,
+            duration: Duration::from_secs(0),
+            outcome: Outcome::Completed,
+        };
+        Pending {
+            record: Some(record),
+            started: Instant::now(),
+            sink: self.sink.clone(),
+        }
+    }
+
+    fn log_fire_and_forget(&self, ctx: Option<RequestContext>, req: Payload) -> Mono<()> {
+        let mut pending = self.start("fire_and_forget", ctx.as_ref(), Some(&req));
+        let fired = match ctx {
+            Some(ctx) => self.inner.fire_and_forget_with_context(ctx, req),
+            None => self.inner.fire_and_forget(req),
+        };
+        Box::pin(async move {
+            fired.await;
+            pending.finish(Outcome::Completed);
+        })
+    }
+
+    fn log_request_response(
+        &self,
+        ctx: Option<RequestContext>,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        let mut pending = self.start("request_response", ctx.as_ref(), Some(&req));
+        let responding = match ctx {
+            Some(ctx) => self.inner.request_response_with_context(ctx, req),
+            None => self.inner.request_response(req),
+        };
+        Box::pin(async move {
+            let res = responding.await;
+            match &res {
+                Ok(it) => {
+                    pending.on_response(it);
+                    pending.finish(Outcome::Completed);
+                }
+                Err(e) => pending.finish(Outcome::Failed(e.to_string())),
+            }
+            res
+        })
+    }
+
+    fn log_request_stream(
+        &self,
+        ctx: Option<RequestContext>,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let pending = self.start("request_stream", ctx.as_ref(), Some(&req));
+        let inner = match ctx {
+            Some(ctx) => self.inner.request_stream_with_context(ctx, req),
+            None => self.inner.request_stream(req),
+        };
+        Box::pin(Logged {
+            inner,
+            pending: Arc::new(Mutex::new(pending)),
+        })
+    }
+
+    fn log_request_channel(
+        &self,
+        ctx: Option<RequestContext>,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let pending = Arc::new(Mutex::new(self.start(
+            "request_channel",
+            ctx.as_ref(),
+            None,
+        )));
+        // the route of a channel is the one of its first request.
+        let counting = pending.clone();
+        let reqs = Box::pin(reqs.inspect(move |it| {
+            if let Ok(req) = it {
+                counting.lock().unwrap().on_request(req);
+            }
+        }));
+        let inner = match ctx {
+            Some(ctx) => self.inner.request_channel_with_context(ctx, reqs),
+            None => self.inner.request_channel(reqs),
+        };
+        Box::pin(Logged { inner, pending })
+    }
+}
+
+impl Pending {
+    fn on_request(&mut self, req: &Payload) {
+        if let Some(record) = &mut self.record {
+            if record.request_size == 0 && record.route.is_none() {
+                record.route = route_of(req);
+            }
+            record.request_size += req.len();
+        }
+    }
+
+    fn on_response(&mut self, res: &Payload) {
+        if let Some(record) = &mut self.record {
+            record.responses += 1;
+            record.response_size += res.len();
+        }
+    }
+
+    fn finish(&mut self, outcome: Outcome) {
+        if let Some(mut record) = self.record.take() {
+            record.duration = self.started.elapsed();
+            record.outcome = outcome;
+            self.sink.record(&record);
+        }
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.finish(Outcome::Cancelled);
+    }
+}
+
+impl Stream for Logged {
+    type Item = Result<Payload, RSocketError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let next = self.inner.as_mut().poll_next(cx);
+        if let Poll::Ready(it) = &next {
+            let mut pending = self.pending.lock().unwrap();
+            match it {
+                Some(Ok(res)) => pending.on_response(res),
+                Some(Err(e)) => pending.finish(Outcome::Failed(e.to_string())),
+                None => pending.finish(Outcome::Completed),
+            }
+        }
+        next
+    }
+}
+
+impl AccessRecord {
+    pub fn get_interaction(&self) -> &str {
+        self.interaction
+    }
+
+    pub fn get_route(&self) -> Option<&str> {
+        self.route.as_deref()
+    }
+
+    pub fn get_peer(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+
+    pub fn get_identity(&self) -> Option<&str> {
+        self.identity.as_deref()
+    }
+
+    /// Returns the bytes of the request payloads.
+    pub fn get_request_size(&self) -> usize {
+        self.request_size
+    }
+
+    /// Returns the bytes of the response payloads.
+    pub fn get_response_size(&self) -> usize {
+        self.response_size
+    }
+
+    pub fn get_responses(&self) -> usize {
+        self.responses
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn get_outcome(&self) -> &Outcome {
+        &self.outcome
+    }
+}
+
+impl fmt::Display for AccessRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "interaction={} route={} peer={} identity={} req_bytes={} res_bytes={} responses={} duration_ms={}",
+            self.interaction,
+            self.route.as_deref().unwrap_or("-"),
+            self.peer.map(|it| it.to_string()).unwrap_or_else(|| "-".to_string()),
+            self.identity.as_deref().unwrap_or("-"),
+            self.request_size,
+            self.response_size,
+            self.responses,
+            self.duration.as_millis(),
+        )?;
+        match &self.outcome {
+            Outcome::Completed => write!(f, " outcome=completed"),
+            Outcome::Failed(e) => write!(f, " outcome=failed error={:?}", e),
+            Outcome::Cancelled => write!(f, " outcome=cancelled"),
+        }
+    }
+}
+
+impl<T> RSocket for AccessLog<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.log_fire_and_forget(None, req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.log_request_response(None, req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.log_request_stream(None, req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.log_request_channel(None, reqs)
+    }
+
+    fn fire_and_forget_with_context(&self, ctx: RequestContext, req: Payload) -> Mono<()> {
+        self.log_fire_and_forget(Some(ctx), req)
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        self.log_request_response(Some(ctx), req)
+    }
+
+    fn request_stream_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.log_request_stream(Some(ctx), req)
+    }
+
+    fn request_channel_with_context(
+        &self,
+        ctx: RequestContext,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.log_request_channel(Some(ctx), reqs)
+    }
+}
@@ -0,0 +1,181 @@
+use super::append_metadata;
+use crate::error::RSocketError;
+use crate::extension::AuthMetadata;
+use crate::mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0;
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono, RSocket};
+use bytes::Bytes;
+use futures::{future, stream, StreamExt};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+type FnTokenProvider = Arc<dyn Fn() -> Mono<Result<AuthToken, RSocketError>> + Send + Sync>;
+
+const DEFAULT_REFRESH_BEFORE: Duration = Duration::from_secs(30);
+
+/// A bearer token with an optional expiry.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AuthToken {
+    token: String,
+    expires_at: Option<Instant>,
+}
+
+/// Requester side interceptor which attaches bearer authentication metadata to every request.
+///
+/// Tokens come from an async provider and are cached until they are about to expire,
+/// a new token is fetched when less than `refresh_before` remains.
+pub struct BearerAuthInjector<T> {
+    inner: Arc<T>,
+    provider: FnTokenProvider,
+    refresh_before: Duration,
+    cached: Arc<Mutex<Option<AuthToken>>>,
+}
+
+impl AuthToken {
+    pub fn new<S>(token: S) -> AuthToken
+    where
+        S: Into<String>,
+    {
+        AuthToken {
+            token: token.into(),
+            expires_at: None,
+        }
+    }
+
+    pub fn expires_in(mut self, ttl: Duration) -> Self {
+        self.expires_at = Some(Instant::now() + ttl);
+        self
+    }
+
+    pub fn expires_at(mut self, deadline: Instant) -> Self {
+        self.expires_at = Some(deadline);
+        self
+    }
+
+    pub fn get_token(&self) -> &String {
+        &self.token
+    }
+
+    pub fn get_expires_at(&self) -> Option<Instant> {
+        self.expires_at
+    }
+
+    fn is_fresh(&self, refresh_before: Duration) -> bool {
+        match self.expires_at {
+            Some(deadline) => Instant::now() + refresh_before < deadline,
+            None => true,
+        }
+    }
+}
+
+impl<T> BearerAuthInjector<T>
+where
+    T: RSocket + 'static,
+{
+    pub fn new<F>(inner: T, provider: F) -> BearerAuthInjector<T>
+    where
+        F: Fn() -> Mono<Result<AuthToken, RSocketError>> + Send + Sync + 'static,
+    {
+        BearerAuthInjector {
+            inner: Arc::new(inner),
+            provider: Arc::new(provider),
+            refresh_before: DEFAULT_REFRESH_BEFORE,
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn refresh_before(mut self, refresh_before: Duration) -> Self {
+        self.refresh_before = refresh_before;
+        self
+    }
+
+    /// Drop the cached token, the next request will fetch a new one.
+    pub fn invalidate(&self) {
+        *self.cached.lock().unwrap() = None;
+    }
+
+    fn token(&self) -> Mono<Result<String, RSocketError>> {
+        let cached = self.cached.clone();
+        let provider = self.provider.clone();
+        let refresh_before = self.refresh_before;
+        if let Some(tk) = cached.lock().unwrap().as_ref() {
+            if tk.is_fresh(refresh_before) {
+                return Box::pin(future::ok(tk.token.clone()));
+            }
+        }
+        Box::pin(async move {
+            let tk = provider().await?;
+            debug!("auth token refreshed, expires at {:?}", tk.expires_at);
+            let token = tk.token.clone();
+            *cached.lock().unwrap() = Some(tk);
+            Ok(token)
+        })
+    }
+}
+
+impl<T> RSocket for BearerAuthInjector<T>
+where
+    T: RSocket + 'static,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        let token = self.token();
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            match token.await {
+                Ok(tk) => inner.fire_and_forget(inject(req, tk)).await,
+                Err(e) => warn!("fetch auth token failed: {}", e),
+            }
+        })
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let token = self.token();
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            let tk = token.await?;
+            inner.request_response(inject(req, tk)).await
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let inner = self.inner.clone();
+        let results = stream::once(self.token()).flat_map(move |it| match it {
+            Ok(tk) => inner.request_stream(inject(req.clone(), tk)),
+            Err(e) => Box::pin(stream::iter(Some(Err(e)))),
+        });
+        Box::pin(results)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let inner = self.inner.clone();
+        let mut reqs = Some(reqs);
+        let results = stream::once(self.token()).flat_map(move |it| match it {
+            Ok(tk) => {
+                let reqs = reqs
+                    .take()
+                    .unwrap()
+                    .enumerate()
+                    .map(move |(i, it)| match it {
+                        Ok(req) if i == 0 => Ok(inject(req, tk.clone())),
+                        other => other,
+                    });
+                inner.request_channel(Box::pin(reqs))
+            }
+            Err(e) => Box::pin(stream::iter(Some(Err(e)))),
+        });
+        Box::pin(results)
+    }
+}
+
+#[inline]
+fn inject(req: Payload, token: String) -> Payload {
+    let auth = AuthMetadata::bearer(token);
+    append_metadata(req, MESSAGE_X_RSOCKET_AUTHENTICATION_V0, Bytes::from(auth))
+}
@@ -0,0 +1,186 @@
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::router::route_of;
+use crate::spi::{Flux, Mono, RSocket};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+const DEFAULT_CAPACITY: usize = 1024;
+
+type Key = (Option<String>, Option<Bytes>);
+
+/// Requester side interceptor which caches the responses of REQUEST_RESPONSE by route and
+/// data, so repeated idempotent lookups are not sent again.
+///
+/// Other metadata is not part of the key, responses are shared by every caller of the
+/// requester. Errors are not cached, entries expire after `ttl` and the least recently used
+/// ones are evicted beyond `capacity`.
+pub struct ResponseCache<T> {
+    inner: Arc<T>,
+    ttl: Duration,
+    capacity: usize,
+    max_entry_size: usize,
+    entries: Arc<Mutex<Entries>>,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<Key, Entry>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+struct Entry {
+    res: Payload,
+    expires_at: Instant,
+    used: u64,
+}
+
+impl<T> ResponseCache<T>
+where
+    T: RSocket + 'static,
+{
+    pub fn new(inner: T) -> ResponseCache<T> {
+        ResponseCache {
+            inner: Arc::new(inner),
+            ttl: DEFAULT_TTL,
+            capacity: DEFAULT_CAPACITY,
+            max_entry_size: usize::MAX,
+            entries: Arc::new(Mutex::new(Entries::default())),
+        }
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Limit the number of cached responses.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Do not cache responses whose data and metadata are larger than `size` bytes.
+    pub fn max_entry_size(mut self, size: usize) -> Self {
+        self.max_entry_size = size;
+        self
+    }
+
+    /// Drop all cached responses.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().map.clear();
+    }
+
+    /// Returns the number of cached responses, including expired ones not evicted yet.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the numbers of requests answered from the cache and sent to the inner requester.
+    pub fn stats(&self) -> (u64, u64) {
+        let entries = self.entries.lock().unwrap();
+        (entries.hits, entries.misses)
+    }
+}
+
+impl Entries {
+    fn get(&mut self, key: &Key) -> Option<Payload> {
+        let now = Instant::now();
+        self.tick += 1;
+        let tick = self.tick;
+        match self.map.get_mut(key) {
+            Some(entry) if entry.expires_at > now => {
+                entry.used = tick;
+                self.hits += 1;
+                return Some(entry.res.clone());
+            }
+            Some(_) => {
+                self.map.remove(key);
+            }
+            None => (),
+        }
+        self.misses += 1;
+        None
+    }
+
+    fn insert(&mut self, key: Key, res: Payload, ttl: Duration, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        let now = Instant::now();
+        if self.map.len() >= capacity && !self.map.contains_key(&key) {
+            self.map.retain(|_, it| it.expires_at > now);
+        }
+        while self.map.len() >= capacity && !self.map.contains_key(&key) {
+            let lru = self
+                .map
+                .iter()
+                .min_by_key(|(_, it)| it.used)
+                .map(|(k, _)| k.clone());
+            match lru {
+                Some(k) => self.map.remove(&k),
+                None => break,
+            };
+        }
+        self.tick += 1;
+        let entry = Entry {
+            res,
+            expires_at: now + ttl,
+            used: self.tick,
+        };
+        self.map.insert(key, entry);
+    }
+}
+
+impl<T> RSocket for ResponseCache<T>
+where
+    T: RSocket + 'static,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.inner.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let key = (route_of(&req), req.data().clone());
+        if let Some(res) = self.entries.lock().unwrap().get(&key) {
+            return Box::pin(async move { Ok(res) });
+        }
+        let inner = self.inner.clone();
+        let entries = self.entries.clone();
+        let (ttl, capacity, max_entry_size) = (self.ttl, self.capacity, self.max_entry_size);
+        Box::pin(async move {
+            let res = inner.request_response(req).await?;
+            if res.len() <= max_entry_size {
+                entries
+                    .lock()
+                    .unwrap()
+                    .insert(key, res.clone(), ttl, capacity);
+            }
+            Ok(res)
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.inner.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.inner.request_channel(reqs)
+    }
+}
@@ -0,0 +1,173 @@
+//! Wire capture of connections, for offline debugging and replaying in tests.
+//!
+//! A capture file starts with the 6 bytes magic `RSCAP` followed by version `0x01`,
+//! then one record per frame, all integers are big-endian:
+//!
+//! ```text
+//! +-----------+----------------+-------------+--------------------+
+//! | direction | timestamp      | length      | frame              |
+//! | u8        | u64 (micros)   | u32         | `length` bytes     |
+//! +-----------+----------------+-------------+--------------------+
+//! ```
+//!
+//! `direction` is `0` for a received frame and `1` for a sent frame, `timestamp` is the
+//! number of microseconds since the UNIX epoch, and `frame` is the encoded frame without
+//! any transport length prefix.
+use crate::frame::Frame;
+use crate::utils::Writeable;
+use bytes::{BufMut, Bytes, BytesMut};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 5] = b"RSCAP";
+const VERSION: u8 = 0x01;
+const RECORD_HEADER_LEN: usize = 13;
+
+/// Connection interceptor which writes every sent and received frame into a capture file.
+#[derive(Clone)]
+pub struct CaptureRecorder {
+    out: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+/// A frame read from a capture.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CapturedFrame {
+    outbound: bool,
+    timestamp: SystemTime,
+    raw: Bytes,
+}
+
+/// Iterator of the frames in a capture.
+pub struct CaptureReader<R> {
+    inner: R,
+}
+
+impl CaptureRecorder {
+    /// Create or truncate the capture file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<CaptureRecorder> {
+        CaptureRecorder::from_writer(BufWriter::new(File::create(path)?))
+    }
+
+    /// Write the capture into `w`, which is flushed after every record.
+    pub fn from_writer<W>(mut w: W) -> io::Result<CaptureRecorder>
+    where
+        W: Write + Send + 'static,
+    {
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION])?;
+        w.flush()?;
+        Ok(CaptureRecorder {
+            out: Arc::new(Mutex::new(Box::new(w))),
+        })
+    }
+
+    pub(crate) fn record(&self, outbound: bool, frame: &Frame) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut bf = BytesMut::with_capacity(RECORD_HEADER_LEN + frame.len());
+        bf.put_u8(if outbound { 1 } else { 0 });
+        bf.put_u64(timestamp.as_micros() as u64);
+        bf.put_u32(frame.len() as u32);
+        frame.write_to(&mut bf);
+        let mut out = self.out.lock().unwrap();
+        if let Err(e) = out.write_all(&bf).and_then(|_| out.flush()) {
+            error!("write capture failed: {}", e);
+        }
+    }
+}
+
+impl std::fmt::Debug for CaptureRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CaptureRecorder").finish()
+    }
+}
+
+impl CapturedFrame {
+    /// Returns true if the frame was sent by the recording side.
+    pub fn is_outbound(&self) -> bool {
+        self.outbound
+    }
+
+    pub fn get_timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Returns the encoded frame.
+    pub fn get_raw(&self) -> &Bytes {
+        &self.raw
+    }
+
+    #[cfg(feature = "frame")]
+    pub fn to_frame(&self) -> crate::utils::RSocketResult<Frame> {
+        Frame::decode(&mut BytesMut::from(self.raw.as_ref()))
+    }
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<CaptureReader<BufReader<File>>> {
+        CaptureReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Read a capture from `inner`, the file header is checked immediately.
+    pub fn new(mut inner: R) -> io::Result<CaptureReader<R>> {
+        let mut header = [0u8; 6];
+        inner.read_exact(&mut header)?;
+        if &header[..5] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an rsocket capture",
+            ));
+        }
+        if header[5] != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported capture version: {}", header[5]),
+            ));
+        }
+        Ok(CaptureReader { inner })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<CapturedFrame>> {
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        // a capture may end at any record boundary.
+        match self.inner.read(&mut header[..1])? {
+            0 => return Ok(None),
+            _ => self.inner.read_exact(&mut header[1..])?,
+        }
+        let outbound = match header[0] {
+            0 => false,
+            1 => true,
+            n => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid direction: {}", n),
+                ))
+            }
+        };
+        let mut micros = [0u8; 8];
+        micros.copy_from_slice(&header[1..9]);
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&header[9..]);
+        let mut raw = vec![0u8; u32::from_be_bytes(len) as usize];
+        self.inner.read_exact(&mut raw)?;
+        Ok(Some(CapturedFrame {
+            outbound,
+            timestamp: UNIX_EPOCH + Duration::from_micros(u64::from_be_bytes(micros)),
+            raw: Bytes::from(raw),
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<CapturedFrame>;
+
+    fn next(&mut self) -> Option<io::Result<CapturedFrame>> {
+        self.read_record().transpose()
+    }
+}
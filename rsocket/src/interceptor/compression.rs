@@ -0,0 +1,344 @@
+use super::composite_of;
+use crate::error::RSocketError;
+use crate::extension::Metadata;
+use crate::mime;
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono, RSocket, RequestContext};
+use bytes::Bytes;
+use futures::{future, StreamExt};
+use std::io::{Read, Write};
+
+/// Payloads whose data is smaller than this are not compressed by default.
+const DEFAULT_THRESHOLD: usize = 1024;
+
+/// A compression algorithm of payload data.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Compression {
+    #[cfg(feature = "gzip")]
+    Gzip,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression a data MIME type asks for by its suffix, such as
+    /// `application/json+gzip`.
+    pub fn from_mime(mime: &str) -> Option<Compression> {
+        let (_, suffix) = mime.rsplit_once('+')?;
+        Self::from_name(suffix)
+    }
+
+    fn from_name(name: &str) -> Option<Compression> {
+        match name {
+            #[cfg(feature = "gzip")]
+            "gzip" => Some(Compression::Gzip),
+            #[cfg(feature = "zstd")]
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => "gzip",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Result<Bytes, RSocketError> {
+        let compressed = match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).and_then(|_| encoder.finish())
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::encode_all(data, 0),
+        };
+        compressed.map(Bytes::from).map_err(RSocketError::from)
+    }
+
+    pub fn decompress(&self, data: &[u8]) -> Result<Bytes, RSocketError> {
+        let decompressed = match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut out = Vec::new();
+                flate2::read::GzDecoder::new(data)
+                    .read_to_end(&mut out)
+                    .map(|_| out)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::decode_all(data),
+        };
+        decompressed.map(Bytes::from).map_err(RSocketError::from)
+    }
+}
+
+/// Requester side interceptor which compresses the data of requests by `compression`, and
+/// decompresses the data of responses which were compressed.
+///
+/// Data smaller than the threshold, or which does not shrink, is sent as it is. Compressed
+/// payloads carry a `message/x.rsocket.compression.v0` entry of composite metadata naming
+/// the algorithm, payloads whose metadata is not composite are never compressed.
+pub struct CompressingRequester<T> {
+    inner: T,
+    compression: Compression,
+    threshold: usize,
+}
+
+/// Responder side interceptor which decompresses the data of requests which were compressed,
+/// and compresses the data of responses by `compression`, see `CompressingRequester`.
+pub struct CompressingResponder<T> {
+    inner: T,
+    compression: Compression,
+    threshold: usize,
+}
+
+impl<T> CompressingRequester<T>
+where
+    T: RSocket,
+{
+    pub fn new(inner: T, compression: Compression) -> CompressingRequester<T> {
+        CompressingRequester {
+            inner,
+            compression,
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Only compress data of at least `size` bytes, 1 KiB by default.
+    pub fn threshold(mut self, size: usize) -> Self {
+        self.threshold = size;
+        self
+    }
+
+    fn compress(&self, req: Payload) -> Payload {
+        compress(self.compression, self.threshold, req)
+    }
+}
+
+impl<T> CompressingResponder<T>
+where
+    T: RSocket,
+{
+    pub fn new(inner: T, compression: Compression) -> CompressingResponder<T> {
+        CompressingResponder {
+            inner,
+            compression,
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Only compress data of at least `size` bytes, 1 KiB by default.
+    pub fn threshold(mut self, size: usize) -> Self {
+        self.threshold = size;
+        self
+    }
+
+    fn decompress_fire_and_forget(&self, ctx: Option<RequestContext>, req: Payload) -> Mono<()> {
+        let req = match decompress(req) {
+            Ok(it) => it,
+            Err(e) => {
+                warn!("drop fire_and_forget: {}", e);
+                return Box::pin(future::ready(()));
+            }
+        };
+        match ctx {
+            Some(ctx) => self.inner.fire_and_forget_with_context(ctx, req),
+            None => self.inner.fire_and_forget(req),
+        }
+    }
+
+    fn decompress_request_response(
+        &self,
+        ctx: Option<RequestContext>,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        let req = match decompress(req) {
+            Ok(it) => it,
+            Err(e) => return Box::pin(future::err(e)),
+        };
+        let (compression, threshold) = (self.compression, self.threshold);
+        let res = match ctx {
+            Some(ctx) => self.inner.request_response_with_context(ctx, req),
+            None => self.inner.request_response(req),
+        };
+        Box::pin(async move { res.await.map(|it| compress(compression, threshold, it)) })
+    }
+
+    fn decompress_request_stream(
+        &self,
+        ctx: Option<RequestContext>,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let req = match decompress(req) {
+            Ok(it) => it,
+            Err(e) => return Box::pin(futures::stream::iter(Some(Err(e)))),
+        };
+        let (compression, threshold) = (self.compression, self.threshold);
+        let results = match ctx {
+            Some(ctx) => self.inner.request_stream_with_context(ctx, req),
+            None => self.inner.request_stream(req),
+        };
+        Box::pin(results.map(move |it| it.map(|res| compress(compression, threshold, res))))
+    }
+
+    fn decompress_request_channel(
+        &self,
+        ctx: Option<RequestContext>,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let (compression, threshold) = (self.compression, self.threshold);
+        let reqs = Box::pin(reqs.map(|it| it.and_then(decompress)));
+        let results = match ctx {
+            Some(ctx) => self.inner.request_channel_with_context(ctx, reqs),
+            None => self.inner.request_channel(reqs),
+        };
+        Box::pin(results.map(move |it| it.map(|res| compress(compression, threshold, res))))
+    }
+}
+
+impl<T> RSocket for CompressingRequester<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.inner.fire_and_forget(self.compress(req))
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let res = self.inner.request_response(self.compress(req));
+        Box::pin(async move { res.await.and_then(decompress) })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let results = self.inner.request_stream(self.compress(req));
+        Box::pin(results.map(|it| it.and_then(decompress)))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let (compression, threshold) = (self.compression, self.threshold);
+        let reqs = reqs.map(move |it| it.map(|req| compress(compression, threshold, req)));
+        let results = self.inner.request_channel(Box::pin(reqs));
+        Box::pin(results.map(|it| it.and_then(decompress)))
+    }
+}
+
+impl<T> RSocket for CompressingResponder<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.decompress_fire_and_forget(None, req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.decompress_request_response(None, req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.decompress_request_stream(None, req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.decompress_request_channel(None, reqs)
+    }
+
+    fn fire_and_forget_with_context(&self, ctx: RequestContext, req: Payload) -> Mono<()> {
+        self.decompress_fire_and_forget(Some(ctx), req)
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        self.decompress_request_response(Some(ctx), req)
+    }
+
+    fn request_stream_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.decompress_request_stream(Some(ctx), req)
+    }
+
+    fn request_channel_with_context(
+        &self,
+        ctx: RequestContext,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.decompress_request_channel(Some(ctx), reqs)
+    }
+}
+
+/// Compress the data of a payload and mark it, unless it is too small to be worth it.
+fn compress(compression: Compression, threshold: usize, req: Payload) -> Payload {
+    let size = req.data().as_ref().map(|it| it.len()).unwrap_or(0);
+    if size == 0 || size < threshold {
+        return req;
+    }
+    let mut composite = match composite_of(&req) {
+        Some(it) => it,
+        None => {
+            warn!("cannot compress payload of non-composite metadata");
+            return req;
+        }
+    };
+    let compressed = match compression.compress(req.data().as_ref().unwrap()) {
+        Ok(it) if it.len() < size => it,
+        Ok(_) => return req,
+        Err(e) => {
+            warn!("compress payload by {} failed: {}", compression.name(), e);
+            return req;
+        }
+    };
+    composite.push(Metadata::new(
+        mime::MESSAGE_X_RSOCKET_COMPRESSION_V0.to_string(),
+        Bytes::from(compression.name()),
+    ));
+    Payload::from((Some(compressed), Some(Bytes::from(composite))))
+}
+
+/// Decompress the data of a payload if it was compressed, the mark is removed.
+fn decompress(req: Payload) -> Result<Payload, RSocketError> {
+    let mut composite = match composite_of(&req) {
+        Some(it) => it,
+        None => return Ok(req),
+    };
+    let name = match composite.remove(mime::MESSAGE_X_RSOCKET_COMPRESSION_V0) {
+        Some(it) => String::from_utf8_lossy(it.get_payload()).into_owned(),
+        None => return Ok(req),
+    };
+    let compression = Compression::from_name(&name)
+        .ok_or_else(|| RSocketError::from(format!("unsupported compression: {}", name)))?;
+    let (d, _) = req.split();
+    let d = match d {
+        Some(d) => Some(compression.decompress(&d)?),
+        None => None,
+    };
+    let m = if composite.iter().next().is_some() {
+        Some(Bytes::from(composite))
+    } else {
+        None
+    };
+    Ok(Payload::from((d, m)))
+}
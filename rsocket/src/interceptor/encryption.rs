@@ -0,0 +1,312 @@
+use super::composite_of;
+use crate::error::RSocketError;
+use crate::extension::Metadata;
+use crate::mime;
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono, RSocket, RequestContext};
+use bytes::Bytes;
+use futures::{future, stream, StreamExt};
+use std::sync::Arc;
+
+/// Encrypts and decrypts payload data, the algorithm and key management are up to the
+/// application.
+pub trait PayloadCipher: Send + Sync {
+    /// The id of the key outgoing data is encrypted by.
+    fn key_id(&self) -> &str;
+
+    fn encrypt(&self, key_id: &str, data: &[u8]) -> Result<Bytes, RSocketError>;
+
+    fn decrypt(&self, key_id: &str, data: &[u8]) -> Result<Bytes, RSocketError>;
+}
+
+/// Requester side interceptor which encrypts the data of requests and decrypts the data of
+/// responses, so payloads pass brokers and proxies which cannot read them.
+///
+/// Only data is encrypted, metadata stays readable for routing. The key id travels in a
+/// `message/x.rsocket.encryption.v0` entry of composite metadata. Wrap it inside a
+/// `CompressingRequester`, encrypted data does not compress.
+pub struct EncryptingRequester<T> {
+    inner: T,
+    cipher: Arc<dyn PayloadCipher>,
+    allow_plaintext: bool,
+}
+
+/// Responder side interceptor which decrypts the data of requests and encrypts the data of
+/// responses by the same cipher, see `EncryptingRequester`.
+pub struct EncryptingResponder<T> {
+    inner: T,
+    cipher: Arc<dyn PayloadCipher>,
+    allow_plaintext: bool,
+}
+
+impl<T> EncryptingRequester<T>
+where
+    T: RSocket,
+{
+    pub fn new<C>(inner: T, cipher: C) -> EncryptingRequester<T>
+    where
+        C: PayloadCipher + 'static,
+    {
+        EncryptingRequester {
+            inner,
+            cipher: Arc::new(cipher),
+            allow_plaintext: false,
+        }
+    }
+
+    /// Accept responses whose data is not encrypted, they are rejected by default.
+    pub fn allow_plaintext(mut self) -> Self {
+        self.allow_plaintext = true;
+        self
+    }
+}
+
+impl<T> EncryptingResponder<T>
+where
+    T: RSocket,
+{
+    pub fn new<C>(inner: T, cipher: C) -> EncryptingResponder<T>
+    where
+        C: PayloadCipher + 'static,
+    {
+        EncryptingResponder {
+            inner,
+            cipher: Arc::new(cipher),
+            allow_plaintext: false,
+        }
+    }
+
+    /// Accept requests whose data is not encrypted, they are rejected by default.
+    pub fn allow_plaintext(mut self) -> Self {
+        self.allow_plaintext = true;
+        self
+    }
+
+    fn decrypt_fire_and_forget(&self, ctx: Option<RequestContext>, req: Payload) -> Mono<()> {
+        let req = match decrypt(self.cipher.as_ref(), self.allow_plaintext, req) {
+            Ok(it) => it,
+            Err(e) => {
+                warn!("drop fire_and_forget: {}", e);
+                return Box::pin(future::ready(()));
+            }
+        };
+        match ctx {
+            Some(ctx) => self.inner.fire_and_forget_with_context(ctx, req),
+            None => self.inner.fire_and_forget(req),
+        }
+    }
+
+    fn decrypt_request_response(
+        &self,
+        ctx: Option<RequestContext>,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        let req = match decrypt(self.cipher.as_ref(), self.allow_plaintext, req) {
+            Ok(it) => it,
+            Err(e) => return Box::pin(future::err(e)),
+        };
+        let cipher = self.cipher.clone();
+        let res = match ctx {
+            Some(ctx) => self.inner.request_response_with_context(ctx, req),
+            None => self.inner.request_response(req),
+        };
+        Box::pin(async move { res.await.and_then(|it| encrypt(&*cipher, it)) })
+    }
+
+    fn decrypt_request_stream(
+        &self,
+        ctx: Option<RequestContext>,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let req = match decrypt(self.cipher.as_ref(), self.allow_plaintext, req) {
+            Ok(it) => it,
+            Err(e) => return Box::pin(stream::iter(Some(Err(e)))),
+        };
+        let cipher = self.cipher.clone();
+        let results = match ctx {
+            Some(ctx) => self.inner.request_stream_with_context(ctx, req),
+            None => self.inner.request_stream(req),
+        };
+        Box::pin(results.map(move |it| it.and_then(|res| encrypt(&*cipher, res))))
+    }
+
+    fn decrypt_request_channel(
+        &self,
+        ctx: Option<RequestContext>,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let (cipher, allow_plaintext) = (self.cipher.clone(), self.allow_plaintext);
+        let reqs = Box::pin(
+            reqs.map(move |it| it.and_then(|req| decrypt(&*cipher, allow_plaintext, req))),
+        );
+        let cipher = self.cipher.clone();
+        let results = match ctx {
+            Some(ctx) => self.inner.request_channel_with_context(ctx, reqs),
+            None => self.inner.request_channel(reqs),
+        };
+        Box::pin(results.map(move |it| it.and_then(|res| encrypt(&*cipher, res))))
+    }
+}
+
+impl<T> RSocket for EncryptingRequester<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        match encrypt(self.cipher.as_ref(), req) {
+            Ok(req) => self.inner.fire_and_forget(req),
+            Err(e) => {
+                warn!("drop fire_and_forget: {}", e);
+                Box::pin(future::ready(()))
+            }
+        }
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let req = match encrypt(self.cipher.as_ref(), req) {
+            Ok(it) => it,
+            Err(e) => return Box::pin(future::err(e)),
+        };
+        let (cipher, allow_plaintext) = (self.cipher.clone(), self.allow_plaintext);
+        let res = self.inner.request_response(req);
+        Box::pin(async move {
+            res.await
+                .and_then(|it| decrypt(&*cipher, allow_plaintext, it))
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let req = match encrypt(self.cipher.as_ref(), req) {
+            Ok(it) => it,
+            Err(e) => return Box::pin(stream::iter(Some(Err(e)))),
+        };
+        let (cipher, allow_plaintext) = (self.cipher.clone(), self.allow_plaintext);
+        let results = self.inner.request_stream(req);
+        Box::pin(results.map(move |it| it.and_then(|res| decrypt(&*cipher, allow_plaintext, res))))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let cipher = self.cipher.clone();
+        let reqs = reqs.map(move |it| it.and_then(|req| encrypt(&*cipher, req)));
+        let (cipher, allow_plaintext) = (self.cipher.clone(), self.allow_plaintext);
+        let results = self.inner.request_channel(Box::pin(reqs));
+        Box::pin(results.map(move |it| it.and_then(|res| decrypt(&*cipher, allow_plaintext, res))))
+    }
+}
+
+impl<T> RSocket for EncryptingResponder<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.decrypt_fire_and_forget(None, req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.decrypt_request_response(None, req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.decrypt_request_stream(None, req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.decrypt_request_channel(None, reqs)
+    }
+
+    fn fire_and_forget_with_context(&self, ctx: RequestContext, req: Payload) -> Mono<()> {
+        self.decrypt_fire_and_forget(Some(ctx), req)
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        self.decrypt_request_response(Some(ctx), req)
+    }
+
+    fn request_stream_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.decrypt_request_stream(Some(ctx), req)
+    }
+
+    fn request_channel_with_context(
+        &self,
+        ctx: RequestContext,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.decrypt_request_channel(Some(ctx), reqs)
+    }
+}
+
+/// Encrypt the data of a payload and attach the key id, payloads without data are left alone.
+fn encrypt(cipher: &dyn PayloadCipher, req: Payload) -> Result<Payload, RSocketError> {
+    if req.data().is_none() {
+        return Ok(req);
+    }
+    let mut composite = composite_of(&req)
+        .ok_or_else(|| RSocketError::from("cannot encrypt payload of non-composite metadata"))?;
+    let key_id = cipher.key_id();
+    let (d, _) = req.split();
+    let encrypted = cipher.encrypt(key_id, &d.unwrap())?;
+    composite.push(Metadata::new(
+        mime::MESSAGE_X_RSOCKET_ENCRYPTION_V0.to_string(),
+        Bytes::from(key_id.to_string()),
+    ));
+    Ok(Payload::from((
+        Some(encrypted),
+        Some(Bytes::from(composite)),
+    )))
+}
+
+/// Decrypt the data of a payload by the key it names, the key id entry is removed.
+fn decrypt(
+    cipher: &dyn PayloadCipher,
+    allow_plaintext: bool,
+    req: Payload,
+) -> Result<Payload, RSocketError> {
+    let key_id = composite_of(&req).and_then(|mut composite| {
+        composite
+            .remove(mime::MESSAGE_X_RSOCKET_ENCRYPTION_V0)
+            .map(|it| {
+                (
+                    String::from_utf8_lossy(it.get_payload()).into_owned(),
+                    composite,
+                )
+            })
+    });
+    let (key_id, composite) = match key_id {
+        Some(it) => it,
+        None if allow_plaintext || req.data().is_none() => return Ok(req),
+        None => return Err(RSocketError::from("payload data is not encrypted")),
+    };
+    let (d, _) = req.split();
+    let d = match d {
+        Some(d) => Some(cipher.decrypt(&key_id, &d)?),
+        None => None,
+    };
+    let m = if composite.iter().next().is_some() {
+        Some(Bytes::from(composite))
+    } else {
+        None
+    };
+    Ok(Payload::from((d, m)))
+}
@@ -0,0 +1,86 @@
+use super::composite_of;
+use crate::error::RSocketError;
+use crate::extension::CompositeMetadata;
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono, RSocket};
+use bytes::Bytes;
+use futures::StreamExt;
+use std::sync::Arc;
+
+type FnEnrich = Arc<dyn Fn(&mut CompositeMetadata) + Send + Sync>;
+
+/// Requester side interceptor which lets `enrich` add or modify composite metadata entries
+/// of every request, e.g. a tenant id, the client version or a request id.
+///
+/// Requests whose metadata is not composite are sent unchanged.
+pub struct MetadataEnricher<T> {
+    inner: T,
+    enrich: FnEnrich,
+}
+
+impl<T> MetadataEnricher<T>
+where
+    T: RSocket,
+{
+    pub fn new<F>(inner: T, enrich: F) -> MetadataEnricher<T>
+    where
+        F: Fn(&mut CompositeMetadata) + Send + Sync + 'static,
+    {
+        MetadataEnricher {
+            inner,
+            enrich: Arc::new(enrich),
+        }
+    }
+}
+
+impl<T> RSocket for MetadataEnricher<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.inner.fire_and_forget(enrich(&self.enrich, req))
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.inner.request_response(enrich(&self.enrich, req))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.inner.request_stream(enrich(&self.enrich, req))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let f = self.enrich.clone();
+        let reqs = reqs.enumerate().map(move |(i, it)| match it {
+            Ok(req) if i == 0 => Ok(enrich(&f, req)),
+            other => other,
+        });
+        self.inner.request_channel(Box::pin(reqs))
+    }
+}
+
+#[inline]
+fn enrich(f: &FnEnrich, req: Payload) -> Payload {
+    let mut composite = match composite_of(&req) {
+        Some(it) => it,
+        None => {
+            warn!("cannot enrich non-composite metadata");
+            return req;
+        }
+    };
+    f(&mut composite);
+    let (d, _) = req.split();
+    let m = if composite.iter().next().is_some() {
+        Some(Bytes::from(composite))
+    } else {
+        None
+    };
+    Payload::from((d, m))
+}
@@ -0,0 +1,87 @@
+use crate::frame::Frame;
+use bytes::Bytes;
+use std::fmt::Write;
+
+const LOG_TARGET: &str = "rsocket_rust::frames";
+
+/// How the data or metadata of a frame is rendered in logs.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Redaction {
+    /// Only the length is logged.
+    Redact,
+    /// At most the first n bytes are logged.
+    Truncate(usize),
+    /// The full content is logged.
+    Show,
+}
+
+/// Connection interceptor which logs every sent and received frame at debug level.
+///
+/// Records are logged with target `rsocket_rust::frames`, and carry frame type, stream id,
+/// flags and sizes. Data and metadata contents are redacted by default.
+#[derive(Debug, Clone)]
+pub struct FrameLogger {
+    data: Redaction,
+    metadata: Redaction,
+}
+
+impl FrameLogger {
+    pub fn new() -> FrameLogger {
+        FrameLogger {
+            data: Redaction::Redact,
+            metadata: Redaction::Redact,
+        }
+    }
+
+    pub fn data(mut self, redaction: Redaction) -> Self {
+        self.data = redaction;
+        self
+    }
+
+    pub fn metadata(mut self, redaction: Redaction) -> Self {
+        self.metadata = redaction;
+        self
+    }
+
+    pub(crate) fn log(&self, outbound: bool, frame: &Frame) {
+        if log_enabled!(target: LOG_TARGET, log::Level::Debug) {
+            debug!(target: LOG_TARGET, "{}", self.format(outbound, frame));
+        }
+    }
+
+    pub(crate) fn format(&self, outbound: bool, frame: &Frame) -> String {
+        let mut s = format!(
+            "{} {} sid={} flags=0x{:03X}",
+            if outbound { "===> SND:" } else { "<=== RCV:" },
+            frame.get_frame_type_name(),
+            frame.get_stream_id(),
+            frame.get_flag(),
+        );
+        if let Some(b) = frame.get_metadata() {
+            write!(s, " metadata={}", render(b, self.metadata)).unwrap();
+        }
+        if let Some(b) = frame.get_data() {
+            write!(s, " data={}", render(b, self.data)).unwrap();
+        }
+        s
+    }
+}
+
+impl Default for FrameLogger {
+    fn default() -> FrameLogger {
+        FrameLogger::new()
+    }
+}
+
+#[inline]
+fn render(b: &Bytes, redaction: Redaction) -> String {
+    match redaction {
+        Redaction::Redact => format!("<{} bytes>", b.len()),
+        Redaction::Truncate(n) if b.len() > n => format!(
+            "{:?}...<{} bytes>",
+            String::from_utf8_lossy(&b[..n]),
+            b.len()
+        ),
+        _ => format!("{:?}", String::from_utf8_lossy(b)),
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(feature = "interceptor")]
+mod access_log;
+#[cfg(feature = "interceptor")]
+mod auth;
+#[cfg(feature = "interceptor")]
+mod cache;
+mod capture;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compression;
+#[cfg(feature = "interceptor")]
+mod encryption;
+#[cfg(feature = "interceptor")]
+mod enricher;
+mod frame_logger;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "interceptor")]
+mod singleflight;
+#[cfg(feature = "interceptor")]
+mod zipkin;
+
+#[cfg(feature = "interceptor")]
+pub use access_log::{AccessLog, AccessRecord, AccessSink, Outcome};
+#[cfg(feature = "interceptor")]
+pub use auth::{AuthToken, BearerAuthInjector};
+#[cfg(feature = "interceptor")]
+pub use cache::ResponseCache;
+pub use capture::{CaptureReader, CaptureRecorder, CapturedFrame};
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use compression::{CompressingRequester, CompressingResponder, Compression};
+#[cfg(feature = "interceptor")]
+pub use encryption::{EncryptingRequester, EncryptingResponder, PayloadCipher};
+#[cfg(feature = "interceptor")]
+pub use enricher::MetadataEnricher;
+pub use frame_logger::{FrameLogger, Redaction};
+#[cfg(feature = "otel")]
+pub use otel::{OtelExtractor, OtelInjector};
+#[cfg(feature = "interceptor")]
+pub use singleflight::Singleflight;
+#[cfg(feature = "interceptor")]
+pub use zipkin::{ZipkinExtractor, ZipkinInjector};
+
+#[cfg(feature = "extension")]
+use crate::extension::{CompositeMetadata, Metadata};
+use crate::payload::Payload;
+use bytes::{Bytes, BytesMut};
+
+/// Decode the metadata of a payload as composite metadata.
+#[cfg(feature = "extension")]
+#[inline]
+pub(crate) fn composite_of(req: &Payload) -> Option<CompositeMetadata> {
+    match req.metadata() {
+        Some(b) => {
+            let mut bf = BytesMut::from(b.as_ref());
+            match CompositeMetadata::decode(&mut bf) {
+                Ok(it) => Some(it),
+                Err(e) => {
+                    debug!("metadata is not composite: {}", e);
+                    None
+                }
+            }
+        }
+        None => Some(CompositeMetadata::default()),
+    }
+}
+
+/// Append an entry to the composite metadata of a payload.
+/// The payload is left untouched if its metadata is not composite.
+#[cfg(feature = "extension")]
+#[inline]
+pub(crate) fn append_metadata(req: Payload, mime: &str, value: Bytes) -> Payload {
+    let mut composite = match composite_of(&req) {
+        Some(it) => it,
+        None => {
+            warn!("cannot append {} to non-composite metadata", mime);
+            return req;
+        }
+    };
+    composite.push(Metadata::new(mime.to_string(), value));
+    let (d, _) = req.split();
+    Payload::from((d, Some(Bytes::from(composite))))
+}
@@ -0,0 +1,386 @@
+use super::{append_metadata, composite_of};
+use crate::error::{ErrorKind, RSocketError};
+use crate::mime::MESSAGE_X_RSOCKET_TRACE_CONTEXT_V0;
+use crate::payload::Payload;
+use crate::router::route_of;
+use crate::spi::{Flux, Mono, RSocket};
+use bytes::Bytes;
+use futures::{stream, FutureExt, StreamExt};
+use opentelemetry::metrics::Histogram;
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::{FutureExt as _, SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use std::collections::HashMap;
+use std::future::Future;
+use std::task::Poll;
+use std::time::Instant;
+
+const INSTRUMENTATION_NAME: &str = "rsocket_rust";
+
+/// Requester side interceptor which traces every request in a client span of OpenTelemetry,
+/// and propagates its W3C trace context to the responder.
+///
+/// The span is a child of the current context. Its trace context is injected as `traceparent`
+/// and `tracestate` lines into a `message/x.rsocket.trace-context.v0` entry of composite
+/// metadata. Spans carry `rpc.system`, `rpc.method` (the route, if any), `rsocket.interaction`,
+/// `rsocket.outcome` and `rsocket.error_code` on errors.
+///
+/// The latency until a request terminates is recorded into histogram `rsocket.request.duration`
+/// of the global meter provider, with the same attributes.
+pub struct OtelInjector<T> {
+    inner: T,
+    telemetry: Telemetry,
+}
+
+/// Responder side interceptor which traces every request in a server span of OpenTelemetry,
+/// as a child of the trace context propagated by `OtelInjector`. Handlers run in the context
+/// of the span.
+pub struct OtelExtractor<T> {
+    inner: T,
+    telemetry: Telemetry,
+}
+
+#[derive(Clone)]
+struct Telemetry {
+    kind: SpanKind,
+    duration: Histogram<f64>,
+}
+
+/// The span of a request, which ends once the request terminates or is dropped.
+struct RequestSpan {
+    cx: Context,
+    started: Instant,
+    attributes: Vec<KeyValue>,
+    duration: Histogram<f64>,
+    done: bool,
+}
+
+/// Header-like lines of a trace context entry.
+#[derive(Default)]
+struct Carrier(HashMap<String, String>);
+
+impl<T> OtelInjector<T>
+where
+    T: RSocket,
+{
+    pub fn new(inner: T) -> OtelInjector<T> {
+        OtelInjector {
+            inner,
+            telemetry: Telemetry::new(SpanKind::Client),
+        }
+    }
+}
+
+impl<T> OtelExtractor<T>
+where
+    T: RSocket,
+{
+    pub fn new(inner: T) -> OtelExtractor<T> {
+        OtelExtractor {
+            inner,
+            telemetry: Telemetry::new(SpanKind::Server),
+        }
+    }
+}
+
+impl<T> RSocket for OtelInjector<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        let span = self
+            .telemetry
+            .start("fire_and_forget", &Context::current(), Some(&req));
+        let req = inject(&span.cx, req);
+        Box::pin(span.unit(self.inner.fire_and_forget(req)))
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let span = self
+            .telemetry
+            .start("request_response", &Context::current(), Some(&req));
+        let req = inject(&span.cx, req);
+        Box::pin(span.mono(self.inner.request_response(req)))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let span = self
+            .telemetry
+            .start("request_stream", &Context::current(), Some(&req));
+        let req = inject(&span.cx, req);
+        span.flux(self.inner.request_stream(req))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        // the route of a channel is unknown until its first payload is sent.
+        let span = self
+            .telemetry
+            .start("request_channel", &Context::current(), None);
+        let cx = span.cx.clone();
+        let reqs = reqs.enumerate().map(move |(i, it)| match it {
+            Ok(req) if i == 0 => Ok(inject(&cx, req)),
+            other => other,
+        });
+        span.flux(self.inner.request_channel(Box::pin(reqs)))
+    }
+}
+
+impl<T> RSocket for OtelExtractor<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        let span = self
+            .telemetry
+            .start("fire_and_forget", &extract(&req), Some(&req));
+        let cx = span.cx.clone();
+        let task = {
+            let _attached = cx.clone().attach();
+            self.inner.fire_and_forget(req)
+        };
+        Box::pin(span.unit(task.with_context(cx)))
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let span = self
+            .telemetry
+            .start("request_response", &extract(&req), Some(&req));
+        let cx = span.cx.clone();
+        let task = {
+            let _attached = cx.clone().attach();
+            self.inner.request_response(req)
+        };
+        Box::pin(span.mono(task.with_context(cx)))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let span = self
+            .telemetry
+            .start("request_stream", &extract(&req), Some(&req));
+        let cx = span.cx.clone();
+        let results = {
+            let _attached = cx.clone().attach();
+            self.inner.request_stream(req)
+        };
+        span.flux(Box::pin(results.with_context(cx)))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        // the trace context is carried by the first payload of a channel.
+        let telemetry = self.telemetry.clone();
+        let (tx, rx) = futures::channel::oneshot::channel::<Flux<Result<Payload, RSocketError>>>();
+        let results = self.inner.request_channel(Box::pin(
+            stream::once(rx.map(|it| it.unwrap_or_else(|_| Box::pin(stream::empty())))).flatten(),
+        ));
+        let traced = reqs.into_future().map(move |(first, rest)| {
+            let parent = match &first {
+                Some(Ok(req)) => extract(req),
+                _ => Context::new(),
+            };
+            let req = first.as_ref().and_then(|it| it.as_ref().ok());
+            let span = telemetry.start("request_channel", &parent, req);
+            let _ = tx.send(Box::pin(stream::iter(first).chain(rest)));
+            let cx = span.cx.clone();
+            span.flux(Box::pin(results.with_context(cx)))
+        });
+        Box::pin(stream::once(traced).flatten())
+    }
+}
+
+impl Telemetry {
+    fn new(kind: SpanKind) -> Telemetry {
+        let duration = global::meter(INSTRUMENTATION_NAME)
+            .f64_histogram("rsocket.request.duration")
+            .with_unit("s")
+            .with_description("Duration of RSocket requests until they terminate.")
+            .build();
+        Telemetry { kind, duration }
+    }
+
+    /// Start the span of a request as a child of `parent`.
+    fn start(
+        &self,
+        interaction: &'static str,
+        parent: &Context,
+        req: Option<&Payload>,
+    ) -> RequestSpan {
+        let route = req.and_then(route_of);
+        let mut attributes = vec![
+            KeyValue::new("rpc.system", "rsocket"),
+            KeyValue::new("rsocket.interaction", interaction),
+        ];
+        let name = match route {
+            Some(route) => {
+                attributes.push(KeyValue::new("rpc.method", route.clone()));
+                route
+            }
+            None => interaction.to_string(),
+        };
+        let tracer = global::tracer(INSTRUMENTATION_NAME);
+        let span = tracer
+            .span_builder(name)
+            .with_kind(self.kind.clone())
+            .with_attributes(attributes.clone())
+            .start_with_context(&tracer, parent);
+        RequestSpan {
+            cx: parent.with_span(span),
+            started: Instant::now(),
+            attributes,
+            duration: self.duration.clone(),
+            done: false,
+        }
+    }
+}
+
+impl RequestSpan {
+    fn unit<F>(self, task: F) -> impl Future<Output = ()>
+    where
+        F: Future<Output = ()>,
+    {
+        let mut this = self;
+        task.map(move |_| this.finish(None))
+    }
+
+    fn mono<F>(self, task: F) -> impl Future<Output = Result<Payload, RSocketError>>
+    where
+        F: Future<Output = Result<Payload, RSocketError>>,
+    {
+        let mut this = self;
+        task.map(move |result| {
+            this.finish(result.as_ref().err());
+            result
+        })
+    }
+
+    fn flux(
+        self,
+        mut results: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let mut this = self;
+        Box::pin(stream::poll_fn(move |cx| {
+            let next = results.poll_next_unpin(cx);
+            match &next {
+                Poll::Ready(Some(Err(e))) => this.finish(Some(e)),
+                Poll::Ready(None) => this.finish(None),
+                _ => (),
+            }
+            next
+        }))
+    }
+
+    fn finish(&mut self, error: Option<&RSocketError>) {
+        let outcome = match error.map(|e| e.kind()) {
+            None => "ok",
+            Some(ErrorKind::Cancelled()) => "cancelled",
+            Some(_) => "error",
+        };
+        self.end(outcome, error);
+    }
+
+    fn end(&mut self, outcome: &'static str, error: Option<&RSocketError>) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        let span = self.cx.span();
+        self.attributes
+            .push(KeyValue::new("rsocket.outcome", outcome));
+        if let Some(e) = error {
+            if let ErrorKind::Internal(code, _) = e.kind() {
+                self.attributes
+                    .push(KeyValue::new("rsocket.error_code", i64::from(*code)));
+            }
+            span.set_status(Status::error(e.to_string()));
+        }
+        span.set_attributes(self.attributes.clone());
+        span.end();
+        self.duration
+            .record(self.started.elapsed().as_secs_f64(), &self.attributes);
+    }
+}
+
+impl Drop for RequestSpan {
+    fn drop(&mut self) {
+        self.end("cancelled", None);
+    }
+}
+
+impl Injector for Carrier {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+impl Extractor for Carrier {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(|it| it.as_str())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|it| it.as_str()).collect()
+    }
+}
+
+impl From<Carrier> for Bytes {
+    fn from(carrier: Carrier) -> Bytes {
+        let mut lines = String::new();
+        for (k, v) in carrier.0 {
+            lines.push_str(&k);
+            lines.push_str(": ");
+            lines.push_str(&v);
+            lines.push('\n');
+        }
+        Bytes::from(lines)
+    }
+}
+
+impl From<&[u8]> for Carrier {
+    fn from(b: &[u8]) -> Carrier {
+        let lines = String::from_utf8_lossy(b);
+        let headers = lines
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+            .collect();
+        Carrier(headers)
+    }
+}
+
+/// Attach the W3C trace context of `cx` to a request.
+fn inject(cx: &Context, req: Payload) -> Payload {
+    let mut carrier = Carrier::default();
+    TraceContextPropagator::new().inject_context(cx, &mut carrier);
+    if carrier.0.is_empty() {
+        return req;
+    }
+    append_metadata(
+        req,
+        MESSAGE_X_RSOCKET_TRACE_CONTEXT_V0,
+        Bytes::from(carrier),
+    )
+}
+
+/// Returns the context of the W3C trace context of a request, an empty context if it has none.
+fn extract(req: &Payload) -> Context {
+    let carrier = composite_of(req)
+        .as_ref()
+        .and_then(|it| it.find(MESSAGE_X_RSOCKET_TRACE_CONTEXT_V0))
+        .map(|it| Carrier::from(it.get_payload().as_ref()))
+        .unwrap_or_default();
+    TraceContextPropagator::new().extract_with_context(&Context::new(), &carrier)
+}
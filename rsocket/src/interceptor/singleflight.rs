@@ -0,0 +1,96 @@
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::runtime::{DefaultSpawner, Spawner};
+use crate::spi::{Flux, Mono, RSocket};
+use bytes::Bytes;
+use futures::channel::oneshot;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type Key = (Option<Bytes>, Option<Bytes>);
+type Waiters = Vec<oneshot::Sender<Result<Payload, RSocketError>>>;
+
+/// Requester side interceptor which coalesces concurrent identical REQUEST_RESPONSE calls,
+/// by data and metadata, into one request whose result is sent to every caller.
+///
+/// The request is sent by a task spawned at the call, so it completes even if the caller
+/// which started it is cancelled.
+pub struct Singleflight<T> {
+    inner: Arc<T>,
+    in_flight: Arc<Mutex<HashMap<Key, Waiters>>>,
+}
+
+impl<T> Singleflight<T>
+where
+    T: RSocket + 'static,
+{
+    pub fn new(inner: T) -> Singleflight<T> {
+        Singleflight {
+            inner: Arc::new(inner),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the number of distinct requests in flight.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+}
+
+impl<T> RSocket for Singleflight<T>
+where
+    T: RSocket + 'static,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.inner.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let key = (req.data().clone(), req.metadata().clone());
+        let (tx, rx) = oneshot::channel();
+        let leader = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get_mut(&key) {
+                Some(waiters) => {
+                    waiters.push(tx);
+                    false
+                }
+                None => {
+                    in_flight.insert(key.clone(), vec![tx]);
+                    true
+                }
+            }
+        };
+        if leader {
+            let res = self.inner.request_response(req);
+            let in_flight = self.in_flight.clone();
+            DefaultSpawner.spawn(async move {
+                let res = res.await;
+                let waiters = in_flight.lock().unwrap().remove(&key).unwrap_or_default();
+                debug!("fan out a response to {} callers", waiters.len());
+                for it in waiters {
+                    let _ = it.send(res.clone());
+                }
+            });
+        }
+        Box::pin(async move {
+            rx.await
+                .unwrap_or_else(|_| Err(RSocketError::from("coalesced request is gone")))
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.inner.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.inner.request_channel(reqs)
+    }
+}
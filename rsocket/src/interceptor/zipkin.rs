@@ -0,0 +1,231 @@
+use super::{append_metadata, composite_of};
+use crate::error::RSocketError;
+use crate::extension::{Sampling, TracingMetadata};
+use crate::mime::MESSAGE_X_RSOCKET_TRACING_ZIPKIN_V0;
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono, RSocket, RequestContext};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use std::sync::Arc;
+
+type FnCurrentSpan = Arc<dyn Fn() -> Option<TracingMetadata> + Send + Sync>;
+type FnOnSpan = Arc<dyn Fn(&TracingMetadata) + Send + Sync>;
+
+/// Requester side interceptor which injects zipkin tracing metadata into every request.
+///
+/// A child span of `current_span` is injected when it is set and returns a span,
+/// otherwise a new trace is started.
+pub struct ZipkinInjector<T> {
+    inner: T,
+    sampling: Sampling,
+    current_span: Option<FnCurrentSpan>,
+    on_inject: Option<FnOnSpan>,
+}
+
+/// Responder side interceptor which extracts zipkin tracing metadata from every request.
+pub struct ZipkinExtractor<T> {
+    inner: T,
+    on_extract: FnOnSpan,
+}
+
+impl<T> ZipkinInjector<T>
+where
+    T: RSocket,
+{
+    pub fn new(inner: T) -> ZipkinInjector<T> {
+        ZipkinInjector {
+            inner,
+            sampling: Sampling::Unspecified,
+            current_span: None,
+            on_inject: None,
+        }
+    }
+
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn current_span<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> Option<TracingMetadata> + Send + Sync + 'static,
+    {
+        self.current_span = Some(Arc::new(f));
+        self
+    }
+
+    pub fn on_inject<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&TracingMetadata) + Send + Sync + 'static,
+    {
+        self.on_inject = Some(Arc::new(f));
+        self
+    }
+
+    fn inject(&self, req: Payload) -> Payload {
+        inject(&self.sampling, &self.current_span, &self.on_inject, req)
+    }
+}
+
+impl<T> RSocket for ZipkinInjector<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.inner.fire_and_forget(self.inject(req))
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.inner.request_response(self.inject(req))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.inner.request_stream(self.inject(req))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let sampling = self.sampling;
+        let current_span = self.current_span.clone();
+        let on_inject = self.on_inject.clone();
+        let reqs = reqs.enumerate().map(move |(i, it)| match it {
+            Ok(req) if i == 0 => Ok(inject(&sampling, &current_span, &on_inject, req)),
+            other => other,
+        });
+        self.inner.request_channel(Box::pin(reqs))
+    }
+}
+
+impl<T> ZipkinExtractor<T>
+where
+    T: RSocket,
+{
+    pub fn new<F>(inner: T, on_extract: F) -> ZipkinExtractor<T>
+    where
+        F: Fn(&TracingMetadata) + Send + Sync + 'static,
+    {
+        ZipkinExtractor {
+            inner,
+            on_extract: Arc::new(on_extract),
+        }
+    }
+
+    fn extract(&self, req: &Payload) {
+        extract(&self.on_extract, req)
+    }
+
+    /// Extract the span of the first request of a channel.
+    fn extract_first(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let on_extract = self.on_extract.clone();
+        Box::pin(reqs.enumerate().map(move |(i, it)| {
+            if let (0, Ok(req)) = (i, &it) {
+                extract(&on_extract, req);
+            }
+            it
+        }))
+    }
+}
+
+impl<T> RSocket for ZipkinExtractor<T>
+where
+    T: RSocket,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.inner.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.extract(&req);
+        self.inner.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.extract(&req);
+        self.inner.request_response(req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.extract(&req);
+        self.inner.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.inner.request_channel(self.extract_first(reqs))
+    }
+
+    fn fire_and_forget_with_context(&self, ctx: RequestContext, req: Payload) -> Mono<()> {
+        self.extract(&req);
+        self.inner.fire_and_forget_with_context(ctx, req)
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        self.extract(&req);
+        self.inner.request_response_with_context(ctx, req)
+    }
+
+    fn request_stream_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.extract(&req);
+        self.inner.request_stream_with_context(ctx, req)
+    }
+
+    fn request_channel_with_context(
+        &self,
+        ctx: RequestContext,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.inner
+            .request_channel_with_context(ctx, self.extract_first(reqs))
+    }
+}
+
+#[inline]
+fn inject(
+    sampling: &Sampling,
+    current_span: &Option<FnCurrentSpan>,
+    on_inject: &Option<FnOnSpan>,
+    req: Payload,
+) -> Payload {
+    let span = match current_span.as_ref().and_then(|f| f()) {
+        Some(parent) => parent.new_child(),
+        None => TracingMetadata::new_root(*sampling),
+    };
+    if let Some(f) = on_inject {
+        f(&span);
+    }
+    append_metadata(req, MESSAGE_X_RSOCKET_TRACING_ZIPKIN_V0, Bytes::from(span))
+}
+
+#[inline]
+fn extract(on_extract: &FnOnSpan, req: &Payload) {
+    let composite = match composite_of(req) {
+        Some(it) => it,
+        None => return,
+    };
+    if let Some(it) = composite.find(MESSAGE_X_RSOCKET_TRACING_ZIPKIN_V0) {
+        let mut bf = BytesMut::from(it.get_payload().as_ref());
+        match TracingMetadata::decode(&mut bf) {
+            Ok(span) => on_extract(&span),
+            Err(e) => warn!("decode tracing metadata failed: {}", e),
+        }
+    }
+}
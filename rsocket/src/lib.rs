@@ -1,34 +1,76 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(dead_code)]
 #![allow(unused_imports)]
 #![allow(unused_variables)]
 
+#[macro_use]
+extern crate alloc;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(feature = "balancer")]
+pub mod balancer;
+#[cfg(feature = "broker")]
+pub mod broker;
+#[cfg(any(feature = "serde", feature = "flatbuffers"))]
+pub mod codec;
 pub mod error;
+#[cfg(feature = "extension")]
 pub mod extension;
 
 #[cfg(feature = "frame")]
 pub mod frame;
 #[cfg(not(feature = "frame"))]
 mod frame;
+#[cfg(feature = "extension")]
+pub mod health;
 
+#[cfg(feature = "std")]
+pub mod interceptor;
+#[cfg(feature = "std")]
 pub mod mime;
+#[cfg(feature = "std")]
 mod payload;
+#[cfg(feature = "proxy")]
+pub mod proxy;
+#[cfg(feature = "reload")]
+pub mod reload;
+#[cfg(feature = "replay")]
+pub mod replay;
+#[cfg(feature = "extension")]
+pub mod router;
+#[cfg(feature = "serde")]
+pub mod rpc;
+#[cfg(feature = "std")]
 pub mod runtime;
+#[cfg(feature = "share")]
+pub mod share;
+#[cfg(feature = "std")]
 mod spi;
+#[cfg(feature = "tck")]
+pub mod tck;
+#[cfg(feature = "tenant")]
+pub mod tenant;
+#[cfg(feature = "test-helpers")]
+pub mod test_helpers;
+#[cfg(feature = "std")]
 pub mod transport;
 pub mod utils;
+#[cfg(feature = "std")]
 mod x;
 
+#[cfg(feature = "std")]
 pub mod prelude {
+    #[cfg(feature = "extension")]
+    pub use crate::payload::MetadataBuilder;
     pub use crate::payload::{Payload, PayloadBuilder, SetupPayload, SetupPayloadBuilder};
     pub use crate::runtime::Spawner;
     pub use crate::spi::*;
     pub use crate::transport::{ClientTransport, Rx, ServerTransport, Tx};
     pub use crate::utils::RSocketResult;
-    pub use crate::x::{Client, RSocketFactory};
+    pub use crate::x::{Client, Connection, RSocketFactory, Requester};
     pub use futures::{Sink, SinkExt, Stream, StreamExt};
 }
@@ -43,9 +43,21 @@ pub const VIDEO_VP8: &str = "video/VP8";
 pub const APPLICATION_X_HESSIAN: &str = "application/x-hessian";
 pub const APPLICATION_X_JAVA_OBJECT: &str = "application/x-java-object";
 pub const APPLICATION_CLOUDEVENTS_JSON: &str = "application/cloudevents+json";
+pub const APPLICATION_MSGPACK: &str = "application/msgpack";
+pub const MESSAGE_X_RSOCKET_MIME_TYPE_V0: &str = "message/x.rsocket.mime-type.v0";
+pub const MESSAGE_X_RSOCKET_ACCEPT_MIME_TYPES_V0: &str = "message/x.rsocket.accept-mime-types.v0";
+pub const MESSAGE_X_RSOCKET_AUTHENTICATION_V0: &str = "message/x.rsocket.authentication.v0";
 pub const MESSAGE_X_RSOCKET_TRACING_ZIPKIN_V0: &str = "message/x.rsocket.tracing-zipkin.v0";
 pub const MESSAGE_X_RSOCKET_ROUTING_V0: &str = "message/x.rsocket.routing.v0";
 pub const MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0: &str = "message/x.rsocket.composite-metadata.v0";
+/// Not a well-known MIME type, it is always encoded as a string.
+pub const MESSAGE_X_RSOCKET_PRIORITY_V0: &str = "message/x.rsocket.priority.v0";
+/// Not a well-known MIME type, it is always encoded as a string.
+pub const MESSAGE_X_RSOCKET_COMPRESSION_V0: &str = "message/x.rsocket.compression.v0";
+/// Not a well-known MIME type, it is always encoded as a string.
+pub const MESSAGE_X_RSOCKET_ENCRYPTION_V0: &str = "message/x.rsocket.encryption.v0";
+/// Not a well-known MIME type, it is always encoded as a string.
+pub const MESSAGE_X_RSOCKET_TRACE_CONTEXT_V0: &str = "message/x.rsocket.trace-context.v0";
 
 lazy_static! {
     static ref MIME_MAP: HashMap<WellKnownMIME, (u8, &'static str)> = {
@@ -121,6 +133,18 @@ lazy_static! {
             WellKnownMIME::ApplicationCloudeventsJson,
             (0x28, APPLICATION_CLOUDEVENTS_JSON),
         );
+        m.insert(
+            WellKnownMIME::MessageXRSocketMimeTypeV0,
+            (0x7A, MESSAGE_X_RSOCKET_MIME_TYPE_V0),
+        );
+        m.insert(
+            WellKnownMIME::MessageXRSocketAcceptMimeTypesV0,
+            (0x7B, MESSAGE_X_RSOCKET_ACCEPT_MIME_TYPES_V0),
+        );
+        m.insert(
+            WellKnownMIME::MessageXRSocketAuthenticationV0,
+            (0x7C, MESSAGE_X_RSOCKET_AUTHENTICATION_V0),
+        );
         m.insert(
             WellKnownMIME::MessageXRSocketTracingZipkinV0,
             (0x7D, MESSAGE_X_RSOCKET_TRACING_ZIPKIN_V0),
@@ -180,6 +204,9 @@ pub enum WellKnownMIME {
     ApplicationXHessian,
     ApplicationXJavaObject,
     ApplicationCloudeventsJson,
+    MessageXRSocketMimeTypeV0,
+    MessageXRSocketAcceptMimeTypesV0,
+    MessageXRSocketAuthenticationV0,
     MessageXRSocketTracingZipkinV0,
     MessageXRSocketRoutingV0,
     MessageXRsocketCompositeMetadataV0,
@@ -0,0 +1,106 @@
+use super::{Payload, PayloadBuilder};
+use crate::extension::{
+    AuthMetadata, CompositeMetadata, Metadata, MimeTypeMetadata, PriorityMetadata, RoutingMetadata,
+    TracingMetadata,
+};
+use crate::mime;
+use crate::utils::RSocketResult;
+use bytes::Bytes;
+
+/// Fluent builder of composite metadata, created by `PayloadBuilder::metadata`.
+///
+/// ```
+/// use rsocket_rust::prelude::*;
+///
+/// let req = Payload::builder()
+///     .set_data_utf8("Hello World!")
+///     .metadata()
+///     .route("orders.create")
+///     .bearer("my_token")
+///     .custom("application/x.tenant", "tenant_1")
+///     .build();
+/// ```
+pub struct MetadataBuilder {
+    payload: PayloadBuilder,
+    tags: Vec<String>,
+    entries: Vec<Metadata>,
+}
+
+impl MetadataBuilder {
+    pub(crate) fn new(payload: PayloadBuilder) -> MetadataBuilder {
+        MetadataBuilder {
+            payload,
+            tags: vec![],
+            entries: vec![],
+        }
+    }
+
+    /// Add a routing tag, all tags are encoded in a single routing metadata entry.
+    pub fn route(mut self, tag: &str) -> Self {
+        self.tags.push(String::from(tag));
+        self
+    }
+
+    pub fn bearer(self, token: &str) -> Self {
+        self.auth(AuthMetadata::bearer(token))
+    }
+
+    pub fn simple_auth(self, username: &str, password: &str) -> Self {
+        self.auth(AuthMetadata::simple(username, password))
+    }
+
+    pub fn auth(self, auth: AuthMetadata) -> Self {
+        self.custom(mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0, Bytes::from(auth))
+    }
+
+    /// Override the connection data MIME type for this stream, see `MimeTypeMetadata::new`.
+    pub fn mime_type(self, mime_type: &str) -> RSocketResult<Self> {
+        let entry = MimeTypeMetadata::new(mime_type)?;
+        Ok(self.custom(mime::MESSAGE_X_RSOCKET_MIME_TYPE_V0, Bytes::from(entry)))
+    }
+
+    /// Set the priority of this stream, see `PriorityMetadata`.
+    pub fn priority(self, priority: u8) -> Self {
+        self.custom(
+            mime::MESSAGE_X_RSOCKET_PRIORITY_V0,
+            Bytes::from(PriorityMetadata::new(priority)),
+        )
+    }
+
+    pub fn tracing(self, span: TracingMetadata) -> Self {
+        self.custom(mime::MESSAGE_X_RSOCKET_TRACING_ZIPKIN_V0, Bytes::from(span))
+    }
+
+    pub fn custom<A>(mut self, mime: &str, payload: A) -> Self
+    where
+        A: AsRef<[u8]>,
+    {
+        let payload = Bytes::from(payload.as_ref().to_vec());
+        self.entries
+            .push(Metadata::new(String::from(mime), payload));
+        self
+    }
+
+    /// Finish the metadata and return to the payload builder.
+    pub fn end(self) -> PayloadBuilder {
+        let mut composite = CompositeMetadata::default();
+        if !self.tags.is_empty() {
+            let mut routing = RoutingMetadata::builder();
+            for tag in self.tags {
+                routing = routing.push(tag);
+            }
+            composite.push(Metadata::new(
+                String::from(mime::MESSAGE_X_RSOCKET_ROUTING_V0),
+                Bytes::from(routing.build()),
+            ));
+        }
+        for it in self.entries {
+            composite.push(it);
+        }
+        self.payload.set_metadata(Bytes::from(composite))
+    }
+
+    pub fn build(self) -> Payload {
+        self.end().build()
+    }
+}
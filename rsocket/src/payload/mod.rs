@@ -1,5 +1,9 @@
+#[cfg(feature = "extension")]
+mod metadata;
 mod normal;
 mod setup;
 
+#[cfg(feature = "extension")]
+pub use metadata::MetadataBuilder;
 pub use normal::{Payload, PayloadBuilder};
 pub use setup::{SetupPayload, SetupPayloadBuilder};
@@ -1,3 +1,5 @@
+#[cfg(feature = "extension")]
+use super::MetadataBuilder;
 use crate::frame;
 use bytes::Bytes;
 
@@ -37,6 +39,12 @@ impl PayloadBuilder {
         self.set_data(Bytes::from(String::from(data)))
     }
 
+    /// Start building composite metadata for this payload.
+    #[cfg(feature = "extension")]
+    pub fn metadata(self) -> MetadataBuilder {
+        MetadataBuilder::new(self)
+    }
+
     pub fn build(self) -> Payload {
         self.value
     }
@@ -47,6 +55,24 @@ impl Payload {
         PayloadBuilder::new()
     }
 
+    pub fn new(data: Option<Bytes>, metadata: Option<Bytes>) -> Payload {
+        Payload {
+            d: data,
+            m: metadata,
+        }
+    }
+
+    pub fn from_utf8(data: &str) -> Payload {
+        Payload::builder().set_data_utf8(data).build()
+    }
+
+    pub fn from_utf8_with_metadata(data: &str, metadata: &str) -> Payload {
+        Payload::builder()
+            .set_data_utf8(data)
+            .set_metadata_utf8(metadata)
+            .build()
+    }
+
     pub fn metadata(&self) -> &Option<Bytes> {
         &self.m
     }
@@ -55,6 +81,31 @@ impl Payload {
         &self.d
     }
 
+    /// Returns the data as a string slice, or None if it is absent or not valid UTF-8.
+    pub fn data_utf8(&self) -> Option<&str> {
+        self.d.as_ref().and_then(|b| std::str::from_utf8(b).ok())
+    }
+
+    /// Returns the metadata as a string slice, or None if it is absent or not valid UTF-8.
+    pub fn metadata_utf8(&self) -> Option<&str> {
+        self.m.as_ref().and_then(|b| std::str::from_utf8(b).ok())
+    }
+
+    pub fn len(&self) -> usize {
+        let mut n = 0;
+        if let Some(b) = &self.d {
+            n += b.len();
+        }
+        if let Some(b) = &self.m {
+            n += b.len();
+        }
+        n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn split(self) -> (Option<Bytes>, Option<Bytes>) {
         (self.d, self.m)
     }
@@ -78,6 +129,36 @@ impl From<(&'static str, &'static str)> for Payload {
     }
 }
 
+impl From<String> for Payload {
+    fn from(data: String) -> Payload {
+        Payload::new(Some(Bytes::from(data)), None)
+    }
+}
+
+impl From<Bytes> for Payload {
+    fn from(data: Bytes) -> Payload {
+        Payload::new(Some(data), None)
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(data: Vec<u8>) -> Payload {
+        Payload::new(Some(Bytes::from(data)), None)
+    }
+}
+
+impl From<(Bytes, Bytes)> for Payload {
+    fn from((data, metadata): (Bytes, Bytes)) -> Payload {
+        Payload::new(Some(data), Some(metadata))
+    }
+}
+
+impl From<Payload> for (Option<Bytes>, Option<Bytes>) {
+    fn from(input: Payload) -> (Option<Bytes>, Option<Bytes>) {
+        input.split()
+    }
+}
+
 impl From<(Option<Bytes>, Option<Bytes>)> for Payload {
     fn from((data, metadata): (Option<Bytes>, Option<Bytes>)) -> Payload {
         let mut bu = Payload::builder();
@@ -93,9 +174,7 @@ impl From<(Option<Bytes>, Option<Bytes>)> for Payload {
 
 impl From<frame::Payload> for Payload {
     fn from(input: frame::Payload) -> Payload {
-        let d = input.get_data().clone();
-        let m = input.get_metadata().clone();
-        Payload::from((d, m))
+        Payload::from(input.split())
     }
 }
 
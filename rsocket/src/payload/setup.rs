@@ -3,7 +3,7 @@ use crate::utils::DEFAULT_MIME_TYPE;
 use bytes::Bytes;
 use std::time::Duration;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SetupPayload {
     m: Option<Bytes>,
     d: Option<Bytes>,
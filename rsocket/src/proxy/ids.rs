@@ -0,0 +1,189 @@
+use super::Direction;
+use crate::frame::{self, Body, Frame};
+use std::collections::HashMap;
+
+/// Maps stream IDs between the downstream and the upstream connection.
+///
+/// Streams requested by the client have odd IDs on both connections and streams requested by
+/// the server even ones, so the parity of an ID tells who requested the stream. IDs of each
+/// side are allocated by the proxy, a mapping lives until both halves of its stream terminated.
+#[derive(Debug)]
+pub(crate) struct StreamIds {
+    client: Table,
+    server: Table,
+}
+
+#[derive(Debug)]
+struct Table {
+    next_id: u32,
+    // ID on the requesting side -> ID on the other side.
+    forward: HashMap<u32, u32>,
+    backward: HashMap<u32, u32>,
+    streams: HashMap<u32, Stream>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Kind {
+    FireAndForget,
+    RequestResponse,
+    RequestStream,
+    RequestChannel,
+}
+
+#[derive(Debug)]
+struct Stream {
+    kind: Kind,
+    requester_done: bool,
+    responder_done: bool,
+}
+
+impl Default for StreamIds {
+    fn default() -> StreamIds {
+        StreamIds {
+            client: Table::new(1),
+            server: Table::new(2),
+        }
+    }
+}
+
+impl StreamIds {
+    /// Rewrite the stream ID of a frame for the connection it is forwarded to,
+    /// returns None for frames of unknown streams.
+    pub(crate) fn rewrite(&mut self, direction: Direction, mut frame: Frame) -> Option<Frame> {
+        let sid = frame.get_stream_id();
+        if sid == 0 {
+            return Some(frame);
+        }
+        let by_client = sid & 1 == 1;
+        let table = if by_client {
+            &mut self.client
+        } else {
+            &mut self.server
+        };
+        let from_requester = by_client == (direction == Direction::Upstream);
+        let peer = if from_requester {
+            match kind_of(&frame) {
+                Some(kind) => table.open(sid, kind, frame.get_flag()),
+                None => table.update(sid, true, &frame)?,
+            }
+        } else {
+            let requester_sid = *table.backward.get(&sid)?;
+            table.update(requester_sid, false, &frame)?;
+            requester_sid
+        };
+        frame.set_stream_id(peer);
+        Some(frame)
+    }
+}
+
+impl Table {
+    fn new(first_id: u32) -> Table {
+        Table {
+            next_id: first_id,
+            forward: HashMap::new(),
+            backward: HashMap::new(),
+            streams: HashMap::new(),
+        }
+    }
+
+    fn open(&mut self, sid: u32, kind: Kind, flag: u16) -> u32 {
+        if self.forward.contains_key(&sid) {
+            warn!("stream {} is requested again, drop the old one", sid);
+            self.close(sid);
+        }
+        let peer = self.allocate();
+        let fragmented = flag & frame::FLAG_FOLLOW != 0;
+        let stream = Stream {
+            kind,
+            requester_done: match kind {
+                Kind::RequestChannel => flag & frame::FLAG_COMPLETE != 0,
+                Kind::FireAndForget => !fragmented,
+                _ => true,
+            },
+            responder_done: kind == Kind::FireAndForget,
+        };
+        if stream.requester_done && stream.responder_done {
+            return peer;
+        }
+        self.forward.insert(sid, peer);
+        self.backward.insert(peer, sid);
+        self.streams.insert(sid, stream);
+        peer
+    }
+
+    /// Track a frame of a stream, `sid` is the ID on the requesting side.
+    /// Returns the ID of the stream on the side the frame is forwarded to.
+    fn update(&mut self, sid: u32, from_requester: bool, frame: &Frame) -> Option<u32> {
+        let peer = if from_requester {
+            *self.forward.get(&sid)?
+        } else {
+            sid
+        };
+        let stream = self.streams.get_mut(&sid)?;
+        let flag = frame.get_flag();
+        match frame.get_body_ref() {
+            Body::Error(_) => {
+                stream.requester_done = true;
+                stream.responder_done = true;
+            }
+            Body::Cancel() => {
+                // the sender of a CANCEL stops receiving, requesters of streams without an
+                // input send nothing else either.
+                if from_requester {
+                    stream.responder_done = true;
+                    stream.requester_done |= stream.kind != Kind::RequestChannel;
+                } else {
+                    stream.requester_done = true;
+                }
+            }
+            Body::Payload(_) => {
+                let last = flag & frame::FLAG_COMPLETE != 0
+                    || match stream.kind {
+                        Kind::RequestResponse => !from_requester && flag & frame::FLAG_FOLLOW == 0,
+                        Kind::FireAndForget => flag & frame::FLAG_FOLLOW == 0,
+                        _ => false,
+                    };
+                if last && from_requester {
+                    stream.requester_done = true;
+                } else if last {
+                    stream.responder_done = true;
+                }
+            }
+            _ => (),
+        }
+        if stream.requester_done && stream.responder_done {
+            self.close(sid);
+        }
+        Some(peer)
+    }
+
+    fn close(&mut self, sid: u32) {
+        if let Some(peer) = self.forward.remove(&sid) {
+            self.backward.remove(&peer);
+        }
+        self.streams.remove(&sid);
+    }
+
+    fn allocate(&mut self) -> u32 {
+        loop {
+            let id = self.next_id;
+            self.next_id = match self.next_id.checked_add(2) {
+                Some(next) if next <= frame::REQUEST_MAX => next,
+                _ => 2 - (id & 1),
+            };
+            if !self.backward.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+}
+
+fn kind_of(frame: &Frame) -> Option<Kind> {
+    match frame.get_body_ref() {
+        Body::RequestFNF(_) => Some(Kind::FireAndForget),
+        Body::RequestResponse(_) => Some(Kind::RequestResponse),
+        Body::RequestStream(_) => Some(Kind::RequestStream),
+        Body::RequestChannel(_) => Some(Kind::RequestChannel),
+        _ => None,
+    }
+}
@@ -0,0 +1,205 @@
+//! A transparent proxy which relays frames between each accepted connection and an upstream
+//! connection opened for it.
+//!
+//! Frames are not interpreted beyond their stream IDs, which are rewritten so both connections
+//! keep valid and independent ID spaces. A hook set with `ProxyBuilder::on_frame` sees every
+//! relayed frame and may replace it, e.g. to observe or enrich traffic in a sidecar.
+//!
+//! ```ignore
+//! use rsocket_rust::proxy::Proxy;
+//! use rsocket_rust_transport_tcp::{TcpClientTransport, TcpServerTransport};
+//!
+//! Proxy::builder()
+//!     .transport(TcpServerTransport::from("127.0.0.1:7979"))
+//!     .upstream(|| TcpClientTransport::from("127.0.0.1:7878"))
+//!     .on_frame(|direction, frame| {
+//!         info!("{:?}: {:?}", direction, frame);
+//!         frame
+//!     })
+//!     .serve()
+//!     .await
+//! ```
+mod ids;
+
+use crate::error::{self, RSocketError};
+use crate::frame::{self, Frame};
+use crate::runtime::{DefaultSpawner, Spawner};
+use crate::transport::{self, ClientTransport, ServerTransport, SocketOptions};
+use futures::StreamExt;
+use ids::StreamIds;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+/// Which way a frame is relayed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Direction {
+    /// From the client to the upstream server.
+    Upstream,
+    /// From the upstream server to the client.
+    Downstream,
+}
+
+type FnUpstream<U> = dyn Fn() -> U + Send + Sync;
+type FnFrame = dyn Fn(Direction, Frame) -> Frame + Send + Sync;
+
+pub struct Proxy;
+
+pub struct ProxyBuilder<T, C, U> {
+    transport: Option<T>,
+    upstream: Option<Arc<FnUpstream<U>>>,
+    on_frame: Option<Arc<FnFrame>>,
+    start_handler: Option<fn()>,
+    outbound_capacity: usize,
+    _accepted: std::marker::PhantomData<C>,
+}
+
+impl Proxy {
+    pub fn builder<T, C, U>() -> ProxyBuilder<T, C, U>
+    where
+        T: Send + Sync + ServerTransport<Item = C> + 'static,
+        C: Send + Sync + ClientTransport + 'static,
+        U: Send + Sync + ClientTransport + 'static,
+    {
+        ProxyBuilder {
+            transport: None,
+            upstream: None,
+            on_frame: None,
+            start_handler: None,
+            outbound_capacity: SocketOptions::default().outbound_capacity(),
+            _accepted: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T, C, U> ProxyBuilder<T, C, U>
+where
+    T: Send + Sync + ServerTransport<Item = C> + 'static,
+    C: Send + Sync + ClientTransport + 'static,
+    U: Send + Sync + ClientTransport + 'static,
+{
+    /// Set the transport which accepts client connections.
+    pub fn transport(mut self, transport: T) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Set how the upstream connection of each accepted connection is opened.
+    pub fn upstream<F>(mut self, upstream: F) -> Self
+    where
+        F: Fn() -> U + Send + Sync + 'static,
+    {
+        self.upstream = Some(Arc::new(upstream));
+        self
+    }
+
+    /// Set a hook called with every relayed frame, after its stream ID was rewritten.
+    /// The returned frame is sent in place of it.
+    pub fn on_frame<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(Direction, Frame) -> Frame + Send + Sync + 'static,
+    {
+        self.on_frame = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn on_start(mut self, handler: fn()) -> Self {
+        self.start_handler = Some(handler);
+        self
+    }
+
+    /// Bound the frames queued in either direction of each connection, see
+    /// `ClientBuilder::outbound_capacity`.
+    pub fn outbound_capacity(mut self, capacity: usize) -> Self {
+        self.outbound_capacity = capacity;
+        self
+    }
+
+    pub async fn serve(mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let tp = self.transport.take().expect("missing transport");
+        let upstream = self.upstream.take().expect("missing upstream");
+        let on_frame = self.on_frame.take();
+        let capacity = self.outbound_capacity;
+        tp.start(self.start_handler, move |downstream| {
+            relay(downstream, upstream(), on_frame.clone(), capacity)
+        })
+        .await
+    }
+}
+
+/// Relay frames between an accepted connection and its upstream connection until either closes.
+fn relay<C, U>(downstream: C, upstream: U, on_frame: Option<Arc<FnFrame>>, capacity: usize)
+where
+    C: ClientTransport,
+    U: ClientTransport,
+{
+    let (down_in_tx, mut down_in) = transport::new_tx_rx::<Frame>(capacity);
+    let (mut down_out, down_out_rx) = transport::new_tx_rx_bounded::<Frame>(capacity);
+    downstream.attach(down_in_tx, down_out_rx, None);
+    let (up_in_tx, mut up_in) = transport::new_tx_rx::<Frame>(capacity);
+    let (mut up_out, up_out_rx) = transport::new_tx_rx_bounded::<Frame>(capacity);
+    let (connected_tx, connected_rx) = transport::new_tx_rx_once();
+    upstream.attach(up_in_tx, up_out_rx, Some(connected_tx));
+
+    DefaultSpawner.spawn(async move {
+        let connected = connected_rx
+            .await
+            .unwrap_or_else(|_| Err(RSocketError::from("upstream transport is gone")));
+        if let Err(e) = connected {
+            warn!("connect upstream failed: {}", e);
+            let err = frame::Error::builder(0, 0)
+                .set_code(error::ERR_CONN_FAILED)
+                .set_data(bytes::Bytes::from(e.to_string()))
+                .build();
+            let _ = down_out.send(err).await;
+            return;
+        }
+        let ids = Arc::new(Mutex::new(StreamIds::default()));
+        DefaultSpawner.spawn({
+            let ids = ids.clone();
+            let on_frame = on_frame.clone();
+            async move {
+                while let Some(it) = up_in.next().await {
+                    let it = match forward(&ids, &on_frame, Direction::Downstream, it) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    if down_out.send(it).await.is_err() {
+                        break;
+                    }
+                }
+                debug!("upstream connection closed");
+            }
+        });
+        while let Some(it) = down_in.next().await {
+            let it = match forward(&ids, &on_frame, Direction::Upstream, it) {
+                Some(it) => it,
+                None => continue,
+            };
+            if up_out.send(it).await.is_err() {
+                break;
+            }
+        }
+        debug!("downstream connection closed");
+    });
+}
+
+#[inline]
+fn forward(
+    ids: &Mutex<StreamIds>,
+    on_frame: &Option<Arc<FnFrame>>,
+    direction: Direction,
+    frame: Frame,
+) -> Option<Frame> {
+    let sid = frame.get_stream_id();
+    let frame = match ids.lock().unwrap().rewrite(direction, frame) {
+        Some(it) => it,
+        None => {
+            debug!("drop {:?} frame of unknown stream {}", direction, sid);
+            return None;
+        }
+    };
+    Some(match on_frame {
+        Some(hook) => hook(direction, frame),
+        None => frame,
+    })
+}
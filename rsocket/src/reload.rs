@@ -0,0 +1,218 @@
+//! Replace the acceptor of a running server without dropping its listener, such as when a
+//! service reloads its configuration.
+use crate::error::RSocketError;
+use crate::payload::{Payload, SetupPayload};
+use crate::spi::{Flux, Mono, RSocket, RequestContext};
+use crate::transport::BoxedAcceptor;
+use std::error::Error;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+
+/// An acceptor of a server which can be replaced while the server runs.
+///
+/// New connections are accepted by the current acceptor. With `set_all`, the existing
+/// connections are accepted again by the new one, with the SETUP payload they connected with,
+/// and serve their next requests by the new responder. Requests in flight finish on the
+/// responder they started on.
+#[derive(Clone)]
+pub struct Reloadable {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    acceptor: RwLock<Arc<BoxedAcceptor>>,
+    accepted: Mutex<Vec<Weak<Accepted>>>,
+}
+
+/// A connection accepted by a `Reloadable`, alive as long as its responder.
+struct Accepted {
+    setup: SetupPayload,
+    requester: Arc<dyn RSocket>,
+    responder: RwLock<Box<dyn RSocket>>,
+}
+
+/// The responder of an accepted connection, which forwards to its current responder.
+struct Swappable(Arc<Accepted>);
+
+/// The requester of an accepted connection, shared by the responders it is given to.
+struct Requester(Arc<dyn RSocket>);
+
+impl Reloadable {
+    pub fn new<F>(acceptor: F) -> Reloadable
+    where
+        F: Fn(SetupPayload, Box<dyn RSocket>) -> Result<Box<dyn RSocket>, Box<dyn Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Reloadable {
+            inner: Arc::new(Inner {
+                acceptor: RwLock::new(Arc::new(acceptor)),
+                accepted: Mutex::new(vec![]),
+            }),
+        }
+    }
+
+    /// Accept the new connections by `acceptor`.
+    pub fn set<F>(&self, acceptor: F)
+    where
+        F: Fn(SetupPayload, Box<dyn RSocket>) -> Result<Box<dyn RSocket>, Box<dyn Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        *self.inner.acceptor.write().unwrap() = Arc::new(acceptor);
+    }
+
+    /// Accept the new and the existing connections by `acceptor`. An existing connection
+    /// which `acceptor` rejects keeps its responder. Returns the number of connections whose
+    /// responder was replaced.
+    pub fn set_all<F>(&self, acceptor: F) -> usize
+    where
+        F: Fn(SetupPayload, Box<dyn RSocket>) -> Result<Box<dyn RSocket>, Box<dyn Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.set(acceptor);
+        let acceptor = self.inner.acceptor.read().unwrap().clone();
+        let mut replaced = 0;
+        for accepted in self.connections() {
+            let requester = Box::new(Requester(accepted.requester.clone()));
+            match acceptor(accepted.setup.clone(), requester) {
+                Ok(responder) => {
+                    *accepted.responder.write().unwrap() = responder;
+                    replaced += 1;
+                }
+                Err(e) => warn!("keep responder of connection: {}", e),
+            }
+        }
+        replaced
+    }
+
+    /// Returns the number of open connections accepted by this.
+    pub fn get_connections(&self) -> usize {
+        self.connections().len()
+    }
+
+    pub(crate) fn accept(
+        &self,
+        setup: SetupPayload,
+        socket: Box<dyn RSocket>,
+    ) -> Result<Box<dyn RSocket>, Box<dyn Error>> {
+        let acceptor = self.inner.acceptor.read().unwrap().clone();
+        let requester: Arc<dyn RSocket> = Arc::from(socket);
+        let responder = acceptor(setup.clone(), Box::new(Requester(requester.clone())))?;
+        let accepted = Arc::new(Accepted {
+            setup,
+            requester,
+            responder: RwLock::new(responder),
+        });
+        self.inner
+            .accepted
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&accepted));
+        Ok(Box::new(Swappable(accepted)))
+    }
+
+    /// Returns the connections which are still open, forgetting the closed ones.
+    fn connections(&self) -> Vec<Arc<Accepted>> {
+        let mut accepted = self.inner.accepted.lock().unwrap();
+        accepted.retain(|it| it.strong_count() > 0);
+        accepted.iter().filter_map(|it| it.upgrade()).collect()
+    }
+}
+
+impl RSocket for Swappable {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.0.responder.read().unwrap().metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.0.responder.read().unwrap().fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.0.responder.read().unwrap().request_response(req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.0.responder.read().unwrap().request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.0.responder.read().unwrap().request_channel(reqs)
+    }
+
+    fn fire_and_forget_with_context(&self, ctx: RequestContext, req: Payload) -> Mono<()> {
+        self.0
+            .responder
+            .read()
+            .unwrap()
+            .fire_and_forget_with_context(ctx, req)
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        self.0
+            .responder
+            .read()
+            .unwrap()
+            .request_response_with_context(ctx, req)
+    }
+
+    fn request_stream_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.0
+            .responder
+            .read()
+            .unwrap()
+            .request_stream_with_context(ctx, req)
+    }
+
+    fn request_channel_with_context(
+        &self,
+        ctx: RequestContext,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.0
+            .responder
+            .read()
+            .unwrap()
+            .request_channel_with_context(ctx, reqs)
+    }
+}
+
+impl RSocket for Requester {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.0.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.0.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.0.request_response(req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.0.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.0.request_channel(reqs)
+    }
+}
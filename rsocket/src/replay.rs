@@ -0,0 +1,176 @@
+//! Hot streams which replay their recent items to new subscribers.
+//!
+//! A `ReplayTopics` keeps the last items published to each topic, as bounded by its
+//! `ReplayPolicy`, and sends them to every subscriber before the items published after it
+//! subscribed. A client which lost its connection gets the updates it missed by subscribing
+//! again, without resuming the connection. It responds to REQUEST_STREAM with the topic named
+//! by the data of the request, so each route of a router may serve topics of its own policy.
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono, RSocket};
+use futures::channel::mpsc;
+use futures::{future, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How many of the items published to a topic are replayed.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ReplayPolicy {
+    max_items: Option<usize>,
+    max_age: Option<Duration>,
+}
+
+#[derive(Clone)]
+pub struct ReplayTopics {
+    policy: ReplayPolicy,
+    topics: Arc<Mutex<HashMap<String, Topic>>>,
+}
+
+#[derive(Default)]
+struct Topic {
+    retained: VecDeque<(Instant, Payload)>,
+    subscribers: Vec<mpsc::UnboundedSender<Result<Payload, RSocketError>>>,
+}
+
+impl ReplayPolicy {
+    /// Replay the last n items.
+    pub fn last(n: usize) -> ReplayPolicy {
+        ReplayPolicy {
+            max_items: Some(n),
+            max_age: None,
+        }
+    }
+
+    /// Replay the items published within the last `max_age`.
+    pub fn within(max_age: Duration) -> ReplayPolicy {
+        ReplayPolicy {
+            max_items: None,
+            max_age: Some(max_age),
+        }
+    }
+
+    pub fn max_items(mut self, n: usize) -> Self {
+        self.max_items = Some(n);
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn get_max_items(&self) -> Option<usize> {
+        self.max_items
+    }
+
+    pub fn get_max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+}
+
+impl ReplayTopics {
+    pub fn new(policy: ReplayPolicy) -> ReplayTopics {
+        ReplayTopics {
+            policy,
+            topics: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Publish an item to the current subscribers of a topic and retain it for later ones.
+    pub fn publish(&self, topic: &str, item: Payload) {
+        let mut topics = self.topics.lock().unwrap();
+        let topic = topics.entry(topic.to_string()).or_default();
+        topic
+            .subscribers
+            .retain(|it| it.unbounded_send(Ok(item.clone())).is_ok());
+        topic.retained.push_back((Instant::now(), item));
+        self.evict(topic);
+    }
+
+    /// Complete the subscribers of a topic and forget its retained items.
+    pub fn close(&self, topic: &str) {
+        self.topics.lock().unwrap().remove(topic);
+    }
+
+    /// Subscribe to a topic, the retained items are received first.
+    pub fn subscribe(&self, topic: &str) -> Flux<Result<Payload, RSocketError>> {
+        let (tx, rx) = mpsc::unbounded();
+        let mut topics = self.topics.lock().unwrap();
+        let topic = topics.entry(topic.to_string()).or_default();
+        self.evict(topic);
+        for (_, item) in topic.retained.iter() {
+            let _ = tx.unbounded_send(Ok(item.clone()));
+        }
+        topic.subscribers.push(tx);
+        Box::pin(rx)
+    }
+
+    /// Returns the items a new subscriber of the topic would receive first.
+    pub fn retained(&self, topic: &str) -> Vec<Payload> {
+        let mut topics = self.topics.lock().unwrap();
+        match topics.get_mut(topic) {
+            Some(topic) => {
+                self.evict(topic);
+                topic.retained.iter().map(|(_, it)| it.clone()).collect()
+            }
+            None => vec![],
+        }
+    }
+
+    pub fn get_policy(&self) -> &ReplayPolicy {
+        &self.policy
+    }
+
+    fn evict(&self, topic: &mut Topic) {
+        if let Some(max_age) = self.policy.max_age {
+            let now = Instant::now();
+            while topic
+                .retained
+                .front()
+                .is_some_and(|(at, _)| now.duration_since(*at) > max_age)
+            {
+                topic.retained.pop_front();
+            }
+        }
+        if let Some(n) = self.policy.max_items {
+            while topic.retained.len() > n {
+                topic.retained.pop_front();
+            }
+        }
+    }
+}
+
+impl RSocket for ReplayTopics {
+    fn metadata_push(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, _req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, _req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        Box::pin(future::err(RSocketError::from(
+            "replay topics only support request-stream",
+        )))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        match req.data_utf8() {
+            Some(topic) => self.subscribe(topic),
+            None => Box::pin(futures::stream::iter(vec![Err(RSocketError::from(
+                "missing topic",
+            ))])),
+        }
+    }
+
+    fn request_channel(
+        &self,
+        _reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(futures::stream::iter(vec![Err(RSocketError::from(
+            "replay topics only support request-stream",
+        ))]))
+    }
+}
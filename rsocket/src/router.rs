@@ -0,0 +1,341 @@
+use crate::error::{self, ErrorKind, RSocketError};
+use crate::extension::{CompositeMetadata, RoutingMetadata};
+use crate::interceptor::composite_of;
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono, RSocket, RequestContext};
+use crate::transport::Principal;
+use futures::{future, stream, FutureExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Responder which dispatches requests to handlers by the first tag of their routing metadata.
+///
+/// A route ending with `*` matches every route with that prefix, exact routes are matched
+/// first and then the longest prefix. Requests without a matched route are sent to the
+/// fallback handler, or rejected with an APPLICATION_ERROR if there is none.
+///
+/// Routes may be added, replaced and removed while the router serves, the clones of a router
+/// share its routes. Requests in flight finish on the handler they were dispatched to.
+///
+/// Routes may be guarded by `authorize`, requests a guard denies are rejected with REJECTED.
+#[derive(Clone, Default)]
+pub struct Router {
+    table: Arc<RwLock<Table>>,
+}
+
+type FnAuthorize = Arc<dyn Fn(Option<&Principal>, &CompositeMetadata) -> bool + Send + Sync>;
+
+#[derive(Default)]
+struct Table {
+    routes: Routes<Arc<dyn RSocket>>,
+    guards: Routes<FnAuthorize>,
+    fallback: Option<Arc<dyn RSocket>>,
+}
+
+/// Values by exact routes and by route prefixes.
+struct Routes<T> {
+    exact: HashMap<String, T>,
+    prefixes: Vec<(String, T)>,
+}
+
+/// A handler which knows its own route, such as those generated by `#[route]`.
+pub trait Routed: RSocket {
+    fn route(&self) -> &str;
+}
+
+impl Router {
+    pub fn new() -> Router {
+        Router::default()
+    }
+
+    pub fn route<R>(self, route: &str, handler: R) -> Self
+    where
+        R: RSocket + 'static,
+    {
+        self.add_route(route, handler);
+        self
+    }
+
+    /// Add the handler of a route, or replace the current one. Returns true if it replaced
+    /// a handler.
+    pub fn add_route<R>(&self, route: &str, handler: R) -> bool
+    where
+        R: RSocket + 'static,
+    {
+        let handler: Arc<dyn RSocket> = Arc::new(handler);
+        self.table.write().unwrap().routes.insert(route, handler)
+    }
+
+    /// Remove the handler of a route, a prefix route is given with its `*`. Returns false if
+    /// there was no such route.
+    pub fn remove_route(&self, route: &str) -> bool {
+        self.table.write().unwrap().routes.remove(route)
+    }
+
+    /// Guard a route by `predicate`, given the principal of the connection and the metadata
+    /// of the request. Requests it denies are rejected with REJECTED.
+    ///
+    /// Guards are looked up like handlers, a guard of a prefix route covers the exact routes
+    /// under it which have none of their own. Guards stay when handlers are replaced or removed.
+    pub fn authorize<F>(self, route: &str, predicate: F) -> Self
+    where
+        F: Fn(Option<&Principal>, &CompositeMetadata) -> bool + Send + Sync + 'static,
+    {
+        self.table
+            .write()
+            .unwrap()
+            .guards
+            .insert(route, Arc::new(predicate));
+        self
+    }
+
+    pub fn handler<R>(self, handler: R) -> Self
+    where
+        R: Routed + 'static,
+    {
+        let route = handler.route().to_string();
+        self.route(&route, handler)
+    }
+
+    pub fn fallback<R>(self, handler: R) -> Self
+    where
+        R: RSocket + 'static,
+    {
+        self.table.write().unwrap().fallback = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn get_routes(&self) -> Vec<String> {
+        let table = self.table.read().unwrap();
+        let prefixes = table
+            .routes
+            .prefixes
+            .iter()
+            .map(|(it, _)| format!("{}*", it));
+        table.routes.exact.keys().cloned().chain(prefixes).collect()
+    }
+
+    /// Find the handler of a request, once its guard allows it.
+    fn find(
+        &self,
+        principal: Option<&Principal>,
+        req: &Payload,
+    ) -> Result<Arc<dyn RSocket>, RSocketError> {
+        let route = route_of(req);
+        if let Some(route) = &route {
+            let guard = self.table.read().unwrap().guards.get(route).cloned();
+            if let Some(guard) = guard {
+                let metadata = composite_of(req).unwrap_or_default();
+                if !guard(principal, &metadata) {
+                    return Err(access_denied(route));
+                }
+            }
+        }
+        self.lookup(route.as_deref())
+    }
+
+    fn lookup(&self, route: Option<&str>) -> Result<Arc<dyn RSocket>, RSocketError> {
+        let table = self.table.read().unwrap();
+        match route
+            .and_then(|it| table.routes.get(it))
+            .or(table.fallback.as_ref())
+        {
+            Some(handler) => Ok(handler.clone()),
+            None => Err(unknown_route(route)),
+        }
+    }
+
+    fn dispatch_channel<F>(
+        &self,
+        principal: Option<Principal>,
+        reqs: Flux<Result<Payload, RSocketError>>,
+        call: F,
+    ) -> Flux<Result<Payload, RSocketError>>
+    where
+        F: FnOnce(
+                Arc<dyn RSocket>,
+                Flux<Result<Payload, RSocketError>>,
+            ) -> Flux<Result<Payload, RSocketError>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        // the route is carried by the first payload of a channel.
+        let router = self.clone();
+        let results = reqs.into_future().map(move |(first, rest)| {
+            let found = match &first {
+                Some(Ok(req)) => router.find(principal.as_ref(), req),
+                _ => router.lookup(None),
+            };
+            match found {
+                Ok(handler) => {
+                    let reqs = stream::iter(first).chain(rest);
+                    call(handler, Box::pin(reqs))
+                }
+                Err(e) => Box::pin(stream::iter(Some(Err(e)))) as Flux<_>,
+            }
+        });
+        Box::pin(stream::once(results).flatten())
+    }
+}
+
+impl<T> Default for Routes<T> {
+    fn default() -> Self {
+        Routes {
+            exact: HashMap::new(),
+            prefixes: vec![],
+        }
+    }
+}
+
+impl<T> Routes<T> {
+    /// Insert the value of a route, a prefix route ends with `*`. Returns true if it replaced
+    /// a value.
+    fn insert(&mut self, route: &str, value: T) -> bool {
+        match route.strip_suffix('*') {
+            Some(prefix) => {
+                let replaced = self.remove(route);
+                self.prefixes.push((prefix.to_string(), value));
+                // longest prefix first.
+                self.prefixes
+                    .sort_by_key(|it| std::cmp::Reverse(it.0.len()));
+                replaced
+            }
+            None => self.exact.insert(route.to_string(), value).is_some(),
+        }
+    }
+
+    fn remove(&mut self, route: &str) -> bool {
+        match route.strip_suffix('*') {
+            Some(prefix) => {
+                let before = self.prefixes.len();
+                self.prefixes.retain(|(it, _)| it != prefix);
+                self.prefixes.len() < before
+            }
+            None => self.exact.remove(route).is_some(),
+        }
+    }
+
+    /// Returns the value of the exact route, or else of the longest prefix of it.
+    fn get(&self, route: &str) -> Option<&T> {
+        self.exact.get(route).or_else(|| {
+            self.prefixes
+                .iter()
+                .find(|(prefix, _)| route.starts_with(prefix.as_str()))
+                .map(|(_, it)| it)
+        })
+    }
+}
+
+impl RSocket for Router {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        match &self.table.read().unwrap().fallback {
+            Some(handler) => handler.metadata_push(req),
+            None => Box::pin(future::ready(())),
+        }
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        match self.find(None, &req) {
+            Ok(handler) => handler.fire_and_forget(req),
+            Err(e) => {
+                warn!("drop fire-and-forget request: {}", e);
+                Box::pin(future::ready(()))
+            }
+        }
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        match self.find(None, &req) {
+            Ok(handler) => handler.request_response(req),
+            Err(e) => Box::pin(future::err(e)),
+        }
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        match self.find(None, &req) {
+            Ok(handler) => handler.request_stream(req),
+            Err(e) => Box::pin(stream::iter(Some(Err(e)))),
+        }
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.dispatch_channel(None, reqs, |handler, reqs| handler.request_channel(reqs))
+    }
+
+    fn fire_and_forget_with_context(&self, ctx: RequestContext, req: Payload) -> Mono<()> {
+        match self.find(ctx.get_principal(), &req) {
+            Ok(handler) => handler.fire_and_forget_with_context(ctx, req),
+            Err(e) => {
+                warn!("drop fire-and-forget request: {}", e);
+                Box::pin(future::ready(()))
+            }
+        }
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        match self.find(ctx.get_principal(), &req) {
+            Ok(handler) => handler.request_response_with_context(ctx, req),
+            Err(e) => Box::pin(future::err(e)),
+        }
+    }
+
+    fn request_stream_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        match self.find(ctx.get_principal(), &req) {
+            Ok(handler) => handler.request_stream_with_context(ctx, req),
+            Err(e) => Box::pin(stream::iter(Some(Err(e)))),
+        }
+    }
+
+    fn request_channel_with_context(
+        &self,
+        ctx: RequestContext,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let principal = ctx.get_principal().cloned();
+        self.dispatch_channel(principal, reqs, move |handler, reqs| {
+            handler.request_channel_with_context(ctx, reqs)
+        })
+    }
+}
+
+/// Returns the first routing tag of a request.
+pub fn route_of(req: &Payload) -> Option<String> {
+    let composite = composite_of(req)?;
+    match RoutingMetadata::from_composite(&composite) {
+        Ok(it) => it.and_then(|it| it.get_tags().first().cloned()),
+        Err(e) => {
+            warn!("decode routing metadata failed: {}", e);
+            None
+        }
+    }
+}
+
+pub fn unknown_route(route: Option<&str>) -> RSocketError {
+    let msg = match route {
+        Some(route) => format!("no handler for route: {}", route),
+        None => String::from("missing routing metadata"),
+    };
+    RSocketError::from(ErrorKind::Internal(error::ERR_APPLICATION, msg))
+}
+
+pub fn access_denied(route: &str) -> RSocketError {
+    let msg = format!("access denied to route: {}", route);
+    RSocketError::from(ErrorKind::Internal(error::ERR_REJECTED, msg))
+}
+
+pub fn unsupported_interaction(route: &str) -> RSocketError {
+    let msg = format!("unsupported interaction for route: {}", route);
+    RSocketError::from(ErrorKind::Internal(error::ERR_APPLICATION, msg))
+}
@@ -0,0 +1,186 @@
+use crate::codec;
+use crate::error::RSocketError;
+use crate::extension::{
+    AuthMetadata, CompositeMetadata, MimeTypeMetadata, RoutingMetadata, TracingMetadata,
+};
+use crate::interceptor;
+use crate::mime;
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono};
+use crate::utils::RSocketResult;
+use bytes::BytesMut;
+use futures::{future, stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub use crate::router::{route_of, unknown_route, unsupported_interaction};
+
+/// Types which can be extracted from the composite metadata of a request,
+/// used by `#[metadata]` arguments of `#[route]` handlers.
+pub trait FromMetadata: Sized {
+    /// Returns None if the entry is absent.
+    fn from_metadata(composite: &CompositeMetadata) -> RSocketResult<Option<Self>>;
+}
+
+/// Decode the composite metadata of a request, or an empty one if it is not composite.
+pub fn composite_of(req: &Payload) -> CompositeMetadata {
+    interceptor::composite_of(req).unwrap_or_default()
+}
+
+/// Extract a required metadata entry.
+pub fn extract<T>(composite: &CompositeMetadata) -> RSocketResult<T>
+where
+    T: FromMetadata,
+{
+    T::from_metadata(composite)?.ok_or_else(|| {
+        RSocketError::from(format!("missing metadata: {}", std::any::type_name::<T>()))
+    })
+}
+
+impl FromMetadata for CompositeMetadata {
+    fn from_metadata(composite: &CompositeMetadata) -> RSocketResult<Option<Self>> {
+        Ok(Some(composite.clone()))
+    }
+}
+
+impl FromMetadata for RoutingMetadata {
+    fn from_metadata(composite: &CompositeMetadata) -> RSocketResult<Option<Self>> {
+        RoutingMetadata::from_composite(composite)
+    }
+}
+
+impl FromMetadata for MimeTypeMetadata {
+    fn from_metadata(composite: &CompositeMetadata) -> RSocketResult<Option<Self>> {
+        MimeTypeMetadata::from_composite(composite)
+    }
+}
+
+impl FromMetadata for AuthMetadata {
+    fn from_metadata(composite: &CompositeMetadata) -> RSocketResult<Option<Self>> {
+        match composite.find(mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0) {
+            Some(it) => {
+                AuthMetadata::decode(&mut BytesMut::from(it.get_payload().as_ref())).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl FromMetadata for TracingMetadata {
+    fn from_metadata(composite: &CompositeMetadata) -> RSocketResult<Option<Self>> {
+        match composite.find(mime::MESSAGE_X_RSOCKET_TRACING_ZIPKIN_V0) {
+            Some(it) => {
+                TracingMetadata::decode(&mut BytesMut::from(it.get_payload().as_ref())).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// An optional entry, it is None if absent but still an error if it fails to decode.
+impl<T> FromMetadata for Option<T>
+where
+    T: FromMetadata,
+{
+    fn from_metadata(composite: &CompositeMetadata) -> RSocketResult<Option<Self>> {
+        T::from_metadata(composite).map(Some)
+    }
+}
+
+/// Serialize a request, the route and the data MIME type are carried in composite metadata.
+pub fn encode_request<T>(route: &str, mime_type: &str, value: &T) -> RSocketResult<Payload>
+where
+    T: Serialize,
+{
+    let data = codec::encode(mime_type, value)?;
+    Ok(Payload::builder()
+        .set_data(data)
+        .metadata()
+        .route(route)
+        .mime_type(mime_type)?
+        .build())
+}
+
+/// Deserialize a request, returns the value and the data MIME type it was encoded with.
+pub fn decode_request<T>(req: &Payload, default_mime_type: &str) -> RSocketResult<(T, String)>
+where
+    T: DeserializeOwned,
+{
+    let mime_type = codec::data_mime_type_of(req).unwrap_or_else(|| default_mime_type.to_string());
+    let value = codec::decode_payload(req, &mime_type)?;
+    Ok((value, mime_type))
+}
+
+pub fn encode_response<T>(mime_type: &str, value: &T) -> RSocketResult<Payload>
+where
+    T: Serialize,
+{
+    let data = codec::encode(mime_type, value)?;
+    Ok(Payload::builder()
+        .set_data(data)
+        .metadata()
+        .mime_type(mime_type)?
+        .build())
+}
+
+pub fn encode_mono<T>(
+    res: Mono<RSocketResult<T>>,
+    mime_type: String,
+) -> Mono<RSocketResult<Payload>>
+where
+    T: Serialize + 'static,
+{
+    Box::pin(async move { res.await.and_then(|v| encode_response(&mime_type, &v)) })
+}
+
+pub fn encode_flux<T>(
+    res: Flux<RSocketResult<T>>,
+    mime_type: String,
+) -> Flux<RSocketResult<Payload>>
+where
+    T: Serialize + 'static,
+{
+    Box::pin(res.map(move |it| it.and_then(|v| encode_response(&mime_type, &v))))
+}
+
+pub fn decode_mono<T>(
+    res: Mono<RSocketResult<Payload>>,
+    mime_type: String,
+) -> Mono<RSocketResult<T>>
+where
+    T: DeserializeOwned + 'static,
+{
+    Box::pin(async move {
+        res.await
+            .and_then(|it| codec::decode_payload(&it, &mime_type))
+    })
+}
+
+pub fn decode_flux<T>(
+    res: Flux<RSocketResult<Payload>>,
+    mime_type: String,
+) -> Flux<RSocketResult<T>>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+{
+    Box::pin(res.map(move |it| it.and_then(|res| codec::decode_payload(&res, &mime_type))))
+}
+
+pub fn error_mono<T>(e: RSocketError) -> Mono<RSocketResult<T>>
+where
+    T: Send + Sync + 'static,
+{
+    Box::pin(future::err(e))
+}
+
+pub fn error_flux<T>(e: RSocketError) -> Flux<RSocketResult<T>>
+where
+    T: Send + Sync + 'static,
+{
+    Box::pin(stream::iter(Some(Err(e))))
+}
+
+pub fn error_fnf(e: RSocketError) -> Mono<()> {
+    warn!("drop fire-and-forget request: {}", e);
+    Box::pin(future::ready(()))
+}
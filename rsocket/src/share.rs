@@ -0,0 +1,323 @@
+//! Multicast one upstream stream to many local subscribers.
+//!
+//! A `Shared` stream requests its upstream when the first subscriber subscribes and cancels it
+//! when the last one leaves. Subscribers see the items produced after they subscribed, each
+//! buffers them by its own `BufferPolicy` so a slow subscriber does not stall the others.
+//! `SharedStreams` collapses identical requests of a requester into one shared stream.
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::runtime::{DefaultSpawner, Spawner};
+use crate::spi::{Flux, RSocket};
+use bytes::Bytes;
+use futures::future::{self, AbortHandle};
+use futures::{Stream, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+
+/// How a subscriber buffers items it did not consume yet.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BufferPolicy {
+    Unbounded,
+    /// Keep at most n items, dropping the oldest ones.
+    DropOldest(usize),
+    /// Keep at most n items, dropping the incoming ones.
+    DropNewest(usize),
+    /// Keep at most n items, terminate the subscriber with an error on overflow.
+    Error(usize),
+}
+
+pub struct Shared<T> {
+    state: Arc<Mutex<State<T>>>,
+}
+
+/// Shares the streams of identical requests, by data and metadata, while they are active.
+#[derive(Clone)]
+pub struct SharedStreams {
+    requester: Arc<dyn RSocket>,
+    streams: Arc<Mutex<HashMap<Key, Shared<Payload>>>>,
+}
+
+pub trait ShareExt<T> {
+    fn share(self) -> Shared<T>;
+}
+
+type Key = (Option<Bytes>, Option<Bytes>);
+type OnTerminate = Box<dyn FnOnce() + Send>;
+
+struct State<T> {
+    upstream: Option<Flux<Result<T, RSocketError>>>,
+    abort: Option<AbortHandle>,
+    // None while active, the error if the upstream failed.
+    terminal: Option<Option<RSocketError>>,
+    subscribers: HashMap<u64, Subscriber<T>>,
+    next_id: u64,
+    on_terminate: Option<OnTerminate>,
+}
+
+struct Subscriber<T> {
+    queue: VecDeque<Result<T, RSocketError>>,
+    policy: BufferPolicy,
+    done: bool,
+    waker: Option<Waker>,
+}
+
+struct Subscription<T> {
+    id: u64,
+    state: Arc<Mutex<State<T>>>,
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T> Shared<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    pub fn new(upstream: Flux<Result<T, RSocketError>>) -> Shared<T> {
+        Shared {
+            state: Arc::new(Mutex::new(State {
+                upstream: Some(upstream),
+                abort: None,
+                terminal: None,
+                subscribers: HashMap::new(),
+                next_id: 0,
+                on_terminate: None,
+            })),
+        }
+    }
+
+    /// Subscribe to the items produced from now on. Subscribers of a terminated stream
+    /// only receive its error, if any.
+    pub fn subscribe(&self, policy: BufferPolicy) -> Flux<Result<T, RSocketError>> {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        let mut subscriber = Subscriber {
+            queue: VecDeque::new(),
+            policy,
+            done: false,
+            waker: None,
+        };
+        if let Some(terminal) = &state.terminal {
+            subscriber.queue.extend(terminal.clone().map(Err));
+            subscriber.done = true;
+        }
+        state.subscribers.insert(id, subscriber);
+        if let Some(upstream) = state.upstream.take() {
+            let (task, abort) = future::abortable(pump(upstream, Arc::downgrade(&self.state)));
+            state.abort = Some(abort);
+            DefaultSpawner.spawn(async move {
+                let _ = task.await;
+            });
+        }
+        Box::pin(Subscription {
+            id,
+            state: self.state.clone(),
+        })
+    }
+
+    /// Returns the number of current subscribers.
+    pub fn subscribers(&self) -> usize {
+        self.state.lock().unwrap().subscribers.len()
+    }
+
+    /// Returns true if the upstream completed, failed or was cancelled.
+    pub fn is_terminated(&self) -> bool {
+        self.state.lock().unwrap().terminal.is_some()
+    }
+
+    fn on_terminate(&self, f: OnTerminate) {
+        self.state.lock().unwrap().on_terminate = Some(f);
+    }
+}
+
+impl<T, S> ShareExt<T> for S
+where
+    T: Clone + Send + Sync + 'static,
+    S: Stream<Item = Result<T, RSocketError>> + Send + Sync + 'static,
+{
+    fn share(self) -> Shared<T> {
+        Shared::new(Box::pin(self))
+    }
+}
+
+impl SharedStreams {
+    pub fn new<R>(requester: R) -> SharedStreams
+    where
+        R: RSocket + 'static,
+    {
+        SharedStreams {
+            requester: Arc::new(requester),
+            streams: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Subscribe to the stream of a request, which is only requested if no identical
+    /// request is active.
+    pub fn request_stream(
+        &self,
+        req: Payload,
+        policy: BufferPolicy,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let key = (req.data().clone(), req.metadata().clone());
+        let mut streams = self.streams.lock().unwrap();
+        if let Some(shared) = streams.get(&key).filter(|it| !it.is_terminated()) {
+            return shared.subscribe(policy);
+        }
+        let shared = Shared::new(self.requester.request_stream(req));
+        let weak = Arc::downgrade(&self.streams);
+        let removed = key.clone();
+        shared.on_terminate(Box::new(move || {
+            if let Some(streams) = weak.upgrade() {
+                let mut streams = streams.lock().unwrap();
+                if streams.get(&removed).is_some_and(|it| it.is_terminated()) {
+                    streams.remove(&removed);
+                }
+            }
+        }));
+        streams.insert(key, shared.clone());
+        drop(streams);
+        shared.subscribe(policy)
+    }
+
+    /// Returns the number of active shared streams.
+    pub fn active(&self) -> usize {
+        self.streams.lock().unwrap().len()
+    }
+}
+
+async fn pump<T>(mut upstream: Flux<Result<T, RSocketError>>, state: Weak<Mutex<State<T>>>)
+where
+    T: Clone,
+{
+    loop {
+        let next = upstream.next().await;
+        let shared = match state.upgrade() {
+            Some(it) => it,
+            None => return,
+        };
+        let mut locked = shared.lock().unwrap();
+        let on_terminate = match next {
+            Some(Ok(item)) => {
+                for subscriber in locked.subscribers.values_mut() {
+                    subscriber.push(Ok(item.clone()));
+                }
+                continue;
+            }
+            Some(Err(e)) => locked.terminate(Some(e)),
+            None => locked.terminate(None),
+        };
+        drop(locked);
+        return finish(on_terminate);
+    }
+}
+
+/// Run the termination hook once the lock of the stream is released.
+#[inline]
+fn finish(on_terminate: Option<OnTerminate>) {
+    if let Some(f) = on_terminate {
+        f();
+    }
+}
+
+impl<T> State<T> {
+    /// Terminate all subscribers, returns the termination hook to run.
+    #[must_use]
+    fn terminate(&mut self, err: Option<RSocketError>) -> Option<OnTerminate> {
+        if self.terminal.is_some() {
+            return None;
+        }
+        for subscriber in self.subscribers.values_mut() {
+            if let Some(e) = &err {
+                subscriber.push(Err(e.clone()));
+            }
+            subscriber.finish();
+        }
+        self.terminal = Some(err);
+        self.on_terminate.take()
+    }
+}
+
+impl<T> Subscriber<T> {
+    fn push(&mut self, item: Result<T, RSocketError>) {
+        if self.done {
+            return;
+        }
+        let full = match self.policy {
+            BufferPolicy::Unbounded => false,
+            BufferPolicy::DropOldest(n) | BufferPolicy::DropNewest(n) | BufferPolicy::Error(n) => {
+                self.queue.len() >= n
+            }
+        };
+        if full && item.is_ok() {
+            match self.policy {
+                BufferPolicy::DropOldest(_) => {
+                    self.queue.pop_front();
+                }
+                BufferPolicy::DropNewest(_) => return,
+                _ => {
+                    self.queue.push_back(Err(RSocketError::from(
+                        "shared stream subscriber overflowed",
+                    )));
+                    self.finish();
+                    return;
+                }
+            }
+        }
+        self.queue.push_back(item);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.done = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = Result<T, RSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.state.lock().unwrap();
+        let subscriber = match state.subscribers.get_mut(&self.id) {
+            Some(it) => it,
+            None => return Poll::Ready(None),
+        };
+        if let Some(item) = subscriber.queue.pop_front() {
+            return Poll::Ready(Some(item));
+        }
+        if subscriber.done {
+            return Poll::Ready(None);
+        }
+        subscriber.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.subscribers.remove(&self.id);
+        if !state.subscribers.is_empty() || state.terminal.is_some() {
+            return;
+        }
+        // the last subscriber left, cancel the upstream.
+        if let Some(abort) = state.abort.take() {
+            abort.abort();
+            let on_terminate = state.terminate(None);
+            drop(state);
+            finish(on_terminate);
+        }
+    }
+}
@@ -1,14 +1,22 @@
 use crate::error::{self, ErrorKind, RSocketError};
+#[cfg(feature = "extension")]
+use crate::extension::CompositeMetadata;
 use crate::frame;
+use crate::mime;
 use crate::payload::Payload;
+use crate::transport::{ConnectionParams, PeerInfo, Principal};
 use crate::utils::RSocketResult;
 
+use bytes::{Bytes, BytesMut};
+use futures::channel::mpsc as bounded;
 use futures::future;
+use futures::task::{Context, Poll};
 use futures::{Sink, SinkExt, Stream, StreamExt};
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::result::Result;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tokio::sync::mpsc;
 
 pub type Mono<T> = Pin<Box<dyn Send + Sync + Future<Output = T>>>;
@@ -23,6 +31,217 @@ pub trait RSocket: Sync + Send {
         &self,
         reqs: Flux<Result<Payload, RSocketError>>,
     ) -> Flux<Result<Payload, RSocketError>>;
+
+    /// Handle a request together with its context, which is ignored by default. Responders
+    /// override these to read the context, wrappers forward them to keep it.
+    fn fire_and_forget_with_context(&self, _ctx: RequestContext, req: Payload) -> Mono<()> {
+        self.fire_and_forget(req)
+    }
+
+    fn request_response_with_context(
+        &self,
+        _ctx: RequestContext,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        self.request_response(req)
+    }
+
+    fn request_stream_with_context(
+        &self,
+        _ctx: RequestContext,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.request_stream(req)
+    }
+
+    fn request_channel_with_context(
+        &self,
+        _ctx: RequestContext,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.request_channel(reqs)
+    }
+}
+
+/// Attributes shared by all requests of a connection.
+#[derive(Debug, Clone, Default)]
+pub struct Attributes {
+    inner: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl Attributes {
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.inner.read().unwrap().get(key).cloned()
+    }
+
+    pub fn set(&self, key: &str, value: &str) {
+        self.inner
+            .write()
+            .unwrap()
+            .insert(key.to_string(), value.to_string());
+    }
+
+    pub fn remove(&self, key: &str) -> Option<String> {
+        self.inner.write().unwrap().remove(key)
+    }
+}
+
+/// What a responder knows about a request besides its payload.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    stream_id: u32,
+    peer: PeerInfo,
+    principal: Option<Principal>,
+    params: Option<ConnectionParams>,
+    attributes: Attributes,
+    #[cfg(feature = "extension")]
+    metadata: Option<CompositeMetadata>,
+}
+
+impl RequestContext {
+    pub(crate) fn new(
+        stream_id: u32,
+        peer: PeerInfo,
+        principal: Option<Principal>,
+        params: Option<ConnectionParams>,
+        attributes: Attributes,
+        metadata: Option<&Bytes>,
+    ) -> RequestContext {
+        #[cfg(feature = "extension")]
+        let metadata = match (&params, metadata) {
+            (Some(it), Some(b))
+                if it.get_metadata_mime_type() == mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0 =>
+            {
+                match CompositeMetadata::decode(&mut BytesMut::from(b.as_ref())) {
+                    Ok(it) => Some(it),
+                    Err(e) => {
+                        debug!("decode composite metadata failed: {}", e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        RequestContext {
+            stream_id,
+            peer,
+            principal,
+            params,
+            attributes,
+            #[cfg(feature = "extension")]
+            metadata,
+        }
+    }
+
+    pub fn get_stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    /// Returns the remote end of the connection, including the identity it authenticated with.
+    pub fn get_peer(&self) -> &PeerInfo {
+        &self.peer
+    }
+
+    /// Returns the parameters negotiated by SETUP.
+    pub fn get_params(&self) -> Option<&ConnectionParams> {
+        self.params.as_ref()
+    }
+
+    pub fn get_attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    /// Returns the decoded metadata of the request, if the connection uses composite metadata.
+    #[cfg(feature = "extension")]
+    pub fn get_metadata(&self) -> Option<&CompositeMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Returns who the connection authenticated as, see `ServerBuilder::authenticator`.
+    pub fn get_principal(&self) -> Option<&Principal> {
+        self.principal.as_ref()
+    }
+}
+
+const RESPONSE_SINK_BUFFER: usize = 16;
+
+/// Sink of the responses of a channel written by `sink_channel`, payloads are taken
+/// as far as the requester requests them. Closing or dropping it completes the responses.
+pub struct ResponseSink {
+    tx: bounded::Sender<Result<Payload, RSocketError>>,
+}
+
+impl ResponseSink {
+    /// Terminate the responses with `e`.
+    pub async fn error(mut self, e: RSocketError) {
+        if self.tx.send(Err(e)).await.is_err() {
+            debug!("channel responses are dropped");
+        }
+    }
+}
+
+impl Sink<Payload> for ResponseSink {
+    type Error = RSocketError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.tx
+            .poll_ready(cx)
+            .map_err(|_| RSocketError::from("REQUEST_CHANNEL is closed"))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Payload) -> Result<(), Self::Error> {
+        self.tx
+            .start_send(Ok(item))
+            .map_err(|_| RSocketError::from("REQUEST_CHANNEL is closed"))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.tx)
+            .poll_flush(cx)
+            .map_err(|_| RSocketError::from("REQUEST_CHANNEL is closed"))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.tx)
+            .poll_close(cx)
+            .map_err(|_| RSocketError::from("REQUEST_CHANNEL is closed"))
+    }
+}
+
+/// Respond a channel by writing into a sink instead of returning a stream.
+///
+/// `handler` gets the incoming payloads and the sink of the responses, it runs as long as the
+/// responses are consumed and is dropped with them.
+///
+/// ```
+/// use rsocket_rust::error::RSocketError;
+/// use rsocket_rust::prelude::*;
+///
+/// fn upper(reqs: Flux<Result<Payload, RSocketError>>) -> Flux<Result<Payload, RSocketError>> {
+///     sink_channel(reqs, |mut reqs, mut responses| async move {
+///         while let Some(Ok(req)) = reqs.next().await {
+///             let data = req.data_utf8().unwrap_or_default().to_uppercase();
+///             let res = Payload::builder().set_data_utf8(&data).build();
+///             if responses.send(res).await.is_err() {
+///                 break;
+///             }
+///         }
+///     })
+/// }
+/// ```
+pub fn sink_channel<F, Fut>(
+    reqs: Flux<Result<Payload, RSocketError>>,
+    handler: F,
+) -> Flux<Result<Payload, RSocketError>>
+where
+    F: FnOnce(Flux<Result<Payload, RSocketError>>, ResponseSink) -> Fut,
+    Fut: Future<Output = ()> + Send + Sync + 'static,
+{
+    let (tx, rx) = bounded::channel(RESPONSE_SINK_BUFFER);
+    let running = handler(reqs, ResponseSink { tx });
+    // the handler is polled together with its responses, it never yields any of them itself.
+    let running = futures::stream::once(running).filter_map(|()| future::ready(None));
+    Box::pin(futures::stream::select(rx, running))
 }
 
 pub struct EchoRSocket;
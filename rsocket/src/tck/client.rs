@@ -0,0 +1,261 @@
+use super::script::{Assertion, ClientTest, Interaction, Step};
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::runtime::{DefaultSpawner, Spawner};
+use crate::spi::{Flux, RSocket};
+use futures::future::{self, AbortHandle};
+use futures::{stream, FutureExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::{self, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Runs TCK client tests against a requester.
+#[derive(Debug, Clone)]
+pub struct ClientDriver {
+    timeout: Duration,
+}
+
+/// The outcome of a test, it is ok if a test passed as expected or failed as expected.
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    name: String,
+    ok: bool,
+    failure: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Received {
+    values: Vec<(String, String)>,
+    completed: bool,
+    error: Option<String>,
+    canceled: bool,
+}
+
+/// Values are only received as far as they were requested, like a reactive streams subscriber.
+struct Subscriber {
+    received: Arc<Mutex<Received>>,
+    permits: Arc<Semaphore>,
+    abort: AbortHandle,
+}
+
+impl TestResult {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.ok
+    }
+
+    /// Returns why the test failed, also for tests which are expected to fail.
+    pub fn get_failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+}
+
+impl Default for ClientDriver {
+    fn default() -> ClientDriver {
+        ClientDriver {
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ClientDriver {
+    pub fn new() -> ClientDriver {
+        ClientDriver::default()
+    }
+
+    /// Set how long `await%%terminal` waits, 10 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn run(&self, rsocket: &dyn RSocket, test: &ClientTest) -> TestResult {
+        let mut subscribers = HashMap::new();
+        let mut outcome = Ok(());
+        for step in test.get_steps() {
+            outcome = self.step(rsocket, &mut subscribers, step).await;
+            if outcome.is_err() {
+                break;
+            }
+        }
+        for subscriber in subscribers.values() {
+            subscriber.abort.abort();
+        }
+        let failure = outcome.err();
+        TestResult {
+            name: test.get_name().to_string(),
+            ok: failure.is_none() == test.is_expected_to_pass(),
+            failure,
+        }
+    }
+
+    pub async fn run_all(&self, rsocket: &dyn RSocket, tests: &[ClientTest]) -> Vec<TestResult> {
+        let mut results = vec![];
+        for test in tests {
+            results.push(self.run(rsocket, test).await);
+        }
+        results
+    }
+
+    async fn step(
+        &self,
+        rsocket: &dyn RSocket,
+        subscribers: &mut HashMap<String, Subscriber>,
+        step: &Step,
+    ) -> Result<(), String> {
+        match step {
+            Step::Subscribe {
+                interaction,
+                id,
+                data,
+                metadata,
+            } => {
+                let req = Payload::builder()
+                    .set_data_utf8(data)
+                    .set_metadata_utf8(metadata)
+                    .build();
+                let responses: Flux<Result<Payload, RSocketError>> = match interaction {
+                    Interaction::RequestResponse => {
+                        Box::pin(stream::once(rsocket.request_response(req)))
+                    }
+                    Interaction::RequestStream => rsocket.request_stream(req),
+                    Interaction::FireAndForget => Box::pin(
+                        stream::once(rsocket.fire_and_forget(req))
+                            .filter_map(|_| future::ready(None)),
+                    ),
+                    Interaction::RequestChannel => {
+                        return Err(String::from("channel tests are not supported"))
+                    }
+                };
+                subscribers.insert(id.clone(), Subscriber::subscribe(responses));
+                Ok(())
+            }
+            Step::Request { n, id } => {
+                find(subscribers, id)?.permits.add_permits(*n);
+                Ok(())
+            }
+            Step::Cancel { id } => {
+                let subscriber = find(subscribers, id)?;
+                subscriber.abort.abort();
+                subscriber.received.lock().unwrap().canceled = true;
+                Ok(())
+            }
+            Step::AwaitTerminal { id } => {
+                let subscriber = find(subscribers, id)?;
+                wait_until(self.timeout, || {
+                    let received = subscriber.received.lock().unwrap();
+                    received.completed || received.error.is_some()
+                })
+                .await
+                .map_err(|_| format!("{} did not terminate", id))
+            }
+            Step::AwaitAtLeast { id, n, timeout } => {
+                let subscriber = find(subscribers, id)?;
+                wait_until(*timeout, || {
+                    subscriber.received.lock().unwrap().values.len() >= *n
+                })
+                .await
+                .map_err(|_| format!("{} did not receive {} values", id, n))
+            }
+            Step::AwaitNoEvents { id, duration } => {
+                let subscriber = find(subscribers, id)?;
+                let before = subscriber.events();
+                time::delay_for(*duration).await;
+                if subscriber.events() == before {
+                    Ok(())
+                } else {
+                    Err(format!("{} received events", id))
+                }
+            }
+            Step::Assert { id, assertion } => {
+                let subscriber = find(subscribers, id)?;
+                let received = subscriber.received.lock().unwrap();
+                let ok = match assertion {
+                    Assertion::NoError => received.error.is_none(),
+                    Assertion::Error => received.error.is_some(),
+                    Assertion::Completed => received.completed,
+                    Assertion::NoCompleted => !received.completed,
+                    Assertion::Canceled => received.canceled,
+                    Assertion::Received(values) => &received.values == values,
+                    Assertion::ReceivedN(n) => received.values.len() == *n,
+                    Assertion::ReceivedAtLeast(n) => received.values.len() >= *n,
+                };
+                if ok {
+                    Ok(())
+                } else {
+                    Err(format!("{:?} of {} failed: {:?}", assertion, id, *received))
+                }
+            }
+        }
+    }
+}
+
+impl Subscriber {
+    fn subscribe(mut responses: Flux<Result<Payload, RSocketError>>) -> Subscriber {
+        let received = Arc::new(Mutex::new(Received::default()));
+        let permits = Arc::new(Semaphore::new(0));
+        let (task, abort) = future::abortable({
+            let received = received.clone();
+            let permits = permits.clone();
+            async move {
+                while let Some(next) = responses.next().await {
+                    match next {
+                        Ok(it) => {
+                            // values wait for demand, terminal signals do not.
+                            permits.acquire().await.forget();
+                            let data = it.data_utf8().unwrap_or_default().to_string();
+                            let metadata = it.metadata_utf8().unwrap_or_default().to_string();
+                            received.lock().unwrap().values.push((data, metadata));
+                        }
+                        Err(e) => {
+                            received.lock().unwrap().error = Some(e.to_string());
+                            return;
+                        }
+                    }
+                }
+                received.lock().unwrap().completed = true;
+            }
+        });
+        DefaultSpawner.spawn(task.map(|_| ()));
+        Subscriber {
+            received,
+            permits,
+            abort,
+        }
+    }
+
+    fn events(&self) -> usize {
+        let received = self.received.lock().unwrap();
+        received.values.len() + received.completed as usize + received.error.is_some() as usize
+    }
+}
+
+fn find<'a>(
+    subscribers: &'a HashMap<String, Subscriber>,
+    id: &str,
+) -> Result<&'a Subscriber, String> {
+    subscribers
+        .get(id)
+        .ok_or_else(|| format!("unknown subscriber {}", id))
+}
+
+async fn wait_until<F>(timeout: Duration, done: F) -> Result<(), ()>
+where
+    F: Fn() -> bool,
+{
+    let deadline = Instant::now() + timeout;
+    while !done() {
+        if Instant::now() >= deadline {
+            return Err(());
+        }
+        time::delay_for(POLL_INTERVAL).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::error::{self, ErrorKind, RSocketError};
+use crate::payload::Payload;
+use crate::spi::Flux;
+use futures::stream;
+use futures::StreamExt;
+use serde_json::Value;
+
+/// A response written as a marble diagram, e.g. `-a-b-|&&{"a":{"hello":"world"}}`.
+///
+/// `-` is a tick and ignored, a letter emits a value, `|` completes and `#` fails. An argument
+/// maps a letter to its data and metadata, other letters emit themselves as data.
+/// Without a terminal signal the stream never completes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marble {
+    signals: Vec<Signal>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Signal {
+    Next(String, String),
+    Complete,
+    Error,
+}
+
+impl Marble {
+    pub fn parse(src: &str) -> Result<Marble, RSocketError> {
+        let mut parts = src.splitn(2, "&&");
+        let diagram = parts.next().unwrap_or_default();
+        let args = match parts.next() {
+            Some(it) => serde_json::from_str::<Value>(it)
+                .map_err(|e| RSocketError::from(format!("invalid marble arguments: {}", e)))?,
+            None => Value::Null,
+        };
+        let mut signals = vec![];
+        for c in diagram.chars() {
+            match c {
+                '-' => (),
+                '|' => {
+                    signals.push(Signal::Complete);
+                    break;
+                }
+                '#' => {
+                    signals.push(Signal::Error);
+                    break;
+                }
+                c if c.is_alphanumeric() => {
+                    let key = c.to_string();
+                    let next = match args.get(&key).and_then(|it| it.as_object()) {
+                        Some(kv) => match kv.iter().next() {
+                            Some((data, metadata)) => Signal::Next(
+                                data.clone(),
+                                metadata.as_str().unwrap_or_default().to_string(),
+                            ),
+                            None => Signal::Next(key, String::new()),
+                        },
+                        None => Signal::Next(key, String::new()),
+                    };
+                    signals.push(next);
+                }
+                c => {
+                    return Err(RSocketError::from(format!(
+                        "unsupported marble signal: {}",
+                        c
+                    )))
+                }
+            }
+        }
+        Ok(Marble { signals })
+    }
+
+    pub fn get_signals(&self) -> &[Signal] {
+        &self.signals
+    }
+
+    pub fn to_flux(&self) -> Flux<Result<Payload, RSocketError>> {
+        let mut results = vec![];
+        let mut terminated = false;
+        for signal in &self.signals {
+            match signal {
+                Signal::Next(data, metadata) => results.push(Ok(Payload::builder()
+                    .set_data_utf8(data)
+                    .set_metadata_utf8(metadata)
+                    .build())),
+                Signal::Complete => terminated = true,
+                Signal::Error => {
+                    results.push(Err(RSocketError::from(ErrorKind::Internal(
+                        error::ERR_APPLICATION,
+                        String::from("marble error"),
+                    ))));
+                    terminated = true;
+                }
+            }
+        }
+        let values = stream::iter(results);
+        if terminated {
+            Box::pin(values)
+        } else {
+            Box::pin(values.chain(stream::pending()))
+        }
+    }
+}
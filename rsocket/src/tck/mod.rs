@@ -0,0 +1,16 @@
+//! Driver for the RSocket Technology Compatibility Kit.
+//!
+//! - `parse_client_script` reads the tests of a TCK client script and `ClientDriver` runs them
+//!   against any `RSocket`, usually a connected client.
+//! - `TckResponder` answers requests with the marbles of a TCK server script.
+//!
+//! Channel tests of client scripts are not supported yet.
+mod client;
+mod marble;
+mod script;
+mod server;
+
+pub use client::{ClientDriver, TestResult};
+pub use marble::{Marble, Signal};
+pub use script::{parse_client_script, Assertion, ClientTest, Interaction, Step};
+pub use server::TckResponder;
@@ -0,0 +1,214 @@
+use crate::error::RSocketError;
+use std::time::Duration;
+
+const SEPARATOR: &str = "%%";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Interaction {
+    RequestResponse,
+    RequestStream,
+    FireAndForget,
+    RequestChannel,
+}
+
+/// One command of a client test, subscribers are referred to by their id.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Subscribe {
+        interaction: Interaction,
+        id: String,
+        data: String,
+        metadata: String,
+    },
+    Request {
+        n: usize,
+        id: String,
+    },
+    Cancel {
+        id: String,
+    },
+    AwaitTerminal {
+        id: String,
+    },
+    AwaitAtLeast {
+        id: String,
+        n: usize,
+        timeout: Duration,
+    },
+    AwaitNoEvents {
+        id: String,
+        duration: Duration,
+    },
+    Assert {
+        id: String,
+        assertion: Assertion,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Assertion {
+    NoError,
+    Error,
+    Completed,
+    NoCompleted,
+    Canceled,
+    /// The exact data and metadata of every received value.
+    Received(Vec<(String, String)>),
+    ReceivedN(usize),
+    ReceivedAtLeast(usize),
+}
+
+/// A named test of a client script, which is expected to pass unless marked with `fail`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientTest {
+    name: String,
+    pass: bool,
+    steps: Vec<Step>,
+}
+
+impl ClientTest {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_expected_to_pass(&self) -> bool {
+        self.pass
+    }
+
+    pub fn get_steps(&self) -> &[Step] {
+        &self.steps
+    }
+}
+
+impl Interaction {
+    pub(crate) fn parse(s: &str) -> Option<Interaction> {
+        match s {
+            "rr" => Some(Interaction::RequestResponse),
+            "rs" | "sub" => Some(Interaction::RequestStream),
+            "fnf" => Some(Interaction::FireAndForget),
+            "channel" => Some(Interaction::RequestChannel),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the tests of a TCK client script. Tests start with a `!` line and the script ends
+/// at `EOF` or at its last line.
+pub fn parse_client_script(src: &str) -> Result<Vec<ClientTest>, RSocketError> {
+    let mut tests: Vec<ClientTest> = vec![];
+    for (i, line) in src.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "EOF" {
+            break;
+        }
+        if line == "!" {
+            tests.push(ClientTest {
+                name: format!("test{}", tests.len()),
+                pass: true,
+                steps: vec![],
+            });
+            continue;
+        }
+        let test = match tests.last_mut() {
+            Some(it) => it,
+            None => return Err(invalid(i, "command outside of a test")),
+        };
+        let args: Vec<&str> = line.split(SEPARATOR).collect();
+        match args[0] {
+            "name" => test.name = arg(&args, 1, i)?.to_string(),
+            "pass" => test.pass = true,
+            "fail" => test.pass = false,
+            _ => test.steps.push(parse_step(&args, i)?),
+        }
+    }
+    Ok(tests)
+}
+
+fn parse_step(args: &[&str], i: usize) -> Result<Step, RSocketError> {
+    match args[0] {
+        "subscribe" => {
+            let interaction = arg(args, 1, i)?;
+            let interaction = match Interaction::parse(interaction) {
+                Some(Interaction::RequestChannel) => {
+                    return Err(invalid(i, "channel tests are not supported"))
+                }
+                Some(it) => it,
+                None => return Err(invalid(i, "unknown interaction")),
+            };
+            Ok(Step::Subscribe {
+                interaction,
+                id: arg(args, 2, i)?.to_string(),
+                data: arg(args, 3, i)?.to_string(),
+                metadata: arg(args, 4, i)?.to_string(),
+            })
+        }
+        "request" => Ok(Step::Request {
+            n: number(args, 1, i)?,
+            id: arg(args, 2, i)?.to_string(),
+        }),
+        "cancel" => Ok(Step::Cancel {
+            id: arg(args, 1, i)?.to_string(),
+        }),
+        "await" => {
+            let id = arg(args, 2, i)?.to_string();
+            match arg(args, 1, i)? {
+                "terminal" => Ok(Step::AwaitTerminal { id }),
+                "atLeast" => Ok(Step::AwaitAtLeast {
+                    id,
+                    n: number(args, 3, i)?,
+                    timeout: Duration::from_millis(number(args, 4, i)? as u64),
+                }),
+                "no_events" => Ok(Step::AwaitNoEvents {
+                    id,
+                    duration: Duration::from_millis(number(args, 3, i)? as u64),
+                }),
+                _ => Err(invalid(i, "unknown await")),
+            }
+        }
+        "assert" => {
+            let id = arg(args, 2, i)?.to_string();
+            let assertion = match arg(args, 1, i)? {
+                "no_error" => Assertion::NoError,
+                "error" => Assertion::Error,
+                "completed" => Assertion::Completed,
+                "no_completed" => Assertion::NoCompleted,
+                "canceled" => Assertion::Canceled,
+                "received" => Assertion::Received(
+                    arg(args, 3, i)?
+                        .split("&&")
+                        .map(|it| {
+                            let mut kv = it.splitn(2, ',');
+                            let data = kv.next().unwrap_or_default().to_string();
+                            let metadata = kv.next().unwrap_or_default().to_string();
+                            (data, metadata)
+                        })
+                        .collect(),
+                ),
+                "received_n" => Assertion::ReceivedN(number(args, 3, i)?),
+                "received_at_least" => Assertion::ReceivedAtLeast(number(args, 3, i)?),
+                _ => return Err(invalid(i, "unknown assertion")),
+            };
+            Ok(Step::Assert { id, assertion })
+        }
+        _ => Err(invalid(i, "unknown command")),
+    }
+}
+
+fn arg<'a>(args: &[&'a str], n: usize, i: usize) -> Result<&'a str, RSocketError> {
+    args.get(n)
+        .copied()
+        .ok_or_else(|| invalid(i, "missing argument"))
+}
+
+fn number(args: &[&str], n: usize, i: usize) -> Result<usize, RSocketError> {
+    arg(args, n, i)?
+        .parse()
+        .map_err(|_| invalid(i, "invalid number"))
+}
+
+fn invalid(i: usize, msg: &str) -> RSocketError {
+    RSocketError::from(format!("line {}: {}", i + 1, msg))
+}
@@ -0,0 +1,116 @@
+use super::marble::{Marble, Signal};
+use super::script::Interaction;
+use crate::error::{self, ErrorKind, RSocketError};
+use crate::payload::Payload;
+use crate::spi::{Flux, Mono, RSocket};
+use futures::{future, stream, FutureExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+type Key = (Interaction, String, String);
+
+/// Responder of a TCK server script, every line maps the data and metadata of a request to
+/// a marble, e.g. `rs%%a%%b%%---a-b-|`.
+#[derive(Debug, Clone, Default)]
+pub struct TckResponder {
+    marbles: Arc<HashMap<Key, Marble>>,
+}
+
+impl TckResponder {
+    pub fn parse(src: &str) -> Result<TckResponder, RSocketError> {
+        let mut marbles = HashMap::new();
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line == "EOF" {
+                continue;
+            }
+            let args: Vec<&str> = line.splitn(4, "%%").collect();
+            let interaction = Interaction::parse(args[0]).ok_or_else(|| {
+                RSocketError::from(format!("line {}: unknown interaction", i + 1))
+            })?;
+            if args.len() < 3 {
+                return Err(RSocketError::from(format!(
+                    "line {}: missing argument",
+                    i + 1
+                )));
+            }
+            // responses of fire and forget are never sent.
+            let marble = Marble::parse(args.get(3).copied().unwrap_or("|"))?;
+            marbles.insert(
+                (interaction, args[1].to_string(), args[2].to_string()),
+                marble,
+            );
+        }
+        Ok(TckResponder {
+            marbles: Arc::new(marbles),
+        })
+    }
+
+    fn request_channel_with(&self, req: &Payload) -> Flux<Result<Payload, RSocketError>> {
+        match self.find(Interaction::RequestChannel, req) {
+            Ok(it) => it.to_flux(),
+            Err(e) => Box::pin(stream::iter(vec![Err(e)])),
+        }
+    }
+
+    fn find(&self, interaction: Interaction, req: &Payload) -> Result<&Marble, RSocketError> {
+        let key = (
+            interaction,
+            req.data_utf8().unwrap_or_default().to_string(),
+            req.metadata_utf8().unwrap_or_default().to_string(),
+        );
+        self.marbles.get(&key).ok_or_else(|| {
+            RSocketError::from(ErrorKind::Internal(
+                error::ERR_APPLICATION,
+                format!("no marble for {:?} {:?} {:?}", key.0, key.1, key.2),
+            ))
+        })
+    }
+}
+
+impl RSocket for TckResponder {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        Box::pin(async {})
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        if self.find(Interaction::FireAndForget, &req).is_err() {
+            warn!("unexpected fire and forget: {:?}", req);
+        }
+        Box::pin(async {})
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let mut responses = match self.find(Interaction::RequestResponse, &req) {
+            Ok(it) => it.to_flux(),
+            Err(e) => return Box::pin(future::ready(Err(e))),
+        };
+        Box::pin(async move {
+            match responses.next().await {
+                Some(it) => it,
+                None => Ok(Payload::builder().build()),
+            }
+        })
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        match self.find(Interaction::RequestStream, &req) {
+            Ok(it) => it.to_flux(),
+            Err(e) => Box::pin(stream::iter(vec![Err(e)])),
+        }
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        // channels are matched by their first payload.
+        let responder = self.clone();
+        let responses = reqs.into_future().map(move |(first, _rest)| match first {
+            Some(Ok(req)) => responder.request_channel_with(&req),
+            Some(Err(e)) => Box::pin(stream::iter(vec![Err(e)])),
+            None => Box::pin(stream::empty()),
+        });
+        Box::pin(responses.flatten_stream())
+    }
+}
@@ -0,0 +1,99 @@
+//! One server for several logical services, each connection is served by the acceptor of
+//! the tenant its SETUP payload selects, such as by a tenant id or an API version.
+#[cfg(feature = "extension")]
+use crate::extension::CompositeMetadata;
+use crate::payload::SetupPayload;
+use crate::spi::RSocket;
+use crate::transport::BoxedAcceptor;
+#[cfg(feature = "extension")]
+use bytes::BytesMut;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+type TenantKey = Arc<dyn Fn(&SetupPayload) -> Option<String> + Send + Sync>;
+
+/// Acceptors of the tenants of a server, keyed by their SETUP payloads.
+#[derive(Clone)]
+pub struct Tenants {
+    key: TenantKey,
+    tenants: HashMap<String, Arc<BoxedAcceptor>>,
+    fallback: Option<Arc<BoxedAcceptor>>,
+}
+
+impl Tenants {
+    /// Select tenants by the key `key` returns for a SETUP payload.
+    pub fn by<F>(key: F) -> Tenants
+    where
+        F: Fn(&SetupPayload) -> Option<String> + Send + Sync + 'static,
+    {
+        Tenants {
+            key: Arc::new(key),
+            tenants: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// Select tenants by the value of the entry of `mime` in the composite metadata of SETUP.
+    #[cfg(feature = "extension")]
+    pub fn by_metadata(mime: &str) -> Tenants {
+        let mime = mime.to_string();
+        Tenants::by(move |setup| {
+            let metadata = setup.metadata().as_ref()?;
+            let composite =
+                CompositeMetadata::decode(&mut BytesMut::from(metadata.as_ref())).ok()?;
+            let entry = composite.find(&mime)?;
+            String::from_utf8(entry.get_payload().to_vec()).ok()
+        })
+    }
+
+    /// Serve the connections of tenant `key` by `acceptor`.
+    pub fn tenant<F>(mut self, key: &str, acceptor: F) -> Self
+    where
+        F: Fn(SetupPayload, Box<dyn RSocket>) -> Result<Box<dyn RSocket>, Box<dyn Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.tenants.insert(key.to_string(), Arc::new(acceptor));
+        self
+    }
+
+    /// Serve the connections without a registered tenant by `acceptor`, they are rejected
+    /// otherwise.
+    pub fn fallback<F>(mut self, acceptor: F) -> Self
+    where
+        F: Fn(SetupPayload, Box<dyn RSocket>) -> Result<Box<dyn RSocket>, Box<dyn Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.fallback = Some(Arc::new(acceptor));
+        self
+    }
+
+    pub fn get_tenants(&self) -> Vec<String> {
+        let mut tenants: Vec<String> = self.tenants.keys().cloned().collect();
+        tenants.sort();
+        tenants
+    }
+
+    pub(crate) fn accept(
+        &self,
+        setup: SetupPayload,
+        socket: Box<dyn RSocket>,
+    ) -> Result<Box<dyn RSocket>, Box<dyn Error>> {
+        let key = (self.key)(&setup);
+        let acceptor = key
+            .as_ref()
+            .and_then(|it| self.tenants.get(it))
+            .or(self.fallback.as_ref());
+        match acceptor {
+            Some(acceptor) => acceptor(setup, socket),
+            None => match key {
+                Some(key) => Err(format!("unknown tenant: {}", key).into()),
+                None => Err("missing tenant".into()),
+            },
+        }
+    }
+}
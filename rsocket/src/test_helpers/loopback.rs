@@ -0,0 +1,200 @@
+use super::sim::SimNetwork;
+use crate::error::RSocketError;
+use crate::frame::Frame;
+use crate::payload::SetupPayload;
+use crate::runtime::{DefaultSpawner, Spawner};
+use crate::spi::RSocket;
+use crate::transport::{
+    BoxedAcceptor, ClientTransport, PeerInfo, RxBounded, ServeFuture, ServerTransport,
+    SocketOptions, Tx, TxOnce,
+};
+use crate::x::{self, Client, RSocketFactory};
+use futures::channel::{mpsc, oneshot};
+use futures::{SinkExt, StreamExt};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+/// One end of an in-memory connection, frames are handed to the other end without encoding.
+pub struct LoopbackTransport {
+    local: oneshot::Sender<Tx<Frame>>,
+    remote: oneshot::Receiver<Tx<Frame>>,
+    network: Option<SimNetwork>,
+    peer: PeerInfo,
+}
+
+/// Server transport which accepts connections made by its `LoopbackConnector`.
+pub struct LoopbackServerTransport {
+    accepted: mpsc::UnboundedReceiver<LoopbackTransport>,
+}
+
+/// Opens connections to a `LoopbackServerTransport`.
+#[derive(Clone)]
+pub struct LoopbackConnector {
+    accepting: mpsc::UnboundedSender<LoopbackTransport>,
+    network: Option<SimNetwork>,
+}
+
+/// Create both ends of an in-memory connection.
+pub fn loopback() -> (LoopbackTransport, LoopbackTransport) {
+    let (a_tx, a_rx) = oneshot::channel();
+    let (b_tx, b_rx) = oneshot::channel();
+    (
+        LoopbackTransport {
+            local: a_tx,
+            remote: b_rx,
+            network: None,
+            peer: PeerInfo::default(),
+        },
+        LoopbackTransport {
+            local: b_tx,
+            remote: a_rx,
+            network: None,
+            peer: PeerInfo::default(),
+        },
+    )
+}
+
+/// Connect a client to a server which responds with `responder`, both served in memory.
+pub async fn pair<R>(responder: R) -> Client<DefaultSpawner>
+where
+    R: RSocket + 'static,
+{
+    let (client, server) = loopback();
+    pair_over(client, server, responder).await
+}
+
+pub(crate) async fn pair_over<R>(
+    client: LoopbackTransport,
+    server: LoopbackTransport,
+    responder: R,
+) -> Client<DefaultSpawner>
+where
+    R: RSocket + 'static,
+{
+    let responder = Mutex::new(Some(Box::new(responder) as Box<dyn RSocket>));
+    let setuper: Arc<BoxedAcceptor> = Arc::new(move |_setup: SetupPayload, _socket| {
+        responder
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| Box::<dyn Error>::from("loopback pair accepts one connection"))
+    });
+    x::accept(DefaultSpawner, server, setuper, SocketOptions::default());
+    RSocketFactory::connect()
+        .transport(client)
+        .start()
+        .await
+        .expect("connect loopback pair failed")
+}
+
+impl ClientTransport for LoopbackTransport {
+    fn peer(&self) -> PeerInfo {
+        self.peer.clone()
+    }
+
+    fn attach(
+        self,
+        incoming: Tx<Frame>,
+        mut sending: RxBounded<Frame>,
+        connected: Option<TxOnce<Result<(), RSocketError>>>,
+    ) {
+        let LoopbackTransport {
+            local,
+            remote,
+            network,
+            ..
+        } = self;
+        // a peer which is gone closes the connection.
+        let _ = local.send(incoming);
+        DefaultSpawner.spawn(async move {
+            let mut remote = match remote.await {
+                Ok(it) => {
+                    if let Some(sender) = connected {
+                        let _ = sender.send(Ok(()));
+                    }
+                    it
+                }
+                Err(_) => {
+                    if let Some(sender) = connected {
+                        let _ = sender.send(Err(RSocketError::from("loopback peer is gone")));
+                    }
+                    return;
+                }
+            };
+            if let Some(network) = network {
+                network.pump(sending, remote).await;
+                return;
+            }
+            while let Some(it) = sending.next().await {
+                if remote.send(it).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+impl LoopbackTransport {
+    pub(crate) fn via(mut self, network: SimNetwork) -> LoopbackTransport {
+        self.network = Some(network);
+        self
+    }
+}
+
+impl LoopbackServerTransport {
+    pub fn new() -> (LoopbackServerTransport, LoopbackConnector) {
+        let (accepting, accepted) = mpsc::unbounded();
+        (
+            LoopbackServerTransport { accepted },
+            LoopbackConnector {
+                accepting,
+                network: None,
+            },
+        )
+    }
+}
+
+impl LoopbackConnector {
+    /// Returns the client end of a new connection, or None if the server is gone.
+    pub fn connect(&self) -> Option<LoopbackTransport> {
+        self.connect_as(PeerInfo::default())
+    }
+
+    /// Like `connect`, the server sees the connection coming from `peer`.
+    pub fn connect_as(&self, peer: PeerInfo) -> Option<LoopbackTransport> {
+        let (client, mut server) = match &self.network {
+            Some(network) => network.link(),
+            None => loopback(),
+        };
+        server.peer = peer;
+        self.accepting.unbounded_send(server).ok().map(|_| client)
+    }
+
+    pub(crate) fn via(mut self, network: SimNetwork) -> LoopbackConnector {
+        self.network = Some(network);
+        self
+    }
+}
+
+impl ServerTransport for LoopbackServerTransport {
+    type Item = LoopbackTransport;
+
+    fn start(
+        mut self,
+        starter: Option<fn()>,
+        acceptor: impl Fn(Self::Item) + Send + Sync + 'static,
+    ) -> ServeFuture
+    where
+        Self::Item: ClientTransport + Sized,
+    {
+        Box::pin(async move {
+            if let Some(bingo) = starter {
+                bingo();
+            }
+            while let Some(it) = self.accepted.next().await {
+                acceptor(it);
+            }
+            Ok(())
+        })
+    }
+}
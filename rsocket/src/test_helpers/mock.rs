@@ -0,0 +1,330 @@
+use crate::error::{self, ErrorKind, RSocketError};
+use crate::payload::Payload;
+use crate::router::route_of;
+use crate::spi::{Flux, Mono, RSocket};
+use futures::{future, stream, FutureExt, StreamExt};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+type Matcher = Box<dyn Fn(&Payload) -> bool + Send + Sync>;
+type Responder = Arc<dyn Fn(Payload) -> Vec<Result<Payload, RSocketError>> + Send + Sync>;
+
+/// A scripted `RSocket`, clones share their expectations and recorded calls.
+///
+/// ```
+/// use rsocket_rust::prelude::*;
+/// use rsocket_rust::test_helpers::{Expectation, MockRSocket};
+///
+/// # futures::executor::block_on(async {
+/// let mock = MockRSocket::new();
+/// mock.expect(
+///     Expectation::request_response()
+///         .route("orders.get")
+///         .respond(Payload::from("order 42"))
+///         .times(1),
+/// );
+/// let req = Payload::builder()
+///     .set_data_utf8("42")
+///     .metadata()
+///     .route("orders.get")
+///     .build();
+/// let res = mock.request_response(req).await.unwrap();
+/// assert_eq!(Some("order 42"), res.data_utf8());
+/// mock.verify();
+/// # });
+/// ```
+#[derive(Clone, Default)]
+pub struct MockRSocket {
+    inner: Arc<Mutex<State>>,
+}
+
+/// An expected request and its canned response.
+///
+/// An expectation matches requests of its interaction which pass all of its matchers, and
+/// is expected to be called at least once unless `times` is set. Requests without a response
+/// are completed empty.
+pub struct Expectation {
+    interaction: &'static str,
+    description: Vec<String>,
+    matchers: Vec<Matcher>,
+    responder: Option<Responder>,
+    times: Option<usize>,
+    calls: usize,
+}
+
+/// A request received by a `MockRSocket`.
+#[derive(Debug, Clone)]
+pub struct MockCall {
+    interaction: &'static str,
+    payload: Payload,
+    matched: bool,
+}
+
+#[derive(Default)]
+struct State {
+    expectations: Vec<Expectation>,
+    calls: Vec<MockCall>,
+}
+
+impl MockRSocket {
+    pub fn new() -> MockRSocket {
+        MockRSocket::default()
+    }
+
+    /// Add an expectation, earlier expectations are matched first.
+    pub fn expect(&self, expectation: Expectation) -> &Self {
+        self.inner.lock().unwrap().expectations.push(expectation);
+        self
+    }
+
+    /// Returns all received requests in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.inner.lock().unwrap().calls.clone()
+    }
+
+    /// Returns the number of received requests of an interaction, such as `request_response`.
+    pub fn count(&self, interaction: &str) -> usize {
+        let state = self.inner.lock().unwrap();
+        state
+            .calls
+            .iter()
+            .filter(|it| it.interaction == interaction)
+            .count()
+    }
+
+    /// Panic if a request matched no expectation or an expectation was not called as expected.
+    pub fn verify(&self) {
+        let state = self.inner.lock().unwrap();
+        let mut failures = vec![];
+        for it in state.calls.iter().filter(|it| !it.matched) {
+            failures.push(format!("unexpected {}: {:?}", it.interaction, it.payload));
+        }
+        for it in state.expectations.iter() {
+            match it.times {
+                Some(n) if it.calls != n => {
+                    failures.push(format!("{} expected {} call(s), got {}", it, n, it.calls))
+                }
+                None if it.calls == 0 => failures.push(format!("{} was never called", it)),
+                _ => (),
+            }
+        }
+        if !failures.is_empty() {
+            panic!("MockRSocket verify failed:\n  {}", failures.join("\n  "));
+        }
+    }
+
+    fn call(
+        &self,
+        interaction: &'static str,
+        req: Payload,
+    ) -> Option<Vec<Result<Payload, RSocketError>>> {
+        let mut state = self.inner.lock().unwrap();
+        let found = state.expectations.iter_mut().find(|it| {
+            it.interaction == interaction
+                && it.times.is_none_or(|n| it.calls < n)
+                && it.matchers.iter().all(|m| m(&req))
+        });
+        let responder = found.map(|it| {
+            it.calls += 1;
+            it.responder.clone()
+        });
+        state.calls.push(MockCall {
+            interaction,
+            payload: req.clone(),
+            matched: responder.is_some(),
+        });
+        drop(state);
+        // responders may call the mock themselves.
+        responder.map(|it| match it {
+            Some(respond) => respond(req),
+            None => vec![],
+        })
+    }
+}
+
+#[inline]
+fn unexpected(interaction: &str) -> RSocketError {
+    RSocketError::from(ErrorKind::Internal(
+        error::ERR_APPLICATION,
+        format!("unexpected {}", interaction),
+    ))
+}
+
+impl RSocket for MockRSocket {
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.call("metadata_push", req);
+        Box::pin(future::ready(()))
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.call("fire_and_forget", req);
+        Box::pin(future::ready(()))
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        let result = match self.call("request_response", req) {
+            Some(results) => results
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| Ok(Payload::builder().build())),
+            None => Err(unexpected("request_response")),
+        };
+        Box::pin(future::ready(result))
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        let results = self
+            .call("request_stream", req)
+            .unwrap_or_else(|| vec![Err(unexpected("request_stream"))]);
+        Box::pin(stream::iter(results))
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        // channels are matched and recorded by their first payload.
+        let mock = self.clone();
+        let results = reqs.into_future().map(move |(first, _rest)| {
+            let results = match first {
+                Some(Ok(req)) => mock.call("request_channel", req),
+                _ => None,
+            };
+            stream::iter(results.unwrap_or_else(|| vec![Err(unexpected("request_channel"))]))
+        });
+        Box::pin(stream::once(results).flatten())
+    }
+}
+
+impl Expectation {
+    fn new(interaction: &'static str) -> Expectation {
+        Expectation {
+            interaction,
+            description: vec![],
+            matchers: vec![],
+            responder: None,
+            times: None,
+            calls: 0,
+        }
+    }
+
+    pub fn metadata_push() -> Expectation {
+        Expectation::new("metadata_push")
+    }
+
+    pub fn fire_and_forget() -> Expectation {
+        Expectation::new("fire_and_forget")
+    }
+
+    pub fn request_response() -> Expectation {
+        Expectation::new("request_response")
+    }
+
+    pub fn request_stream() -> Expectation {
+        Expectation::new("request_stream")
+    }
+
+    /// Expect a channel, matchers are applied to its first payload.
+    pub fn request_channel() -> Expectation {
+        Expectation::new("request_channel")
+    }
+
+    /// Match requests whose first routing tag is `route`.
+    pub fn route(mut self, route: &str) -> Self {
+        let route = route.to_string();
+        self.description.push(format!("route={}", route));
+        self.matchers.push(Box::new(move |req| {
+            route_of(req).as_deref() == Some(&route)
+        }));
+        self
+    }
+
+    /// Match requests whose data is the UTF-8 string `data`.
+    pub fn data(mut self, data: &str) -> Self {
+        let data = data.to_string();
+        self.description.push(format!("data={:?}", data));
+        self.matchers
+            .push(Box::new(move |req| req.data_utf8() == Some(&data)));
+        self
+    }
+
+    /// Match requests with a custom predicate.
+    pub fn matching<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Payload) -> bool + Send + Sync + 'static,
+    {
+        self.description.push(String::from("custom"));
+        self.matchers.push(Box::new(predicate));
+        self
+    }
+
+    /// Expect exactly `n` matching calls, later requests are matched by other expectations.
+    pub fn times(mut self, n: usize) -> Self {
+        self.times = Some(n);
+        self
+    }
+
+    pub fn respond(self, res: Payload) -> Self {
+        self.respond_with(move |_| Ok(res.clone()))
+    }
+
+    /// Respond with an APPLICATION_ERROR.
+    pub fn respond_error(self, msg: &str) -> Self {
+        let msg = msg.to_string();
+        self.respond_with(move |_| {
+            Err(RSocketError::from(ErrorKind::Internal(
+                error::ERR_APPLICATION,
+                msg.clone(),
+            )))
+        })
+    }
+
+    /// Respond with the result of `f` applied to the request.
+    pub fn respond_with<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Payload) -> Result<Payload, RSocketError> + Send + Sync + 'static,
+    {
+        self.responder = Some(Arc::new(move |req| vec![f(req)]));
+        self
+    }
+
+    /// Respond to a stream or channel with `items`.
+    pub fn respond_stream(self, items: Vec<Payload>) -> Self {
+        self.respond_stream_with(move |_| items.iter().cloned().map(Ok).collect())
+    }
+
+    /// Respond to a stream or channel with the items returned by `f`.
+    pub fn respond_stream_with<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Payload) -> Vec<Result<Payload, RSocketError>> + Send + Sync + 'static,
+    {
+        self.responder = Some(Arc::new(f));
+        self
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.description.is_empty() {
+            write!(f, "{}", self.interaction)
+        } else {
+            write!(f, "{}({})", self.interaction, self.description.join(", "))
+        }
+    }
+}
+
+impl MockCall {
+    /// Returns the interaction name, such as `request_response`.
+    pub fn get_interaction(&self) -> &str {
+        self.interaction
+    }
+
+    pub fn get_payload(&self) -> &Payload {
+        &self.payload
+    }
+
+    /// Returns true if the request matched an expectation.
+    pub fn is_matched(&self) -> bool {
+        self.matched
+    }
+}
@@ -0,0 +1,12 @@
+//! Helpers for testing applications built on rsocket without a server.
+//!
+//! - `MockRSocket` answers requests from scripted expectations and records every call.
+//! - `pair` connects a real client and server in memory.
+//! - `SimNetwork` adds delays, reordering, partitions and disconnects to such connections.
+mod loopback;
+mod mock;
+mod sim;
+
+pub use loopback::{loopback, pair, LoopbackConnector, LoopbackServerTransport, LoopbackTransport};
+pub use mock::{Expectation, MockCall, MockRSocket};
+pub use sim::SimNetwork;
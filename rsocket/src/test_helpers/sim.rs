@@ -0,0 +1,205 @@
+use super::loopback::{LoopbackConnector, LoopbackServerTransport, LoopbackTransport};
+use crate::frame::Frame;
+use crate::runtime::DefaultSpawner;
+use crate::spi::RSocket;
+use crate::transport::{RxBounded, Tx};
+use crate::x::Client;
+use futures::{future, FutureExt, SinkExt, StreamExt};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::{self, Instant};
+
+/// A simulated network between in-memory connections, which delays, reorders, drops and
+/// disconnects frames at the transport level.
+///
+/// Delays follow the tokio clock, after `tokio::time::pause` the clock jumps to the next timer
+/// whenever the runtime is idle, so a test does not depend on real time. Random decisions come from a seeded
+/// generator, a seed replays the same faults.
+#[derive(Clone)]
+pub struct SimNetwork {
+    inner: Arc<Mutex<SimState>>,
+    disconnects: Arc<watch::Sender<u64>>,
+    watching: watch::Receiver<u64>,
+}
+
+#[derive(Debug)]
+struct SimState {
+    rng: u64,
+    delay: Duration,
+    jitter: Duration,
+    loss: f64,
+    partitioned: bool,
+    generation: u64,
+    delivered: u64,
+    dropped: u64,
+}
+
+impl SimNetwork {
+    pub fn new(seed: u64) -> SimNetwork {
+        let (disconnects, watching) = watch::channel(0);
+        SimNetwork {
+            inner: Arc::new(Mutex::new(SimState {
+                // xorshift never leaves zero.
+                rng: seed.max(1),
+                delay: Duration::from_secs(0),
+                jitter: Duration::from_secs(0),
+                loss: 0.0,
+                partitioned: false,
+                generation: 0,
+                delivered: 0,
+                dropped: 0,
+            })),
+            disconnects: Arc::new(disconnects),
+            watching,
+        }
+    }
+
+    /// Delay every frame by `delay`.
+    pub fn set_delay(&self, delay: Duration) -> &Self {
+        self.inner.lock().unwrap().delay = delay;
+        self
+    }
+
+    /// Add a random delay of up to `jitter` to every frame, frames may overtake each other.
+    pub fn set_jitter(&self, jitter: Duration) -> &Self {
+        self.inner.lock().unwrap().jitter = jitter;
+        self
+    }
+
+    /// Drop frames with the probability `loss`, between 0 and 1.
+    pub fn set_loss(&self, loss: f64) -> &Self {
+        self.inner.lock().unwrap().loss = loss;
+        self
+    }
+
+    /// Drop every frame sent until `heal` is called, connections stay open.
+    pub fn partition(&self) {
+        self.inner.lock().unwrap().partitioned = true;
+    }
+
+    pub fn heal(&self) {
+        self.inner.lock().unwrap().partitioned = false;
+    }
+
+    pub fn is_partitioned(&self) -> bool {
+        self.inner.lock().unwrap().partitioned
+    }
+
+    /// Close every connection created so far, frames still in flight are lost.
+    pub fn disconnect(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.generation += 1;
+        let _ = self.disconnects.broadcast(state.generation);
+    }
+
+    /// Returns the number of frames handed to the other end.
+    pub fn delivered(&self) -> u64 {
+        self.inner.lock().unwrap().delivered
+    }
+
+    /// Returns the number of frames lost to partitions, loss and disconnects.
+    pub fn dropped(&self) -> u64 {
+        self.inner.lock().unwrap().dropped
+    }
+
+    /// Create both ends of a connection over this network.
+    pub fn link(&self) -> (LoopbackTransport, LoopbackTransport) {
+        let (a, b) = super::loopback::loopback();
+        (a.via(self.clone()), b.via(self.clone()))
+    }
+
+    /// Create a server transport whose connections are made over this network.
+    pub fn server(&self) -> (LoopbackServerTransport, LoopbackConnector) {
+        let (server, connector) = LoopbackServerTransport::new();
+        (server, connector.via(self.clone()))
+    }
+
+    /// Like `pair`, but the client and the server talk over this network.
+    pub async fn pair<R>(&self, responder: R) -> Client<DefaultSpawner>
+    where
+        R: RSocket + 'static,
+    {
+        let (client, server) = self.link();
+        super::loopback::pair_over(client, server, responder).await
+    }
+
+    /// Returns the delivery time of a frame sent now, or None if it is lost.
+    fn schedule(&self) -> Option<Instant> {
+        let mut state = self.inner.lock().unwrap();
+        if state.partitioned || (state.loss > 0.0 && state.next_f64() < state.loss) {
+            state.dropped += 1;
+            return None;
+        }
+        let mut delay = state.delay;
+        if state.jitter > Duration::from_secs(0) {
+            let jitter = state.jitter.mul_f64(state.next_f64());
+            delay += jitter;
+        }
+        Some(Instant::now() + delay)
+    }
+
+    pub(crate) async fn pump(self, mut sending: RxBounded<Frame>, mut remote: Tx<Frame>) {
+        let mut watching = self.watching.clone();
+        let generation = *watching.borrow();
+        let mut in_flight: Vec<(Instant, u64, Frame)> = Vec::new();
+        let mut seq = 0u64;
+        let mut open = true;
+        loop {
+            let now = Instant::now();
+            while in_flight.first().is_some_and(|it| it.0 <= now) {
+                let (_, _, frame) = in_flight.remove(0);
+                if remote.send(frame).await.is_err() {
+                    return;
+                }
+                self.inner.lock().unwrap().delivered += 1;
+            }
+            if !open && in_flight.is_empty() {
+                return;
+            }
+            let next_at = in_flight.first().map(|it| it.0);
+            let due = async move {
+                match next_at {
+                    Some(at) => time::delay_until(at).await,
+                    None => future::pending().await,
+                }
+            };
+            let next = async {
+                if open {
+                    sending.next().await
+                } else {
+                    future::pending().await
+                }
+            };
+            futures::select! {
+                it = next.fuse() => match it {
+                    Some(frame) => {
+                        if let Some(at) = self.schedule() {
+                            // equal times keep the sending order.
+                            let pos = in_flight.partition_point(|it| (it.0, it.1) <= (at, seq));
+                            in_flight.insert(pos, (at, seq, frame));
+                            seq += 1;
+                        }
+                    }
+                    None => open = false,
+                },
+                _ = due.fuse() => (),
+                changed = watching.recv().fuse() => {
+                    if changed.is_none_or(|it| it != generation) {
+                        self.inner.lock().unwrap().dropped += in_flight.len() as u64;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl SimState {
+    fn next_f64(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::RSocketError;
+use crate::extension::{AuthMetadata, CompositeMetadata};
+use crate::mime;
+use crate::payload::SetupPayload;
+use crate::spi::Mono;
+use crate::transport::Principal;
+use bytes::BytesMut;
+use std::future::Future;
+
+/// Authenticates accepted connections by the authentication metadata of their SETUP, before
+/// the acceptor runs. Any `Fn(Option<AuthMetadata>) -> Future` is an authenticator.
+///
+/// Connections which fail are rejected with REJECTED_SETUP, the principal of the others is
+/// attached to the context of their requests.
+pub trait Authenticator: Send + Sync {
+    /// Authenticate a connection, `auth` is None if its SETUP carries no authentication.
+    fn authenticate(&self, auth: Option<AuthMetadata>) -> Mono<Result<Principal, RSocketError>>;
+}
+
+impl<F, Fut> Authenticator for F
+where
+    F: Fn(Option<AuthMetadata>) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Principal, RSocketError>> + Send + Sync + 'static,
+{
+    fn authenticate(&self, auth: Option<AuthMetadata>) -> Mono<Result<Principal, RSocketError>> {
+        Box::pin(self(auth))
+    }
+}
+
+/// Returns the authentication metadata of a SETUP, carried as an entry of composite metadata
+/// or as the whole metadata.
+pub(crate) fn auth_of(setup: &SetupPayload) -> Result<Option<AuthMetadata>, RSocketError> {
+    let metadata = match setup.metadata() {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let mut bf = BytesMut::from(metadata.as_ref());
+    match setup.metadata_mime_type().as_deref() {
+        Some(mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0) => AuthMetadata::decode(&mut bf).map(Some),
+        Some(mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0) => {
+            let composite = CompositeMetadata::decode(&mut bf)?;
+            match composite.find(mime::MESSAGE_X_RSOCKET_AUTHENTICATION_V0) {
+                Some(it) => {
+                    AuthMetadata::decode(&mut BytesMut::from(it.get_payload().as_ref())).map(Some)
+                }
+                None => Ok(None),
+            }
+        }
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,77 @@
+use super::demand::Demand;
+use super::inbound::{Delivery, Inbound};
+use crate::error::RSocketError;
+use crate::payload::Payload;
+
+/// State of a REQUEST_CHANNEL, whose directions terminate independently. A closed channel
+/// is removed from the handlers.
+#[derive(Debug)]
+pub(crate) enum Channel {
+    /// Payloads flow in both directions.
+    Open(Inbound, Demand),
+    /// The local side completed, payloads of the remote side keep flowing.
+    HalfClosedLocal(Inbound),
+    /// The remote side completed, the local side keeps sending.
+    HalfClosedRemote(Demand),
+}
+
+impl Channel {
+    pub(crate) fn new(inbound: Inbound, outbound: Demand) -> Channel {
+        Channel::Open(inbound, outbound)
+    }
+
+    pub(crate) fn outbound(&self) -> Option<&Demand> {
+        match self {
+            Channel::Open(_, demand) | Channel::HalfClosedRemote(demand) => Some(demand),
+            Channel::HalfClosedLocal(_) => None,
+        }
+    }
+
+    /// Forward a payload of the remote side.
+    pub(crate) fn on_next(&mut self, sid: u32, input: Payload) -> Delivery {
+        match self {
+            Channel::Open(inbound, _) | Channel::HalfClosedLocal(inbound) => inbound.deliver(input),
+            Channel::HalfClosedRemote(_) => {
+                warn!("unexpected PAYLOAD of completed REQUEST_CHANNEL {}", sid);
+                Delivery::Sent
+            }
+        }
+    }
+
+    /// The remote side completed, returns None if the channel is closed.
+    pub(crate) fn on_remote_complete(self) -> Option<Channel> {
+        match self {
+            Channel::Open(_, demand) => Some(Channel::HalfClosedRemote(demand)),
+            Channel::HalfClosedLocal(_) => None,
+            it @ Channel::HalfClosedRemote(_) => Some(it),
+        }
+    }
+
+    /// The local side completed or stopped sending, returns None if the channel is closed.
+    pub(crate) fn on_local_complete(self) -> Option<Channel> {
+        match self {
+            Channel::Open(inbound, demand) => {
+                demand.cancel();
+                Some(Channel::HalfClosedLocal(inbound))
+            }
+            Channel::HalfClosedRemote(demand) => {
+                demand.cancel();
+                None
+            }
+            it @ Channel::HalfClosedLocal(_) => Some(it),
+        }
+    }
+
+    /// Close both directions, the inbound payloads end with `e` if they are still flowing and
+    /// the outbound side fails with it.
+    pub(crate) fn terminate(self, e: RSocketError) {
+        match self {
+            Channel::Open(inbound, demand) => {
+                demand.fail(e.clone());
+                inbound.fail(e);
+            }
+            Channel::HalfClosedLocal(inbound) => inbound.fail(e),
+            Channel::HalfClosedRemote(demand) => demand.fail(e),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use super::pool::Pooled;
+use crate::error::RSocketError;
+use crate::frame::REQUEST_MAX;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+const UNBOUNDED: u64 = u64::MAX;
+
+/// What to do with a responder stream whose requester stopped requesting items.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SlowConsumerPolicy {
+    /// Only log and record the event, keep waiting for demand.
+    Notify,
+    /// Terminate the stream with an APPLICATION_ERROR.
+    Error,
+    /// Keep consuming the producer, buffering at most n items and dropping the oldest ones.
+    DropOldest(usize),
+}
+
+/// What to do with the outputs of a responder stream whose buffer is full.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Stop consuming the producer until the requester demands more.
+    #[default]
+    Suspend,
+    /// Keep consuming the producer, dropping the oldest buffered items.
+    DropOldest,
+    /// Keep consuming the producer, dropping the new items.
+    DropNewest,
+    /// Terminate the stream with an APPLICATION_ERROR.
+    Error,
+}
+
+/// Demand of a responder stream, granted by the initial request n and REQUEST_N frames.
+#[derive(Debug, Clone)]
+pub(crate) struct Demand {
+    inner: Pooled<DemandState>,
+}
+
+#[derive(Debug)]
+pub(crate) struct DemandState {
+    n: AtomicU64,
+    cancelled: AtomicBool,
+    error: Mutex<Option<RSocketError>>,
+    notify: Notify,
+}
+
+impl DemandState {
+    pub(crate) fn new(initial_request_n: u32) -> DemandState {
+        DemandState {
+            n: AtomicU64::new(to_demand(initial_request_n)),
+            cancelled: AtomicBool::new(false),
+            error: Mutex::new(None),
+            notify: Notify::new(),
+        }
+    }
+}
+
+impl From<Pooled<DemandState>> for Demand {
+    fn from(inner: Pooled<DemandState>) -> Demand {
+        Demand { inner }
+    }
+}
+
+impl Demand {
+    pub(crate) fn new(initial_request_n: u32) -> Demand {
+        Demand::from(Pooled::new(DemandState::new(initial_request_n)))
+    }
+
+    /// Reset a released state, a notification left by its last stream only wakes one
+    /// waiter spuriously.
+    pub(crate) fn reuse(inner: Pooled<DemandState>, initial_request_n: u32) -> Demand {
+        inner
+            .n
+            .store(to_demand(initial_request_n), Ordering::SeqCst);
+        inner.cancelled.store(false, Ordering::SeqCst);
+        inner.error.lock().unwrap().take();
+        Demand { inner }
+    }
+
+    /// Add `n` to the demand. Demand accumulates across REQUEST_N frames, a total which
+    /// reaches REQUEST_MAX is unbounded for the rest of the stream.
+    pub(crate) fn request(&self, n: u32) {
+        let n = to_demand(n);
+        let mut current = self.inner.n.load(Ordering::SeqCst);
+        loop {
+            let next = if n == UNBOUNDED {
+                UNBOUNDED
+            } else {
+                to_demand_total(current.saturating_add(n))
+            };
+            match self
+                .inner
+                .n
+                .compare_exchange(current, next, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        self.inner.notify.notify();
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify();
+    }
+
+    /// Cancel because the stream failed with `e`.
+    pub(crate) fn fail(&self, e: RSocketError) {
+        self.inner.error.lock().unwrap().replace(e);
+        self.cancel();
+    }
+
+    /// Returns the error the stream failed with.
+    pub(crate) fn error(&self) -> Option<RSocketError> {
+        self.inner.error.lock().unwrap().clone()
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn has_demand(&self) -> bool {
+        self.inner.n.load(Ordering::SeqCst) > 0
+    }
+
+    /// Take one from the demand, returns false if the demand is exhausted.
+    pub(crate) fn try_acquire(&self) -> bool {
+        let mut current = self.inner.n.load(Ordering::SeqCst);
+        loop {
+            if current == 0 {
+                return false;
+            }
+            if current == UNBOUNDED {
+                return true;
+            }
+            match self.inner.n.compare_exchange(
+                current,
+                current - 1,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Wait until the demand is changed or the stream is cancelled.
+    pub(crate) async fn changed(&self) {
+        self.inner.notify.notified().await
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SlowConsumer {
+    pub(crate) threshold: Duration,
+    pub(crate) policy: SlowConsumerPolicy,
+}
+
+/// The items a responder stream consumes ahead of the demand of the requester.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StreamBuffer {
+    pub(crate) capacity: usize,
+    pub(crate) policy: OverflowPolicy,
+}
+
+#[inline]
+fn to_demand(n: u32) -> u64 {
+    to_demand_total(u64::from(n))
+}
+
+#[inline]
+fn to_demand_total(n: u64) -> u64 {
+    if n >= u64::from(REQUEST_MAX) {
+        UNBOUNDED
+    } else {
+        n
+    }
+}
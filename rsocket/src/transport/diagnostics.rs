@@ -0,0 +1,116 @@
+//! Stream leak detection, enabled by `leak_detection` of client and server builders.
+//!
+//! Local stream state is recorded with its creation time and backtrace, streams which
+//! have neither activity nor a terminal frame for longer than the threshold are logged
+//! periodically with target `rsocket_rust::leaks`.
+use crate::frame::{Body, Frame};
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const LOG_TARGET: &str = "rsocket_rust::leaks";
+
+#[derive(Clone)]
+pub(crate) struct LeakDetector {
+    threshold: Duration,
+    streams: Arc<Mutex<HashMap<u32, StreamTrace>>>,
+    closed: Arc<AtomicBool>,
+}
+
+struct StreamTrace {
+    interaction: &'static str,
+    created_at: Instant,
+    last_active_at: Instant,
+    backtrace: Backtrace,
+    // channels terminate when both sides have completed.
+    completes: u8,
+    reported: bool,
+}
+
+impl LeakDetector {
+    pub(crate) fn new(threshold: Duration) -> LeakDetector {
+        LeakDetector {
+            threshold,
+            streams: Arc::new(Mutex::new(HashMap::new())),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub(crate) fn open(&self, sid: u32, interaction: &'static str) {
+        let now = Instant::now();
+        let trace = StreamTrace {
+            interaction,
+            created_at: now,
+            last_active_at: now,
+            backtrace: Backtrace::force_capture(),
+            completes: 0,
+            reported: false,
+        };
+        self.streams.lock().unwrap().insert(sid, trace);
+    }
+
+    pub(crate) fn on_frame(&self, frame: &Frame) {
+        let sid = frame.get_stream_id();
+        if sid == 0 {
+            return;
+        }
+        let mut streams = self.streams.lock().unwrap();
+        let trace = match streams.get_mut(&sid) {
+            Some(it) => it,
+            None => return,
+        };
+        trace.last_active_at = Instant::now();
+        trace.reported = false;
+        let terminated = match frame.get_body_ref() {
+            Body::Error(_) | Body::Cancel() => true,
+            Body::Payload(_) | Body::RequestChannel(_) if frame.has_complete() => {
+                trace.completes += 1;
+                trace.interaction != "request_channel" || trace.completes > 1
+            }
+            _ => false,
+        };
+        if terminated {
+            streams.remove(&sid);
+        }
+    }
+
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.streams.lock().unwrap().clear();
+    }
+
+    /// Check idle streams periodically until the connection is closed.
+    pub(crate) async fn run(self) {
+        let mut ticker = tokio::time::interval(self.threshold);
+        loop {
+            ticker.tick().await;
+            if self.closed.load(Ordering::SeqCst) {
+                break;
+            }
+            self.report();
+        }
+    }
+
+    fn report(&self) {
+        let now = Instant::now();
+        let mut streams = self.streams.lock().unwrap();
+        for (sid, trace) in streams.iter_mut() {
+            if trace.reported || now - trace.last_active_at < self.threshold {
+                continue;
+            }
+            trace.reported = true;
+            warn!(
+                target: LOG_TARGET,
+                "possible leaked stream: sid={}, interaction={}, age={:?}, idle={:?}, created at:\n{}",
+                sid,
+                trace.interaction,
+                now - trace.created_at,
+                now - trace.last_active_at,
+                trace.backtrace
+            );
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::error::{self, ErrorKind, RSocketError};
+use futures::future::BoxFuture;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Where the handlers of the responder run.
+#[derive(Clone, Default)]
+pub enum Execution {
+    /// On the task of the connection, which reads no more frames until the handler is done.
+    /// Only for handlers which never block; streams and channels are spawned anyway as they
+    /// wait on the REQUEST_N frames of the peer.
+    Inline,
+    /// As tasks of the runtime, the default.
+    #[default]
+    Spawn,
+    /// On the threads of a worker pool, see `WorkerPool`.
+    Pool(WorkerPool),
+}
+
+/// Threads dedicated to the handlers of responders, such as CPU heavy ones which would
+/// stall the runtime otherwise.
+///
+/// Each thread runs one handler at a time, a stream holds its thread until it completes.
+/// The handlers run on a runtime of their thread, which has timers but no I/O. Requests
+/// beyond `queue_size` waiting for a thread are rejected. A pool may be shared by many
+/// connections, its threads stop once every copy of it is dropped.
+#[derive(Clone)]
+pub struct WorkerPool {
+    jobs: SyncSender<BoxFuture<'static, ()>>,
+}
+
+impl WorkerPool {
+    pub fn new(workers: usize, queue_size: usize) -> WorkerPool {
+        let (jobs, rx) = mpsc::sync_channel::<BoxFuture<'static, ()>>(queue_size);
+        let rx = Arc::new(Mutex::new(rx));
+        for i in 0..workers.max(1) {
+            let rx = rx.clone();
+            thread::Builder::new()
+                .name(format!("rsocket-worker-{}", i))
+                .spawn(move || {
+                    let mut rt = tokio::runtime::Builder::new()
+                        .basic_scheduler()
+                        .enable_time()
+                        .build()
+                        .expect("Build worker runtime failed");
+                    loop {
+                        let job = match rx.lock().unwrap().recv() {
+                            Ok(job) => job,
+                            Err(_) => break,
+                        };
+                        rt.block_on(job);
+                    }
+                })
+                .expect("Spawn worker failed");
+        }
+        WorkerPool { jobs }
+    }
+
+    /// Queue a handler for the next free thread, fails if the queue is full.
+    pub(crate) fn submit(&self, job: BoxFuture<'static, ()>) -> Result<(), RSocketError> {
+        self.jobs.try_send(job).map_err(|e| {
+            let msg = match e {
+                TrySendError::Full(_) => "worker pool is full",
+                TrySendError::Disconnected(_) => "worker pool is closed",
+            };
+            RSocketError::from(ErrorKind::Internal(error::ERR_REJECTED, String::from(msg)))
+        })
+    }
+}
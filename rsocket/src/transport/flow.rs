@@ -0,0 +1,243 @@
+use super::spi::TxBounded;
+use crate::error::RSocketError;
+use crate::frame::{self, Frame, REQUEST_MAX};
+use crate::payload::Payload;
+use crate::spi::Flux;
+use futures::task::{Context, Poll};
+use futures::{ready, Stream, StreamExt};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Budgets of the bytes of the payloads a requester has outstanding, both unlimited by default.
+///
+/// Payloads are outstanding from the time they are requested until they are consumed.
+/// Requester streams translate the budgets into REQUEST_N by the average size of the payloads
+/// they consumed: the first payload is requested alone, more are requested as payloads are
+/// consumed, as far as they fit in the budgets of both the stream and the connection. A stream
+/// with nothing outstanding requests a payload regardless, so the budgets may be exceeded by
+/// a payload per stream.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ByteBudget {
+    connection: Option<usize>,
+    stream: Option<usize>,
+}
+
+impl ByteBudget {
+    pub fn new() -> ByteBudget {
+        ByteBudget::default()
+    }
+
+    /// Keep at most `size` bytes outstanding over all the streams of the connection.
+    pub fn max_connection_bytes(mut self, size: usize) -> Self {
+        self.connection = Some(size);
+        self
+    }
+
+    /// Keep at most `size` bytes outstanding for each stream.
+    pub fn max_stream_bytes(mut self, size: usize) -> Self {
+        self.stream = Some(size);
+        self
+    }
+
+    pub fn get_max_connection_bytes(&self) -> Option<usize> {
+        self.connection
+    }
+
+    pub fn get_max_stream_bytes(&self) -> Option<usize> {
+        self.stream
+    }
+}
+
+/// The outstanding bytes of the requester streams of a connection, by their `ByteBudget`.
+#[derive(Debug, Clone)]
+pub(crate) struct Flow {
+    budget: ByteBudget,
+    inner: Arc<Mutex<FlowState>>,
+}
+
+#[derive(Debug, Default)]
+struct FlowState {
+    outstanding: usize,
+    streams: HashMap<u32, StreamFlow>,
+}
+
+#[derive(Debug, Default)]
+struct StreamFlow {
+    // payloads requested but not received yet, and the bytes reserved for them.
+    requested: u64,
+    reserved: usize,
+    // bytes received but not consumed yet.
+    buffered: usize,
+    // the average size of the consumed payloads.
+    estimate: usize,
+}
+
+impl StreamFlow {
+    fn outstanding(&self) -> usize {
+        self.reserved + self.buffered
+    }
+}
+
+impl Flow {
+    pub(crate) fn new(budget: ByteBudget) -> Flow {
+        Flow {
+            budget,
+            inner: Arc::new(Mutex::new(FlowState::default())),
+        }
+    }
+
+    /// Track requester stream `sid`, returns its initial request n.
+    pub(crate) fn open(&self, sid: u32) -> u32 {
+        let mut inner = self.inner.lock().unwrap();
+        inner.streams.insert(
+            sid,
+            StreamFlow {
+                requested: 1,
+                ..Default::default()
+            },
+        );
+        1
+    }
+
+    /// A payload of `size` bytes was received for stream `sid`, if it is tracked.
+    pub(crate) fn on_receive(&self, sid: u32, size: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        let stream = match inner.streams.get_mut(&sid) {
+            Some(it) => it,
+            None => return,
+        };
+        let released = if stream.requested > 0 {
+            stream.reserved / stream.requested as usize
+        } else {
+            0
+        };
+        stream.requested = stream.requested.saturating_sub(1);
+        stream.reserved -= released;
+        stream.buffered += size;
+        inner.outstanding = inner.outstanding - released + size;
+    }
+
+    /// A payload of `size` bytes of stream `sid` was consumed, returns the payloads to request
+    /// next, if any.
+    pub(crate) fn on_consume(&self, sid: u32, size: usize) -> Option<u32> {
+        let mut inner = self.inner.lock().unwrap();
+        let outstanding = inner.outstanding;
+        let stream = inner.streams.get_mut(&sid)?;
+        let consumed = size.min(stream.buffered);
+        stream.buffered -= consumed;
+        stream.estimate = if stream.estimate == 0 {
+            size
+        } else {
+            (stream.estimate * 7 + size) / 8
+        };
+        let estimate = stream.estimate.max(1);
+        let room = [
+            self.budget
+                .stream
+                .map(|it| it.saturating_sub(stream.outstanding())),
+            self.budget
+                .connection
+                .map(|it| it.saturating_sub(outstanding - consumed)),
+        ]
+        .iter()
+        .flatten()
+        .copied()
+        .min()
+        .unwrap_or(usize::MAX);
+        let mut n = (room / estimate).min(REQUEST_MAX as usize);
+        if n == 0 && stream.outstanding() == 0 {
+            n = 1;
+        }
+        let reserved = n * estimate;
+        if n > 0 {
+            stream.requested += n as u64;
+            stream.reserved += reserved;
+        }
+        inner.outstanding = outstanding - consumed + reserved;
+        if n > 0 {
+            Some(n as u32)
+        } else {
+            None
+        }
+    }
+
+    /// Stop tracking stream `sid`, its outstanding bytes are released.
+    pub(crate) fn close(&self, sid: u32) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(stream) = inner.streams.remove(&sid) {
+            inner.outstanding -= stream.outstanding();
+        }
+    }
+
+    /// Returns the payloads of requester stream `sid`, which request more by REQUEST_N frames
+    /// sent to `tx` as they are consumed.
+    pub(crate) fn consume(
+        &self,
+        sid: u32,
+        results: Flux<Result<Payload, RSocketError>>,
+        tx: TxBounded<Frame>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(Budgeted {
+            flow: self.clone(),
+            sid,
+            results,
+            tx,
+            pending: 0,
+        })
+    }
+}
+
+/// The payloads of a requester stream within its budget.
+struct Budgeted {
+    flow: Flow,
+    sid: u32,
+    results: Flux<Result<Payload, RSocketError>>,
+    tx: TxBounded<Frame>,
+    // payloads to request once the outbound queue has room.
+    pending: u32,
+}
+
+impl Budgeted {
+    fn send_request_n(&mut self, cx: &mut Context<'_>) {
+        if self.pending == 0 {
+            return;
+        }
+        match self.tx.poll_ready(cx) {
+            Poll::Ready(Ok(())) => {
+                let sending = frame::RequestN::builder(self.sid, 0)
+                    .set_n(self.pending)
+                    .build();
+                if let Err(e) = self.tx.try_send(sending) {
+                    error!("send REQUEST_N failed: {}", e);
+                }
+                self.pending = 0;
+            }
+            // the connection is closed.
+            Poll::Ready(Err(_)) => self.pending = 0,
+            Poll::Pending => (),
+        }
+    }
+}
+
+impl Stream for Budgeted {
+    type Item = Result<Payload, RSocketError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.send_request_n(cx);
+        let next = ready!(self.results.poll_next_unpin(cx));
+        if let Some(Ok(payload)) = &next {
+            if let Some(n) = self.flow.on_consume(self.sid, payload.len()) {
+                self.pending = self.pending.saturating_add(n).min(REQUEST_MAX);
+                self.send_request_n(cx);
+            }
+        }
+        Poll::Ready(next)
+    }
+}
+
+impl Drop for Budgeted {
+    fn drop(&mut self) {
+        self.flow.close(self.sid);
+    }
+}
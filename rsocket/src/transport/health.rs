@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+/// KEEPALIVE round-trip time at which the latency factor is one half.
+const RTT_REFERENCE: Duration = Duration::from_millis(500);
+
+/// How healthy a connection is, scored from 0 to 1, so a connection which degrades is
+/// deprioritized before it fails outright.
+///
+/// The score is the product of three factors: the latency factor of the KEEPALIVE round-trip
+/// time, one minus the error rate of the streams which ended recently, and one minus half the
+/// saturation, the share of responder streams stalled by the lack of demand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Health {
+    latency: f64,
+    error_rate: f64,
+    saturation: f64,
+}
+
+impl Health {
+    pub(crate) fn new(
+        rtt: Option<Duration>,
+        failed: usize,
+        ended: usize,
+        stalled: usize,
+        active: usize,
+    ) -> Health {
+        let latency = match rtt {
+            Some(rtt) => {
+                let reference = RTT_REFERENCE.as_secs_f64();
+                reference / (reference + rtt.as_secs_f64())
+            }
+            None => 1.0,
+        };
+        Health {
+            latency,
+            error_rate: ratio(failed, ended),
+            saturation: ratio(stalled, active),
+        }
+    }
+
+    pub fn get_score(&self) -> f64 {
+        self.latency * (1.0 - self.error_rate) * (1.0 - self.saturation / 2.0)
+    }
+
+    /// Returns the factor of the KEEPALIVE round-trip time, 1 until it is measured.
+    pub fn get_latency(&self) -> f64 {
+        self.latency
+    }
+
+    /// Returns the share of failed streams among the streams which ended recently.
+    pub fn get_error_rate(&self) -> f64 {
+        self.error_rate
+    }
+
+    /// Returns the share of active streams stalled by the lack of demand.
+    pub fn get_saturation(&self) -> f64 {
+        self.saturation
+    }
+}
+
+fn ratio(n: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (n as f64 / total as f64).min(1.0)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::frame::Frame;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The last activity of the streams of a connection, by the frames of either direction.
+/// Streams without frames for longer than the timeout are expired.
+#[derive(Debug, Clone)]
+pub(crate) struct IdleStreams {
+    timeout: Duration,
+    active: Arc<Mutex<HashMap<u32, Instant>>>,
+}
+
+impl IdleStreams {
+    pub(crate) fn new(timeout: Duration) -> IdleStreams {
+        IdleStreams {
+            timeout,
+            active: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub(crate) fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub(crate) fn on_frame(&self, frame: &Frame) {
+        let sid = frame.get_stream_id();
+        if sid == 0 {
+            return;
+        }
+        self.active.lock().unwrap().insert(sid, Instant::now());
+    }
+
+    /// Returns the streams of `alive` which are idle for longer than the timeout, the
+    /// activity of the other streams is forgotten. A stream seen for the first time is active.
+    pub(crate) fn expired(&self, alive: &[u32]) -> Vec<u32> {
+        let now = Instant::now();
+        let mut active = self.active.lock().unwrap();
+        let mut expired = vec![];
+        let mut kept = HashMap::with_capacity(alive.len());
+        for sid in alive {
+            let last = active.get(sid).copied().unwrap_or(now);
+            if now - last >= self.timeout {
+                expired.push(*sid);
+            } else {
+                kept.insert(*sid, last);
+            }
+        }
+        *active = kept;
+        expired
+    }
+}
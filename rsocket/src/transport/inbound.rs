@@ -0,0 +1,95 @@
+use super::spi::{new_tx_rx_bounded, RxBounded, TxBounded};
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::spi::Flux;
+use futures::task::{Context, Poll};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// The payloads of the peer for a requester stream or channel, buffered up to a capacity
+/// until they are consumed.
+///
+/// The error which terminates them is kept aside rather than buffered, so it is never lost
+/// to a full buffer: it is delivered once the payloads before it are consumed.
+#[derive(Debug, Clone)]
+pub(crate) struct Inbound {
+    tx: TxBounded<Payload>,
+    error: Arc<Mutex<Option<RSocketError>>>,
+}
+
+/// The outcome of forwarding a payload to the consumer of a stream.
+pub(crate) enum Delivery {
+    Sent,
+    /// The buffer is full, the payload is to be sent by `Pending::send` once it has room.
+    Pending(Pending),
+    /// Nobody consumes the payloads anymore.
+    Closed,
+}
+
+pub(crate) struct Pending {
+    tx: TxBounded<Payload>,
+    input: Payload,
+}
+
+struct Consumer {
+    rx: RxBounded<Payload>,
+    error: Arc<Mutex<Option<RSocketError>>>,
+}
+
+impl Inbound {
+    /// Returns the sending side of new inbound payloads and their consumer.
+    pub(crate) fn new(capacity: usize) -> (Inbound, Flux<Result<Payload, RSocketError>>) {
+        let (tx, rx) = new_tx_rx_bounded(capacity);
+        let error = Arc::new(Mutex::new(None));
+        let consumer = Consumer {
+            rx,
+            error: error.clone(),
+        };
+        (Inbound { tx, error }, Box::pin(consumer))
+    }
+
+    /// Returns inbound payloads which consist of `e` alone.
+    pub(crate) fn failed(e: RSocketError) -> Flux<Result<Payload, RSocketError>> {
+        Box::pin(futures::stream::iter(Some(Err(e))))
+    }
+
+    /// Forward `input` without waiting for room in the buffer.
+    pub(crate) fn deliver(&mut self, input: Payload) -> Delivery {
+        match self.tx.try_send(input) {
+            Ok(()) => Delivery::Sent,
+            Err(tokio::sync::mpsc::error::TrySendError::Full(input)) => {
+                Delivery::Pending(Pending {
+                    tx: self.tx.clone(),
+                    input,
+                })
+            }
+            Err(_) => Delivery::Closed,
+        }
+    }
+
+    /// End the payloads with `e`, after those which are buffered.
+    pub(crate) fn fail(self, e: RSocketError) {
+        self.error.lock().unwrap().get_or_insert(e);
+    }
+}
+
+impl Pending {
+    /// Wait for room in the buffer and send the payload, returns false if nobody consumes
+    /// the payloads anymore.
+    pub(crate) async fn send(mut self) -> bool {
+        self.tx.send(self.input).await.is_ok()
+    }
+}
+
+impl Stream for Consumer {
+    type Item = Result<Payload, RSocketError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.rx.poll_next_unpin(cx) {
+            Poll::Ready(Some(it)) => Poll::Ready(Some(Ok(it))),
+            Poll::Ready(None) => Poll::Ready(self.error.lock().unwrap().take().map(Err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
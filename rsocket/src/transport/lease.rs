@@ -0,0 +1,194 @@
+use crate::error::{self, ErrorKind, RSocketError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub(crate) type LoadSignal = Arc<dyn Fn() -> f64 + Send + Sync>;
+
+/// How a server grants leases to the clients which negotiated leasing, sized by its load so
+/// the clients back off before their requests time out.
+///
+/// A lease of `ttl` is renewed when half of it elapsed. It allows `max_requests` requests
+/// while the server is idle, fewer as the load grows, and none once it is overloaded: the
+/// lease is withheld and the current one lapses.
+#[derive(Clone)]
+pub struct LeasePolicy {
+    ttl: Duration,
+    max_requests: u32,
+    max_in_flight: Option<usize>,
+    load: Option<LoadSignal>,
+}
+
+impl LeasePolicy {
+    /// Panics if `ttl` is zero.
+    pub fn new(ttl: Duration, max_requests: u32) -> LeasePolicy {
+        assert!(ttl > Duration::from_secs(0), "lease ttl must be positive");
+        LeasePolicy {
+            ttl,
+            max_requests,
+            max_in_flight: None,
+            load: None,
+        }
+    }
+
+    /// Lease no more requests than `max` minus those in flight on the connection.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Shrink the leases by the load `load` returns, from 0 when idle to 1 when overloaded,
+    /// such as the CPU usage or the depth of a work queue.
+    pub fn load<F>(mut self, load: F) -> Self
+    where
+        F: Fn() -> f64 + Send + Sync + 'static,
+    {
+        self.load = Some(Arc::new(load));
+        self
+    }
+
+    pub fn get_ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn get_max_requests(&self) -> u32 {
+        self.max_requests
+    }
+
+    /// Returns the number of requests to lease with `in_flight` requests in flight, 0 to
+    /// withhold the lease.
+    pub(crate) fn lease_size(&self, in_flight: usize) -> u32 {
+        let pressure = match &self.load {
+            Some(load) => load(),
+            None => 0.0,
+        };
+        let mut size = self.max_requests;
+        if let Some(max) = self.max_in_flight {
+            let headroom = max.saturating_sub(in_flight);
+            if headroom < size as usize {
+                size = headroom as u32;
+            }
+        }
+        if pressure.is_nan() || pressure >= 1.0 {
+            return 0;
+        }
+        let scaled = (f64::from(self.max_requests) * (1.0 - pressure.max(0.0))).ceil() as u32;
+        size.min(scaled)
+    }
+}
+
+/// A snapshot of the leases granted by the peer and of the requests sent under them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LeaseStats {
+    leases_granted: u64,
+    requests_allowed: u64,
+    requests_rejected: u64,
+    remaining_requests: u32,
+    remaining_ttl: Duration,
+}
+
+impl LeaseStats {
+    /// Returns the number of LEASE frames received.
+    pub fn get_leases_granted(&self) -> u64 {
+        self.leases_granted
+    }
+
+    pub fn get_requests_allowed(&self) -> u64 {
+        self.requests_allowed
+    }
+
+    /// Returns the number of requests failed locally for lack of a valid lease.
+    pub fn get_requests_rejected(&self) -> u64 {
+        self.requests_rejected
+    }
+
+    /// Returns the number of requests the current lease still allows, 0 once it expired.
+    pub fn get_remaining_requests(&self) -> u32 {
+        self.remaining_requests
+    }
+
+    pub fn get_remaining_ttl(&self) -> Duration {
+        self.remaining_ttl
+    }
+}
+
+/// Requests allowed by the leases of one end, those of the peer or those granted to it.
+#[derive(Debug, Clone)]
+pub(crate) struct LeaseTracker {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    leases_granted: u64,
+    requests_allowed: u64,
+    requests_rejected: u64,
+    allowance: u32,
+    expires_at: Option<Instant>,
+}
+
+impl LeaseTracker {
+    pub(crate) fn new() -> LeaseTracker {
+        LeaseTracker {
+            inner: Arc::new(Mutex::new(Inner {
+                leases_granted: 0,
+                requests_allowed: 0,
+                requests_rejected: 0,
+                allowance: 0,
+                expires_at: None,
+            })),
+        }
+    }
+
+    /// A new lease replaces the current one.
+    pub(crate) fn on_lease(&self, ttl: Duration, number_of_requests: u32) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.leases_granted += 1;
+        inner.allowance = number_of_requests;
+        inner.expires_at = Some(Instant::now() + ttl);
+    }
+
+    /// Take one request of the current lease, returns the number of requests left.
+    pub(crate) fn acquire(&self) -> Result<u32, RSocketError> {
+        let mut inner = self.inner.lock().unwrap();
+        let rejected = match inner.expires_at {
+            None => Some("no lease granted"),
+            Some(at) if at <= Instant::now() => Some("lease expired"),
+            Some(_) if inner.allowance == 0 => Some("lease exhausted"),
+            Some(_) => None,
+        };
+        match rejected {
+            Some(msg) => {
+                inner.requests_rejected += 1;
+                Err(RSocketError::from(ErrorKind::Internal(
+                    error::ERR_REJECTED,
+                    String::from(msg),
+                )))
+            }
+            None => {
+                inner.requests_allowed += 1;
+                inner.allowance -= 1;
+                Ok(inner.allowance)
+            }
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> LeaseStats {
+        let inner = self.inner.lock().unwrap();
+        let remaining_ttl = inner
+            .expires_at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+            .unwrap_or_default();
+        LeaseStats {
+            leases_granted: inner.leases_granted,
+            requests_allowed: inner.requests_allowed,
+            requests_rejected: inner.requests_rejected,
+            remaining_requests: if remaining_ttl > Duration::from_secs(0) {
+                inner.allowance
+            } else {
+                0
+            },
+            remaining_ttl,
+        }
+    }
+}
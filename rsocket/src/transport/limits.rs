@@ -0,0 +1,107 @@
+use crate::frame::{Body, Frame};
+
+/// Maximum sizes of the data and the metadata of a payload, both unlimited by default.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct SizeLimit {
+    data: Option<usize>,
+    metadata: Option<usize>,
+}
+
+/// Size limits of the payloads the peer sends, per interaction model.
+///
+/// A request which exceeds them is rejected with ERROR(REJECTED), and so is a payload of a
+/// channel, which also terminates the channel. A SETUP is rejected with
+/// ERROR(REJECTED_SETUP). Fire-and-forget and METADATA_PUSH frames are dropped.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PayloadLimits {
+    setup: SizeLimit,
+    fire_and_forget: SizeLimit,
+    request_response: SizeLimit,
+    stream: SizeLimit,
+    metadata_push: SizeLimit,
+}
+
+impl SizeLimit {
+    pub fn new() -> SizeLimit {
+        SizeLimit::default()
+    }
+
+    pub fn max_data(mut self, size: usize) -> Self {
+        self.data = Some(size);
+        self
+    }
+
+    pub fn max_metadata(mut self, size: usize) -> Self {
+        self.metadata = Some(size);
+        self
+    }
+
+    pub fn get_max_data(&self) -> Option<usize> {
+        self.data
+    }
+
+    pub fn get_max_metadata(&self) -> Option<usize> {
+        self.metadata
+    }
+
+    fn check(&self, frame: &Frame) -> Result<(), String> {
+        let exceeds = |part: &str, len: usize, max: Option<usize>| match max {
+            Some(max) if len > max => Err(format!("{} of {} bytes exceeds {}", part, len, max)),
+            _ => Ok(()),
+        };
+        exceeds("data", frame.get_data().map_or(0, |it| it.len()), self.data)?;
+        exceeds(
+            "metadata",
+            frame.get_metadata().map_or(0, |it| it.len()),
+            self.metadata,
+        )
+    }
+}
+
+impl PayloadLimits {
+    pub fn new() -> PayloadLimits {
+        PayloadLimits::default()
+    }
+
+    pub fn setup(mut self, limit: SizeLimit) -> Self {
+        self.setup = limit;
+        self
+    }
+
+    pub fn fire_and_forget(mut self, limit: SizeLimit) -> Self {
+        self.fire_and_forget = limit;
+        self
+    }
+
+    pub fn request_response(mut self, limit: SizeLimit) -> Self {
+        self.request_response = limit;
+        self
+    }
+
+    /// Limit the requests of streams and channels, and the payloads of channels.
+    pub fn stream(mut self, limit: SizeLimit) -> Self {
+        self.stream = limit;
+        self
+    }
+
+    pub fn metadata_push(mut self, limit: SizeLimit) -> Self {
+        self.metadata_push = limit;
+        self
+    }
+
+    /// Check the payload of a frame of the peer, whose requests use the stream IDs of
+    /// `peer_parity`. Returns the violation, if any.
+    pub(crate) fn check(&self, frame: &Frame, peer_parity: u32) -> Result<(), String> {
+        let limit = match frame.get_body_ref() {
+            Body::Setup(_) => &self.setup,
+            Body::RequestFNF(_) => &self.fire_and_forget,
+            Body::RequestResponse(_) => &self.request_response,
+            Body::RequestStream(_) | Body::RequestChannel(_) => &self.stream,
+            // payloads of the peer on its own streams belong to channels.
+            Body::Payload(_) if frame.get_stream_id() % 2 == peer_parity => &self.stream,
+            Body::MetadataPush(_) => &self.metadata_push,
+            _ => return Ok(()),
+        };
+        limit.check(frame)
+    }
+}
@@ -0,0 +1,194 @@
+//! Connection, stream and frame metrics recorded through the `metrics` facade.
+//!
+//! Exported metrics:
+//!   - `rsocket_connections{side}` (gauge)
+//!   - `rsocket_active_streams{side}` (gauge)
+//!   - `rsocket_requests_total{side, role, interaction}` (counter)
+//!   - `rsocket_errors_total{side, code}` (counter)
+//!   - `rsocket_frames_total{side, direction, type}` (counter)
+//!   - `rsocket_bytes_total{side, direction}` (counter)
+//!   - `rsocket_frame_bytes{side, direction}` (histogram)
+//!   - `rsocket_slow_consumers_total{side, policy}` (counter)
+//!   - `rsocket_stream_pool_total{side, outcome}` (counter, `outcome` is `hit` or `miss`)
+//!   - `rsocket_request_duration_seconds{side, interaction, route, outcome}` (histogram)
+//!   - `rsocket_leases_total{side}` (counter)
+//!   - `rsocket_lease_requests_total{side, outcome}` (counter, `outcome` is `allowed` or `rejected`)
+//!   - `rsocket_lease_remaining_requests{side}` (gauge)
+use super::demand::SlowConsumerPolicy;
+#[cfg(feature = "metrics")]
+use crate::frame::Body;
+use crate::frame::Frame;
+#[cfg(feature = "metrics")]
+use crate::utils::Writeable;
+#[cfg(feature = "metrics")]
+use std::collections::HashSet;
+#[cfg(feature = "metrics")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug)]
+pub(crate) struct Metrics {
+    side: &'static str,
+    streams: Arc<Mutex<HashSet<u32>>>,
+}
+
+#[cfg(not(feature = "metrics"))]
+#[derive(Clone, Debug)]
+pub(crate) struct Metrics;
+
+#[cfg(feature = "metrics")]
+impl Metrics {
+    pub(crate) fn new(first_stream_id: u32) -> Metrics {
+        let side = if first_stream_id & 1 == 1 {
+            "client"
+        } else {
+            "server"
+        };
+        ::metrics::gauge!("rsocket_connections", "side" => side).increment(1.0);
+        Metrics {
+            side,
+            streams: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub(crate) fn on_inbound(&self, frame: &Frame) {
+        self.on_frame("inbound", "responder", frame);
+    }
+
+    pub(crate) fn on_outbound(&self, frame: &Frame) {
+        self.on_frame("outbound", "requester", frame);
+    }
+
+    pub(crate) fn on_slow_consumer(&self, policy: SlowConsumerPolicy) {
+        let policy = match policy {
+            SlowConsumerPolicy::Notify => "notify",
+            SlowConsumerPolicy::Error => "error",
+            SlowConsumerPolicy::DropOldest(_) => "drop_oldest",
+        };
+        ::metrics::counter!("rsocket_slow_consumers_total", "side" => self.side, "policy" => policy)
+            .increment(1);
+    }
+
+    pub(crate) fn on_stream_pool(&self, hit: bool) {
+        let outcome = if hit { "hit" } else { "miss" };
+        ::metrics::counter!("rsocket_stream_pool_total", "side" => self.side, "outcome" => outcome)
+            .increment(1);
+    }
+
+    #[cfg(feature = "lease")]
+    pub(crate) fn on_lease(&self, number_of_requests: u32) {
+        ::metrics::counter!("rsocket_leases_total", "side" => self.side).increment(1);
+        ::metrics::gauge!("rsocket_lease_remaining_requests", "side" => self.side)
+            .set(f64::from(number_of_requests));
+    }
+
+    #[cfg(feature = "lease")]
+    pub(crate) fn on_lease_request(&self, remaining: Option<u32>) {
+        let outcome = if remaining.is_some() {
+            "allowed"
+        } else {
+            "rejected"
+        };
+        ::metrics::counter!("rsocket_lease_requests_total", "side" => self.side, "outcome" => outcome)
+            .increment(1);
+        if let Some(n) = remaining {
+            ::metrics::gauge!("rsocket_lease_remaining_requests", "side" => self.side)
+                .set(f64::from(n));
+        }
+    }
+
+    pub(crate) fn on_close(&self) {
+        let mut streams = self.streams.lock().unwrap();
+        ::metrics::gauge!("rsocket_active_streams", "side" => self.side)
+            .decrement(streams.len() as f64);
+        streams.clear();
+        ::metrics::gauge!("rsocket_connections", "side" => self.side).decrement(1.0);
+    }
+
+    fn on_frame(&self, direction: &'static str, role: &'static str, frame: &Frame) {
+        let side = self.side;
+        let size = frame.len() as u64;
+        ::metrics::counter!(
+            "rsocket_frames_total",
+            "side" => side,
+            "direction" => direction,
+            "type" => frame.get_frame_type_name()
+        )
+        .increment(1);
+        ::metrics::counter!("rsocket_bytes_total", "side" => side, "direction" => direction)
+            .increment(size);
+        ::metrics::histogram!("rsocket_frame_bytes", "side" => side, "direction" => direction)
+            .record(size as f64);
+
+        let sid = frame.get_stream_id();
+        let (interaction, opened, closed) = match frame.get_body_ref() {
+            Body::RequestFNF(_) => (Some("fire_and_forget"), false, false),
+            Body::RequestResponse(_) => (Some("request_response"), true, false),
+            Body::RequestStream(_) => (Some("request_stream"), true, false),
+            Body::RequestChannel(_) => (Some("request_channel"), true, false),
+            Body::MetadataPush(_) => (Some("metadata_push"), false, false),
+            Body::Payload(_) => (None, false, frame.has_complete()),
+            Body::Error(e) => {
+                ::metrics::counter!(
+                    "rsocket_errors_total",
+                    "side" => side,
+                    "code" => format!("0x{:08X}", e.get_code())
+                )
+                .increment(1);
+                (None, false, true)
+            }
+            Body::Cancel() => (None, false, true),
+            _ => (None, false, false),
+        };
+        if let Some(interaction) = interaction {
+            ::metrics::counter!(
+                "rsocket_requests_total",
+                "side" => side,
+                "role" => role,
+                "interaction" => interaction
+            )
+            .increment(1);
+        }
+        if sid == 0 {
+            return;
+        }
+        let gauge = ::metrics::gauge!("rsocket_active_streams", "side" => side);
+        let mut streams = self.streams.lock().unwrap();
+        if opened && streams.insert(sid) {
+            gauge.increment(1.0);
+        } else if closed && streams.remove(&sid) {
+            gauge.decrement(1.0);
+        }
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+impl Metrics {
+    #[inline]
+    pub(crate) fn new(_first_stream_id: u32) -> Metrics {
+        Metrics
+    }
+
+    #[inline]
+    pub(crate) fn on_inbound(&self, _frame: &Frame) {}
+
+    #[inline]
+    pub(crate) fn on_outbound(&self, _frame: &Frame) {}
+
+    #[inline]
+    pub(crate) fn on_slow_consumer(&self, _policy: SlowConsumerPolicy) {}
+
+    #[inline]
+    pub(crate) fn on_stream_pool(&self, _hit: bool) {}
+
+    #[cfg(feature = "lease")]
+    #[inline]
+    pub(crate) fn on_lease(&self, _number_of_requests: u32) {}
+
+    #[cfg(feature = "lease")]
+    #[inline]
+    pub(crate) fn on_lease_request(&self, _remaining: Option<u32>) {}
+
+    #[inline]
+    pub(crate) fn on_close(&self) {}
+}
@@ -1,3 +1,4 @@
+use super::pool::Pooled;
 use crate::error::{ErrorKind, RSocketError};
 use crate::frame::{self};
 use crate::payload::{Payload, SetupPayload};
@@ -35,14 +36,23 @@ impl From<u32> for StreamID {
 
 #[derive(Debug, Clone)]
 pub(crate) struct Counter {
-    inner: Arc<AtomicI64>,
+    inner: Pooled<AtomicI64>,
+}
+
+impl From<Pooled<AtomicI64>> for Counter {
+    fn from(inner: Pooled<AtomicI64>) -> Counter {
+        Counter { inner }
+    }
 }
 
 impl Counter {
     pub(crate) fn new(value: i64) -> Counter {
-        Counter {
-            inner: Arc::new(AtomicI64::new(value)),
-        }
+        Counter::from(Pooled::new(AtomicI64::new(value)))
+    }
+
+    pub(crate) fn reuse(inner: Pooled<AtomicI64>, value: i64) -> Counter {
+        inner.store(value, Ordering::SeqCst);
+        Counter { inner }
     }
 
     pub(crate) fn count_down(&self) -> i64 {
@@ -1,6 +1,49 @@
+#[cfg(feature = "extension")]
+mod authentication;
+mod channel;
+mod demand;
+mod diagnostics;
+mod execution;
+mod flow;
+mod health;
+mod idle;
+mod inbound;
+#[cfg(feature = "lease")]
+mod lease;
+mod limits;
+mod metrics;
 mod misc;
+mod params;
+mod pool;
+#[cfg(feature = "resume")]
+mod resume;
+mod scheduler;
+mod sink;
 mod socket;
+mod spans;
 mod spi;
+mod stats;
+mod streams;
+mod tasks;
+mod throttle;
+mod validation;
 
-pub(crate) use socket::DuplexSocket;
+#[cfg(feature = "extension")]
+pub use authentication::Authenticator;
+pub use demand::{OverflowPolicy, SlowConsumerPolicy};
+pub(crate) use demand::{SlowConsumer, StreamBuffer};
+pub use execution::{Execution, WorkerPool};
+pub use flow::ByteBudget;
+pub use health::Health;
+#[cfg(feature = "lease")]
+pub use lease::{LeasePolicy, LeaseStats};
+pub use limits::{PayloadLimits, SizeLimit};
+pub use params::ConnectionParams;
+#[cfg(feature = "resume")]
+pub use resume::{Reconnected, ResumeSession};
+pub use sink::ChannelSink;
+pub(crate) use socket::{DuplexSocket, SocketOptions};
 pub use spi::*;
+pub use stats::ConnectionStats;
+pub use throttle::FrameRate;
+pub use validation::ValidationMode;
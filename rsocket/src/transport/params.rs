@@ -0,0 +1,56 @@
+use crate::frame::{self, Setup, Version};
+use std::time::Duration;
+
+/// Parameters of a connection as negotiated by its SETUP.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionParams {
+    version: Version,
+    data_mime_type: String,
+    metadata_mime_type: String,
+    keepalive_interval: Duration,
+    max_lifetime: Duration,
+    lease: bool,
+    resume: bool,
+}
+
+impl ConnectionParams {
+    pub(crate) fn new(setup: &Setup, flag: u16) -> ConnectionParams {
+        ConnectionParams {
+            version: setup.get_version(),
+            data_mime_type: setup.get_mime_data().clone(),
+            metadata_mime_type: setup.get_mime_metadata().clone(),
+            keepalive_interval: setup.get_keepalive(),
+            max_lifetime: setup.get_lifetime(),
+            lease: flag & frame::FLAG_LEASE != 0,
+            resume: flag & frame::FLAG_RESUME != 0,
+        }
+    }
+
+    pub fn get_version(&self) -> Version {
+        self.version
+    }
+
+    pub fn get_data_mime_type(&self) -> &String {
+        &self.data_mime_type
+    }
+
+    pub fn get_metadata_mime_type(&self) -> &String {
+        &self.metadata_mime_type
+    }
+
+    pub fn get_keepalive_interval(&self) -> Duration {
+        self.keepalive_interval
+    }
+
+    pub fn get_max_lifetime(&self) -> Duration {
+        self.max_lifetime
+    }
+
+    pub fn is_lease_enabled(&self) -> bool {
+        self.lease
+    }
+
+    pub fn is_resume_enabled(&self) -> bool {
+        self.resume
+    }
+}
@@ -0,0 +1,163 @@
+use super::demand::{Demand, DemandState};
+use super::metrics::Metrics;
+use super::misc::Counter;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub(crate) const DEFAULT_STREAM_POOL_SIZE: usize = 256;
+
+/// Free list of shared per-stream states, a value goes back once its last handle is dropped.
+#[derive(Debug)]
+pub(crate) struct Pool<T> {
+    inner: Arc<PoolInner<T>>,
+}
+
+#[derive(Debug)]
+struct PoolInner<T> {
+    capacity: usize,
+    free: Mutex<Vec<Arc<T>>>,
+}
+
+/// A shared value which may come from and goes back to a `Pool`.
+#[derive(Debug)]
+pub(crate) struct Pooled<T> {
+    value: Arc<T>,
+    pool: Option<Pool<T>>,
+}
+
+/// Pools of the states allocated for every request of a connection.
+#[derive(Debug, Clone)]
+pub(crate) struct StreamPool {
+    counters: Pool<AtomicI64>,
+    demands: Pool<DemandState>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    metrics: Metrics,
+}
+
+impl<T> Clone for Pool<T> {
+    fn clone(&self) -> Self {
+        Pool {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Pool<T> {
+    pub(crate) fn new(capacity: usize) -> Pool<T> {
+        Pool {
+            inner: Arc::new(PoolInner {
+                capacity,
+                free: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub(crate) fn take(&self) -> Option<Arc<T>> {
+        self.inner.free.lock().unwrap().pop()
+    }
+
+    pub(crate) fn wrap(&self, value: Arc<T>) -> Pooled<T> {
+        Pooled {
+            value,
+            pool: if self.inner.capacity > 0 {
+                Some(self.clone())
+            } else {
+                None
+            },
+        }
+    }
+
+    fn put(&self, value: Arc<T>) {
+        let mut free = self.inner.free.lock().unwrap();
+        if free.len() < self.inner.capacity {
+            free.push(value);
+        }
+    }
+}
+
+impl<T> Pooled<T> {
+    pub(crate) fn new(value: T) -> Pooled<T> {
+        Pooled {
+            value: Arc::new(value),
+            pool: None,
+        }
+    }
+}
+
+impl<T> Clone for Pooled<T> {
+    fn clone(&self) -> Self {
+        Pooled {
+            value: self.value.clone(),
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+impl<T> Deref for Pooled<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> Drop for Pooled<T> {
+    fn drop(&mut self) {
+        // no other handle can be cloned from the last one while it is dropping.
+        if let Some(pool) = &self.pool {
+            if Arc::strong_count(&self.value) == 1 {
+                pool.put(self.value.clone());
+            }
+        }
+    }
+}
+
+impl StreamPool {
+    pub(crate) fn new(capacity: usize, metrics: Metrics) -> StreamPool {
+        StreamPool {
+            counters: Pool::new(capacity),
+            demands: Pool::new(capacity),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            metrics,
+        }
+    }
+
+    pub(crate) fn counter(&self, value: i64) -> Counter {
+        match self.record(self.counters.take()) {
+            Some(it) => Counter::reuse(self.counters.wrap(it), value),
+            None => Counter::from(self.counters.wrap(Arc::new(AtomicI64::new(value)))),
+        }
+    }
+
+    pub(crate) fn demand(&self, initial_request_n: u32) -> Demand {
+        match self.record(self.demands.take()) {
+            Some(it) => Demand::reuse(self.demands.wrap(it), initial_request_n),
+            None => Demand::from(
+                self.demands
+                    .wrap(Arc::new(DemandState::new(initial_request_n))),
+            ),
+        }
+    }
+
+    pub(crate) fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    fn record<T>(&self, taken: Option<T>) -> Option<T> {
+        let hit = taken.is_some();
+        if hit {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        self.metrics.on_stream_pool(hit);
+        taken
+    }
+}
@@ -0,0 +1,137 @@
+use super::socket::{connection_closed, from_error_frame};
+use super::spi::{Rx, TxBounded};
+use crate::error::{self, ErrorKind, RSocketError};
+use crate::frame::{self, Body, Frame};
+use bytes::Bytes;
+use futures::StreamExt;
+use std::time::Duration;
+
+const DEFAULT_RESUME_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A session a client resumes by RESUME rather than starting one by SETUP.
+///
+/// The server is to answer with RESUME_OK within the timeout, 5 seconds by default. The
+/// connection fails if it does not, or rejects the RESUME, unless the client falls back to a
+/// fresh SETUP, see `ClientBuilder::resume_fallback`.
+///
+/// Resuming only means reconnecting with the same token: the client keeps no frames to
+/// replay, so the RESUME_OK position may not be before `first_available_client_position`,
+/// and stream IDs start at 1 again. The server of this crate keeps no sessions and rejects
+/// every RESUME with REJECTED_RESUME.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ResumeSession {
+    token: Bytes,
+    last_received_server_position: u64,
+    first_available_client_position: u64,
+    timeout: Duration,
+}
+
+/// How a client connection with a `ResumeSession` was established.
+#[derive(Debug)]
+pub enum Reconnected {
+    /// The session was resumed, the server had received the frames of the client up to
+    /// `position`.
+    Resumed { position: u64 },
+    /// A fresh SETUP was sent instead, as the RESUME failed with `reason`.
+    Setup { reason: RSocketError },
+}
+
+impl ResumeSession {
+    pub fn new(token: Bytes) -> ResumeSession {
+        ResumeSession {
+            token,
+            last_received_server_position: 0,
+            first_available_client_position: 0,
+            timeout: DEFAULT_RESUME_TIMEOUT,
+        }
+    }
+
+    pub fn last_received_server_position(mut self, position: u64) -> Self {
+        self.last_received_server_position = position;
+        self
+    }
+
+    pub fn first_available_client_position(mut self, position: u64) -> Self {
+        self.first_available_client_position = position;
+        self
+    }
+
+    /// Wait for the answer of the server no longer than `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        assert!(timeout > Duration::from_secs(0), "timeout must be positive");
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn get_token(&self) -> &Bytes {
+        &self.token
+    }
+
+    pub fn get_last_received_server_position(&self) -> u64 {
+        self.last_received_server_position
+    }
+
+    pub fn get_first_available_client_position(&self) -> u64 {
+        self.first_available_client_position
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Send RESUME and wait for RESUME_OK, returns its position. A position the client has no
+    /// frames from fails the connection by CONNECTION_ERROR.
+    pub(crate) async fn handshake(
+        &self,
+        tx: &mut TxBounded<Frame>,
+        rx: &mut Rx<Frame>,
+    ) -> Result<u64, RSocketError> {
+        let sending = frame::Resume::builder(0, 0)
+            .set_token(self.token.clone())
+            .set_last_received_server_position(self.last_received_server_position)
+            .set_first_available_client_position(self.first_available_client_position)
+            .build();
+        if tx.send(sending).await.is_err() {
+            return Err(connection_closed());
+        }
+        let answer = match tokio::time::timeout(self.timeout, rx.next()).await {
+            Ok(Some(it)) => it,
+            Ok(None) => return Err(connection_closed()),
+            Err(_) => {
+                return Err(RSocketError::from(ErrorKind::Internal(
+                    error::ERR_CONN_FAILED,
+                    String::from("RESUME timed out"),
+                )))
+            }
+        };
+        let sid = answer.get_stream_id();
+        match answer.get_body() {
+            Body::ResumeOK(v) if v.get_position() >= self.first_available_client_position => {
+                Ok(v.get_position())
+            }
+            Body::ResumeOK(v) => {
+                let reason = format!(
+                    "RESUME_OK position {} is before the first available position {}",
+                    v.get_position(),
+                    self.first_available_client_position
+                );
+                let sending = frame::Error::builder(0, 0)
+                    .set_code(error::ERR_CONN_FAILED)
+                    .set_data(Bytes::from(reason.clone()))
+                    .build();
+                if tx.send(sending).await.is_err() {
+                    return Err(connection_closed());
+                }
+                Err(RSocketError::from(ErrorKind::Internal(
+                    error::ERR_CONN_FAILED,
+                    reason,
+                )))
+            }
+            Body::Error(e) if sid == 0 => Err(from_error_frame(&e)),
+            other => Err(RSocketError::from(ErrorKind::Internal(
+                error::ERR_INVALID,
+                format!("unexpected answer to RESUME: {:?}", other),
+            ))),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use super::spi::TxBounded;
+use super::throttle::Throttle;
+#[cfg(feature = "extension")]
+use crate::extension::{CompositeMetadata, PriorityMetadata};
+use crate::frame::{Body, Frame};
+#[cfg(feature = "extension")]
+use bytes::BytesMut;
+use futures::future;
+use futures::task::Poll;
+use futures::{Stream, StreamExt};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::time::{self, Delay, Instant};
+
+/// Frames of the connection itself go before the frames of any stream.
+const CONNECTION_PRIORITY: u16 = 0x100;
+
+/// Priorities of the active streams, set by the priority metadata of their requests and
+/// dropped once the stream terminates in any direction.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StreamPriorities {
+    inner: Arc<Mutex<HashMap<u32, u8>>>,
+}
+
+impl StreamPriorities {
+    /// Track a frame of either direction, returns the priority of its stream.
+    pub(crate) fn on_frame(&self, frame: &Frame) -> u16 {
+        let sid = frame.get_stream_id();
+        if sid == 0 {
+            return CONNECTION_PRIORITY;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        match frame.get_body_ref() {
+            Body::RequestResponse(_)
+            | Body::RequestStream(_)
+            | Body::RequestChannel(_)
+            | Body::RequestFNF(_) => {
+                let priority = Self::decode(frame);
+                if priority > 0 {
+                    inner.insert(sid, priority);
+                }
+                u16::from(priority)
+            }
+            Body::Cancel() | Body::Error(_) => inner.remove(&sid).map(u16::from).unwrap_or(0),
+            Body::Payload(_) if frame.has_complete() => {
+                inner.remove(&sid).map(u16::from).unwrap_or(0)
+            }
+            _ => inner.get(&sid).copied().map(u16::from).unwrap_or(0),
+        }
+    }
+
+    #[cfg(feature = "extension")]
+    fn decode(frame: &Frame) -> u8 {
+        let metadata = match frame.get_metadata() {
+            Some(it) => it,
+            None => return 0,
+        };
+        CompositeMetadata::decode(&mut BytesMut::from(metadata.as_ref()))
+            .ok()
+            .and_then(|composite| PriorityMetadata::from_composite(&composite).ok())
+            .flatten()
+            .map(|it| it.get_priority())
+            .unwrap_or(0)
+    }
+
+    #[cfg(not(feature = "extension"))]
+    fn decode(_frame: &Frame) -> u8 {
+        0
+    }
+}
+
+struct Queued {
+    priority: u16,
+    round: u64,
+    seq: u64,
+    frame: Frame,
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // frames of the same priority and round keep their order.
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.round.cmp(&self.round))
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// Outbound frames waiting for the transport writer, the frames of the highest priority
+/// streams are written first. Up to `capacity` frames are taken off the sending queue.
+///
+/// Without priorities every stream has the same priority. When `fair`, the streams of the
+/// same priority take turns by rounds, one frame each, instead of being written in the
+/// order their frames were sent, so a busy stream can not hold back the others.
+///
+/// With a throttle, the frames over its rates are held back in the queue while the others
+/// are written, the writer waits once all of them are.
+pub(crate) struct Scheduler {
+    priorities: Option<StreamPriorities>,
+    fair: bool,
+    queue: BinaryHeap<Queued>,
+    capacity: usize,
+    seq: u64,
+    // the round of the last queued frame of each stream which has frames queued.
+    rounds: HashMap<u32, u64>,
+    // the latest round of the written frames.
+    round: u64,
+    throttle: Option<Throttle>,
+    // wakes the writer once a throttled frame may be written.
+    delay: Option<Pin<Box<Delay>>>,
+}
+
+impl Scheduler {
+    pub(crate) fn new(
+        priorities: Option<StreamPriorities>,
+        fair: bool,
+        capacity: usize,
+        throttle: Option<Throttle>,
+    ) -> Scheduler {
+        Scheduler {
+            priorities,
+            fair,
+            queue: BinaryHeap::new(),
+            capacity,
+            seq: 0,
+            rounds: HashMap::new(),
+            round: 0,
+            throttle,
+            delay: None,
+        }
+    }
+
+    /// Returns the next frame to write once `tx` has room for it, None once `rx` is drained
+    /// and closed or `tx` is closed. The frame must be written by `try_send`.
+    pub(crate) async fn next<S>(&mut self, rx: &mut S, tx: &mut TxBounded<Frame>) -> Option<Frame>
+    where
+        S: Stream<Item = Frame> + Unpin,
+    {
+        future::poll_fn(|cx| {
+            let mut closed = false;
+            while !closed && self.queue.len() < self.capacity {
+                match rx.poll_next_unpin(cx) {
+                    Poll::Ready(Some(frame)) => self.push(frame),
+                    Poll::Ready(None) => closed = true,
+                    Poll::Pending => break,
+                }
+            }
+            if self.queue.is_empty() {
+                return if closed {
+                    Poll::Ready(None)
+                } else {
+                    Poll::Pending
+                };
+            }
+            match tx.poll_ready(cx) {
+                Poll::Ready(Ok(())) => (),
+                Poll::Ready(Err(_)) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+            loop {
+                let at = match self.pop(Instant::now()) {
+                    Ok(frame) => return Poll::Ready(Some(frame)),
+                    Err(at) => at,
+                };
+                if self.delay.as_ref().is_some_and(|it| it.deadline() != at) {
+                    self.delay = None;
+                }
+                let delay = self
+                    .delay
+                    .get_or_insert_with(|| Box::pin(time::delay_until(at)));
+                if delay.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                self.delay = None;
+            }
+        })
+        .await
+    }
+
+    fn push(&mut self, frame: Frame) {
+        let sid = frame.get_stream_id();
+        let priority = match &self.priorities {
+            Some(priorities) => priorities.on_frame(&frame),
+            None if sid == 0 => CONNECTION_PRIORITY,
+            None => 0,
+        };
+        let round = if self.fair {
+            // a stream joins the current round, or queues behind its own frames.
+            let round = match self.rounds.get(&sid) {
+                Some(last) => last + 1,
+                None => self.round,
+            };
+            self.rounds.insert(sid, round);
+            round
+        } else {
+            0
+        };
+        self.seq += 1;
+        self.queue.push(Queued {
+            priority,
+            round,
+            seq: self.seq,
+            frame,
+        });
+    }
+
+    /// Pops the next frame the throttle lets through, or returns when the earliest of them may
+    /// be written. The queue must not be empty.
+    fn pop(&mut self, now: Instant) -> Result<Frame, Instant> {
+        let throttle = match &mut self.throttle {
+            Some(it) => it,
+            None => {
+                let next = self.queue.pop().expect("queue is empty");
+                return Ok(self.take(next));
+            }
+        };
+        // frames of the connection come first, the others wait for the overall rate alike.
+        let overall = throttle.overall_ready_at(now);
+        let mut held = vec![];
+        let mut earliest: Option<Instant> = None;
+        let mut permitted = None;
+        while let Some(next) = self.queue.pop() {
+            let sid = next.frame.get_stream_id();
+            let at = if sid != 0 && overall > now {
+                overall
+            } else {
+                throttle.ready_at(&next.frame, now)
+            };
+            if at <= now {
+                permitted = Some(next);
+                break;
+            }
+            earliest = Some(earliest.map_or(at, |it| it.min(at)));
+            held.push(next);
+            if at == overall {
+                break;
+            }
+        }
+        self.queue.extend(held);
+        match permitted {
+            Some(next) => {
+                throttle.on_write(&next.frame, now);
+                Ok(self.take(next))
+            }
+            None => Err(earliest.expect("queue is empty")),
+        }
+    }
+
+    fn take(&mut self, next: Queued) -> Frame {
+        if self.fair {
+            self.round = self.round.max(next.round);
+            let sid = next.frame.get_stream_id();
+            if self.rounds.get(&sid) == Some(&next.round) {
+                self.rounds.remove(&sid);
+            }
+        }
+        next.frame
+    }
+}
@@ -0,0 +1,179 @@
+use super::demand::Demand;
+use super::socket::{on_outbound_end, to_error_frame, Handler, StreamEnd};
+use super::spi::TxBounded;
+use super::streams::StreamMap;
+use crate::error::{ErrorKind, RSocketError};
+use crate::frame::{self, Frame};
+use crate::payload::Payload;
+use futures::task::{Context, Poll};
+use futures::Sink;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Sink of the outbound payloads of a REQUEST_CHANNEL, the first payload is sent with
+/// the request and the others only as far as the responder requests them by REQUEST_N.
+///
+/// Closing or dropping the sink completes the outbound side of the channel. Once the channel
+/// is cancelled or failed, the sink fails with the error it terminated with.
+pub struct ChannelSink {
+    sid: u32,
+    tx: TxBounded<Frame>,
+    handlers: StreamMap<Handler>,
+    outbound: Demand,
+    started: bool,
+    closed: bool,
+    changed: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl ChannelSink {
+    pub(crate) fn new(
+        sid: u32,
+        tx: TxBounded<Frame>,
+        handlers: StreamMap<Handler>,
+        outbound: Demand,
+    ) -> ChannelSink {
+        ChannelSink {
+            sid,
+            tx,
+            handlers,
+            outbound,
+            started: false,
+            closed: false,
+            changed: None,
+        }
+    }
+
+    /// A sink of a channel which was never opened.
+    pub(crate) fn closed(
+        tx: TxBounded<Frame>,
+        handlers: StreamMap<Handler>,
+        outbound: Demand,
+    ) -> ChannelSink {
+        let mut sink = ChannelSink::new(0, tx, handlers, outbound);
+        sink.closed = true;
+        sink
+    }
+
+    /// Terminate the channel with `e`, which also ends the inbound payloads.
+    pub async fn error(mut self, e: RSocketError) {
+        if self.started && !self.outbound.is_cancelled() {
+            if let Err(e) = self.tx.send(to_error_frame(self.sid, &e)).await {
+                error!("send REQUEST_CHANNEL error failed: {}", e);
+            }
+        }
+        self.closed = true;
+        on_outbound_end(&self.handlers, self.sid, StreamEnd::Failed(e));
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        if self.started {
+            on_outbound_end(&self.handlers, self.sid, StreamEnd::Completed);
+        } else {
+            // the responder never knew the channel.
+            self.handlers.remove(self.sid);
+        }
+    }
+}
+
+impl Sink<Payload> for ChannelSink {
+    type Error = RSocketError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+        loop {
+            if this.closed {
+                return Poll::Ready(Err(RSocketError::from("REQUEST_CHANNEL is closed")));
+            }
+            if this.outbound.is_cancelled() {
+                let e = this.outbound.error();
+                return Poll::Ready(Err(
+                    e.unwrap_or_else(|| RSocketError::from(ErrorKind::Cancelled()))
+                ));
+            }
+            if !this.started || this.outbound.has_demand() {
+                break;
+            }
+            if this.changed.is_none() {
+                let outbound = this.outbound.clone();
+                this.changed = Some(Box::pin(async move { outbound.changed().await }));
+            }
+            match this.changed.as_mut().unwrap().as_mut().poll(cx) {
+                Poll::Ready(()) => this.changed = None,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        this.tx
+            .poll_ready(cx)
+            .map_err(|_| RSocketError::from("connection is closed"))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Payload) -> Result<(), Self::Error> {
+        let this = &mut *self;
+        let (d, m) = item.split();
+        let sending = if this.started {
+            if !this.outbound.try_acquire() {
+                return Err(RSocketError::from("REQUEST_CHANNEL is not requested"));
+            }
+            let mut bu = frame::Payload::builder(this.sid, frame::FLAG_NEXT);
+            if let Some(b) = d {
+                bu = bu.set_data(b);
+            }
+            if let Some(b) = m {
+                bu = bu.set_metadata(b);
+            }
+            bu.build()
+        } else {
+            this.started = true;
+            let mut bu = frame::RequestChannel::builder(this.sid, frame::FLAG_NEXT);
+            if let Some(b) = d {
+                bu = bu.set_data(b);
+            }
+            if let Some(b) = m {
+                bu = bu.set_metadata(b);
+            }
+            bu.build()
+        };
+        this.tx
+            .try_send(sending)
+            .map_err(|_| RSocketError::from("connection is closed"))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+        if this.closed {
+            return Poll::Ready(Ok(()));
+        }
+        if this.started && !this.outbound.is_cancelled() {
+            match this.tx.poll_ready(cx) {
+                Poll::Ready(Ok(())) => {
+                    let complete = frame::Payload::builder(this.sid, frame::FLAG_COMPLETE).build();
+                    let _ = this.tx.try_send(complete);
+                }
+                Poll::Ready(Err(_)) => (),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        this.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for ChannelSink {
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+        if self.started && !self.outbound.is_cancelled() {
+            let complete = frame::Payload::builder(self.sid, frame::FLAG_COMPLETE).build();
+            if self.tx.try_send(complete).is_err() {
+                warn!("complete dropped REQUEST_CHANNEL {} failed", self.sid);
+            }
+        }
+        self.close();
+    }
+}
@@ -1,23 +1,49 @@
+#[cfg(feature = "extension")]
+use super::authentication::{auth_of, Authenticator};
+use super::channel::Channel;
+use super::demand::{Demand, OverflowPolicy, SlowConsumer, SlowConsumerPolicy, StreamBuffer};
+use super::diagnostics::LeakDetector;
+use super::execution::Execution;
+use super::flow::{ByteBudget, Flow};
+use super::idle::IdleStreams;
+use super::inbound::{Delivery, Inbound};
+#[cfg(feature = "lease")]
+use super::lease::{LeasePolicy, LeaseTracker};
+use super::limits::PayloadLimits;
+use super::metrics::Metrics;
 use super::misc::{self, Counter, StreamID};
+use super::params::ConnectionParams;
+use super::pool::{StreamPool, DEFAULT_STREAM_POOL_SIZE};
+use super::scheduler::{Scheduler, StreamPriorities};
+use super::sink::ChannelSink;
+use super::spans;
 use super::spi::*;
+use super::stats::{ConnectionStats, StatsRecorder};
+use super::streams::StreamMap;
+use super::tasks::TaskGroup;
+use super::throttle::{FrameRate, Throttle};
+use super::validation::{self, ValidationMode};
 use crate::error::{self, ErrorKind, RSocketError};
 use crate::frame::{self, Body, Frame};
+use crate::interceptor::{CaptureRecorder, FrameLogger};
 use crate::payload::{Payload, SetupPayload};
 use crate::runtime::Spawner;
-use crate::spi::{EmptyRSocket, Flux, Mono, RSocket};
+use crate::spi::{Attributes, EmptyRSocket, Flux, Mono, RSocket, RequestContext};
 use crate::utils::RSocketResult;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use futures::{future, Sink, SinkExt, Stream, StreamExt};
-use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::env;
 use std::error::Error;
 use std::future::Future;
 use std::pin::Pin;
 use std::ptr;
 use std::result::Result;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use tokio::prelude::*;
-use tokio::sync::Mutex;
+use tokio::time::Instant;
 
 #[derive(Clone)]
 pub(crate) struct DuplexSocket<R>
@@ -27,9 +53,88 @@ where
     rt: R,
     seq: StreamID,
     responder: Responder,
-    tx: Tx<Frame>,
-    handlers: Arc<Mutex<HashMap<u32, Handler>>>,
+    tx: TxBounded<Frame>,
+    handlers: StreamMap<Handler>,
+    pool: StreamPool,
     canceller: Tx<u32>,
+    stream_capacity: usize,
+    metrics: Metrics,
+    stats: StatsRecorder,
+    frame_logger: Option<FrameLogger>,
+    capture: Option<CaptureRecorder>,
+    leaks: Option<LeakDetector>,
+    idle: Option<IdleStreams>,
+    slow_consumer: Option<SlowConsumer>,
+    stream_buffer: Option<StreamBuffer>,
+    on_metadata_push: Option<MetadataPushHandler>,
+    max_metadata_push_size: Option<usize>,
+    params: Arc<RwLock<Option<ConnectionParams>>>,
+    #[cfg(feature = "lease")]
+    lease: LeaseTracker,
+    #[cfg(feature = "lease")]
+    lease_policy: Option<LeasePolicy>,
+    #[cfg(feature = "lease")]
+    granted: LeaseTracker,
+    priorities: Option<StreamPriorities>,
+    flow: Option<Flow>,
+    execution: Execution,
+    tasks: TaskGroup,
+    peer: PeerInfo,
+    #[cfg(feature = "extension")]
+    authenticator: Option<Arc<dyn Authenticator>>,
+    principal: Arc<RwLock<Option<Principal>>>,
+    attributes: Attributes,
+    closed: Arc<AtomicBool>,
+    draining: Arc<AtomicBool>,
+    pings: Arc<Mutex<Vec<TxOnce<Duration>>>>,
+    keepalive_data: Option<KeepaliveData>,
+    on_keepalive: Option<KeepaliveHandler>,
+    #[cfg(feature = "lease")]
+    lease_requested: bool,
+    validation: ValidationMode,
+    payload_limits: PayloadLimits,
+    peer_parity: u32,
+}
+
+pub(crate) type MetadataPushHandler = Arc<dyn Fn(Payload) -> Mono<()> + Send + Sync>;
+pub(crate) type KeepaliveData = Arc<dyn Fn() -> Bytes + Send + Sync>;
+pub(crate) type KeepaliveHandler = Arc<dyn Fn(Bytes) + Send + Sync>;
+
+const DEFAULT_OUTBOUND_CAPACITY: usize = 1024;
+const DEFAULT_INBOUND_CAPACITY: usize = 1024;
+const DEFAULT_STREAM_CAPACITY: usize = 256;
+/// How often `close_gracefully` checks whether the streams ended.
+const DRAIN_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Default)]
+pub(crate) struct SocketOptions {
+    pub(crate) outbound_capacity: Option<usize>,
+    pub(crate) inbound_capacity: Option<usize>,
+    pub(crate) stream_capacity: Option<usize>,
+    pub(crate) stream_pool_size: Option<usize>,
+    pub(crate) frame_logger: Option<FrameLogger>,
+    pub(crate) capture: Option<CaptureRecorder>,
+    pub(crate) leak_threshold: Option<Duration>,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) slow_consumer: Option<SlowConsumer>,
+    pub(crate) stream_buffer: Option<StreamBuffer>,
+    pub(crate) on_metadata_push: Option<MetadataPushHandler>,
+    pub(crate) max_metadata_push_size: Option<usize>,
+    pub(crate) priority_scheduling: bool,
+    pub(crate) fair_scheduling: bool,
+    pub(crate) frame_rate: Option<FrameRate>,
+    pub(crate) byte_budget: Option<ByteBudget>,
+    pub(crate) execution: Execution,
+    pub(crate) keepalive_data: Option<KeepaliveData>,
+    pub(crate) on_keepalive: Option<KeepaliveHandler>,
+    #[cfg(feature = "lease")]
+    pub(crate) lease: bool,
+    #[cfg(feature = "lease")]
+    pub(crate) lease_policy: Option<LeasePolicy>,
+    pub(crate) validation: ValidationMode,
+    pub(crate) payload_limits: PayloadLimits,
+    #[cfg(feature = "extension")]
+    pub(crate) authenticator: Option<Arc<dyn Authenticator>>,
 }
 
 #[derive(Clone)]
@@ -38,47 +143,574 @@ struct Responder {
 }
 
 #[derive(Debug)]
-enum Handler {
+pub(crate) enum Handler {
     ReqRR(TxOnce<Result<Payload, RSocketError>>),
     ResRR(Counter),
-    ReqRS(Tx<Result<Payload, RSocketError>>),
-    ReqRC(Tx<Result<Payload, RSocketError>>),
+    ReqRS(Inbound),
+    ReqRC(Channel),
+    ResRS(Demand),
+    ResRC(Channel),
+}
+
+impl SocketOptions {
+    pub(crate) fn outbound_capacity(&self) -> usize {
+        self.outbound_capacity.unwrap_or(DEFAULT_OUTBOUND_CAPACITY)
+    }
+
+    pub(crate) fn inbound_capacity(&self) -> usize {
+        self.inbound_capacity.unwrap_or(DEFAULT_INBOUND_CAPACITY)
+    }
+
+    pub(crate) fn stream_capacity(&self) -> usize {
+        self.stream_capacity.unwrap_or(DEFAULT_STREAM_CAPACITY)
+    }
+
+    pub(crate) fn stream_pool_size(&self) -> usize {
+        self.stream_pool_size.unwrap_or(DEFAULT_STREAM_POOL_SIZE)
+    }
 }
 
 impl<R> DuplexSocket<R>
 where
     R: Send + Sync + Clone + Spawner + 'static,
 {
-    pub(crate) async fn new(rt: R, first_stream_id: u32, tx: Tx<Frame>) -> DuplexSocket<R> {
+    pub(crate) async fn new(
+        rt: R,
+        first_stream_id: u32,
+        tx: TxBounded<Frame>,
+        opts: SocketOptions,
+    ) -> DuplexSocket<R> {
         let rt2 = rt.clone();
-        let (canceller_tx, canceller_rx) = new_tx_rx::<u32>();
+        let stream_capacity = opts.stream_capacity();
+        let (canceller_tx, canceller_rx) = new_tx_rx::<u32>(stream_capacity);
+        let metrics = Metrics::new(first_stream_id);
+        let stats = StatsRecorder::new();
+        let outbound_capacity = opts.outbound_capacity();
+        let pool = StreamPool::new(opts.stream_pool_size(), metrics.clone());
+        let frame_logger = opts.frame_logger;
+        let capture = opts.capture;
+        let leaks = opts.leak_threshold.map(LeakDetector::new);
+        let idle = opts.idle_timeout.map(IdleStreams::new);
+        let tasks = TaskGroup::default();
+        if let Some(detector) = &leaks {
+            tasks.spawn(&rt2, detector.clone().run());
+        }
+        // observe outgoing frames before handing them to the transport.
+        let (pump_tx, pump_rx) = new_tx_rx_bounded::<Frame>(outbound_capacity);
+        let priorities = if opts.priority_scheduling {
+            Some(StreamPriorities::default())
+        } else {
+            None
+        };
+        {
+            let mut tx = tx;
+            let metrics = metrics.clone();
+            let stats = stats.clone();
+            let frame_logger = frame_logger.clone();
+            let capture = capture.clone();
+            let leaks = leaks.clone();
+            let idle = idle.clone();
+            let observe = move |frame: &Frame| {
+                stats.on_outbound(frame);
+                metrics.on_outbound(frame);
+                if let Some(logger) = &frame_logger {
+                    logger.log(true, frame);
+                }
+                if let Some(recorder) = &capture {
+                    recorder.record(true, frame);
+                }
+                if let Some(detector) = &leaks {
+                    detector.on_frame(frame);
+                }
+                if let Some(idle) = &idle {
+                    idle.on_frame(frame);
+                }
+            };
+            // throttled frames wait in the scheduler, behind which the others go on.
+            let mut scheduler =
+                if priorities.is_some() || opts.fair_scheduling || opts.frame_rate.is_some() {
+                    Some(Scheduler::new(
+                        priorities.clone(),
+                        opts.fair_scheduling,
+                        outbound_capacity,
+                        opts.frame_rate.map(Throttle::new),
+                    ))
+                } else {
+                    None
+                };
+            let closing = tasks.closing();
+            tasks.spawn_graceful(&rt2, async move {
+                let mut frames = pump_rx.take_until(closing);
+                write_frames(&mut frames, &mut tx, scheduler.as_mut(), &observe).await;
+                // the frames sent before the connection closed are still written.
+                let mut pump_rx = frames.into_inner();
+                pump_rx.close();
+                write_frames(&mut pump_rx, &mut tx, scheduler.as_mut(), &observe).await;
+            });
+        }
         let ds = DuplexSocket {
             rt,
             seq: StreamID::from(first_stream_id),
-            tx,
+            tx: pump_tx,
             canceller: canceller_tx,
+            stream_capacity,
             responder: Responder::new(),
-            handlers: Arc::new(Mutex::new(HashMap::new())),
+            handlers: StreamMap::new(),
+            pool,
+            metrics,
+            stats,
+            frame_logger,
+            capture,
+            leaks,
+            idle,
+            slow_consumer: opts.slow_consumer,
+            stream_buffer: opts.stream_buffer,
+            on_metadata_push: opts.on_metadata_push,
+            max_metadata_push_size: opts.max_metadata_push_size,
+            params: Arc::new(RwLock::new(None)),
+            #[cfg(feature = "lease")]
+            lease: LeaseTracker::new(),
+            #[cfg(feature = "lease")]
+            lease_policy: opts.lease_policy,
+            #[cfg(feature = "lease")]
+            granted: LeaseTracker::new(),
+            priorities,
+            flow: opts.byte_budget.map(Flow::new),
+            execution: opts.execution,
+            tasks,
+            peer: PeerInfo::default(),
+            #[cfg(feature = "extension")]
+            authenticator: opts.authenticator,
+            principal: Arc::new(RwLock::new(None)),
+            attributes: Attributes::default(),
+            closed: Arc::new(AtomicBool::new(false)),
+            draining: Arc::new(AtomicBool::new(false)),
+            pings: Arc::new(Mutex::new(vec![])),
+            keepalive_data: opts.keepalive_data,
+            on_keepalive: opts.on_keepalive,
+            #[cfg(feature = "lease")]
+            lease_requested: opts.lease,
+            validation: opts.validation,
+            payload_limits: opts.payload_limits,
+            // the requests of the peer use the stream IDs of the other parity.
+            peer_parity: (first_stream_id + 1) % 2,
         };
 
         let ds2 = ds.clone();
-        rt2.spawn(async move {
+        ds.tasks.spawn(&rt2, async move {
             ds2.loop_canceller(canceller_rx).await;
         });
+        if let Some(idle) = ds.idle.clone() {
+            ds.tasks.spawn(&rt2, ds.clone().expire_idle_streams(idle));
+        }
         ds
     }
 
+    /// Send KEEPALIVE frames periodically, until the connection is closed.
+    pub(crate) fn start_keepalive(&self, interval: Duration) {
+        if interval == Duration::from_secs(0) {
+            return;
+        }
+        let mut tx = self.tx.clone();
+        let stats = self.stats.clone();
+        let data = self.keepalive_data.clone();
+        self.tasks.spawn(&self.rt, async move {
+            let mut ticker = tokio::time::interval(interval);
+            // the first tick completes immediately.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let sending = keepalive_of(&stats, &data);
+                if tx.send(sending).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Grant leases to the peer periodically by the lease policy, until the connection is
+    /// closed.
+    #[cfg(feature = "lease")]
+    fn start_leasing(&self) {
+        let policy = match &self.lease_policy {
+            Some(policy) => policy.clone(),
+            None => return,
+        };
+        let socket = self.clone();
+        self.tasks.spawn(&self.rt, async move {
+            let ttl = policy.get_ttl();
+            let ttl_millis = ttl.as_millis().min(u128::from(u32::MAX)) as u32;
+            while !socket.is_closed() {
+                let in_flight = socket.handlers.count(|it| {
+                    matches!(
+                        it,
+                        Handler::ResRR(_) | Handler::ResRS(_) | Handler::ResRC(_)
+                    )
+                });
+                let n = policy.lease_size(in_flight);
+                if n > 0 {
+                    socket.granted.on_lease(ttl, n);
+                    let sending = frame::Lease::builder(0, 0)
+                        .set_ttl(ttl_millis)
+                        .set_number_of_requests(n)
+                        .build();
+                    if socket.tx.clone().send(sending).await.is_err() {
+                        break;
+                    }
+                } else {
+                    debug!("withhold lease, {} requests in flight", in_flight);
+                }
+                tokio::time::delay_for(ttl / 2).await;
+            }
+        });
+    }
+
+    /// Run the handler of a request of the peer by the execution model of the responder.
+    /// Streams are never run inline, they wait on REQUEST_N frames read by this task.
+    async fn execute<F>(&self, task: F, stream: bool) -> Result<(), RSocketError>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        match &self.execution {
+            Execution::Inline if !stream => task.await,
+            Execution::Inline | Execution::Spawn => self.tasks.spawn(&self.rt, task),
+            Execution::Pool(workers) => {
+                if let Some(task) = self.tasks.bind(task) {
+                    return workers.submit(Box::pin(task));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail a request of the peer whose handler could not be run.
+    async fn reject_request(&self, sid: u32, err: RSocketError) {
+        warn!("reject request {} of the peer: {}", sid, err);
+        self.handlers.remove(sid);
+        if let Err(e) = self.tx.clone().send(to_error_frame(sid, &err)).await {
+            error!("reject request failed: {}", e);
+        }
+    }
+
+    /// Returns true if this side was given a policy to grant leases by.
+    fn has_lease_policy(&self) -> bool {
+        #[cfg(feature = "lease")]
+        return self.lease_policy.is_some();
+        #[cfg(not(feature = "lease"))]
+        return false;
+    }
+
+    /// Returns true if this side leases the requests of the peer.
+    #[cfg(feature = "lease")]
+    fn is_granting_leases(&self) -> bool {
+        self.lease_policy.is_some() && self.is_lease_enabled()
+    }
+
+    /// Send a KEEPALIVE and wait for the peer to echo it, returns the round trip time.
+    pub(crate) async fn ping(&self) -> Result<Duration, RSocketError> {
+        let closed = || RSocketError::from("connection is closed");
+        if self.is_closed() {
+            return Err(closed());
+        }
+        let (tx, rx) = new_tx_rx_once();
+        self.pings.lock().unwrap().push(tx);
+        let sending = keepalive_of(&self.stats, &self.keepalive_data);
+        self.tx.clone().send(sending).await.map_err(|_| closed())?;
+        rx.await.map_err(|_| closed())
+    }
+
+    /// Returns the initial request n of requester stream `sid`, within the byte budget if any.
+    fn open_flow(&self, sid: u32) -> u32 {
+        match &self.flow {
+            Some(flow) => flow.open(sid),
+            None => frame::REQUEST_MAX,
+        }
+    }
+
+    /// Request the payloads of requester stream `sid` as they are consumed, within the byte
+    /// budget if any.
+    fn consume_flow(
+        &self,
+        sid: u32,
+        results: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        match &self.flow {
+            Some(flow) => flow.consume(sid, results, self.tx.clone()),
+            None => results,
+        }
+    }
+
+    #[inline]
+    fn track(&self, sid: u32, interaction: &'static str) {
+        if let Some(detector) = &self.leaks {
+            detector.open(sid, interaction);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ConnectionStats {
+        let stats = self
+            .stats
+            .snapshot()
+            .with_stream_pool(self.pool.hits(), self.pool.misses())
+            .with_stalled_streams(self.handlers.count(|it| match it {
+                Handler::ResRS(demand) => !demand.has_demand(),
+                _ => false,
+            }));
+        #[cfg(feature = "lease")]
+        {
+            if self.is_lease_enabled() {
+                return stats.with_lease(self.lease.snapshot());
+            }
+        }
+        stats
+    }
+
+    #[cfg(feature = "lease")]
+    fn is_lease_enabled(&self) -> bool {
+        match &*self.params.read().unwrap() {
+            Some(params) => params.is_lease_enabled(),
+            None => false,
+        }
+    }
+
+    /// Check a frame of the peer against the protocol and the state of its stream.
+    fn validate(&self, msg: &Frame) -> Result<(), String> {
+        validation::check(msg, self.peer_parity)?;
+        let sid = msg.get_stream_id();
+        match msg.get_body_ref() {
+            Body::Setup(_) if self.params.read().unwrap().is_some() => {
+                Err(String::from("unexpected SETUP"))
+            }
+            Body::RequestFNF(_)
+            | Body::RequestResponse(_)
+            | Body::RequestStream(_)
+            | Body::RequestChannel(_) => match self.handlers.shard(sid).get(&sid) {
+                Some(_) => Err(format!("stream {} is in use", sid)),
+                None => Ok(()),
+            },
+            Body::Payload(_) => match self.handlers.shard(sid).get(&sid) {
+                Some(Handler::ResRR(_)) | Some(Handler::ResRS(_)) => {
+                    Err(format!("PAYLOAD {} of a request of the peer", sid))
+                }
+                _ => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Close the connection because the peer violated the protocol.
+    async fn fail_connection(&self, violation: String) -> RSocketError {
+        warn!("close connection, invalid frame: {}", violation);
+        let err = RSocketError::from(ErrorKind::Internal(error::ERR_CONN_FAILED, violation));
+        if let Err(e) = self.tx.clone().send(to_error_frame(0, &err)).await {
+            error!("send CONNECTION_ERROR failed: {}", e);
+        }
+        self.terminate_streams(err.clone());
+        err
+    }
+
+    #[cfg(feature = "lease")]
+    async fn reject_unleased(&self, sid: u32, msg: &Frame, err: RSocketError) {
+        warn!("reject request {} of the peer: {}", sid, err);
+        if let Body::RequestFNF(_) = msg.get_body_ref() {
+            return;
+        }
+        if let Err(e) = self.tx.clone().send(to_error_frame(sid, &err)).await {
+            error!("reject request failed: {}", e);
+        }
+    }
+
+    /// Reject a frame of the peer whose payload exceeds the size limits, returns true if the
+    /// connection is closed, because it was a SETUP.
+    async fn reject_oversized(&self, sid: u32, msg: &Frame, violation: String) -> bool {
+        let setup = matches!(msg.get_body_ref(), Body::Setup(_));
+        let code = match msg.get_body_ref() {
+            Body::RequestFNF(_) | Body::MetadataPush(_) => {
+                warn!("drop frame of stream {}: {}", sid, violation);
+                return false;
+            }
+            Body::Setup(_) => error::ERR_REJECT_SETUP,
+            _ => error::ERR_REJECTED,
+        };
+        warn!("reject frame of stream {}: {}", sid, violation);
+        let err = RSocketError::from(ErrorKind::Internal(code, violation));
+        if let Body::Payload(_) = msg.get_body_ref() {
+            match self.handlers.remove(sid) {
+                Some(Handler::ResRC(channel)) => channel.terminate(err.clone()),
+                // the payload of a finished channel.
+                _ => return false,
+            }
+        }
+        if let Err(e) = self.tx.clone().send(to_error_frame(sid, &err)).await {
+            error!("reject frame failed: {}", e);
+        }
+        setup
+    }
+
+    /// Admit a new request of this side, none is admitted once the connection is closing.
+    fn admit(&self) -> Result<(), RSocketError> {
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(RSocketError::from(ErrorKind::Internal(
+                error::ERR_REJECTED,
+                String::from("connection is closing"),
+            )));
+        }
+        #[cfg(feature = "lease")]
+        self.acquire_lease()?;
+        Ok(())
+    }
+
+    /// Take a request of the lease granted by the peer, if leasing was negotiated.
+    #[cfg(feature = "lease")]
+    fn acquire_lease(&self) -> Result<(), RSocketError> {
+        if !self.is_lease_enabled() {
+            return Ok(());
+        }
+        let acquired = self.lease.acquire();
+        self.metrics
+            .on_lease_request(acquired.as_ref().ok().copied());
+        acquired.map(|_| ())
+    }
+
+    /// Describe the remote end in the context of the requests it sends.
+    pub(crate) fn with_peer(mut self, peer: PeerInfo) -> Self {
+        self.peer = peer;
+        self
+    }
+
+    fn context(&self, sid: u32, metadata: Option<&Bytes>) -> RequestContext {
+        RequestContext::new(
+            sid,
+            self.peer.clone(),
+            self.principal.read().unwrap().clone(),
+            self.params(),
+            self.attributes.clone(),
+            metadata,
+        )
+    }
+
+    /// Authenticate the peer by its SETUP if an authenticator is set, the principal is kept
+    /// for the context of its requests.
+    #[cfg(feature = "extension")]
+    async fn authenticate(&self, setup: &SetupPayload) -> Result<(), RSocketError> {
+        let authenticator = match &self.authenticator {
+            Some(it) => it,
+            None => return Ok(()),
+        };
+        let principal = authenticator.authenticate(auth_of(setup)?).await?;
+        debug!("authenticated as {}", principal.get_name());
+        *self.principal.write().unwrap() = Some(principal);
+        Ok(())
+    }
+
+    /// Returns the parameters negotiated by SETUP, None before it was sent or received.
+    pub(crate) fn params(&self) -> Option<ConnectionParams> {
+        self.params.read().unwrap().clone()
+    }
+
+    fn set_params(&self, setup: &frame::Setup, flag: u16) {
+        *self.params.write().unwrap() = Some(ConnectionParams::new(setup, flag));
+    }
+
+    pub(crate) fn set_responder(&self, responder: Box<dyn RSocket>) {
+        self.responder.set(responder);
+    }
+
+    /// Close the connection, the tasks spawned for it are aborted.
     pub(crate) fn close(self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.terminate_streams(connection_closed());
+        self.tasks.close();
         drop(self.tx);
     }
 
+    /// Close the connection once its streams end, waiting no longer than `grace`. New requests
+    /// of this side are rejected meanwhile, the peer is told by ERROR(CONNECTION_CLOSE).
+    pub(crate) async fn close_gracefully(self, grace: Duration) {
+        self.draining.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + grace;
+        let mut in_flight = self.handlers.count(|_| true);
+        while in_flight > 0 && !self.is_closed() && Instant::now() < deadline {
+            tokio::time::delay_for(DRAIN_INTERVAL).await;
+            in_flight = self.handlers.count(|_| true);
+        }
+        if in_flight > 0 {
+            warn!("close connection with {} streams in flight", in_flight);
+        }
+        if !self.is_closed() {
+            let sending = frame::Error::builder(0, 0)
+                .set_code(error::ERR_CONN_CLOSED)
+                .set_data(Bytes::from("connection closed"))
+                .build();
+            if let Err(e) = self.tx.clone().send(sending).await {
+                debug!("send CONNECTION_CLOSE failed: {}", e);
+            }
+        }
+        let socket = self.clone();
+        self.close();
+        socket.join().await
+    }
+
+    /// Wait for the tasks spawned for the connection to end, once it is closed.
+    pub(crate) async fn join(&self) {
+        self.tasks.join().await
+    }
+
+    /// Returns true once the connection was closed by either end.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Open a REQUEST_CHANNEL whose outbound payloads are sent through the returned sink.
+    pub(crate) fn request_channel_sink(
+        &self,
+    ) -> (ChannelSink, Flux<Result<Payload, RSocketError>>) {
+        if let Err(e) = self.admit() {
+            let sink =
+                ChannelSink::closed(self.tx.clone(), self.handlers.clone(), self.pool.demand(0));
+            return (sink, Inbound::failed(e));
+        }
+        let sid = self.seq.next();
+        self.track(sid, "request_channel");
+        let (sender, receiver) = Inbound::new(self.stream_capacity);
+        let outbound = self.pool.demand(0);
+        self.handlers
+            .insert(sid, Handler::ReqRC(Channel::new(sender, outbound.clone())));
+        let sink = ChannelSink::new(sid, self.tx.clone(), self.handlers.clone(), outbound);
+        let inbound = spans::requester("request_channel", sid, None).flux(receiver);
+        (sink, inbound)
+    }
+
     pub(crate) async fn setup(&self, setup: SetupPayload) {
-        let mut bu = frame::Setup::builder(0, 0);
+        let sending = self.setup_frame(setup);
+        self.tx
+            .clone()
+            .send(sending)
+            .await
+            .expect("Send setup failed");
+    }
+
+    /// Take the parameters of a resumed session, which are those of the SETUP it started by.
+    #[cfg(feature = "resume")]
+    pub(crate) fn resumed(&self, setup: SetupPayload) {
+        self.setup_frame(setup);
+    }
+
+    fn setup_frame(&self, setup: SetupPayload) -> Frame {
+        #[cfg(feature = "lease")]
+        let flag = if self.lease_requested {
+            frame::FLAG_LEASE
+        } else {
+            0
+        };
+        #[cfg(not(feature = "lease"))]
+        let flag = 0;
+        let mut bu = frame::Setup::builder(0, flag);
         if let Some(s) = setup.data_mime_type() {
-            bu = bu.set_mime_data(&s);
+            bu = bu.set_mime_data(s);
         }
         if let Some(s) = setup.metadata_mime_type() {
-            bu = bu.set_mime_metadata(&s);
+            bu = bu.set_mime_metadata(s);
         }
         bu = bu.set_keepalive(setup.keepalive_interval());
         bu = bu.set_lifetime(setup.keepalive_lifetime());
@@ -89,53 +721,168 @@ where
         if let Some(b) = m {
             bu = bu.set_metadata(b);
         }
-        self.tx
-            .unbounded_send(bu.build())
-            .expect("Send setup failed");
+        let sending = bu.build();
+        if let Body::Setup(v) = sending.get_body_ref() {
+            self.set_params(v, sending.get_flag());
+        }
+        sending
     }
 
     #[inline]
     async fn register_handler(&self, sid: u32, handler: Handler) {
-        let mut handlers = self.handlers.lock().await;
-        (*handlers).insert(sid, handler);
+        self.handlers.insert(sid, handler);
     }
 
     #[inline]
     pub(crate) async fn loop_canceller(&self, mut rx: Rx<u32>) {
         while let Some(sid) = rx.next().await {
-            let mut handlers = self.handlers.lock().await;
-            (*handlers).remove(&sid);
+            self.handlers.remove(sid);
         }
     }
 
-    pub(crate) async fn event_loop(&self, acceptor: Acceptor, mut rx: Rx<Frame>) {
+    /// Read the frames of the peer until the connection is closed, returns the error which
+    /// failed it if any.
+    pub(crate) async fn event_loop(
+        &self,
+        acceptor: Acceptor,
+        mut rx: Rx<Frame>,
+    ) -> Result<(), RSocketError> {
+        let mut outcome = Ok(());
         while let Some(msg) = rx.next().await {
             let sid = msg.get_stream_id();
             let flag = msg.get_flag();
             misc::debug_frame(false, &msg);
+            self.stats.on_inbound(&msg);
+            self.metrics.on_inbound(&msg);
+            if let Some(logger) = &self.frame_logger {
+                logger.log(false, &msg);
+            }
+            if let Some(recorder) = &self.capture {
+                recorder.record(false, &msg);
+            }
+            if let Some(detector) = &self.leaks {
+                detector.on_frame(&msg);
+            }
+            if let Some(idle) = &self.idle {
+                idle.on_frame(&msg);
+            }
+            if let Some(priorities) = &self.priorities {
+                priorities.on_frame(&msg);
+            }
+            if let Err(violation) = self.validate(&msg) {
+                match self.validation {
+                    ValidationMode::Strict => {
+                        outcome = Err(self.fail_connection(violation).await);
+                        break;
+                    }
+                    ValidationMode::Lenient => {
+                        warn!("ignore invalid frame: {}", violation);
+                        continue;
+                    }
+                }
+            }
+            #[cfg(feature = "lease")]
+            if self.lease_requested && validation::is_request(&msg) {
+                // this side never grants leases, so the peer may not send requests.
+                let err = RSocketError::from(ErrorKind::Internal(
+                    error::ERR_REJECTED,
+                    String::from("no lease granted to the peer"),
+                ));
+                self.reject_unleased(sid, &msg, err).await;
+                continue;
+            }
+            #[cfg(feature = "lease")]
+            if self.is_granting_leases() && validation::is_request(&msg) {
+                if let Err(e) = self.granted.acquire() {
+                    self.reject_unleased(sid, &msg, e).await;
+                    continue;
+                }
+            }
+            if let Err(violation) = self.payload_limits.check(&msg, self.peer_parity) {
+                if self.reject_oversized(sid, &msg, violation).await {
+                    break;
+                }
+                continue;
+            }
             match msg.get_body() {
                 Body::Setup(v) => {
-                    if let Err(e) = self.on_setup(&acceptor, sid, flag, SetupPayload::from(v)) {
-                        let errmsg = format!("{}", e);
+                    self.set_params(&v, flag);
+                    let setup = SetupPayload::from(v);
+                    let rejected = if flag & frame::FLAG_LEASE != 0 && !self.has_lease_policy() {
+                        Some((
+                            error::ERR_UNSUPPORTED_SETUP,
+                            String::from("leasing is not supported"),
+                        ))
+                    } else {
+                        #[cfg(feature = "extension")]
+                        let authenticated = self.authenticate(&setup).await;
+                        #[cfg(not(feature = "extension"))]
+                        let authenticated: Result<(), RSocketError> = Ok(());
+                        // the error of the acceptor is not Send, so it is not kept across awaits.
+                        match authenticated {
+                            Ok(()) => self
+                                .on_setup(&acceptor, sid, flag, setup)
+                                .err()
+                                .map(|e| setup_rejection(&*e)),
+                            Err(e) => Some(setup_rejection(&e)),
+                        }
+                    };
+                    if let Some((code, errmsg)) = rejected {
                         let sending = frame::Error::builder(0, 0)
-                            .set_code(error::ERR_REJECT_SETUP)
+                            .set_code(code)
                             .set_data(Bytes::from(errmsg))
                             .build();
                         self.tx
-                            .unbounded_send(sending)
+                            .clone()
+                            .send(sending)
+                            .await
                             .expect("Reject setup failed");
-                        return;
+                        break;
+                    }
+                    #[cfg(feature = "lease")]
+                    {
+                        if self.is_granting_leases() {
+                            self.start_leasing();
+                        }
                     }
                 }
                 Body::Resume(v) => {
-                    // TODO: support resume
+                    // sessions are not kept, so they can not be resumed.
+                    let sending = frame::Error::builder(0, 0)
+                        .set_code(error::ERR_REJECT_RESUME)
+                        .set_data(Bytes::from("resumption is not supported"))
+                        .build();
+                    if let Err(e) = self.tx.clone().send(sending).await {
+                        error!("reject RESUME failed: {}", e);
+                    }
+                    break;
                 }
                 Body::ResumeOK(v) => {
-                    // TODO: support resume ok
+                    warn!("unexpected RESUME_OK: {:?}", v);
                 }
                 Body::MetadataPush(v) => {
+                    if sid != 0 {
+                        // METADATA_PUSH belongs to the connection.
+                        let sending = frame::Error::builder(0, 0)
+                            .set_code(error::ERR_CONN_FAILED)
+                            .set_data(Bytes::from("METADATA_PUSH must be sent on stream 0"))
+                            .build();
+                        if let Err(e) = self.tx.clone().send(sending).await {
+                            error!("reject METADATA_PUSH failed: {}", e);
+                        }
+                        break;
+                    }
                     let input = Payload::from(v);
-                    self.on_metadata_push(input).await;
+                    match self.max_metadata_push_size {
+                        Some(max) if input.len() > max => {
+                            warn!(
+                                "drop METADATA_PUSH of {} bytes, exceeds {}",
+                                input.len(),
+                                max
+                            )
+                        }
+                        _ => self.on_metadata_push(input).await,
+                    }
                 }
                 Body::RequestFNF(v) => {
                     let input = Payload::from(v);
@@ -146,59 +893,121 @@ where
                     self.on_request_response(sid, flag, input).await;
                 }
                 Body::RequestStream(v) => {
+                    let n = v.get_initial_request_n();
                     let input = Payload::from(v);
-                    self.on_request_stream(sid, flag, input).await;
+                    self.on_request_stream(sid, flag, n, input).await;
                 }
                 Body::RequestChannel(v) => {
+                    let n = v.get_initial_request_n();
                     let input = Payload::from(v);
-                    self.on_request_channel(sid, flag, input).await;
+                    self.on_request_channel(sid, flag, n, input).await;
                 }
                 Body::Payload(v) => {
                     let input = Payload::from(v);
                     self.on_payload(sid, flag, input).await;
                 }
                 Body::Keepalive(v) => {
+                    if let (Some(handler), Some(data)) = (&self.on_keepalive, v.get_data()) {
+                        handler(data.clone());
+                    }
                     if flag & frame::FLAG_RESPOND != 0 {
                         debug!("got keepalive: {:?}", v);
                         self.on_keepalive(v).await;
+                    } else if let Some(rtt) = self.stats.on_keepalive_ack(v.get_data().as_ref()) {
+                        for ping in self.pings.lock().unwrap().drain(..) {
+                            let _ = ping.send(rtt);
+                        }
                     }
                 }
                 Body::RequestN(v) => {
-                    // TODO: support RequestN
+                    self.on_request_n(sid, v.get_n()).await;
                 }
                 Body::Error(v) => {
-                    // TODO: support error
+                    if sid == 0 {
+                        outcome = Err(self.on_connection_error(v));
+                        break;
+                    }
                     self.on_error(sid, flag, v).await;
                 }
                 Body::Cancel() => {
                     self.on_cancel(sid, flag).await;
                 }
+                #[cfg(feature = "lease")]
                 Body::Lease(v) => {
-                    // TODO: support Lease
+                    let ttl = Duration::from_millis(u64::from(v.get_ttl()));
+                    self.lease.on_lease(ttl, v.get_number_of_requests());
+                    self.metrics.on_lease(v.get_number_of_requests());
+                }
+                #[cfg(not(feature = "lease"))]
+                Body::Lease(v) => {
+                    // leasing is never negotiated without support for it.
+                    warn!("unexpected LEASE: {:?}", v);
                 }
             }
         }
+        self.closed.store(true, Ordering::SeqCst);
+        self.pings.lock().unwrap().clear();
+        // the responder may hold the requester, drop it with the connection.
+        self.responder.set(Box::new(EmptyRSocket));
+        // streams left are lost with the connection.
+        self.terminate_streams(connection_closed());
+        self.metrics.on_close();
+        if let Some(detector) = &self.leaks {
+            detector.close();
+        }
+        self.tasks.close();
+        outcome
     }
 
     #[inline]
     async fn on_error(&self, sid: u32, flag: u16, input: frame::Error) {
         // pick handler
-        let mut handlers = self.handlers.lock().await;
-        if let Some(handler) = (*handlers).remove(&sid) {
-            let kind = ErrorKind::Internal(input.get_code(), input.get_data_utf8());
-            let e = Err(RSocketError::from(kind));
+        let removed = self.handlers.remove(sid);
+        if let Some(handler) = removed {
+            let err = from_error_frame(&input);
+            match handler {
+                Handler::ReqRR(tx) => {
+                    let _ = tx.send(Err(err));
+                }
+                Handler::ResRR(c) => {
+                    // the peer gave up its request, like a CANCEL.
+                    c.count_down();
+                }
+                Handler::ReqRS(inbound) => inbound.fail(err),
+                Handler::ResRS(demand) => demand.cancel(),
+                Handler::ReqRC(channel) | Handler::ResRC(channel) => channel.terminate(err),
+            }
+        }
+    }
+
+    /// The peer closed the connection with `input`, every stream terminates with it.
+    fn on_connection_error(&self, input: frame::Error) -> RSocketError {
+        let err = from_error_frame(&input);
+        warn!("connection closed by peer: {}", err);
+        self.terminate_streams(err.clone());
+        err
+    }
+
+    /// Terminate every stream of the connection with `err`.
+    fn terminate_streams(&self, err: RSocketError) {
+        for (_, handler) in self.handlers.drain() {
             match handler {
-                Handler::ReqRR(tx) => tx.send(e).expect("Send RR failed"),
-                Handler::ResRR(_) => unreachable!(),
-                Handler::ReqRS(tx) => tx.unbounded_send(e).expect("Send RS failed"),
-                _ => unimplemented!(),
+                Handler::ReqRR(tx) => {
+                    let _ = tx.send(Err(err.clone()));
+                }
+                Handler::ResRR(c) => {
+                    c.count_down();
+                }
+                Handler::ReqRS(inbound) => inbound.fail(err.clone()),
+                Handler::ResRS(demand) => demand.cancel(),
+                Handler::ReqRC(channel) | Handler::ResRC(channel) => channel.terminate(err.clone()),
             }
         }
     }
 
     #[inline]
     async fn on_cancel(&self, sid: u32, _flag: u16) {
-        let mut handlers = self.handlers.lock().await;
+        let mut handlers = self.handlers.shard(sid);
         if let Some(handler) = (*handlers).remove(&sid) {
             let e = Err(RSocketError::from(ErrorKind::Cancelled()));
             match handler {
@@ -213,42 +1022,164 @@ where
                 Handler::ReqRS(sender) => {
                     info!("REQUEST_STREAM {} cancelled!", sid);
                 }
-                Handler::ReqRC(sender) => {
+                Handler::ReqRC(channel) => {
+                    // the responder stops consuming, its payloads keep flowing.
+                    info!("outbound of REQUEST_CHANNEL {} cancelled!", sid);
+                    if let Some(channel) = channel.on_local_complete() {
+                        (*handlers).insert(sid, Handler::ReqRC(channel));
+                    }
+                }
+                Handler::ResRS(demand) => {
+                    info!("REQUEST_STREAM {} cancelled!", sid);
+                    demand.cancel();
+                }
+                Handler::ResRC(channel) => {
                     info!("REQUEST_CHANNEL {} cancelled!", sid);
+                    channel.terminate(RSocketError::from(ErrorKind::Cancelled()));
                 }
             };
         }
     }
 
+    /// Cancel the streams which are idle for longer than the idle timeout, checked every half
+    /// of it until the connection is closed.
+    async fn expire_idle_streams(self, idle: IdleStreams) {
+        let mut ticker = tokio::time::interval(idle.get_timeout() / 2);
+        loop {
+            ticker.tick().await;
+            for sid in idle.expired(&self.handlers.ids()) {
+                let sending = match self.expire(sid) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                if self.tx.clone().send(sending).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Terminate idle stream `sid` locally, returns the frame which tells the peer: requesters
+    /// cancel the stream, responders and channels send an ERROR.
+    fn expire(&self, sid: u32) -> Option<Frame> {
+        let handler = self.handlers.remove(sid)?;
+        warn!("cancel idle stream {}", sid);
+        let e = RSocketError::from(ErrorKind::Internal(
+            error::ERR_CANCELED,
+            String::from("stream is idle"),
+        ));
+        let cancel = || frame::Cancel::builder(sid, 0).build();
+        let sending = match handler {
+            Handler::ReqRR(tx) => {
+                let _ = tx.send(Err(e));
+                cancel()
+            }
+            Handler::ReqRS(inbound) => {
+                inbound.fail(e);
+                cancel()
+            }
+            Handler::ResRR(c) => {
+                // the response is dropped once it is ready.
+                c.count_down();
+                to_error_frame(sid, &e)
+            }
+            Handler::ResRS(demand) => {
+                demand.cancel();
+                to_error_frame(sid, &e)
+            }
+            Handler::ReqRC(channel) | Handler::ResRC(channel) => {
+                let sending = to_error_frame(sid, &e);
+                channel.terminate(e);
+                sending
+            }
+        };
+        Some(sending)
+    }
+
     #[inline]
     async fn on_payload(&self, sid: u32, flag: u16, input: Payload) {
-        let mut handlers = self.handlers.lock().await;
+        if let Some(flow) = &self.flow {
+            if flag & frame::FLAG_NEXT != 0 {
+                flow.on_receive(sid, input.len());
+            }
+        }
+        let (cancelling, delivery) = self.forward_payload(sid, flag, input);
+        // the reader waits for the consumer of a full stream, rather than buffering more.
+        if let Delivery::Pending(pending) = delivery {
+            if !pending.send().await {
+                debug!("drop PAYLOAD of stream {}, nobody consumes it", sid);
+            }
+        }
+        if cancelling {
+            let sending = frame::Cancel::builder(sid, 0).build();
+            if let Err(e) = self.tx.clone().send(sending).await {
+                error!("cancel inbound of REQUEST_CHANNEL failed: {}", e);
+            }
+        }
+    }
+
+    /// Hand a PAYLOAD to the handler of stream `sid`, returns whether the stream is to be
+    /// cancelled and how the payload was delivered.
+    fn forward_payload(&self, sid: u32, flag: u16, input: Payload) -> (bool, Delivery) {
+        let mut handlers = self.handlers.shard(sid);
+        let handler = match (*handlers).remove(&sid) {
+            Some(it) => it,
+            None => {
+                debug!("ignore PAYLOAD of stream {}", sid);
+                return (false, Delivery::Sent);
+            }
+        };
         // fire event!
-        match (*handlers).remove(&sid).unwrap() {
-            Handler::ReqRR(sender) => sender.send(Ok(input)).unwrap(),
+        let (mut channel, requester) = match handler {
+            Handler::ReqRR(sender) => {
+                sender.send(Ok(input)).unwrap();
+                return (false, Delivery::Sent);
+            }
             Handler::ResRR(c) => unreachable!(),
-            Handler::ReqRS(sender) => {
-                if flag & frame::FLAG_NEXT != 0 {
-                    sender
-                        .unbounded_send(Ok(input))
-                        .expect("Send payload response failed.");
-                }
+            Handler::ReqRS(mut sender) => {
+                let delivery = if flag & frame::FLAG_NEXT != 0 {
+                    sender.deliver(input)
+                } else {
+                    Delivery::Sent
+                };
                 if flag & frame::FLAG_COMPLETE == 0 {
                     (*handlers).insert(sid, Handler::ReqRS(sender));
                 }
+                return (false, delivery);
             }
-            Handler::ReqRC(sender) => {
-                // TODO: support channel
-                if flag & frame::FLAG_NEXT != 0 {
-                    sender
-                        .unbounded_send(Ok(input))
-                        .expect("Send payload response failed");
-                }
-                if flag & frame::FLAG_COMPLETE == 0 {
-                    (*handlers).insert(sid, Handler::ReqRC(sender));
-                }
+            Handler::ResRS(demand) => {
+                warn!("unexpected PAYLOAD of REQUEST_STREAM {}", sid);
+                (*handlers).insert(sid, Handler::ResRS(demand));
+                return (false, Delivery::Sent);
             }
+            Handler::ReqRC(channel) => (channel, true),
+            Handler::ResRC(channel) => (channel, false),
+        };
+        let delivery = if flag & frame::FLAG_NEXT != 0 {
+            channel.on_next(sid, input)
+        } else {
+            Delivery::Sent
         };
+        let consumed = !matches!(delivery, Delivery::Closed);
+        if !consumed && requester {
+            // the requester does not want the responses anymore.
+            channel.terminate(RSocketError::from(ErrorKind::Cancelled()));
+            return (true, delivery);
+        }
+        let left = if !consumed || flag & frame::FLAG_COMPLETE != 0 {
+            channel.on_remote_complete()
+        } else {
+            Some(channel)
+        };
+        if let Some(channel) = left {
+            let handler = if requester {
+                Handler::ReqRC(channel)
+            } else {
+                Handler::ResRC(channel)
+            };
+            (*handlers).insert(sid, handler);
+        }
+        (!consumed, delivery)
     }
 
     #[inline]
@@ -280,31 +1211,45 @@ where
 
     #[inline]
     async fn on_fire_and_forget(&self, sid: u32, flag: u16, input: Payload) {
-        self.responder.clone().fire_and_forget(input).await
+        let responder = self.responder.clone();
+        let span = spans::responder("fire_and_forget", sid, Some(&input));
+        let ctx = self.context(sid, input.metadata().as_ref());
+        let task = async move {
+            span.unit(responder.fire_and_forget_with_context(ctx, input))
+                .await
+        };
+        if let Err(e) = self.execute(task, false).await {
+            warn!("drop REQUEST_FNF {}: {}", sid, e);
+        }
     }
 
     #[inline]
     async fn on_request_response(&self, sid: u32, _flag: u16, input: Payload) {
         let responder = self.responder.clone();
-        let canceller = self.canceller.clone();
-        let tx = self.tx.clone();
+        let mut canceller = self.canceller.clone();
+        let mut tx = self.tx.clone();
 
-        let counter = Counter::new(2);
+        let counter = self.pool.counter(2);
         self.register_handler(sid, Handler::ResRR(counter.clone()))
             .await;
 
-        self.rt.spawn(async move {
+        let span = spans::responder("request_response", sid, Some(&input));
+        let ctx = self.context(sid, input.metadata().as_ref());
+        self.track(sid, "request_response");
+        let task = async move {
             // TODO: use future select
-            let result = responder.request_response(input).await;
+            let result = span
+                .mono(responder.request_response_with_context(ctx, input))
+                .await;
             if counter.count_down() == 0 {
                 // cancelled
                 return;
             }
 
             // async remove canceller
-            canceller
-                .unbounded_send(sid)
-                .expect("Send canceller failed");
+            if let Err(e) = canceller.send(sid).await {
+                error!("remove REQUEST_RESPONSE handler failed: {}", e);
+            }
 
             let sending = match result {
                 Ok(it) => {
@@ -319,109 +1264,141 @@ where
                     }
                     bu.build()
                 }
-                Err(e) => frame::Error::builder(sid, 0)
-                    .set_code(error::ERR_APPLICATION)
-                    .set_data(Bytes::from("TODO: should be error details"))
-                    .build(),
+                Err(e) => to_error_frame(sid, &e),
             };
-            if let Err(e) = tx.unbounded_send(sending) {
+            if let Err(e) = tx.send(sending).await {
                 error!("respond REQUEST_RESPONSE failed: {}", e);
             }
-        });
+        };
+        if let Err(e) = self.execute(task, false).await {
+            self.reject_request(sid, e).await;
+        }
     }
 
     #[inline]
-    async fn on_request_stream(&self, sid: u32, flag: u16, input: Payload) {
+    async fn on_request_stream(&self, sid: u32, flag: u16, initial_request_n: u32, input: Payload) {
         let responder = self.responder.clone();
-        let tx = self.tx.clone();
-        self.rt.spawn(async move {
-            // TODO: support cancel
-            let mut payloads = responder.request_stream(input);
-            while let Some(next) = payloads.next().await {
-                let sending = match next {
-                    Ok(it) => {
-                        let (d, m) = it.split();
-                        let mut bu = frame::Payload::builder(sid, frame::FLAG_NEXT);
-                        if let Some(b) = d {
-                            bu = bu.set_data(b);
-                        }
-                        if let Some(b) = m {
-                            bu = bu.set_metadata(b);
-                        }
-                        bu.build()
-                    }
-                    Err(e) => frame::Error::builder(sid, 0)
-                        .set_code(error::ERR_APPLICATION)
-                        .set_data(Bytes::from(format!("{}", e)))
-                        .build(),
-                };
-                tx.unbounded_send(sending)
-                    .expect("Send stream response failed");
+        let mut tx = self.tx.clone();
+        let mut canceller = self.canceller.clone();
+        let demand = self.pool.demand(initial_request_n);
+        let metrics = self.metrics.clone();
+        let slow_consumer = self.slow_consumer;
+        let stream_buffer = self.stream_buffer;
+        let span = spans::responder("request_stream", sid, Some(&input));
+        let ctx = self.context(sid, input.metadata().as_ref());
+        self.track(sid, "request_stream");
+        self.register_handler(sid, Handler::ResRS(demand.clone()))
+            .await;
+        let task = async move {
+            let payloads = span.flux(responder.request_stream_with_context(ctx, input));
+            send_stream(
+                sid,
+                payloads,
+                demand,
+                &mut tx,
+                slow_consumer,
+                stream_buffer,
+                &metrics,
+            )
+            .await;
+            if let Err(e) = canceller.send(sid).await {
+                error!("remove REQUEST_STREAM handler failed: {}", e);
             }
-            let complete = frame::Payload::builder(sid, frame::FLAG_COMPLETE).build();
-            tx.unbounded_send(complete)
-                .expect("Send stream complete response failed");
-        });
+        };
+        if let Err(e) = self.execute(task, true).await {
+            self.reject_request(sid, e).await;
+        }
     }
 
     #[inline]
-    async fn on_request_channel(&self, sid: u32, flag: u16, first: Payload) {
+    async fn on_request_channel(
+        &self,
+        sid: u32,
+        flag: u16,
+        initial_request_n: u32,
+        first: Payload,
+    ) {
         let responder = self.responder.clone();
-        let tx = self.tx.clone();
-        let (sender, receiver) = new_tx_rx::<Result<Payload, RSocketError>>();
-        sender.unbounded_send(Ok(first)).unwrap();
-        self.register_handler(sid, Handler::ReqRC(sender)).await;
-        self.rt.spawn(async move {
+        let mut tx = self.tx.clone();
+        let handlers = self.handlers.clone();
+        let demand = self.pool.demand(initial_request_n);
+        let metrics = self.metrics.clone();
+        let slow_consumer = self.slow_consumer;
+        let stream_buffer = self.stream_buffer;
+        let (mut sender, receiver) = Inbound::new(self.stream_capacity);
+        let span = spans::responder("request_channel", sid, Some(&first));
+        let ctx = self.context(sid, first.metadata().as_ref());
+        self.track(sid, "request_channel");
+        // the buffer is empty, so the first payload always fits.
+        sender.deliver(first);
+        let channel = if flag & frame::FLAG_COMPLETE != 0 {
+            Channel::HalfClosedRemote(demand.clone())
+        } else {
+            Channel::new(sender, demand.clone())
+        };
+        self.register_handler(sid, Handler::ResRC(channel)).await;
+        let task = async move {
             // respond client channel
-            let mut outputs = responder.request_channel(Box::pin(receiver));
+            let outputs = span.flux(responder.request_channel_with_context(ctx, receiver));
             // TODO: support custom RequestN.
             let request_n = frame::RequestN::builder(sid, 0).build();
 
-            if let Err(e) = tx.unbounded_send(request_n) {
+            if let Err(e) = tx.send(request_n).await {
                 error!("respond REQUEST_N failed: {}", e);
             }
+            let end = send_stream(
+                sid,
+                outputs,
+                demand,
+                &mut tx,
+                slow_consumer,
+                stream_buffer,
+                &metrics,
+            )
+            .await;
+            // the handler is kept until the inbound side completes.
+            on_outbound_end(&handlers, sid, end);
+        };
+        if let Err(e) = self.execute(task, true).await {
+            self.reject_request(sid, e).await;
+        }
+    }
 
-            while let Some(next) = outputs.next().await {
-                let sending = match next {
-                    Ok(v) => {
-                        let (d, m) = v.split();
-                        let mut bu = frame::Payload::builder(sid, frame::FLAG_NEXT);
-                        if let Some(b) = d {
-                            bu = bu.set_data(b);
-                        }
-                        if let Some(b) = m {
-                            bu = bu.set_metadata(b);
-                        }
-                        bu.build()
-                    }
-                    Err(e) => frame::Error::builder(sid, 0)
-                        .set_code(error::ERR_APPLICATION)
-                        .set_data(Bytes::from(format!("{}", e)))
-                        .build(),
-                };
-                tx.unbounded_send(sending).unwrap();
-            }
-            let complete = frame::Payload::builder(sid, frame::FLAG_COMPLETE).build();
-            if let Err(e) = tx.unbounded_send(complete) {
-                error!("complete REQUEST_CHANNEL failed: {}", e);
+    #[inline]
+    async fn on_request_n(&self, sid: u32, n: u32) {
+        let handlers = self.handlers.shard(sid);
+        match (*handlers).get(&sid) {
+            Some(Handler::ResRS(demand)) => demand.request(n),
+            Some(Handler::ReqRC(channel)) | Some(Handler::ResRC(channel)) => {
+                match channel.outbound() {
+                    Some(demand) => demand.request(n),
+                    None => debug!("ignore REQUEST_N of completed channel {}", sid),
+                }
             }
-        });
+            _ => debug!("ignore REQUEST_N of stream {}", sid),
+        }
     }
 
     #[inline]
     async fn on_metadata_push(&self, input: Payload) {
-        self.responder.clone().metadata_push(input).await
+        match &self.on_metadata_push {
+            Some(handler) => handler(input).await,
+            None => self.responder.clone().metadata_push(input).await,
+        }
     }
 
     #[inline]
     async fn on_keepalive(&self, keepalive: frame::Keepalive) {
-        let tx = self.tx.clone();
-        let (data, _) = keepalive.split();
+        let mut tx = self.tx.clone();
+        let data = match &self.keepalive_data {
+            Some(f) => Some(f()),
+            None => keepalive.split().0,
+        };
         let mut sending = frame::Keepalive::builder(0, 0);
         if let Some(b) = data {
             sending = sending.set_data(b);
         }
-        if let Err(e) = tx.unbounded_send(sending.build()) {
+        if let Err(e) = tx.send(sending.build()).await {
             error!("respond KEEPALIVE failed: {}", e);
         }
     }
@@ -432,23 +1409,31 @@ where
     R: Send + Sync + Clone + Spawner + 'static,
 {
     fn metadata_push(&self, req: Payload) -> Mono<()> {
-        let sid = self.seq.next();
-        let tx = self.tx.clone();
+        let mut tx = self.tx.clone();
         Box::pin(async move {
-            let (_d, m) = req.split();
-            let mut bu = frame::MetadataPush::builder(sid, 0);
+            let (d, m) = req.split();
+            if d.is_some() {
+                warn!("drop data of METADATA_PUSH");
+            }
+            // METADATA_PUSH belongs to the connection.
+            let mut bu = frame::MetadataPush::builder(0, 0);
             if let Some(b) = m {
                 bu = bu.set_metadata(b);
             }
-            if let Err(e) = tx.unbounded_send(bu.build()) {
+            if let Err(e) = tx.send(bu.build()).await {
                 error!("send metadata_push failed: {}", e);
             }
         })
     }
     fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        if let Err(e) = self.admit() {
+            warn!("drop fire_and_forget: {}", e);
+            return Box::pin(async {});
+        }
         let sid = self.seq.next();
-        let tx = self.tx.clone();
-        Box::pin(async move {
+        let mut tx = self.tx.clone();
+        let span = spans::requester("fire_and_forget", sid, Some(&req));
+        Box::pin(span.unit(async move {
             let (d, m) = req.split();
             let mut bu = frame::RequestFNF::builder(sid, 0);
             if let Some(b) = d {
@@ -457,22 +1442,24 @@ where
             if let Some(b) = m {
                 bu = bu.set_metadata(b);
             }
-            if let Err(e) = tx.unbounded_send(bu.build()) {
+            if let Err(e) = tx.send(bu.build()).await {
                 error!("send fire_and_forget failed: {}", e);
             }
-        })
+        }))
     }
     fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        if let Err(e) = self.admit() {
+            return Box::pin(future::err(e));
+        }
         let (tx, rx) = new_tx_rx_once::<Result<Payload, RSocketError>>();
         let sid = self.seq.next();
-        let handlers = Arc::clone(&self.handlers);
-        let sender = self.tx.clone();
-        self.rt.spawn(async move {
-            {
-                // register handler
-                let mut map = handlers.lock().await;
-                (*map).insert(sid, Handler::ReqRR(tx));
-            }
+        let handlers = self.handlers.clone();
+        let mut sender = self.tx.clone();
+        let span = spans::requester("request_response", sid, Some(&req));
+        self.track(sid, "request_response");
+        self.tasks.spawn(&self.rt, async move {
+            // register handler
+            handlers.insert(sid, Handler::ReqRR(tx));
 
             let (d, m) = req.split();
             // crate request frame
@@ -484,100 +1471,373 @@ where
                 bu = bu.set_metadata(b);
             }
             // send frame
-            if let Err(e) = sender.unbounded_send(bu.build()) {
+            if let Err(e) = sender.send(bu.build()).await {
                 error!("send request_response failed: {}", e);
             }
         });
-        Box::pin(async move {
+        Box::pin(span.mono(async move {
             match rx.await {
                 Ok(v) => v,
                 Err(_e) => Err(RSocketError::from("request_response failed")),
             }
-        })
+        }))
     }
 
     fn request_stream(&self, input: Payload) -> Flux<Result<Payload, RSocketError>> {
+        if let Err(e) = self.admit() {
+            return Inbound::failed(e);
+        }
         let sid = self.seq.next();
-        let tx = self.tx.clone();
+        let mut tx = self.tx.clone();
         // register handler
-        let (sender, receiver) = new_tx_rx::<Result<Payload, RSocketError>>();
-        let handlers = Arc::clone(&self.handlers);
-        self.rt.spawn(async move {
-            {
-                let mut map = handlers.lock().await;
-                (*map).insert(sid, Handler::ReqRS(sender));
-            }
+        let (sender, receiver) = Inbound::new(self.stream_capacity);
+        let handlers = self.handlers.clone();
+        let span = spans::requester("request_stream", sid, Some(&input));
+        self.track(sid, "request_stream");
+        let initial_request_n = self.open_flow(sid);
+        self.tasks.spawn(&self.rt, async move {
+            // register handler
+            handlers.insert(sid, Handler::ReqRS(sender));
             let (d, m) = input.split();
             // crate stream frame
-            let mut bu = frame::RequestStream::builder(sid, 0);
+            let mut bu =
+                frame::RequestStream::builder(sid, 0).set_initial_request_n(initial_request_n);
             if let Some(b) = d {
                 bu = bu.set_data(b);
             }
             if let Some(b) = m {
                 bu = bu.set_metadata(b);
             }
-            if let Err(e) = tx.unbounded_send(bu.build()) {
+            if let Err(e) = tx.send(bu.build()).await {
                 error!("send request_stream failed: {}", e);
             }
         });
-        Box::pin(receiver)
+        span.flux(self.consume_flow(sid, receiver))
     }
 
     fn request_channel(
         &self,
         mut reqs: Flux<Result<Payload, RSocketError>>,
     ) -> Flux<Result<Payload, RSocketError>> {
+        if let Err(e) = self.admit() {
+            return Inbound::failed(e);
+        }
         let sid = self.seq.next();
-        let tx = self.tx.clone();
+        self.track(sid, "request_channel");
+        let mut tx = self.tx.clone();
         // register handler
-        let (sender, receiver) = new_tx_rx::<Result<Payload, RSocketError>>();
-        let handlers = Arc::clone(&self.handlers);
-        self.rt.spawn(async move {
-            {
-                let mut map = handlers.lock().await;
-                (*map).insert(sid, Handler::ReqRC(sender));
-            }
-            let mut first = true;
-            while let Some(next) = reqs.next().await {
-                let sending = match next {
-                    Ok(it) => {
-                        let (d, m) = it.split();
-                        if first {
-                            first = false;
-                            let mut bu = frame::RequestChannel::builder(sid, frame::FLAG_NEXT);
-                            if let Some(b) = d {
-                                bu = bu.set_data(b);
-                            }
-                            if let Some(b) = m {
-                                bu = bu.set_metadata(b);
-                            }
-                            bu.build()
-                        } else {
-                            let mut bu = frame::Payload::builder(sid, frame::FLAG_NEXT);
-                            if let Some(b) = d {
-                                bu = bu.set_data(b);
-                            }
-                            if let Some(b) = m {
-                                bu = bu.set_metadata(b);
-                            }
-                            bu.build()
-                        }
-                    }
-                    Err(e) => frame::Error::builder(sid, 0)
-                        .set_code(error::ERR_APPLICATION)
-                        .set_data(Bytes::from(format!("{}", e)))
-                        .build(),
+        let (sender, receiver) = Inbound::new(self.stream_capacity);
+        let handlers = self.handlers.clone();
+        // the first payload is sent with the request, the others are requested by REQUEST_N.
+        let outbound = self.pool.demand(0);
+        let metrics = self.metrics.clone();
+        let initial_request_n = self.open_flow(sid);
+        self.tasks.spawn(&self.rt, async move {
+            let first = match reqs.next().await {
+                Some(Ok(it)) => it,
+                Some(Err(e)) => {
+                    sender.fail(e);
+                    return;
+                }
+                // nothing is requested.
+                None => return,
+            };
+            // register handler
+            handlers.insert(sid, Handler::ReqRC(Channel::new(sender, outbound.clone())));
+            let (d, m) = first.split();
+            let mut bu = frame::RequestChannel::builder(sid, frame::FLAG_NEXT)
+                .set_initial_request_n(initial_request_n);
+            if let Some(b) = d {
+                bu = bu.set_data(b);
+            }
+            if let Some(b) = m {
+                bu = bu.set_metadata(b);
+            }
+            if let Err(e) = tx.send(bu.build()).await {
+                error!("send REQUEST_CHANNEL failed: {}", e);
+            }
+            let end = send_stream(sid, reqs, outbound, &mut tx, None, None, &metrics).await;
+            on_outbound_end(&handlers, sid, end);
+        });
+        spans::requester("request_channel", sid, None).flux(self.consume_flow(sid, receiver))
+    }
+}
+
+/// How the outbound side of a stream ended.
+#[derive(Debug)]
+pub(crate) enum StreamEnd {
+    Completed,
+    Cancelled,
+    Failed(RSocketError),
+}
+
+/// Apply the end of the local side of channel `sid`, which is closed if it failed.
+pub(crate) fn on_outbound_end(handlers: &StreamMap<Handler>, sid: u32, end: StreamEnd) {
+    let mut handlers = handlers.shard(sid);
+    let (channel, requester) = match (*handlers).remove(&sid) {
+        Some(Handler::ReqRC(channel)) => (channel, true),
+        Some(Handler::ResRC(channel)) => (channel, false),
+        Some(other) => {
+            (*handlers).insert(sid, other);
+            return;
+        }
+        None => return,
+    };
+    if let StreamEnd::Failed(e) = end {
+        channel.terminate(e);
+        return;
+    }
+    match channel.on_local_complete() {
+        Some(channel) if requester => (*handlers).insert(sid, Handler::ReqRC(channel)),
+        Some(channel) => (*handlers).insert(sid, Handler::ResRC(channel)),
+        None => None,
+    };
+}
+
+/// Hand the frames of `frames` to the transport until either ends, by the order of the
+/// scheduler if any.
+async fn write_frames<S, F>(
+    frames: &mut S,
+    tx: &mut TxBounded<Frame>,
+    scheduler: Option<&mut Scheduler>,
+    observe: &F,
+) where
+    S: Stream<Item = Frame> + Unpin,
+    F: Fn(&Frame),
+{
+    match scheduler {
+        Some(scheduler) => {
+            while let Some(frame) = scheduler.next(frames, tx).await {
+                observe(&frame);
+                if tx.try_send(frame).is_err() {
+                    break;
+                }
+            }
+        }
+        None => {
+            while let Some(frame) = frames.next().await {
+                observe(&frame);
+                if tx.send(frame).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Send the outputs of a stream as far as the peer demands, an error output terminates it.
+/// Outputs are consumed ahead of the demand into the stream buffer, if any.
+async fn send_stream(
+    sid: u32,
+    mut outputs: Flux<Result<Payload, RSocketError>>,
+    demand: Demand,
+    tx: &mut TxBounded<Frame>,
+    slow_consumer: Option<SlowConsumer>,
+    stream_buffer: Option<StreamBuffer>,
+    metrics: &Metrics,
+) -> StreamEnd {
+    let mut buffered = VecDeque::new();
+    let mut completed = false;
+    let mut waiting_since: Option<Instant> = None;
+    let mut reported = false;
+    let mut dropping = None;
+    loop {
+        if demand.is_cancelled() {
+            return StreamEnd::Cancelled;
+        }
+        if !buffered.is_empty() {
+            if demand.try_acquire() {
+                waiting_since = None;
+                reported = false;
+                dropping = None;
+                let (sending, failure) = match buffered.pop_front().unwrap() {
+                    Ok(it) => (to_payload_frame(sid, it), None),
+                    Err(e) => (to_error_frame(sid, &e), Some(e)),
                 };
-                if let Err(e) = tx.unbounded_send(sending) {
-                    error!("send REQUEST_CHANNEL failed: {}", e);
+                if let Err(e) = tx.send(sending).await {
+                    error!("send stream response failed: {}", e);
+                    return StreamEnd::Failed(RSocketError::from("connection is closed"));
+                }
+                if let Some(e) = failure {
+                    return StreamEnd::Failed(e);
+                }
+                continue;
+            }
+        } else if completed {
+            let complete = frame::Payload::builder(sid, frame::FLAG_COMPLETE).build();
+            if let Err(e) = tx.send(complete).await {
+                error!("send stream complete failed: {}", e);
+                return StreamEnd::Failed(RSocketError::from("connection is closed"));
+            }
+            return StreamEnd::Completed;
+        } else if demand.has_demand() {
+            // a cancelled stream stops waiting for the producer.
+            let changed = demand.changed();
+            futures::pin_mut!(changed);
+            if let future::Either::Left((next, _)) = future::select(outputs.next(), changed).await {
+                match next {
+                    Some(next) => buffered.push_back(next),
+                    None => completed = true,
                 }
             }
-            let sending = frame::Payload::builder(sid, frame::FLAG_COMPLETE).build();
-            if let Err(e) = tx.unbounded_send(sending) {
-                error!("complete REQUEST_CHANNEL failed: {}", e);
+            continue;
+        }
+
+        // the requester demands nothing.
+        let since = *waiting_since.get_or_insert_with(Instant::now);
+        let mut deadline = None;
+        if let Some(sc) = slow_consumer.filter(|_| !reported) {
+            let now = Instant::now();
+            if now < since + sc.threshold {
+                deadline = Some(since + sc.threshold);
+            } else {
+                reported = true;
+                warn!(
+                    target: "rsocket_rust::slow_consumer",
+                    "slow consumer: sid={}, no demand for {:?}, buffered={}, policy={:?}",
+                    sid,
+                    now - since,
+                    buffered.len(),
+                    sc.policy
+                );
+                metrics.on_slow_consumer(sc.policy);
+                match sc.policy {
+                    SlowConsumerPolicy::Notify => (),
+                    SlowConsumerPolicy::Error => {
+                        return fail_stream(sid, tx, "slow consumer").await;
+                    }
+                    SlowConsumerPolicy::DropOldest(n) => dropping = Some(n),
+                }
             }
+        }
+        let buffer = match dropping {
+            Some(capacity) => Some(StreamBuffer {
+                capacity,
+                policy: OverflowPolicy::DropOldest,
+            }),
+            None => stream_buffer,
+        }
+        .filter(|it| {
+            !completed && (buffered.len() < it.capacity || it.policy != OverflowPolicy::Suspend)
         });
-        Box::pin(receiver)
+
+        let changed = demand.changed();
+        let timer = async move {
+            match deadline {
+                Some(deadline) => tokio::time::delay_until(deadline).await,
+                None => future::pending().await,
+            }
+        };
+        let consuming = async {
+            match buffer {
+                Some(_) => outputs.next().await,
+                None => future::pending().await,
+            }
+        };
+        futures::pin_mut!(changed, timer, consuming);
+        let next = match future::select(changed, future::select(timer, consuming)).await {
+            future::Either::Right((future::Either::Right((next, _)), _)) => next,
+            _ => continue,
+        };
+        let buffer = buffer.unwrap();
+        match next {
+            Some(Err(e)) => {
+                // an error terminates the stream, it is never dropped.
+                buffered.push_back(Err(e));
+                completed = true;
+            }
+            Some(next) if buffered.len() < buffer.capacity => buffered.push_back(next),
+            Some(next) => match buffer.policy {
+                OverflowPolicy::Suspend => unreachable!(),
+                OverflowPolicy::DropOldest => {
+                    buffered.pop_front();
+                    buffered.push_back(next);
+                }
+                OverflowPolicy::DropNewest => debug!("drop stream output: sid={}", sid),
+                OverflowPolicy::Error => {
+                    return fail_stream(sid, tx, "stream buffer overflow").await;
+                }
+            },
+            None => completed = true,
+        }
+    }
+}
+
+/// Terminate a responder stream with an APPLICATION_ERROR of `reason`.
+async fn fail_stream(sid: u32, tx: &mut TxBounded<Frame>, reason: &'static str) -> StreamEnd {
+    let sending = frame::Error::builder(sid, 0)
+        .set_code(error::ERR_APPLICATION)
+        .set_data(Bytes::from(reason))
+        .build();
+    if let Err(e) = tx.send(sending).await {
+        error!("send stream error failed: {}", e);
+    }
+    StreamEnd::Failed(RSocketError::from(reason))
+}
+
+#[inline]
+fn to_payload_frame(sid: u32, it: Payload) -> Frame {
+    let (d, m) = it.split();
+    let mut bu = frame::Payload::builder(sid, frame::FLAG_NEXT);
+    if let Some(b) = d {
+        bu = bu.set_data(b);
+    }
+    if let Some(b) = m {
+        bu = bu.set_metadata(b);
+    }
+    bu.build()
+}
+
+/// Returns a KEEPALIVE to send, its data is produced by `data` if set.
+fn keepalive_of(stats: &StatsRecorder, data: &Option<KeepaliveData>) -> Frame {
+    let data = match data {
+        Some(f) => f(),
+        None => stats.keepalive_data(),
+    };
+    stats.on_keepalive_sent();
+    frame::Keepalive::builder(0, frame::FLAG_RESPOND)
+        .set_data(data)
+        .build()
+}
+
+pub(crate) fn connection_closed() -> RSocketError {
+    RSocketError::from(ErrorKind::Internal(
+        error::ERR_CONN_CLOSED,
+        String::from("connection closed"),
+    ))
+}
+
+/// Returns the code and the message of the ERROR which rejects a SETUP failed by `e`.
+fn setup_rejection(e: &(dyn Error + 'static)) -> (u32, String) {
+    match e.downcast_ref::<RSocketError>().map(|it| it.kind()) {
+        Some(ErrorKind::Internal(code, msg)) => (*code, msg.clone()),
+        _ => (error::ERR_REJECT_SETUP, format!("{}", e)),
+    }
+}
+
+/// Translate a local error, errors received from the peer keep their code and data.
+#[inline]
+pub(crate) fn to_error_frame(sid: u32, e: &RSocketError) -> Frame {
+    let (code, msg) = match e.kind() {
+        ErrorKind::Internal(code, msg) => (*code, msg.clone()),
+        _ => (error::ERR_APPLICATION, format!("{}", e)),
+    };
+    let data = match e.get_data() {
+        Some(data) => data.clone(),
+        None => Bytes::from(msg),
+    };
+    frame::Error::builder(sid, 0)
+        .set_code(code)
+        .set_data(data)
+        .build()
+}
+
+/// Returns the error of an ERROR frame, which keeps its raw data.
+pub(crate) fn from_error_frame(input: &frame::Error) -> RSocketError {
+    let err = RSocketError::from(ErrorKind::Internal(input.get_code(), input.get_data_utf8()));
+    match input.get_data() {
+        Some(data) => err.with_data(data.clone()),
+        None => err,
     }
 }
 
@@ -631,4 +1891,36 @@ impl RSocket for Responder {
         let inner = self.inner.read().unwrap();
         (*inner).request_channel(reqs)
     }
+
+    fn fire_and_forget_with_context(&self, ctx: RequestContext, req: Payload) -> Mono<()> {
+        let inner = self.inner.read().unwrap();
+        (*inner).fire_and_forget_with_context(ctx, req)
+    }
+
+    fn request_response_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Mono<Result<Payload, RSocketError>> {
+        let inner = self.inner.read().unwrap();
+        (*inner).request_response_with_context(ctx, req)
+    }
+
+    fn request_stream_with_context(
+        &self,
+        ctx: RequestContext,
+        req: Payload,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let inner = self.inner.read().unwrap();
+        (*inner).request_stream_with_context(ctx, req)
+    }
+
+    fn request_channel_with_context(
+        &self,
+        ctx: RequestContext,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let inner = self.inner.read().unwrap();
+        (*inner).request_channel_with_context(ctx, reqs)
+    }
 }
@@ -0,0 +1,213 @@
+//! Observation of requester and responder calls.
+//!
+//! With the `tracing` feature, every call runs in a span named `rsocket` which carries
+//! `side`, `interaction`, `stream_id`, `route` (when routing metadata is present) and
+//! `outcome` (`ok`, `error` or `cancelled`).
+//!
+//! With the `metrics` feature, the latency until the call terminates is recorded into
+//! histogram `rsocket_request_duration_seconds{side, interaction, route, outcome}`,
+//! `route` is empty without routing metadata.
+use crate::error::RSocketError;
+use crate::payload::Payload;
+use crate::spi::Flux;
+use std::future::Future;
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+use crate::error::ErrorKind;
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+use crate::router::route_of;
+#[cfg(feature = "tracing")]
+use ::tracing::{field, Instrument, Span};
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+use futures::{stream, StreamExt};
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub(crate) struct RequestSpan {
+    #[cfg(feature = "tracing")]
+    span: Span,
+    #[cfg(feature = "metrics")]
+    latency: (Instant, &'static str, &'static str, String),
+    done: bool,
+}
+
+#[cfg(not(any(feature = "tracing", feature = "metrics")))]
+pub(crate) struct RequestSpan;
+
+pub(crate) fn requester(interaction: &'static str, sid: u32, req: Option<&Payload>) -> RequestSpan {
+    RequestSpan::new("requester", interaction, sid, req)
+}
+
+pub(crate) fn responder(interaction: &'static str, sid: u32, req: Option<&Payload>) -> RequestSpan {
+    RequestSpan::new("responder", interaction, sid, req)
+}
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+impl RequestSpan {
+    fn new(
+        side: &'static str,
+        interaction: &'static str,
+        sid: u32,
+        req: Option<&Payload>,
+    ) -> RequestSpan {
+        #[cfg(feature = "tracing")]
+        let span = ::tracing::info_span!(
+            "rsocket",
+            side,
+            interaction,
+            stream_id = sid,
+            route = field::Empty,
+            outcome = field::Empty,
+            error = field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let wanted = !span.is_disabled();
+        #[cfg(not(feature = "tracing"))]
+        let wanted = false;
+        let route = if wanted || cfg!(feature = "metrics") {
+            req.and_then(route_of)
+        } else {
+            None
+        };
+        #[cfg(feature = "tracing")]
+        {
+            if let Some(route) = &route {
+                span.record("route", route.as_str());
+            }
+        }
+        RequestSpan {
+            #[cfg(feature = "tracing")]
+            span,
+            #[cfg(feature = "metrics")]
+            latency: (Instant::now(), side, interaction, route.unwrap_or_default()),
+            done: false,
+        }
+    }
+
+    pub(crate) fn unit<F>(self, task: F) -> impl Future<Output = ()>
+    where
+        F: Future<Output = ()>,
+    {
+        #[cfg(feature = "tracing")]
+        let span = self.span.clone();
+        let task = async move {
+            let mut this = self;
+            task.await;
+            this.finish("ok");
+        };
+        #[cfg(feature = "tracing")]
+        let task = task.instrument(span);
+        task
+    }
+
+    pub(crate) fn mono<F, T>(self, task: F) -> impl Future<Output = Result<T, RSocketError>>
+    where
+        F: Future<Output = Result<T, RSocketError>>,
+    {
+        #[cfg(feature = "tracing")]
+        let span = self.span.clone();
+        let task = async move {
+            let mut this = self;
+            let result = task.await;
+            match &result {
+                Ok(_) => this.finish("ok"),
+                Err(e) => this.fail(e),
+            }
+            result
+        };
+        #[cfg(feature = "tracing")]
+        let task = task.instrument(span);
+        task
+    }
+
+    pub(crate) fn flux(
+        self,
+        mut results: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        let mut this = self;
+        Box::pin(stream::poll_fn(move |cx| {
+            #[cfg(feature = "tracing")]
+            let span = this.span.clone();
+            #[cfg(feature = "tracing")]
+            let _entered = span.enter();
+            let next = results.poll_next_unpin(cx);
+            match &next {
+                std::task::Poll::Ready(Some(Err(e))) => this.fail(e),
+                std::task::Poll::Ready(None) => this.finish("ok"),
+                _ => (),
+            }
+            next
+        }))
+    }
+
+    fn fail(&mut self, e: &RSocketError) {
+        let outcome = match e.kind() {
+            ErrorKind::Cancelled() => "cancelled",
+            _ => "error",
+        };
+        #[cfg(feature = "tracing")]
+        self.span.record("error", field::display(e));
+        self.finish(outcome);
+    }
+
+    fn finish(&mut self, outcome: &'static str) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+        #[cfg(feature = "tracing")]
+        self.span.record("outcome", outcome);
+        #[cfg(feature = "metrics")]
+        {
+            let (started_at, side, interaction, route) = &self.latency;
+            ::metrics::histogram!(
+                "rsocket_request_duration_seconds",
+                "side" => *side,
+                "interaction" => *interaction,
+                "route" => route.clone(),
+                "outcome" => outcome
+            )
+            .record(started_at.elapsed().as_secs_f64());
+        }
+    }
+}
+
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+impl Drop for RequestSpan {
+    fn drop(&mut self) {
+        self.finish("cancelled");
+    }
+}
+
+#[cfg(not(any(feature = "tracing", feature = "metrics")))]
+impl RequestSpan {
+    #[inline]
+    fn new(_side: &str, _interaction: &str, _sid: u32, _req: Option<&Payload>) -> RequestSpan {
+        RequestSpan
+    }
+
+    #[inline]
+    pub(crate) fn unit<F>(self, task: F) -> F
+    where
+        F: Future<Output = ()>,
+    {
+        task
+    }
+
+    #[inline]
+    pub(crate) fn mono<F, T>(self, task: F) -> F
+    where
+        F: Future<Output = Result<T, RSocketError>>,
+    {
+        task
+    }
+
+    #[inline]
+    pub(crate) fn flux(
+        self,
+        results: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        results
+    }
+}
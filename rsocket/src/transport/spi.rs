@@ -5,12 +5,16 @@ use crate::spi::RSocket;
 use futures::channel::{mpsc, oneshot};
 use std::error::Error;
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::result::Result;
 use std::sync::Arc;
 
-pub type Tx<T> = mpsc::UnboundedSender<T>;
-pub type Rx<T> = mpsc::UnboundedReceiver<T>;
+pub type Tx<T> = mpsc::Sender<T>;
+pub type Rx<T> = mpsc::Receiver<T>;
+
+pub type TxBounded<T> = tokio::sync::mpsc::Sender<T>;
+pub type RxBounded<T> = tokio::sync::mpsc::Receiver<T>;
 
 pub type TxOnce<T> = oneshot::Sender<T>;
 pub type RxOnce<T> = oneshot::Receiver<T>;
@@ -19,19 +23,100 @@ pub(crate) fn new_tx_rx_once<T>() -> (TxOnce<T>, RxOnce<T>) {
     oneshot::channel()
 }
 
-pub(crate) fn new_tx_rx<T>() -> (Tx<T>, Rx<T>) {
-    mpsc::unbounded()
+pub(crate) fn new_tx_rx<T>(capacity: usize) -> (Tx<T>, Rx<T>) {
+    mpsc::channel(capacity)
+}
+
+pub(crate) fn new_tx_rx_bounded<T>(capacity: usize) -> (TxBounded<T>, RxBounded<T>) {
+    tokio::sync::mpsc::channel(capacity)
+}
+
+/// What is known about the remote end of an accepted connection.
+#[derive(Debug, Clone, Default)]
+pub struct PeerInfo {
+    addr: Option<SocketAddr>,
+    identity: Option<String>,
+}
+
+impl PeerInfo {
+    pub fn new(addr: Option<SocketAddr>) -> PeerInfo {
+        PeerInfo {
+            addr,
+            identity: None,
+        }
+    }
+
+    /// Set the identity the peer authenticated with, e.g. the subject of a TLS client certificate.
+    pub fn identity(mut self, identity: &str) -> PeerInfo {
+        self.identity = Some(identity.to_string());
+        self
+    }
+
+    pub fn get_addr(&self) -> Option<SocketAddr> {
+        self.addr
+    }
+
+    pub fn get_identity(&self) -> Option<&str> {
+        self.identity.as_deref()
+    }
+}
+
+/// Who a connection authenticated as by its SETUP, see `Authenticator`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Principal {
+    name: String,
+    roles: Vec<String>,
+}
+
+impl Principal {
+    pub fn new<S>(name: S) -> Principal
+    where
+        S: Into<String>,
+    {
+        Principal {
+            name: name.into(),
+            roles: vec![],
+        }
+    }
+
+    pub fn role<S>(mut self, role: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.roles.push(role.into());
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_roles(&self) -> &[String] {
+        &self.roles
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|it| it == role)
+    }
 }
 
 pub trait ClientTransport {
+    /// Describe the remote end, which is unknown by default.
+    fn peer(&self) -> PeerInfo {
+        PeerInfo::default()
+    }
+
     fn attach(
         self,
         incoming: Tx<Frame>,
-        sending: Rx<Frame>,
+        sending: RxBounded<Frame>,
         connected: Option<TxOnce<Result<(), RSocketError>>>,
     );
 }
 
+pub type ServeFuture =
+    Pin<Box<dyn Send + Future<Output = Result<(), Box<dyn Send + Sync + Error>>>>>;
+
 pub trait ServerTransport {
     type Item;
 
@@ -39,7 +124,7 @@ pub trait ServerTransport {
         self,
         starter: Option<fn()>,
         acceptor: impl Fn(Self::Item) + Send + Sync + 'static,
-    ) -> Pin<Box<dyn Send + Future<Output = Result<(), Box<dyn Send + Sync + Error>>>>>
+    ) -> ServeFuture
     where
         Self::Item: ClientTransport + Sized;
 }
@@ -47,8 +132,12 @@ pub trait ServerTransport {
 pub type FnAcceptorWithSetup =
     fn(SetupPayload, Box<dyn RSocket>) -> Result<Box<dyn RSocket>, Box<dyn Error>>;
 
+pub(crate) type BoxedAcceptor = dyn Fn(SetupPayload, Box<dyn RSocket>) -> Result<Box<dyn RSocket>, Box<dyn Error>>
+    + Send
+    + Sync;
+
 pub(crate) enum Acceptor {
     Simple(Arc<fn() -> Box<dyn RSocket>>),
-    Generate(Arc<FnAcceptorWithSetup>),
+    Generate(Arc<BoxedAcceptor>),
     Empty(),
 }
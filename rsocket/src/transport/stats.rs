@@ -0,0 +1,300 @@
+use super::health::Health;
+#[cfg(feature = "lease")]
+use super::lease::LeaseStats;
+use crate::frame::{Body, Frame};
+use crate::utils::Writeable;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const RTT_EWMA_ALPHA: f64 = 0.2;
+
+/// KEEPALIVE frames whose send times are kept, waiting for their echo.
+const MAX_KEEPALIVES_SENT: usize = 16;
+
+/// How long an ended stream counts for the error rate of the health score.
+const RECENT_STREAMS_WINDOW: Duration = Duration::from_secs(30);
+
+/// Ended streams kept for the error rate of the health score, the oldest go first.
+const MAX_RECENT_STREAMS: usize = 128;
+
+/// A snapshot of connection statistics.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConnectionStats {
+    frames_sent: u64,
+    frames_received: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    active_streams: usize,
+    stalled_streams: usize,
+    recent_streams: usize,
+    recent_failures: usize,
+    keepalive_rtt: Option<Duration>,
+    keepalive_rtt_ewma: Option<Duration>,
+    uptime: Duration,
+    last_error: Option<(u32, String)>,
+    stream_pool_hits: u64,
+    stream_pool_misses: u64,
+    #[cfg(feature = "lease")]
+    lease: Option<LeaseStats>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct StatsRecorder {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    started_at: Instant,
+    frames_sent: AtomicU64,
+    frames_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    streams: Mutex<HashSet<u32>>,
+    keepalive_rtt: Mutex<Option<(Duration, Duration)>>,
+    keepalives_sent: Mutex<VecDeque<Duration>>,
+    last_error: Mutex<Option<(u32, String)>>,
+    /// End times of the recent streams, and whether they failed.
+    recent: Mutex<VecDeque<(Instant, bool)>>,
+}
+
+impl ConnectionStats {
+    pub fn get_frames_sent(&self) -> u64 {
+        self.frames_sent
+    }
+
+    pub fn get_frames_received(&self) -> u64 {
+        self.frames_received
+    }
+
+    pub fn get_bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    pub fn get_bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// Returns the number of streams which have not terminated yet.
+    pub fn get_active_streams(&self) -> usize {
+        self.active_streams
+    }
+
+    /// Returns the number of responder streams waiting for the requester to demand more.
+    pub fn get_stalled_streams(&self) -> usize {
+        self.stalled_streams
+    }
+
+    /// Returns the health score of the connection.
+    pub fn get_health(&self) -> Health {
+        Health::new(
+            self.keepalive_rtt_ewma,
+            self.recent_failures,
+            self.recent_streams,
+            self.stalled_streams,
+            self.active_streams,
+        )
+    }
+
+    /// Returns the last measured KEEPALIVE round-trip time.
+    pub fn get_keepalive_rtt(&self) -> Option<Duration> {
+        self.keepalive_rtt
+    }
+
+    /// Returns the exponentially weighted moving average of KEEPALIVE round-trip times.
+    pub fn get_keepalive_rtt_ewma(&self) -> Option<Duration> {
+        self.keepalive_rtt_ewma
+    }
+
+    pub fn get_uptime(&self) -> Duration {
+        self.uptime
+    }
+
+    /// Returns the code and message of the last ERROR frame sent or received.
+    pub fn get_last_error(&self) -> Option<(u32, &str)> {
+        self.last_error
+            .as_ref()
+            .map(|(code, msg)| (*code, msg.as_str()))
+    }
+
+    /// Returns the number of per-stream states reused from the pool.
+    pub fn get_stream_pool_hits(&self) -> u64 {
+        self.stream_pool_hits
+    }
+
+    /// Returns the number of per-stream states which had to be allocated.
+    pub fn get_stream_pool_misses(&self) -> u64 {
+        self.stream_pool_misses
+    }
+
+    /// Returns the lease statistics, None unless leasing was negotiated by SETUP.
+    #[cfg(feature = "lease")]
+    pub fn get_lease(&self) -> Option<&LeaseStats> {
+        self.lease.as_ref()
+    }
+
+    #[cfg(feature = "lease")]
+    pub(crate) fn with_lease(mut self, lease: LeaseStats) -> ConnectionStats {
+        self.lease = Some(lease);
+        self
+    }
+
+    pub(crate) fn with_stalled_streams(mut self, stalled: usize) -> ConnectionStats {
+        self.stalled_streams = stalled;
+        self
+    }
+
+    pub(crate) fn with_stream_pool(mut self, hits: u64, misses: u64) -> ConnectionStats {
+        self.stream_pool_hits = hits;
+        self.stream_pool_misses = misses;
+        self
+    }
+}
+
+impl StatsRecorder {
+    pub(crate) fn new() -> StatsRecorder {
+        StatsRecorder {
+            inner: Arc::new(Inner {
+                started_at: Instant::now(),
+                frames_sent: AtomicU64::new(0),
+                frames_received: AtomicU64::new(0),
+                bytes_sent: AtomicU64::new(0),
+                bytes_received: AtomicU64::new(0),
+                streams: Mutex::new(HashSet::new()),
+                keepalive_rtt: Mutex::new(None),
+                keepalives_sent: Mutex::new(VecDeque::new()),
+                last_error: Mutex::new(None),
+                recent: Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    pub(crate) fn on_inbound(&self, frame: &Frame) {
+        self.inner.frames_received.fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .bytes_received
+            .fetch_add(frame.len() as u64, Ordering::Relaxed);
+        self.on_frame(frame);
+    }
+
+    pub(crate) fn on_outbound(&self, frame: &Frame) {
+        self.inner.frames_sent.fetch_add(1, Ordering::Relaxed);
+        self.inner
+            .bytes_sent
+            .fetch_add(frame.len() as u64, Ordering::Relaxed);
+        self.on_frame(frame);
+    }
+
+    /// Returns the default data of a KEEPALIVE frame, which is its send time.
+    pub(crate) fn keepalive_data(&self) -> Bytes {
+        let mut bf = BytesMut::with_capacity(8);
+        bf.put_u64(self.inner.started_at.elapsed().as_micros() as u64);
+        bf.freeze()
+    }
+
+    /// Record the send time of a KEEPALIVE, the peer answers KEEPALIVE frames in order, with
+    /// any data.
+    pub(crate) fn on_keepalive_sent(&self) {
+        let mut sent = self.inner.keepalives_sent.lock().unwrap();
+        if sent.len() >= MAX_KEEPALIVES_SENT {
+            sent.pop_front();
+        }
+        sent.push_back(self.inner.started_at.elapsed());
+    }
+
+    /// Record the round trip of an echoed KEEPALIVE, returns it if its send time is known.
+    pub(crate) fn on_keepalive_ack(&self, data: Option<&Bytes>) -> Option<Duration> {
+        let sent = self.inner.keepalives_sent.lock().unwrap().pop_front();
+        let sent = match (sent, data) {
+            (Some(sent), _) => sent,
+            (None, Some(b)) if b.len() == 8 => Duration::from_micros(b.clone().get_u64()),
+            _ => return None,
+        };
+        let rtt = self.inner.started_at.elapsed().checked_sub(sent)?;
+        let mut current = self.inner.keepalive_rtt.lock().unwrap();
+        let ewma = match *current {
+            Some((_, avg)) => avg.mul_f64(1.0 - RTT_EWMA_ALPHA) + rtt.mul_f64(RTT_EWMA_ALPHA),
+            None => rtt,
+        };
+        *current = Some((rtt, ewma));
+        Some(rtt)
+    }
+
+    pub(crate) fn snapshot(&self) -> ConnectionStats {
+        let inner = &self.inner;
+        let keepalive_rtt = *inner.keepalive_rtt.lock().unwrap();
+        let (recent_streams, recent_failures) = {
+            let mut recent = inner.recent.lock().unwrap();
+            expire(&mut recent);
+            (
+                recent.len(),
+                recent.iter().filter(|(_, failed)| *failed).count(),
+            )
+        };
+        ConnectionStats {
+            frames_sent: inner.frames_sent.load(Ordering::Relaxed),
+            frames_received: inner.frames_received.load(Ordering::Relaxed),
+            bytes_sent: inner.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: inner.bytes_received.load(Ordering::Relaxed),
+            active_streams: inner.streams.lock().unwrap().len(),
+            stalled_streams: 0,
+            recent_streams,
+            recent_failures,
+            keepalive_rtt: keepalive_rtt.map(|it| it.0),
+            keepalive_rtt_ewma: keepalive_rtt.map(|it| it.1),
+            uptime: inner.started_at.elapsed(),
+            last_error: inner.last_error.lock().unwrap().clone(),
+            stream_pool_hits: 0,
+            stream_pool_misses: 0,
+            #[cfg(feature = "lease")]
+            lease: None,
+        }
+    }
+
+    fn on_frame(&self, frame: &Frame) {
+        let sid = frame.get_stream_id();
+        match frame.get_body_ref() {
+            Body::RequestResponse(_) | Body::RequestStream(_) | Body::RequestChannel(_) => {
+                self.inner.streams.lock().unwrap().insert(sid);
+            }
+            Body::Payload(_) if frame.has_complete() => self.on_end(sid, false),
+            Body::Cancel() => self.on_end(sid, false),
+            Body::Error(e) => {
+                if sid != 0 {
+                    self.on_end(sid, true);
+                }
+                let msg = match e.get_data() {
+                    Some(b) => String::from_utf8_lossy(b).into_owned(),
+                    None => String::new(),
+                };
+                *self.inner.last_error.lock().unwrap() = Some((e.get_code(), msg));
+            }
+            _ => (),
+        }
+    }
+
+    fn on_end(&self, sid: u32, failed: bool) {
+        if !self.inner.streams.lock().unwrap().remove(&sid) {
+            return;
+        }
+        let mut recent = self.inner.recent.lock().unwrap();
+        expire(&mut recent);
+        if recent.len() >= MAX_RECENT_STREAMS {
+            recent.pop_front();
+        }
+        recent.push_back((Instant::now(), failed));
+    }
+}
+
+fn expire(recent: &mut VecDeque<(Instant, bool)>) {
+    while let Some((ended, _)) = recent.front() {
+        if ended.elapsed() < RECENT_STREAMS_WINDOW {
+            break;
+        }
+        recent.pop_front();
+    }
+}
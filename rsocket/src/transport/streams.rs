@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+const SHARDS: usize = 32;
+
+/// Active streams of a connection by stream id, split into independently locked shards so
+/// frames of different streams rarely contend.
+///
+/// Locks are synchronous, a shard must never be held across an await point.
+#[derive(Debug)]
+pub(crate) struct StreamMap<V> {
+    shards: Arc<[Mutex<HashMap<u32, V>>]>,
+}
+
+impl<V> Clone for StreamMap<V> {
+    fn clone(&self) -> Self {
+        StreamMap {
+            shards: self.shards.clone(),
+        }
+    }
+}
+
+impl<V> StreamMap<V> {
+    pub(crate) fn new() -> StreamMap<V> {
+        StreamMap {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+        }
+    }
+
+    /// Lock the shard which owns `sid`.
+    #[inline]
+    pub(crate) fn shard(&self, sid: u32) -> MutexGuard<'_, HashMap<u32, V>> {
+        // ids of one side share their lowest bit.
+        self.shards[(sid >> 1) as usize % SHARDS].lock().unwrap()
+    }
+
+    #[inline]
+    pub(crate) fn insert(&self, sid: u32, value: V) {
+        self.shard(sid).insert(sid, value);
+    }
+
+    #[inline]
+    pub(crate) fn remove(&self, sid: u32) -> Option<V> {
+        self.shard(sid).remove(&sid)
+    }
+
+    /// Count the streams whose value matches `f`, shard by shard.
+    pub(crate) fn count<F>(&self, f: F) -> usize
+    where
+        F: Fn(&V) -> bool,
+    {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().values().filter(|it| f(it)).count())
+            .sum()
+    }
+
+    /// Returns the ids of the streams, shard by shard.
+    pub(crate) fn ids(&self) -> Vec<u32> {
+        let mut ids = vec![];
+        for shard in self.shards.iter() {
+            ids.extend(shard.lock().unwrap().keys());
+        }
+        ids
+    }
+
+    /// Remove every stream, shard by shard.
+    pub(crate) fn drain(&self) -> Vec<(u32, V)> {
+        let mut drained = vec![];
+        for shard in self.shards.iter() {
+            drained.extend(shard.lock().unwrap().drain());
+        }
+        drained
+    }
+}
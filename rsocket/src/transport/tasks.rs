@@ -0,0 +1,123 @@
+use crate::runtime::Spawner;
+use futures::channel::oneshot;
+use futures::future::{AbortHandle, Abortable};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// The tasks spawned for a connection, which live no longer than it.
+///
+/// Closing the group aborts its tasks, except the graceful ones which are told to end by
+/// `closing`. Tasks spawned once it is closed are dropped, `join` waits for the rest to end.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TaskGroup {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    seq: u64,
+    tasks: HashMap<u64, Option<AbortHandle>>,
+    closing: Vec<oneshot::Sender<()>>,
+    idle: Vec<oneshot::Sender<()>>,
+    closed: bool,
+}
+
+impl TaskGroup {
+    /// Spawn a task which is aborted once the group is closed.
+    pub(crate) fn spawn<R, F>(&self, rt: &R, task: F)
+    where
+        R: Spawner,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        if let Some(task) = self.bind(task) {
+            rt.spawn(task);
+        }
+    }
+
+    /// Spawn a task which ends by itself once `closing` completes, to finish its work first.
+    pub(crate) fn spawn_graceful<R, F>(&self, rt: &R, task: F)
+    where
+        R: Spawner,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        if let Some(id) = self.register(None) {
+            let group = self.clone();
+            rt.spawn(async move {
+                task.await;
+                group.done(id);
+            });
+        }
+    }
+
+    /// Returns `task` as a member of the group to run elsewhere, None if it is closed.
+    pub(crate) fn bind<F>(&self, task: F) -> Option<impl Future<Output = ()> + Send + 'static>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (handle, registration) = AbortHandle::new_pair();
+        let id = self.register(Some(handle))?;
+        let group = self.clone();
+        Some(async move {
+            let _ = Abortable::new(task, registration).await;
+            group.done(id);
+        })
+    }
+
+    /// Returns a future which completes once the group is closed.
+    pub(crate) fn closing(&self) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.closed {
+            inner.closing.push(tx);
+        }
+        rx
+    }
+
+    pub(crate) fn close(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closed {
+            return;
+        }
+        inner.closed = true;
+        for handle in inner.tasks.values().flatten() {
+            handle.abort();
+        }
+        inner.closing.clear();
+    }
+
+    /// Wait for every task of the group to end.
+    pub(crate) async fn join(&self) {
+        let rx = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.tasks.is_empty() {
+                return;
+            }
+            let (tx, rx) = oneshot::channel();
+            inner.idle.push(tx);
+            rx
+        };
+        let _ = rx.await;
+    }
+
+    fn register(&self, handle: Option<AbortHandle>) -> Option<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closed {
+            return None;
+        }
+        inner.seq += 1;
+        let id = inner.seq;
+        inner.tasks.insert(id, handle);
+        Some(id)
+    }
+
+    fn done(&self, id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tasks.remove(&id);
+        if inner.tasks.is_empty() {
+            for tx in inner.idle.drain(..) {
+                let _ = tx.send(());
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::frame::Frame;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Per-stream buckets are pruned once there are more of them than this.
+const PRUNE_THRESHOLD: usize = 256;
+
+/// Maximum rates of the frames a connection writes, both unlimited by default.
+///
+/// The writer spreads the frames evenly over time, at most `burst` of them are written back
+/// to back once it was idle. Frames of the connection itself, such as KEEPALIVE, are never
+/// held back, so a busy stream can not get the connection timed out by the peer. Throttled
+/// frames wait in the outbound queue, see `ClientBuilder::outbound_capacity`: once it is full
+/// of them, the frames sent after them wait as well.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct FrameRate {
+    overall: Option<u32>,
+    per_stream: Option<u32>,
+    burst: Option<u32>,
+}
+
+impl FrameRate {
+    pub fn new() -> FrameRate {
+        FrameRate::default()
+    }
+
+    /// Write at most `n` frames per second over all the streams.
+    pub fn max_frames(mut self, n: u32) -> Self {
+        assert!(n > 0, "frame rate must be positive");
+        self.overall = Some(n);
+        self
+    }
+
+    /// Write at most `n` frames per second of each stream.
+    pub fn max_stream_frames(mut self, n: u32) -> Self {
+        assert!(n > 0, "frame rate must be positive");
+        self.per_stream = Some(n);
+        self
+    }
+
+    /// Write up to `n` frames back to back, a tenth of each rate by default.
+    pub fn burst(mut self, n: u32) -> Self {
+        assert!(n > 0, "burst must be positive");
+        self.burst = Some(n);
+        self
+    }
+
+    pub fn get_max_frames(&self) -> Option<u32> {
+        self.overall
+    }
+
+    pub fn get_max_stream_frames(&self) -> Option<u32> {
+        self.per_stream
+    }
+
+    pub fn get_burst(&self) -> Option<u32> {
+        self.burst
+    }
+
+    fn burst_of(&self, rate: u32) -> f64 {
+        f64::from(self.burst.unwrap_or_else(|| (rate / 10).max(1)))
+    }
+}
+
+/// A token bucket, which refills by `rate` tokens per second up to `burst`.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(rate: u32, burst: f64, now: Instant) -> Bucket {
+        Bucket {
+            rate: f64::from(rate),
+            burst,
+            tokens: burst,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = now;
+    }
+
+    /// Returns when a frame may be written, `now` if right away.
+    fn ready_at(&mut self, now: Instant) -> Instant {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            now
+        } else {
+            now + Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        }
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.burst
+    }
+}
+
+/// Enforces the `FrameRate` of a connection for its writer.
+pub(crate) struct Throttle {
+    rate: FrameRate,
+    overall: Option<Bucket>,
+    streams: HashMap<u32, Bucket>,
+    prune_at: usize,
+}
+
+impl Throttle {
+    pub(crate) fn new(rate: FrameRate) -> Throttle {
+        let now = Instant::now();
+        Throttle {
+            rate,
+            overall: rate
+                .overall
+                .map(|it| Bucket::new(it, rate.burst_of(it), now)),
+            streams: HashMap::new(),
+            prune_at: PRUNE_THRESHOLD,
+        }
+    }
+
+    /// Returns when frames of streams may be written as far as the overall rate goes.
+    pub(crate) fn overall_ready_at(&mut self, now: Instant) -> Instant {
+        match &mut self.overall {
+            Some(bucket) => bucket.ready_at(now),
+            None => now,
+        }
+    }
+
+    /// Returns when `frame` may be written, `now` if right away.
+    pub(crate) fn ready_at(&mut self, frame: &Frame, now: Instant) -> Instant {
+        let sid = frame.get_stream_id();
+        if sid == 0 {
+            return now;
+        }
+        let at = self.overall_ready_at(now);
+        match self.rate.per_stream {
+            Some(rate) => {
+                let burst = self.rate.burst_of(rate);
+                let bucket = self
+                    .streams
+                    .entry(sid)
+                    .or_insert_with(|| Bucket::new(rate, burst, now));
+                at.max(bucket.ready_at(now))
+            }
+            None => at,
+        }
+    }
+
+    /// Take the tokens of a frame which is written.
+    pub(crate) fn on_write(&mut self, frame: &Frame, now: Instant) {
+        let sid = frame.get_stream_id();
+        if sid == 0 {
+            return;
+        }
+        if let Some(bucket) = &mut self.overall {
+            bucket.tokens -= 1.0;
+        }
+        if let Some(bucket) = self.streams.get_mut(&sid) {
+            bucket.tokens -= 1.0;
+        }
+        // a full bucket is as good as none, which drops the buckets of ended streams.
+        if self.streams.len() > self.prune_at {
+            self.streams.retain(|_, bucket| !bucket.is_full(now));
+            self.prune_at = (self.streams.len() * 2).max(PRUNE_THRESHOLD);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::frame::{self, Body, Frame, REQUEST_MAX};
+
+/// How a connection handles frames of the peer which violate the protocol.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ValidationMode {
+    /// Close the connection with a CONNECTION_ERROR.
+    Strict,
+    /// Log and ignore the frame.
+    #[default]
+    Lenient,
+}
+
+/// Check the stream ID, flags and fields of a frame of the peer, whose requests use the
+/// stream IDs of `peer_parity`. Returns the violation, if any.
+pub(crate) fn check(frame: &Frame, peer_parity: u32) -> Result<(), String> {
+    let sid = frame.get_stream_id();
+    let body = frame.get_body_ref();
+    let name = name_of(body);
+    match body {
+        Body::Setup(_)
+        | Body::Lease(_)
+        | Body::Keepalive(_)
+        | Body::Resume(_)
+        | Body::ResumeOK(_)
+            if sid != 0 =>
+        {
+            return Err(format!("{} must be sent on stream 0", name));
+        }
+        Body::RequestFNF(_)
+        | Body::RequestResponse(_)
+        | Body::RequestStream(_)
+        | Body::RequestChannel(_)
+        | Body::Payload(_)
+        | Body::RequestN(_)
+        | Body::Cancel()
+            if sid == 0 =>
+        {
+            return Err(format!("{} must not be sent on stream 0", name));
+        }
+        _ => (),
+    }
+    let invalid_n = |n: u32| n == 0 || n > REQUEST_MAX;
+    match body {
+        Body::RequestFNF(_)
+        | Body::RequestResponse(_)
+        | Body::RequestStream(_)
+        | Body::RequestChannel(_)
+            if sid % 2 != peer_parity =>
+        {
+            Err(format!(
+                "{} {} uses a stream ID of the other side",
+                name, sid
+            ))
+        }
+        Body::RequestStream(v) if invalid_n(v.get_initial_request_n()) => Err(format!(
+            "{} {} requests {}",
+            name,
+            sid,
+            v.get_initial_request_n()
+        )),
+        Body::RequestChannel(v) if invalid_n(v.get_initial_request_n()) => Err(format!(
+            "{} {} requests {}",
+            name,
+            sid,
+            v.get_initial_request_n()
+        )),
+        Body::RequestN(v) if invalid_n(v.get_n()) => {
+            Err(format!("{} {} requests {}", name, sid, v.get_n()))
+        }
+        Body::Payload(_) if frame.get_flag() & (frame::FLAG_NEXT | frame::FLAG_COMPLETE) == 0 => {
+            Err(format!("{} {} is neither NEXT nor COMPLETE", name, sid))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn is_request(frame: &Frame) -> bool {
+    matches!(
+        frame.get_body_ref(),
+        Body::RequestFNF(_)
+            | Body::RequestResponse(_)
+            | Body::RequestStream(_)
+            | Body::RequestChannel(_)
+    )
+}
+
+fn name_of(body: &Body) -> &'static str {
+    match body {
+        Body::Setup(_) => "SETUP",
+        Body::Lease(_) => "LEASE",
+        Body::Keepalive(_) => "KEEPALIVE",
+        Body::RequestFNF(_) => "REQUEST_FNF",
+        Body::RequestResponse(_) => "REQUEST_RESPONSE",
+        Body::RequestStream(_) => "REQUEST_STREAM",
+        Body::RequestChannel(_) => "REQUEST_CHANNEL",
+        Body::RequestN(_) => "REQUEST_N",
+        Body::Cancel() => "CANCEL",
+        Body::Payload(_) => "PAYLOAD",
+        Body::Error(_) => "ERROR",
+        Body::MetadataPush(_) => "METADATA_PUSH",
+        Body::Resume(_) => "RESUME",
+        Body::ResumeOK(_) => "RESUME_OK",
+    }
+}
@@ -1,9 +1,6 @@
 use crate::error::RSocketError;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use std::error::Error;
-use std::future::Future;
-use std::pin::Pin;
-use std::result::Result;
+use core::result::Result;
 
 pub const DEFAULT_MIME_TYPE: &str = "application/binary";
 
@@ -15,6 +12,14 @@ pub trait Writeable {
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Encode behind a u24 length prefix, reserving room for both in `bf` first.
+    fn write_length_prefixed_to(&self, bf: &mut BytesMut) {
+        let n = self.len();
+        bf.reserve(3 + n);
+        U24::write(n as u32, bf);
+        self.write_to(bf);
+    }
 }
 
 pub struct U24;
@@ -1,26 +1,73 @@
+#[cfg(feature = "serde")]
+use crate::codec;
 use crate::error::RSocketError;
 use crate::frame::{self, Frame};
+#[cfg(feature = "extension")]
+use crate::health;
+use crate::interceptor::{CaptureRecorder, FrameLogger};
+use crate::mime;
 use crate::payload::{Payload, SetupPayload, SetupPayloadBuilder};
 use crate::runtime::{DefaultSpawner, Spawner};
 use crate::spi::{Flux, Mono, RSocket};
-use crate::transport::{self, Acceptor, ClientTransport, DuplexSocket, Rx, Tx};
+use crate::transport::{
+    self, Acceptor, ByteBudget, ChannelSink, ClientTransport, ConnectionParams, ConnectionStats,
+    DuplexSocket, Execution, FrameRate, OverflowPolicy, PeerInfo, Rx, SlowConsumer,
+    SlowConsumerPolicy, SocketOptions, StreamBuffer, Tx, TxBounded, ValidationMode,
+};
+#[cfg(feature = "resume")]
+use crate::transport::{Reconnected, ResumeSession};
+use crate::utils::DEFAULT_MIME_TYPE;
+use bytes::Bytes;
 use futures::channel::{mpsc, oneshot};
-use futures::{Future, Stream};
+use futures::{Future, Stream, StreamExt};
 use std::error::Error;
 use std::net::SocketAddr;
+use std::ops::Deref;
 use std::pin::Pin;
 use std::result::Result;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
 
+#[cfg(feature = "resume")]
+type FnOnReconnect = Box<dyn Fn(&Reconnected) + Send + Sync>;
+
+/// A client connection driven by a task spawned for it, see `ClientBuilder::start`. It has
+/// the API of its `Requester`.
 #[derive(Clone)]
 pub struct Client<R>
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+{
+    requester: Requester<R>,
+}
+
+/// The handle of a client connection to send requests by, see `ClientBuilder::connect`.
+///
+/// It is cheap to clone, all the clones send over the same connection.
+#[derive(Clone)]
+pub struct Requester<R>
 where
     R: Send + Sync + Clone + Spawner + 'static,
 {
     socket: DuplexSocket<R>,
+    data_mime_type: String,
+}
+
+/// Drives a client connection, reading and dispatching the frames of the server until the
+/// connection is closed. It resolves with the error which failed the connection, if any.
+///
+/// Requests get no responses unless it is polled, dropping it closes the connection.
+#[must_use = "the connection makes no progress unless it is polled"]
+pub struct Connection {
+    inner: Pin<Box<dyn Future<Output = Result<(), RSocketError>> + Send>>,
 }
 
+/// Closes the socket of a connection dropped before its end.
+struct CloseOnDrop<R>(Option<DuplexSocket<R>>)
+where
+    R: Send + Sync + Clone + Spawner + 'static;
+
 pub struct ClientBuilder<T>
 where
     T: Send + Sync + ClientTransport + 'static,
@@ -28,19 +75,270 @@ where
     transport: Option<T>,
     setup: SetupPayloadBuilder,
     responder: Option<fn() -> Box<dyn RSocket>>,
+    opts: SocketOptions,
+    #[cfg(feature = "resume")]
+    resume: Option<ResumeSession>,
+    #[cfg(feature = "resume")]
+    resume_fallback: Option<Box<dyn FnOnce() -> T + Send + Sync>>,
+    #[cfg(feature = "resume")]
+    on_reconnect: Option<FnOnReconnect>,
 }
 
+/// The peer, the sending and the receiving side of a dialed transport.
+type Dialed = (PeerInfo, TxBounded<Frame>, Rx<Frame>);
+
 impl<R> Client<R>
 where
     R: Send + Sync + Clone + Spawner + 'static,
 {
-    fn new(socket: DuplexSocket<R>) -> Client<R> {
-        Client { socket }
+    fn new(requester: Requester<R>) -> Client<R> {
+        Client { requester }
+    }
+
+    pub fn close(self) {
+        self.requester.close();
+    }
+
+    /// Close the connection and wait for the tasks spawned for it to end.
+    pub async fn shutdown(self) {
+        self.requester.shutdown().await
+    }
+
+    /// Close the connection once the requests in flight end, waiting for them no longer than
+    /// `grace`. New requests are rejected meanwhile, the streams left are terminated.
+    pub async fn close_gracefully(self, grace: Duration) {
+        self.requester.close_gracefully(grace).await
+    }
+}
+
+impl<R> Deref for Client<R>
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+{
+    type Target = Requester<R>;
+
+    fn deref(&self) -> &Requester<R> {
+        &self.requester
+    }
+}
+
+impl<R> Requester<R>
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+{
+    fn new(socket: DuplexSocket<R>, data_mime_type: String) -> Requester<R> {
+        Requester {
+            socket,
+            data_mime_type,
+        }
+    }
+
+    /// Returns a snapshot of the connection statistics.
+    pub fn stats(&self) -> ConnectionStats {
+        self.socket.stats()
+    }
+
+    /// Returns the data MIME type declared in SETUP.
+    pub fn get_data_mime_type(&self) -> &String {
+        &self.data_mime_type
+    }
+
+    /// Returns the connection parameters negotiated by SETUP.
+    pub fn params(&self) -> ConnectionParams {
+        self.socket.params().expect("SETUP is sent on start")
     }
 
+    /// Close the connection, which resolves its `Connection`.
     pub fn close(self) {
         self.socket.close();
     }
+
+    /// Close the connection and wait for the tasks spawned for it to end.
+    pub async fn shutdown(self) {
+        let socket = self.socket.clone();
+        self.socket.close();
+        socket.join().await
+    }
+
+    /// Close the connection once the requests in flight end, waiting for them no longer than
+    /// `grace`. New requests are rejected meanwhile, the streams left are terminated.
+    pub async fn close_gracefully(self, grace: Duration) {
+        self.socket.close_gracefully(grace).await
+    }
+
+    /// Send a KEEPALIVE and wait for the server to echo it, returns the round trip time.
+    pub async fn ping(&self) -> Result<Duration, RSocketError> {
+        self.socket.ping().await
+    }
+
+    /// Check the server is healthy within `timeout` by a request to the health route, see
+    /// `health::HealthCheck`. Servers which do not serve it are checked by a KEEPALIVE round
+    /// trip instead, as are connections without composite metadata.
+    pub async fn health_check(&self, timeout: Duration) -> Result<(), RSocketError> {
+        match tokio::time::timeout(timeout, self.check_health()).await {
+            Ok(result) => result,
+            Err(_) => Err(RSocketError::from("health check timed out")),
+        }
+    }
+
+    async fn check_health(&self) -> Result<(), RSocketError> {
+        #[cfg(feature = "extension")]
+        {
+            if self.params().get_metadata_mime_type()
+                == mime::MESSAGE_X_RSOCKET_COMPOSITE_METADATA_V0
+            {
+                let req = Payload::builder()
+                    .metadata()
+                    .route(health::HEALTH_ROUTE)
+                    .build();
+                match self.request_response(req).await {
+                    Ok(_) => return Ok(()),
+                    Err(e) if !health::is_unsupported(&e) => return Err(e),
+                    Err(e) => debug!("health check by KEEPALIVE: {}", e),
+                }
+            }
+        }
+        self.ping().await.map(|_| ())
+    }
+
+    /// Returns true once the connection was closed by either end.
+    pub fn is_closed(&self) -> bool {
+        self.socket.is_closed()
+    }
+
+    /// Open a REQUEST_CHANNEL whose payloads are sent through a sink, which is ready only as
+    /// far as the responder requests payloads.
+    pub fn request_channel_sink(&self) -> (ChannelSink, Flux<Result<Payload, RSocketError>>) {
+        self.socket.request_channel_sink()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<R> Requester<R>
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+{
+    pub async fn fire_and_forget_json<Req>(&self, req: &Req) -> Result<(), RSocketError>
+    where
+        Req: serde::Serialize,
+    {
+        self.fire_and_forget_as(mime::APPLICATION_JSON, req).await
+    }
+
+    pub async fn request_response_json<Req, Resp>(&self, req: &Req) -> Result<Resp, RSocketError>
+    where
+        Req: serde::Serialize,
+        Resp: serde::de::DeserializeOwned,
+    {
+        self.request_response_as(mime::APPLICATION_JSON, req).await
+    }
+
+    pub fn request_stream_json<Req, Resp>(&self, req: &Req) -> Flux<Result<Resp, RSocketError>>
+    where
+        Req: serde::Serialize,
+        Resp: serde::de::DeserializeOwned + Send + Sync + 'static,
+    {
+        self.request_stream_as(mime::APPLICATION_JSON, req)
+    }
+
+    /// Serialize the request with the data MIME type of the connection.
+    pub async fn fire_and_forget_typed<Req>(&self, req: &Req) -> Result<(), RSocketError>
+    where
+        Req: serde::Serialize,
+    {
+        self.fire_and_forget_as(&self.data_mime_type, req).await
+    }
+
+    /// Serialize the request with the data MIME type of the connection, the response is
+    /// deserialized with its per-stream MIME type if present.
+    pub async fn request_response_typed<Req, Resp>(&self, req: &Req) -> Result<Resp, RSocketError>
+    where
+        Req: serde::Serialize,
+        Resp: serde::de::DeserializeOwned,
+    {
+        self.request_response_as(&self.data_mime_type, req).await
+    }
+
+    pub fn request_stream_typed<Req, Resp>(&self, req: &Req) -> Flux<Result<Resp, RSocketError>>
+    where
+        Req: serde::Serialize,
+        Resp: serde::de::DeserializeOwned + Send + Sync + 'static,
+    {
+        self.request_stream_as(&self.data_mime_type, req)
+    }
+
+    pub async fn fire_and_forget_as<Req>(
+        &self,
+        mime_type: &str,
+        req: &Req,
+    ) -> Result<(), RSocketError>
+    where
+        Req: serde::Serialize,
+    {
+        let req = self.typed_payload(mime_type, req)?;
+        self.socket.fire_and_forget(req).await;
+        Ok(())
+    }
+
+    pub async fn request_response_as<Req, Resp>(
+        &self,
+        mime_type: &str,
+        req: &Req,
+    ) -> Result<Resp, RSocketError>
+    where
+        Req: serde::Serialize,
+        Resp: serde::de::DeserializeOwned,
+    {
+        let req = self.typed_payload(mime_type, req)?;
+        let res = self.socket.request_response(req).await?;
+        codec::decode_payload(&res, mime_type)
+    }
+
+    pub fn request_stream_as<Req, Resp>(
+        &self,
+        mime_type: &str,
+        req: &Req,
+    ) -> Flux<Result<Resp, RSocketError>>
+    where
+        Req: serde::Serialize,
+        Resp: serde::de::DeserializeOwned + Send + Sync + 'static,
+    {
+        match self.typed_payload(mime_type, req) {
+            Ok(req) => Box::pin(codec::decode_stream(
+                self.socket.request_stream(req),
+                mime_type,
+            )),
+            Err(e) => Box::pin(futures::stream::iter(Some(Err(e)))),
+        }
+    }
+
+    /// Serialize every request with the data MIME type of the connection.
+    pub fn request_channel_typed<S, Req, Resp>(&self, reqs: S) -> Flux<Result<Resp, RSocketError>>
+    where
+        S: Stream<Item = Req> + Send + Sync + 'static,
+        Req: serde::Serialize,
+        Resp: serde::de::DeserializeOwned + Send + Sync + 'static,
+    {
+        let reqs = codec::encode_stream(reqs, &self.data_mime_type);
+        Box::pin(codec::decode_stream(
+            self.socket.request_channel(reqs),
+            &self.data_mime_type,
+        ))
+    }
+
+    /// Serialize the request, a per-stream MIME type is attached
+    /// when it differs from the data MIME type of the connection.
+    fn typed_payload<Req>(&self, mime_type: &str, req: &Req) -> Result<Payload, RSocketError>
+    where
+        Req: serde::Serialize,
+    {
+        let data = codec::encode(mime_type, req)?;
+        let mut bu = Payload::builder().set_data(data);
+        if self.data_mime_type != mime_type {
+            bu = bu.metadata().mime_type(mime_type)?.end();
+        }
+        Ok(bu.build())
+    }
 }
 
 impl<T> ClientBuilder<T>
@@ -52,6 +350,13 @@ where
             transport: None,
             responder: None,
             setup: SetupPayload::builder(),
+            opts: SocketOptions::default(),
+            #[cfg(feature = "resume")]
+            resume: None,
+            #[cfg(feature = "resume")]
+            resume_fallback: None,
+            #[cfg(feature = "resume")]
+            on_reconnect: None,
         }
     }
 
@@ -104,41 +409,395 @@ where
         self
     }
 
+    /// Bound the number of frames queued for the transport writer, producers wait
+    /// for room once it is reached. Defaults to 1024.
+    pub fn outbound_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "outbound capacity must be positive");
+        self.opts.outbound_capacity = Some(capacity);
+        self
+    }
+
+    /// Bound the number of frames read from the transport and not handled yet, the
+    /// transport stops reading once it is reached. Defaults to 1024.
+    pub fn inbound_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "inbound capacity must be positive");
+        self.opts.inbound_capacity = Some(capacity);
+        self
+    }
+
+    /// Bound the number of payloads buffered for each requested stream or channel until
+    /// they are consumed, frames of the connection are not read while a stream is full.
+    /// Defaults to 256.
+    pub fn stream_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "stream capacity must be positive");
+        self.opts.stream_capacity = Some(capacity);
+        self
+    }
+
+    /// Keep up to `size` released per-stream states of each kind for reuse by later
+    /// requests, 0 disables pooling. Defaults to 256.
+    pub fn stream_pool_size(mut self, size: usize) -> Self {
+        self.opts.stream_pool_size = Some(size);
+        self
+    }
+
+    /// Log every frame of the connection, see `FrameLogger`.
+    pub fn frame_logger(mut self, logger: FrameLogger) -> Self {
+        self.opts.frame_logger = Some(logger);
+        self
+    }
+
+    /// Write every frame of the connection into a capture, see `CaptureRecorder`.
+    pub fn capture(mut self, recorder: CaptureRecorder) -> Self {
+        self.opts.capture = Some(recorder);
+        self
+    }
+
+    /// Log streams without activity nor terminal frame for longer than `threshold`,
+    /// together with the backtrace where they were created. Intended for diagnostics only.
+    pub fn leak_detection(mut self, threshold: Duration) -> Self {
+        self.opts.leak_threshold = Some(threshold);
+        self
+    }
+
+    /// Cancel streams without frames in either direction for longer than `timeout`, their
+    /// requesters fail with a CANCELED error. Streams are checked every half of `timeout`.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        assert!(
+            timeout > Duration::from_secs(0),
+            "idle timeout must be positive"
+        );
+        self.opts.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Detect responder streams whose requester demands nothing for longer than `threshold`,
+    /// the event is logged with target `rsocket_rust::slow_consumer` and `policy` is applied.
+    pub fn slow_consumer(mut self, threshold: Duration, policy: SlowConsumerPolicy) -> Self {
+        self.opts.slow_consumer = Some(SlowConsumer { threshold, policy });
+        self
+    }
+
+    /// Consume up to `capacity` outputs of responder streams ahead of the demand of the
+    /// requester, `policy` decides what happens once the buffer is full.
+    pub fn stream_buffer(mut self, capacity: usize, policy: OverflowPolicy) -> Self {
+        self.opts.stream_buffer = Some(StreamBuffer { capacity, policy });
+        self
+    }
+
+    /// Handle METADATA_PUSH frames of the peer with `handler` instead of the responder.
+    pub fn on_metadata_push<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(Payload) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.opts.on_metadata_push = Some(Arc::new(move |req| Box::pin(handler(req))));
+        self
+    }
+
+    /// Drop METADATA_PUSH frames whose metadata is larger than `size` bytes.
+    pub fn max_metadata_push_size(mut self, size: usize) -> Self {
+        self.opts.max_metadata_push_size = Some(size);
+        self
+    }
+
+    /// Negotiate leasing by SETUP: requests are sent only as far as the server granted leases,
+    /// the others fail right away with a REJECTED error. The server may not send requests as
+    /// the client grants no leases.
+    #[cfg(feature = "lease")]
+    pub fn lease(mut self) -> Self {
+        self.opts.lease = true;
+        self
+    }
+
+    /// Send KEEPALIVE frames with the data produced by `data`, their send time by default.
+    /// The round trip time is measured either way.
+    pub fn keepalive_data<F>(mut self, data: F) -> Self
+    where
+        F: Fn() -> Bytes + Send + Sync + 'static,
+    {
+        self.opts.keepalive_data = Some(Arc::new(data));
+        self
+    }
+
+    /// Call `handler` with the data of every KEEPALIVE received from the server.
+    pub fn on_keepalive<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(Bytes) + Send + Sync + 'static,
+    {
+        self.opts.on_keepalive = Some(Arc::new(handler));
+        self
+    }
+
+    /// Write the frames of higher priority streams first while the connection is busy, see
+    /// `PriorityMetadata`.
+    pub fn priority_scheduling(mut self) -> Self {
+        self.opts.priority_scheduling = true;
+        self
+    }
+
+    /// Write the frames of the streams of the same priority by turns while the connection is busy,
+    /// rather than in the order they were sent.
+    pub fn fair_scheduling(mut self) -> Self {
+        self.opts.fair_scheduling = true;
+        self
+    }
+
+    /// Limit the rates of the frames written to the server, see `FrameRate`.
+    pub fn frame_rate(mut self, rate: FrameRate) -> Self {
+        self.opts.frame_rate = Some(rate);
+        self
+    }
+
+    /// Bound the bytes of the payloads of the server which are outstanding, requested but not
+    /// consumed yet, see `ByteBudget`.
+    pub fn byte_budget(mut self, budget: ByteBudget) -> Self {
+        self.opts.byte_budget = Some(budget);
+        self
+    }
+
+    /// Set where the handlers of the responder run, they are spawned onto the runtime by
+    /// default. See `Execution`.
+    pub fn execution(mut self, execution: Execution) -> Self {
+        self.opts.execution = execution;
+        self
+    }
+
+    /// Set how frames of the server which violate the protocol are handled, they are logged
+    /// and ignored by default.
+    pub fn validation(mut self, mode: ValidationMode) -> Self {
+        self.opts.validation = mode;
+        self
+    }
+
+    /// Resume `session` by RESUME rather than send SETUP, see `ResumeSession`. The SETUP
+    /// payload of the builder is to be the one the session started by.
+    #[cfg(feature = "resume")]
+    pub fn resume(mut self, session: ResumeSession) -> Self {
+        self.resume = Some(session);
+        self
+    }
+
+    /// Send a fresh SETUP over the transport made by `transport` when the server rejects the
+    /// RESUME, or does not answer it in time. The connection fails then by default.
+    #[cfg(feature = "resume")]
+    pub fn resume_fallback<F>(mut self, transport: F) -> Self
+    where
+        F: FnOnce() -> T + Send + Sync + 'static,
+    {
+        self.resume_fallback = Some(Box::new(transport));
+        self
+    }
+
+    /// Call `hook` with how the connection was established once a `ResumeSession` was
+    /// resumed, or replaced by a fresh SETUP.
+    #[cfg(feature = "resume")]
+    pub fn on_reconnect<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Reconnected) + Send + Sync + 'static,
+    {
+        self.on_reconnect = Some(Box::new(hook));
+        self
+    }
+
     pub async fn start(self) -> Result<Client<DefaultSpawner>, Box<dyn Error + Send + Sync>> {
         self.start_with_runtime(DefaultSpawner).await
     }
 
     pub async fn start_with_runtime<R>(
-        mut self,
+        self,
         rt: R,
     ) -> Result<Client<R>, Box<dyn Error + Send + Sync>>
+    where
+        R: Send + Sync + Clone + Spawner + 'static,
+    {
+        let (socket, data_mime_type, connection) = self.open(rt.clone()).await?;
+        rt.spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("connection failed: {}", e);
+            }
+        });
+        Ok(Client::new(Requester::new(socket, data_mime_type)))
+    }
+
+    /// Connect to the server, the connection is driven by the returned `Connection` which
+    /// the caller is to poll, rather than by a task spawned for it.
+    pub async fn connect(
+        self,
+    ) -> Result<(Requester<DefaultSpawner>, Connection), Box<dyn Error + Send + Sync>> {
+        self.connect_with_runtime(DefaultSpawner).await
+    }
+
+    pub async fn connect_with_runtime<R>(
+        self,
+        rt: R,
+    ) -> Result<(Requester<R>, Connection), Box<dyn Error + Send + Sync>>
+    where
+        R: Send + Sync + Clone + Spawner + 'static,
+    {
+        let (socket, data_mime_type, connection) = self.open(rt).await?;
+        Ok((Requester::new(socket, data_mime_type), connection))
+    }
+
+    /// Connect and send SETUP, returns the socket, the data MIME type and the connection.
+    async fn open<R>(
+        mut self,
+        rt: R,
+    ) -> Result<(DuplexSocket<R>, String, Connection), Box<dyn Error + Send + Sync>>
     where
         R: Send + Sync + Clone + Spawner + 'static,
     {
         let tp = self.transport.take().expect("missint transport");
-        let cloned_rt = rt.clone();
-        let (rcv_tx, rcv_rx) = mpsc::unbounded::<Frame>();
-        let (snd_tx, snd_rx) = mpsc::unbounded::<Frame>();
-        let (connected_tx, connected_rx) = oneshot::channel::<Result<(), RSocketError>>();
-        tp.attach(rcv_tx, snd_rx, Some(connected_tx));
-        connected_rx.await??;
+        let dialed = self.dial(tp).await?;
+        #[cfg(feature = "resume")]
+        let (dialed, reconnected) = self.reconnect(dialed).await?;
+        let (peer, snd_tx, rcv_rx) = dialed;
 
-        let duplex_socket = DuplexSocket::new(rt, 1, snd_tx.clone()).await;
-        let cloned_duplex_socket = duplex_socket.clone();
+        let duplex_socket = DuplexSocket::new(rt, 1, snd_tx.clone(), self.opts.clone())
+            .await
+            .with_peer(peer);
+        // a client never receives SETUP, so its responder is installed right away.
         let acceptor = match self.responder {
-            Some(r) => Acceptor::Simple(Arc::new(r)),
+            Some(r) => {
+                duplex_socket.set_responder(r());
+                Acceptor::Simple(Arc::new(r))
+            }
             None => Acceptor::Empty(),
         };
-        cloned_rt.spawn(async move {
-            cloned_duplex_socket.event_loop(acceptor, rcv_rx).await;
-        });
+        let connection = Connection::new(duplex_socket.clone(), acceptor, rcv_rx);
         let setup = self.setup.build();
+        let keepalive_interval = setup.keepalive_interval();
+        let data_mime_type = setup
+            .data_mime_type()
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_MIME_TYPE));
+        #[cfg(feature = "resume")]
+        match reconnected {
+            Some(Reconnected::Resumed { .. }) => duplex_socket.resumed(setup),
+            _ => duplex_socket.setup(setup).await,
+        }
+        #[cfg(not(feature = "resume"))]
         duplex_socket.setup(setup).await;
-        Ok(Client::new(duplex_socket))
+        duplex_socket.start_keepalive(keepalive_interval);
+        #[cfg(feature = "resume")]
+        {
+            if let (Some(hook), Some(reconnected)) = (&self.on_reconnect, &reconnected) {
+                hook(reconnected);
+            }
+        }
+        Ok((duplex_socket, data_mime_type, connection))
+    }
+
+    /// Resume the session of the builder over `dialed` if it has one, or over the fallback
+    /// transport by a fresh SETUP.
+    #[cfg(feature = "resume")]
+    async fn reconnect(
+        &mut self,
+        mut dialed: Dialed,
+    ) -> Result<(Dialed, Option<Reconnected>), Box<dyn Error + Send + Sync>> {
+        let session = match self.resume.take() {
+            Some(it) => it,
+            None => return Ok((dialed, None)),
+        };
+        match session.handshake(&mut dialed.1, &mut dialed.2).await {
+            Ok(position) => Ok((dialed, Some(Reconnected::Resumed { position }))),
+            Err(reason) => match self.resume_fallback.take() {
+                Some(fallback) => {
+                    debug!("resume failed, fall back to SETUP: {}", reason);
+                    let dialed = self.dial(fallback()).await?;
+                    Ok((dialed, Some(Reconnected::Setup { reason })))
+                }
+                None => Err(reason.into()),
+            },
+        }
+    }
+
+    /// Attach `tp` and wait for it to connect.
+    async fn dial(&self, tp: T) -> Result<Dialed, Box<dyn Error + Send + Sync>> {
+        let peer = tp.peer();
+        let (rcv_tx, rcv_rx) = transport::new_tx_rx::<Frame>(self.opts.inbound_capacity());
+        let (snd_tx, snd_rx) = transport::new_tx_rx_bounded::<Frame>(self.opts.outbound_capacity());
+        let (connected_tx, connected_rx) = oneshot::channel::<Result<(), RSocketError>>();
+        tp.attach(rcv_tx, snd_rx, Some(connected_tx));
+        connected_rx.await??;
+        Ok((peer, snd_tx, rcv_rx))
+    }
+}
+
+impl Connection {
+    fn new<R>(socket: DuplexSocket<R>, acceptor: Acceptor, rx: Rx<Frame>) -> Connection
+    where
+        R: Send + Sync + Clone + Spawner + 'static,
+    {
+        let guard = CloseOnDrop(Some(socket.clone()));
+        Connection {
+            inner: Box::pin(async move {
+                let outcome = socket.event_loop(acceptor, rx).await;
+                guard.disarm();
+                outcome
+            }),
+        }
+    }
+}
+
+impl Future for Connection {
+    type Output = Result<(), RSocketError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+impl<R> CloseOnDrop<R>
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+{
+    /// The connection ended by itself, leave the socket be.
+    fn disarm(mut self) {
+        self.0.take();
+    }
+}
+
+impl<R> Drop for CloseOnDrop<R>
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+{
+    fn drop(&mut self) {
+        if let Some(socket) = self.0.take() {
+            socket.close();
+        }
     }
 }
 
 impl<R> RSocket for Client<R>
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+{
+    fn metadata_push(&self, req: Payload) -> Mono<()> {
+        self.requester.metadata_push(req)
+    }
+
+    fn fire_and_forget(&self, req: Payload) -> Mono<()> {
+        self.requester.fire_and_forget(req)
+    }
+
+    fn request_response(&self, req: Payload) -> Mono<Result<Payload, RSocketError>> {
+        self.requester.request_response(req)
+    }
+
+    fn request_stream(&self, req: Payload) -> Flux<Result<Payload, RSocketError>> {
+        self.requester.request_stream(req)
+    }
+
+    fn request_channel(
+        &self,
+        reqs: Flux<Result<Payload, RSocketError>>,
+    ) -> Flux<Result<Payload, RSocketError>> {
+        self.requester.request_channel(reqs)
+    }
+}
+
+impl<R> RSocket for Requester<R>
 where
     R: Send + Sync + Clone + Spawner + 'static,
 {
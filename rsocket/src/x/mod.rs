@@ -2,6 +2,7 @@ mod client;
 mod factory;
 mod server;
 
-pub use client::{Client, ClientBuilder};
+pub use client::{Client, ClientBuilder, Connection, Requester};
 pub use factory::RSocketFactory;
+pub(crate) use server::accept;
 pub use server::ServerBuilder;
@@ -1,20 +1,36 @@
-use crate::error::RSocketError;
+use crate::error::{self, ErrorKind, RSocketError};
 use crate::frame::{self, Frame};
-use crate::payload::SetupPayload;
+use crate::interceptor::{CaptureRecorder, FrameLogger};
+use crate::payload::{Payload, SetupPayload};
+#[cfg(feature = "reload")]
+use crate::reload::Reloadable;
 use crate::runtime::{DefaultSpawner, Spawner};
 use crate::spi::{EmptyRSocket, RSocket};
+#[cfg(feature = "tenant")]
+use crate::tenant::Tenants;
+#[cfg(feature = "extension")]
+use crate::transport::Authenticator;
+#[cfg(feature = "lease")]
+use crate::transport::LeasePolicy;
 use crate::transport::{
-    Acceptor, ClientTransport, DuplexSocket, FnAcceptorWithSetup, ServerTransport,
+    self, Acceptor, BoxedAcceptor, ByteBudget, ClientTransport, DuplexSocket, Execution,
+    FnAcceptorWithSetup, FrameRate, OverflowPolicy, PayloadLimits, PeerInfo, ServerTransport,
+    SlowConsumer, SlowConsumerPolicy, SocketOptions, StreamBuffer, ValidationMode,
 };
+use bytes::Bytes;
 use futures::channel::{mpsc, oneshot};
 use std::error::Error;
 use std::future::Future;
 use std::net::SocketAddr;
 use std::pin::Pin;
 use std::result::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 type FnStart = fn();
+type AcceptFilter =
+    Arc<dyn Fn(PeerInfo) -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync>;
 
 pub struct ServerBuilder<T, C>
 where
@@ -24,8 +40,17 @@ where
     transport: Option<T>,
     on_setup: FnAcceptorWithSetup,
     start_handler: Option<FnStart>,
+    data_mime_types: Vec<String>,
+    metadata_mime_types: Vec<String>,
+    max_connections: Option<usize>,
+    accept_filter: Option<AcceptFilter>,
+    accept_by: Option<Arc<BoxedAcceptor>>,
+    opts: SocketOptions,
 }
 
+/// Counts a served connection until it is dropped.
+struct ConnectionPermit(Arc<AtomicUsize>);
+
 impl<T, C> ServerBuilder<T, C>
 where
     T: Send + Sync + ServerTransport<Item = C> + 'static,
@@ -36,6 +61,12 @@ where
             transport: None,
             on_setup: on_setup_noop,
             start_handler: None,
+            data_mime_types: vec![],
+            metadata_mime_types: vec![],
+            max_connections: None,
+            accept_filter: None,
+            accept_by: None,
+            opts: SocketOptions::default(),
         }
     }
 
@@ -44,6 +75,230 @@ where
         self
     }
 
+    /// Accept connections by the acceptor of the tenant their SETUP selects, instead of
+    /// `acceptor`.
+    #[cfg(feature = "tenant")]
+    pub fn tenants(mut self, tenants: Tenants) -> Self {
+        self.accept_by = Some(Arc::new(move |setup, socket| tenants.accept(setup, socket)));
+        self
+    }
+
+    /// Accept connections by the current acceptor of `reloadable`, instead of `acceptor`.
+    #[cfg(feature = "reload")]
+    pub fn reloadable(mut self, reloadable: Reloadable) -> Self {
+        self.accept_by = Some(Arc::new(move |setup, socket| {
+            reloadable.accept(setup, socket)
+        }));
+        self
+    }
+
+    /// Declare supported data MIME types, SETUP frames with other data MIME types
+    /// are rejected with INVALID_SETUP. All MIME types are accepted by default.
+    pub fn data_mime_types(mut self, mime_types: &[&str]) -> Self {
+        self.data_mime_types = mime_types.iter().map(|it| it.to_string()).collect();
+        self
+    }
+
+    /// Declare supported metadata MIME types, works like `data_mime_types`.
+    pub fn metadata_mime_types(mut self, mime_types: &[&str]) -> Self {
+        self.metadata_mime_types = mime_types.iter().map(|it| it.to_string()).collect();
+        self
+    }
+
+    /// Bound the number of frames queued for the writer of accepted connections,
+    /// see `ClientBuilder::outbound_capacity`.
+    pub fn outbound_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "outbound capacity must be positive");
+        self.opts.outbound_capacity = Some(capacity);
+        self
+    }
+
+    /// Bound the number of frames read from accepted connections and not handled yet,
+    /// see `ClientBuilder::inbound_capacity`.
+    pub fn inbound_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "inbound capacity must be positive");
+        self.opts.inbound_capacity = Some(capacity);
+        self
+    }
+
+    /// Bound the payloads buffered for each stream requested by accepted connections,
+    /// see `ClientBuilder::stream_capacity`.
+    pub fn stream_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "stream capacity must be positive");
+        self.opts.stream_capacity = Some(capacity);
+        self
+    }
+
+    /// Pool per-stream states of accepted connections, see `ClientBuilder::stream_pool_size`.
+    pub fn stream_pool_size(mut self, size: usize) -> Self {
+        self.opts.stream_pool_size = Some(size);
+        self
+    }
+
+    /// Log every frame of accepted connections, see `FrameLogger`.
+    pub fn frame_logger(mut self, logger: FrameLogger) -> Self {
+        self.opts.frame_logger = Some(logger);
+        self
+    }
+
+    /// Write every frame of accepted connections into a capture, see `CaptureRecorder`.
+    pub fn capture(mut self, recorder: CaptureRecorder) -> Self {
+        self.opts.capture = Some(recorder);
+        self
+    }
+
+    /// Log streams of accepted connections which are idle for longer than `threshold`,
+    /// see `ClientBuilder::leak_detection`.
+    pub fn leak_detection(mut self, threshold: Duration) -> Self {
+        self.opts.leak_threshold = Some(threshold);
+        self
+    }
+
+    /// Cancel streams of accepted connections which are idle for longer than `timeout`, see
+    /// `ClientBuilder::idle_timeout`.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        assert!(
+            timeout > Duration::from_secs(0),
+            "idle timeout must be positive"
+        );
+        self.opts.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Detect slow consumers of responder streams, see `ClientBuilder::slow_consumer`.
+    pub fn slow_consumer(mut self, threshold: Duration, policy: SlowConsumerPolicy) -> Self {
+        self.opts.slow_consumer = Some(SlowConsumer { threshold, policy });
+        self
+    }
+
+    /// Buffer the outputs of responder streams, see `ClientBuilder::stream_buffer`.
+    pub fn stream_buffer(mut self, capacity: usize, policy: OverflowPolicy) -> Self {
+        self.opts.stream_buffer = Some(StreamBuffer { capacity, policy });
+        self
+    }
+
+    /// Handle METADATA_PUSH frames of accepted connections with `handler` instead of the
+    /// responders, see `ClientBuilder::on_metadata_push`.
+    pub fn on_metadata_push<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(Payload) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.opts.on_metadata_push = Some(Arc::new(move |req| Box::pin(handler(req))));
+        self
+    }
+
+    /// Drop METADATA_PUSH frames of accepted connections whose metadata is larger than
+    /// `size` bytes.
+    pub fn max_metadata_push_size(mut self, size: usize) -> Self {
+        self.opts.max_metadata_push_size = Some(size);
+        self
+    }
+
+    /// Grant leases to the clients which negotiate leasing by SETUP, sized by `policy` from
+    /// the load of the server. Their requests beyond the leases are rejected.
+    #[cfg(feature = "lease")]
+    pub fn lease(mut self, policy: LeasePolicy) -> Self {
+        self.opts.lease_policy = Some(policy);
+        self
+    }
+
+    /// Authenticate accepted connections by their SETUP before the acceptor runs, see
+    /// `Authenticator`.
+    #[cfg(feature = "extension")]
+    pub fn authenticator<A>(mut self, authenticator: A) -> Self
+    where
+        A: Authenticator + 'static,
+    {
+        self.opts.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// Limit the sizes of the payloads of accepted connections per interaction model, see
+    /// `PayloadLimits`.
+    pub fn payload_limits(mut self, limits: PayloadLimits) -> Self {
+        self.opts.payload_limits = limits;
+        self
+    }
+
+    /// Write the frames of higher priority streams first while a connection is busy, see
+    /// `PriorityMetadata`.
+    pub fn priority_scheduling(mut self) -> Self {
+        self.opts.priority_scheduling = true;
+        self
+    }
+
+    /// Write the frames of the streams of the same priority by turns while a connection is busy,
+    /// rather than in the order they were sent.
+    pub fn fair_scheduling(mut self) -> Self {
+        self.opts.fair_scheduling = true;
+        self
+    }
+
+    /// Limit the rates of the frames written to each accepted connection, see `FrameRate`.
+    pub fn frame_rate(mut self, rate: FrameRate) -> Self {
+        self.opts.frame_rate = Some(rate);
+        self
+    }
+
+    /// Bound the bytes of the payloads of each accepted connection which are outstanding for
+    /// the requests of the server, see `ClientBuilder::byte_budget`.
+    pub fn byte_budget(mut self, budget: ByteBudget) -> Self {
+        self.opts.byte_budget = Some(budget);
+        self
+    }
+
+    /// Set where the handlers of the responders of accepted connections run, see
+    /// `ClientBuilder::execution`.
+    pub fn execution(mut self, execution: Execution) -> Self {
+        self.opts.execution = execution;
+        self
+    }
+
+    /// Set how frames of accepted connections which violate the protocol are handled, see
+    /// `ClientBuilder::validation`.
+    pub fn validation(mut self, mode: ValidationMode) -> Self {
+        self.opts.validation = mode;
+        self
+    }
+
+    /// Answer KEEPALIVE frames of accepted connections with the data produced by `data`
+    /// instead of echoing theirs.
+    pub fn keepalive_data<F>(mut self, data: F) -> Self
+    where
+        F: Fn() -> Bytes + Send + Sync + 'static,
+    {
+        self.opts.keepalive_data = Some(Arc::new(data));
+        self
+    }
+
+    /// Call `handler` with the data of every KEEPALIVE received by accepted connections.
+    pub fn on_keepalive<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(Bytes) + Send + Sync + 'static,
+    {
+        self.opts.on_keepalive = Some(Arc::new(handler));
+        self
+    }
+
+    /// Limit the number of connections served at once, connections accepted beyond it are
+    /// closed before their SETUP frame is read.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = Some(max);
+        self
+    }
+
+    /// Decide whether to serve an accepted connection by its peer, rejected connections are
+    /// closed before their SETUP frame is read.
+    pub fn accept_filter<F, Fut>(mut self, filter: F) -> Self
+    where
+        F: Fn(PeerInfo) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.accept_filter = Some(Arc::new(move |peer| Box::pin(filter(peer))));
+        self
+    }
+
     pub fn on_start(mut self, hanlder: FnStart) -> Self {
         self.start_handler = Some(hanlder);
         self
@@ -63,22 +318,154 @@ where
         R: Send + Sync + Clone + Spawner + 'static,
     {
         let tp = self.transport.take().expect("missing transport");
+        let on_setup = self.on_setup;
+        let data_mime_types = self.data_mime_types;
+        let metadata_mime_types = self.metadata_mime_types;
+        let opts = self.opts;
+        let max_connections = self.max_connections.unwrap_or(usize::MAX);
+        let accept_filter = self.accept_filter;
+        let accept_by = self.accept_by;
+        let connections = Arc::new(AtomicUsize::new(0));
+        let setuper: Arc<BoxedAcceptor> = Arc::new(move |setup, socket| {
+            validate_mime_type("data", &data_mime_types, setup.data_mime_type())?;
+            validate_mime_type("metadata", &metadata_mime_types, setup.metadata_mime_type())?;
+            match &accept_by {
+                Some(accept) => accept(setup, socket),
+                None => on_setup(setup, socket),
+            }
+        });
         tp.start(self.start_handler, move |tp| {
-            let cloned_rt = rt.clone();
-            let setuper = Arc::new(self.on_setup);
-            let (rcv_tx, rcv_rx) = mpsc::unbounded::<Frame>();
-            let (snd_tx, snd_rx) = mpsc::unbounded::<Frame>();
-            tp.attach(rcv_tx, snd_rx, None);
+            let permit = match ConnectionPermit::acquire(&connections, max_connections) {
+                Some(permit) => permit,
+                None => {
+                    warn!(
+                        "reject connection: reach max connections {}",
+                        max_connections
+                    );
+                    return;
+                }
+            };
+            let filter = accept_filter.clone();
+            let conn = Connection::new(rt.clone(), tp, setuper.clone(), opts.clone());
             rt.spawn(async move {
-                let ds = DuplexSocket::new(cloned_rt, 0, snd_tx).await;
-                let acceptor = Acceptor::Generate(setuper.clone());
-                ds.event_loop(acceptor, rcv_rx).await;
+                if let Some(filter) = filter {
+                    if !filter(conn.peer.clone()).await {
+                        debug!("reject connection from {:?}", conn.peer);
+                        return;
+                    }
+                }
+                conn.serve().await;
+                drop(permit);
             });
         })
         .await
     }
 }
 
+impl ConnectionPermit {
+    fn acquire(connections: &Arc<AtomicUsize>, max: usize) -> Option<ConnectionPermit> {
+        connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                if n < max {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| ConnectionPermit(connections.clone()))
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Serve one accepted connection.
+pub(crate) fn accept<R, C>(rt: R, tp: C, setuper: Arc<BoxedAcceptor>, opts: SocketOptions)
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+    C: ClientTransport,
+{
+    let conn = Connection::new(rt.clone(), tp, setuper, opts);
+    rt.spawn(conn.serve());
+}
+
+/// An accepted connection which is not attached yet, dropping it closes the transport.
+struct Connection<R, C> {
+    rt: R,
+    tp: C,
+    peer: PeerInfo,
+    setuper: Arc<BoxedAcceptor>,
+    opts: SocketOptions,
+}
+
+impl<R, C> Connection<R, C>
+where
+    R: Send + Sync + Clone + Spawner + 'static,
+    C: ClientTransport,
+{
+    fn new(rt: R, tp: C, setuper: Arc<BoxedAcceptor>, opts: SocketOptions) -> Connection<R, C> {
+        let peer = tp.peer();
+        Connection {
+            rt,
+            tp,
+            peer,
+            setuper,
+            opts,
+        }
+    }
+
+    /// Attach the transport, the returned future runs the connection until it is closed.
+    fn serve(self) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let Connection {
+            rt,
+            tp,
+            peer,
+            setuper,
+            opts,
+        } = self;
+        let (rcv_tx, rcv_rx) = transport::new_tx_rx::<Frame>(opts.inbound_capacity());
+        let (snd_tx, snd_rx) = transport::new_tx_rx_bounded::<Frame>(opts.outbound_capacity());
+        tp.attach(rcv_tx, snd_rx, None);
+        Box::pin(async move {
+            let ds = DuplexSocket::new(rt, 2, snd_tx, opts).await.with_peer(peer);
+            let acceptor = Acceptor::Generate(setuper);
+            if let Err(e) = ds.event_loop(acceptor, rcv_rx).await {
+                debug!("connection failed: {}", e);
+            }
+            ds.join().await;
+        })
+    }
+}
+
+#[inline]
+fn validate_mime_type(
+    kind: &str,
+    supported: &[String],
+    actual: &Option<String>,
+) -> Result<(), RSocketError> {
+    if supported.is_empty() {
+        return Ok(());
+    }
+    let actual = actual.as_deref().unwrap_or("");
+    if supported.iter().any(|it| it == actual) {
+        return Ok(());
+    }
+    let msg = format!(
+        "unsupported {} MIME type: {}, expect one of [{}]",
+        kind,
+        actual,
+        supported.join(", ")
+    );
+    Err(RSocketError::from(ErrorKind::Internal(
+        error::ERR_INVALID_SETUP,
+        msg,
+    )))
+}
+
 #[inline]
 fn on_setup_noop(
     _setup: SetupPayload,